@@ -1,5 +1,8 @@
 use criterion::{Criterion, criterion_group, criterion_main};
-use sqllog_analysis::sqllog::{Sqllog, utils::is_first_row};
+use sqllog_analysis::sqllog::{
+    ParserContext, Sqllog, find_first_row_pos, is_first_row_bytes,
+    utils::is_first_row,
+};
 use std::hint::black_box;
 use std::io::Write;
 use tempfile::NamedTempFile;
@@ -22,6 +25,47 @@ fn bench_sqllog_from_line(c: &mut Criterion) {
     });
 }
 
+fn bench_parser_context_parse_lines(c: &mut Criterion) {
+    c.bench_function("parser_context_parse_lines_1000", |b| {
+        let test_line = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname:App ip:::ffff:10.0.0.1) [SEL]: select * from test_table where id = ? EXECTIME: 3(ms) ROWCOUNT: 1 EXEC_ID: 2.";
+        let segments = vec![test_line; 1000];
+        let ctx = ParserContext::new();
+        b.iter(|| black_box(ctx.parse_lines(black_box(&segments))))
+    });
+}
+
+/// 约 1MB 的多行日志：每条记录一行头部加两行 SQL 续行
+fn boundary_scan_text() -> String {
+    let record = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname:App ip:::ffff:10.0.0.1) [SEL]: select *\n  from test_table\n  where id = ? EXECTIME: 3(ms) ROWCOUNT: 1 EXEC_ID: 2.\n";
+    record.repeat(1024 * 1024 / record.len())
+}
+
+fn bench_boundary_scan(c: &mut Criterion) {
+    let text = boundary_scan_text();
+    // 与逐行读取相同：memchr 定位换行符，检查每行的时间戳前缀
+    c.bench_function("boundary_scan_lines_1mb", |b| {
+        b.iter(|| {
+            let bytes = black_box(text.as_bytes());
+            let mut start = 0;
+            let mut starts = 0usize;
+            for end in memchr::memchr_iter(b'\n', bytes) {
+                let line = &bytes[start..=end];
+                start = end + 1;
+                starts += usize::from(
+                    line.get(0..23).is_some_and(is_first_row_bytes),
+                );
+            }
+            starts
+        })
+    });
+
+    // 没有时间戳的长文本：逐位置查找的最坏情况
+    let garbage = "2025-13-40 99:99:99.999 x-y-z: ".repeat(32 * 1024);
+    c.bench_function("find_first_row_pos_1mb_miss", |b| {
+        b.iter(|| black_box(find_first_row_pos(black_box(&garbage))))
+    });
+}
+
 fn bench_parse_small_file(c: &mut Criterion) {
     c.bench_function("parse_small_file_100_records", |b| {
         b.iter_batched(
@@ -110,6 +154,8 @@ criterion_group!(
     benches,
     bench_is_first_row,
     bench_sqllog_from_line,
+    bench_parser_context_parse_lines,
+    bench_boundary_scan,
     bench_parse_small_file,
     bench_parse_chunked_file
 );
@@ -0,0 +1,1133 @@
+//! 分组聚合 - `analyze` 命令的迷你查询语法与内存聚合引擎
+//!
+//! 查询由四个子句组成，语法接近 SQL 但只覆盖日志分析的常见需求：
+//!
+//! ```text
+//! --select   "user, count(*), p95(execute_time)"
+//! --group-by "user"
+//! --having   "count(*) > 100 and avg(execute_time) >= 5"
+//! --order-by "2 desc, user"
+//! ```
+//!
+//! - select 项为列名或聚合函数：`count(*)`、`count(col)`、`sum`、`avg`、
+//!   `min`、`max` 以及百分位 `pNN`（如 `p50`、`p95`、`p99`）
+//! - select 中出现的普通列必须同时出现在 group-by 中
+//! - having 条件为「表达式 比较符 数值」，多个条件以 `and` 连接
+//! - order-by 可引用 select 的序号（从 1 开始）或 select 中的表达式
+//!
+//! 聚合在内存中逐批完成，百分位按最近秩（nearest-rank）计算。
+//! 同一查询也可通过 [`AggregateQuery::to_sql`] 翻译为 SQL，交给 `DuckDB`
+//! 在磁盘上完成，结果与内存聚合一致。
+
+use crate::sqllog::Sqllog;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::fmt;
+use std::mem::size_of;
+
+/// 查询解析错误
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum QueryError {
+    /// 未知的列名
+    #[error("未知的列: {0}")]
+    UnknownColumn(String),
+    /// 未知的聚合函数
+    #[error("未知的聚合函数: {0}")]
+    UnknownFunction(String),
+    /// 对非数值列使用了数值聚合
+    #[error("{func} 只能用于数值列，{column} 不是数值列")]
+    NotNumeric {
+        /// 聚合函数名
+        func: String,
+        /// 列名
+        column: String,
+    },
+    /// 语法错误
+    #[error("语法错误: {0}")]
+    Syntax(String),
+    /// 语义错误（如未分组的列、越界的排序序号）
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// 可参与查询的记录列
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Column {
+    /// 发生时间
+    OccurrenceTime,
+    /// EP 编号
+    Ep,
+    /// 会话
+    Session,
+    /// 线程
+    Thread,
+    /// 用户
+    User,
+    /// 事务 ID
+    TrxId,
+    /// 语句句柄
+    Statement,
+    /// 应用名
+    Appname,
+    /// 客户端 IP
+    Ip,
+    /// SQL 类型
+    SqlType,
+    /// 描述（SQL 文本）
+    Description,
+    /// 执行时间（毫秒）
+    ExecuteTime,
+    /// 执行时间（微秒）
+    ExecuteTimeUs,
+    /// 影响行数
+    Rowcount,
+    /// 执行 ID
+    ExecuteId,
+}
+
+impl Column {
+    /// 全部列
+    pub const ALL: [Self; 15] = [
+        Self::OccurrenceTime,
+        Self::Ep,
+        Self::Session,
+        Self::Thread,
+        Self::User,
+        Self::TrxId,
+        Self::Statement,
+        Self::Appname,
+        Self::Ip,
+        Self::SqlType,
+        Self::Description,
+        Self::ExecuteTime,
+        Self::ExecuteTimeUs,
+        Self::Rowcount,
+        Self::ExecuteId,
+    ];
+
+    /// 按名称查找列（不区分大小写）
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|c| c.name().eq_ignore_ascii_case(name))
+    }
+
+    /// 查询语法中使用的列名（与 `Sqllog` 字段名一致）
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::OccurrenceTime => "occurrence_time",
+            Self::Ep => "ep",
+            Self::Session => "session",
+            Self::Thread => "thread",
+            Self::User => "user",
+            Self::TrxId => "trx_id",
+            Self::Statement => "statement",
+            Self::Appname => "appname",
+            Self::Ip => "ip",
+            Self::SqlType => "sql_type",
+            Self::Description => "description",
+            Self::ExecuteTime => "execute_time",
+            Self::ExecuteTimeUs => "execute_time_us",
+            Self::Rowcount => "rowcount",
+            Self::ExecuteId => "execute_id",
+        }
+    }
+
+    /// 是否为数值列
+    #[must_use]
+    pub const fn is_numeric(self) -> bool {
+        matches!(
+            self,
+            Self::Ep
+                | Self::ExecuteTime
+                | Self::ExecuteTimeUs
+                | Self::Rowcount
+                | Self::ExecuteId
+        )
+    }
+
+    /// 取数值列的值；非数值列或缺失时返回 `None`
+    #[must_use]
+    pub fn number(self, record: &Sqllog) -> Option<i64> {
+        match self {
+            Self::Ep => Some(i64::from(record.ep)),
+            Self::ExecuteTime => record.execute_time.map(i64::from),
+            Self::ExecuteTimeUs => record.execute_time_us,
+            Self::Rowcount => record.rowcount.map(i64::from),
+            Self::ExecuteId => record.execute_id.map(i64::from),
+            _ => None,
+        }
+    }
+
+    fn text(self, record: &Sqllog) -> Option<&str> {
+        match self {
+            Self::OccurrenceTime => Some(&record.occurrence_time),
+            Self::Session => record.session.as_deref(),
+            Self::Thread => record.thread.as_deref(),
+            Self::User => record.user.as_deref(),
+            Self::TrxId => record.trx_id.as_deref(),
+            Self::Statement => record.statement.as_deref(),
+            Self::Appname => record.appname.as_deref(),
+            Self::Ip => record.ip.as_deref(),
+            Self::SqlType => record.sql_type.as_deref(),
+            Self::Description => Some(&record.description),
+            _ => None,
+        }
+    }
+
+    /// 在 `sqllogs` 表中对应的 SQL 表达式
+    #[must_use]
+    pub const fn sql_expr(self) -> &'static str {
+        match self {
+            Self::User => "username",
+            other => other.name(),
+        }
+    }
+
+    fn key(self, record: &Sqllog) -> Cell {
+        if self.is_numeric() {
+            self.number(record).map_or(Cell::Null, Cell::Int)
+        } else {
+            self.text(record).map_or(Cell::Null, |s| Cell::Text(s.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for Column {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// 聚合函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AggFunc {
+    /// 计数
+    Count,
+    /// 求和
+    Sum,
+    /// 平均值
+    Avg,
+    /// 最小值
+    Min,
+    /// 最大值
+    Max,
+    /// 百分位（1-100）
+    Percentile(u8),
+}
+
+impl AggFunc {
+    fn from_name(name: &str) -> Option<Self> {
+        let lower = name.to_ascii_lowercase();
+        match lower.as_str() {
+            "count" => Some(Self::Count),
+            "sum" => Some(Self::Sum),
+            "avg" => Some(Self::Avg),
+            "min" => Some(Self::Min),
+            "max" => Some(Self::Max),
+            _ => lower
+                .strip_prefix('p')
+                .and_then(|p| p.parse::<u8>().ok())
+                .filter(|p| (1..=100).contains(p))
+                .map(Self::Percentile),
+        }
+    }
+}
+
+impl fmt::Display for AggFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Count => f.write_str("count"),
+            Self::Sum => f.write_str("sum"),
+            Self::Avg => f.write_str("avg"),
+            Self::Min => f.write_str("min"),
+            Self::Max => f.write_str("max"),
+            Self::Percentile(p) => write!(f, "p{p}"),
+        }
+    }
+}
+
+/// 查询表达式：普通列或聚合
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Expr {
+    /// 分组列
+    Column(Column),
+    /// 聚合；`arg` 为 `None` 时表示 `count(*)`
+    Agg {
+        /// 聚合函数
+        func: AggFunc,
+        /// 聚合的列
+        arg: Option<Column>,
+    },
+}
+
+impl Expr {
+    /// 翻译为 SQL 表达式
+    #[must_use]
+    pub fn to_sql(&self) -> String {
+        match *self {
+            Self::Column(c) => c.sql_expr().to_string(),
+            Self::Agg { func: AggFunc::Count, arg: None } => {
+                "COUNT(*)".to_string()
+            }
+            Self::Agg { func, arg: Some(c) } => {
+                let col = c.sql_expr();
+                match func {
+                    AggFunc::Count => format!("COUNT({col})"),
+                    AggFunc::Sum => format!("CAST(SUM({col}) AS BIGINT)"),
+                    AggFunc::Avg => format!("AVG({col})"),
+                    AggFunc::Min => format!("MIN({col})"),
+                    AggFunc::Max => format!("MAX({col})"),
+                    // DuckDB 的 quantile_disc 与最近秩定义一致
+                    AggFunc::Percentile(p) => format!(
+                        "quantile_disc({col}, {})",
+                        f64::from(p) / 100.0
+                    ),
+                }
+            }
+            // 解析阶段已拒绝 count 以外的 `*`
+            Self::Agg { arg: None, .. } => "NULL".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Column(c) => write!(f, "{c}"),
+            Self::Agg { func, arg: None } => write!(f, "{func}(*)"),
+            Self::Agg { func, arg: Some(c) } => write!(f, "{func}({c})"),
+        }
+    }
+}
+
+/// 比较运算符
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `=`
+    Eq,
+    /// `!=` 或 `<>`
+    Ne,
+}
+
+impl CompareOp {
+    fn eval(self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            Self::Gt => lhs > rhs,
+            Self::Ge => lhs >= rhs,
+            Self::Lt => lhs < rhs,
+            Self::Le => lhs <= rhs,
+            Self::Eq => (lhs - rhs).abs() < f64::EPSILON,
+            Self::Ne => (lhs - rhs).abs() >= f64::EPSILON,
+        }
+    }
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Gt => ">",
+            Self::Ge => ">=",
+            Self::Lt => "<",
+            Self::Le => "<=",
+            Self::Eq => "=",
+            Self::Ne => "<>",
+        })
+    }
+}
+
+/// having 条件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Condition {
+    /// 左侧表达式
+    pub expr: Expr,
+    /// 比较符
+    pub op: CompareOp,
+    /// 右侧数值
+    pub value: f64,
+}
+
+/// 排序键
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OrderKey {
+    /// select 项下标（从 0 开始）
+    pub index: usize,
+    /// 是否降序
+    pub descending: bool,
+}
+
+/// 解析后的聚合查询
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateQuery {
+    /// 输出列
+    pub select: Vec<Expr>,
+    /// 分组列
+    pub group_by: Vec<Column>,
+    /// 分组过滤条件（全部满足）
+    pub having: Vec<Condition>,
+    /// 排序键；为空时按分组键升序
+    pub order_by: Vec<OrderKey>,
+    /// 最多输出的行数
+    pub limit: Option<usize>,
+}
+
+impl AggregateQuery {
+    /// 解析各子句；`group_by`、`having`、`order_by` 可为空串
+    ///
+    /// # Errors
+    /// 子句语法错误、引用未知列或函数、select 中有未分组的列时返回
+    /// `QueryError`。
+    pub fn parse(
+        select: &str,
+        group_by: &str,
+        having: &str,
+        order_by: &str,
+    ) -> Result<Self, QueryError> {
+        let select = Parser::new(select)?.expr_list()?;
+        if select.is_empty() {
+            return Err(QueryError::Syntax("select 不能为空".to_string()));
+        }
+        let group_by = Parser::new(group_by)?.column_list()?;
+        let having = Parser::new(having)?.conditions()?;
+        let order = Parser::new(order_by)?.order_items()?;
+
+        for expr in &select {
+            if let Expr::Column(c) = expr {
+                if !group_by.contains(c) {
+                    return Err(QueryError::Invalid(format!(
+                        "select 中的列 {c} 必须出现在 group-by 中"
+                    )));
+                }
+            }
+        }
+        let has_agg = select.iter().any(|e| matches!(e, Expr::Agg { .. }));
+        if !has_agg && group_by.is_empty() {
+            return Err(QueryError::Invalid(
+                "select 中必须包含聚合函数或指定 group-by".to_string(),
+            ));
+        }
+        if let Some(cond) = having.iter().find(|c| match c.expr {
+            Expr::Column(col) => !group_by.contains(&col),
+            Expr::Agg { .. } => false,
+        }) {
+            return Err(QueryError::Invalid(format!(
+                "having 中的列 {} 必须出现在 group-by 中",
+                cond.expr
+            )));
+        }
+
+        let mut order_by = Vec::with_capacity(order.len());
+        for (target, descending) in order {
+            let index = match target {
+                OrderTarget::Position(n) => {
+                    if n == 0 || n > select.len() {
+                        return Err(QueryError::Invalid(format!(
+                            "order-by 序号 {n} 超出 select 范围 1..={}",
+                            select.len()
+                        )));
+                    }
+                    n - 1
+                }
+                OrderTarget::Expr(expr) => {
+                    select.iter().position(|e| *e == expr).ok_or_else(|| {
+                        QueryError::Invalid(format!(
+                            "order-by 中的 {expr} 必须出现在 select 中"
+                        ))
+                    })?
+                }
+            };
+            order_by.push(OrderKey { index, descending });
+        }
+
+        Ok(Self { select, group_by, having, order_by, limit: None })
+    }
+
+    /// 设置最多输出的行数
+    #[must_use]
+    pub const fn with_limit(mut self, limit: Option<usize>) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// 输出列标题
+    #[must_use]
+    pub fn columns(&self) -> Vec<String> {
+        self.select.iter().map(ToString::to_string).collect()
+    }
+
+    /// 翻译为针对 `table` 的 SQL 查询
+    ///
+    /// 排序与内存聚合保持一致：空值视为最小值，未指定或相同的排序键
+    /// 按分组键升序排列。
+    #[must_use]
+    pub fn to_sql(&self, table: &str) -> String {
+        let select: Vec<String> =
+            self.select.iter().map(Expr::to_sql).collect();
+        let mut sql = format!("SELECT {} FROM {table}", select.join(", "));
+
+        let group_by: Vec<&str> =
+            self.group_by.iter().map(|c| c.sql_expr()).collect();
+        if !group_by.is_empty() {
+            sql.push_str(&format!(" GROUP BY {}", group_by.join(", ")));
+        }
+        if !self.having.is_empty() {
+            let conds: Vec<String> = self
+                .having
+                .iter()
+                .map(|c| format!("{} {} {}", c.expr.to_sql(), c.op, c.value))
+                .collect();
+            sql.push_str(&format!(" HAVING {}", conds.join(" AND ")));
+        }
+
+        let order: Vec<String> = self
+            .order_by
+            .iter()
+            .map(|k| {
+                let dir = if k.descending {
+                    "DESC NULLS LAST"
+                } else {
+                    "ASC NULLS FIRST"
+                };
+                format!("{} {dir}", k.index + 1)
+            })
+            .chain(group_by.iter().map(|c| format!("{c} ASC NULLS FIRST")))
+            .collect();
+        if !order.is_empty() {
+            sql.push_str(&format!(" ORDER BY {}", order.join(", ")));
+        }
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
+        }
+        sql
+    }
+
+    /// select 与 having 中用到的全部聚合（去重，保持首次出现顺序）
+    fn aggregates(&self) -> Vec<(AggFunc, Option<Column>)> {
+        let mut aggs = Vec::new();
+        let exprs =
+            self.select.iter().chain(self.having.iter().map(|c| &c.expr));
+        for expr in exprs {
+            if let Expr::Agg { func, arg } = *expr {
+                if !aggs.contains(&(func, arg)) {
+                    aggs.push((func, arg));
+                }
+            }
+        }
+        aggs
+    }
+}
+
+/// 结果单元格
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// 空值
+    Null,
+    /// 整数
+    Int(i64),
+    /// 浮点数（平均值）
+    Float(f64),
+    /// 文本
+    Text(String),
+}
+
+impl Value {
+    /// 数值形式；文本与空值返回 `None`
+    #[must_use]
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            #[allow(clippy::cast_precision_loss)]
+            Self::Int(v) => Some(*v as f64),
+            Self::Float(v) => Some(*v),
+            Self::Null | Self::Text(_) => None,
+        }
+    }
+
+    /// 排序比较：空值最小，数值按大小，文本按字典序，数值排在文本前
+    fn order(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Self::Null, Self::Null) => Ordering::Equal,
+            (Self::Null, _) => Ordering::Less,
+            (_, Self::Null) => Ordering::Greater,
+            (Self::Text(a), Self::Text(b)) => a.cmp(b),
+            (Self::Text(_), _) => Ordering::Greater,
+            (_, Self::Text(_)) => Ordering::Less,
+            (a, b) => {
+                a.as_f64().partial_cmp(&b.as_f64()).unwrap_or(Ordering::Equal)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Null => f.write_str("NULL"),
+            Self::Int(v) => write!(f, "{v}"),
+            Self::Float(v) => write!(f, "{v:.2}"),
+            Self::Text(s) => f.write_str(s),
+        }
+    }
+}
+
+/// 聚合结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregateResult {
+    /// 列标题
+    pub columns: Vec<String>,
+    /// 数据行
+    pub rows: Vec<Vec<Value>>,
+}
+
+impl fmt::Display for AggregateResult {
+    /// 以对齐的文本表格输出
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| row.iter().map(ToString::to_string).collect())
+            .collect();
+        let widths: Vec<usize> = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(i, title)| {
+                cells
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .fold(title.chars().count(), usize::max)
+            })
+            .collect();
+
+        let write_row = |f: &mut fmt::Formatter<'_>, row: &[String]| {
+            let line: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, w)| format!("{cell:<w$}"))
+                .collect();
+            writeln!(f, "{}", line.join("  ").trim_end())
+        };
+        write_row(f, &self.columns)?;
+        let rule: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+        writeln!(f, "{}", rule.join("  "))?;
+        for row in &cells {
+            write_row(f, row)?;
+        }
+        Ok(())
+    }
+}
+
+/// 分组键中的单元格
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+enum Cell {
+    Null,
+    Int(i64),
+    Text(String),
+}
+
+impl Cell {
+    fn size(&self) -> usize {
+        size_of::<Self>()
+            + match self {
+                Self::Text(s) => s.len(),
+                Self::Null | Self::Int(_) => 0,
+            }
+    }
+}
+
+/// 单个分组在哈希表中的固定开销（键、值的 `Vec` 头部与表项）
+const GROUP_OVERHEAD: usize = 64;
+
+impl From<Cell> for Value {
+    fn from(cell: Cell) -> Self {
+        match cell {
+            Cell::Null => Self::Null,
+            Cell::Int(v) => Self::Int(v),
+            Cell::Text(s) => Self::Text(s),
+        }
+    }
+}
+
+/// 单个聚合在单个分组内的累计状态
+#[derive(Debug, Clone, Default)]
+struct AggState {
+    count: i64,
+    sum: i64,
+    min: Option<i64>,
+    max: Option<i64>,
+    /// 仅百分位聚合保存原始值
+    values: Vec<i64>,
+}
+
+impl AggState {
+    fn update(&mut self, func: AggFunc, value: i64) {
+        self.count += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.min = Some(self.min.map_or(value, |m| m.min(value)));
+        self.max = Some(self.max.map_or(value, |m| m.max(value)));
+        if matches!(func, AggFunc::Percentile(_)) {
+            self.values.push(value);
+        }
+    }
+
+    fn finish(&mut self, func: AggFunc) -> Value {
+        match func {
+            AggFunc::Count => Value::Int(self.count),
+            AggFunc::Sum
+            | AggFunc::Avg
+            | AggFunc::Min
+            | AggFunc::Max
+            | AggFunc::Percentile(_)
+                if self.count == 0 =>
+            {
+                Value::Null
+            }
+            AggFunc::Sum => Value::Int(self.sum),
+            #[allow(clippy::cast_precision_loss)]
+            AggFunc::Avg => Value::Float(self.sum as f64 / self.count as f64),
+            AggFunc::Min => self.min.map_or(Value::Null, Value::Int),
+            AggFunc::Max => self.max.map_or(Value::Null, Value::Int),
+            AggFunc::Percentile(p) => {
+                self.values.sort_unstable();
+                let n = self.values.len();
+                let rank = ((usize::from(p) * n + 99) / 100).max(1);
+                Value::Int(self.values[rank - 1])
+            }
+        }
+    }
+}
+
+/// 内存聚合引擎
+#[derive(Debug, Clone)]
+pub struct Aggregator {
+    query: AggregateQuery,
+    aggs: Vec<(AggFunc, Option<Column>)>,
+    groups: HashMap<Vec<Cell>, Vec<AggState>>,
+    records: u64,
+    estimated_bytes: usize,
+}
+
+impl Aggregator {
+    /// 按查询创建聚合引擎
+    #[must_use]
+    pub fn new(query: AggregateQuery) -> Self {
+        let aggs = query.aggregates();
+        Self {
+            query,
+            aggs,
+            groups: HashMap::new(),
+            records: 0,
+            estimated_bytes: 0,
+        }
+    }
+
+    /// 分组状态占用内存的估算值（字节）
+    ///
+    /// 包括分组键、各聚合状态以及百分位保存的原始值，用于决定是否
+    /// 改用磁盘聚合；不含哈希表自身的扩容余量。
+    #[must_use]
+    pub const fn estimated_bytes(&self) -> usize {
+        self.estimated_bytes
+    }
+
+    /// 已聚合的记录数
+    #[must_use]
+    pub const fn records(&self) -> u64 {
+        self.records
+    }
+
+    /// 当前分组数
+    #[must_use]
+    pub fn groups(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// 聚合一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            self.records += 1;
+            let key: Vec<Cell> =
+                self.query.group_by.iter().map(|c| c.key(record)).collect();
+            let states = match self.groups.entry(key) {
+                Entry::Occupied(e) => e.into_mut(),
+                Entry::Vacant(e) => {
+                    self.estimated_bytes += GROUP_OVERHEAD
+                        + e.key().iter().map(Cell::size).sum::<usize>()
+                        + self.aggs.len() * size_of::<AggState>();
+                    e.insert(vec![AggState::default(); self.aggs.len()])
+                }
+            };
+            for ((func, arg), state) in self.aggs.iter().zip(states.iter_mut())
+            {
+                match arg {
+                    None => state.update(*func, 0),
+                    Some(col) if *func == AggFunc::Count => {
+                        if !col.key(record).eq(&Cell::Null) {
+                            state.update(*func, 0);
+                        }
+                    }
+                    Some(col) => {
+                        if let Some(v) = col.number(record) {
+                            if matches!(func, AggFunc::Percentile(_)) {
+                                self.estimated_bytes += size_of::<i64>();
+                            }
+                            state.update(*func, v);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// 计算聚合结果：应用 having、order-by 与 limit
+    #[must_use]
+    pub fn finish(mut self) -> AggregateResult {
+        // 无分组的全局聚合即使没有记录也输出一行
+        if self.query.group_by.is_empty() && self.groups.is_empty() {
+            self.groups
+                .insert(Vec::new(), vec![AggState::default(); self.aggs.len()]);
+        }
+
+        let mut groups: Vec<_> = self.groups.into_iter().collect();
+        groups.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let query = &self.query;
+        let mut rows = Vec::with_capacity(groups.len());
+        for (key, mut states) in groups {
+            let agg_values: Vec<Value> = self
+                .aggs
+                .iter()
+                .zip(states.iter_mut())
+                .map(|((func, _), state)| state.finish(*func))
+                .collect();
+            let key: Vec<Value> = key.into_iter().map(Value::from).collect();
+            let eval = |expr: &Expr| -> Value {
+                match *expr {
+                    Expr::Column(c) => query
+                        .group_by
+                        .iter()
+                        .position(|g| *g == c)
+                        .map_or(Value::Null, |i| key[i].clone()),
+                    Expr::Agg { func, arg } => self
+                        .aggs
+                        .iter()
+                        .position(|a| *a == (func, arg))
+                        .map_or(Value::Null, |i| agg_values[i].clone()),
+                }
+            };
+
+            let keep = query.having.iter().all(|cond| {
+                eval(&cond.expr)
+                    .as_f64()
+                    .is_some_and(|v| cond.op.eval(v, cond.value))
+            });
+            if keep {
+                rows.push(query.select.iter().map(eval).collect::<Vec<_>>());
+            }
+        }
+
+        if !query.order_by.is_empty() {
+            rows.sort_by(|a, b| {
+                query.order_by.iter().fold(Ordering::Equal, |acc, key| {
+                    acc.then_with(|| {
+                        let ord = a[key.index].order(&b[key.index]);
+                        if key.descending { ord.reverse() } else { ord }
+                    })
+                })
+            });
+        }
+        if let Some(limit) = query.limit {
+            rows.truncate(limit);
+        }
+
+        AggregateResult { columns: query.columns(), rows }
+    }
+}
+
+/// order-by 项的目标
+enum OrderTarget {
+    Position(usize),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(f64),
+    Star,
+    Comma,
+    LParen,
+    RParen,
+    Op(CompareOp),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '*' | ',' | '(' | ')' => {
+                chars.next();
+                tokens.push(match c {
+                    '*' => Token::Star,
+                    ',' => Token::Comma,
+                    '(' => Token::LParen,
+                    _ => Token::RParen,
+                });
+            }
+            '>' | '<' | '=' | '!' => {
+                chars.next();
+                let next = chars.peek().map(|&(_, n)| n);
+                let (op, two) = match (c, next) {
+                    ('>', Some('=')) => (CompareOp::Ge, true),
+                    ('<', Some('=')) => (CompareOp::Le, true),
+                    ('<', Some('>')) | ('!', Some('=')) => {
+                        (CompareOp::Ne, true)
+                    }
+                    ('=', Some('=')) => (CompareOp::Eq, true),
+                    ('>', _) => (CompareOp::Gt, false),
+                    ('<', _) => (CompareOp::Lt, false),
+                    ('=', _) => (CompareOp::Eq, false),
+                    _ => {
+                        return Err(QueryError::Syntax(format!(
+                            "位置 {start} 处无法识别的运算符 `!`"
+                        )));
+                    }
+                };
+                if two {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_ascii_digit() || c == '.' || c == '-' => {
+                let mut end = start;
+                while let Some(&(i, d)) = chars.peek() {
+                    if d.is_ascii_digit()
+                        || d == '.'
+                        || (i == start && d == '-')
+                    {
+                        end = i + d.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                let text = &input[start..end];
+                let n = text.parse::<f64>().map_err(|_| {
+                    QueryError::Syntax(format!("无效的数值 `{text}`"))
+                })?;
+                tokens.push(Token::Number(n));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut end = start;
+                while let Some(&(i, d)) = chars.peek() {
+                    if d.is_alphanumeric() || d == '_' {
+                        end = i + d.len_utf8();
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(input[start..end].to_string()));
+            }
+            other => {
+                return Err(QueryError::Syntax(format!(
+                    "位置 {start} 处无法识别的字符 `{other}`"
+                )));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// 递归下降解析器
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn new(input: &str) -> Result<Self, QueryError> {
+        Ok(Self { tokens: tokenize(input)?, pos: 0 })
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), QueryError> {
+        match self.next() {
+            Some(t) if t == *expected => Ok(()),
+            other => Err(QueryError::Syntax(format!(
+                "期望 {expected:?}，实际为 {other:?}"
+            ))),
+        }
+    }
+
+    fn ident(&mut self) -> Result<String, QueryError> {
+        match self.next() {
+            Some(Token::Ident(s)) => Ok(s),
+            other => {
+                Err(QueryError::Syntax(format!("期望标识符，实际为 {other:?}")))
+            }
+        }
+    }
+
+    fn column(name: &str) -> Result<Column, QueryError> {
+        Column::from_name(name)
+            .ok_or_else(|| QueryError::UnknownColumn(name.to_string()))
+    }
+
+    /// `ident` 或 `func(*)` / `func(col)`
+    fn expr(&mut self) -> Result<Expr, QueryError> {
+        let name = self.ident()?;
+        if self.peek() != Some(&Token::LParen) {
+            return Ok(Expr::Column(Self::column(&name)?));
+        }
+        self.next();
+        let func = AggFunc::from_name(&name)
+            .ok_or_else(|| QueryError::UnknownFunction(name.clone()))?;
+        let arg = if self.peek() == Some(&Token::Star) {
+            self.next();
+            if func != AggFunc::Count {
+                return Err(QueryError::Syntax(format!(
+                    "只有 count 支持 `*`，不支持 {func}(*)"
+                )));
+            }
+            None
+        } else {
+            let column = Self::column(&self.ident()?)?;
+            if func != AggFunc::Count && !column.is_numeric() {
+                return Err(QueryError::NotNumeric {
+                    func: func.to_string(),
+                    column: column.to_string(),
+                });
+            }
+            Some(column)
+        };
+        self.expect(&Token::RParen)?;
+        Ok(Expr::Agg { func, arg })
+    }
+
+    /// 以逗号分隔的列表
+    fn list<T>(
+        &mut self,
+        mut item: impl FnMut(&mut Self) -> Result<T, QueryError>,
+    ) -> Result<Vec<T>, QueryError> {
+        let mut items = Vec::new();
+        if self.at_end() {
+            return Ok(items);
+        }
+        loop {
+            items.push(item(self)?);
+            match self.next() {
+                None => return Ok(items),
+                Some(Token::Comma) => {}
+                Some(other) => {
+                    return Err(QueryError::Syntax(format!(
+                        "期望 `,`，实际为 {other:?}"
+                    )));
+                }
+            }
+        }
+    }
+
+    fn expr_list(&mut self) -> Result<Vec<Expr>, QueryError> {
+        self.list(Self::expr)
+    }
+
+    fn column_list(&mut self) -> Result<Vec<Column>, QueryError> {
+        self.list(|p| Self::column(&p.ident()?))
+    }
+
+    fn conditions(&mut self) -> Result<Vec<Condition>, QueryError> {
+        let mut conds = Vec::new();
+        if self.at_end() {
+            return Ok(conds);
+        }
+        loop {
+            let expr = self.expr()?;
+            let op = match self.next() {
+                Some(Token::Op(op)) => op,
+                other => {
+                    return Err(QueryError::Syntax(format!(
+                        "期望比较运算符，实际为 {other:?}"
+                    )));
+                }
+            };
+            let value = match self.next() {
+                Some(Token::Number(n)) => n,
+                other => {
+                    return Err(QueryError::Syntax(format!(
+                        "期望数值，实际为 {other:?}"
+                    )));
+                }
+            };
+            conds.push(Condition { expr, op, value });
+            match self.next() {
+                None => return Ok(conds),
+                Some(Token::Ident(s)) if s.eq_ignore_ascii_case("and") => {}
+                Some(other) => {
+                    return Err(QueryError::Syntax(format!(
+                        "期望 `and`，实际为 {other:?}"
+                    )));
+                }
+            }
+        }
+    }
+
+    fn order_items(&mut self) -> Result<Vec<(OrderTarget, bool)>, QueryError> {
+        self.list(|p| {
+            let target = match p.peek() {
+                Some(Token::Number(n)) => {
+                    let n = *n;
+                    p.next();
+                    if n.fract() != 0.0 || n < 0.0 {
+                        return Err(QueryError::Syntax(format!(
+                            "order-by 序号必须为正整数: {n}"
+                        )));
+                    }
+                    #[allow(
+                        clippy::cast_possible_truncation,
+                        clippy::cast_sign_loss
+                    )]
+                    OrderTarget::Position(n as usize)
+                }
+                _ => OrderTarget::Expr(p.expr()?),
+            };
+            let descending = match p.peek() {
+                Some(Token::Ident(s)) if s.eq_ignore_ascii_case("desc") => {
+                    p.next();
+                    true
+                }
+                Some(Token::Ident(s)) if s.eq_ignore_ascii_case("asc") => {
+                    p.next();
+                    false
+                }
+                _ => false,
+            };
+            Ok((target, descending))
+        })
+    }
+}
@@ -0,0 +1,275 @@
+//! 应用负载 - 按 appname 汇总各应用对数据库的访问
+//!
+//! 产品团队常问"我的应用对数据库做了什么"。[`AppWorkloadAnalyzer`] 按规范化
+//! 后的 appname 聚合调用次数、执行时间 p50/p95/p99，以及每个应用执行时间
+//! 合计最高的若干 SQL 指纹（口径与 [`diff`](super::diff) 一致）。
+//!
+//! 同一应用在日志里的名字往往不统一（大小写、多余空格、版本号后缀），
+//! appname 先去掉首尾空白、合并连续空白并转为小写，再按 [`AppAliases`]
+//! 映射到统一的应用名：别名键同样规范化后比较，以 `*` 结尾的键按前缀匹配，
+//! 精确匹配优先，多个前缀都匹配时取最长的。没有 appname 的记录归入
+//! [`UNKNOWN_APP`]。
+
+use super::fingerprint::fingerprint;
+use crate::sqllog::{ExecTimeMs, Sqllog};
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// 没有 appname 的记录所归入的应用名
+pub const UNKNOWN_APP: &str = "(unknown)";
+
+/// 每个应用默认列出的指纹数
+pub const DEFAULT_TOP_FINGERPRINTS: usize = 5;
+
+/// appname 别名表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AppAliases {
+    exact: HashMap<String, String>,
+    /// 按前缀长度降序
+    prefixes: Vec<(String, String)>,
+}
+
+impl AppAliases {
+    /// 由 `原始名称（可以 * 结尾）-> 应用名` 的映射创建别名表
+    #[must_use]
+    pub fn new<'a, I>(aliases: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a String, &'a String)>,
+    {
+        let mut table = Self::default();
+        for (from, to) in aliases {
+            match from.trim().strip_suffix('*') {
+                Some(prefix) => table
+                    .prefixes
+                    .push((normalize_appname(prefix), to.trim().to_string())),
+                None => {
+                    table
+                        .exact
+                        .insert(normalize_appname(from), to.trim().to_string());
+                }
+            }
+        }
+        table
+            .prefixes
+            .sort_by_key(|(prefix, _)| std::cmp::Reverse(prefix.len()));
+        table
+    }
+
+    /// 记录所属的应用名
+    #[must_use]
+    pub fn resolve(&self, appname: Option<&str>) -> String {
+        let name = appname.map(normalize_appname).unwrap_or_default();
+        if name.is_empty() {
+            return UNKNOWN_APP.to_string();
+        }
+        if let Some(app) = self.exact.get(&name) {
+            return app.clone();
+        }
+        self.prefixes
+            .iter()
+            .find(|(prefix, _)| name.starts_with(prefix.as_str()))
+            .map_or(name, |(_, app)| app.clone())
+    }
+}
+
+/// 去掉首尾空白、合并连续空白并转为小写
+#[must_use]
+pub fn normalize_appname(appname: &str) -> String {
+    appname.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+/// 应用内的一类语句
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppStatement {
+    /// SQL 指纹
+    pub fingerprint: String,
+    /// 调用次数
+    pub calls: u64,
+    /// 执行时间合计
+    pub total_time: ExecTimeMs,
+}
+
+/// 单个应用的负载
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AppStats {
+    /// 统一后的应用名
+    pub app: String,
+    /// 归入该应用的原始 appname（去重、排序）
+    pub raw_names: Vec<String>,
+    /// 记录数
+    pub calls: u64,
+    /// 执行时间合计
+    pub total_time: ExecTimeMs,
+    pub p50: Option<ExecTimeMs>,
+    pub p95: Option<ExecTimeMs>,
+    pub p99: Option<ExecTimeMs>,
+    /// 执行时间合计最高的指纹（相同时按调用次数）
+    pub top_statements: Vec<AppStatement>,
+}
+
+/// 应用负载报告
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AppReport {
+    /// 记录总数
+    pub records: u64,
+    /// 各应用，按执行时间合计降序（相同时按调用次数）
+    pub apps: Vec<AppStats>,
+}
+
+impl fmt::Display for AppReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = |t: Option<ExecTimeMs>| {
+            t.map_or_else(|| "-".to_string(), |t| t.to_string())
+        };
+        writeln!(f, "记录数: {}，应用数: {}", self.records, self.apps.len())?;
+        for app in &self.apps {
+            writeln!(
+                f,
+                "{}: 调用 {}，合计 {}，p50 {}，p95 {}，p99 {}",
+                app.app,
+                app.calls,
+                app.total_time,
+                time(app.p50),
+                time(app.p95),
+                time(app.p99)
+            )?;
+            for statement in &app.top_statements {
+                writeln!(
+                    f,
+                    "  {:>8} 次  {:>12}  {}",
+                    statement.calls,
+                    statement.total_time.to_string(),
+                    statement.fingerprint
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct AppGroup {
+    raw_names: BTreeSet<String>,
+    calls: u64,
+    total_time: ExecTimeMs,
+    times: Vec<i64>,
+    statements: HashMap<String, (u64, ExecTimeMs)>,
+}
+
+/// 按应用聚合负载
+#[derive(Debug, Clone)]
+pub struct AppWorkloadAnalyzer {
+    aliases: AppAliases,
+    top: usize,
+    records: u64,
+    apps: HashMap<String, AppGroup>,
+}
+
+impl Default for AppWorkloadAnalyzer {
+    fn default() -> Self {
+        Self::new(AppAliases::default())
+    }
+}
+
+impl AppWorkloadAnalyzer {
+    /// 使用给定别名表创建分析器，每个应用列出
+    /// [`DEFAULT_TOP_FINGERPRINTS`] 个指纹
+    #[must_use]
+    pub fn new(aliases: AppAliases) -> Self {
+        Self {
+            aliases,
+            top: DEFAULT_TOP_FINGERPRINTS,
+            records: 0,
+            apps: HashMap::new(),
+        }
+    }
+
+    /// 设置每个应用列出的指纹数
+    #[must_use]
+    pub const fn with_top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// 聚合一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            self.records += 1;
+            let app = self.aliases.resolve(record.appname.as_deref());
+            let group = self.apps.entry(app).or_default();
+            if let Some(raw) = &record.appname {
+                if !group.raw_names.contains(raw) {
+                    group.raw_names.insert(raw.clone());
+                }
+            }
+            group.calls += 1;
+            let time = record.execute_time.unwrap_or_default();
+            if let Some(t) = record.execute_time {
+                group.total_time += t;
+                group.times.push(t.get());
+            }
+            let statement = group
+                .statements
+                .entry(fingerprint(&record.description))
+                .or_default();
+            statement.0 += 1;
+            statement.1 += time;
+        }
+    }
+
+    /// 生成报告
+    #[must_use]
+    pub fn report(&self) -> AppReport {
+        let mut apps: Vec<AppStats> = self
+            .apps
+            .iter()
+            .map(|(app, group)| {
+                let mut times = group.times.clone();
+                times.sort_unstable();
+                // 最近秩百分位，与 diff 的 p95 口径一致
+                let pick = |p: usize| {
+                    let n = times.len();
+                    (n > 0).then(|| {
+                        ExecTimeMs::new(times[((p * n + 99) / 100).max(1) - 1])
+                    })
+                };
+                let mut statements: Vec<AppStatement> = group
+                    .statements
+                    .iter()
+                    .map(|(fp, &(calls, total_time))| AppStatement {
+                        fingerprint: fp.clone(),
+                        calls,
+                        total_time,
+                    })
+                    .collect();
+                statements.sort_by(|a, b| {
+                    (b.total_time, b.calls, &a.fingerprint).cmp(&(
+                        a.total_time,
+                        a.calls,
+                        &b.fingerprint,
+                    ))
+                });
+                statements.truncate(self.top);
+                AppStats {
+                    app: app.clone(),
+                    raw_names: group.raw_names.iter().cloned().collect(),
+                    calls: group.calls,
+                    total_time: group.total_time,
+                    p50: pick(50),
+                    p95: pick(95),
+                    p99: pick(99),
+                    top_statements: statements,
+                }
+            })
+            .collect();
+        apps.sort_by(|a, b| {
+            (b.total_time, b.calls, &a.app).cmp(&(
+                a.total_time,
+                a.calls,
+                &b.app,
+            ))
+        });
+        AppReport { records: self.records, apps }
+    }
+}
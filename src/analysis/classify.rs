@@ -0,0 +1,251 @@
+//! 安全分级 - 按用户、IP 与语句模式为记录分级
+//!
+//! 规则在配置中定义（`[[classify.rules]]`），每条规则给出名称、级别，以及
+//! `user` / `ip` / `pattern`（匹配 description）三项正则中的至少一项；给出的
+//! 各项都匹配时规则命中。一条记录可以命中多条规则，各规则分别计数，记录的
+//! 级别取命中规则中最高的一级（forbidden > sensitive > normal），未命中任何
+//! 规则的记录为 normal。
+//!
+//! ```toml
+//! [[classify.rules]]
+//! name = "drop_table"
+//! class = "forbidden"
+//! pattern = '(?i)^\s*drop\s+table'
+//!
+//! [[classify.rules]]
+//! name = "salary_from_vpn"
+//! class = "sensitive"
+//! ip = '^172\.16\.'
+//! pattern = '(?i)\bsalary\b'
+//! ```
+
+use crate::sqllog::Sqllog;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// 记录的安全级别
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum SecurityClass {
+    /// 普通语句
+    #[default]
+    Normal,
+    /// 涉及敏感数据，需要留痕
+    Sensitive,
+    /// 违反规范，需要逐条审查
+    Forbidden,
+}
+
+impl SecurityClass {
+    /// 级别名称
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Sensitive => "sensitive",
+            Self::Forbidden => "forbidden",
+        }
+    }
+}
+
+impl fmt::Display for SecurityClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.as_str())
+    }
+}
+
+/// 可从配置文件反序列化的分级规则
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ClassifyRuleConfig {
+    /// 规则名称，用于计数与命中记录中的标注
+    pub name: String,
+    /// 命中时的级别
+    pub class: SecurityClass,
+    /// 匹配用户名的正则
+    pub user: Option<String>,
+    /// 匹配客户端 IP 的正则
+    pub ip: Option<String>,
+    /// 匹配 description 的正则
+    pub pattern: Option<String>,
+}
+
+/// 单条分级规则
+#[derive(Debug, Clone)]
+pub struct ClassifyRule {
+    /// 规则名称
+    pub name: String,
+    /// 命中时的级别
+    pub class: SecurityClass,
+    user: Option<Regex>,
+    ip: Option<Regex>,
+    pattern: Option<Regex>,
+}
+
+impl ClassifyRule {
+    /// 从配置定义构造规则
+    ///
+    /// # Errors
+    /// 三项条件都未给出或任一正则无法编译时返回错误描述
+    pub fn from_config(config: &ClassifyRuleConfig) -> Result<Self, String> {
+        let compile = |field: &str, pattern: &Option<String>| {
+            pattern.as_deref().map(Regex::new).transpose().map_err(|e| {
+                format!("规则 {} 的 {field} 无效: {e}", config.name)
+            })
+        };
+        let rule = Self {
+            name: config.name.clone(),
+            class: config.class,
+            user: compile("user", &config.user)?,
+            ip: compile("ip", &config.ip)?,
+            pattern: compile("pattern", &config.pattern)?,
+        };
+        if rule.user.is_none() && rule.ip.is_none() && rule.pattern.is_none() {
+            return Err(format!(
+                "规则 {} 至少需要 user/ip/pattern 中的一项",
+                config.name
+            ));
+        }
+        Ok(rule)
+    }
+
+    /// 记录是否命中规则；规则要求的字段在记录中缺失时不命中
+    #[must_use]
+    pub fn matches(&self, record: &Sqllog) -> bool {
+        let field_matches = |regex: &Option<Regex>, value: Option<&str>| {
+            regex.as_ref().map_or(true, |regex| {
+                value.is_some_and(|value| regex.is_match(value))
+            })
+        };
+        field_matches(&self.user, record.user.as_deref())
+            && field_matches(&self.ip, record.ip.as_deref())
+            && field_matches(&self.pattern, Some(&record.description))
+    }
+}
+
+/// 安全分级结果
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ClassifyReport {
+    /// 参与分级的记录总数
+    pub records_scanned: u64,
+    /// 各级别的记录数
+    pub by_class: BTreeMap<SecurityClass, u64>,
+    /// 各规则的命中次数（未命中的规则计为 0）
+    pub by_rule: BTreeMap<String, u64>,
+}
+
+impl fmt::Display for ClassifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "记录数: {}", self.records_scanned)?;
+        for class in [
+            SecurityClass::Forbidden,
+            SecurityClass::Sensitive,
+            SecurityClass::Normal,
+        ] {
+            let count = self.by_class.get(&class).copied().unwrap_or(0);
+            writeln!(f, "  {class:<10}{count:>10}")?;
+        }
+        writeln!(f, "规则命中:")?;
+        for (rule, count) in &self.by_rule {
+            writeln!(f, "  {rule:<24}{count:>10}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 安全分级器
+#[derive(Debug, Clone)]
+pub struct Classifier {
+    rules: Vec<ClassifyRule>,
+    report: ClassifyReport,
+}
+
+impl Classifier {
+    /// 使用给定规则列表创建分级器
+    #[must_use]
+    pub fn new(rules: Vec<ClassifyRule>) -> Self {
+        let by_rule = rules.iter().map(|rule| (rule.name.clone(), 0)).collect();
+        Self { rules, report: ClassifyReport { by_rule, ..Default::default() } }
+    }
+
+    /// 从配置定义构造分级器
+    ///
+    /// # Errors
+    /// 任一规则无效时返回错误描述
+    pub fn from_configs(
+        configs: &[ClassifyRuleConfig],
+    ) -> Result<Self, String> {
+        let rules = configs
+            .iter()
+            .map(ClassifyRule::from_config)
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(rules))
+    }
+
+    /// 返回当前使用的规则列表
+    #[must_use]
+    pub fn rules(&self) -> &[ClassifyRule] {
+        &self.rules
+    }
+
+    /// 返回单条记录的级别与命中的规则名称（按规则顺序）
+    #[must_use]
+    pub fn classify(&self, record: &Sqllog) -> (SecurityClass, Vec<&str>) {
+        classify_by(&self.rules, record)
+    }
+
+    /// 分级一批记录并累加统计
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        self.observe_with(records, |_, _, _| {});
+    }
+
+    /// 分级一批记录并累加统计，同时把每条记录的级别与命中的规则交给
+    /// `on_record`，便于单独写出 forbidden 记录
+    pub fn observe_with<F>(&mut self, records: &[Sqllog], mut on_record: F)
+    where
+        F: FnMut(&Sqllog, SecurityClass, &[&str]),
+    {
+        for record in records {
+            let (class, names) = classify_by(&self.rules, record);
+            let report = &mut self.report;
+            report.records_scanned += 1;
+            *report.by_class.entry(class).or_insert(0) += 1;
+            for name in &names {
+                *report.by_rule.entry((*name).to_string()).or_insert(0) += 1;
+            }
+            on_record(record, class, &names);
+        }
+    }
+
+    /// 返回当前累计的分级结果
+    #[must_use]
+    pub const fn report(&self) -> &ClassifyReport {
+        &self.report
+    }
+}
+
+fn classify_by<'a>(
+    rules: &'a [ClassifyRule],
+    record: &Sqllog,
+) -> (SecurityClass, Vec<&'a str>) {
+    let mut class = SecurityClass::Normal;
+    let mut names = Vec::new();
+    for rule in rules.iter().filter(|rule| rule.matches(record)) {
+        class = class.max(rule.class);
+        names.push(rule.name.as_str());
+    }
+    (class, names)
+}
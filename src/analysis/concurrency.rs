@@ -0,0 +1,244 @@
+//! 会话并发 - 按时间桶重建活跃会话数
+//!
+//! 以会话在日志中首次出现到最后一条语句执行结束（`occurrence_time` 加上
+//! `execute_time`）的区间作为其活跃期，统计每个时间桶内活跃的会话数，
+//! 并按用户、appname 给出并发峰值及出现时间。sqllog 只记录执行过语句的
+//! 时刻，空闲会话在两条语句之间视为一直活跃，因此结果是近似值。
+//! 没有会话信息（`sess:NULL`）或时间戳无法解析的记录不参与统计。
+
+use super::timeline::parse_occurrence_time;
+use crate::sqllog::Sqllog;
+use chrono::{DateTime, NaiveDateTime};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// 默认时间桶宽度（秒）
+pub const DEFAULT_BUCKET_SECS: u32 = 60;
+
+/// 单个时间桶的活跃会话数
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConcurrencyBucket {
+    /// 桶起始时间
+    pub start: NaiveDateTime,
+    pub active_sessions: u64,
+}
+
+/// 某个用户或 appname 的并发峰值
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PeakConcurrency {
+    pub name: String,
+    /// 同一时间桶内的最大活跃会话数
+    pub peak: u64,
+    /// 首次达到峰值的时间桶起点
+    pub at: NaiveDateTime,
+    /// 出现过的会话总数
+    pub sessions: u64,
+}
+
+/// 会话并发统计结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ConcurrencyReport {
+    pub bucket_secs: u32,
+    /// 首末活跃桶之间的所有时间桶（含活跃数为 0 的桶）
+    pub buckets: Vec<ConcurrencyBucket>,
+    /// 整体并发峰值（取最早的一个）
+    pub peak: Option<ConcurrencyBucket>,
+    /// 按用户的并发峰值，按峰值降序
+    pub by_user: Vec<PeakConcurrency>,
+    /// 按 appname 的并发峰值，按峰值降序
+    pub by_appname: Vec<PeakConcurrency>,
+    /// 参与统计的会话数
+    pub sessions: u64,
+    /// 缺少会话信息或时间戳无法解析而被跳过的记录数
+    pub skipped: u64,
+}
+
+impl fmt::Display for ConcurrencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(peak) = &self.peak else {
+            return writeln!(f, "没有可用的会话记录");
+        };
+        writeln!(
+            f,
+            "会话数: {}  桶宽: {} 秒  并发峰值: {}（{}）",
+            self.sessions, self.bucket_secs, peak.active_sessions, peak.start
+        )?;
+        for (title, peaks) in
+            [("按用户", &self.by_user), ("按 appname", &self.by_appname)]
+        {
+            writeln!(f, "{title}:")?;
+            for p in peaks {
+                writeln!(
+                    f,
+                    "  {}  峰值 {}（{}）  会话 {}",
+                    p.name, p.peak, p.at, p.sessions
+                )?;
+            }
+        }
+        writeln!(f, "按时间桶:")?;
+        for bucket in &self.buckets {
+            writeln!(f, "  {}  {}", bucket.start, bucket.active_sessions)?;
+        }
+        if self.skipped > 0 {
+            writeln!(f, "跳过的记录: {}", self.skipped)?;
+        }
+        Ok(())
+    }
+}
+
+/// 单个会话的活跃区间（Unix 秒）与归属
+#[derive(Debug, Clone)]
+struct SessionSpan {
+    start: i64,
+    end: i64,
+    user: Option<String>,
+    appname: Option<String>,
+}
+
+/// 会话并发分析器
+#[derive(Debug, Clone)]
+pub struct ConcurrencyAnalyzer {
+    bucket_secs: u32,
+    sessions: HashMap<String, SessionSpan>,
+    skipped: u64,
+}
+
+impl ConcurrencyAnalyzer {
+    /// 创建分析器，`bucket_secs` 为时间桶宽度（秒），为 0 时按 1 秒处理。
+    #[must_use]
+    pub fn new(bucket_secs: u32) -> Self {
+        Self {
+            bucket_secs: bucket_secs.max(1),
+            sessions: HashMap::new(),
+            skipped: 0,
+        }
+    }
+
+    /// 处理一批记录，记录不必按时间顺序到达。
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            let (Some(session), Some(ts)) = (
+                &record.session,
+                parse_occurrence_time(&record.occurrence_time),
+            ) else {
+                self.skipped += 1;
+                continue;
+            };
+            let start = ts.and_utc().timestamp();
+            let end = start
+                + record.execute_time.map_or(0, |t| t.get().max(0) / 1000);
+            let span =
+                self.sessions.entry(session.clone()).or_insert_with(|| {
+                    SessionSpan { start, end, user: None, appname: None }
+                });
+            span.start = span.start.min(start);
+            span.end = span.end.max(end);
+            if span.user.is_none() {
+                span.user.clone_from(&record.user);
+            }
+            if span.appname.is_none() {
+                span.appname.clone_from(&record.appname);
+            }
+        }
+    }
+
+    fn bucket_of(&self, secs: i64) -> i64 {
+        let width = i64::from(self.bucket_secs);
+        secs.div_euclid(width) * width
+    }
+
+    /// 活跃数变化量：会话起始桶 +1，结束桶的下一个桶 -1
+    fn deltas<'a>(
+        &self,
+        spans: impl Iterator<Item = &'a SessionSpan>,
+    ) -> BTreeMap<i64, i64> {
+        let mut deltas = BTreeMap::new();
+        for span in spans {
+            *deltas.entry(self.bucket_of(span.start)).or_insert(0) += 1;
+            let after = self.bucket_of(span.end) + i64::from(self.bucket_secs);
+            *deltas.entry(after).or_insert(0) -= 1;
+        }
+        deltas
+    }
+
+    /// 按用户或 appname 分组计算并发峰值
+    fn peaks_by(
+        &self,
+        group: impl Fn(&SessionSpan) -> Option<&String>,
+    ) -> Vec<PeakConcurrency> {
+        let mut groups: HashMap<&String, Vec<&SessionSpan>> = HashMap::new();
+        for span in self.sessions.values() {
+            if let Some(name) = group(span) {
+                groups.entry(name).or_default().push(span);
+            }
+        }
+        let mut peaks: Vec<PeakConcurrency> = groups
+            .into_iter()
+            .map(|(name, spans)| {
+                let mut active = 0i64;
+                let mut peak = (0i64, 0i64);
+                for (&bucket, &delta) in &self.deltas(spans.iter().copied()) {
+                    active += delta;
+                    if active > peak.0 {
+                        peak = (active, bucket);
+                    }
+                }
+                PeakConcurrency {
+                    name: name.clone(),
+                    peak: u64::try_from(peak.0).unwrap_or(0),
+                    at: to_naive(peak.1),
+                    sessions: spans.len() as u64,
+                }
+            })
+            .collect();
+        peaks.sort_by(|a, b| b.peak.cmp(&a.peak).then(a.name.cmp(&b.name)));
+        peaks
+    }
+
+    /// 生成并发统计结果
+    #[must_use]
+    pub fn report(&self) -> ConcurrencyReport {
+        let deltas = self.deltas(self.sessions.values());
+        let mut buckets = Vec::new();
+        if let (Some((&first, _)), Some((&after_last, _))) =
+            (deltas.first_key_value(), deltas.last_key_value())
+        {
+            let mut active = 0i64;
+            for bucket in (first..after_last).step_by(self.bucket_secs as usize)
+            {
+                active += deltas.get(&bucket).copied().unwrap_or(0);
+                buckets.push(ConcurrencyBucket {
+                    start: to_naive(bucket),
+                    active_sessions: u64::try_from(active).unwrap_or(0),
+                });
+            }
+        }
+        let peak = buckets
+            .iter()
+            .reduce(|best, b| {
+                if b.active_sessions > best.active_sessions { b } else { best }
+            })
+            .cloned();
+
+        ConcurrencyReport {
+            bucket_secs: self.bucket_secs,
+            buckets,
+            peak,
+            by_user: self.peaks_by(|s| s.user.as_ref()),
+            by_appname: self.peaks_by(|s| s.appname.as_ref()),
+            sessions: self.sessions.len() as u64,
+            skipped: self.skipped,
+        }
+    }
+}
+
+impl Default for ConcurrencyAnalyzer {
+    fn default() -> Self {
+        Self::new(DEFAULT_BUCKET_SECS)
+    }
+}
+
+fn to_naive(secs: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default().naive_utc()
+}
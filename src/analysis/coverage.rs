@@ -0,0 +1,208 @@
+//! 日志覆盖检测 - 发现 `occurrence_time` 中的长时间空档
+//!
+//! 相邻两条记录的时间差超过阈值时视为一个空档，通常意味着 sqllog 被关闭
+//! 或数据库不可用。按小时统计覆盖率：每个小时内（限于首末记录之间的部分）
+//! 不落在空档中的时间占比。记录不必按时间顺序到达。
+
+use super::timeline::parse_occurrence_time;
+use crate::sqllog::Sqllog;
+use chrono::{DateTime, NaiveDateTime};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// 默认空档阈值（秒）
+pub const DEFAULT_GAP_SECS: u32 = 300;
+
+const HOUR_SECS: i64 = 3600;
+
+/// 一个时间空档：两条相邻记录之间没有任何日志
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimeGap {
+    /// 空档前最后一条记录的时间（秒级）
+    pub start: NaiveDateTime,
+    /// 空档后第一条记录的时间（秒级）
+    pub end: NaiveDateTime,
+    pub duration_secs: i64,
+}
+
+/// 单个小时的覆盖情况
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HourCoverage {
+    /// 小时起始时间
+    pub hour: NaiveDateTime,
+    pub records: u64,
+    /// 覆盖率（0.0 ~ 1.0）
+    pub coverage: f64,
+}
+
+/// 覆盖检测结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CoverageReport {
+    pub first: Option<NaiveDateTime>,
+    pub last: Option<NaiveDateTime>,
+    pub gap_threshold_secs: u32,
+    pub gaps: Vec<TimeGap>,
+    pub hours: Vec<HourCoverage>,
+    /// 首末记录之间的整体覆盖率
+    pub coverage: f64,
+    /// 时间戳无法解析而被跳过的记录数
+    pub skipped: u64,
+}
+
+impl fmt::Display for CoverageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (Some(first), Some(last)) = (self.first, self.last) else {
+            return writeln!(f, "没有可用的记录时间");
+        };
+        writeln!(f, "时间范围: {first} ~ {last}")?;
+        writeln!(f, "整体覆盖率: {:.1}%", self.coverage * 100.0)?;
+        writeln!(
+            f,
+            "超过 {} 秒的空档: {} 个",
+            self.gap_threshold_secs,
+            self.gaps.len()
+        )?;
+        for gap in &self.gaps {
+            writeln!(
+                f,
+                "  {} ~ {}（{} 秒）",
+                gap.start, gap.end, gap.duration_secs
+            )?;
+        }
+        writeln!(f, "按小时覆盖率:")?;
+        for hour in &self.hours {
+            writeln!(
+                f,
+                "  {}  {:>6.1}%  {} 条",
+                hour.hour,
+                hour.coverage * 100.0,
+                hour.records
+            )?;
+        }
+        if self.skipped > 0 {
+            writeln!(f, "时间戳无法解析的记录: {}", self.skipped)?;
+        }
+        Ok(())
+    }
+}
+
+/// 日志覆盖检测器
+#[derive(Debug, Clone)]
+pub struct CoverageAnalyzer {
+    gap_secs: u32,
+    /// 出现过记录的秒（Unix 时间戳）
+    seconds: BTreeSet<i64>,
+    /// 小时起点 -> 记录数
+    hour_records: BTreeMap<i64, u64>,
+    skipped: u64,
+}
+
+impl CoverageAnalyzer {
+    /// 创建检测器，`gap_secs` 为空档阈值（秒），为 0 时按 1 秒处理。
+    #[must_use]
+    pub fn new(gap_secs: u32) -> Self {
+        Self {
+            gap_secs: gap_secs.max(1),
+            seconds: BTreeSet::new(),
+            hour_records: BTreeMap::new(),
+            skipped: 0,
+        }
+    }
+
+    /// 处理一批记录。时间戳无法解析的记录会被计入 `skipped`。
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            let Some(ts) = parse_occurrence_time(&record.occurrence_time)
+            else {
+                self.skipped += 1;
+                continue;
+            };
+            let secs = ts.and_utc().timestamp();
+            self.seconds.insert(secs);
+            *self
+                .hour_records
+                .entry(secs.div_euclid(HOUR_SECS) * HOUR_SECS)
+                .or_insert(0) += 1;
+        }
+    }
+
+    /// 生成覆盖检测结果
+    #[must_use]
+    pub fn report(&self) -> CoverageReport {
+        let threshold = i64::from(self.gap_secs);
+        let gaps: Vec<(i64, i64)> = self
+            .seconds
+            .iter()
+            .zip(self.seconds.iter().skip(1))
+            .filter(|(a, b)| **b - **a > threshold)
+            .map(|(a, b)| (*a, *b))
+            .collect();
+
+        let (Some(&first), Some(&last)) =
+            (self.seconds.first(), self.seconds.last())
+        else {
+            return CoverageReport {
+                first: None,
+                last: None,
+                gap_threshold_secs: self.gap_secs,
+                gaps: Vec::new(),
+                hours: Vec::new(),
+                coverage: 0.0,
+                skipped: self.skipped,
+            };
+        };
+
+        // 覆盖区间按秒计，最后一条记录所在的那一秒也算作已覆盖
+        let end = last + 1;
+        let gap_overlap = |from: i64, to: i64| -> i64 {
+            gaps.iter()
+                .map(|&(a, b)| (b.min(to) - (a + 1).max(from)).max(0))
+                .sum()
+        };
+        let ratio = |from: i64, to: i64| {
+            let span = to - from;
+            if span <= 0 {
+                return 1.0;
+            }
+            (span - gap_overlap(from, to)) as f64 / span as f64
+        };
+
+        let first_hour = first.div_euclid(HOUR_SECS) * HOUR_SECS;
+        let hours = (first_hour..end)
+            .step_by(HOUR_SECS as usize)
+            .map(|hour| HourCoverage {
+                hour: to_naive(hour),
+                records: self.hour_records.get(&hour).copied().unwrap_or(0),
+                coverage: ratio(hour.max(first), (hour + HOUR_SECS).min(end)),
+            })
+            .collect();
+
+        CoverageReport {
+            first: Some(to_naive(first)),
+            last: Some(to_naive(last)),
+            gap_threshold_secs: self.gap_secs,
+            gaps: gaps
+                .iter()
+                .map(|&(a, b)| TimeGap {
+                    start: to_naive(a),
+                    end: to_naive(b),
+                    duration_secs: b - a,
+                })
+                .collect(),
+            hours,
+            coverage: ratio(first, end),
+            skipped: self.skipped,
+        }
+    }
+}
+
+impl Default for CoverageAnalyzer {
+    fn default() -> Self {
+        Self::new(DEFAULT_GAP_SECS)
+    }
+}
+
+fn to_naive(secs: i64) -> NaiveDateTime {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default().naive_utc()
+}
@@ -0,0 +1,267 @@
+//! 日志集对比 - 按 SQL 指纹比较基线与当前两组日志
+//!
+//! 两组日志分别按 [`fingerprint`](super::fingerprint::fingerprint) 聚合出每类
+//! 语句的调用次数与 p95 执行时间，再对比得到：
+//! - **新增语句**：只出现在当前日志中
+//! - **消失语句**：只出现在基线日志中
+//! - **变化语句**：p95 或调用次数的相对变化超过阈值
+//!
+//! 典型用法是版本升级前后各取一段日志对比，快速发现变慢或调用激增的语句。
+
+use super::fingerprint::fingerprint;
+use crate::sqllog::{ExecTimeMs, Sqllog};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// 单类语句的统计
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StatementStats {
+    /// 调用次数
+    pub calls: u64,
+    /// 执行时间合计
+    pub total_time: ExecTimeMs,
+    /// p95 执行时间（无执行时间时为 `None`）
+    pub p95: Option<ExecTimeMs>,
+    /// 一条原始 SQL 示例
+    pub sample: String,
+}
+
+/// 按指纹聚合记录
+#[derive(Debug, Default, Clone)]
+pub struct FingerprintAggregator {
+    groups: HashMap<String, Group>,
+}
+
+#[derive(Debug, Clone)]
+struct Group {
+    calls: u64,
+    times: Vec<i64>,
+    sample: String,
+}
+
+impl FingerprintAggregator {
+    /// 创建聚合器
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 聚合一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            let group = self
+                .groups
+                .entry(fingerprint(&record.description))
+                .or_insert_with(|| Group {
+                    calls: 0,
+                    times: Vec::new(),
+                    sample: record.description.clone(),
+                });
+            group.calls += 1;
+            if let Some(t) = record.execute_time {
+                group.times.push(t.get());
+            }
+        }
+    }
+
+    /// 计算各指纹的统计，按指纹排序
+    #[must_use]
+    pub fn finish(self) -> BTreeMap<String, StatementStats> {
+        self.groups
+            .into_iter()
+            .map(|(fp, mut group)| {
+                group.times.sort_unstable();
+                let n = group.times.len();
+                // 最近秩百分位
+                let p95 = (n > 0).then(|| {
+                    ExecTimeMs::new(
+                        group.times[((95 * n + 99) / 100).max(1) - 1],
+                    )
+                });
+                let stats = StatementStats {
+                    calls: group.calls,
+                    total_time: group
+                        .times
+                        .iter()
+                        .copied()
+                        .map(ExecTimeMs::new)
+                        .sum(),
+                    p95,
+                    sample: group.sample,
+                };
+                (fp, stats)
+            })
+            .collect()
+    }
+}
+
+/// 变化判定阈值
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiffThresholds {
+    /// p95 相对变化阈值（0.2 表示 ±20%）
+    pub p95_ratio: f64,
+    /// p95 的最小绝对变化，低于该值的抖动不计为变化
+    pub p95_min_delta: ExecTimeMs,
+    /// 调用次数相对变化阈值（0.5 表示 ±50%）
+    pub calls_ratio: f64,
+}
+
+impl Default for DiffThresholds {
+    fn default() -> Self {
+        Self {
+            p95_ratio: 0.2,
+            p95_min_delta: ExecTimeMs::new(1),
+            calls_ratio: 0.5,
+        }
+    }
+}
+
+/// 超过阈值的语句变化
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatementChange {
+    /// SQL 指纹
+    pub fingerprint: String,
+    /// 基线统计
+    pub baseline: StatementStats,
+    /// 当前统计
+    pub current: StatementStats,
+    /// p95 相对变化（基线 p95 为 0 或缺失时为 `None`）
+    pub p95_change: Option<f64>,
+    /// 调用次数相对变化
+    pub calls_change: f64,
+    /// p95 是否超过阈值
+    pub p95_changed: bool,
+    /// 调用次数是否超过阈值
+    pub calls_changed: bool,
+}
+
+/// 对比结果
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct DiffReport {
+    /// 新增语句（按调用次数降序）
+    pub new: Vec<(String, StatementStats)>,
+    /// 消失语句（按调用次数降序）
+    pub disappeared: Vec<(String, StatementStats)>,
+    /// 变化语句（按 p95 变化幅度降序）
+    pub changed: Vec<StatementChange>,
+    /// 两侧都存在且未超过阈值的语句数
+    pub unchanged: usize,
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn relative(base: i64, current: i64) -> Option<f64> {
+    (base != 0).then(|| (current - base) as f64 / base as f64)
+}
+
+/// 对比基线与当前的指纹统计
+#[must_use]
+pub fn diff(
+    baseline: &BTreeMap<String, StatementStats>,
+    current: &BTreeMap<String, StatementStats>,
+    thresholds: &DiffThresholds,
+) -> DiffReport {
+    let mut report = DiffReport::default();
+
+    for (fp, cur) in current {
+        let Some(base) = baseline.get(fp) else {
+            report.new.push((fp.clone(), cur.clone()));
+            continue;
+        };
+
+        let base_p95 = base.p95.unwrap_or_default().get();
+        let cur_p95 = cur.p95.unwrap_or_default().get();
+        let p95_change = relative(base_p95, cur_p95);
+        let p95_changed = (cur_p95 - base_p95).abs()
+            >= thresholds.p95_min_delta.get()
+            // 基线 p95 为 0 时视为无穷大的变化
+            && p95_change.map_or(true, |r| r.abs() > thresholds.p95_ratio);
+
+        let calls_change = relative(
+            i64::try_from(base.calls).unwrap_or(i64::MAX),
+            i64::try_from(cur.calls).unwrap_or(i64::MAX),
+        )
+        .unwrap_or(0.0);
+        let calls_changed = calls_change.abs() > thresholds.calls_ratio;
+
+        if p95_changed || calls_changed {
+            report.changed.push(StatementChange {
+                fingerprint: fp.clone(),
+                baseline: base.clone(),
+                current: cur.clone(),
+                p95_change,
+                calls_change,
+                p95_changed,
+                calls_changed,
+            });
+        } else {
+            report.unchanged += 1;
+        }
+    }
+    for (fp, base) in baseline {
+        if !current.contains_key(fp) {
+            report.disappeared.push((fp.clone(), base.clone()));
+        }
+    }
+
+    report.new.sort_by_key(|(_, s)| std::cmp::Reverse(s.calls));
+    report.disappeared.sort_by_key(|(_, s)| std::cmp::Reverse(s.calls));
+    report.changed.sort_by(|a, b| {
+        let key =
+            |c: &StatementChange| c.p95_change.map_or(f64::INFINITY, f64::abs);
+        key(b).total_cmp(&key(a))
+    });
+    report
+}
+
+fn fmt_p95(p95: Option<ExecTimeMs>) -> String {
+    p95.map_or_else(|| "-".to_string(), |t| t.to_string())
+}
+
+fn fmt_ratio(ratio: Option<f64>) -> String {
+    ratio.map_or_else(|| "new".to_string(), |r| format!("{:+.1}%", r * 100.0))
+}
+
+impl fmt::Display for DiffReport {
+    /// 以文本报告输出，每类语句一行：调用次数、p95 与指纹
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "新增语句: {}", self.new.len())?;
+        for (fp, stats) in &self.new {
+            writeln!(
+                f,
+                "  + calls={} p95={}  {fp}",
+                stats.calls,
+                fmt_p95(stats.p95)
+            )?;
+        }
+        writeln!(f, "消失语句: {}", self.disappeared.len())?;
+        for (fp, stats) in &self.disappeared {
+            writeln!(
+                f,
+                "  - calls={} p95={}  {fp}",
+                stats.calls,
+                fmt_p95(stats.p95)
+            )?;
+        }
+        writeln!(
+            f,
+            "变化语句: {}（未变化 {}）",
+            self.changed.len(),
+            self.unchanged
+        )?;
+        for change in &self.changed {
+            writeln!(
+                f,
+                "  ~ calls={}->{} ({:+.1}%) p95={}->{} ({})  {}",
+                change.baseline.calls,
+                change.current.calls,
+                change.calls_change * 100.0,
+                fmt_p95(change.baseline.p95),
+                fmt_p95(change.current.p95),
+                fmt_ratio(change.p95_change),
+                change.fingerprint
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,304 @@
+//! 分析引擎 - 在一次解析中驱动多个分析器
+//!
+//! [`Analyzer`] 是可插拔分析器的统一接口：逐条接收记录、在每个文件结束时
+//! 收到通知，最后产出一份 [`Report`]。下游 crate 实现该 trait 后通过
+//! [`AnalysisEngine::register`] 注册，即可与内置分析器（关键字、执行计划、
+//! 时间桶、日志覆盖、会话排名）共用同一次解析，而无需重复读取日志文件。
+//!
+//! ```rust
+//! use sqllog_analysis::analysis::{AnalysisEngine, Analyzer, Report};
+//! use sqllog_analysis::sqllog::Sqllog;
+//!
+//! /// 统计 SYSDBA 执行的语句数
+//! #[derive(Default)]
+//! struct SysdbaCounter(u64);
+//!
+//! impl Analyzer for SysdbaCounter {
+//!     fn name(&self) -> &str {
+//!         "sysdba"
+//!     }
+//!     fn on_record(&mut self, record: &Sqllog) {
+//!         if record.user.as_deref() == Some("SYSDBA") {
+//!             self.0 += 1;
+//!         }
+//!     }
+//!     fn finish(self: Box<Self>) -> Report {
+//!         Report::new("sysdba", self.0)
+//!     }
+//! }
+//!
+//! let mut engine = AnalysisEngine::new();
+//! engine.register(Box::new(SysdbaCounter::default()));
+//! engine.observe(&[Sqllog { user: Some("SYSDBA".into()), ..Default::default() }]);
+//!
+//! let reports = engine.finish();
+//! assert_eq!(reports[0].data, 1);
+//! ```
+
+use super::apps::AppWorkloadAnalyzer;
+use super::classify::Classifier;
+use super::concurrency::ConcurrencyAnalyzer;
+use super::coverage::CoverageAnalyzer;
+use super::indexes::IndexAdvisor;
+use super::keywords::KeywordAnalyzer;
+use super::plans::PlanAnalyzer;
+use super::profile::ProfileAnalyzer;
+use super::sessions::SessionAnalyzer;
+use super::timeline::TimeBucketAggregator;
+use crate::sqllog::{ParseOptions, SResult, Sqllog};
+use serde::Serialize;
+use std::path::Path;
+
+/// 单个分析器的输出
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Report {
+    /// 分析器名称
+    pub analyzer: String,
+    /// 分析结果（任意可序列化结构转换得到的 JSON）
+    pub data: serde_json::Value,
+}
+
+impl Report {
+    /// 由可序列化的结果构造报告，序列化失败时结果为 `null`
+    pub fn new<T: Serialize>(analyzer: &str, data: T) -> Self {
+        Self {
+            analyzer: analyzer.to_string(),
+            data: serde_json::to_value(data).unwrap_or_default(),
+        }
+    }
+}
+
+/// 可插拔分析器接口
+pub trait Analyzer: Send {
+    /// 分析器名称，用于标识报告
+    fn name(&self) -> &str;
+
+    /// 处理一条记录
+    fn on_record(&mut self, record: &Sqllog);
+
+    /// 一个文件的记录全部处理完成
+    fn on_file_end(&mut self, _path: &Path) {}
+
+    /// 结束分析并产出报告
+    fn finish(self: Box<Self>) -> Report;
+}
+
+impl Analyzer for KeywordAnalyzer {
+    fn name(&self) -> &str {
+        "keywords"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for Classifier {
+    fn name(&self) -> &str {
+        "classify"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for PlanAnalyzer {
+    fn name(&self) -> &str {
+        "plans"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for TimeBucketAggregator {
+    fn name(&self) -> &str {
+        "timeline"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(
+            self.name(),
+            serde_json::json!({
+                "buckets": self.buckets(),
+                "skipped": self.skipped(),
+            }),
+        )
+    }
+}
+
+impl Analyzer for CoverageAnalyzer {
+    fn name(&self) -> &str {
+        "coverage"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for SessionAnalyzer {
+    fn name(&self) -> &str {
+        "sessions"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for ConcurrencyAnalyzer {
+    fn name(&self) -> &str {
+        "concurrency"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for AppWorkloadAnalyzer {
+    fn name(&self) -> &str {
+        "apps"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for IndexAdvisor {
+    fn name(&self) -> &str {
+        "indexes"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+impl Analyzer for ProfileAnalyzer {
+    fn name(&self) -> &str {
+        "profile"
+    }
+
+    fn on_record(&mut self, record: &Sqllog) {
+        self.observe(std::slice::from_ref(record));
+    }
+
+    fn finish(self: Box<Self>) -> Report {
+        Report::new(self.name(), self.report())
+    }
+}
+
+/// 分析引擎：按注册顺序把每条记录交给所有分析器
+#[derive(Default)]
+pub struct AnalysisEngine {
+    analyzers: Vec<Box<dyn Analyzer>>,
+}
+
+impl AnalysisEngine {
+    /// 创建空引擎
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 注册一个分析器
+    pub fn register(&mut self, analyzer: Box<dyn Analyzer>) -> &mut Self {
+        self.analyzers.push(analyzer);
+        self
+    }
+
+    /// 已注册的分析器数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.analyzers.len()
+    }
+
+    /// 是否未注册任何分析器
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.analyzers.is_empty()
+    }
+
+    /// 处理一批记录，可直接挂接在解析回调中
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            for analyzer in &mut self.analyzers {
+                analyzer.on_record(record);
+            }
+        }
+    }
+
+    /// 通知所有分析器一个文件已结束
+    pub fn file_end(&mut self, path: &Path) {
+        for analyzer in &mut self.analyzers {
+            analyzer.on_file_end(path);
+        }
+    }
+
+    /// 解析一个文件并交给所有分析器，返回解析错误数
+    ///
+    /// # Errors
+    /// 当文件无法打开或读取时返回错误
+    pub fn run_file(
+        &mut self,
+        path: &Path,
+        options: &ParseOptions,
+    ) -> SResult<usize> {
+        let mut errors = 0usize;
+        Sqllog::parse_with_options(
+            path,
+            options,
+            |records| self.observe(records),
+            |errs| errors += errs.len(),
+        )?;
+        self.file_end(path);
+        Ok(errors)
+    }
+
+    /// 结束分析，按注册顺序返回各分析器的报告
+    #[must_use]
+    pub fn finish(self) -> Vec<Report> {
+        self.analyzers.into_iter().map(Analyzer::finish).collect()
+    }
+}
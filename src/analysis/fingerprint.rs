@@ -0,0 +1,123 @@
+//! SQL 指纹 - 将字面量不同的同类语句归并为同一个键
+//!
+//! 指纹由 description 中的 SQL 文本归一化得到：
+//! - 去掉达梦追加的 `EXECTIME: ... ROWCOUNT: ... EXEC_ID: ...` 尾部
+//! - 去掉 `--` 与 `/* */` 注释
+//! - 字符串与数值字面量替换为 `?`，`IN (?, ?, ?)` 与多行 `VALUES` 折叠为一项
+//! - 引号外的文本转为小写，连续空白压缩为一个空格
+//!
+//! 双引号包围的标识符保持原样。
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// 计算 description 的 SQL 指纹
+#[must_use]
+pub fn fingerprint(description: &str) -> String {
+    lazy_static! {
+        static ref STATS_TAIL: Regex =
+            Regex::new(r"(?s)\s*EXECTIME:\s*[\d.]+\(ms\).*$").unwrap();
+        static ref VALUE_LIST: Regex =
+            Regex::new(r"\(\s*\?(?:\s*,\s*\?)+\s*\)").unwrap();
+        static ref ROW_LIST: Regex =
+            Regex::new(r"\(\?\)(?:\s*,\s*\(\?\))+").unwrap();
+    }
+
+    let sql = STATS_TAIL.replace(description, "");
+    let normalized = normalize(&sql);
+    let collapsed = VALUE_LIST.replace_all(&normalized, "(?)");
+    ROW_LIST.replace_all(&collapsed, "(?)").into_owned()
+}
+
+/// 归一化输出缓冲
+#[derive(Default)]
+struct Output {
+    text: String,
+    /// 上一个输出是否为标识符字符，用于区分 `t1` 中的数字与独立数值
+    prev_ident: bool,
+    /// 是否有待输出的空白
+    pending_space: bool,
+}
+
+impl Output {
+    fn push(&mut self, s: &str, ident: bool) {
+        if self.pending_space && !self.text.is_empty() {
+            self.text.push(' ');
+        }
+        self.pending_space = false;
+        self.text.push_str(s);
+        self.prev_ident = ident;
+    }
+}
+
+/// 逐字符归一化：替换字面量、去注释、小写并压缩空白
+fn normalize(sql: &str) -> String {
+    let mut out =
+        Output { text: String::with_capacity(sql.len()), ..Output::default() };
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                out.pending_space = true;
+                out.prev_ident = false;
+            }
+            '\'' => {
+                // 字符串字面量，`''` 为转义的单引号
+                while let Some(n) = chars.next() {
+                    if n == '\'' {
+                        if chars.peek() == Some(&'\'') {
+                            chars.next();
+                        } else {
+                            break;
+                        }
+                    }
+                }
+                out.push("?", false);
+            }
+            '"' => {
+                let mut ident = String::from('"');
+                for n in chars.by_ref() {
+                    ident.push(n);
+                    if n == '"' {
+                        break;
+                    }
+                }
+                out.push(&ident, true);
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for n in chars.by_ref() {
+                    if n == '\n' {
+                        break;
+                    }
+                }
+                out.pending_space = true;
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = '\0';
+                for n in chars.by_ref() {
+                    if prev == '*' && n == '/' {
+                        break;
+                    }
+                    prev = n;
+                }
+                out.pending_space = true;
+            }
+            c if c.is_ascii_digit() && !out.prev_ident => {
+                while chars
+                    .peek()
+                    .is_some_and(|n| n.is_ascii_alphanumeric() || *n == '.')
+                {
+                    chars.next();
+                }
+                out.push("?", false);
+            }
+            c => {
+                let lower: String = c.to_lowercase().collect();
+                out.push(&lower, c.is_alphanumeric() || c == '_');
+            }
+        }
+    }
+    out.text
+}
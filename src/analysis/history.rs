@@ -0,0 +1,222 @@
+//! 统计快照 - 跨运行保存分析结果并做趋势对比
+//!
+//! 原始记录通常不会长期保留，但按天对比需要上一次运行的统计。
+//! [`SnapshotAggregator`] 在一次运行中同时聚合：
+//! - 各 SQL 指纹的调用次数与 p95（与 [`diff`](super::diff) 的口径一致）
+//! - 各用户执行时间的 p50/p95/p99
+//!
+//! 得到的 [`StatsSnapshot`] 可由 [`crate::history::HistoryStore`] 持久化，
+//! 下次运行时载入作为基线，用 [`compare`] 生成 [`TrendReport`]。
+
+use super::diff::{
+    DiffReport, DiffThresholds, FingerprintAggregator, StatementStats, diff,
+};
+use crate::sqllog::{ExecTimeMs, Sqllog};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// 单个用户的执行时间分布
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UserPercentiles {
+    /// 记录数
+    pub calls: u64,
+    pub p50: Option<ExecTimeMs>,
+    pub p95: Option<ExecTimeMs>,
+    pub p99: Option<ExecTimeMs>,
+}
+
+/// 按用户聚合执行时间百分位；没有用户名的记录不计入
+#[derive(Debug, Default, Clone)]
+pub struct UserStatsAggregator {
+    users: HashMap<String, (u64, Vec<i64>)>,
+}
+
+impl UserStatsAggregator {
+    /// 创建聚合器
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 聚合一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            let Some(user) = &record.user else { continue };
+            let (calls, times) = self.users.entry(user.clone()).or_default();
+            *calls += 1;
+            if let Some(t) = record.execute_time {
+                times.push(t.get());
+            }
+        }
+    }
+
+    /// 计算各用户的百分位，按用户名排序
+    #[must_use]
+    pub fn finish(self) -> BTreeMap<String, UserPercentiles> {
+        self.users
+            .into_iter()
+            .map(|(user, (calls, mut times))| {
+                times.sort_unstable();
+                // 最近秩百分位，与 diff 的 p95 口径一致
+                let pick = |p: usize| {
+                    let n = times.len();
+                    (n > 0).then(|| {
+                        ExecTimeMs::new(times[((p * n + 99) / 100).max(1) - 1])
+                    })
+                };
+                let stats = UserPercentiles {
+                    calls,
+                    p50: pick(50),
+                    p95: pick(95),
+                    p99: pick(99),
+                };
+                (user, stats)
+            })
+            .collect()
+    }
+}
+
+/// 一次运行的统计快照
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct StatsSnapshot {
+    /// 快照标签（如日期），仅用于展示
+    pub label: String,
+    pub fingerprints: BTreeMap<String, StatementStats>,
+    pub users: BTreeMap<String, UserPercentiles>,
+}
+
+/// 同时聚合指纹与用户统计
+#[derive(Debug, Default, Clone)]
+pub struct SnapshotAggregator {
+    fingerprints: FingerprintAggregator,
+    users: UserStatsAggregator,
+}
+
+impl SnapshotAggregator {
+    /// 创建聚合器
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 聚合一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        self.fingerprints.observe(records);
+        self.users.observe(records);
+    }
+
+    /// 生成快照
+    #[must_use]
+    pub fn finish(self, label: &str) -> StatsSnapshot {
+        StatsSnapshot {
+            label: label.to_string(),
+            fingerprints: self.fingerprints.finish(),
+            users: self.users.finish(),
+        }
+    }
+}
+
+/// 单个用户的 p95 变化
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct UserTrend {
+    pub user: String,
+    /// 基线统计（新出现的用户为 `None`）
+    pub baseline: Option<UserPercentiles>,
+    /// 当前统计（本次未出现的用户为 `None`）
+    pub current: Option<UserPercentiles>,
+    /// p95 相对变化（任一侧缺失或基线为 0 时为 `None`）
+    pub p95_change: Option<f64>,
+}
+
+/// 当前运行与基线快照的对比
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrendReport {
+    pub baseline_label: String,
+    pub current_label: String,
+    /// 按指纹的语句对比
+    pub statements: DiffReport,
+    /// 各用户的 p95 变化，按变化幅度降序
+    pub users: Vec<UserTrend>,
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn p95_change(
+    baseline: Option<&UserPercentiles>,
+    current: Option<&UserPercentiles>,
+) -> Option<f64> {
+    let base = baseline?.p95?.get();
+    let cur = current?.p95?.get();
+    (base != 0).then(|| (cur - base) as f64 / base as f64)
+}
+
+/// 对比当前快照与基线快照
+#[must_use]
+pub fn compare(
+    baseline: &StatsSnapshot,
+    current: &StatsSnapshot,
+    thresholds: &DiffThresholds,
+) -> TrendReport {
+    let mut names: Vec<&String> =
+        baseline.users.keys().chain(current.users.keys()).collect();
+    names.sort();
+    names.dedup();
+    let mut users: Vec<UserTrend> = names
+        .into_iter()
+        .map(|user| {
+            let base = baseline.users.get(user);
+            let cur = current.users.get(user);
+            UserTrend {
+                user: user.clone(),
+                baseline: base.cloned(),
+                current: cur.cloned(),
+                p95_change: p95_change(base, cur),
+            }
+        })
+        .collect();
+    users.sort_by(|a, b| {
+        let key = |t: &UserTrend| t.p95_change.map_or(-1.0, f64::abs);
+        key(b).total_cmp(&key(a))
+    });
+
+    TrendReport {
+        baseline_label: baseline.label.clone(),
+        current_label: current.label.clone(),
+        statements: diff(
+            &baseline.fingerprints,
+            &current.fingerprints,
+            thresholds,
+        ),
+        users,
+    }
+}
+
+impl fmt::Display for TrendReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let p95 = |s: Option<&UserPercentiles>| {
+            s.and_then(|s| s.p95)
+                .map_or_else(|| "-".to_string(), |t| t.to_string())
+        };
+        writeln!(
+            f,
+            "基线: {}  当前: {}",
+            self.baseline_label, self.current_label
+        )?;
+        write!(f, "{}", self.statements)?;
+        writeln!(f, "用户 p95: {}", self.users.len())?;
+        for trend in &self.users {
+            let change = trend.p95_change.map_or_else(
+                || "-".to_string(),
+                |r| format!("{:+.1}%", r * 100.0),
+            );
+            writeln!(
+                f,
+                "  {} p95={}->{} ({change})",
+                trend.user,
+                p95(trend.baseline.as_ref()),
+                p95(trend.current.as_ref()),
+            )?;
+        }
+        Ok(())
+    }
+}
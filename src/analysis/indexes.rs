@@ -0,0 +1,430 @@
+//! 索引建议 - 按过滤条件中的常用列为源表推荐索引
+//!
+//! [`IndexAdvisor`] 按 SQL 指纹（口径与 [`diff`](super::diff) 一致）聚合
+//! 调用次数与执行时间，再从指纹中提取 `FROM`/`JOIN`/`UPDATE` 引用的表与
+//! `WHERE`/`ON` 中和字面量比较的列：
+//!
+//! - `col = ?`、`col IN (?)` 为等值条件，`col < ?`、`col BETWEEN ? AND ?`、
+//!   `col LIKE ?` 等为范围条件；`<>`、`IS NULL` 与列之间的比较不参与
+//! - 带限定名的列按别名或表名归属到表；不带限定名的列只在语句只引用一张表
+//!   时归属，否则忽略
+//!
+//! 每条语句对每张表给出一个候选索引：等值列（按列名排序）在前，再加第一个
+//! 范围列。候选索引是另一个候选索引的前缀时并入后者。建议按涉及语句的
+//! 执行时间合计降序排列（相同时按调用次数），越靠前收益越大。
+
+use super::fingerprint::fingerprint;
+use crate::sqllog::{ExecTimeMs, Sqllog};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// 默认列出的建议数
+pub const DEFAULT_TOP_SUGGESTIONS: usize = 10;
+
+/// 不能作为表别名或列名的关键字
+const KEYWORDS: [&str; 30] = [
+    "select", "from", "where", "and", "or", "not", "on", "join", "inner",
+    "left", "right", "full", "outer", "cross", "natural", "using", "group",
+    "order", "by", "having", "limit", "union", "set", "values", "as", "in",
+    "is", "null", "exists", "between",
+];
+
+/// 候选索引（表, 列）
+type Candidate = (String, Vec<String>);
+
+/// 受益语句：指纹 -> (调用次数, 执行时间合计)
+type Beneficiaries<'a> = BTreeMap<&'a str, (u64, ExecTimeMs)>;
+
+/// 一条索引建议
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexSuggestion {
+    /// 表名（含模式名时为 `模式.表`）
+    pub table: String,
+    /// 索引列，等值列在前
+    pub columns: Vec<String>,
+    /// 建索引语句
+    pub ddl: String,
+    /// 可受益的语句指纹数
+    pub statements: usize,
+    /// 可受益语句的调用次数合计
+    pub calls: u64,
+    /// 可受益语句的执行时间合计
+    pub total_time: ExecTimeMs,
+    /// 执行时间合计最高的受益语句指纹
+    pub example: String,
+}
+
+/// 索引建议报告
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct IndexAdvice {
+    /// 记录总数
+    pub records: u64,
+    /// 语句指纹数
+    pub statements: usize,
+    /// 建议，按收益降序
+    pub suggestions: Vec<IndexSuggestion>,
+}
+
+impl fmt::Display for IndexAdvice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "记录数: {}，语句: {}，建议索引: {}",
+            self.records,
+            self.statements,
+            self.suggestions.len()
+        )?;
+        for (i, s) in self.suggestions.iter().enumerate() {
+            writeln!(f, "{:>3}. {}", i + 1, s.ddl)?;
+            writeln!(
+                f,
+                "     {} 类语句，调用 {}，合计 {}，如: {}",
+                s.statements, s.calls, s.total_time, s.example
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 按过滤条件推荐索引
+#[derive(Debug, Clone)]
+pub struct IndexAdvisor {
+    top: usize,
+    records: u64,
+    /// 指纹 -> (调用次数, 执行时间合计)
+    statements: HashMap<String, (u64, ExecTimeMs)>,
+}
+
+impl Default for IndexAdvisor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IndexAdvisor {
+    /// 创建分析器，列出 [`DEFAULT_TOP_SUGGESTIONS`] 条建议
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            top: DEFAULT_TOP_SUGGESTIONS,
+            records: 0,
+            statements: HashMap::new(),
+        }
+    }
+
+    /// 设置列出的建议数
+    #[must_use]
+    pub const fn with_top(mut self, top: usize) -> Self {
+        self.top = top;
+        self
+    }
+
+    /// 聚合一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            self.records += 1;
+            let statement = self
+                .statements
+                .entry(fingerprint(&record.description))
+                .or_default();
+            statement.0 += 1;
+            statement.1 += record.execute_time.unwrap_or_default();
+        }
+    }
+
+    /// 生成报告
+    #[must_use]
+    pub fn report(&self) -> IndexAdvice {
+        let mut candidates: BTreeMap<Candidate, Beneficiaries> =
+            BTreeMap::new();
+        for (fp, &stats) in &self.statements {
+            for (table, columns) in index_candidates(fp) {
+                candidates
+                    .entry((table, columns))
+                    .or_default()
+                    .insert(fp, stats);
+            }
+        }
+
+        // 较长的候选先保留，前缀候选并入覆盖它的候选
+        let mut ordered: Vec<_> = candidates.into_iter().collect();
+        ordered
+            .sort_by_key(|((_, columns), _)| std::cmp::Reverse(columns.len()));
+        let mut kept: Vec<(Candidate, Beneficiaries)> = Vec::new();
+        for ((table, columns), fps) in ordered {
+            let covering = kept.iter_mut().find(|((t, c), _)| {
+                *t == table
+                    && c.len() > columns.len()
+                    && c.starts_with(&columns)
+            });
+            match covering {
+                Some((_, covered)) => covered.extend(fps),
+                None => kept.push(((table, columns), fps)),
+            }
+        }
+
+        let mut suggestions: Vec<IndexSuggestion> = kept
+            .into_iter()
+            .map(|((table, columns), fps)| {
+                let calls = fps.values().map(|&(calls, _)| calls).sum();
+                let total_time = fps.values().map(|&(_, time)| time).sum();
+                let example = fps
+                    .iter()
+                    .max_by(|a, b| (a.1.1, b.0).cmp(&(b.1.1, a.0)))
+                    .map(|(fp, _)| (*fp).to_string())
+                    .unwrap_or_default();
+                IndexSuggestion {
+                    ddl: index_ddl(&table, &columns),
+                    statements: fps.len(),
+                    table,
+                    columns,
+                    calls,
+                    total_time,
+                    example,
+                }
+            })
+            .collect();
+        suggestions.sort_by(|a, b| {
+            (b.total_time, b.calls, &a.table, &a.columns).cmp(&(
+                a.total_time,
+                a.calls,
+                &b.table,
+                &b.columns,
+            ))
+        });
+        suggestions.truncate(self.top);
+        IndexAdvice {
+            records: self.records,
+            statements: self.statements.len(),
+            suggestions,
+        }
+    }
+}
+
+/// `CREATE INDEX idx_<表>_<列>... ON <表> (<列>, ...);`
+fn index_ddl(table: &str, columns: &[String]) -> String {
+    let base = table.rsplit('.').next().unwrap_or(table);
+    let mut name = format!("idx_{}", base.trim_matches('"'));
+    for column in columns {
+        name.push('_');
+        name.push_str(column.trim_matches('"'));
+    }
+    format!("CREATE INDEX {name} ON {table} ({});", columns.join(", "))
+}
+
+/// 条件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Predicate {
+    Equality,
+    Range,
+}
+
+/// 从指纹中提取各表的候选索引列
+fn index_candidates(fp: &str) -> Vec<(String, Vec<String>)> {
+    let tokens = tokenize(fp);
+    // (表名, 别名)
+    let mut tables: Vec<(String, Option<String>)> = Vec::new();
+    // (限定名, 列名, 条件类型)
+    let mut predicates: Vec<(Option<String>, String, Predicate)> = Vec::new();
+    let mut in_predicate = false;
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i].as_str() {
+            "from" | "join" | "update" | "into" => {
+                in_predicate = false;
+                let list = tokens[i] == "from";
+                i = table_refs(&tokens, i + 1, list, &mut tables);
+                continue;
+            }
+            "where" | "on" => in_predicate = true,
+            "select" | "set" | "values" | "group" | "order" | "having"
+            | "limit" | "union" => in_predicate = false,
+            _ if in_predicate => {
+                if let Some((qualifier, column, next)) = column_ref(&tokens, i)
+                {
+                    if let Some(kind) = predicate_kind(&tokens, next) {
+                        predicates.push((qualifier, column, kind));
+                    }
+                    i = next;
+                    continue;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+
+    let distinct_tables: Vec<&String> = {
+        let mut names: Vec<&String> = tables.iter().map(|(t, _)| t).collect();
+        names.sort();
+        names.dedup();
+        names
+    };
+    let resolve = |qualifier: &Option<String>| -> Option<String> {
+        match qualifier {
+            Some(q) => tables
+                .iter()
+                .find(|(table, alias)| {
+                    alias.as_deref() == Some(q.as_str())
+                        || table == q
+                        || table.rsplit('.').next() == Some(q.as_str())
+                })
+                .map(|(table, _)| table.clone()),
+            None => match distinct_tables.as_slice() {
+                [table] => Some((*table).clone()),
+                _ => None,
+            },
+        }
+    };
+
+    let mut by_table: BTreeMap<String, (Vec<String>, Vec<String>)> =
+        BTreeMap::new();
+    for (qualifier, column, kind) in predicates {
+        let Some(table) = resolve(&qualifier) else {
+            continue;
+        };
+        let (eq, range) = by_table.entry(table).or_default();
+        let list = match kind {
+            Predicate::Equality => eq,
+            Predicate::Range => range,
+        };
+        if !list.contains(&column) {
+            list.push(column);
+        }
+    }
+    by_table
+        .into_iter()
+        .map(|(table, (mut eq, range))| {
+            eq.sort();
+            if let Some(r) = range.into_iter().find(|r| !eq.contains(r)) {
+                eq.push(r);
+            }
+            (table, eq)
+        })
+        .collect()
+}
+
+/// 解析 `FROM`/`JOIN` 等之后的表引用，返回下一个未处理的位置
+fn table_refs(
+    tokens: &[String],
+    mut i: usize,
+    list: bool,
+    tables: &mut Vec<(String, Option<String>)>,
+) -> usize {
+    loop {
+        // 子查询由外层循环继续处理
+        let Some((name, next)) = qualified_name(tokens, i) else {
+            return i;
+        };
+        i = next;
+        if tokens.get(i).map(String::as_str) == Some("as") {
+            i += 1;
+        }
+        let alias = tokens.get(i).filter(|t| is_identifier(t)).cloned();
+        if alias.is_some() {
+            i += 1;
+        }
+        tables.push((name, alias));
+        if !(list && tokens.get(i).map(String::as_str) == Some(",")) {
+            return i;
+        }
+        i += 1;
+    }
+}
+
+/// `[限定名.]列名`，返回限定名、列名与下一个位置
+fn column_ref(
+    tokens: &[String],
+    i: usize,
+) -> Option<(Option<String>, String, usize)> {
+    let (name, next) = qualified_name(tokens, i)?;
+    Some(match name.rsplit_once('.') {
+        Some((qualifier, column)) => {
+            (Some(qualifier.to_string()), column.to_string(), next)
+        }
+        None => (None, name, next),
+    })
+}
+
+/// 以点连接的标识符，返回完整名称与下一个位置
+fn qualified_name(tokens: &[String], mut i: usize) -> Option<(String, usize)> {
+    let first = tokens.get(i).filter(|t| is_identifier(t))?;
+    let mut name = first.clone();
+    i += 1;
+    while tokens.get(i).map(String::as_str) == Some(".") {
+        let Some(part) = tokens.get(i + 1).filter(|t| is_identifier(t)) else {
+            break;
+        };
+        name.push('.');
+        name.push_str(part);
+        i += 2;
+    }
+    Some((name, i))
+}
+
+/// 列之后的比较是否为与字面量比较的索引友好条件
+fn predicate_kind(tokens: &[String], i: usize) -> Option<Predicate> {
+    let at = |k: usize| tokens.get(i + k).map(String::as_str);
+    match (at(0)?, at(1), at(2)) {
+        ("=", Some("?"), _) => Some(Predicate::Equality),
+        ("in", Some("("), Some("?")) => Some(Predicate::Equality),
+        ("<" | "<=" | ">" | ">=" | "like" | "between", Some("?"), _) => {
+            Some(Predicate::Range)
+        }
+        _ => None,
+    }
+}
+
+fn is_identifier(token: &str) -> bool {
+    let Some(first) = token.chars().next() else {
+        return false;
+    };
+    (first == '"' || first == '_' || first.is_alphabetic())
+        && !KEYWORDS.contains(&token)
+}
+
+/// 把指纹切分为标识符、`?`、标点与比较运算符
+fn tokenize(fp: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = fp.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '"' => {
+                let mut ident = String::from('"');
+                for n in chars.by_ref() {
+                    ident.push(n);
+                    if n == '"' {
+                        break;
+                    }
+                }
+                tokens.push(ident);
+            }
+            '<' | '>' | '!' => {
+                let mut op = c.to_string();
+                if let Some(&n) = chars.peek() {
+                    if n == '=' || (c == '<' && n == '>') {
+                        op.push(n);
+                        chars.next();
+                    }
+                }
+                tokens.push(op);
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '$' || c == '#' => {
+                let mut word = c.to_string();
+                while let Some(&n) = chars.peek() {
+                    if n.is_alphanumeric() || n == '_' || n == '$' || n == '#' {
+                        word.push(n);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(word);
+            }
+            other => tokens.push(other.to_string()),
+        }
+    }
+    tokens
+}
@@ -0,0 +1,172 @@
+//! 关键字分析器 - 锁等待与错误码提取
+//!
+//! 达梦 sqllog 的 description 中偶尔会携带错误码（如 `[-6403]`）或锁等待、
+//! 死锁等提示信息。本分析器按规则列表逐条匹配 description，为每条记录
+//! 打上命中的类别标签，并按类别、按（类别, 用户）聚合命中次数。
+//!
+//! ## 规则来源
+//!
+//! - [`KeywordAnalyzer::with_default_rules`]：内置的常见达梦错误类别
+//! - [`KeywordAnalyzer::from_configs`]：从配置（类别名 + 正则）构造，
+//!   便于在 TOML 中追加或替换规则
+
+use crate::sqllog::Sqllog;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// 内置规则：（类别, 正则）
+const DEFAULT_RULES: [(&str, &str); 6] = [
+    ("lock_wait", r"(?i)锁等待|锁超时|lock\s*wait|lock\s*timeout|-6403\b"),
+    ("deadlock", r"(?i)死锁|deadlock"),
+    ("timeout", r"(?i)语句执行超时|执行超时|statement\s+timeout|-6407\b"),
+    ("constraint", r"(?i)违反.{0,16}约束|unique\s+constraint|-6602\b"),
+    ("syntax_error", r"(?i)语法分析出错|syntax\s+error|-2007\b"),
+    ("error_code", r"\[-\d{3,5}\]"),
+];
+
+/// 单条关键字规则
+#[derive(Debug, Clone)]
+pub struct KeywordRule {
+    /// 类别名称，用于标签和聚合
+    pub category: String,
+    /// 匹配 description 的正则
+    pub pattern: Regex,
+}
+
+impl KeywordRule {
+    /// 使用类别名和正则文本创建规则。
+    ///
+    /// # Errors
+    /// 当正则表达式无法编译时返回 `regex::Error`。
+    pub fn new(category: &str, pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self {
+            category: category.to_string(),
+            pattern: Regex::new(pattern)?,
+        })
+    }
+}
+
+/// 可从配置文件反序列化的规则定义
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct KeywordRuleConfig {
+    /// 类别名称
+    pub category: String,
+    /// 正则表达式文本
+    pub pattern: String,
+}
+
+/// 关键字分析结果
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct KeywordReport {
+    /// 参与分析的记录总数
+    pub records_scanned: u64,
+    /// 至少命中一个类别的记录数
+    pub records_matched: u64,
+    /// 各类别命中次数
+    pub by_category: BTreeMap<String, u64>,
+    /// 各类别下按用户统计的命中次数（用户为空时记为 `NULL`）
+    pub by_category_user: BTreeMap<String, BTreeMap<String, u64>>,
+}
+
+/// 关键字分析器
+#[derive(Debug, Clone)]
+pub struct KeywordAnalyzer {
+    rules: Vec<KeywordRule>,
+    report: KeywordReport,
+}
+
+impl KeywordAnalyzer {
+    /// 使用给定规则列表创建分析器。
+    #[must_use]
+    pub fn new(rules: Vec<KeywordRule>) -> Self {
+        Self { rules, report: KeywordReport::default() }
+    }
+
+    /// 使用内置的常见达梦错误类别规则创建分析器。
+    ///
+    /// # Panics
+    /// 内置规则均为常量正则，编译失败属于程序缺陷。
+    #[must_use]
+    pub fn with_default_rules() -> Self {
+        let rules = DEFAULT_RULES
+            .iter()
+            .map(|(category, pattern)| {
+                KeywordRule::new(category, pattern).expect("内置规则正则无效")
+            })
+            .collect();
+        Self::new(rules)
+    }
+
+    /// 从配置定义构造分析器。
+    ///
+    /// 当 `include_defaults` 为 true 时，配置中的规则会追加在内置规则之后。
+    ///
+    /// # Errors
+    /// 任一规则的正则无法编译时返回 `regex::Error`。
+    pub fn from_configs(
+        configs: &[KeywordRuleConfig],
+        include_defaults: bool,
+    ) -> Result<Self, regex::Error> {
+        let mut analyzer = if include_defaults {
+            Self::with_default_rules()
+        } else {
+            Self::new(Vec::new())
+        };
+        for cfg in configs {
+            analyzer.rules.push(KeywordRule::new(&cfg.category, &cfg.pattern)?);
+        }
+        Ok(analyzer)
+    }
+
+    /// 返回当前使用的规则列表。
+    #[must_use]
+    pub fn rules(&self) -> &[KeywordRule] {
+        &self.rules
+    }
+
+    /// 返回单条记录命中的类别列表（按规则顺序，不去重同名类别）。
+    #[must_use]
+    pub fn tag(&self, record: &Sqllog) -> Vec<&str> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(&record.description))
+            .map(|rule| rule.category.as_str())
+            .collect()
+    }
+
+    /// 分析一批记录并累加统计。
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            self.report.records_scanned += 1;
+
+            let mut categories = self.tag(record);
+            if categories.is_empty() {
+                continue;
+            }
+            categories.sort_unstable();
+            categories.dedup();
+            let categories: Vec<String> =
+                categories.into_iter().map(str::to_string).collect();
+
+            self.report.records_matched += 1;
+            let user = record.user.as_deref().unwrap_or("NULL");
+            for category in categories {
+                *self
+                    .report
+                    .by_category_user
+                    .entry(category.clone())
+                    .or_default()
+                    .entry(user.to_string())
+                    .or_insert(0) += 1;
+                *self.report.by_category.entry(category).or_insert(0) += 1;
+            }
+        }
+    }
+
+    /// 返回当前累计的分析结果。
+    #[must_use]
+    pub const fn report(&self) -> &KeywordReport {
+        &self.report
+    }
+}
@@ -0,0 +1,170 @@
+//! 外部时间标记 - 关联 AWR 快照、发布记录等系统事件
+//!
+//! 从外部 CSV/JSON 文件载入带时间范围的元数据（如 AWR 快照 ID、
+//! 发布/变更标记），并为每个时间桶标注与之重叠的标记，便于在报告中
+//! 把 SQL 负载变化与系统事件对应起来。
+//!
+//! ## 文件格式
+//!
+//! JSON：对象数组
+//! ```json
+//! [{"label": "snap_1024", "start": "2025-09-21 12:00:00", "end": "2025-09-21 13:00:00"}]
+//! ```
+//!
+//! CSV：首行为表头，需包含 `label,start,end` 三列（顺序任意）
+//! ```text
+//! label,start,end
+//! deploy_v2,2025-09-21 12:30:00,2025-09-21 12:45:00
+//! ```
+//!
+//! 时间支持 `YYYY-MM-DD HH:MM:SS` 与带毫秒的 `YYYY-MM-DD HH:MM:SS.fff` 两种写法。
+
+use super::timeline::TimeBucket;
+use anyhow::{Context, Result, bail};
+use chrono::NaiveDateTime;
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+/// 单个时间标记
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeMarker {
+    /// 标记名称（如快照 ID、发布版本号）
+    pub label: String,
+    /// 起始时间（包含）
+    pub start: NaiveDateTime,
+    /// 结束时间（不包含）
+    pub end: NaiveDateTime,
+}
+
+/// 文件中的原始标记定义
+#[derive(Debug, Deserialize)]
+struct RawMarker {
+    label: String,
+    start: String,
+    end: String,
+}
+
+/// 时间标记集合
+#[derive(Debug, Clone, Default)]
+pub struct MarkerSet {
+    markers: Vec<TimeMarker>,
+}
+
+impl MarkerSet {
+    /// 使用给定标记创建集合。
+    #[must_use]
+    pub fn new(markers: Vec<TimeMarker>) -> Self {
+        Self { markers }
+    }
+
+    /// 从文件载入标记，按扩展名（`.json` / `.csv`）选择解析方式。
+    ///
+    /// # Errors
+    /// 文件读取失败、扩展名不受支持或内容格式错误时返回错误。
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("无法读取标记文件: {}", path.display()))?;
+        let ext = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(str::to_ascii_lowercase);
+        match ext.as_deref() {
+            Some("json") => Self::from_json(&contents),
+            Some("csv") => Self::from_csv(&contents),
+            _ => bail!("不支持的标记文件格式: {}", path.display()),
+        }
+    }
+
+    /// 从 JSON 文本解析标记。
+    ///
+    /// # Errors
+    /// JSON 格式错误或时间字段无法解析时返回错误。
+    pub fn from_json(contents: &str) -> Result<Self> {
+        let raw: Vec<RawMarker> =
+            serde_json::from_str(contents).context("标记 JSON 解析失败")?;
+        raw.into_iter()
+            .map(|r| Self::convert(&r.label, &r.start, &r.end))
+            .collect::<Result<Vec<_>>>()
+            .map(Self::new)
+    }
+
+    /// 从 CSV 文本解析标记（不支持带引号的字段）。
+    ///
+    /// # Errors
+    /// 缺少表头列或时间字段无法解析时返回错误。
+    pub fn from_csv(contents: &str) -> Result<Self> {
+        let mut lines =
+            contents.lines().map(str::trim).filter(|l| !l.is_empty());
+        let header = lines.next().context("标记 CSV 为空")?;
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+        let find = |name: &str| {
+            columns
+                .iter()
+                .position(|c| c.eq_ignore_ascii_case(name))
+                .with_context(|| format!("标记 CSV 缺少列: {name}"))
+        };
+        let (label_idx, start_idx, end_idx) =
+            (find("label")?, find("start")?, find("end")?);
+
+        let mut markers = Vec::new();
+        for (i, line) in lines.enumerate() {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let get = |idx: usize| {
+                fields.get(idx).copied().with_context(|| {
+                    format!("标记 CSV 第 {} 行字段不足", i + 2)
+                })
+            };
+            markers.push(Self::convert(
+                get(label_idx)?,
+                get(start_idx)?,
+                get(end_idx)?,
+            )?);
+        }
+        Ok(Self::new(markers))
+    }
+
+    /// 构造并校验单个标记。
+    fn convert(label: &str, start: &str, end: &str) -> Result<TimeMarker> {
+        let start = parse_marker_time(start)?;
+        let end = parse_marker_time(end)?;
+        if end < start {
+            bail!("标记 {label} 的结束时间早于起始时间");
+        }
+        Ok(TimeMarker { label: label.to_string(), start, end })
+    }
+
+    /// 返回所有标记。
+    #[must_use]
+    pub fn markers(&self) -> &[TimeMarker] {
+        &self.markers
+    }
+
+    /// 返回与 `[start, end)` 重叠的标记。
+    pub fn overlapping(
+        &self,
+        start: NaiveDateTime,
+        end: NaiveDateTime,
+    ) -> impl Iterator<Item = &TimeMarker> {
+        self.markers.iter().filter(move |m| m.start < end && start < m.end)
+    }
+
+    /// 为每个时间桶填充与之重叠的标记名称。
+    pub fn annotate(&self, buckets: &mut [TimeBucket]) {
+        for bucket in buckets {
+            bucket.markers = self
+                .overlapping(bucket.start, bucket.end)
+                .map(|m| m.label.clone())
+                .collect();
+        }
+    }
+}
+
+/// 解析标记时间，支持带或不带毫秒两种格式。
+fn parse_marker_time(s: &str) -> Result<NaiveDateTime> {
+    let s = s.trim();
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S"))
+        .with_context(|| format!("无法解析标记时间: {s}"))
+}
@@ -0,0 +1,114 @@
+//! 记录分析模块 - 基于解析结果的统计与诊断
+//!
+//! 本模块提供对解析得到的 `Sqllog` 记录进行二次分析的能力，
+//! 所有分析器都只依赖内存中的记录切片，可以直接挂接在解析回调中使用。
+//!
+//! ## 已提供的分析器
+//!
+//! - **关键字分析**（[`keywords`]）：按可配置的关键字/正则规则为记录打标签，
+//!   统计锁等待、超时、错误码等类别在各用户下的出现次数
+//! - **时间桶聚合**（[`timeline`]）：按固定时间窗口统计记录数与执行时间
+//! - **时间分区**（[`partition`]）：把时间戳划入自然日、ISO 周、班次或
+//!   自定义时间窗，供时间桶聚合按运维班次组织结果
+//! - **外部时间标记**（[`markers`]）：载入 AWR 快照、发布记录等带时间范围的
+//!   元数据，为时间桶标注重叠的系统事件
+//! - **执行计划热点**（[`plans`]）：统计计划树中各操作符的出现次数与代价
+//! - **分组聚合**（[`aggregate`]）：`analyze` 命令的迷你查询语法，按任意列
+//!   分组计算计数、求和、平均值与百分位
+//! - **SQL 指纹**（[`fingerprint`]）：替换字面量后归并同类语句
+//! - **日志集对比**（[`diff`]）：按指纹对比两组日志的调用次数与 p95
+//! - **日志覆盖**（[`coverage`]）：发现超过阈值的时间空档，按小时统计覆盖率
+//! - **会话排名**（[`sessions`]）：按累计执行时间与影响行数排名会话
+//! - **滑动窗口**（[`window`]）：最近 N 秒的 QPS、p95 与错误率及阈值告警，
+//!   供 `watch` 模式实时监控
+//! - **统计快照**（[`history`]）：按指纹与用户保存一次运行的统计，
+//!   与上次运行的快照做趋势对比
+//! - **会话并发**（[`concurrency`]）：按时间桶重建活跃会话数，给出各用户与
+//!   appname 的并发峰值
+//! - **数据画像**（[`profile`]）：字段缺失率、取值个数、时间范围与
+//!   description 长度分布
+//! - **安全分级**（[`classify`]）：按用户、IP 与语句模式规则把记录分为
+//!   normal/sensitive/forbidden，统计各规则的命中次数
+//! - **应用负载**（[`apps`]）：按规范化并经别名统一的 appname 统计调用次数、
+//!   执行时间百分位与各应用的主要 SQL 指纹
+//! - **索引建议**（[`indexes`]）：从 SQL 指纹的过滤条件中提取常用列，按可
+//!   受益语句的执行时间为源表推荐索引
+//!
+//! 关键字、执行计划、时间桶、日志覆盖、会话排名、会话并发、数据画像、安全分级、应用负载与索引建议分析器实现了 [`Analyzer`] trait，可以与自定义
+//! 分析器一起注册到 [`AnalysisEngine`]，在同一次解析中运行（见 [`engine`]）。
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use sqllog_analysis::analysis::KeywordAnalyzer;
+//! use sqllog_analysis::sqllog::Sqllog;
+//!
+//! let mut analyzer = KeywordAnalyzer::with_default_rules();
+//! let record = Sqllog {
+//!     user: Some("SYSDBA".into()),
+//!     description: "[-6403]:锁超时".into(),
+//!     ..Default::default()
+//! };
+//! analyzer.observe(std::slice::from_ref(&record));
+//!
+//! let report = analyzer.report();
+//! assert_eq!(report.by_category.get("lock_wait"), Some(&1));
+//! ```
+
+pub mod aggregate;
+pub mod apps;
+pub mod classify;
+pub mod concurrency;
+pub mod coverage;
+pub mod diff;
+pub mod engine;
+pub mod fingerprint;
+pub mod history;
+pub mod indexes;
+pub mod keywords;
+pub mod markers;
+pub mod partition;
+pub mod plans;
+pub mod profile;
+pub mod sessions;
+pub mod timeline;
+pub mod window;
+
+pub use aggregate::{
+    AggFunc, AggregateQuery, AggregateResult, Aggregator, Column, QueryError,
+    Value,
+};
+pub use apps::{
+    AppAliases, AppReport, AppStatement, AppStats, AppWorkloadAnalyzer,
+    normalize_appname,
+};
+pub use classify::{
+    Classifier, ClassifyReport, ClassifyRule, ClassifyRuleConfig, SecurityClass,
+};
+pub use concurrency::{
+    ConcurrencyAnalyzer, ConcurrencyBucket, ConcurrencyReport, PeakConcurrency,
+};
+pub use coverage::{CoverageAnalyzer, CoverageReport, HourCoverage, TimeGap};
+pub use diff::{
+    DiffReport, DiffThresholds, FingerprintAggregator, StatementChange,
+    StatementStats, diff,
+};
+pub use engine::{AnalysisEngine, Analyzer, Report};
+pub use fingerprint::fingerprint;
+pub use history::{
+    SnapshotAggregator, StatsSnapshot, TrendReport, UserPercentiles,
+    UserStatsAggregator, UserTrend, compare,
+};
+pub use indexes::{IndexAdvice, IndexAdvisor, IndexSuggestion};
+pub use keywords::{
+    KeywordAnalyzer, KeywordReport, KeywordRule, KeywordRuleConfig,
+};
+pub use markers::{MarkerSet, TimeMarker};
+pub use partition::{Partition, Partitioner, Shift, TimeWindow};
+pub use plans::{OperatorStats, PlanAnalyzer, PlanReport};
+pub use profile::{
+    FieldProfile, LengthDistribution, ProfileAnalyzer, ProfileReport,
+};
+pub use sessions::{SessionAnalyzer, SessionStats};
+pub use timeline::{TimeBucket, TimeBucketAggregator};
+pub use window::{Alert, AlertThresholds, SlidingWindow, WindowStats};
@@ -0,0 +1,302 @@
+//! 时间分区 - 把时间戳划入固定时间桶、自然日、ISO 周、班次或自定义时间窗
+//!
+//! DBA 报表常按运维班次（如每 8 小时一班）而不是自然日组织。[`Partitioner`]
+//! 决定一个时间戳落入哪个分区，供时间桶聚合（[`super::timeline`]）等按时间
+//! 组织结果的功能使用。以文本形式配置（[`Partitioner::from_str`]）：
+//!
+//! - `fixed:<秒>`：固定宽度，从 Unix 纪元起对齐（与按秒数创建的时间桶相同）
+//! - `day`：自然日，键形如 `2025-09-21`
+//! - `iso-week`：ISO 8601 周（周一开始），键形如 `2025-W38`
+//! - `shifts:<名称>=<HH:MM>,...`：每日班次，按开始时间排序，最后一个班次延续到
+//!   次日第一个班次开始；跨零点的部分归属班次开始的日期，键形如
+//!   `2025-09-21 night`。名称可省略，按开始时间顺序命名为 1、2、3……
+//! - `windows:<名称>=<星期> <HH:MM>-<HH:MM>;...`：类 cron 的自定义时间窗，如
+//!   `peak=mon-fri 09:00-18:00;batch=sat,sun 00:00-24:00`。星期可写 `*`、单日、
+//!   范围或以逗号分隔的列表；结束时间不晚于开始时间时跨零点。时间窗重叠时取
+//!   先列出的，不落在任何时间窗内的时间戳没有分区
+//!
+//! ```rust
+//! use sqllog_analysis::analysis::Partitioner;
+//!
+//! let shifts: Partitioner = "shifts:day=06:00,swing=14:00,night=22:00".parse().unwrap();
+//! let ts = chrono::NaiveDateTime::parse_from_str("2025-09-22 03:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+//! assert_eq!(shifts.partition(ts).unwrap().key, "2025-09-21 night");
+//! ```
+
+use chrono::{
+    Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Timelike, Weekday,
+};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// 一天的秒数
+const DAY_SECS: u32 = 24 * 3600;
+
+/// 时间戳所在的分区
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Partition {
+    /// 分区键，同一分区的时间戳键相同
+    pub key: String,
+    /// 分区起始时间（包含）
+    pub start: NaiveDateTime,
+    /// 分区结束时间（不包含）
+    pub end: NaiveDateTime,
+}
+
+/// 一个班次
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shift {
+    /// 班次名称
+    pub name: String,
+    /// 开始时间（当日零点起的秒数）
+    start: u32,
+}
+
+/// 一个自定义时间窗
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeWindow {
+    /// 时间窗名称
+    pub name: String,
+    /// 按周一到周日排列，为真表示时间窗在当天开始
+    days: [bool; 7],
+    /// 开始时间（当日零点起的秒数）
+    start: u32,
+    /// 持续时长（秒）
+    len: u32,
+}
+
+/// 时间分区方式
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Partitioner {
+    /// 固定宽度（秒），从 Unix 纪元起对齐
+    Fixed(u32),
+    /// 自然日
+    Day,
+    /// ISO 8601 周
+    IsoWeek,
+    /// 每日班次（按开始时间排序，至少一个）
+    Shifts(Vec<Shift>),
+    /// 自定义时间窗（按列出顺序匹配）
+    Windows(Vec<TimeWindow>),
+}
+
+impl Partitioner {
+    /// 时间戳所在的分区；自定义时间窗之外的时间戳返回 `None`
+    #[must_use]
+    pub fn partition(&self, ts: NaiveDateTime) -> Option<Partition> {
+        match self {
+            Self::Fixed(secs) => {
+                let width = i64::from((*secs).max(1));
+                let secs = ts.and_utc().timestamp();
+                let key = secs.div_euclid(width) * width;
+                let start =
+                    chrono::DateTime::from_timestamp(key, 0)?.naive_utc();
+                Some(Partition {
+                    key: start.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    start,
+                    end: start + Duration::seconds(width),
+                })
+            }
+            Self::Day => {
+                let start = ts.date().and_time(NaiveTime::MIN);
+                Some(Partition {
+                    key: ts.date().format("%Y-%m-%d").to_string(),
+                    start,
+                    end: start + Duration::days(1),
+                })
+            }
+            Self::IsoWeek => {
+                let week = ts.date().iso_week();
+                let monday = NaiveDate::from_isoywd_opt(
+                    week.year(),
+                    week.week(),
+                    Weekday::Mon,
+                )?;
+                let start = monday.and_time(NaiveTime::MIN);
+                Some(Partition {
+                    key: format!("{}-W{:02}", week.year(), week.week()),
+                    start,
+                    end: start + Duration::weeks(1),
+                })
+            }
+            Self::Shifts(shifts) => shift_partition(shifts, ts),
+            Self::Windows(windows) => {
+                windows.iter().find_map(|w| w.partition(ts))
+            }
+        }
+    }
+}
+
+fn shift_partition(shifts: &[Shift], ts: NaiveDateTime) -> Option<Partition> {
+    let secs = ts.num_seconds_from_midnight();
+    // 早于第一个班次开始的时间属于前一天的最后一个班次
+    let (index, date) = match shifts.iter().rposition(|s| s.start <= secs) {
+        Some(index) => (index, ts.date()),
+        None => (shifts.len().checked_sub(1)?, ts.date().pred_opt()?),
+    };
+    let shift = &shifts[index];
+    let start = at(date, shift.start);
+    let end = match shifts.get(index + 1) {
+        Some(next) => at(date, next.start),
+        None => at(date.succ_opt()?, shifts[0].start),
+    };
+    Some(Partition { key: format!("{date} {}", shift.name), start, end })
+}
+
+impl TimeWindow {
+    fn partition(&self, ts: NaiveDateTime) -> Option<Partition> {
+        let secs = ts.num_seconds_from_midnight();
+        let today = ts.date();
+        // 时间窗可能从当天开始，也可能从前一天开始、跨零点延续到当天
+        let begins = if secs >= self.start && secs < self.start + self.len {
+            today
+        } else if self.start + self.len > DAY_SECS
+            && secs < self.start + self.len - DAY_SECS
+        {
+            today.pred_opt()?
+        } else {
+            return None;
+        };
+        if !self.days[begins.weekday().num_days_from_monday() as usize] {
+            return None;
+        }
+        let start = at(begins, self.start);
+        Some(Partition {
+            key: format!("{begins} {}", self.name),
+            start,
+            end: start + Duration::seconds(i64::from(self.len)),
+        })
+    }
+}
+
+fn at(date: NaiveDate, secs: u32) -> NaiveDateTime {
+    date.and_time(NaiveTime::MIN) + Duration::seconds(i64::from(secs))
+}
+
+impl FromStr for Partitioner {
+    type Err = String;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let spec = spec.trim();
+        let (kind, rest) = spec.split_once(':').unwrap_or((spec, ""));
+        match kind.trim().to_ascii_lowercase().as_str() {
+            "fixed" => match rest.trim().parse::<u32>() {
+                Ok(secs) if secs > 0 => Ok(Self::Fixed(secs)),
+                _ => Err(format!("fixed 需要正整数秒数: {spec}")),
+            },
+            "day" if rest.is_empty() => Ok(Self::Day),
+            "iso-week" | "isoweek" | "week" if rest.is_empty() => {
+                Ok(Self::IsoWeek)
+            }
+            "shifts" => parse_shifts(rest).map(Self::Shifts),
+            "windows" => parse_windows(rest).map(Self::Windows),
+            _ => Err(format!(
+                "未知的分区方式: {spec}；可选 fixed:<秒>、day、iso-week、shifts:...、windows:..."
+            )),
+        }
+    }
+}
+
+fn parse_shifts(spec: &str) -> Result<Vec<Shift>, String> {
+    let mut shifts = Vec::new();
+    for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (name, time) = match item.split_once('=') {
+            Some((name, time)) => (Some(name.trim()), time),
+            None => (None, item),
+        };
+        let start = parse_clock(time)?;
+        if start >= DAY_SECS {
+            return Err(format!("班次开始时间必须早于 24:00: {item}"));
+        }
+        shifts.push((name.map(str::to_string), start));
+    }
+    if shifts.is_empty() {
+        return Err("shifts 至少需要一个班次开始时间".to_string());
+    }
+    shifts.sort_by_key(|&(_, start)| start);
+    if shifts.windows(2).any(|w| w[0].1 == w[1].1) {
+        return Err(format!("班次开始时间重复: {spec}"));
+    }
+    Ok(shifts
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, start))| Shift {
+            name: name.unwrap_or_else(|| (i + 1).to_string()),
+            start,
+        })
+        .collect())
+}
+
+fn parse_windows(spec: &str) -> Result<Vec<TimeWindow>, String> {
+    let mut windows = Vec::new();
+    for item in spec.split(';').map(str::trim).filter(|s| !s.is_empty()) {
+        let (name, rule) = item.split_once('=').ok_or_else(|| {
+            format!("时间窗应为 名称=星期 HH:MM-HH:MM: {item}")
+        })?;
+        let (days, range) = rule
+            .trim()
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| format!("时间窗缺少时间范围: {item}"))?;
+        let (start, end) = range
+            .trim()
+            .split_once('-')
+            .ok_or_else(|| format!("时间范围应为 HH:MM-HH:MM: {item}"))?;
+        let start = parse_clock(start)?;
+        let end = parse_clock(end)?;
+        if start >= DAY_SECS {
+            return Err(format!("时间窗开始时间必须早于 24:00: {item}"));
+        }
+        let len =
+            if end > start { end - start } else { end + DAY_SECS - start };
+        windows.push(TimeWindow {
+            name: name.trim().to_string(),
+            days: parse_days(days)?,
+            start,
+            len,
+        });
+    }
+    if windows.is_empty() {
+        return Err("windows 至少需要一个时间窗".to_string());
+    }
+    Ok(windows)
+}
+
+/// `*`、`mon`、`mon-fri`、`sat,sun` 等星期写法
+fn parse_days(spec: &str) -> Result<[bool; 7], String> {
+    let mut days = [false; 7];
+    for part in spec.split(',').map(str::trim) {
+        if part == "*" {
+            return Ok([true; 7]);
+        }
+        let (from, to) = part.split_once('-').unwrap_or((part, part));
+        let from = parse_weekday(from)?;
+        let to = parse_weekday(to)?;
+        // 范围可以跨周末，如 fri-mon
+        let mut day = from;
+        loop {
+            days[day] = true;
+            if day == to {
+                break;
+            }
+            day = (day + 1) % 7;
+        }
+    }
+    Ok(days)
+}
+
+fn parse_weekday(s: &str) -> Result<usize, String> {
+    s.trim()
+        .parse::<Weekday>()
+        .map(|d| d.num_days_from_monday() as usize)
+        .map_err(|_| format!("无法识别的星期: {s}"))
+}
+
+/// `HH:MM`（允许 `24:00`），返回零点起的秒数
+fn parse_clock(s: &str) -> Result<u32, String> {
+    let s = s.trim();
+    let parsed = s.split_once(':').and_then(|(h, m)| {
+        let (h, m) = (h.parse::<u32>().ok()?, m.parse::<u32>().ok()?);
+        (m < 60 && (h < 24 || (h == 24 && m == 0))).then_some(h * 3600 + m * 60)
+    });
+    parsed.ok_or_else(|| format!("时间应为 HH:MM: {s}"))
+}
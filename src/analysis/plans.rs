@@ -0,0 +1,101 @@
+//! 执行计划分析器 - 操作符热点统计
+//!
+//! 基于解析阶段提取到 `Sqllog::plan` 的执行计划树（需启用
+//! `ParseOptions::extract_plans`），按操作符统计出现次数、涉及的记录数以及
+//! 估算代价，用于发现全表扫描（`CSCN2`）、排序（`SORT3`）、哈希连接等
+//! 高频或高代价的操作符。
+
+use crate::sqllog::{ExecTimeMs, PlanNode, Sqllog};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// 单个操作符的统计
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct OperatorStats {
+    /// 在所有计划中出现的次数
+    pub occurrences: u64,
+    /// 包含该操作符的记录数
+    pub records: u64,
+    /// 估算代价合计
+    pub total_cost: i64,
+    /// 最大估算代价
+    pub max_cost: i64,
+    /// 包含该操作符的记录执行时间合计
+    pub total_execute_time: ExecTimeMs,
+}
+
+/// 执行计划分析结果
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct PlanReport {
+    /// 参与分析的记录总数
+    pub records_scanned: u64,
+    /// 带执行计划的记录数
+    pub records_with_plan: u64,
+    /// 各操作符统计
+    pub operators: BTreeMap<String, OperatorStats>,
+}
+
+impl PlanReport {
+    /// 按包含该操作符的记录执行时间合计降序返回前 `n` 个操作符
+    #[must_use]
+    pub fn hotspots(&self, n: usize) -> Vec<(&str, &OperatorStats)> {
+        let mut ops: Vec<_> =
+            self.operators.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        ops.sort_by(|a, b| {
+            b.1.total_execute_time
+                .cmp(&a.1.total_execute_time)
+                .then(b.1.occurrences.cmp(&a.1.occurrences))
+                .then(a.0.cmp(b.0))
+        });
+        ops.truncate(n);
+        ops
+    }
+}
+
+/// 执行计划分析器
+#[derive(Debug, Default, Clone)]
+pub struct PlanAnalyzer {
+    report: PlanReport,
+}
+
+impl PlanAnalyzer {
+    /// 创建分析器
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 分析一批记录并累加统计
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            self.report.records_scanned += 1;
+            let Some(plan) = &record.plan else {
+                continue;
+            };
+            self.report.records_with_plan += 1;
+
+            let mut seen = BTreeSet::new();
+            plan.walk(&mut |node: &PlanNode| {
+                let stats = self
+                    .report
+                    .operators
+                    .entry(node.operator.clone())
+                    .or_default();
+                stats.occurrences += 1;
+                stats.total_cost += node.cost;
+                stats.max_cost = stats.max_cost.max(node.cost);
+                if seen.insert(node.operator.as_str()) {
+                    stats.records += 1;
+                    stats.total_execute_time +=
+                        record.execute_time.unwrap_or_default();
+                }
+            });
+        }
+    }
+
+    /// 返回当前累计的分析结果
+    #[must_use]
+    pub const fn report(&self) -> &PlanReport {
+        &self.report
+    }
+}
@@ -0,0 +1,224 @@
+//! 数据画像 - 统计解析结果的字段分布
+//!
+//! 在为一批新日志设计导出方案之前，先了解各字段的缺失率、`user`/`appname`/
+//! `ip`/`sql_type` 的取值个数、时间范围以及 description 的长度分布。
+//! description 长度按 UTF-8 字节数统计（与 `export.description_max_bytes` 口径一致），
+//! 按长度计数保存，百分位是精确值。
+
+use super::timeline::parse_occurrence_time;
+use crate::sqllog::Sqllog;
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+/// 统计缺失率的字段
+const NULLABLE_FIELDS: [&str; 11] = [
+    "session",
+    "thread",
+    "user",
+    "trx_id",
+    "statement",
+    "appname",
+    "ip",
+    "sql_type",
+    "execute_time",
+    "rowcount",
+    "execute_id",
+];
+
+/// 额外统计取值个数的字段（在 [`NULLABLE_FIELDS`] 中的下标）
+const DISTINCT_FIELDS: [usize; 4] = [2, 5, 6, 7];
+
+/// 单个字段的画像
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldProfile {
+    pub field: String,
+    /// 缺失（`None`）的记录数
+    pub nulls: u64,
+    /// 缺失率（0.0 ~ 1.0）
+    pub null_rate: f64,
+    /// 不同取值的个数，仅对 user/appname/ip/sql_type 统计
+    pub distinct: Option<u64>,
+}
+
+/// description 长度分布（UTF-8 字节数）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LengthDistribution {
+    pub min: usize,
+    pub max: usize,
+    pub mean: f64,
+    pub p50: usize,
+    pub p95: usize,
+    pub p99: usize,
+}
+
+/// 数据画像结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProfileReport {
+    pub records: u64,
+    pub first: Option<NaiveDateTime>,
+    pub last: Option<NaiveDateTime>,
+    /// 时间戳无法解析的记录数
+    pub invalid_timestamps: u64,
+    pub fields: Vec<FieldProfile>,
+    /// 没有记录时为 `None`
+    pub description_length: Option<LengthDistribution>,
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "记录数: {}", self.records)?;
+        match (self.first, self.last) {
+            (Some(first), Some(last)) => {
+                writeln!(f, "时间范围: {first} ~ {last}")?;
+            }
+            _ => writeln!(f, "时间范围: -")?,
+        }
+        if self.invalid_timestamps > 0 {
+            writeln!(f, "时间戳无法解析的记录: {}", self.invalid_timestamps)?;
+        }
+        writeln!(f, "字段缺失率:")?;
+        for field in &self.fields {
+            write!(
+                f,
+                "  {:<14}{:>6.1}%  ({} 条)",
+                field.field,
+                field.null_rate * 100.0,
+                field.nulls
+            )?;
+            if let Some(distinct) = field.distinct {
+                write!(f, "  取值 {distinct} 个")?;
+            }
+            writeln!(f)?;
+        }
+        if let Some(len) = &self.description_length {
+            writeln!(
+                f,
+                "description 长度（字节）: 最小 {}，平均 {:.1}，p50 {}，p95 {}，p99 {}，最大 {}",
+                len.min, len.mean, len.p50, len.p95, len.p99, len.max
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// 数据画像分析器
+#[derive(Debug, Clone, Default)]
+pub struct ProfileAnalyzer {
+    records: u64,
+    first: Option<NaiveDateTime>,
+    last: Option<NaiveDateTime>,
+    invalid_timestamps: u64,
+    nulls: [u64; NULLABLE_FIELDS.len()],
+    distinct: [HashSet<String>; DISTINCT_FIELDS.len()],
+    /// description 字节数 -> 记录数
+    lengths: BTreeMap<usize, u64>,
+}
+
+impl ProfileAnalyzer {
+    /// 创建分析器
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 处理一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            self.records += 1;
+            match parse_occurrence_time(&record.occurrence_time) {
+                Some(ts) => {
+                    self.first = Some(self.first.map_or(ts, |t| t.min(ts)));
+                    self.last = Some(self.last.map_or(ts, |t| t.max(ts)));
+                }
+                None => self.invalid_timestamps += 1,
+            }
+
+            let text = [
+                &record.session,
+                &record.thread,
+                &record.user,
+                &record.trx_id,
+                &record.statement,
+                &record.appname,
+                &record.ip,
+                &record.sql_type,
+            ];
+            let present = text.iter().map(|v| v.is_some()).chain([
+                record.execute_time.is_some(),
+                record.rowcount.is_some(),
+                record.execute_id.is_some(),
+            ]);
+            for (nulls, present) in self.nulls.iter_mut().zip(present) {
+                *nulls += u64::from(!present);
+            }
+            for (set, &idx) in self.distinct.iter_mut().zip(&DISTINCT_FIELDS) {
+                if let Some(value) = text[idx] {
+                    if !set.contains(value) {
+                        set.insert(value.clone());
+                    }
+                }
+            }
+
+            *self.lengths.entry(record.description.len()).or_insert(0) += 1;
+        }
+    }
+
+    /// 生成数据画像
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn report(&self) -> ProfileReport {
+        let total = self.records.max(1) as f64;
+        let fields = NULLABLE_FIELDS
+            .iter()
+            .enumerate()
+            .map(|(idx, &field)| FieldProfile {
+                field: field.to_string(),
+                nulls: self.nulls[idx],
+                null_rate: self.nulls[idx] as f64 / total,
+                distinct: DISTINCT_FIELDS
+                    .iter()
+                    .position(|&d| d == idx)
+                    .map(|d| self.distinct[d].len() as u64),
+            })
+            .collect();
+
+        ProfileReport {
+            records: self.records,
+            first: self.first,
+            last: self.last,
+            invalid_timestamps: self.invalid_timestamps,
+            fields,
+            description_length: self.length_distribution(),
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn length_distribution(&self) -> Option<LengthDistribution> {
+        let (&min, _) = self.lengths.first_key_value()?;
+        let (&max, _) = self.lengths.last_key_value()?;
+        let sum: u64 =
+            self.lengths.iter().map(|(&len, &n)| len as u64 * n).sum();
+        // 最近秩百分位
+        let pick = |p: u64| {
+            let rank = ((p * self.records + 99) / 100).max(1);
+            let mut seen = 0;
+            self.lengths
+                .iter()
+                .find(|&(_, &n)| {
+                    seen += n;
+                    seen >= rank
+                })
+                .map_or(max, |(&len, _)| len)
+        };
+        Some(LengthDistribution {
+            min,
+            max,
+            mean: sum as f64 / self.records as f64,
+            p50: pick(50),
+            p95: pick(95),
+            p99: pick(99),
+        })
+    }
+}
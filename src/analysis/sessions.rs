@@ -0,0 +1,105 @@
+//! 会话排名 - 按累计执行时间找出最重的会话
+//!
+//! 按 `session` 汇总记录数、执行时间与影响行数，并按累计执行时间（相同时
+//! 按影响行数）降序排名。没有会话信息（`sess:NULL`）的记录不参与排名。
+//! 导出时配置 `export.top_sessions` 可只导出排名前 K 的会话的记录，
+//! 排名规则与这里一致。
+
+use crate::sqllog::{ExecTimeMs, RowCount, Sqllog};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// 默认报告的会话数
+pub const DEFAULT_TOP_SESSIONS: usize = 20;
+
+/// 单个会话的统计
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SessionStats {
+    pub session: String,
+    pub records: u64,
+    /// 累计 execute_time
+    pub total_execute_time: ExecTimeMs,
+    pub max_execute_time: Option<ExecTimeMs>,
+    /// 累计影响行数
+    pub total_rowcount: RowCount,
+}
+
+/// 会话排名分析器
+#[derive(Debug, Clone)]
+pub struct SessionAnalyzer {
+    limit: usize,
+    sessions: HashMap<String, SessionStats>,
+}
+
+impl SessionAnalyzer {
+    /// 创建分析器，`limit` 为报告中保留的会话数
+    #[must_use]
+    pub fn new(limit: usize) -> Self {
+        Self { limit, sessions: HashMap::new() }
+    }
+
+    /// 汇总一批记录
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            let Some(session) = &record.session else {
+                continue;
+            };
+            let stats =
+                self.sessions.entry(session.clone()).or_insert_with(|| {
+                    SessionStats {
+                        session: session.clone(),
+                        records: 0,
+                        total_execute_time: ExecTimeMs::default(),
+                        max_execute_time: None,
+                        total_rowcount: RowCount::default(),
+                    }
+                });
+            stats.records += 1;
+            if let Some(t) = record.execute_time {
+                stats.total_execute_time += t;
+                stats.max_execute_time =
+                    Some(stats.max_execute_time.map_or(t, |m| m.max(t)));
+            }
+            if let Some(rows) = record.rowcount {
+                stats.total_rowcount += rows;
+            }
+        }
+    }
+
+    /// 参与汇总的会话数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// 是否尚未汇总任何会话
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// 按累计执行时间、累计影响行数降序返回前 `k` 个会话
+    #[must_use]
+    pub fn top(&self, k: usize) -> Vec<SessionStats> {
+        let mut ranked: Vec<&SessionStats> = self.sessions.values().collect();
+        ranked.sort_by(|a, b| {
+            b.total_execute_time
+                .cmp(&a.total_execute_time)
+                .then(b.total_rowcount.cmp(&a.total_rowcount))
+                .then(a.session.cmp(&b.session))
+        });
+        ranked.into_iter().take(k).cloned().collect()
+    }
+
+    /// 按构造时的 `limit` 返回排名
+    #[must_use]
+    pub fn report(&self) -> Vec<SessionStats> {
+        self.top(self.limit)
+    }
+}
+
+impl Default for SessionAnalyzer {
+    fn default() -> Self {
+        Self::new(DEFAULT_TOP_SESSIONS)
+    }
+}
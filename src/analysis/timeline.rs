@@ -0,0 +1,110 @@
+//! 时间桶聚合 - 按固定时间窗口统计 SQL 负载
+//!
+//! 将记录按 `occurrence_time` 划分到固定长度的时间桶中，统计每个桶内的
+//! 记录数、总执行时间和最大执行时间。也可以按班次、ISO 周或自定义时间窗
+//! 划分（见 [`super::partition`]）。聚合结果可以再与外部时间标记
+//! （见 [`super::markers`]）关联，用于把 SQL 负载与系统事件对照。
+
+use super::partition::Partitioner;
+use crate::sqllog::{ExecTimeMs, Sqllog};
+use chrono::NaiveDateTime;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// sqllog 时间戳格式
+pub const OCCURRENCE_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// 解析 `occurrence_time` 字段，格式不合法时返回 `None`。
+#[must_use]
+pub fn parse_occurrence_time(s: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(s.trim(), OCCURRENCE_TIME_FORMAT).ok()
+}
+
+/// 单个时间桶的聚合结果
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeBucket {
+    /// 分区键（见 [`super::partition::Partition::key`]）
+    pub key: String,
+    /// 桶起始时间（包含）
+    pub start: NaiveDateTime,
+    /// 桶结束时间（不包含）
+    pub end: NaiveDateTime,
+    /// 桶内记录数
+    pub records: u64,
+    /// 桶内 execute_time 之和
+    pub total_execute_time: ExecTimeMs,
+    /// 桶内最大 execute_time
+    pub max_execute_time: Option<ExecTimeMs>,
+    /// 与该桶时间范围重叠的外部标记
+    pub markers: Vec<String>,
+}
+
+/// 时间桶聚合器
+#[derive(Debug, Clone)]
+pub struct TimeBucketAggregator {
+    partitioner: Partitioner,
+    buckets: BTreeMap<(NaiveDateTime, String), TimeBucket>,
+    skipped: u64,
+}
+
+impl TimeBucketAggregator {
+    /// 创建聚合器，`bucket_secs` 为桶宽（秒），为 0 时按 1 秒处理。
+    #[must_use]
+    pub fn new(bucket_secs: u32) -> Self {
+        Self::with_partitioner(Partitioner::Fixed(bucket_secs.max(1)))
+    }
+
+    /// 按指定的分区方式（班次、ISO 周、自定义时间窗等）划分时间桶
+    #[must_use]
+    pub const fn with_partitioner(partitioner: Partitioner) -> Self {
+        Self { partitioner, buckets: BTreeMap::new(), skipped: 0 }
+    }
+
+    /// 聚合一批记录。时间戳无法解析或不属于任何分区（自定义时间窗之外）
+    /// 的记录会被计入 `skipped`。
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            let Some(ts) = parse_occurrence_time(&record.occurrence_time)
+            else {
+                self.skipped += 1;
+                continue;
+            };
+
+            let Some(partition) = self.partitioner.partition(ts) else {
+                self.skipped += 1;
+                continue;
+            };
+            let bucket = self
+                .buckets
+                .entry((partition.start, partition.key))
+                .or_insert_with_key(|(start, key)| TimeBucket {
+                    key: key.clone(),
+                    start: *start,
+                    end: partition.end,
+                    records: 0,
+                    total_execute_time: ExecTimeMs::default(),
+                    max_execute_time: None,
+                    markers: Vec::new(),
+                });
+
+            bucket.records += 1;
+            if let Some(t) = record.execute_time {
+                bucket.total_execute_time += t;
+                bucket.max_execute_time =
+                    Some(bucket.max_execute_time.map_or(t, |m| m.max(t)));
+            }
+        }
+    }
+
+    /// 时间戳无法解析或不属于任何分区而被跳过的记录数
+    #[must_use]
+    pub const fn skipped(&self) -> u64 {
+        self.skipped
+    }
+
+    /// 按时间顺序返回所有非空时间桶。
+    #[must_use]
+    pub fn buckets(&self) -> Vec<TimeBucket> {
+        self.buckets.values().cloned().collect()
+    }
+}
@@ -0,0 +1,212 @@
+//! 滑动窗口统计 - 最近 N 分钟的 QPS、p95 执行时间与错误率
+//!
+//! 供 `watch` 模式实时监控使用：窗口以已观察到的最新 `occurrence_time` 为
+//! 终点，早于窗口起点的记录被淘汰。错误按 [`KeywordAnalyzer`] 的规则判断
+//! （命中任一类别即计为错误记录）。[`AlertThresholds`] 对窗口统计做阈值
+//! 检查，返回越限的指标。
+//!
+//! 记录应大致按时间顺序到达；晚于窗口起点的乱序记录仍会计入。
+
+use super::keywords::KeywordAnalyzer;
+use super::timeline::parse_occurrence_time;
+use crate::sqllog::{ExecTimeMs, Sqllog};
+use chrono::{NaiveDateTime, TimeDelta};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// 默认窗口长度（秒）
+pub const DEFAULT_WINDOW_SECS: u32 = 300;
+
+/// 窗口内的一条记录
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: NaiveDateTime,
+    execute_time: Option<ExecTimeMs>,
+    error: bool,
+}
+
+/// 滑动窗口统计结果
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WindowStats {
+    pub window_secs: u32,
+    /// 窗口终点（最新记录时间）
+    pub end: Option<NaiveDateTime>,
+    pub records: u64,
+    /// 每秒记录数（按整个窗口长度计算）
+    pub qps: f64,
+    pub p95_execute_time: Option<ExecTimeMs>,
+    pub error_records: u64,
+    /// 错误记录占比（0.0 ~ 1.0）
+    pub error_rate: f64,
+}
+
+impl fmt::Display for WindowStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let end = self.end.map_or_else(|| "-".to_string(), |t| t.to_string());
+        let p95 = self
+            .p95_execute_time
+            .map_or_else(|| "-".to_string(), |t| format!("{}ms", t.get()));
+        write!(
+            f,
+            "[{end}] 最近 {} 秒: {} 条，QPS {:.2}，p95 {p95}，错误率 {:.2}%",
+            self.window_secs,
+            self.records,
+            self.qps,
+            self.error_rate * 100.0
+        )
+    }
+}
+
+/// 滑动窗口统计器
+#[derive(Debug, Clone)]
+pub struct SlidingWindow {
+    window_secs: u32,
+    samples: VecDeque<Sample>,
+    latest: Option<NaiveDateTime>,
+    keywords: KeywordAnalyzer,
+}
+
+impl SlidingWindow {
+    /// 创建窗口，`window_secs` 为 0 时按 1 秒处理；使用内置错误规则
+    #[must_use]
+    pub fn new(window_secs: u32) -> Self {
+        Self::with_keywords(window_secs, KeywordAnalyzer::with_default_rules())
+    }
+
+    /// 使用自定义关键字规则判断错误记录
+    #[must_use]
+    pub fn with_keywords(window_secs: u32, keywords: KeywordAnalyzer) -> Self {
+        Self {
+            window_secs: window_secs.max(1),
+            samples: VecDeque::new(),
+            latest: None,
+            keywords,
+        }
+    }
+
+    /// 加入一批记录并淘汰窗口之外的记录；时间戳无法解析的记录被忽略
+    pub fn observe(&mut self, records: &[Sqllog]) {
+        for record in records {
+            let Some(at) = parse_occurrence_time(&record.occurrence_time)
+            else {
+                continue;
+            };
+            self.latest = Some(self.latest.map_or(at, |t| t.max(at)));
+            self.samples.push_back(Sample {
+                at,
+                execute_time: record.execute_time,
+                error: !self.keywords.tag(record).is_empty(),
+            });
+        }
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let Some(latest) = self.latest else { return };
+        let start = latest - TimeDelta::seconds(i64::from(self.window_secs));
+        // 乱序到达的旧记录也要淘汰，不能只检查队首
+        self.samples.retain(|s| s.at > start);
+    }
+
+    /// 当前窗口的统计
+    #[must_use]
+    pub fn stats(&self) -> WindowStats {
+        let records = self.samples.len() as u64;
+        let error_records =
+            self.samples.iter().filter(|s| s.error).count() as u64;
+        let mut times: Vec<ExecTimeMs> =
+            self.samples.iter().filter_map(|s| s.execute_time).collect();
+        times.sort_unstable();
+        let n = times.len();
+        // 最近秩百分位，与 diff 的 p95 口径一致
+        let p95 = (n > 0).then(|| times[((95 * n + 99) / 100).max(1) - 1]);
+
+        WindowStats {
+            window_secs: self.window_secs,
+            end: self.latest,
+            records,
+            qps: records as f64 / f64::from(self.window_secs),
+            p95_execute_time: p95,
+            error_records,
+            error_rate: if records == 0 {
+                0.0
+            } else {
+                error_records as f64 / records as f64
+            },
+        }
+    }
+}
+
+impl Default for SlidingWindow {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_SECS)
+    }
+}
+
+/// 告警阈值，未设置的指标不检查
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct AlertThresholds {
+    pub max_qps: Option<f64>,
+    pub max_p95_ms: Option<i64>,
+    /// 错误率上限（0.0 ~ 1.0）
+    pub max_error_rate: Option<f64>,
+}
+
+/// 越限的指标
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Alert {
+    /// 指标名：`qps` / `p95_ms` / `error_rate`
+    pub metric: &'static str,
+    pub value: f64,
+    pub threshold: f64,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} = {:.4} 超过阈值 {}",
+            self.metric, self.value, self.threshold
+        )
+    }
+}
+
+impl AlertThresholds {
+    /// 检查窗口统计，返回越限的指标（窗口为空时不告警）
+    #[must_use]
+    pub fn check(&self, stats: &WindowStats) -> Vec<Alert> {
+        if stats.records == 0 {
+            return Vec::new();
+        }
+        let mut alerts = Vec::new();
+        if let Some(max) = self.max_qps.filter(|max| stats.qps > *max) {
+            alerts.push(Alert {
+                metric: "qps",
+                value: stats.qps,
+                threshold: max,
+            });
+        }
+        if let (Some(max), Some(p95)) =
+            (self.max_p95_ms, stats.p95_execute_time)
+        {
+            if p95.get() > max {
+                alerts.push(Alert {
+                    metric: "p95_ms",
+                    value: p95.get() as f64,
+                    threshold: max as f64,
+                });
+            }
+        }
+        if let Some(max) =
+            self.max_error_rate.filter(|max| stats.error_rate > *max)
+        {
+            alerts.push(Alert {
+                metric: "error_rate",
+                value: stats.error_rate,
+                threshold: max,
+            });
+        }
+        alerts
+    }
+}
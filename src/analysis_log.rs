@@ -5,6 +5,7 @@
 // - 控制台输出（可选）
 // - 可配置的日志等级
 // - 异步非阻塞写入（提高性能）
+// - 通过 OTLP/HTTP 导出 span 与指标（`otel` 特性）
 
 use chrono::Local;
 use lazy_static::lazy_static;
@@ -13,7 +14,7 @@ use std::sync::Mutex;
 use std::{env, fs, fs::OpenOptions, io, path::PathBuf};
 use tracing::info;
 use tracing_appender::non_blocking::{NonBlocking, WorkerGuard};
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
+use tracing_subscriber::{EnvFilter, Layer, Registry, fmt, prelude::*};
 
 lazy_static! {
     /// 全局日志守护者，用于确保日志工作线程在程序退出时正确清理
@@ -21,6 +22,9 @@ lazy_static! {
     static ref LOG_GUARD: Mutex<Option<WorkerGuard>> = Mutex::new(None);
 }
 
+/// 挂在订阅器最内层的 OTLP 导出层
+type OtelLayer = Option<Box<dyn Layer<Registry> + Send + Sync>>;
+
 /// 日志配置参数结构体
 ///
 /// 控制应用程序的日志行为，包括输出目标、日志等级等设置。
@@ -33,6 +37,13 @@ pub struct LogConfig {
     pub log_file: Option<PathBuf>,
     /// 是否同时在控制台输出日志
     pub enable_stdout: bool,
+    /// 控制台输出的最高等级，`None` 时与 `level` 相同（`-q` 时为 Error）
+    pub stdout_level: Option<LevelFilter>,
+    /// OTLP/HTTP 导出地址（`otel` 特性），`None` 时只使用
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` 环境变量
+    pub otlp_endpoint: Option<String>,
+    /// 导出 span 与指标时的服务名，`None` 为 `sqllog-analysis`
+    pub otlp_service_name: Option<String>,
 }
 
 impl Default for LogConfig {
@@ -48,6 +59,9 @@ impl Default for LogConfig {
             level: LevelFilter::Info,
             log_file: Some("sqllog".into()),
             enable_stdout: false,
+            stdout_level: None,
+            otlp_endpoint: None,
+            otlp_service_name: None,
         }
     }
 }
@@ -75,6 +89,7 @@ impl LogConfig {
     ///     level: LevelFilter::Info,
     ///     log_file: Some(PathBuf::from("logs")),
     ///     enable_stdout: true,
+    ///     ..Default::default()
     /// };
     /// config.init()?;
     /// ```
@@ -142,7 +157,10 @@ impl LogConfig {
 
         // 配置控制台输出层的过滤器
         let stdout_filter = if self.enable_stdout {
-            filter.clone()
+            self.stdout_level.map_or_else(
+                || filter.clone(),
+                |level| EnvFilter::new(format!("{}", level.min(self.level))),
+            )
         } else {
             EnvFilter::new("off") // 关闭控制台输出
         };
@@ -171,7 +189,7 @@ impl LogConfig {
             .with_thread_ids(true) // 文件中显示线程ID
             .with_thread_names(true)
             .compact() // 使用紧凑格式
-            .with_filter(filter);
+            .with_filter(filter.clone());
 
         // 初始化 tracing-log 兼容性层，使 log crate 的消息能被 tracing 处理
         // 需要在 registry 初始化之前设置
@@ -180,9 +198,12 @@ impl LogConfig {
             eprintln!("警告: log 兼容性层初始化失败: {e}");
         }
 
+        let otel_layer = self.otel_layer(&filter);
+
         // 注册并初始化 tracing 订阅器
         // 使用 try_init() 来避免重复初始化问题
         if let Err(e) = tracing_subscriber::registry()
+            .with(otel_layer)
             .with(stdout_layer)
             .with(file_layer)
             .try_init()
@@ -201,3 +222,197 @@ impl LogConfig {
         Ok(())
     }
 }
+
+impl LogConfig {
+    /// 配置了 OTLP 地址（或环境变量）时创建导出层
+    #[cfg(feature = "otel")]
+    fn otel_layer(&self, filter: &EnvFilter) -> OtelLayer {
+        let endpoint = self.otlp_endpoint.as_deref();
+        if endpoint.is_none()
+            && env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_none()
+        {
+            return None;
+        }
+        let service_name =
+            self.otlp_service_name.as_deref().unwrap_or("sqllog-analysis");
+        match otel::init(endpoint, service_name) {
+            Ok(layer) => Some(layer.with_filter(filter.clone()).boxed()),
+            Err(e) => {
+                eprintln!("警告: OTLP 导出初始化失败，不导出 span 与指标: {e}");
+                None
+            }
+        }
+    }
+
+    #[cfg(not(feature = "otel"))]
+    #[allow(clippy::unused_self)]
+    fn otel_layer(&self, _filter: &EnvFilter) -> OtelLayer {
+        if self.otlp_endpoint.is_some() || self.otlp_service_name.is_some() {
+            eprintln!("警告: 未以 otel 特性编译，忽略 log.otlp_* 配置");
+        }
+        None
+    }
+}
+
+/// 命令行输出详细程度
+///
+/// 由 `-q/--quiet` 与 `-v/-vv/-vvv`（或重复的 `--verbose`）决定，覆盖配置
+/// 文件中的 `[log]` 设置：
+/// - `Quiet`：控制台只输出错误，日志文件仍按配置等级记录
+/// - `Normal`：沿用配置
+/// - `Verbose(n)`：打开控制台输出，等级依次为 info/debug/trace
+///
+/// 报告类子命令的结果（如 `inspect`、`diff` 的输出）不受影响。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Verbosity {
+    /// 只输出错误
+    Quiet,
+    /// 沿用配置文件中的日志等级与 stdout 开关
+    #[default]
+    Normal,
+    /// `-v` 的次数（1..=3）
+    Verbose(u8),
+}
+
+impl Verbosity {
+    /// 从命令行参数中取出详细程度选项，其余参数保持原有顺序
+    ///
+    /// # Errors
+    /// `-q` 与 `-v` 同时出现时返回错误描述
+    pub fn take_from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut quiet = false;
+        let mut verbose = 0usize;
+        args.retain(|arg| match arg.as_str() {
+            "-q" | "--quiet" => {
+                quiet = true;
+                false
+            }
+            "--verbose" => {
+                verbose += 1;
+                false
+            }
+            flag if flag.len() > 1
+                && flag.starts_with('-')
+                && flag[1..].bytes().all(|b| b == b'v') =>
+            {
+                verbose += flag.len() - 1;
+                false
+            }
+            _ => true,
+        });
+        match (quiet, verbose) {
+            (true, 0) => Ok(Self::Quiet),
+            (true, _) => Err("-q/--quiet 不能与 -v/--verbose 同时使用".into()),
+            (false, 0) => Ok(Self::Normal),
+            #[allow(clippy::cast_possible_truncation)]
+            (false, n) => Ok(Self::Verbose(n.min(3) as u8)),
+        }
+    }
+
+    /// 是否为 `-q`
+    #[must_use]
+    pub const fn is_quiet(self) -> bool {
+        matches!(self, Self::Quiet)
+    }
+
+    /// 按详细程度调整日志配置
+    pub fn apply(self, config: &mut LogConfig) {
+        match self {
+            Self::Quiet => config.stdout_level = Some(LevelFilter::Error),
+            Self::Normal => {}
+            Self::Verbose(n) => {
+                config.enable_stdout = true;
+                config.level = match n {
+                    1 => LevelFilter::Info,
+                    2 => LevelFilter::Debug,
+                    _ => LevelFilter::Trace,
+                };
+            }
+        }
+    }
+}
+
+/// 导出剩余的 span 与指标并关闭 OTLP 导出（未启用时为空操作）
+///
+/// `process::exit` 不会运行析构函数，退出前需要显式调用。
+pub fn shutdown_telemetry() {
+    #[cfg(feature = "otel")]
+    otel::shutdown();
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use lazy_static::lazy_static;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use std::sync::Mutex;
+    use tracing_subscriber::{Layer, Registry};
+
+    lazy_static! {
+        /// 退出前需要关闭（导出剩余数据）的 provider
+        static ref PROVIDERS: Mutex<Option<(SdkTracerProvider, SdkMeterProvider)>> =
+            Mutex::new(None);
+    }
+
+    /// 创建 span 与指标导出层；`endpoint` 为 `None` 时由导出器读取
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT`
+    pub(super) fn init(
+        endpoint: Option<&str>,
+        service_name: &str,
+    ) -> Result<impl Layer<Registry> + Send + Sync, String> {
+        let url = |path: &str| {
+            endpoint
+                .map(|base| format!("{}/{path}", base.trim_end_matches('/')))
+        };
+        let resource = Resource::builder()
+            .with_service_name(service_name.to_string())
+            .build();
+
+        let mut spans = SpanExporter::builder().with_http();
+        if let Some(url) = url("v1/traces") {
+            spans = spans.with_endpoint(url);
+        }
+        let spans = spans.build().map_err(|e| e.to_string())?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(spans)
+            .with_resource(resource.clone())
+            .build();
+
+        let mut metrics = MetricExporter::builder().with_http();
+        if let Some(url) = url("v1/metrics") {
+            metrics = metrics.with_endpoint(url);
+        }
+        let metrics = metrics.build().map_err(|e| e.to_string())?;
+        let meter_provider = SdkMeterProvider::builder()
+            .with_periodic_exporter(metrics)
+            .with_resource(resource)
+            .build();
+
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(tracer_provider.tracer("sqllog-analysis"))
+            .and_then(tracing_opentelemetry::MetricsLayer::new(
+                meter_provider.clone(),
+            ));
+        if let Ok(mut providers) = PROVIDERS.lock() {
+            *providers = Some((tracer_provider, meter_provider));
+        }
+        Ok(layer)
+    }
+
+    pub(super) fn shutdown() {
+        let Some((tracer, meter)) =
+            PROVIDERS.lock().ok().and_then(|mut p| p.take())
+        else {
+            return;
+        };
+        if let Err(e) = tracer.shutdown() {
+            eprintln!("警告: 导出剩余 span 失败: {e}");
+        }
+        if let Err(e) = meter.shutdown() {
+            eprintln!("警告: 导出剩余指标失败: {e}");
+        }
+    }
+}
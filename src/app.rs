@@ -38,14 +38,39 @@
 //! - **性能优化**：并行处理和内存效率优化
 //! - **监控友好**：丰富的日志和统计信息
 
-use sqllog_analysis::config::Config;
-use sqllog_analysis::database::DuckDbProvider;
+use sqllog_analysis::analysis::concurrency::DEFAULT_BUCKET_SECS;
+use sqllog_analysis::analysis::coverage::DEFAULT_GAP_SECS;
+use sqllog_analysis::analysis::indexes::DEFAULT_TOP_SUGGESTIONS;
+use sqllog_analysis::analysis::window::DEFAULT_WINDOW_SECS;
+use sqllog_analysis::analysis::{
+    AggregateQuery, AlertThresholds, AppAliases, AppWorkloadAnalyzer,
+    Classifier, ClassifyRuleConfig, ConcurrencyAnalyzer, CoverageAnalyzer,
+    DiffThresholds, FingerprintAggregator, IndexAdvisor, ProfileAnalyzer,
+    SecurityClass, SlidingWindow, SnapshotAggregator, StatementStats, compare,
+    diff,
+};
+use sqllog_analysis::config::RuntimeConfig;
 use sqllog_analysis::database::{
-    DatabaseProvider, ExportFormat, process_files_with_independent_databases,
+    AnalyzeRunner, COLD_TABLE, DiskFullError, ExportFormat, ExportManifest,
+    ExportReport, IndependentDatabaseStats, IndexReport, LoadOptions,
+    RunRecord, export_targets, load_saved_records, preflight,
+    process_files_with_independent_databases,
 };
-
+use sqllog_analysis::database::{DatabaseProvider, DuckDbProvider};
+use sqllog_analysis::exit_code::ExitCode;
+use sqllog_analysis::history::HistoryStore;
+use sqllog_analysis::jobs::{JobStore, run_jobs};
+use sqllog_analysis::notify::{self, RunSummary};
+use sqllog_analysis::retention::{self, RetentionPolicy};
+use sqllog_analysis::sqllog::inspect::DEFAULT_SAMPLE_BYTES;
+use sqllog_analysis::sqllog::{
+    ErrorBreaker, ExecTimeMs, FileFollower, Sqllog, count_records, inspect_file,
+};
+use std::collections::BTreeMap;
 use std::fs;
+use std::io::{BufWriter, Write};
 use std::path;
+use std::time::{Duration, SystemTime};
 
 /// 在指定目录中收集符合命名规则的 sqllog 日志文件。
 ///
@@ -101,21 +126,385 @@ fn collect_sqllog_files(sqllog_dir: &path::Path) -> Vec<path::PathBuf> {
     files
 }
 
-/// 程序主逻辑入口（由 `main` 调用），负责加载配置并触发文件扫描与解析。
-pub fn run() {
-    let runtime = Config::load();
+/// 将命令行给出的输入展开为文件列表：目录按 `dmsql_*.log` 规则扫描，文件原样返回
+fn input_files(input: path::PathBuf) -> Vec<path::PathBuf> {
+    if input.is_dir() { collect_sqllog_files(&input) } else { vec![input] }
+}
+
+/// 为本次导出生成清单文件；失败时仅记录错误，不影响已完成的导出。
+fn write_manifest(
+    manifest_path: &path::Path,
+    format: &ExportFormat,
+    report: &ExportReport,
+    stats: &IndependentDatabaseStats,
+) {
+    match ExportManifest::build(format, report, Some(stats))
+        .and_then(|manifest| manifest.write_to(manifest_path))
+    {
+        Ok(()) => log::info!("导出清单已写入: {}", manifest_path.display()),
+        Err(e) => log::error!("写入导出清单失败: {e}"),
+    }
+}
+
+/// 按配置导出解析结果；`export.format` 可列出多种格式，共用同一次解析。
+///
+/// 多种格式时各产物按格式替换输出路径的扩展名，导出清单也按格式分别写出
+/// （如 `manifest.csv.json`）。单个格式导出失败不影响其他格式。配置了
+/// `export.ddl_out_path` 时在各格式之后另外写出 DDL 审计文件。
+///
+/// 返回导出结果：全部成功（或未启用导出）为 `Success`，部分格式失败为
+/// `PartialSuccess`，没有任何格式成功为 `ExportFailed`。
+fn export_results(
+    runtime: &RuntimeConfig,
+    stats: &IndependentDatabaseStats,
+) -> ExitCode {
+    if !runtime.export_enabled {
+        log::debug!("导出功能未启用");
+        return ExitCode::Success;
+    }
+    let Some(export_path) = &runtime.export_out_path else {
+        log::warn!("导出功能已启用，但未指定导出路径");
+        return ExitCode::Success;
+    };
+    let formats = match ExportFormat::parse_list(&runtime.export_format) {
+        Ok(formats) => formats,
+        Err(e) => {
+            log::error!("{e}");
+            return ExitCode::InvalidConfig;
+        }
+    };
+
+    log::info!("开始导出数据...");
+    // 创建数据库提供者进行导出
+    let provider = match DuckDbProvider::new(runtime) {
+        Ok(provider) => provider,
+        Err(e) => {
+            log::error!("创建数据库提供者失败: {e}");
+            return ExitCode::ExportFailed;
+        }
+    };
+    export_from(&provider, runtime, export_path, &formats, stats)
+}
+
+/// 从已写入记录的 `provider` 按 `formats` 逐一导出，见 [`export_results`]
+fn export_from(
+    provider: &DuckDbProvider,
+    runtime: &RuntimeConfig,
+    export_path: &path::Path,
+    formats: &[ExportFormat],
+    stats: &IndependentDatabaseStats,
+) -> ExitCode {
+    let multiple = formats.len() > 1;
+    // 已完整写出的导出文件，磁盘空间不足时一并报告
+    let mut completed = Vec::new();
+    let (mut succeeded, mut failed) = (0usize, 0usize);
+    for (format, path) in export_targets(export_path, formats) {
+        let path_str = path.to_string_lossy();
+        match provider.export_with_options(
+            format.clone(),
+            &path_str,
+            &runtime.export_options,
+        ) {
+            Ok(report) => {
+                succeeded += 1;
+                let written =
+                    report.artifacts.first().map_or(&*path_str, |a| &a.path);
+                log::info!("数据导出完成: {written}");
+                completed.extend(
+                    report
+                        .artifacts
+                        .iter()
+                        .map(|a| path::PathBuf::from(&a.path)),
+                );
+                if let Some(manifest_path) =
+                    &runtime.export_options.manifest_path
+                {
+                    let manifest_path = if multiple {
+                        per_format_manifest_path(manifest_path, &format)
+                    } else {
+                        manifest_path.clone()
+                    };
+                    write_manifest(&manifest_path, &format, &report, stats);
+                }
+            }
+            Err(e) => match e.downcast::<DiskFullError>() {
+                Ok(mut disk_full) => {
+                    // 磁盘已满时后续格式必然失败，停止导出并报告可用的输出
+                    completed.append(&mut disk_full.completed);
+                    disk_full.completed = completed;
+                    log::error!("{disk_full}");
+                    return if succeeded > 0 {
+                        ExitCode::PartialSuccess
+                    } else {
+                        ExitCode::ExportFailed
+                    };
+                }
+                Err(e) => {
+                    failed += 1;
+                    log::error!("{} 导出失败: {e}", format.extension());
+                }
+            },
+        }
+    }
+    if let Some(ddl_path) = &runtime.export_options.ddl_out_path {
+        match provider.export_ddl_audit(
+            &ddl_path.to_string_lossy(),
+            &runtime.export_options,
+        ) {
+            Ok(report) => {
+                succeeded += 1;
+                log::info!(
+                    "DDL 审计文件导出完成: {}（{} 条）",
+                    report.artifacts.first().map_or("", |a| a.path.as_str()),
+                    report.records_exported
+                );
+            }
+            Err(e) => {
+                failed += 1;
+                log::error!("DDL 审计文件导出失败: {e}");
+            }
+        }
+    }
+    match (succeeded, failed) {
+        (_, 0) => ExitCode::Success,
+        (0, _) => ExitCode::ExportFailed,
+        _ => ExitCode::PartialSuccess,
+    }
+}
+
+/// 解析错误数超过 `--fail-on-errors` 阈值时返回 `ParseErrorsExceeded`
+fn check_parse_errors(
+    stats: &IndependentDatabaseStats,
+    fail_on_errors: Option<usize>,
+) -> ExitCode {
+    match fail_on_errors {
+        Some(limit) if stats.parse_errors > limit => {
+            log::error!(
+                "解析错误 {} 条，超过 --fail-on-errors 阈值 {limit}",
+                stats.parse_errors
+            );
+            ExitCode::ParseErrorsExceeded
+        }
+        _ => ExitCode::Success,
+    }
+}
+
+/// 多格式导出时的清单路径：在原扩展名前插入格式名
+fn per_format_manifest_path(
+    manifest_path: &path::Path,
+    format: &ExportFormat,
+) -> path::PathBuf {
+    let ext = manifest_path
+        .extension()
+        .map_or_else(String::new, |e| format!(".{}", e.to_string_lossy()));
+    manifest_path.with_extension(format!("{}{ext}", format.extension()))
+}
+
+/// 导出完成后按 `database.split_cold` 把冷字段移出 sqllogs 表
+fn split_cold_fields(runtime: &RuntimeConfig) -> ExitCode {
+    if !runtime.db_split_cold || runtime.use_in_memory {
+        return ExitCode::Success;
+    }
+    match DuckDbProvider::new(runtime).and_then(|mut p| p.split_cold_fields()) {
+        Ok(moved) => {
+            log::info!(
+                "已将 {moved} 条记录的 description/plan 移入 {COLD_TABLE}"
+            );
+            ExitCode::Success
+        }
+        Err(e) => {
+            log::error!("拆分冷字段失败: {e:#}");
+            ExitCode::Failure
+        }
+    }
+}
+
+/// 按 `database.record_runs` 向 `runs` 表写入本次运行的元数据；
+/// 写入失败只记录日志
+fn record_run(
+    runtime: &RuntimeConfig,
+    files: &[path::PathBuf],
+    stats: &IndependentDatabaseStats,
+    started_at: chrono::DateTime<chrono::Local>,
+) {
+    if !runtime.db_record_runs || runtime.use_in_memory {
+        return;
+    }
+    let recorded = RunRecord::build(runtime, files, stats, started_at)
+        .and_then(|run| {
+            DuckDbProvider::new(runtime)?.record_run(&run)?;
+            Ok(run.run_id)
+        });
+    match recorded {
+        Ok(run_id) => log::info!("已记录运行元数据: {run_id}"),
+        Err(e) => log::warn!("写入运行元数据失败: {e:#}"),
+    }
+}
+
+/// 按 `[notify]` 配置推送运行摘要；通知失败只记录日志
+fn notify_run(
+    runtime: &RuntimeConfig,
+    files: &[path::PathBuf],
+    stats: &IndependentDatabaseStats,
+) {
+    let Some(config) = &runtime.notify else { return };
+    let slow_queries = config.max_slow_queries.and_then(|_| {
+        DuckDbProvider::new(runtime)
+            .and_then(|p| p.count_slow_queries(config.slow_query_ms))
+            .map_err(|e| log::warn!("统计慢语句失败: {e:#}"))
+            .ok()
+    });
+    let summary = RunSummary {
+        files: stats.files_processed,
+        records: stats.records_inserted,
+        parse_errors: stats.parse_errors,
+        slow_queries,
+    };
+    for breach in notify::breaches(config, &summary) {
+        log::warn!("告警: {breach}");
+    }
+    let Some(body) = notify::payload(config, &summary) else { return };
+    #[cfg(feature = "notify")]
+    {
+        let key =
+            sqllog_analysis::database::run_key(files, &runtime.parse_options())
+                .map(|hash| notify::idempotency_key(&hash, 0))
+                .map_err(|e| log::warn!("计算通知幂等键失败: {e:#}"))
+                .ok();
+        match notify::send(config, &body, key.as_deref()) {
+            Ok(()) => log::info!("已发送运行通知"),
+            Err(e) => log::warn!("{e:#}"),
+        }
+    }
+    #[cfg(not(feature = "notify"))]
+    {
+        let _ = (body, files);
+        log::warn!(
+            "配置了 notify.webhook_url，但程序未启用 notify 特性，跳过通知"
+        );
+    }
+}
+
+/// 通过作业队列导入：已完成的文件跳过，中断遗留的作业恢复后继续；
+/// 存在失败作业时结果为部分成功
+fn run_with_jobs(
+    runtime: &RuntimeConfig,
+    state_path: &path::Path,
+    files: &[path::PathBuf],
+    fail_on_errors: Option<usize>,
+    started_at: chrono::DateTime<chrono::Local>,
+) -> ExitCode {
+    let prepared = JobStore::open(state_path).and_then(|store| {
+        let store = store.with_max_retries(runtime.jobs_max_retries);
+        let recovered = store.recover()?;
+        if recovered > 0 {
+            log::warn!("恢复了 {recovered} 个上次中断的作业");
+        }
+        let mut added = 0usize;
+        for file in files {
+            added += usize::from(store.enqueue(file)?);
+        }
+        log::info!("新增 {added} 个作业，状态库: {}", state_path.display());
+        Ok(store)
+    });
+    let store = match prepared {
+        Ok(store) => store,
+        Err(e) => {
+            log::error!("初始化作业队列失败: {e:#}");
+            return ExitCode::Failure;
+        }
+    };
+
+    let result = run_jobs(&store, runtime);
+    let jobs = match store.summary() {
+        Ok(s) => {
+            log::info!(
+                "作业状态: 完成 {}，失败 {}，待处理 {}",
+                s.done,
+                s.failed,
+                s.pending
+            );
+            if s.failed > 0 {
+                ExitCode::PartialSuccess
+            } else {
+                ExitCode::Success
+            }
+        }
+        Err(e) => {
+            log::warn!("读取作业状态失败: {e:#}");
+            ExitCode::Success
+        }
+    };
+    match result {
+        Ok(stats) => {
+            log::info!(
+                "本次导入 {} 个文件，插入 {} 条记录",
+                stats.files_processed,
+                stats.records_inserted
+            );
+            let exported =
+                export_results(runtime, &stats).or(split_cold_fields(runtime));
+            record_run(runtime, files, &stats, started_at);
+            notify_run(runtime, files, &stats);
+            jobs.or(exported).or(check_parse_errors(&stats, fail_on_errors))
+        }
+        Err(e) if ErrorBreaker::tripped(&e) => breaker_abort(&e),
+        Err(e) => {
+            log::error!("作业处理中止: {e:#}");
+            ExitCode::Failure
+        }
+    }
+}
+
+/// 解析错误率熔断中止运行：已写入数据库的记录不完整，不再导出
+fn breaker_abort(e: &anyhow::Error) -> ExitCode {
+    log::error!("{e:#}");
+    log::error!(
+        "本次运行已因解析错误率熔断中止：数据库中已写入的记录不完整（部分输出），未执行导出；请确认输入文件格式或调整 sqllog.breaker_max_error_ratio"
+    );
+    ExitCode::ParseErrorsExceeded
+}
+
+/// 程序主逻辑入口（由 `main` 调用），负责触发文件扫描、解析与导出。
+///
+/// 返回本次运行的退出码（见 [`ExitCode`]）；`fail_on_errors` 为解析错误数
+/// 的上限，超过时结果为 `ParseErrorsExceeded`。
+pub fn run(runtime: &RuntimeConfig, fail_on_errors: Option<usize>) -> ExitCode {
+    let started_at = chrono::Local::now();
     if let Some(sqllog_dir) = runtime.sqllog_dir.clone() {
-        let files = collect_sqllog_files(&sqllog_dir);
+        let mut files = collect_sqllog_files(&sqllog_dir);
 
         if files.is_empty() {
             log::warn!("在 {} 中未找到 dmsql_*.log 文件", sqllog_dir.display());
-            return;
+            return ExitCode::Success;
         }
 
         log::info!("发现 {} 个待处理文件", files.len());
+        runtime.sqllog_file_order.apply(&mut files);
+        let _span =
+            tracing::info_span!("sqllog.run", files = files.len()).entered();
+        log::debug!(
+            "文件处理顺序（{:?}）: {files:?}",
+            runtime.sqllog_file_order
+        );
+
+        if let Err(e) = preflight(runtime) {
+            log::error!("预检失败: {e:#}");
+            return ExitCode::Failure;
+        }
+
+        if let Some(state_path) = &runtime.jobs_state_path {
+            return run_with_jobs(
+                runtime,
+                state_path,
+                &files,
+                fail_on_errors,
+                started_at,
+            );
+        }
 
         // 使用独立数据库处理所有文件（每个线程独立数据库，最后合并）
-        match process_files_with_independent_databases(&files, &runtime) {
+        match process_files_with_independent_databases(&files, runtime) {
             Ok(stats) => {
                 log::info!("所有文件处理完成！统计信息:");
                 log::info!("  - 处理记录数: {}", stats.records_processed);
@@ -125,58 +514,1231 @@ pub fn run() {
                     "  - 临时数据库数: {}",
                     stats.temp_databases_created
                 );
+                if runtime.sqllog_cache_dir.is_some() {
+                    log::info!(
+                        "  - 命中解析缓存的文件数: {}",
+                        stats.files_from_cache
+                    );
+                }
+                if runtime.db_resume {
+                    log::info!(
+                        "  - 断点续写跳过的文件数: {}",
+                        stats.files_resumed
+                    );
+                }
+                log::info!(
+                    "  - 读取字节数: {}，处理耗时: {:?}",
+                    stats.total_bytes(),
+                    stats.total_elapsed()
+                );
 
-                // 如果启用了导出功能，执行数据导出
-                if runtime.export_enabled {
-                    if let Some(export_path) = &runtime.export_out_path {
-                        log::info!("开始导出数据...");
-
-                        // 创建数据库提供者进行导出
-                        match DuckDbProvider::new(&runtime) {
-                            Ok(provider) => {
-                                // 解析导出格式
-                                if let Ok(format) = runtime
-                                    .export_format
-                                    .parse::<ExportFormat>()
-                                {
-                                    let path_str =
-                                        export_path.to_string_lossy();
-                                    match provider
-                                        .export_data(format, &path_str)
-                                    {
-                                        Ok(()) => {
-                                            log::info!(
-                                                "数据导出完成: {path_str}"
-                                            );
-                                        }
-                                        Err(e) => {
-                                            log::error!("数据导出失败: {e}");
-                                        }
-                                    }
-                                } else {
-                                    log::error!(
-                                        "不支持的导出格式: {}",
-                                        runtime.export_format
-                                    );
-                                }
-                            }
-                            Err(e) => {
-                                log::error!("创建数据库提供者失败: {e}");
-                            }
-                        }
-                    } else {
-                        log::warn!("导出功能已启用，但未指定导出路径");
+                let exported = export_results(runtime, &stats)
+                    .or(split_cold_fields(runtime));
+                record_run(runtime, &files, &stats, started_at);
+                notify_run(runtime, &files, &stats);
+                exported.or(check_parse_errors(&stats, fail_on_errors))
+            }
+            Err(e) if ErrorBreaker::tripped(&e) => breaker_abort(&e),
+            Err(e) => {
+                log::error!("处理文件失败: {e}");
+                ExitCode::Failure
+            }
+        }
+    } else {
+        log::warn!("未配置 sqllog_dir，跳过解析");
+        ExitCode::Success
+    }
+}
+
+/// `analyze` 子命令的参数
+#[derive(Debug, Default)]
+struct AnalyzeArgs {
+    select: String,
+    group_by: String,
+    having: String,
+    order_by: String,
+    limit: Option<usize>,
+    input: Option<path::PathBuf>,
+}
+
+impl AnalyzeArgs {
+    /// 解析 `analyze` 之后的参数。
+    ///
+    /// 每个选项的取值为其后直到下一个 `--` 选项之前的所有参数（以空格连接），
+    /// 因此 `--order-by 2 desc` 无需加引号。
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let mut parsed = Self::default();
+        let mut iter = args.iter().peekable();
+        while let Some(flag) = iter.next() {
+            let mut parts = Vec::new();
+            while let Some(v) = iter.next_if(|a| !a.starts_with("--")) {
+                parts.push(v.as_str());
+            }
+            let value = parts.join(" ");
+            if value.is_empty() {
+                return Err(format!("选项 {flag} 缺少取值"));
+            }
+            match flag.as_str() {
+                "--select" => parsed.select = value,
+                "--group-by" => parsed.group_by = value,
+                "--having" => parsed.having = value,
+                "--order-by" => parsed.order_by = value,
+                "--limit" => {
+                    let n = value.parse::<usize>().map_err(|_| {
+                        format!("--limit 必须为非负整数: {value}")
+                    })?;
+                    parsed.limit = Some(n);
+                }
+                "--input" => parsed.input = Some(path::PathBuf::from(value)),
+                other => return Err(format!("未知选项: {other}")),
+            }
+        }
+        if parsed.select.is_empty() {
+            return Err("缺少 --select".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+/// `analyze` 子命令：对日志做分组聚合并打印结果表。
+///
+/// 聚合状态超过 `[analyze].memory_limit_mb` 时自动改用临时 `DuckDB` 数据库。
+/// 输入为 `--input` 指定的文件或目录，未指定时使用配置中的 `sqllog_dir`。
+/// 参数或查询有误时退出码为 2。
+pub fn run_analyze(runtime: &RuntimeConfig, args: &[String]) {
+    let args = AnalyzeArgs::parse(args).unwrap_or_else(|e| {
+        eprintln!("analyze 参数错误: {e}");
+        std::process::exit(2);
+    });
+    let query = AggregateQuery::parse(
+        &args.select,
+        &args.group_by,
+        &args.having,
+        &args.order_by,
+    )
+    .map(|q| q.with_limit(args.limit))
+    .unwrap_or_else(|e| {
+        eprintln!("analyze 查询错误: {e}");
+        std::process::exit(2);
+    });
+
+    let Some(input) = args.input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("analyze 需要 --input 或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+    let files = input_files(input);
+
+    match AnalyzeRunner::new(runtime, query).run(&files) {
+        Ok(outcome) => {
+            if outcome.parse_errors > 0 {
+                log::warn!("有 {} 条记录解析失败", outcome.parse_errors);
+            }
+            log::info!(
+                "analyze 完成: {} 个文件，{} 条记录，{} 行结果{}",
+                files.len(),
+                outcome.records,
+                outcome.result.rows.len(),
+                if outcome.spilled {
+                    "（使用临时数据库）"
+                } else {
+                    ""
+                }
+            );
+            print!("{}", outcome.result);
+        }
+        Err(e) => {
+            log::error!("analyze 失败: {e:#}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// 解析一组文件并按 SQL 指纹聚合
+fn fingerprint_stats(
+    runtime: &RuntimeConfig,
+    files: &[path::PathBuf],
+) -> BTreeMap<String, StatementStats> {
+    let options = runtime.parse_options();
+    let mut aggregator = FingerprintAggregator::new();
+    for file in files {
+        let result = Sqllog::parse_with_options(
+            file,
+            &options,
+            |records| aggregator.observe(records),
+            |_| {},
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    aggregator.finish()
+}
+
+/// 解析百分比形式的阈值参数（如 `20` 表示 20%）
+fn parse_percent(flag: &str, value: Option<&String>) -> f64 {
+    match value.map(|v| v.parse::<f64>()) {
+        Some(Ok(v)) if v >= 0.0 => v / 100.0,
+        _ => {
+            eprintln!("diff 参数错误: {flag} 需要非负数值（百分比）");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// `diff` 子命令：按 SQL 指纹对比基线与当前两组日志并打印报告。
+///
+/// 用法：`diff <baseline> <current> [--p95-threshold 20] [--calls-threshold 50]
+/// [--min-delta-ms 1] [--json]`，两侧各为文件或目录，阈值为百分比。
+pub fn run_diff(runtime: &RuntimeConfig, args: &[String]) {
+    let mut inputs = Vec::new();
+    let mut thresholds = DiffThresholds::default();
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--p95-threshold" => {
+                thresholds.p95_ratio = parse_percent(arg, iter.next());
+            }
+            "--calls-threshold" => {
+                thresholds.calls_ratio = parse_percent(arg, iter.next());
+            }
+            "--min-delta-ms" => {
+                let Some(Ok(ms)) = iter.next().map(|v| v.parse::<i64>()) else {
+                    eprintln!("diff 参数错误: --min-delta-ms 需要整数毫秒");
+                    std::process::exit(2);
+                };
+                thresholds.p95_min_delta = ExecTimeMs::new(ms);
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("diff 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => inputs.push(path::PathBuf::from(arg)),
+        }
+    }
+    let [baseline, current] = <[path::PathBuf; 2]>::try_from(inputs)
+        .unwrap_or_else(|_| {
+            eprintln!("用法: diff <baseline> <current> [选项]");
+            std::process::exit(2);
+        });
+
+    let baseline = fingerprint_stats(runtime, &input_files(baseline));
+    let current = fingerprint_stats(runtime, &input_files(current));
+    let report = diff(&baseline, &current, &thresholds);
+    log::info!(
+        "diff 完成: 基线 {} 类语句，当前 {} 类语句",
+        baseline.len(),
+        current.len()
+    );
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化对比报告失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+}
+
+/// `coverage` 子命令：检测 `occurrence_time` 中超过阈值的空档并按小时报告
+/// 日志覆盖率，用于发现 sqllog 被关闭或数据库宕机的时段。
+///
+/// 用法：`coverage [文件或目录] [--gap-secs 300] [--json]`，未给出输入时
+/// 使用配置中的 `sqllog_dir`。
+pub fn run_coverage(runtime: &RuntimeConfig, args: &[String]) {
+    let mut input = None;
+    let mut gap_secs = DEFAULT_GAP_SECS;
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--gap-secs" => {
+                let Some(Ok(secs)) = iter.next().map(|v| v.parse::<u32>())
+                else {
+                    eprintln!("coverage 参数错误: --gap-secs 需要正整数秒");
+                    std::process::exit(2);
+                };
+                if secs == 0 {
+                    eprintln!("coverage 参数错误: --gap-secs 不能为 0");
+                    std::process::exit(2);
+                }
+                gap_secs = secs;
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("coverage 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    let Some(input) = input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("coverage 需要输入路径或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+
+    let options = runtime.parse_options();
+    let mut analyzer = CoverageAnalyzer::new(gap_secs);
+    for file in input_files(input) {
+        let result = Sqllog::parse_with_options(
+            &file,
+            &options,
+            |records| analyzer.observe(records),
+            |_| {},
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    let report = analyzer.report();
+    log::info!(
+        "coverage 完成: {} 个空档，整体覆盖率 {:.1}%",
+        report.gaps.len(),
+        report.coverage * 100.0
+    );
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化覆盖报告失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+}
+
+/// `concurrency` 子命令：按时间桶重建活跃会话数，并报告各用户与 appname
+/// 的并发峰值。
+///
+/// 用法：`concurrency [文件或目录] [--bucket-secs 60] [--json]`，未给出
+/// 输入时使用配置中的 `sqllog_dir`。
+pub fn run_concurrency(runtime: &RuntimeConfig, args: &[String]) {
+    let mut input = None;
+    let mut bucket_secs = DEFAULT_BUCKET_SECS;
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--bucket-secs" => {
+                bucket_secs = flag_value("concurrency", arg, iter.next());
+                if bucket_secs == 0 {
+                    eprintln!("concurrency 参数错误: --bucket-secs 不能为 0");
+                    std::process::exit(2);
+                }
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("concurrency 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    let Some(input) = input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("concurrency 需要输入路径或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+
+    let options = runtime.parse_options();
+    let mut analyzer = ConcurrencyAnalyzer::new(bucket_secs);
+    for file in input_files(input) {
+        let result = Sqllog::parse_with_options(
+            &file,
+            &options,
+            |records| analyzer.observe(records),
+            |_| {},
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    let report = analyzer.report();
+    log::info!(
+        "concurrency 完成: {} 个会话，并发峰值 {}",
+        report.sessions,
+        report.peak.as_ref().map_or(0, |p| p.active_sessions)
+    );
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化并发报告失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+}
+
+/// `profile` 子命令：统计各字段的缺失率、取值个数、时间范围与 description
+/// 长度分布，帮助在设计导出前了解一批新日志。
+///
+/// 用法：`profile [文件或目录] [--json]`，未给出输入时使用配置中的
+/// `sqllog_dir`。
+pub fn run_profile(runtime: &RuntimeConfig, args: &[String]) {
+    let mut input = None;
+    let mut json = false;
+    for arg in args {
+        match arg.as_str() {
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("profile 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    let Some(input) = input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("profile 需要输入路径或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+
+    let options = runtime.parse_options();
+    let mut analyzer = ProfileAnalyzer::new();
+    let mut parse_errors = 0usize;
+    for file in input_files(input) {
+        let result = Sqllog::parse_with_options(
+            &file,
+            &options,
+            |records| analyzer.observe(records),
+            |errors| parse_errors += errors.len(),
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    let report = analyzer.report();
+    log::info!(
+        "profile 完成: {} 条记录，{parse_errors} 个解析错误",
+        report.records
+    );
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化数据画像失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+}
+
+/// `apps` 子命令：按 appname 汇总各应用的调用次数、执行时间 p50/p95/p99
+/// 与执行时间合计最高的 SQL 指纹，appname 按 `[apps.aliases]` 统一。
+///
+/// 用法：`apps [文件或目录] [--top N] [--json]`，未给出输入时使用配置中的
+/// `sqllog_dir`；`--top` 覆盖 `apps.top_fingerprints`。
+pub fn run_apps(runtime: &RuntimeConfig, args: &[String]) {
+    let mut input = None;
+    let mut top = runtime.apps_top_fingerprints;
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--top" => top = flag_value("apps", arg, iter.next()),
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("apps 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    let Some(input) = input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("apps 需要输入路径或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+
+    let options = runtime.parse_options();
+    let mut analyzer =
+        AppWorkloadAnalyzer::new(AppAliases::new(&runtime.apps_aliases))
+            .with_top(top);
+    let mut parse_errors = 0usize;
+    for file in input_files(input) {
+        let result = Sqllog::parse_with_options(
+            &file,
+            &options,
+            |records| analyzer.observe(records),
+            |errors| parse_errors += errors.len(),
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    let report = analyzer.report();
+    log::info!(
+        "apps 完成: {} 条记录，{} 个应用，{parse_errors} 个解析错误",
+        report.records,
+        report.apps.len()
+    );
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化应用负载报告失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+}
+
+/// `indexes` 子命令：从 SQL 指纹的 `WHERE`/`ON` 条件中提取与字面量比较的
+/// 列，为引用的源表给出索引建议，按受益语句的执行时间合计排序。
+///
+/// 用法：`indexes [文件或目录] [--top N] [--json]`，未给出输入时使用配置中的
+/// `sqllog_dir`；`--top` 默认 10。
+pub fn run_indexes(runtime: &RuntimeConfig, args: &[String]) {
+    let mut input = None;
+    let mut top = DEFAULT_TOP_SUGGESTIONS;
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--top" => top = flag_value("indexes", arg, iter.next()),
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("indexes 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    let Some(input) = input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("indexes 需要输入路径或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+
+    let options = runtime.parse_options();
+    let mut advisor = IndexAdvisor::new().with_top(top);
+    let mut parse_errors = 0usize;
+    for file in input_files(input) {
+        let result = Sqllog::parse_with_options(
+            &file,
+            &options,
+            |records| advisor.observe(records),
+            |errors| parse_errors += errors.len(),
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    let report = advisor.report();
+    log::info!(
+        "indexes 完成: {} 条记录，{} 个指纹，{} 条建议，{parse_errors} 个解析错误",
+        report.records,
+        report.statements,
+        report.suggestions.len()
+    );
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化索引建议失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+}
+
+/// `--rules` 指定的分级规则文件，与配置中的 `[classify]` 节格式相同
+#[derive(serde::Deserialize)]
+struct ClassifyRulesFile {
+    rules: Vec<ClassifyRuleConfig>,
+}
+
+/// `classify` 子命令：按 `[[classify.rules]]` 规则为每条记录做安全分级，
+/// 输出各级别记录数与各规则命中次数，并可把 forbidden 记录逐条写出。
+///
+/// 用法：`classify [文件或目录] [--rules 规则文件] [--forbidden-out 路径] [--json]`。
+/// 给出 `--rules` 时改用该文件中的规则；forbidden 记录写出为 JSONL，每行
+/// 含来源文件、行号、命中的规则与记录的主要字段。
+pub fn run_classify(runtime: &RuntimeConfig, args: &[String]) {
+    let mut input = None;
+    let mut rules = runtime.classify_rules.clone();
+    let mut forbidden_out = runtime.classify_forbidden_out.clone();
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--rules" => {
+                let path: path::PathBuf =
+                    flag_value("classify", arg, iter.next());
+                rules = fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| {
+                        toml::from_str::<ClassifyRulesFile>(&text)
+                            .map_err(|e| e.to_string())
+                    })
+                    .map(|file| file.rules)
+                    .unwrap_or_else(|e| {
+                        eprintln!("无法读取规则文件 {}: {e}", path.display());
+                        std::process::exit(2);
+                    });
+            }
+            "--forbidden-out" => {
+                forbidden_out = Some(flag_value("classify", arg, iter.next()));
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("classify 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    let mut classifier = Classifier::from_configs(&rules).unwrap_or_else(|e| {
+        eprintln!("classify 规则无效: {e}");
+        std::process::exit(2);
+    });
+    if classifier.rules().is_empty() {
+        eprintln!("classify 需要 --rules 或配置 [[classify.rules]]");
+        std::process::exit(2);
+    }
+    let Some(input) = input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("classify 需要输入路径或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+    let mut writer = forbidden_out.as_ref().map(|out| {
+        fs::File::create(out).map(BufWriter::new).unwrap_or_else(|e| {
+            log::error!("无法创建 forbidden 记录文件 {}: {e}", out.display());
+            std::process::exit(1);
+        })
+    });
+
+    let options = runtime.parse_options();
+    let mut write_failed = false;
+    for file in input_files(input) {
+        let file_name = file.to_string_lossy().to_string();
+        let result = Sqllog::parse_with_options(
+            &file,
+            &options,
+            |records| {
+                classifier.observe_with(records, |record, class, names| {
+                    let Some(writer) = writer.as_mut() else { return };
+                    if class != SecurityClass::Forbidden || write_failed {
+                        return;
+                    }
+                    let line = serde_json::json!({
+                        "file": file_name,
+                        "line": record.line,
+                        "rules": names,
+                        "occurrence_time": record.occurrence_time,
+                        "user": record.user,
+                        "ip": record.ip,
+                        "appname": record.appname,
+                        "session": record.session,
+                        "sql_type": record.sql_type,
+                        "description": record.description,
+                    });
+                    if let Err(e) = writeln!(writer, "{line}") {
+                        log::error!("写入 forbidden 记录失败: {e}");
+                        write_failed = true;
+                    }
+                });
+            },
+            |_| {},
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    if let Some(mut writer) = writer {
+        if let Err(e) = writer.flush() {
+            log::error!("写入 forbidden 记录失败: {e}");
+            write_failed = true;
+        }
+    }
+    let report = classifier.report();
+    log::info!(
+        "classify 完成: {} 条记录，forbidden {} 条",
+        report.records_scanned,
+        report.by_class.get(&SecurityClass::Forbidden).copied().unwrap_or(0)
+    );
+
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化分级报告失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+    if write_failed {
+        std::process::exit(1);
+    }
+}
+
+/// `cleanup` 子命令：按保留策略清理输出目录中的导出产物，以导出清单为单位
+/// 保留或删除每次运行（见 [`sqllog_analysis::retention`]）。
+///
+/// 用法：`cleanup [目录] [--max-age-days N] [--keep-last K] [--dry-run] [--json]`。
+/// 未给出目录时使用 `export.out_path` 所在的目录。
+pub fn run_cleanup(runtime: &RuntimeConfig, args: &[String]) {
+    let mut dir = None;
+    let mut policy = RetentionPolicy::default();
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--max-age-days" => {
+                let days: u64 = flag_value("cleanup", arg, iter.next());
+                policy.max_age =
+                    Some(Duration::from_secs(days.saturating_mul(24 * 3600)));
+            }
+            "--keep-last" => {
+                policy.keep_last =
+                    Some(flag_value("cleanup", arg, iter.next()));
+            }
+            "--dry-run" => policy.dry_run = true,
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("cleanup 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => dir = Some(path::PathBuf::from(arg)),
+        }
+    }
+    if policy.max_age.is_none() && policy.keep_last.is_none() {
+        eprintln!("cleanup 需要 --max-age-days 或 --keep-last");
+        std::process::exit(2);
+    }
+    let default_dir = || {
+        let out = runtime.export_out_path.as_ref()?;
+        let parent = out.parent().unwrap_or_else(|| path::Path::new(""));
+        Some(if parent.as_os_str().is_empty() {
+            path::PathBuf::from(".")
+        } else {
+            parent.to_path_buf()
+        })
+    };
+    let Some(dir) = dir.or_else(default_dir) else {
+        eprintln!("cleanup 需要目录参数或配置 export.out_path");
+        std::process::exit(2);
+    };
+
+    let report = retention::cleanup(&dir, &policy, SystemTime::now())
+        .unwrap_or_else(|e| {
+            log::error!("清理 {} 失败: {e:#}", dir.display());
+            std::process::exit(1);
+        });
+    log::info!(
+        "cleanup 完成: 删除 {} 次运行、{} 个文件",
+        report.runs_removed,
+        report.removed.len()
+    );
+    if json {
+        match serde_json::to_string_pretty(&report) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化清理结果失败: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        print!("{report}");
+    }
+}
+
+/// `convert` 子命令：读取此前导出的 JSON/Parquet 记录，跳过解析直接交给
+/// 导出器，用于把已有结果转换为其他格式或写入 DuckDB 库。
+///
+/// 用法：`convert <记录文件> [--db 库文件] [--format 格式] [--output 路径]
+/// [--allow-extra-fields] [--backfill]`
+///
+/// JSON 记录逐条按 sqllogs 表结构校验，缺少字段、类型不符或存在未知字段时
+/// 报错退出；`--allow-extra-fields` 忽略未知字段。记录默认载入内存库；给出 `--db` 时写入该库文件（追加到已有的 sqllogs
+/// 表）。导出格式与路径取 `--format`/`--output`，否则取配置中的 `export`
+/// 节；只给出 `--db` 时不导出文件。
+pub fn run_convert(runtime: &RuntimeConfig, args: &[String]) -> ExitCode {
+    let mut input = None;
+    let mut db = None;
+    let mut options = LoadOptions::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            // 已在入口处应用到运行时配置
+            "--format" | "--output" | "--compress" => {
+                iter.next();
+            }
+            "--backfill" => {}
+            "--db" => {
+                db = Some(flag_value::<String>("convert", arg, iter.next()))
+            }
+            "--allow-extra-fields" => options.allow_extra_fields = true,
+            other if other.starts_with("--") => {
+                eprintln!("convert 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    let Some(input) = input else {
+        eprintln!("convert 需要记录文件参数");
+        std::process::exit(2);
+    };
+    let mut runtime = runtime.clone();
+    if let Some(db) = db {
+        runtime.db_path = db;
+        runtime.use_in_memory = false;
+    } else if !runtime.export_enabled {
+        eprintln!("convert 需要 --db，或通过 --format/--output 指定导出目标");
+        std::process::exit(2);
+    } else {
+        runtime.use_in_memory = true;
+    }
+
+    // 先校验导出目标，避免载入完成后才发现格式无效
+    let target = if runtime.export_enabled {
+        let Some(export_path) = runtime.export_out_path.clone() else {
+            eprintln!("convert 需要 --output 或配置 export.out_path");
+            std::process::exit(2);
+        };
+        match ExportFormat::parse_list(&runtime.export_format) {
+            Ok(formats) => Some((export_path, formats)),
+            Err(e) => {
+                eprintln!("配置错误: {e}");
+                std::process::exit(2);
+            }
+        }
+    } else {
+        None
+    };
+
+    let started = std::time::Instant::now();
+    let mut provider = match DuckDbProvider::new(&runtime) {
+        Ok(provider) => provider,
+        Err(e) => {
+            log::error!("创建数据库提供者失败: {e:#}");
+            return ExitCode::Failure;
+        }
+    };
+    if let Some(chunk_size) = runtime.sqllog_chunk_size.filter(|&n| n > 0) {
+        options.batch_size = chunk_size;
+    }
+    let loaded = (|| {
+        provider.initialize()?;
+        let loaded = load_saved_records(&mut provider, &input, &options)?;
+        provider.finalize_schema()?;
+        anyhow::Ok(loaded)
+    })();
+    let loaded = match loaded {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            log::error!("载入记录失败: {e:#}");
+            return ExitCode::Failure;
+        }
+    };
+    log::info!(
+        "已载入 {loaded} 条记录: {}，耗时 {:?}",
+        input.display(),
+        started.elapsed()
+    );
+
+    let Some((export_path, formats)) = target else {
+        return ExitCode::Success;
+    };
+    let records = usize::try_from(loaded).unwrap_or(usize::MAX);
+    let stats = IndependentDatabaseStats {
+        records_processed: records,
+        records_inserted: records,
+        files_processed: 1,
+        ..Default::default()
+    };
+    export_from(&provider, &runtime, &export_path, &formats, &stats)
+}
+
+/// `history` 子命令：把日志的指纹与用户统计保存为快照，或与最近一次快照
+/// 对比，用于在不保留原始记录的情况下按天观察趋势。
+///
+/// 用法：
+/// - `history save [文件或目录] [--label 标签]`
+/// - `history compare [文件或目录] [--save] [--label 标签] [--json]`
+/// - `history list [--json]`
+///
+/// 历史库取 `--state`，否则取配置中的 `history.state_path`；未给出输入时
+/// 使用配置中的 `sqllog_dir`；标签默认为当天日期。
+pub fn run_history(runtime: &RuntimeConfig, args: &[String]) {
+    let action = args.first().map(String::as_str);
+    let mut input = None;
+    let mut state = runtime.history_state_path.clone();
+    let mut label = chrono::Local::now().format("%Y-%m-%d").to_string();
+    let mut save = action == Some("save");
+    let mut json = false;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--state" => state = Some(flag_value("history", arg, iter.next())),
+            "--label" => label = flag_value("history", arg, iter.next()),
+            "--save" => save = true,
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("history 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    if !matches!(action, Some("save" | "compare" | "list")) {
+        eprintln!("用法: history <save|compare|list> [文件或目录] [选项]");
+        std::process::exit(2);
+    }
+    let Some(state) = state else {
+        eprintln!("history 需要 --state 或配置 history.state_path");
+        std::process::exit(2);
+    };
+    let store = HistoryStore::open(&state).unwrap_or_else(|e| {
+        log::error!("{e:#}");
+        std::process::exit(1);
+    });
+
+    if action == Some("list") {
+        let snapshots = store.snapshots().unwrap_or_else(|e| {
+            log::error!("{e:#}");
+            std::process::exit(1);
+        });
+        if json {
+            match serde_json::to_string_pretty(&snapshots) {
+                Ok(text) => println!("{text}"),
+                Err(e) => log::error!("序列化快照列表失败: {e}"),
+            }
+        } else {
+            for s in &snapshots {
+                println!(
+                    "{}\t{}\t{}\t指纹 {}，用户 {}",
+                    s.id, s.label, s.taken_at, s.fingerprints, s.users
+                );
+            }
+        }
+        return;
+    }
+
+    let Some(input) = input.or_else(|| runtime.sqllog_dir.clone()) else {
+        eprintln!("history 需要输入路径或配置 sqllog_dir");
+        std::process::exit(2);
+    };
+    let options = runtime.parse_options();
+    let mut aggregator = SnapshotAggregator::new();
+    for file in input_files(input) {
+        let result = Sqllog::parse_with_options(
+            &file,
+            &options,
+            |records| aggregator.observe(records),
+            |_| {},
+        );
+        if let Err(e) = result {
+            log::error!("解析 {} 失败: {e}", file.display());
+        }
+    }
+    let snapshot = aggregator.finish(&label);
+
+    if action == Some("compare") {
+        match store.latest() {
+            Ok(Some(baseline)) => {
+                let report =
+                    compare(&baseline, &snapshot, &DiffThresholds::default());
+                if json {
+                    match serde_json::to_string_pretty(&report) {
+                        Ok(text) => println!("{text}"),
+                        Err(e) => log::error!("序列化趋势报告失败: {e}"),
                     }
                 } else {
-                    log::debug!("导出功能未启用");
+                    print!("{report}");
                 }
             }
+            Ok(None) => log::warn!("历史库中还没有快照，无可对比的基线"),
             Err(e) => {
-                log::error!("处理文件失败: {e}");
+                log::error!("{e:#}");
+                std::process::exit(1);
+            }
+        }
+    }
+    if save {
+        match store.save(&snapshot) {
+            Ok(id) => log::info!(
+                "已保存快照 {id}（{label}）: {} 类语句，{} 个用户",
+                snapshot.fingerprints.len(),
+                snapshot.users.len()
+            ),
+            Err(e) => {
+                log::error!("{e:#}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// 解析子命令数值参数，缺失或非法时退出
+fn flag_value<T: std::str::FromStr>(
+    command: &str,
+    flag: &str,
+    value: Option<&String>,
+) -> T {
+    match value.map(|v| v.parse::<T>()) {
+        Some(Ok(v)) => v,
+        _ => {
+            eprintln!("{command} 参数错误: {flag} 缺少取值或取值无效");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// `watch` 子命令：跟随日志文件新追加的记录，按滑动窗口输出 QPS、p95
+/// 执行时间与错误率，超过阈值时记录告警。
+///
+/// 用法：`watch <文件> [--window-secs 300] [--interval-secs 5] [--from-start]
+/// [--max-qps N] [--max-p95-ms N] [--max-error-rate 0.05] [--json]`
+pub fn run_watch(runtime: &RuntimeConfig, args: &[String]) {
+    let mut input = None;
+    let mut window_secs = DEFAULT_WINDOW_SECS;
+    let mut interval_secs = 5u64;
+    let mut from_start = false;
+    let mut thresholds = AlertThresholds::default();
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--window-secs" => {
+                window_secs = flag_value("watch", arg, iter.next());
+            }
+            "--interval-secs" => {
+                interval_secs = flag_value("watch", arg, iter.next());
+            }
+            "--from-start" => from_start = true,
+            "--max-qps" => {
+                thresholds.max_qps =
+                    Some(flag_value("watch", arg, iter.next()));
+            }
+            "--max-p95-ms" => {
+                thresholds.max_p95_ms =
+                    Some(flag_value("watch", arg, iter.next()));
+            }
+            "--max-error-rate" => {
+                thresholds.max_error_rate =
+                    Some(flag_value("watch", arg, iter.next()));
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("watch 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => input = Some(path::PathBuf::from(arg)),
+        }
+    }
+    if window_secs == 0 || interval_secs == 0 {
+        eprintln!("watch 参数错误: --window-secs 与 --interval-secs 不能为 0");
+        std::process::exit(2);
+    }
+    let Some(input) = input else {
+        eprintln!("watch 需要一个日志文件路径");
+        std::process::exit(2);
+    };
+
+    let mut follower = match FileFollower::new(&input, from_start) {
+        Ok(f) => f
+            .with_blank_fields(runtime.sqllog_blank_fields)
+            .with_parse_mode(runtime.sqllog_parse_mode),
+        Err(e) => {
+            eprintln!("无法跟随文件 {}: {e}", input.display());
+            std::process::exit(1);
+        }
+    };
+    let mut window = SlidingWindow::new(window_secs);
+    log::info!("开始跟随 {}，窗口 {window_secs} 秒", input.display());
+    loop {
+        match follower.poll() {
+            Ok((records, errors)) => {
+                if !errors.is_empty() {
+                    log::warn!("解析错误 {} 个", errors.len());
+                }
+                window.observe(&records);
+            }
+            Err(e) => log::error!("读取 {} 失败: {e}", input.display()),
+        }
+        let stats = window.stats();
+        let alerts = thresholds.check(&stats);
+        for alert in &alerts {
+            log::warn!("告警: {alert}");
+        }
+        if json {
+            let line = serde_json::json!({ "stats": stats, "alerts": alerts });
+            println!("{line}");
+        } else {
+            println!("{stats}");
+            for alert in &alerts {
+                println!("  告警: {alert}");
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_secs(interval_secs));
+    }
+}
+
+/// `--count-only`：完整扫描 `sqllog_dir` 下的日志文件，只按记录边界计数，
+/// 不解析、不写数据库，用于在正式导出前估计作业规模
+pub fn run_count_only(runtime: &RuntimeConfig) -> ExitCode {
+    let Some(sqllog_dir) = &runtime.sqllog_dir else {
+        log::warn!("未配置 sqllog_dir，跳过计数");
+        return ExitCode::Success;
+    };
+    let mut files = collect_sqllog_files(sqllog_dir);
+    runtime.sqllog_file_order.apply(&mut files);
+
+    let started = std::time::Instant::now();
+    let mut total = 0u64;
+    let mut bytes = 0u64;
+    let mut code = ExitCode::Success;
+    for file in &files {
+        match count_records(file) {
+            Ok(records) => {
+                println!("{records:>12}  {}", file.display());
+                total += records;
+                bytes += fs::metadata(file).map_or(0, |m| m.len());
+            }
+            Err(e) => {
+                log::error!("无法读取文件 {}: {e}", file.display());
+                code = ExitCode::Failure;
+            }
+        }
+    }
+    println!("共 {} 个文件，记录数合计 {total}", files.len());
+    log::info!("计数完成：读取 {bytes} 字节，耗时 {:?}", started.elapsed());
+    code
+}
+
+/// `--duckdb-report [输出文件]`：为已有的导出库（`database.db_path`）生成
+/// 体积报告（见 [`IndexReport`]），以 JSON 打印到标准输出或写入给定文件，
+/// 不解析日志
+pub fn run_duckdb_report(
+    runtime: &RuntimeConfig,
+    output: Option<&path::Path>,
+) -> ExitCode {
+    if runtime.use_in_memory || !path::Path::new(&runtime.db_path).exists() {
+        eprintln!(
+            "--duckdb-report 需要已存在的库文件（database.db_path）: {}",
+            runtime.db_path
+        );
+        return ExitCode::InvalidConfig;
+    }
+    let report = match DuckDbProvider::new(runtime)
+        .and_then(|provider| IndexReport::generate(&provider))
+    {
+        Ok(report) => report,
+        Err(e) => {
+            log::error!("生成库体积报告失败: {e:#}");
+            return ExitCode::Failure;
+        }
+    };
+    let json = match serde_json::to_string_pretty(&report) {
+        Ok(json) => json,
+        Err(e) => {
+            log::error!("序列化库体积报告失败: {e}");
+            return ExitCode::Failure;
+        }
+    };
+    match output {
+        Some(path) => match fs::write(path, json + "\n") {
+            Ok(()) => {
+                log::info!("库体积报告已写入: {}", path.display());
+                ExitCode::Success
+            }
+            Err(e) => {
+                log::error!("写入库体积报告失败 {}: {e}", path.display());
+                ExitCode::Failure
+            }
+        },
+        None => {
+            println!("{json}");
+            ExitCode::Success
+        }
+    }
+}
+
+/// `inspect` 子命令：只读取每个文件开头的一段样本，报告编码、行尾、
+/// 首条时间与记录数估算，用于在导入前规划大批量作业。
+///
+/// 用法：`inspect <文件或目录>... [--sample-kb 64] [--json]`
+pub fn run_inspect(args: &[String]) {
+    let mut inputs = Vec::new();
+    let mut sample_bytes = DEFAULT_SAMPLE_BYTES;
+    let mut json = false;
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--sample-kb" => {
+                let Some(Ok(kb)) = iter.next().map(|v| v.parse::<usize>())
+                else {
+                    eprintln!("inspect 参数错误: --sample-kb 需要正整数");
+                    std::process::exit(2);
+                };
+                if kb == 0 {
+                    eprintln!("inspect 参数错误: --sample-kb 不能为 0");
+                    std::process::exit(2);
+                }
+                sample_bytes = kb.saturating_mul(1024);
+            }
+            "--json" => json = true,
+            other if other.starts_with("--") => {
+                eprintln!("inspect 参数错误: 未知选项 {other}");
+                std::process::exit(2);
+            }
+            _ => inputs.extend(input_files(path::PathBuf::from(arg))),
+        }
+    }
+    if inputs.is_empty() {
+        eprintln!("用法: inspect <文件或目录>... [--sample-kb 64] [--json]");
+        std::process::exit(2);
+    }
+
+    let mut reports = Vec::new();
+    let mut failed = false;
+    for file in &inputs {
+        match inspect_file(file, sample_bytes) {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                log::error!("无法读取文件 {}: {e}", file.display());
+                failed = true;
+            }
+        }
+    }
+
+    if json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(text) => println!("{text}"),
+            Err(e) => {
+                log::error!("序列化探查结果失败: {e}");
                 std::process::exit(1);
             }
         }
     } else {
-        log::warn!("未配置 sqllog_dir，跳过解析");
+        for report in &reports {
+            print!("{report}");
+        }
+        let total: u64 =
+            reports.iter().filter_map(|r| r.estimated_records).sum();
+        println!("共 {} 个文件，记录数估算合计 ~{total}", reports.len());
+    }
+    if failed {
+        std::process::exit(1);
     }
 }
@@ -21,6 +21,7 @@
 //! [database]
 //! db_path = "sqllog.duckdb"
 //! use_in_memory = false
+//! max_records_per_sec = 50000
 //!
 //! [export]
 //! enabled = true
@@ -32,6 +33,18 @@
 //! parser_threads = 4
 //! write_errors = true
 //! errors_out_path = "parse_errors.jsonl"
+//! file_timeout_secs = 300
+//! record_id = "hash"
+//!
+//! [analyze]
+//! memory_limit_mb = 1024
+//!
+//! [jobs]
+//! state_path = "jobs.duckdb"
+//! max_retries = 3
+//!
+//! [history]
+//! state_path = "history.duckdb"
 //! ```
 //!
 //! ### 3. 运行时配置转换
@@ -67,15 +80,50 @@
 //! }
 //! ```
 
+mod builder;
+
+pub use builder::{
+    ConfigError, MAX_CHUNK_SIZE, MAX_PARSER_THREADS, RuntimeConfigBuilder,
+};
+
+use crate::analysis::apps::DEFAULT_TOP_FINGERPRINTS;
+use crate::analysis::{Classifier, ClassifyRuleConfig};
+use crate::database::{
+    AutoTune, ColumnAliases, Compression, FormatOptions, LineTemplate,
+    RateLimit, Redactions,
+};
+use crate::error_writer::ErrorFormat;
+use crate::input_path::FileOrder;
+use crate::jobs::DEFAULT_MAX_RETRIES;
+use crate::notify::NotifyConfig;
+use crate::sqllog::{
+    BlankFields, ErrorBreaker, KeySample, ParseMode, ParseOptions, Prefilter,
+    RecordIdMode, TraceLines,
+};
 use serde::Deserialize;
-use std::{env, fs, path::PathBuf, process};
+use std::{
+    collections::BTreeMap, env, fs, path::PathBuf, process, time::Duration,
+};
 
-#[derive(Debug, Deserialize)]
+/// 设置了内存上限但未配置 `chunk_size` 时使用的分块大小
+pub const MEMORY_LIMITED_CHUNK_SIZE: usize = 10_000;
+
+/// 设置了错误率熔断但未配置 `breaker_window_mb` 时的检查窗口（MB）
+pub const DEFAULT_BREAKER_WINDOW_MB: u64 = 64;
+
+#[derive(Debug, Default, Deserialize)]
 pub struct Config {
     pub log: Option<LogSection>,
     pub database: Option<DatabaseSection>,
     pub export: Option<ExportSection>,
     pub sqllog: Option<SqllogSection>,
+    pub analyze: Option<AnalyzeSection>,
+    pub jobs: Option<JobsSection>,
+    pub notify: Option<NotifySection>,
+    pub history: Option<HistorySection>,
+    pub classify: Option<ClassifySection>,
+    pub apps: Option<AppsSection>,
+    pub plugin: Option<PluginSection>,
 }
 
 /// 应用层配置结构体，直接从配置文件（TOML）反序列化得到
@@ -85,6 +133,10 @@ pub struct LogSection {
     pub enable_stdout: Option<bool>,
     pub log_dir: Option<PathBuf>,
     pub level: Option<String>,
+    /// OTLP/HTTP 导出地址（如 `http://localhost:4318`），需要 `otel` 特性
+    pub otlp_endpoint: Option<String>,
+    /// 导出 span 与指标时的服务名（默认 `sqllog-analysis`）
+    pub otlp_service_name: Option<String>,
 }
 
 /// 日志相关配置节
@@ -94,6 +146,27 @@ pub struct DatabaseSection {
     // 当为 true 时，在内存 DuckDB 中写入后再将表导出到磁盘（COPY TO），
     // 默认为 false，保持现有直接写入磁盘数据库的行为。
     pub use_in_memory: Option<bool>,
+    /// 写入数据库的速率上限（记录数/秒），未设置表示不限速
+    pub max_records_per_sec: Option<u64>,
+    /// 写入数据库的速率上限（批次数/秒），未设置表示不限速
+    pub max_batches_per_sec: Option<u64>,
+    /// 是否根据写入延迟自适应调整每批写入的记录数
+    pub auto_tune: Option<bool>,
+    /// 自适应模式下期望的单批写入耗时（毫秒）
+    pub auto_tune_target_ms: Option<u64>,
+    /// 自适应模式下的最小批大小
+    pub auto_tune_min_batch: Option<usize>,
+    /// 自适应模式下的最大批大小
+    pub auto_tune_max_batch: Option<usize>,
+    /// 解析完成后是否按 `occurrence_time` 重排 sqllogs 表（默认 false）
+    pub cluster_by_time: Option<bool>,
+    /// 多文件合并是否按批次登记并在重跑时跳过已提交的批次（默认 false）
+    pub resume: Option<bool>,
+    /// 导出完成后是否把 description/plan 移入按记录 ID 关联的冷字段表
+    /// （默认 false，需要设置 `sqllog.record_id`）
+    pub split_cold: Option<bool>,
+    /// 运行结束时是否向 `runs` 表写入本次运行的元数据（默认 false）
+    pub record_runs: Option<bool>,
 }
 
 /// 导出相关配置节
@@ -107,6 +180,29 @@ pub struct ExportSection {
     pub overwrite: Option<bool>,
     pub append: Option<bool>,
     pub file_size_bytes: Option<u64>,
+    /// 导出时 description 的最大字节数（UTF-8），超出部分在字符边界处截断（未设置表示不截断）
+    pub description_max_bytes: Option<usize>,
+    /// 被截断记录的完整 description 写入的旁路文件（JSONL，按 record_id 关联）
+    pub description_overflow_path: Option<PathBuf>,
+    /// 导出清单（JSON）输出路径，列出各产物的 SHA-256、记录数与统计信息
+    pub manifest_path: Option<PathBuf>,
+    /// DDL（CREATE/ALTER/DROP）审计文件路径，格式按扩展名选择
+    pub ddl_out_path: Option<PathBuf>,
+    /// 各导出格式的选项，形如 `["csv.delimiter=;", "json.layout=array"]`
+    pub exporter_opts: Option<Vec<String>>,
+    /// 输出列别名（源列名 = 输出列名），作用于 CSV/JSON 导出与数据库视图
+    pub column_aliases: Option<BTreeMap<String, String>>,
+    /// 只导出累计执行时间排名前 K 的会话的记录（未设置表示导出全部）
+    pub top_sessions: Option<usize>,
+    /// 导出文件的压缩方式：`gzip` / `zstd`（未设置表示不压缩）
+    pub compression: Option<String>,
+    /// `template` 格式的行模板，如 `{occurrence_time}\t{user}\t{description:.100}`
+    pub template: Option<String>,
+    /// 各导出格式的字段脱敏规则（格式 -> 列名 -> `null`/`hash`/`literals`）
+    pub redact: Option<BTreeMap<String, BTreeMap<String, String>>>,
+    /// 是否按记录的 `occurrence_time` 把导出写入 `year=/month=/day=` 分区目录
+    /// （默认 false）
+    pub hive_partitions: Option<bool>,
 }
 
 /// sqllog 相关配置节
@@ -121,16 +217,151 @@ pub struct SqllogSection {
     pub write_errors: Option<bool>,
     /// 解析错误写入的输出文件路径（如果未提供，运行时使用默认 `parse_errors.log`）
     pub errors_out_path: Option<PathBuf>,
+    /// 解析错误文件格式：`jsonl`（默认）/ `csv`
+    pub errors_format: Option<String>,
+    /// 是否同时将解析错误写入数据库的 `parse_errors` 表（默认 false）
+    pub errors_table: Option<bool>,
+    /// 单个文件的解析时限（秒），超时后放弃该文件剩余内容并记录解析错误
+    pub file_timeout_secs: Option<u64>,
+    /// 记录 ID 生成方式：`none`（默认）/ `hash` / `snowflake`
+    pub record_id: Option<String>,
+    /// 是否从 description 中提取执行计划（默认 false）
+    pub extract_plans: Option<bool>,
+    /// 是否把单独输出的执行阶段记录并入对应的 SQL 记录（默认 false）
+    pub link_exec_phase: Option<bool>,
+    /// 原始段预过滤正则，只解析整段原始文本匹配的记录，未设置表示不过滤
+    pub prefilter: Option<String>,
+    /// 在途记录（解析块与待写入批次）的内存上限（字节），未设置表示不限制
+    pub max_memory_bytes: Option<usize>,
+    /// 空白 appname 的处理方式：`null`（默认）/ `empty` / `raw`
+    pub blank_fields: Option<String>,
+    /// 按字段哈希抽样的比例（`(0, 1]`），未设置表示不抽样
+    pub sample_rate: Option<f64>,
+    /// 抽样字段：`session`（默认）/ `user` / `trx`
+    pub sample_key: Option<String>,
+    /// 抽样哈希种子（默认 0）
+    pub sample_seed: Option<u64>,
+    /// 混入的 trace/诊断行的处理方式：`error`（默认）/ `skip` / `collect`
+    pub trace_lines: Option<String>,
+    /// 追加的非 sqllog 行识别规则：分类名 -> 匹配时间戳之后首行内容的正则
+    pub trace_patterns: Option<BTreeMap<String, String>>,
+    /// 日志头解析模式：`strict`（默认）/ `lenient`
+    pub parse_mode: Option<String>,
+    /// 文件处理顺序：`directory`（默认）/ `name` / `largest_first` /
+    /// `round_robin` / `priority`
+    pub file_order: Option<String>,
+    /// `priority` 顺序的文件名片段，靠前的优先
+    pub file_priority: Option<Vec<String>>,
+    /// 文件开头解析错误率的熔断上限（`[0, 1)`），未设置表示不检查
+    pub breaker_max_error_ratio: Option<f64>,
+    /// 熔断检查窗口：文件开头的 MB 数（默认 64）
+    pub breaker_window_mb: Option<u64>,
+    /// 解析缓存目录，设置后复用内容与解析选项未变化的文件的解析结果
+    pub cache_dir: Option<PathBuf>,
 }
 
-#[derive(Debug, Clone)]
+/// analyze 子命令相关配置节
+#[derive(Debug, Deserialize)]
+pub struct AnalyzeSection {
+    /// 内存聚合状态的上限（MB），超出后改用临时 `DuckDB` 数据库聚合
+    pub memory_limit_mb: Option<usize>,
+    /// 临时数据库所在目录（默认系统临时目录）
+    pub temp_dir: Option<PathBuf>,
+}
+
+/// 批量导入作业队列配置节
+#[derive(Debug, Deserialize)]
+pub struct JobsSection {
+    /// 作业状态库路径；设置后按作业逐文件导入，可在中断后继续
+    pub state_path: Option<PathBuf>,
+    /// 失败重试次数（不含首次尝试，默认 3）
+    pub max_retries: Option<u32>,
+}
+
+/// 统计历史配置节
+#[derive(Debug, Deserialize)]
+pub struct HistorySection {
+    /// 统计快照历史库路径，供 `history` 子命令使用
+    pub state_path: Option<PathBuf>,
+}
+
+/// 安全分级配置节
+#[derive(Debug, Deserialize)]
+pub struct ClassifySection {
+    /// 分级规则，供 `classify` 子命令使用
+    pub rules: Option<Vec<ClassifyRuleConfig>>,
+    /// forbidden 记录写出的 JSONL 文件；未设置时只统计
+    pub forbidden_out: Option<PathBuf>,
+}
+
+/// 应用负载配置节
+#[derive(Debug, Deserialize)]
+pub struct AppsSection {
+    /// appname 别名：原始名称（以 `*` 结尾时按前缀匹配）-> 应用名
+    pub aliases: Option<BTreeMap<String, String>>,
+    /// 每个应用列出的 SQL 指纹数
+    pub top_fingerprints: Option<usize>,
+}
+
+/// 记录转换插件配置节
+#[derive(Debug, Deserialize)]
+pub struct PluginSection {
+    /// 插件程序及其参数；未设置时不启用插件
+    pub command: Option<Vec<String>>,
+    /// 每个解析块交给插件处理的时限（毫秒）
+    pub timeout_ms: Option<u64>,
+}
+
+/// 运行结果通知配置节
+#[derive(Debug, Deserialize)]
+pub struct NotifySection {
+    /// Webhook 地址；未设置时不通知
+    pub webhook_url: Option<String>,
+    /// 消息格式：`generic`（默认）/ `dingtalk` / `wecom` / `slack`
+    pub flavor: Option<String>,
+    /// 运行完成时是否总是通知（默认 true，否则只在超过阈值时通知）
+    pub on_complete: Option<bool>,
+    /// 解析错误率上限（0 ~ 1）
+    pub max_error_ratio: Option<f64>,
+    /// 慢语句数上限
+    pub max_slow_queries: Option<u64>,
+    /// 慢语句的 execute_time 下限（毫秒，默认 1000）
+    pub slow_query_ms: Option<i64>,
+    /// 单次请求超时（秒，默认 10）
+    pub timeout_secs: Option<u64>,
+    /// 超时或临时性错误时的重试次数（默认 0）
+    pub retries: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ExportOptions {
     pub per_thread_out: bool,
     pub write_flags: WriteFlags,
     pub file_size_bytes: Option<u64>,
+    pub description_max_bytes: Option<usize>,
+    pub description_overflow_path: Option<PathBuf>,
+    pub manifest_path: Option<PathBuf>,
+    pub format_options: FormatOptions,
+    pub column_aliases: ColumnAliases,
+    /// 只导出累计执行时间排名前 K 的会话的记录
+    pub top_sessions: Option<usize>,
+    /// 导出文件的压缩方式（未设置表示不压缩）
+    pub compression: Option<Compression>,
+    /// `template` 格式使用的行模板
+    pub template: Option<LineTemplate>,
+    /// 各导出格式的字段脱敏规则
+    pub redactions: Redactions,
+    /// DDL 审计文件路径，设置后把 DDL 语句另外导出一份
+    pub ddl_out_path: Option<PathBuf>,
+    /// 只导出 DDL 语句（导出 DDL 审计文件时使用）
+    pub ddl_only: bool,
+    /// 按记录的 `occurrence_time` 把导出写入 Hive 风格的日期分区目录
+    pub hive_partitions: bool,
+    /// 只导出该日期分区（`YYYY-MM-DD`）的记录（写出各分区时使用）
+    pub partition_day: Option<String>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct WriteFlags {
     pub overwrite_or_ignore: bool,
     pub overwrite: bool,
@@ -144,16 +375,129 @@ pub struct RuntimeConfig {
     pub enable_stdout: bool,
     pub log_dir: Option<PathBuf>,
     pub log_level: log::LevelFilter,
+    /// OTLP/HTTP 导出地址，`None` 表示只在设置了
+    /// `OTEL_EXPORTER_OTLP_ENDPOINT` 环境变量时导出（需要 `otel` 特性）
+    pub log_otlp_endpoint: Option<String>,
+    /// 导出 span 与指标时的服务名
+    pub log_otlp_service_name: Option<String>,
     pub sqllog_dir: Option<PathBuf>,
     pub sqllog_chunk_size: Option<usize>,
     pub parser_threads: usize,
     pub sqllog_write_errors: bool,
     pub sqllog_errors_out_path: Option<PathBuf>,
+    pub sqllog_errors_format: ErrorFormat,
+    pub sqllog_errors_table: bool,
+    pub sqllog_file_timeout: Option<Duration>,
+    pub sqllog_record_id: RecordIdMode,
+    pub sqllog_extract_plans: bool,
+    /// 按会话与语句句柄把执行阶段记录并入对应的 SQL 记录
+    pub sqllog_link_exec_phase: bool,
+    /// 原始段预过滤，`None` 表示不过滤
+    pub sqllog_prefilter: Option<Prefilter>,
+    pub sqllog_blank_fields: BlankFields,
+    /// 按字段哈希抽样，`None` 表示不抽样
+    pub sqllog_sample: Option<KeySample>,
+    /// 混入的 trace/诊断行的识别与处理方式
+    pub sqllog_trace_lines: TraceLines,
+    /// 日志头解析模式，宽松模式恢复出的记录带 `partial` 标记
+    pub sqllog_parse_mode: ParseMode,
+    /// 多个文件的处理顺序
+    pub sqllog_file_order: FileOrder,
+    /// 文件开头错误率熔断，`None` 表示不检查
+    pub sqllog_error_breaker: Option<ErrorBreaker>,
+    /// 解析缓存目录，`None` 表示不缓存
+    pub sqllog_cache_dir: Option<PathBuf>,
     pub export_enabled: bool,
     pub export_format: String,
     pub export_out_path: Option<PathBuf>,
     pub export_options: ExportOptions,
     pub use_in_memory: bool,
+    /// 解析完成后按 `occurrence_time` 重排 sqllogs 表
+    pub cluster_by_time: bool,
+    /// 断点续写：按批次提交合并，重跑时跳过已提交的批次
+    pub db_resume: bool,
+    /// 导出完成后把 description/plan 移入冷字段表
+    pub db_split_cold: bool,
+    /// 运行结束时向 `runs` 表写入运行元数据
+    pub db_record_runs: bool,
+    pub insert_rate_limit: RateLimit,
+    /// 自适应批大小配置，`None` 表示使用固定的 `sqllog_chunk_size`
+    pub insert_auto_tune: Option<AutoTune>,
+    /// 在途记录的内存上限（字节），`None` 表示不限制
+    pub max_memory_bytes: Option<usize>,
+    /// analyze 内存聚合状态的上限（MB）
+    pub analyze_memory_limit_mb: usize,
+    /// analyze 溢写时临时数据库所在目录，`None` 表示系统临时目录
+    pub analyze_temp_dir: Option<PathBuf>,
+    /// 作业状态库路径，`None` 表示不使用作业队列
+    pub jobs_state_path: Option<PathBuf>,
+    /// 作业失败重试次数（不含首次尝试）
+    pub jobs_max_retries: u32,
+    /// 运行结果通知，`None` 表示不通知
+    pub notify: Option<NotifyConfig>,
+    /// 统计快照历史库路径，`None` 表示未配置
+    pub history_state_path: Option<PathBuf>,
+    /// 安全分级规则（已校验）
+    pub classify_rules: Vec<ClassifyRuleConfig>,
+    /// forbidden 记录写出的 JSONL 文件，`None` 表示不写出
+    pub classify_forbidden_out: Option<PathBuf>,
+    /// appname 别名，供 `apps` 子命令使用
+    pub apps_aliases: BTreeMap<String, String>,
+    /// `apps` 报告中每个应用列出的 SQL 指纹数
+    pub apps_top_fingerprints: usize,
+    /// 记录转换插件的程序及其参数，`None` 表示不启用
+    pub plugin_command: Option<Vec<String>>,
+    /// 每个解析块交给插件处理的时限，`None` 表示不限时
+    pub plugin_timeout: Option<Duration>,
+}
+
+impl RuntimeConfig {
+    /// 以默认配置为起点的构造器，见 [`RuntimeConfigBuilder`]
+    #[must_use]
+    pub fn builder() -> RuntimeConfigBuilder {
+        RuntimeConfigBuilder::new()
+    }
+
+    /// 校验各字段的取值范围
+    ///
+    /// # Errors
+    /// 存在非法字段时返回每个字段对应的错误
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let errors = builder::validate(self);
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// 根据 sqllog 相关配置构造文件解析选项
+    #[must_use]
+    ///
+    /// 设置了 `max_memory_bytes` 而未启用分块时，按
+    /// [`MEMORY_LIMITED_CHUNK_SIZE`] 分块，避免整个文件的记录同时驻留内存。
+    pub fn parse_options(&self) -> ParseOptions {
+        let chunk_size = match self.sqllog_chunk_size.unwrap_or(0) {
+            0 if self.max_memory_bytes.is_some() => MEMORY_LIMITED_CHUNK_SIZE,
+            n => n,
+        };
+        ParseOptions {
+            chunk_size,
+            timeout: self.sqllog_file_timeout,
+            record_id: self.sqllog_record_id,
+            extract_plans: self.sqllog_extract_plans,
+            blank_fields: self.sqllog_blank_fields,
+            sample: self.sqllog_sample,
+            trace_lines: self.sqllog_trace_lines.clone(),
+            mode: self.sqllog_parse_mode,
+            error_breaker: self.sqllog_error_breaker,
+            link_exec_phase: self.sqllog_link_exec_phase,
+            prefilter: self.sqllog_prefilter.clone(),
+        }
+    }
+}
+
+impl Default for RuntimeConfig {
+    /// 与未找到配置文件时 `Config::load()` 得到的运行时配置一致
+    fn default() -> Self {
+        Config::merge_to_runtime_config(&Config::default())
+    }
 }
 
 /// 将解析得到的 Config 合并为运行时所需的 `RuntimeConfig`，
@@ -161,8 +505,7 @@ pub struct RuntimeConfig {
 impl Config {
     #[must_use]
     pub fn load() -> RuntimeConfig {
-        let mut cfg =
-            Self { log: None, database: None, export: None, sqllog: None };
+        let mut cfg = Self::default();
 
         if let Some(path) = Self::find_config_path() {
             if let Some(parsed) = Self::read_and_parse_config(&path) {
@@ -219,7 +562,9 @@ impl Config {
     }
 
     /// 解析数据库相关配置。
-    fn parse_database_config(cfg: &Self) -> (String, bool) {
+    fn parse_database_config(
+        cfg: &Self,
+    ) -> (String, bool, RateLimit, Option<AutoTune>) {
         let db_path = cfg
             .database
             .as_ref()
@@ -232,7 +577,67 @@ impl Config {
             .and_then(|d| d.use_in_memory)
             .unwrap_or(false);
 
-        (db_path, use_in_memory)
+        let positive = |value: Option<u64>, key: &str| {
+            value.map(|v| {
+                if v == 0 {
+                    eprintln!("配置错误: database.{key} 不能为 0；请设置为正整数或删除该项以表示不限速");
+                    process::exit(2);
+                }
+                v
+            })
+        };
+        let insert_rate_limit = RateLimit {
+            records_per_sec: positive(
+                cfg.database.as_ref().and_then(|d| d.max_records_per_sec),
+                "max_records_per_sec",
+            ),
+            batches_per_sec: positive(
+                cfg.database.as_ref().and_then(|d| d.max_batches_per_sec),
+                "max_batches_per_sec",
+            ),
+        };
+
+        let insert_auto_tune = cfg
+            .database
+            .as_ref()
+            .filter(|d| d.auto_tune.unwrap_or(false))
+            .map(|d| {
+                let defaults = AutoTune::default();
+                let nonzero = |value: Option<usize>, key: &str| {
+                    if value == Some(0) {
+                        eprintln!("配置错误: database.{key} 不能为 0");
+                        process::exit(2);
+                    }
+                    value
+                };
+                let auto_tune = AutoTune {
+                    target_latency: positive(
+                        d.auto_tune_target_ms,
+                        "auto_tune_target_ms",
+                    )
+                    .map_or(defaults.target_latency, Duration::from_millis),
+                    min_batch: nonzero(
+                        d.auto_tune_min_batch,
+                        "auto_tune_min_batch",
+                    )
+                    .unwrap_or(defaults.min_batch),
+                    max_batch: nonzero(
+                        d.auto_tune_max_batch,
+                        "auto_tune_max_batch",
+                    )
+                    .unwrap_or(defaults.max_batch),
+                };
+                if auto_tune.min_batch > auto_tune.max_batch {
+                    eprintln!(
+                        "配置错误: database.auto_tune_min_batch ({}) 不能大于 auto_tune_max_batch ({})",
+                        auto_tune.min_batch, auto_tune.max_batch
+                    );
+                    process::exit(2);
+                }
+                auto_tune
+            });
+
+        (db_path, use_in_memory, insert_rate_limit, insert_auto_tune)
     }
 
     /// 解析日志相关配置。
@@ -306,6 +711,77 @@ impl Config {
                 }
                 v
             });
+        let export_description_max_bytes = cfg
+            .export
+            .as_ref()
+            .and_then(|e| e.description_max_bytes)
+            .map(|v| {
+                if v == 0 {
+                    eprintln!("配置错误: export.description_max_bytes 不能为 0；请设置为正整数或删除该项以表示不截断");
+                    process::exit(2);
+                }
+                v
+            });
+
+        let export_format_options = cfg
+            .export
+            .as_ref()
+            .and_then(|e| e.exporter_opts.as_ref())
+            .map_or_else(FormatOptions::default, |pairs| {
+                FormatOptions::from_pairs(pairs).unwrap_or_else(|e| {
+                    eprintln!("配置错误: export.exporter_opts 无效: {e}");
+                    process::exit(2);
+                })
+            });
+
+        let export_column_aliases = cfg
+            .export
+            .as_ref()
+            .and_then(|e| e.column_aliases.as_ref())
+            .map_or_else(ColumnAliases::default, |map| {
+                ColumnAliases::from_map(map).unwrap_or_else(|e| {
+                    eprintln!("配置错误: export.column_aliases 无效: {e}");
+                    process::exit(2);
+                })
+            });
+
+        let export_top_sessions =
+            cfg.export.as_ref().and_then(|e| e.top_sessions).map(|v| {
+                if v == 0 {
+                    eprintln!("配置错误: export.top_sessions 不能为 0；请设置为正整数或删除该项以导出全部记录");
+                    process::exit(2);
+                }
+                v
+            });
+
+        let export_compression =
+            cfg.export.as_ref().and_then(|e| e.compression.as_deref()).map(
+                |v| {
+                    v.parse::<Compression>().unwrap_or_else(|e| {
+                        eprintln!("配置错误: export.compression 无效: {e}");
+                        process::exit(2);
+                    })
+                },
+            );
+
+        let export_template =
+            cfg.export.as_ref().and_then(|e| e.template.as_deref()).map(|v| {
+                LineTemplate::parse(v).unwrap_or_else(|e| {
+                    eprintln!("配置错误: export.template 无效: {e}");
+                    process::exit(2);
+                })
+            });
+
+        let export_redactions = cfg
+            .export
+            .as_ref()
+            .and_then(|e| e.redact.as_ref())
+            .map_or_else(Redactions::default, |map| {
+                Redactions::from_map(map).unwrap_or_else(|e| {
+                    eprintln!("配置错误: export.redact 无效: {e}");
+                    process::exit(2);
+                })
+            });
 
         let export_options = ExportOptions {
             per_thread_out: export_per_thread_out,
@@ -315,15 +791,53 @@ impl Config {
                 append: export_append,
             },
             file_size_bytes: export_file_size_bytes,
+            description_max_bytes: export_description_max_bytes,
+            description_overflow_path: cfg
+                .export
+                .as_ref()
+                .and_then(|e| e.description_overflow_path.clone()),
+            manifest_path: cfg
+                .export
+                .as_ref()
+                .and_then(|e| e.manifest_path.clone()),
+            format_options: export_format_options,
+            column_aliases: export_column_aliases,
+            top_sessions: export_top_sessions,
+            compression: export_compression,
+            template: export_template,
+            redactions: export_redactions,
+            ddl_out_path: cfg
+                .export
+                .as_ref()
+                .and_then(|e| e.ddl_out_path.clone()),
+            ddl_only: false,
+            hive_partitions: cfg
+                .export
+                .as_ref()
+                .and_then(|e| e.hive_partitions)
+                .unwrap_or(false),
+            partition_day: None,
         };
 
         (export_enabled, export_format, export_out_path, export_options)
     }
 
     /// 解析 sqllog 相关配置。
+    #[allow(clippy::type_complexity)]
     fn parse_sqllog_config(
         cfg: &Self,
-    ) -> (Option<PathBuf>, Option<usize>, usize, bool, Option<PathBuf>) {
+    ) -> (
+        Option<PathBuf>,
+        Option<usize>,
+        usize,
+        bool,
+        Option<PathBuf>,
+        ErrorFormat,
+        bool,
+        Option<Duration>,
+        RecordIdMode,
+        bool,
+    ) {
         let sqllog_dir = cfg
             .sqllog
             .as_ref()
@@ -347,19 +861,296 @@ impl Config {
             .and_then(|s| s.errors_out_path.clone())
             .or_else(|| Some(PathBuf::from("parse_errors.log")));
 
+        let sqllog_errors_format = cfg
+            .sqllog
+            .as_ref()
+            .and_then(|s| s.errors_format.as_deref())
+            .map_or(ErrorFormat::Jsonl, |v| {
+                v.parse().unwrap_or_else(|e| {
+                    eprintln!(
+                        "配置错误: sqllog.errors_format 无效: {e}；可选值为 jsonl/csv"
+                    );
+                    process::exit(2);
+                })
+            });
+
+        let sqllog_errors_table =
+            cfg.sqllog.as_ref().and_then(|s| s.errors_table).unwrap_or(false);
+
+        let sqllog_file_timeout = cfg
+            .sqllog
+            .as_ref()
+            .and_then(|s| s.file_timeout_secs)
+            .map(|v| {
+                if v == 0 {
+                    eprintln!("配置错误: sqllog.file_timeout_secs 不能为 0；请设置为正整数或删除该项以表示不限时");
+                    process::exit(2);
+                }
+                Duration::from_secs(v)
+            });
+
+        let sqllog_record_id = cfg
+            .sqllog
+            .as_ref()
+            .and_then(|s| s.record_id.as_deref())
+            .map_or(RecordIdMode::Disabled, |v| {
+                v.parse().unwrap_or_else(|e| {
+                    eprintln!("配置错误: sqllog.record_id 无效: {e}；可选值为 none/hash/snowflake");
+                    process::exit(2);
+                })
+            });
+
+        let sqllog_extract_plans =
+            cfg.sqllog.as_ref().and_then(|s| s.extract_plans).unwrap_or(false);
+
         (
             sqllog_dir,
             sqllog_chunk_size,
             parser_threads,
             sqllog_write_errors,
             sqllog_errors_out_path,
+            sqllog_errors_format,
+            sqllog_errors_table,
+            sqllog_file_timeout,
+            sqllog_record_id,
+            sqllog_extract_plans,
         )
     }
 
+    /// 解析 analyze 配置节：内存上限（默认 1024 MB，不能为 0）与临时目录
+    fn parse_analyze_config(cfg: &Self) -> (usize, Option<PathBuf>) {
+        let memory_limit_mb =
+            match cfg.analyze.as_ref().and_then(|a| a.memory_limit_mb) {
+                Some(0) => {
+                    eprintln!("配置错误: analyze.memory_limit_mb 不能为 0");
+                    process::exit(2);
+                }
+                Some(mb) => mb,
+                None => 1024,
+            };
+        let temp_dir = cfg.analyze.as_ref().and_then(|a| a.temp_dir.clone());
+        (memory_limit_mb, temp_dir)
+    }
+
+    /// 解析 `sqllog.max_memory_bytes`（不能为 0）
+    fn parse_memory_config(cfg: &Self) -> Option<usize> {
+        match cfg.sqllog.as_ref().and_then(|s| s.max_memory_bytes) {
+            Some(0) => {
+                eprintln!(
+                    "配置错误: sqllog.max_memory_bytes 不能为 0；请设置为正整数或删除该项以表示不限制"
+                );
+                process::exit(2);
+            }
+            limit => limit,
+        }
+    }
+
+    /// 解析 `sqllog.blank_fields`
+    fn parse_blank_fields_config(cfg: &Self) -> BlankFields {
+        cfg.sqllog.as_ref().and_then(|s| s.blank_fields.as_deref()).map_or(
+            BlankFields::default(),
+            |v| {
+                v.parse().unwrap_or_else(|e| {
+                    eprintln!("配置错误: sqllog.blank_fields 无效: {e}；可选值为 null/empty/raw");
+                    process::exit(2);
+                })
+            },
+        )
+    }
+
+    /// 解析 `sqllog.parse_mode`
+    fn parse_mode_config(cfg: &Self) -> ParseMode {
+        cfg.sqllog.as_ref().and_then(|s| s.parse_mode.as_deref()).map_or(
+            ParseMode::default(),
+            |v| {
+                v.parse().unwrap_or_else(|e| {
+                    eprintln!("配置错误: sqllog.parse_mode 无效: {e}；可选值为 strict/lenient");
+                    process::exit(2);
+                })
+            },
+        )
+    }
+
+    /// 解析 `sqllog.file_order` 与 `sqllog.file_priority`：只设置了
+    /// `file_priority` 时按 `priority` 顺序
+    fn parse_file_order_config(cfg: &Self) -> FileOrder {
+        let Some(section) = cfg.sqllog.as_ref() else {
+            return FileOrder::default();
+        };
+        let order = match section.file_order.as_deref() {
+            Some(v) => v.parse().unwrap_or_else(|e| {
+                eprintln!("配置错误: sqllog.file_order 无效: {e}；可选值为 directory/name/largest_first/round_robin/priority");
+                process::exit(2);
+            }),
+            None if section.file_priority.is_some() => {
+                FileOrder::Priority(Vec::new())
+            }
+            None => FileOrder::default(),
+        };
+        match order {
+            FileOrder::Priority(_) => {
+                let patterns =
+                    section.file_priority.clone().unwrap_or_default();
+                if patterns.is_empty() {
+                    eprintln!(
+                        "配置错误: sqllog.file_order = \"priority\" 需要非空的 sqllog.file_priority"
+                    );
+                    process::exit(2);
+                }
+                FileOrder::Priority(patterns)
+            }
+            order => order,
+        }
+    }
+
+    /// 解析 `sqllog.breaker_*`：未设置 `breaker_max_error_ratio` 时不检查
+    fn parse_breaker_config(cfg: &Self) -> Option<ErrorBreaker> {
+        let section = cfg.sqllog.as_ref()?;
+        let ratio = section.breaker_max_error_ratio?;
+        let window_mb =
+            section.breaker_window_mb.unwrap_or(DEFAULT_BREAKER_WINDOW_MB);
+        let breaker =
+            ErrorBreaker::new(ratio, window_mb.saturating_mul(1024 * 1024))
+                .unwrap_or_else(|e| {
+                    eprintln!("配置错误: sqllog.breaker_* 无效: {e}");
+                    process::exit(2);
+                });
+        Some(breaker)
+    }
+
+    /// 解析 `sqllog.sample_*`：未设置 `sample_rate` 时不抽样
+    fn parse_sample_config(cfg: &Self) -> Option<KeySample> {
+        let section = cfg.sqllog.as_ref()?;
+        let rate = section.sample_rate?;
+        let key = section.sample_key.as_deref().map_or_else(
+            Default::default,
+            |v| {
+                v.parse().unwrap_or_else(|e| {
+                    eprintln!("配置错误: sqllog.sample_key 无效: {e}；可选值为 session/user/trx");
+                    process::exit(2);
+                })
+            },
+        );
+        let sample = KeySample::new(key, rate).unwrap_or_else(|e| {
+            eprintln!("配置错误: sqllog.sample_rate 无效: {e}");
+            process::exit(2);
+        });
+        Some(sample.with_seed(section.sample_seed.unwrap_or(0)))
+    }
+
+    /// 解析 `sqllog.trace_lines` 与 `sqllog.trace_patterns`
+    fn parse_trace_lines_config(cfg: &Self) -> TraceLines {
+        let Some(section) = cfg.sqllog.as_ref() else {
+            return TraceLines::default();
+        };
+        let mode = section.trace_lines.as_deref().map_or_else(
+            Default::default,
+            |v| {
+                v.parse().unwrap_or_else(|e| {
+                    eprintln!("配置错误: sqllog.trace_lines 无效: {e}；可选值为 error/skip/collect");
+                    process::exit(2);
+                })
+            },
+        );
+        let mut trace_lines = TraceLines::new(mode);
+        for (category, pattern) in section.trace_patterns.iter().flatten() {
+            trace_lines = trace_lines
+                .with_rule(category, pattern)
+                .unwrap_or_else(|e| {
+                    eprintln!(
+                        "配置错误: sqllog.trace_patterns.{category} 正则无效: {e}"
+                    );
+                    process::exit(2);
+                });
+        }
+        trace_lines
+    }
+
+    /// 解析 `sqllog.prefilter`
+    fn parse_prefilter_config(cfg: &Self) -> Option<Prefilter> {
+        let pattern = cfg.sqllog.as_ref()?.prefilter.as_deref()?;
+        Some(Prefilter::new(pattern).unwrap_or_else(|e| {
+            eprintln!("配置错误: sqllog.prefilter 正则无效: {e}");
+            process::exit(2);
+        }))
+    }
+
+    /// 解析 plugin 配置节：未设置 `command` 时不启用插件
+    fn parse_plugin_config(
+        cfg: &Self,
+    ) -> (Option<Vec<String>>, Option<Duration>) {
+        let Some(section) = cfg.plugin.as_ref() else {
+            return (None, None);
+        };
+        let command = section.command.clone();
+        if command.as_ref().is_some_and(Vec::is_empty) {
+            eprintln!("配置错误: plugin.command 不能为空");
+            process::exit(2);
+        }
+        let timeout = match section.timeout_ms {
+            Some(0) => {
+                eprintln!("配置错误: plugin.timeout_ms 不能为 0");
+                process::exit(2);
+            }
+            ms => ms.map(Duration::from_millis),
+        };
+        (command, timeout)
+    }
+
+    /// 解析 notify 配置节：未设置 `webhook_url` 时不通知
+    fn parse_notify_config(cfg: &Self) -> Option<NotifyConfig> {
+        let section = cfg.notify.as_ref()?;
+        let url = section.webhook_url.as_deref()?;
+        let fail = |msg: &str| -> ! {
+            eprintln!("配置错误: notify.{msg}");
+            process::exit(2);
+        };
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            fail(&format!(
+                "webhook_url 必须以 http:// 或 https:// 开头: {url}"
+            ));
+        }
+        let mut notify = NotifyConfig::new(url);
+        if let Some(flavor) = &section.flavor {
+            notify.flavor = flavor
+                .parse()
+                .unwrap_or_else(|e| fail(&format!("flavor 无效: {e}")));
+        }
+        if let Some(on_complete) = section.on_complete {
+            notify.on_complete = on_complete;
+        }
+        if let Some(ratio) = section.max_error_ratio {
+            if !(0.0..=1.0).contains(&ratio) {
+                fail("max_error_ratio 必须在 0 到 1 之间");
+            }
+            notify.max_error_ratio = Some(ratio);
+        }
+        notify.max_slow_queries = section.max_slow_queries;
+        match section.slow_query_ms {
+            Some(ms) if ms <= 0 => fail("slow_query_ms 必须为正整数"),
+            Some(ms) => notify.slow_query_ms = ms,
+            None => {}
+        }
+        match section.timeout_secs {
+            Some(0) => fail("timeout_secs 不能为 0"),
+            Some(secs) => notify.timeout_secs = secs,
+            None => {}
+        }
+        if let Some(retries) = section.retries {
+            notify.retries = retries;
+        }
+        Some(notify)
+    }
+
     /// 将解析得到的 Config 合并为 RuntimeConfig，应用默认值并进行必要的校验。
     fn merge_to_runtime_config(cfg: &Self) -> RuntimeConfig {
-        let (db_path, use_in_memory) = Self::parse_database_config(cfg);
+        let (db_path, use_in_memory, insert_rate_limit, insert_auto_tune) =
+            Self::parse_database_config(cfg);
         let (enable_stdout, log_dir, log_level) = Self::parse_log_config(cfg);
+        let log_otlp_endpoint =
+            cfg.log.as_ref().and_then(|l| l.otlp_endpoint.clone());
+        let log_otlp_service_name =
+            cfg.log.as_ref().and_then(|l| l.otlp_service_name.clone());
         let (export_enabled, export_format, export_out_path, export_options) =
             Self::parse_export_config(cfg);
         let (
@@ -368,23 +1159,129 @@ impl Config {
             parser_threads,
             sqllog_write_errors,
             sqllog_errors_out_path,
+            sqllog_errors_format,
+            sqllog_errors_table,
+            sqllog_file_timeout,
+            sqllog_record_id,
+            sqllog_extract_plans,
         ) = Self::parse_sqllog_config(cfg);
+        let cluster_by_time = cfg
+            .database
+            .as_ref()
+            .and_then(|d| d.cluster_by_time)
+            .unwrap_or(false);
+        let db_resume =
+            cfg.database.as_ref().and_then(|d| d.resume).unwrap_or(false);
+        let sqllog_link_exec_phase = cfg
+            .sqllog
+            .as_ref()
+            .and_then(|s| s.link_exec_phase)
+            .unwrap_or(false);
+        let db_split_cold =
+            cfg.database.as_ref().and_then(|d| d.split_cold).unwrap_or(false);
+        if db_split_cold && sqllog_record_id == RecordIdMode::Disabled {
+            eprintln!(
+                "配置错误: database.split_cold 需要设置 sqllog.record_id（hash 或 snowflake）"
+            );
+            process::exit(2);
+        }
+        let db_record_runs =
+            cfg.database.as_ref().and_then(|d| d.record_runs).unwrap_or(false);
+        let max_memory_bytes = Self::parse_memory_config(cfg);
+        let sqllog_blank_fields = Self::parse_blank_fields_config(cfg);
+        let sqllog_sample = Self::parse_sample_config(cfg);
+        let sqllog_trace_lines = Self::parse_trace_lines_config(cfg);
+        let sqllog_prefilter = Self::parse_prefilter_config(cfg);
+        let sqllog_parse_mode = Self::parse_mode_config(cfg);
+        let sqllog_file_order = Self::parse_file_order_config(cfg);
+        let sqllog_error_breaker = Self::parse_breaker_config(cfg);
+        let sqllog_cache_dir =
+            cfg.sqllog.as_ref().and_then(|s| s.cache_dir.clone());
+        let (analyze_memory_limit_mb, analyze_temp_dir) =
+            Self::parse_analyze_config(cfg);
+        let jobs_state_path =
+            cfg.jobs.as_ref().and_then(|j| j.state_path.clone());
+        let jobs_max_retries = cfg
+            .jobs
+            .as_ref()
+            .and_then(|j| j.max_retries)
+            .unwrap_or(DEFAULT_MAX_RETRIES);
+        let notify = Self::parse_notify_config(cfg);
+        let history_state_path =
+            cfg.history.as_ref().and_then(|h| h.state_path.clone());
+        let classify_rules = cfg
+            .classify
+            .as_ref()
+            .and_then(|c| c.rules.clone())
+            .unwrap_or_default();
+        if let Err(e) = Classifier::from_configs(&classify_rules) {
+            eprintln!("配置错误: classify.rules 无效: {e}");
+            process::exit(2);
+        }
+        let classify_forbidden_out =
+            cfg.classify.as_ref().and_then(|c| c.forbidden_out.clone());
+        let apps_aliases = cfg
+            .apps
+            .as_ref()
+            .and_then(|a| a.aliases.clone())
+            .unwrap_or_default();
+        let apps_top_fingerprints = cfg
+            .apps
+            .as_ref()
+            .and_then(|a| a.top_fingerprints)
+            .unwrap_or(DEFAULT_TOP_FINGERPRINTS);
+        let (plugin_command, plugin_timeout) = Self::parse_plugin_config(cfg);
 
         RuntimeConfig {
             db_path,
             enable_stdout,
             log_dir,
             log_level,
+            log_otlp_endpoint,
+            log_otlp_service_name,
             sqllog_dir,
             sqllog_chunk_size,
             parser_threads,
             sqllog_write_errors,
             sqllog_errors_out_path,
+            sqllog_errors_format,
+            sqllog_errors_table,
+            sqllog_file_timeout,
+            sqllog_record_id,
+            sqllog_extract_plans,
+            sqllog_link_exec_phase,
+            sqllog_prefilter,
+            sqllog_blank_fields,
+            sqllog_sample,
+            sqllog_trace_lines,
+            sqllog_parse_mode,
+            sqllog_file_order,
+            sqllog_error_breaker,
+            sqllog_cache_dir,
             export_enabled,
             export_format,
             export_out_path,
             export_options,
             use_in_memory,
+            insert_rate_limit,
+            insert_auto_tune,
+            cluster_by_time,
+            db_resume,
+            db_split_cold,
+            db_record_runs,
+            max_memory_bytes,
+            analyze_memory_limit_mb,
+            analyze_temp_dir,
+            jobs_state_path,
+            jobs_max_retries,
+            notify,
+            history_state_path,
+            classify_rules,
+            classify_forbidden_out,
+            apps_aliases,
+            apps_top_fingerprints,
+            plugin_command,
+            plugin_timeout,
         }
     }
 }
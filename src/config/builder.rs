@@ -0,0 +1,302 @@
+//! 运行时配置构造器 - 供嵌入方以代码构造并校验 `RuntimeConfig`
+//!
+//! `RuntimeConfig` 的字段都是公开的，直接赋值时诸如 `parser_threads = 0` 或
+//! `sqllog_chunk_size = Some(0)` 这类取值不会被拦截。构造器从默认配置出发，
+//! 可选地读取环境变量，最后在 [`RuntimeConfigBuilder::build`] 中一次性校验
+//! 所有字段，返回每个非法字段对应的 [`ConfigError`]。
+//!
+//! 支持的环境变量：
+//! - `SQLLOG_DIR`：日志目录
+//! - `SQLLOG_DB_PATH`：数据库文件路径
+//! - `SQLLOG_CHUNK_SIZE`：解析分块大小
+//! - `SQLLOG_PARSER_THREADS`：解析线程数
+//!
+//! ```rust
+//! use sqllog_analysis::config::{ConfigError, RuntimeConfigBuilder};
+//!
+//! let config = RuntimeConfigBuilder::new()
+//!     .sqllog_dir("logs")
+//!     .chunk_size(Some(5000))
+//!     .parser_threads(4)
+//!     .build()
+//!     .unwrap();
+//! assert_eq!(config.parser_threads, 4);
+//!
+//! let errors = RuntimeConfigBuilder::new().parser_threads(1000).build().unwrap_err();
+//! assert!(matches!(errors[0], ConfigError::OutOfRange { field: "sqllog.parser_threads", .. }));
+//! ```
+
+use super::RuntimeConfig;
+use crate::database::ExportFormat;
+use std::path::PathBuf;
+use std::time::Duration;
+use thiserror::Error;
+
+/// 解析线程数上限
+pub const MAX_PARSER_THREADS: usize = 256;
+
+/// 解析分块大小上限（单块记录数）
+pub const MAX_CHUNK_SIZE: usize = 10_000_000;
+
+/// 配置字段校验错误
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum ConfigError {
+    /// 字段不能为 0
+    #[error("{field} 不能为 0：{hint}")]
+    Zero { field: &'static str, hint: &'static str },
+
+    /// 字段超出允许范围
+    #[error("{field} = {value} 超出范围 [{min}, {max}]")]
+    OutOfRange { field: &'static str, value: u64, min: u64, max: u64 },
+
+    /// 字段取值无法识别
+    #[error("{field} 取值无效 {value:?}：{reason}")]
+    Invalid { field: &'static str, value: String, reason: String },
+}
+
+/// `RuntimeConfig` 构造器
+#[derive(Debug, Clone)]
+pub struct RuntimeConfigBuilder {
+    config: RuntimeConfig,
+    /// 读取环境变量时遇到的错误，在 `build` 时一并返回
+    env_errors: Vec<ConfigError>,
+}
+
+impl Default for RuntimeConfigBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuntimeConfigBuilder {
+    /// 以默认配置（与未找到配置文件时一致）为起点
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_config(RuntimeConfig::default())
+    }
+
+    /// 以已有配置为起点
+    #[must_use]
+    pub const fn from_config(config: RuntimeConfig) -> Self {
+        Self { config, env_errors: Vec::new() }
+    }
+
+    /// 以默认配置为起点，并用环境变量覆盖对应字段
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::new().with_env_vars(|key| std::env::var(key).ok())
+    }
+
+    /// 用 `lookup` 提供的环境变量覆盖对应字段（便于测试时注入）
+    #[must_use]
+    pub fn with_env_vars<F>(mut self, lookup: F) -> Self
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        if let Some(dir) = lookup("SQLLOG_DIR") {
+            self.config.sqllog_dir = Some(PathBuf::from(dir));
+        }
+        if let Some(path) = lookup("SQLLOG_DB_PATH") {
+            self.config.db_path = path;
+        }
+        if let Some(n) = self.env_number(&lookup, "SQLLOG_CHUNK_SIZE") {
+            self.config.sqllog_chunk_size = Some(n);
+        }
+        if let Some(n) = self.env_number(&lookup, "SQLLOG_PARSER_THREADS") {
+            self.config.parser_threads = n;
+        }
+        self
+    }
+
+    fn env_number<F>(&mut self, lookup: &F, key: &'static str) -> Option<usize>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let value = lookup(key)?;
+        match value.trim().parse() {
+            Ok(n) => Some(n),
+            Err(e) => {
+                self.env_errors.push(ConfigError::Invalid {
+                    field: key,
+                    value,
+                    reason: format!("需要非负整数（{e}）"),
+                });
+                None
+            }
+        }
+    }
+
+    /// 日志目录
+    #[must_use]
+    pub fn sqllog_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.config.sqllog_dir = Some(dir.into());
+        self
+    }
+
+    /// 解析分块大小，`None` 表示不分块
+    #[must_use]
+    pub const fn chunk_size(mut self, chunk_size: Option<usize>) -> Self {
+        self.config.sqllog_chunk_size = chunk_size;
+        self
+    }
+
+    /// 解析线程数
+    #[must_use]
+    pub const fn parser_threads(mut self, threads: usize) -> Self {
+        self.config.parser_threads = threads;
+        self
+    }
+
+    /// 单个文件的解析时限
+    #[must_use]
+    pub const fn file_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.sqllog_file_timeout = timeout;
+        self
+    }
+
+    /// 数据库文件路径（同时关闭内存模式）
+    #[must_use]
+    pub fn db_path(mut self, path: impl Into<String>) -> Self {
+        self.config.db_path = path.into();
+        self.config.use_in_memory = false;
+        self
+    }
+
+    /// 是否使用内存数据库
+    #[must_use]
+    pub const fn in_memory(mut self, in_memory: bool) -> Self {
+        self.config.use_in_memory = in_memory;
+        self
+    }
+
+    /// 启用导出，`format` 可为逗号分隔的多个格式
+    #[must_use]
+    pub fn export(
+        mut self,
+        format: impl Into<String>,
+        out_path: impl Into<PathBuf>,
+    ) -> Self {
+        self.config.export_enabled = true;
+        self.config.export_format = format.into();
+        self.config.export_out_path = Some(out_path.into());
+        self
+    }
+
+    /// 在途记录的内存上限（字节）
+    #[must_use]
+    pub const fn max_memory_bytes(mut self, bytes: Option<usize>) -> Self {
+        self.config.max_memory_bytes = bytes;
+        self
+    }
+
+    /// 直接修改底层配置中构造器未覆盖的字段
+    #[must_use]
+    pub fn with(mut self, f: impl FnOnce(&mut RuntimeConfig)) -> Self {
+        f(&mut self.config);
+        self
+    }
+
+    /// 校验全部字段，返回所有错误
+    ///
+    /// # Errors
+    /// 存在非法字段时返回错误列表（按字段顺序，环境变量错误在前）
+    pub fn validate(&self) -> Result<(), Vec<ConfigError>> {
+        let mut errors = self.env_errors.clone();
+        errors.extend(validate(&self.config));
+        if errors.is_empty() { Ok(()) } else { Err(errors) }
+    }
+
+    /// 校验并返回配置
+    ///
+    /// # Errors
+    /// 存在非法字段时返回错误列表
+    pub fn build(self) -> Result<RuntimeConfig, Vec<ConfigError>> {
+        self.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// 取值不在 `[min, max]` 内时构造范围错误
+fn out_of_range(
+    field: &'static str,
+    value: usize,
+    min: usize,
+    max: usize,
+) -> Option<ConfigError> {
+    (!(min..=max).contains(&value)).then_some(ConfigError::OutOfRange {
+        field,
+        value: value as u64,
+        min: min as u64,
+        max: max as u64,
+    })
+}
+
+/// 校验运行时配置的各字段
+pub(super) fn validate(config: &RuntimeConfig) -> Vec<ConfigError> {
+    let zero = |field, hint| ConfigError::Zero { field, hint };
+    let mut errors = Vec::new();
+
+    match config.sqllog_chunk_size {
+        Some(0) => {
+            errors.push(zero("sqllog.chunk_size", "不分块时请使用 None"))
+        }
+        Some(n) => errors.extend(out_of_range(
+            "sqllog.chunk_size",
+            n,
+            1,
+            MAX_CHUNK_SIZE,
+        )),
+        None => {}
+    }
+    errors.extend(out_of_range(
+        "sqllog.parser_threads",
+        config.parser_threads,
+        1,
+        MAX_PARSER_THREADS,
+    ));
+    if config.sqllog_file_timeout == Some(Duration::ZERO) {
+        errors.push(zero("sqllog.file_timeout_secs", "不限时请使用 None"));
+    }
+    if config.max_memory_bytes == Some(0) {
+        errors.push(zero("sqllog.max_memory_bytes", "不限制请使用 None"));
+    }
+    if config.analyze_memory_limit_mb == 0 {
+        errors.push(zero("analyze.memory_limit_mb", "请设置为正整数"));
+    }
+
+    let export = &config.export_options;
+    if export.file_size_bytes == Some(0) {
+        errors.push(zero("export.file_size_bytes", "不限制请使用 None"));
+    }
+    if export.description_max_bytes == Some(0) {
+        errors.push(zero("export.description_max_bytes", "不截断请使用 None"));
+    }
+    if export.top_sessions == Some(0) {
+        errors.push(zero("export.top_sessions", "导出全部记录请使用 None"));
+    }
+    if config.export_enabled {
+        for format in config.export_format.split(',') {
+            if let Err(reason) = format.trim().parse::<ExportFormat>() {
+                errors.push(ConfigError::Invalid {
+                    field: "export.format",
+                    value: format.to_string(),
+                    reason,
+                });
+            }
+        }
+    }
+
+    if let Some(tune) = &config.insert_auto_tune {
+        if tune.min_batch == 0 || tune.min_batch > tune.max_batch {
+            errors.push(ConfigError::Invalid {
+                field: "database.auto_tune_min_batch",
+                value: tune.min_batch.to_string(),
+                reason: format!(
+                    "须为正整数且不大于 auto_tune_max_batch（{}）",
+                    tune.max_batch
+                ),
+            });
+        }
+    }
+    errors
+}
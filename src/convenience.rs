@@ -0,0 +1,188 @@
+//! 一次性便捷接口 - 解析或导出文件
+//!
+//! 面向「读一个文件、拿到记录」或「把一个文件转成 CSV/JSON」这类最常见的
+//! 场景，隐藏配置、解析器与导出器的组装细节；多个文件可合并导出并按来源
+//! 文件统计。需要分块回调、并行处理、错误输出或导出选项时，请直接使用
+//! [`crate::sqllog::Sqllog::parse_with_options`] 与
+//! `crate::database::DuckDbProvider`。导出需要 `database` 特性。
+
+#[cfg(feature = "database")]
+use crate::config::{ExportOptions, RuntimeConfig};
+#[cfg(feature = "database")]
+use crate::database::{DatabaseProvider, DuckDbProvider, ExportFormat};
+use crate::sqllog::{ParseOptions, SResult, Sqllog};
+#[cfg(feature = "database")]
+use anyhow::{Context, Result};
+use std::path::Path;
+#[cfg(feature = "database")]
+use std::path::PathBuf;
+
+/// 导出时每批写入数据库的记录数
+#[cfg(feature = "database")]
+const EXPORT_CHUNK_SIZE: usize = 10_000;
+
+/// [`export_file`] / [`export_files`] 的统计结果
+#[cfg(feature = "database")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportStats {
+    /// 解析出的记录数
+    pub records_parsed: u64,
+    /// 无法解析而被跳过的段数
+    pub parse_errors: u64,
+    /// 写入输出文件的记录数
+    pub records_exported: u64,
+    sources: Vec<SourceStats>,
+}
+
+/// 单个来源文件的导出统计
+#[cfg(feature = "database")]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceStats {
+    /// 来源文件
+    pub path: PathBuf,
+    /// 解析出的记录数
+    pub records_parsed: u64,
+    /// 无法解析而被跳过的段数
+    pub parse_errors: u64,
+    /// 写入数据库的记录数
+    pub records_inserted: u64,
+    /// 文件无法读取或记录写入失败时的错误信息，出错后该文件不再写入
+    pub error: Option<String>,
+}
+
+#[cfg(feature = "database")]
+impl ExportStats {
+    /// 按来源文件的统计，顺序与传入的文件一致
+    #[must_use]
+    pub fn by_source(&self) -> &[SourceStats] {
+        &self.sources
+    }
+
+    /// 出错的来源文件
+    pub fn failed_sources(&self) -> impl Iterator<Item = &SourceStats> {
+        self.sources.iter().filter(|source| source.error.is_some())
+    }
+}
+
+/// 解析整个文件并返回全部记录，无法解析的段被跳过
+///
+/// # Errors
+/// 当文件无法打开或读取时返回错误
+pub fn parse_file<P: AsRef<Path>>(path: P) -> SResult<Vec<Sqllog>> {
+    let mut records = Vec::new();
+    Sqllog::parse_with_options(
+        path.as_ref(),
+        &ParseOptions::default(),
+        |chunk| records.extend_from_slice(chunk),
+        |_| {},
+    )?;
+    Ok(records)
+}
+
+/// 解析文件并以指定格式导出到 `out_path`
+///
+/// 记录经由内存 `DuckDB` 数据库中转，不会在磁盘上留下数据库文件。
+///
+/// # Errors
+/// 当文件无法读取、记录写入失败或输出文件无法写入时返回错误
+#[cfg(feature = "database")]
+pub fn export_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    path: P,
+    format: ExportFormat,
+    out_path: Q,
+) -> Result<ExportStats> {
+    let mut provider = in_memory_provider()?;
+    let (source, result) = export_source(&mut provider, path.as_ref());
+    result?;
+    finish_export(&provider, vec![source], format, out_path.as_ref())
+}
+
+/// 解析多个文件并合并导出到同一个 `out_path`
+///
+/// 每个文件的记录分批写入同一个内存 `DuckDB` 数据库，批次按来源文件记账，
+/// 可通过 [`ExportStats::by_source`] 查看各文件的解析与写入情况。某个文件
+/// 无法读取或写入失败时记录在该文件的 [`SourceStats::error`] 中并继续处理
+/// 其余文件，已写入的记录仍会导出。
+///
+/// # Errors
+/// 当数据库无法创建或输出文件无法写入时返回错误
+#[cfg(feature = "database")]
+pub fn export_files<I, P, Q>(
+    paths: I,
+    format: ExportFormat,
+    out_path: Q,
+) -> Result<ExportStats>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut provider = in_memory_provider()?;
+    let sources = paths
+        .into_iter()
+        .map(|path| {
+            let (mut source, result) =
+                export_source(&mut provider, path.as_ref());
+            source.error = result.err().map(|e| format!("{e:#}"));
+            source
+        })
+        .collect();
+    finish_export(&provider, sources, format, out_path.as_ref())
+}
+
+#[cfg(feature = "database")]
+fn in_memory_provider() -> Result<DuckDbProvider> {
+    let config = RuntimeConfig { use_in_memory: true, ..Default::default() };
+    let mut provider = DuckDbProvider::new(&config)?;
+    provider.initialize()?;
+    Ok(provider)
+}
+
+/// 解析单个文件并写入数据库，返回该文件的统计与第一个错误
+#[cfg(feature = "database")]
+fn export_source(
+    provider: &mut DuckDbProvider,
+    path: &Path,
+) -> (SourceStats, Result<()>) {
+    let mut source =
+        SourceStats { path: path.to_path_buf(), ..Default::default() };
+    let mut insert_error = None;
+    let parsed = Sqllog::parse_with_options(
+        path,
+        &ParseOptions::with_chunk_size(EXPORT_CHUNK_SIZE),
+        |chunk| {
+            source.records_parsed += chunk.len() as u64;
+            if insert_error.is_none() {
+                match provider.insert_batch(chunk) {
+                    Ok(n) => source.records_inserted += n as u64,
+                    Err(e) => insert_error = Some(e),
+                }
+            }
+        },
+        |errors| source.parse_errors += errors.len() as u64,
+    )
+    .with_context(|| format!("无法解析文件: {}", path.display()));
+    let result = parsed.and_then(|()| insert_error.map_or(Ok(()), Err));
+    (source, result)
+}
+
+/// 导出数据库中的全部记录并汇总各来源文件的统计
+#[cfg(feature = "database")]
+fn finish_export(
+    provider: &DuckDbProvider,
+    sources: Vec<SourceStats>,
+    format: ExportFormat,
+    out_path: &Path,
+) -> Result<ExportStats> {
+    let report = provider.export_with_options(
+        format,
+        &out_path.to_string_lossy(),
+        &ExportOptions::default(),
+    )?;
+    Ok(ExportStats {
+        records_parsed: sources.iter().map(|s| s.records_parsed).sum(),
+        parse_errors: sources.iter().map(|s| s.parse_errors).sum(),
+        records_exported: report.records_exported,
+        sources,
+    })
+}
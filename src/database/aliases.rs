@@ -0,0 +1,82 @@
+// 输出列别名 - 按下游 BI 表结构重命名导出列
+//
+// 别名在配置中以「源列名 = 输出列名」给出，CSV/JSON 导出在 COPY 的 SELECT 中
+// 以 `AS "别名"` 改名，数据库输出则额外提供一个带别名的视图
+// （`ALIASED_VIEW`），内部的 `sqllogs` 表结构保持不变。
+// 源列名使用表中的列名，`user` 作为 `username` 的同义词也被接受。
+
+use super::duckdb_impl::{SQLLOG_COLUMNS, sql_identifier};
+use std::collections::BTreeMap;
+
+/// 配置了别名时在数据库中创建的只读视图名
+pub const ALIASED_VIEW: &str = "sqllogs_aliased";
+
+/// 输出列别名表
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ColumnAliases {
+    /// 源列名 -> 输出列名
+    aliases: BTreeMap<String, String>,
+}
+
+impl ColumnAliases {
+    /// 从「源列名 -> 输出列名」映射构造并校验
+    ///
+    /// # Errors
+    /// 当源列不存在、别名为空，或改名后出现重复列名时返回错误描述
+    pub fn from_map(map: &BTreeMap<String, String>) -> Result<Self, String> {
+        let mut aliases = BTreeMap::new();
+        for (source, alias) in map {
+            let column = if source == "user" { "username" } else { source };
+            if !SQLLOG_COLUMNS.contains(&column) {
+                return Err(format!("未知的列: {source}"));
+            }
+            if alias.trim().is_empty() {
+                return Err(format!("列 {source} 的别名不能为空"));
+            }
+            aliases.insert(column.to_string(), alias.clone());
+        }
+
+        let parsed = Self { aliases };
+        let mut seen = std::collections::BTreeSet::new();
+        for column in SQLLOG_COLUMNS {
+            let name = parsed.output_name(column);
+            if !seen.insert(name) {
+                return Err(format!("别名后出现重复的列名: {name}"));
+            }
+        }
+        Ok(parsed)
+    }
+
+    /// 是否未配置任何别名
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.aliases.is_empty()
+    }
+
+    /// 列的输出名称；未配置别名时为原列名
+    #[must_use]
+    pub fn output_name<'a>(&'a self, column: &'a str) -> &'a str {
+        self.aliases.get(column).map_or(column, String::as_str)
+    }
+
+    /// 构造 SELECT 列表中的一项：`expr` 为取值表达式，`column` 为源列名
+    #[must_use]
+    pub fn select_item(&self, expr: &str, column: &str) -> String {
+        let name = self.output_name(column);
+        if expr == name {
+            expr.to_string()
+        } else {
+            format!("{expr} AS {}", sql_identifier(name))
+        }
+    }
+
+    /// 全部列按别名输出的 SELECT 列表
+    #[must_use]
+    pub fn select_list(&self) -> String {
+        SQLLOG_COLUMNS
+            .iter()
+            .map(|col| self.select_item(col, col))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
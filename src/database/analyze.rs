@@ -0,0 +1,193 @@
+// 聚合分析执行器 - 内存聚合与 DuckDB 溢写
+//
+// 默认在内存中逐块聚合；当分组状态（分组键、百分位样本）的估算内存超过
+// `analyze_memory_limit_mb` 时，放弃内存结果，把全部输入写入临时 DuckDB
+// 数据库，再将同一查询翻译为 SQL 执行。DuckDB 的聚合算子会在内存不足时
+// 自行溢写到磁盘，因此输入规模只受磁盘空间限制。
+//
+// 溢写前已处理的文件会被重新解析写入临时库，换取不在内存中保留原始记录。
+
+use super::{DatabaseProvider, DuckDbProvider};
+use crate::analysis::{AggregateQuery, AggregateResult, Aggregator};
+use crate::config::RuntimeConfig;
+use crate::sqllog::{ParseOptions, Sqllog};
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 未配置分块大小时，analyze 使用的解析块大小
+const ANALYZE_CHUNK_SIZE: usize = 10_000;
+
+/// 聚合分析结果
+#[derive(Debug, Clone)]
+pub struct AnalyzeOutcome {
+    /// 聚合结果表
+    pub result: AggregateResult,
+    /// 参与聚合的记录数
+    pub records: u64,
+    /// 解析错误数
+    pub parse_errors: usize,
+    /// 是否改用了临时 `DuckDB` 数据库
+    pub spilled: bool,
+}
+
+/// 聚合分析执行器
+#[derive(Debug)]
+pub struct AnalyzeRunner<'a> {
+    runtime: &'a RuntimeConfig,
+    query: AggregateQuery,
+    memory_limit: usize,
+}
+
+impl<'a> AnalyzeRunner<'a> {
+    /// 以运行时配置中的内存上限创建执行器
+    #[must_use]
+    pub const fn new(
+        runtime: &'a RuntimeConfig,
+        query: AggregateQuery,
+    ) -> Self {
+        Self {
+            runtime,
+            query,
+            memory_limit: runtime
+                .analyze_memory_limit_mb
+                .saturating_mul(1 << 20),
+        }
+    }
+
+    /// 覆盖内存上限（字节）
+    #[must_use]
+    pub const fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = bytes;
+        self
+    }
+
+    fn parse_options(&self) -> ParseOptions {
+        let mut options = self.runtime.parse_options();
+        if options.chunk_size == 0 {
+            options.chunk_size = ANALYZE_CHUNK_SIZE;
+        }
+        options
+    }
+
+    /// 对全部文件执行聚合
+    ///
+    /// # Errors
+    /// 当文件无法读取、临时数据库创建或写入失败、聚合 SQL 执行失败时返回错误
+    pub fn run<P: AsRef<Path>>(&self, files: &[P]) -> Result<AnalyzeOutcome> {
+        let options = self.parse_options();
+        let mut aggregator = Aggregator::new(self.query.clone());
+        let mut parse_errors = 0usize;
+
+        for file in files {
+            let path = file.as_ref();
+            let mut exceeded = false;
+            Sqllog::parse_with_options(
+                path,
+                &options,
+                |records| {
+                    if exceeded {
+                        return;
+                    }
+                    aggregator.observe(records);
+                    exceeded = aggregator.estimated_bytes() > self.memory_limit;
+                },
+                |errors| parse_errors += errors.len(),
+            )
+            .with_context(|| format!("解析文件失败: {}", path.display()))?;
+
+            if exceeded {
+                log::warn!(
+                    "内存聚合状态约 {} MB，超过上限 {} MB（{} 个分组），改用临时 DuckDB 数据库",
+                    aggregator.estimated_bytes() >> 20,
+                    self.memory_limit >> 20,
+                    aggregator.groups()
+                );
+                drop(aggregator);
+                return self.run_on_disk(files, &options);
+            }
+        }
+
+        Ok(AnalyzeOutcome {
+            records: aggregator.records(),
+            result: aggregator.finish(),
+            parse_errors,
+            spilled: false,
+        })
+    }
+
+    /// 将全部文件写入临时数据库后以 SQL 聚合
+    fn run_on_disk<P: AsRef<Path>>(
+        &self,
+        files: &[P],
+        options: &ParseOptions,
+    ) -> Result<AnalyzeOutcome> {
+        let temp = TempDatabase::new(self.runtime.analyze_temp_dir.as_deref());
+        let mut config = self.runtime.clone();
+        config.use_in_memory = false;
+        config.db_path = temp.path.to_string_lossy().to_string();
+
+        let mut provider = DuckDbProvider::new(&config)?;
+        provider.initialize()?;
+        let limit_mb = (self.memory_limit >> 20).max(64);
+        provider.execute_sql(&format!("SET memory_limit = '{limit_mb}MB'"))?;
+
+        let mut parse_errors = 0usize;
+        for file in files {
+            let path = file.as_ref();
+            let mut insert_error = None;
+            Sqllog::parse_with_options(
+                path,
+                options,
+                |records| {
+                    if insert_error.is_none() {
+                        if let Err(e) = provider.insert_batch(records) {
+                            insert_error = Some(e);
+                        }
+                    }
+                },
+                |errors| parse_errors += errors.len(),
+            )
+            .with_context(|| format!("解析文件失败: {}", path.display()))?;
+            if let Some(e) = insert_error {
+                return Err(e.context(format!(
+                    "写入临时数据库失败: {}",
+                    path.display()
+                )));
+            }
+        }
+
+        Ok(AnalyzeOutcome {
+            records: provider.count_records()?,
+            result: provider.aggregate(&self.query)?,
+            parse_errors,
+            spilled: true,
+        })
+    }
+}
+
+/// 临时数据库文件，离开作用域时连同 WAL 一起删除
+struct TempDatabase {
+    path: PathBuf,
+}
+
+impl TempDatabase {
+    fn new(dir: Option<&Path>) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos());
+        let name =
+            format!("sqllog_analyze_{}_{nanos}.duckdb", std::process::id());
+        let dir = dir.map_or_else(std::env::temp_dir, Path::to_path_buf);
+        Self { path: dir.join(name) }
+    }
+}
+
+impl Drop for TempDatabase {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+        let mut wal = self.path.clone().into_os_string();
+        wal.push(".wal");
+        let _ = std::fs::remove_file(wal);
+    }
+}
@@ -0,0 +1,81 @@
+// Arrow 批次 - 以 Arrow `RecordBatch` 形式提供解析结果
+//
+// 使用 duckdb 依赖中已有的 arrow（`duckdb::arrow`），不额外引入依赖。
+// 列名、顺序与类型由 sqllogs 表结构（`SQLLOG_TABLE`）生成：文本列为 Utf8，ep 为 Int32，
+// 数值列为 Int64，record_id 为 UInt64，partial 为 Boolean；执行计划以 JSON
+// 文本保存。
+
+use super::schema::{ColumnType, SQLLOG_TABLE};
+use crate::sqllog::Sqllog;
+use duckdb::arrow::array::{
+    ArrayRef, BooleanArray, Int32Array, Int64Array, StringArray, UInt64Array,
+};
+use duckdb::arrow::datatypes::{DataType, Field, Schema, SchemaRef};
+use duckdb::arrow::error::ArrowError;
+use duckdb::arrow::record_batch::RecordBatch;
+use std::sync::Arc;
+
+/// 与 sqllogs 表对应的 Arrow schema
+#[must_use]
+pub fn sqllog_schema() -> SchemaRef {
+    let fields: Vec<Field> = SQLLOG_TABLE
+        .columns
+        .iter()
+        .map(|column| {
+            let data_type = match column.ty {
+                ColumnType::Integer => DataType::Int32,
+                ColumnType::BigInt => DataType::Int64,
+                ColumnType::UBigInt => DataType::UInt64,
+                ColumnType::Boolean => DataType::Boolean,
+                ColumnType::Char(_)
+                | ColumnType::Varchar(_)
+                | ColumnType::Text => DataType::Utf8,
+            };
+            Field::new(column.name, data_type, !column.not_null)
+        })
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+/// 把一批记录转换为 `RecordBatch`
+///
+/// # Errors
+/// 当列与 schema 不一致时返回 Arrow 错误（正常情况下不会发生）
+pub fn records_to_batch(records: &[Sqllog]) -> Result<RecordBatch, ArrowError> {
+    let text = |f: fn(&Sqllog) -> Option<&str>| -> ArrayRef {
+        Arc::new(records.iter().map(f).collect::<StringArray>())
+    };
+    let long = |f: fn(&Sqllog) -> Option<i64>| -> ArrayRef {
+        Arc::new(records.iter().map(f).collect::<Int64Array>())
+    };
+    let columns: Vec<ArrayRef> = vec![
+        text(|r| Some(&r.occurrence_time)),
+        Arc::new(records.iter().map(|r| Some(r.ep)).collect::<Int32Array>()),
+        text(|r| r.session.as_deref()),
+        text(|r| r.thread.as_deref()),
+        text(|r| r.user.as_deref()),
+        text(|r| r.trx_id.as_deref()),
+        text(|r| r.statement.as_deref()),
+        text(|r| r.appname.as_deref()),
+        text(|r| r.ip.as_deref()),
+        text(|r| r.sql_type.as_deref()),
+        text(|r| Some(&r.description)),
+        long(|r| r.execute_time.map(|v| v.get())),
+        long(|r| r.rowcount.map(|v| v.get())),
+        long(|r| r.execute_id.map(|v| v.get())),
+        Arc::new(records.iter().map(|r| r.record_id).collect::<UInt64Array>()),
+        Arc::new(
+            records
+                .iter()
+                .map(|r| {
+                    r.plan.as_ref().and_then(|p| serde_json::to_string(p).ok())
+                })
+                .collect::<StringArray>(),
+        ),
+        long(|r| r.execute_time_us),
+        Arc::new(
+            records.iter().map(|r| Some(r.partial)).collect::<BooleanArray>(),
+        ),
+    ];
+    RecordBatch::try_new(sqllog_schema(), columns)
+}
@@ -0,0 +1,86 @@
+// 自适应批大小 - 根据写入延迟动态调整每批写入的记录数
+//
+// 固定的 chunk_size 很难同时兼顾吞吐与内存：批次过小时写入开销占比高，
+// 过大时单批占用内存多且写入延迟抖动明显。调优器以「单批写入耗时」为反馈：
+// 满批写入明显快于目标延迟时批大小翻倍，超过目标延迟时减半，
+// 并始终限制在 `[min_batch, max_batch]` 之间以避免内存无限增长。
+
+use std::time::Duration;
+
+/// 自适应批大小配置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AutoTune {
+    /// 期望的单批写入耗时
+    pub target_latency: Duration,
+    /// 最小批大小（也是解析时的分块大小）
+    pub min_batch: usize,
+    /// 最大批大小
+    pub max_batch: usize,
+}
+
+impl Default for AutoTune {
+    fn default() -> Self {
+        Self {
+            target_latency: Duration::from_millis(250),
+            min_batch: 500,
+            max_batch: 100_000,
+        }
+    }
+}
+
+/// 批大小调优器
+#[derive(Debug, Clone)]
+pub struct BatchTuner {
+    config: AutoTune,
+    batch_size: usize,
+    adjustments: usize,
+}
+
+impl BatchTuner {
+    /// 以 `min_batch` 为初始批大小创建调优器
+    #[must_use]
+    pub fn new(config: AutoTune) -> Self {
+        let min_batch = config.min_batch.max(1);
+        let config = AutoTune {
+            min_batch,
+            max_batch: config.max_batch.max(min_batch),
+            ..config
+        };
+        Self { config, batch_size: min_batch, adjustments: 0 }
+    }
+
+    /// 当前批大小
+    #[must_use]
+    pub const fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// 批大小调整次数
+    #[must_use]
+    pub const fn adjustments(&self) -> usize {
+        self.adjustments
+    }
+
+    /// 反馈一次写入：`records` 条记录耗时 `elapsed`，返回调整后的批大小
+    pub fn observe(&mut self, records: usize, elapsed: Duration) -> usize {
+        let target = self.config.target_latency;
+        let next = if elapsed > target {
+            (self.batch_size / 2).max(self.config.min_batch)
+        } else if records >= self.batch_size && elapsed < target / 2 {
+            self.batch_size.saturating_mul(2).min(self.config.max_batch)
+        } else {
+            self.batch_size
+        };
+
+        if next != self.batch_size {
+            log::debug!(
+                "自适应批大小: {} -> {}（{records} 条写入耗时 {elapsed:?}）",
+                self.batch_size,
+                next
+            );
+            self.batch_size = next;
+            self.adjustments += 1;
+        }
+        next
+    }
+}
@@ -0,0 +1,302 @@
+// Avro 导出 - 把记录写成 Avro 对象容器文件（Object Container File）
+//
+// 按 Avro 1.11 规范直接编码，不依赖额外的 crate：文件头写入 schema 与
+// `avro.codec = null`，记录按块写出，每块之后跟随 16 字节的同步标记。
+//
+// schema 由 sqllogs 表结构（`SQLLOG_TABLE`）生成（字段名与表列名一致，不应用列别名）。
+// 可选字段编码为 `["null", T]` 联合类型并带 `"default": null`，下游读者用旧
+// schema 读取新增了可选字段的文件时不受影响；`AvroCompatibility::Full` 进一步
+// 让所有字段都可为空。执行计划以 JSON 文本写出。
+
+use super::format_options::{
+    AvroCompatibility, AvroExportOptions, AvroTimestamps,
+};
+use super::schema::{ColumnType, SQLLOG_TABLE};
+use crate::analysis::timeline::parse_occurrence_time;
+use crate::sqllog::Sqllog;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::io::{self, Write};
+
+/// schema 的记录名与命名空间
+pub const AVRO_RECORD_NAME: &str = "Sqllog";
+pub const AVRO_NAMESPACE: &str = "sqllog_analysis";
+
+/// 每块的记录数
+const BLOCK_RECORDS: usize = 4096;
+
+/// 字段的 Avro 基本类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldType {
+    Int,
+    Long,
+    String,
+    Boolean,
+    /// `long` + `local-timestamp-millis`
+    Timestamp,
+}
+
+/// 字段名、类型与是否总是有值（按 sqllogs 表的列生成）
+///
+/// 旧版本中新增的列在兼容旧 schema 的读取方时不能是必填字段，一律可空。
+fn fields(
+    options: &AvroExportOptions,
+) -> impl Iterator<Item = (&'static str, FieldType, bool)> {
+    let time_type = match options.timestamps {
+        AvroTimestamps::String => FieldType::String,
+        AvroTimestamps::Logical => FieldType::Timestamp,
+    };
+    let backward = options.compatibility == AvroCompatibility::Backward;
+    SQLLOG_TABLE.columns.iter().map(move |column| {
+        let ty = match column.ty {
+            _ if column.name == "occurrence_time" => time_type,
+            ColumnType::Integer => FieldType::Int,
+            ColumnType::BigInt | ColumnType::UBigInt => FieldType::Long,
+            ColumnType::Boolean => FieldType::Boolean,
+            ColumnType::Char(_) | ColumnType::Varchar(_) | ColumnType::Text => {
+                FieldType::String
+            }
+        };
+        let required = backward && column.always_present && !column.migrated;
+        (column.name, ty, required)
+    })
+}
+
+/// 生成导出使用的 Avro schema
+#[must_use]
+pub fn avro_schema(options: &AvroExportOptions) -> serde_json::Value {
+    let fields: Vec<serde_json::Value> = fields(options)
+        .map(|(name, ty, required)| {
+            let ty = match ty {
+                FieldType::Int => serde_json::json!("int"),
+                FieldType::Long => serde_json::json!("long"),
+                FieldType::String => serde_json::json!("string"),
+                FieldType::Boolean => serde_json::json!("boolean"),
+                FieldType::Timestamp => serde_json::json!({
+                    "type": "long",
+                    "logicalType": "local-timestamp-millis",
+                }),
+            };
+            if required {
+                serde_json::json!({ "name": name, "type": ty })
+            } else {
+                serde_json::json!({
+                    "name": name,
+                    "type": ["null", ty],
+                    "default": null,
+                })
+            }
+        })
+        .collect();
+    serde_json::json!({
+        "type": "record",
+        "name": AVRO_RECORD_NAME,
+        "namespace": AVRO_NAMESPACE,
+        "fields": fields,
+    })
+}
+
+/// 字段值（编码前）
+enum FieldValue<'a> {
+    Null,
+    Long(i64),
+    Bool(bool),
+    Str(std::borrow::Cow<'a, str>),
+}
+
+fn field_value<'a>(record: &'a Sqllog, name: &str) -> FieldValue<'a> {
+    use std::borrow::Cow;
+
+    let text = |v: &'a Option<String>| {
+        v.as_deref()
+            .map_or(FieldValue::Null, |s| FieldValue::Str(Cow::Borrowed(s)))
+    };
+    let long = |v: Option<i64>| v.map_or(FieldValue::Null, FieldValue::Long);
+    match name {
+        "occurrence_time" => {
+            FieldValue::Str(Cow::Borrowed(&record.occurrence_time))
+        }
+        "ep" => FieldValue::Long(i64::from(record.ep)),
+        "session" => text(&record.session),
+        "thread" => text(&record.thread),
+        "username" => text(&record.user),
+        "trx_id" => text(&record.trx_id),
+        "statement" => text(&record.statement),
+        "appname" => text(&record.appname),
+        "ip" => text(&record.ip),
+        "sql_type" => text(&record.sql_type),
+        "description" => FieldValue::Str(Cow::Borrowed(&record.description)),
+        "execute_time" => long(record.execute_time.map(|v| v.get())),
+        "rowcount" => long(record.rowcount.map(|v| v.get())),
+        "execute_id" => long(record.execute_id.map(|v| v.get())),
+        "record_id" => {
+            long(record.record_id.map(|v| i64::try_from(v).unwrap_or(i64::MAX)))
+        }
+        "plan" => record
+            .plan
+            .as_ref()
+            .and_then(|p| serde_json::to_string(p).ok())
+            .map_or(FieldValue::Null, |s| FieldValue::Str(Cow::Owned(s))),
+        "execute_time_us" => long(record.execute_time_us),
+        "partial" => FieldValue::Bool(record.partial),
+        _ => FieldValue::Null,
+    }
+}
+
+/// 写入 zigzag 变长编码的 `long`
+fn write_long(buf: &mut Vec<u8>, value: i64) {
+    let mut n = ((value << 1) ^ (value >> 63)) as u64;
+    while n >= 0x80 {
+        buf.push((n as u8) | 0x80);
+        n >>= 7;
+    }
+    buf.push(n as u8);
+}
+
+fn write_bytes(buf: &mut Vec<u8>, bytes: &[u8]) {
+    write_long(buf, bytes.len() as i64);
+    buf.extend_from_slice(bytes);
+}
+
+/// 生成随机的 16 字节同步标记
+fn sync_marker() -> [u8; 16] {
+    let mut marker = [0u8; 16];
+    for chunk in marker.chunks_mut(8) {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u128(
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |d| d.as_nanos()),
+        );
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    marker
+}
+
+/// Avro 对象容器文件导出器
+#[derive(Debug)]
+pub struct AvroExporter<W: Write> {
+    writer: W,
+    options: AvroExportOptions,
+    sync: [u8; 16],
+    block: Vec<u8>,
+    block_records: usize,
+    written: u64,
+}
+
+impl<W: Write> AvroExporter<W> {
+    /// 创建导出器并写出文件头
+    ///
+    /// # Errors
+    /// 写入文件头失败时返回 I/O 错误
+    pub fn new(mut writer: W, options: AvroExportOptions) -> io::Result<Self> {
+        let sync = sync_marker();
+        let mut header = b"Obj\x01".to_vec();
+        write_long(&mut header, 2);
+        write_bytes(&mut header, b"avro.schema");
+        write_bytes(&mut header, avro_schema(&options).to_string().as_bytes());
+        write_bytes(&mut header, b"avro.codec");
+        write_bytes(&mut header, b"null");
+        write_long(&mut header, 0);
+        header.extend_from_slice(&sync);
+        writer.write_all(&header)?;
+        Ok(Self {
+            writer,
+            options,
+            sync,
+            block: Vec::new(),
+            block_records: 0,
+            written: 0,
+        })
+    }
+
+    /// 写出一批记录
+    ///
+    /// # Errors
+    /// 逻辑时间戳模式下 `occurrence_time` 无法解析，或写入失败时返回错误
+    pub fn write_records(&mut self, records: &[Sqllog]) -> io::Result<()> {
+        for record in records {
+            let start = self.block.len();
+            if let Err(e) = self.encode(record) {
+                // 丢弃编码了一半的记录，保持块内容完整
+                self.block.truncate(start);
+                return Err(e);
+            }
+            self.block_records += 1;
+            self.written += 1;
+            if self.block_records >= BLOCK_RECORDS {
+                self.flush_block()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn encode(&mut self, record: &Sqllog) -> io::Result<()> {
+        for (name, ty, required) in fields(&self.options) {
+            let value = field_value(record, name);
+            if !required {
+                // 联合类型分支：0 = null，1 = 值
+                let present = !matches!(value, FieldValue::Null);
+                write_long(&mut self.block, i64::from(present));
+                if !present {
+                    continue;
+                }
+            }
+            match (ty, value) {
+                (FieldType::Timestamp, FieldValue::Str(s)) => {
+                    let at = parse_occurrence_time(&s).ok_or_else(|| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!("无法解析 occurrence_time: {s}"),
+                        )
+                    })?;
+                    write_long(
+                        &mut self.block,
+                        at.and_utc().timestamp_millis(),
+                    );
+                }
+                (_, FieldValue::Long(v)) => write_long(&mut self.block, v),
+                (_, FieldValue::Bool(v)) => self.block.push(u8::from(v)),
+                (_, FieldValue::Str(s)) => {
+                    write_bytes(&mut self.block, s.as_bytes());
+                }
+                (_, FieldValue::Null) => {
+                    // 必填字段不会为 null（见 field_value）
+                    write_bytes(&mut self.block, b"");
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn flush_block(&mut self) -> io::Result<()> {
+        if self.block_records == 0 {
+            return Ok(());
+        }
+        let mut head = Vec::with_capacity(20);
+        write_long(&mut head, self.block_records as i64);
+        write_long(&mut head, self.block.len() as i64);
+        self.writer.write_all(&head)?;
+        self.writer.write_all(&self.block)?;
+        self.writer.write_all(&self.sync)?;
+        self.block.clear();
+        self.block_records = 0;
+        Ok(())
+    }
+
+    /// 已写出的记录数
+    #[must_use]
+    pub const fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// 写出最后一块并返回记录总数
+    ///
+    /// # Errors
+    /// 写入失败时返回 I/O 错误
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.flush_block()?;
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
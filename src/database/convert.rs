@@ -0,0 +1,390 @@
+// 记录转换 - 从已保存的记录重新导出，跳过日志解析
+//
+// 已经导出过一次 JSON 后，只想换一种格式（或写入 DuckDB 库）时不必重新解析
+// 原始日志。[`load_saved_records`] 读取此前导出的记录文件并写入 sqllogs 表，
+// 之后交给现有的导出器：
+//
+// - `.json`/`.jsonl`/`.ndjson`：JSON Lines 或数组布局，按 serde 逐条反序列化，
+//   字段名与 sqllogs 表的列名一致（`username` 也可写作 `user`），`plan` 可以是
+//   JSON 文本或对象
+// - `.parquet`：由 DuckDB 的 `read_parquet` 按列名读取，需要可用的 parquet 扩展
+//
+// 输入须使用原始列名；导出时配置过 `column_aliases` 的文件需先还原列名。
+//
+// JSON 记录在反序列化前按 [`SQLLOG_TABLE`] 逐条校验，手工编辑或第三方生成的
+// 文件不会静默丢失数据：
+//
+// - 缺少 `occurrence_time`、`ep`、`description`，或这些字段为 null
+// - 字段类型与列类型不符（如 `ep` 为字符串、`record_id` 为负数）
+// - 未知字段（通常是改了名的列）；与某列名称相近时提示可能的原名。
+//   [`LoadOptions::allow_extra_fields`] 为真时忽略未知字段
+//
+// 一条记录的所有问题合并为一个错误，附带文件位置（JSON Lines 为行号，
+// 数组布局为下标）。其他可空列可以省略，视为 null。
+
+use super::DuckDbProvider;
+use super::schema::{Column, ColumnType, SQLLOG_TABLE};
+use crate::sqllog::{ExecId, ExecTimeMs, PlanNode, RowCount, Sqllog};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::Deserialize;
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+
+/// 未配置 `chunk_size` 时每批写入的记录数
+pub const DEFAULT_LOAD_BATCH: usize = 10_000;
+
+/// 载入已保存记录的选项
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LoadOptions {
+    /// 每批写入的记录数（JSON 输入）
+    pub batch_size: usize,
+    /// 忽略不属于 sqllogs 表的字段，而不是报错
+    pub allow_extra_fields: bool,
+}
+
+impl Default for LoadOptions {
+    fn default() -> Self {
+        Self { batch_size: DEFAULT_LOAD_BATCH, allow_extra_fields: false }
+    }
+}
+
+/// 已保存记录的文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SavedFormat {
+    /// JSON Lines 或 JSON 数组
+    Json,
+    /// Parquet
+    Parquet,
+}
+
+impl SavedFormat {
+    /// 按扩展名识别格式
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" | "jsonl" | "ndjson" => Some(Self::Json),
+            "parquet" => Some(Self::Parquet),
+            _ => None,
+        }
+    }
+}
+
+/// 已保存的一条记录（列名与 sqllogs 表一致）
+#[derive(Debug, Deserialize)]
+struct SavedRecord {
+    occurrence_time: String,
+    ep: i32,
+    session: Option<String>,
+    thread: Option<String>,
+    username: Option<String>,
+    trx_id: Option<String>,
+    statement: Option<String>,
+    appname: Option<String>,
+    ip: Option<String>,
+    sql_type: Option<String>,
+    description: String,
+    execute_time: Option<i64>,
+    rowcount: Option<i64>,
+    execute_id: Option<i64>,
+    record_id: Option<u64>,
+    plan: Option<serde_json::Value>,
+    execute_time_us: Option<i64>,
+    partial: Option<bool>,
+}
+
+impl SavedRecord {
+    fn into_sqllog(self) -> Sqllog {
+        // 从库中导出的 plan 是 JSON 文本，手写的文件也可能直接给出对象
+        let plan = self.plan.and_then(|plan| match plan {
+            serde_json::Value::String(text) => {
+                serde_json::from_str::<PlanNode>(&text).ok()
+            }
+            value => serde_json::from_value(value).ok(),
+        });
+        Sqllog {
+            occurrence_time: self.occurrence_time,
+            ep: self.ep,
+            session: self.session,
+            thread: self.thread,
+            user: self.username,
+            trx_id: self.trx_id,
+            statement: self.statement,
+            appname: self.appname,
+            ip: self.ip,
+            sql_type: self.sql_type,
+            description: self.description,
+            execute_time: self.execute_time.map(ExecTimeMs::new),
+            rowcount: self.rowcount.map(RowCount::new),
+            execute_id: self.execute_id.map(ExecId::new),
+            record_id: self.record_id,
+            plan,
+            execute_time_us: self.execute_time_us,
+            partial: self.partial.unwrap_or(false),
+            line: 0,
+            byte_offset: None,
+        }
+    }
+}
+
+/// 读取已保存的 JSON 记录（JSON Lines 或数组布局）
+///
+/// # Errors
+/// 当文件无法读取、某条记录未通过校验或无法反序列化时返回错误
+pub fn read_saved_records(
+    path: &Path,
+    options: &LoadOptions,
+) -> Result<Vec<Sqllog>> {
+    let mut records = Vec::new();
+    let options = LoadOptions { batch_size: usize::MAX, ..*options };
+    for_each_saved_batch(path, &options, |mut batch| {
+        records.append(&mut batch);
+        Ok(())
+    })?;
+    Ok(records)
+}
+
+/// 把已保存的记录按批写入 `provider` 的 sqllogs 表，返回写入的记录数
+///
+/// 调用前需已初始化表结构；JSON Lines 按 `batch_size` 条一批流式写入，
+/// 遇到未通过校验的记录时停止，之前的批次已经写入。
+///
+/// # Errors
+/// 当文件格式无法识别、读取、校验或反序列化失败、写入失败时返回错误
+pub fn load_saved_records(
+    provider: &mut DuckDbProvider,
+    path: &Path,
+    options: &LoadOptions,
+) -> Result<u64> {
+    match SavedFormat::from_path(path) {
+        Some(SavedFormat::Json) => {
+            let mut loaded = 0u64;
+            for_each_saved_batch(path, options, |batch| {
+                provider.insert_batch(&batch)?;
+                loaded += batch.len() as u64;
+                Ok(())
+            })?;
+            Ok(loaded)
+        }
+        Some(SavedFormat::Parquet) => provider.load_parquet(path),
+        None => bail!(
+            "无法从扩展名识别记录文件格式（支持 .json/.jsonl/.ndjson/.parquet）: {}",
+            path.display()
+        ),
+    }
+}
+
+fn for_each_saved_batch(
+    path: &Path,
+    options: &LoadOptions,
+    mut sink: impl FnMut(Vec<Sqllog>) -> Result<()>,
+) -> Result<()> {
+    let file = File::open(path)
+        .with_context(|| format!("无法打开记录文件: {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let array = loop {
+        let buf = reader.fill_buf()?;
+        match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(pos) => break buf[pos] == b'[',
+            None if buf.is_empty() => return Ok(()),
+            None => {
+                let len = buf.len();
+                reader.consume(len);
+            }
+        }
+    };
+
+    let batch_size = options.batch_size.max(1);
+    let mut batch = Vec::new();
+    let mut push = |value: Value, location: &dyn Fn() -> String| {
+        let record = parse_saved_record(value, options.allow_extra_fields)
+            .map_err(|e| anyhow!("{}: {e}", location()))?;
+        batch.push(record);
+        if batch.len() >= batch_size {
+            sink(std::mem::take(&mut batch))?;
+        }
+        anyhow::Ok(())
+    };
+    if array {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        let values: Vec<Value> = serde_json::from_str(&text)
+            .with_context(|| format!("无法解析记录文件: {}", path.display()))?;
+        for (index, value) in values.into_iter().enumerate() {
+            push(value, &|| format!("{}[{index}]", path.display()))?;
+        }
+    } else {
+        for (index, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let location = || format!("{}:{}", path.display(), index + 1);
+            let value: Value = serde_json::from_str(&line)
+                .with_context(|| format!("无法解析记录: {}", location()))?;
+            push(value, &location)?;
+        }
+    }
+    if !batch.is_empty() {
+        sink(batch)?;
+    }
+    Ok(())
+}
+
+/// 把一条记录转换为与导出相同字段名的 JSON 对象（`plan` 为对象）
+pub(crate) fn record_to_json(record: &Sqllog) -> Value {
+    serde_json::json!({
+        "occurrence_time": record.occurrence_time,
+        "ep": record.ep,
+        "session": record.session,
+        "thread": record.thread,
+        "username": record.user,
+        "trx_id": record.trx_id,
+        "statement": record.statement,
+        "appname": record.appname,
+        "ip": record.ip,
+        "sql_type": record.sql_type,
+        "description": record.description,
+        "execute_time": record.execute_time.map(ExecTimeMs::get),
+        "rowcount": record.rowcount.map(RowCount::get),
+        "execute_id": record.execute_id.map(ExecId::get),
+        "record_id": record.record_id,
+        "plan": record.plan,
+        "execute_time_us": record.execute_time_us,
+        "partial": record.partial,
+    })
+}
+
+/// 校验并反序列化一条记录
+pub(crate) fn parse_saved_record(
+    value: Value,
+    allow_extra_fields: bool,
+) -> Result<Sqllog> {
+    let Value::Object(mut fields) = value else {
+        bail!("记录应为 JSON 对象");
+    };
+    // `user` 是 `username` 的别名，同时出现时以 `username` 为准
+    if let Some(user) = fields.remove("user") {
+        fields.entry("username").or_insert(user);
+    }
+
+    let mut problems = Vec::new();
+    for column in SQLLOG_TABLE.columns {
+        let required = column.always_present && !column.migrated;
+        match fields.get(column.name) {
+            None if required => {
+                problems.push(format!("缺少字段 {}", column.name));
+            }
+            None => {}
+            Some(Value::Null) if required || column.not_null => {
+                problems.push(format!("字段 {} 不能为 null", column.name));
+            }
+            Some(value) => {
+                if let Some(expected) = type_mismatch(column, value) {
+                    problems.push(format!(
+                        "字段 {} 应为{expected}，实际为 {}",
+                        column.name,
+                        json_kind(value)
+                    ));
+                }
+            }
+        }
+    }
+    let extra: Vec<String> = fields
+        .keys()
+        .filter(|key| SQLLOG_TABLE.column(key).is_none())
+        .cloned()
+        .collect();
+    for key in extra {
+        if allow_extra_fields {
+            fields.remove(&key);
+            continue;
+        }
+        match similar_column(&key) {
+            Some(column) => problems
+                .push(format!("未知字段 {key}（是否为 {column} 改名？）")),
+            None => problems.push(format!("未知字段 {key}")),
+        }
+    }
+    if !problems.is_empty() {
+        bail!("{}", problems.join("；"));
+    }
+
+    let saved: SavedRecord = serde_json::from_value(Value::Object(fields))?;
+    Ok(saved.into_sqllog())
+}
+
+/// 取值与列类型不符时返回期望的类型描述（null 不在此检查）
+fn type_mismatch(column: &Column, value: &Value) -> Option<&'static str> {
+    let ok = match (column.ty, value) {
+        (_, Value::Null) => true,
+        // plan 可以是 JSON 文本或对象
+        (ColumnType::Text, Value::Object(_)) => column.name == "plan",
+        (ty, Value::String(_)) => ty.is_text(),
+        (ColumnType::Integer, Value::Number(n)) => {
+            n.as_i64().is_some_and(|v| i32::try_from(v).is_ok())
+        }
+        (ColumnType::BigInt, Value::Number(n)) => n.as_i64().is_some(),
+        (ColumnType::UBigInt, Value::Number(n)) => n.as_u64().is_some(),
+        (ColumnType::Boolean, Value::Bool(_)) => true,
+        _ => false,
+    };
+    if ok {
+        return None;
+    }
+    Some(match column.ty {
+        ColumnType::Char(_) | ColumnType::Varchar(_) | ColumnType::Text => {
+            if column.name == "plan" {
+                "字符串或对象"
+            } else {
+                "字符串"
+            }
+        }
+        ColumnType::Integer => "32 位整数",
+        ColumnType::BigInt => "整数",
+        ColumnType::UBigInt => "非负整数",
+        ColumnType::Boolean => "布尔值",
+    })
+}
+
+fn json_kind(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => format!("布尔值 {b}"),
+        Value::Number(n) => format!("数字 {n}"),
+        Value::String(_) => "字符串".to_string(),
+        Value::Array(_) => "数组".to_string(),
+        Value::Object(_) => "对象".to_string(),
+    }
+}
+
+/// 与未知字段名称相近的列：编辑距离足够小，或一方包含另一方
+fn similar_column(key: &str) -> Option<&'static str> {
+    let key = key.to_ascii_lowercase();
+    SQLLOG_TABLE
+        .column_names()
+        .map(|name| (edit_distance(&key, name), name))
+        .filter(|&(distance, name)| {
+            distance <= (name.len() / 3).max(2)
+                || (key.len() >= 4
+                    && (name.contains(key.as_str()) || key.contains(name)))
+        })
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
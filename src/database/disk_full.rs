@@ -0,0 +1,100 @@
+//! 磁盘空间不足 - 识别 ENOSPC 类错误并给出结构化的失败报告
+//!
+//! 输出磁盘写满时，`DuckDB` 与标准库返回的错误分散在各层错误链中，仅记录
+//! 原始信息难以判断是哪个导出目标失败、哪些输出仍然可用。[`is_disk_full`]
+//! 沿错误链查找磁盘空间不足（含超出配额）的 I/O 错误或 `DuckDB` 错误信息，
+//! 导出与管道据此停止后续写入，并以 [`DiskFullError`] 报告受影响的导出目标、
+//! 已完成与不完整的输出文件。
+
+use std::fmt::Write as _;
+use std::io;
+use std::path::PathBuf;
+use thiserror::Error;
+
+/// `DuckDB` 等未保留 I/O 错误码的组件在错误信息中使用的文本
+const DISK_FULL_MESSAGES: &[&str] =
+    &["no space left on device", "disk full", "disk quota exceeded"];
+
+/// 磁盘空间不足导致的导出失败
+#[derive(Debug, Error)]
+#[error("{}", describe(self))]
+pub struct DiskFullError {
+    /// 失败的导出目标（导出格式或管道阶段名）
+    pub exporter: String,
+    /// 写入失败的路径（管道阶段中无法确定时为 `None`）
+    pub path: Option<PathBuf>,
+    /// 失败前已完整写出的文件
+    pub completed: Vec<PathBuf>,
+    /// 写入中断、内容不完整的文件
+    pub partial: Vec<PathBuf>,
+    /// 底层错误信息
+    pub detail: String,
+}
+
+impl DiskFullError {
+    /// 由底层错误构造，`path` 同时计入不完整的输出
+    pub fn new(
+        exporter: impl Into<String>,
+        path: Option<PathBuf>,
+        source: &anyhow::Error,
+    ) -> Self {
+        Self {
+            exporter: exporter.into(),
+            partial: path.iter().cloned().collect(),
+            path,
+            completed: Vec::new(),
+            detail: format!("{source:#}"),
+        }
+    }
+}
+
+fn describe(e: &DiskFullError) -> String {
+    let mut msg = format!("磁盘空间不足，导出 {} 已停止", e.exporter);
+    if let Some(path) = &e.path {
+        let _ = write!(msg, "（写入 {}）", path.display());
+    }
+    let _ = write!(msg, ": {}", e.detail);
+    let join = |paths: &[PathBuf]| {
+        paths
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    if !e.completed.is_empty() {
+        let _ = write!(msg, "；已完成: {}", join(&e.completed));
+    }
+    if !e.partial.is_empty() {
+        let _ = write!(msg, "；不完整: {}", join(&e.partial));
+    }
+    msg
+}
+
+/// 错误链中是否包含磁盘空间不足（含超出配额）的错误
+#[must_use]
+pub fn is_disk_full(err: &anyhow::Error) -> bool {
+    err.chain().any(|cause| {
+        if cause.is::<DiskFullError>() {
+            return true;
+        }
+        if let Some(io_err) = cause.downcast_ref::<io::Error>() {
+            if io_err.raw_os_error().is_some_and(is_disk_full_code) {
+                return true;
+            }
+        }
+        let msg = cause.to_string().to_lowercase();
+        DISK_FULL_MESSAGES.iter().any(|m| msg.contains(m))
+    })
+}
+
+/// 操作系统的磁盘空间不足错误码
+const fn is_disk_full_code(code: i32) -> bool {
+    if cfg!(windows) {
+        // ERROR_HANDLE_DISK_FULL / ERROR_DISK_FULL
+        matches!(code, 39 | 112)
+    } else {
+        // ENOSPC / EDQUOT（Linux 为 122，BSD 与 macOS 为 69）
+        let edquot = if cfg!(target_os = "linux") { 122 } else { 69 };
+        code == 28 || code == edquot
+    }
+}
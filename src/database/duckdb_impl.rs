@@ -7,24 +7,37 @@
 // - 多格式数据导出
 // - 性能优化的查询
 
+use super::aliases::{ALIASED_VIEW, ColumnAliases};
+use super::resume::{RESUME_TABLE_SQL, run_key};
+use super::run_meta::{RUNS_TABLE_SQL, RunRecord};
+use super::schema::{SQLLOG_TABLE, column_names};
 use super::{
-    DatabaseInfo, DatabaseMode, DatabaseProvider, DatabaseStats, DatabaseType,
-    ExportFormat,
+    BatchTuner, DatabaseInfo, DatabaseMode, DatabaseProvider, DatabaseStats,
+    DatabaseType, DiskFullError, ExportArtifact, ExportFormat, ExportReport,
+    LineTemplate, Newlines, ParseCache, ProcessPlugin, RateLimiter,
+    TemplateExporter, is_disk_full, plugin_stage,
 };
-use crate::config::RuntimeConfig;
-use crate::error_writer::ErrorWriter;
+use crate::analysis::aggregate::{
+    AggFunc, AggregateQuery, AggregateResult, Expr, Value,
+};
+use crate::config::{ExportOptions, RuntimeConfig};
+use crate::error_writer::{ErrorExporter, ErrorWriter, ParseErrorRecord};
+use crate::spans::{self, enter_span};
 use crate::sqllog::Sqllog;
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
 use duckdb::{Connection, Result as DuckResult};
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 // 类型别名，用于简化复杂的元组类型
 type SqllogRowData = (
     String,         // occurrence_time
-    String,         // ep
+    i32,            // ep
     Option<String>, // session
     Option<String>, // thread
     Option<String>, // username
@@ -37,8 +50,150 @@ type SqllogRowData = (
     Option<i64>,    // execute_time
     Option<i64>,    // rowcount
     Option<i64>,    // execute_id
+    Option<u64>,    // record_id
+    Option<String>, // plan
+    Option<i64>,    // execute_time_us
+    bool,           // partial
 );
 
+/// sqllogs 表的列顺序（由 [`SQLLOG_TABLE`] 生成）
+pub(super) const SQLLOG_COLUMNS: [&str; SQLLOG_TABLE.columns.len()] =
+    column_names(SQLLOG_TABLE.columns);
+
+/// 导出与 description 旁路文件之间的关联键
+const RECORD_KEY_SQL: &str = "COALESCE(record_id, CAST(rowid AS UBIGINT))";
+
+/// DDL 语句的识别条件：SQL 类型不是 SEL/INS/UPD/DEL（DDL 通常记为 ORA
+/// 或没有类型标记），且 description 以 CREATE/ALTER/DROP 开头（不区分大小写）
+const DDL_FILTER_SQL: &str = "COALESCE(sql_type, '') NOT IN ('SEL', 'INS', 'UPD', 'DEL') \
+     AND regexp_matches(description, '(?i)^\\s*(create|alter|drop)\\b')";
+
+/// 记录所在的日期分区（`YYYY-MM-DD`），日期无法识别时为 NULL
+const OCCURRENCE_DAY_SQL: &str = "strftime(try_strptime(left(occurrence_time, 10), \
+     '%Y-%m-%d'), '%Y-%m-%d')";
+
+/// Hive 约定的空分区目录名，日期无法识别的记录写入该目录
+pub const HIVE_DEFAULT_PARTITION: &str = "__HIVE_DEFAULT_PARTITION__";
+
+/// 解析错误表建表语句（仅在写入解析错误时创建）
+const CREATE_ERRORS_TABLE_SQL: &str = r"
+    CREATE TABLE IF NOT EXISTS parse_errors (
+        path VARCHAR,
+        line UBIGINT,
+        error TEXT,
+        raw TEXT
+    );
+";
+
+/// 冷字段表：按 `record_id` 保存从 sqllogs 移出的 description 与 plan
+pub const COLD_TABLE: &str = "sqllog_cold";
+
+/// 把冷字段拼回 sqllogs 的视图：列与 sqllogs 相同，另有按写入顺序排序用的
+/// `row_order`
+pub const FULL_VIEW: &str = "sqllogs_full";
+
+/// 数据写入完成后创建的索引
+const INDEX_SQLS: [&str; 3] = [
+    "CREATE INDEX IF NOT EXISTS idx_sqllogs_dmlg01 ON sqllogs(session)",
+    "CREATE INDEX IF NOT EXISTS idx_sqllogs_dmlg02 ON sqllogs(thread)",
+    "CREATE INDEX IF NOT EXISTS idx_sqllogs_dmlg03 ON sqllogs(trx_id)",
+];
+
+/// 旧版本创建的表补齐 `record_id` 列后代替 UNIQUE 约束的唯一索引
+const RECORD_ID_UNIQUE_INDEX_SQL: &str = "CREATE UNIQUE INDEX IF NOT EXISTS idx_sqllogs_record_id ON sqllogs(record_id)";
+
+/// 迁移列类型前需删除的索引（收尾时由 `create_indexes` 重建）
+const INDEX_NAMES: [&str; 4] = [
+    "idx_sqllogs_dmlg01",
+    "idx_sqllogs_dmlg02",
+    "idx_sqllogs_dmlg03",
+    "idx_sqllogs_record_id",
+];
+
+/// 将文本转为 SQL 字符串字面量（单引号转义）
+pub(super) fn sql_string_literal(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// 将标识符转义为 SQL 双引号标识符
+pub(super) fn sql_identifier(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+/// 截取文本表达式 `value` 的 UTF-8 编码前 `max_bytes` 个字节，截断点落在
+/// 多字节字符中间时退到该字符之前
+///
+/// `DuckDB` 的 `left` 按字符截取；这里先取前 `max_bytes` 个字符（字节数
+/// 不少于 `max_bytes`），再按十六进制检查第 `max_bytes + 1` 个字节及其前两个
+/// 字节是否为续字节（`10xxxxxx`），据此退回 0–3 个字节后解码。
+fn utf8_prefix_sql(value: &str, max_bytes: usize) -> String {
+    let hex = format!("hex(left({value}, {max_bytes}))");
+    let continuation = |byte: usize| {
+        format!("substr({hex}, {}, 1) IN ('8', '9', 'A', 'B')", 2 * byte - 1)
+    };
+    // 三个字节都是续字节时，下一字符从第 max_bytes-2 个字节开始
+    let checks = (0..3.min(max_bytes))
+        .map(|back| {
+            format!(
+                "WHEN NOT {} THEN {back}",
+                continuation(max_bytes + 1 - back)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let back = format!("CASE {checks} ELSE {} END", 3.min(max_bytes));
+    format!("decode(unhex(left({hex}, 2 * ({max_bytes} - {back}))))")
+}
+
+/// 按 select 项的类型读取聚合结果中的一列
+fn aggregate_value(
+    row: &duckdb::Row<'_>,
+    index: usize,
+    expr: &Expr,
+) -> DuckResult<Value> {
+    let value = match expr {
+        Expr::Column(c) if !c.is_numeric() => {
+            row.get::<_, Option<String>>(index)?.map(Value::Text)
+        }
+        Expr::Agg { func: AggFunc::Avg, .. } => {
+            row.get::<_, Option<f64>>(index)?.map(Value::Float)
+        }
+        _ => row.get::<_, Option<i64>>(index)?.map(Value::Int),
+    };
+    Ok(value.unwrap_or(Value::Null))
+}
+
+/// 把按 [`SQLLOG_COLUMNS`] 顺序查询出的一行还原为记录
+pub(super) fn sqllog_from_row(row: &duckdb::Row<'_>) -> DuckResult<Sqllog> {
+    use crate::sqllog::{ExecId, ExecTimeMs, RowCount};
+
+    let plan: Option<String> = row.get(15)?;
+    Ok(Sqllog {
+        occurrence_time: row.get(0)?,
+        ep: row.get(1)?,
+        session: row.get(2)?,
+        thread: row.get(3)?,
+        user: row.get(4)?,
+        trx_id: row.get(5)?,
+        statement: row.get(6)?,
+        appname: row.get(7)?,
+        ip: row.get(8)?,
+        sql_type: row.get(9)?,
+        description: row.get(10)?,
+        execute_time: row.get::<_, Option<i64>>(11)?.map(ExecTimeMs::new),
+        rowcount: row.get::<_, Option<i64>>(12)?.map(RowCount::new),
+        execute_id: row.get::<_, Option<i64>>(13)?.map(ExecId::new),
+        record_id: row.get(14)?,
+        plan: plan.and_then(|p| serde_json::from_str(&p).ok()),
+        execute_time_us: row.get(16)?,
+        // 旧版本创建的表中该列为 NULL
+        partial: row.get::<_, Option<bool>>(17)?.unwrap_or(false),
+        // 源文件位置不保存在表中
+        line: 0,
+        byte_offset: None,
+    })
+}
+
 /// `DuckDB` 数据库提供者
 ///
 /// 实现 `DatabaseProvider` trait，提供 `DuckDB` 特定的功能，
@@ -93,45 +248,93 @@ impl DuckDbProvider {
         })
     }
 
+    /// 底层连接，供同模块的只读报告使用
+    pub(super) const fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    /// 返回初始化与收尾阶段会执行的建表及索引语句，不访问数据库
+    ///
+    /// 用于在导入前审阅或调整目标库结构；`record_id` 的唯一索引仅用于旧版本
+    /// 创建、没有 UNIQUE 约束的表，在输出中以注释标明。
+    #[must_use]
+    pub fn schema_sql() -> String {
+        let mut sql = SQLLOG_TABLE
+            .create_table_sql(&DatabaseType::DuckDb)
+            .trim_end()
+            .to_string();
+        sql.push_str("\n\n-- 索引在数据写入完成后创建\n");
+        for index_sql in INDEX_SQLS {
+            sql.push_str(index_sql);
+            sql.push_str(";\n");
+        }
+        sql.push_str("-- 仅当表中的 record_id 没有 UNIQUE 约束时创建\n");
+        sql.push_str(RECORD_ID_UNIQUE_INDEX_SQL);
+        sql.push_str(";\n");
+        sql
+    }
+
     /// 创建 sqllogs 表
     fn create_table(&self) -> DuckResult<()> {
-        let create_sql = r"
-            CREATE TABLE IF NOT EXISTS sqllogs (
-                occurrence_time CHAR(32) NOT NULL,
-                ep CHAR(1),
-                session VARCHAR(64),
-                thread VARCHAR(64),
-                username VARCHAR(128),
-                trx_id VARCHAR(64),
-                statement VARCHAR(64),
-                appname VARCHAR(256),
-                ip VARCHAR(45),
-                sql_type VARCHAR(32),
-                description TEXT,
-                execute_time BIGINT,
-                rowcount BIGINT,
-                execute_id BIGINT
-            )
-        ";
+        self.connection.execute_batch(
+            &SQLLOG_TABLE.create_table_sql(&DatabaseType::DuckDb),
+        )?;
+
+        Ok(())
+    }
 
-        // 直接创建表
-        self.connection.execute_batch(create_sql)?;
+    /// 将旧版本以 `CHAR(1)` 保存的 ep 列迁移为 `INTEGER`
+    ///
+    /// 无法转换为整数的旧值迁移后为 NULL。`DuckDB` 不允许修改带索引的列所在
+    /// 表的列类型，因此先删除索引，收尾时再重建。
+    fn migrate_ep_column(&self) -> Result<()> {
+        let data_type: String = self
+            .connection
+            .query_row(
+                "SELECT data_type FROM information_schema.columns
+                 WHERE table_schema = 'main' AND table_name = 'sqllogs'
+                   AND column_name = 'ep'",
+                [],
+                |row| row.get(0),
+            )
+            .context("读取 ep 列类型失败")?;
+        if data_type == "INTEGER" {
+            return Ok(());
+        }
 
+        log::info!("迁移 sqllogs.ep 列：{data_type} -> INTEGER");
+        for index in INDEX_NAMES {
+            self.connection
+                .execute_batch(&format!("DROP INDEX IF EXISTS {index}"))?;
+        }
+        self.connection
+            .execute_batch(
+                "ALTER TABLE sqllogs ALTER COLUMN ep SET DATA TYPE INTEGER
+                 USING TRY_CAST(ep AS INTEGER)",
+            )
+            .context("迁移 ep 列类型失败")?;
         Ok(())
     }
 
     /// 创建索引（延迟创建以提高插入性能）
     fn create_indexes(&self) -> DuckResult<()> {
-        let index_sqls = [
-            "CREATE INDEX IF NOT EXISTS idx_sqllogs_dmlg01 ON sqllogs(session)",
-            "CREATE INDEX IF NOT EXISTS idx_sqllogs_dmlg02 ON sqllogs(thread)",
-            "CREATE INDEX IF NOT EXISTS idx_sqllogs_dmlg03 ON sqllogs(trx_id)",
-        ];
-
-        for index_sql in &index_sqls {
+        for index_sql in INDEX_SQLS {
             self.connection.execute_batch(index_sql)?;
         }
 
+        // 新建的表由 record_id 列的 UNIQUE 约束保证唯一；旧版本创建的表
+        // 补齐该列时没有约束，以唯一索引代替，存在重复时直接报错
+        let constrained: i64 = self.connection.query_row(
+            "SELECT count(*) FROM duckdb_constraints() \
+             WHERE database_name = current_database() \
+             AND table_name = 'sqllogs' AND constraint_type = 'UNIQUE'",
+            [],
+            |row| row.get(0),
+        )?;
+        if constrained == 0 {
+            self.connection.execute_batch(RECORD_ID_UNIQUE_INDEX_SQL)?;
+        }
+
         Ok(())
     }
 
@@ -160,7 +363,7 @@ impl DuckDbProvider {
         for record in records {
             all_data.push((
                 record.occurrence_time.clone(), // occurrence_time CHAR(32)
-                record.ep.to_string(),          // ep CHAR(1)
+                record.ep,                      // ep INTEGER
                 record.session.clone(),         // session VARCHAR(64)
                 record.thread.clone(),          // thread VARCHAR(64)
                 record.user.clone(),            // username VARCHAR(128)
@@ -170,16 +373,23 @@ impl DuckDbProvider {
                 record.ip.clone(),              // ip VARCHAR(45)
                 record.sql_type.clone(),        // sql_type VARCHAR(32)
                 record.description.clone(),     // description TEXT
-                record.execute_time,            // execute_time BIGINT
-                record.rowcount,                // rowcount BIGINT
-                record.execute_id,              // execute_id BIGINT
+                record.execute_time.map(i64::from), // execute_time BIGINT
+                record.rowcount.map(i64::from), // rowcount BIGINT
+                record.execute_id.map(i64::from), // execute_id BIGINT
+                record.record_id,               // record_id UBIGINT
+                record
+                    .plan
+                    .as_ref()
+                    .and_then(|p| serde_json::to_string(p).ok()), // plan TEXT (JSON)
+                record.execute_time_us, // execute_time_us BIGINT
+                record.partial,         // partial BOOLEAN
             ));
         }
 
         // 构造引用数组用于 append_rows 一次性批量插入
-        // 表列顺序：occurrence_time, ep, session, thread, username, trx_id, statement, appname, ip, sql_type, description, execute_time, rowcount, execute_id
+        // 表列顺序：occurrence_time, ep, session, thread, username, trx_id, statement, appname, ip, sql_type, description, execute_time, rowcount, execute_id, record_id, plan, execute_time_us, partial
         log::debug!("insert_sqllog_batch: 构造批量插入数据");
-        let batch_rows: Vec<[&dyn duckdb::ToSql; 14]> = all_data
+        let batch_rows: Vec<[&dyn duckdb::ToSql; 18]> = all_data
             .iter()
             .map(
                 |(
@@ -197,10 +407,14 @@ impl DuckDbProvider {
                     execute_time,
                     rowcount,
                     execute_id,
+                    record_id,
+                    plan,
+                    execute_time_us,
+                    partial,
                 )| {
                     [
                         occurrence_time as &dyn duckdb::ToSql, // occurrence_time CHAR(32)
-                        ep as &dyn duckdb::ToSql,              // ep CHAR(1)
+                        ep as &dyn duckdb::ToSql,              // ep INTEGER
                         session as &dyn duckdb::ToSql, // session VARCHAR(64)
                         thread as &dyn duckdb::ToSql,  // thread VARCHAR(64)
                         user as &dyn duckdb::ToSql,    // username VARCHAR(128)
@@ -213,6 +427,10 @@ impl DuckDbProvider {
                         execute_time as &dyn duckdb::ToSql, // execute_time BIGINT
                         rowcount as &dyn duckdb::ToSql,     // rowcount BIGINT
                         execute_id as &dyn duckdb::ToSql,   // execute_id BIGINT
+                        record_id as &dyn duckdb::ToSql,    // record_id UBIGINT
+                        plan as &dyn duckdb::ToSql,         // plan TEXT
+                        execute_time_us as &dyn duckdb::ToSql, // execute_time_us BIGINT
+                        partial as &dyn duckdb::ToSql, // partial BOOLEAN
                     ]
                 },
             )
@@ -250,6 +468,34 @@ impl DuckDbProvider {
         Ok(())
     }
 
+    /// 将解析错误写入 `parse_errors` 表（表不存在时自动创建）
+    ///
+    /// # Errors
+    /// 当建表或写入失败时返回错误
+    pub fn insert_parse_errors(
+        &mut self,
+        records: &[ParseErrorRecord],
+    ) -> Result<usize> {
+        if records.is_empty() {
+            return Ok(0);
+        }
+        self.connection
+            .execute_batch(CREATE_ERRORS_TABLE_SQL)
+            .context("创建解析错误表失败")?;
+
+        let mut appender = self.connection.appender("parse_errors")?;
+        for record in records {
+            appender.append_row(duckdb::params![
+                record.path,
+                record.line as u64,
+                record.error,
+                record.raw,
+            ])?;
+        }
+        appender.flush()?;
+        Ok(records.len())
+    }
+
     /// 批量插入记录
     /// 批量插入数据到数据库
     ///
@@ -288,32 +534,577 @@ impl DuckDbProvider {
         Ok(inserted)
     }
 
-    /// 导出数据到 JSON 格式（使用 `DuckDB` COPY 命令）
-    fn export_to_json(&self, output_path: &str) -> Result<()> {
+    /// 按导出选项构造 COPY 使用的 SELECT 语句
+    ///
+    /// 未配置截断与列别名时等价于 `SELECT * FROM sqllogs`；配置了
+    /// `description_max_bytes` 时 description 列按字节数截断（见
+    /// [`utf8_prefix_sql`]），若同时配置了
+    /// 旁路文件，则在首列追加 `record_id`（`DuckDB` rowid）以便与旁路记录关联。
+    /// 配置了 `column_aliases` 时各列以别名输出，为该格式配置了 `redact`
+    /// 规则时对应列输出脱敏后的取值，配置了 `top_sessions` 时只导出排名
+    /// 前 K 的会话的记录（见 [`Self::export_filter_sql`]）；CSV 导出配置了
+    /// `csv.newlines` 时对 description 内的换行做转义或替换（在截断之后）。
+    fn export_select_sql(
+        format: &ExportFormat,
+        options: &ExportOptions,
+    ) -> String {
+        let aliases = &options.column_aliases;
+        let redactions = &options.redactions;
+        let filter = Self::export_filter_sql(options);
+        let newlines = match format {
+            ExportFormat::Csv => options.format_options.csv.newlines,
+            _ => Newlines::Keep,
+        };
+        if options.description_max_bytes.is_none()
+            && aliases.is_empty()
+            && redactions.is_empty_for(format)
+            && newlines == Newlines::Keep
+        {
+            return format!("SELECT * FROM sqllogs{filter}");
+        }
+
+        let with_key = options.description_max_bytes.is_some()
+            && options.description_overflow_path.is_some();
+        let columns: Vec<String> = SQLLOG_COLUMNS
+            .iter()
+            .filter(|&&col| !(with_key && col == "record_id"))
+            .map(|&col| {
+                let value = redactions.column_sql(format, col);
+                if col != "description" {
+                    return aliases.select_item(&value, col);
+                }
+                let value = match options.description_max_bytes {
+                    Some(max_bytes) => {
+                        Cow::Owned(utf8_prefix_sql(&value, max_bytes))
+                    }
+                    None => value,
+                };
+                aliases.select_item(&newlines.sql(&value), col)
+            })
+            .collect();
+        // 有旁路文件时把关联键放在首列；未生成记录 ID 时以 rowid 代替
+        let key = if with_key {
+            format!("{}, ", aliases.select_item(RECORD_KEY_SQL, "record_id"))
+        } else {
+            String::new()
+        };
+        format!("SELECT {key}{} FROM sqllogs{filter}", columns.join(", "))
+    }
+
+    /// 导出的行过滤条件（含前导 ` WHERE`），未配置过滤时为空串
+    ///
+    /// 配置了 `top_sessions` 时只保留累计执行时间（相同时按累计影响行数）
+    /// 排名前 K 的会话的记录，排名规则与 `SessionAnalyzer` 一致；`ddl_only`
+    /// 时只保留 DDL 语句（见 [`DDL_FILTER_SQL`]）；`partition_day` 时只保留
+    /// 该日期分区的记录。
+    fn export_filter_sql(options: &ExportOptions) -> String {
+        let mut conditions = Vec::new();
+        if options.ddl_only {
+            conditions.push(DDL_FILTER_SQL.to_string());
+        }
+        match options.partition_day.as_deref() {
+            Some(HIVE_DEFAULT_PARTITION) => {
+                conditions.push(format!("{OCCURRENCE_DAY_SQL} IS NULL"));
+            }
+            Some(day) => conditions.push(format!(
+                "{OCCURRENCE_DAY_SQL} = {}",
+                sql_string_literal(day)
+            )),
+            None => {}
+        }
+        if let Some(k) = options.top_sessions {
+            conditions.push(format!(
+                "session IN (SELECT session FROM sqllogs \
+                 WHERE session IS NOT NULL GROUP BY session \
+                 ORDER BY COALESCE(SUM(execute_time), 0) DESC, \
+                 COALESCE(SUM(rowcount), 0) DESC, session LIMIT {k})"
+            ));
+        }
+        if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        }
+    }
+
+    /// 使用 `DuckDB` COPY 命令将查询结果写入文件
+    fn copy_to(
+        &self,
+        select_sql: &str,
+        output_path: &str,
+        copy_options: &str,
+    ) -> Result<()> {
         let copy_sql = format!(
-            "COPY (SELECT * FROM sqllogs) TO '{}' (FORMAT JSON)",
-            output_path.replace('\\', "\\\\")
+            "COPY ({select_sql}) TO {} ({copy_options})",
+            sql_string_literal(output_path)
         );
 
         self.connection
             .execute_batch(&copy_sql)
-            .with_context(|| format!("无法导出 JSON 文件: {output_path}"))?;
+            .with_context(|| format!("无法导出文件: {output_path}"))?;
 
         Ok(())
     }
 
-    /// 导出数据到 CSV 格式（使用 `DuckDB` COPY 命令）
-    fn export_to_csv(&self, output_path: &str) -> Result<()> {
-        let copy_sql = format!(
-            "COPY (SELECT * FROM sqllogs) TO '{}' (FORMAT CSV, HEADER)",
-            output_path.replace('\\', "\\\\")
+    /// 将超出截断长度的完整 description 写入旁路 JSONL 文件
+    ///
+    /// 旁路文件每行一个 `{"record_id": .., "description": ..}` 对象，
+    /// 直接在 Rust 侧序列化，不依赖 `DuckDB` 的 json 扩展。旁路文件中的
+    /// description 与主导出文件一样按 `format` 的脱敏规则输出。
+    ///
+    /// 返回写入旁路文件的记录数。
+    fn export_description_overflow(
+        &self,
+        format: &ExportFormat,
+        max_bytes: usize,
+        overflow_path: &Path,
+        options: &ExportOptions,
+    ) -> Result<u64> {
+        let aliases = &options.column_aliases;
+        // 与主导出文件使用相同的行过滤条件
+        let filter = match Self::export_filter_sql(options) {
+            f if f.is_empty() => String::new(),
+            f => f.replacen(" WHERE", " AND", 1),
+        };
+        let file = File::create(overflow_path).with_context(|| {
+            format!(
+                "无法创建 description 旁路文件: {}",
+                overflow_path.display()
+            )
+        })?;
+        let mut writer = BufWriter::new(file);
+
+        let mut stmt = self
+            .connection
+            .prepare(&format!(
+                "SELECT {RECORD_KEY_SQL}, {} FROM sqllogs \
+                 WHERE strlen(description) > ?{filter} ORDER BY rowid",
+                options.redactions.column_sql(format, "description")
+            ))
+            .context("查询超长 description 失败")?;
+        let max_bytes = i64::try_from(max_bytes).unwrap_or(i64::MAX);
+        let rows = stmt.query_map([max_bytes], |row| {
+            Ok((row.get::<_, u64>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut written = 0u64;
+        for row in rows {
+            let (record_id, description) = row?;
+            let mut line = serde_json::Map::new();
+            line.insert(
+                aliases.output_name("record_id").to_string(),
+                record_id.into(),
+            );
+            line.insert(
+                aliases.output_name("description").to_string(),
+                description.into(),
+            );
+            let line = serde_json::Value::Object(line);
+            writeln!(writer, "{line}")
+                .context("写入 description 旁路文件失败")?;
+            written += 1;
+        }
+        writer.flush().context("写入 description 旁路文件失败")?;
+
+        Ok(written)
+    }
+
+    /// 按导出选项导出数据
+    ///
+    /// 与 [`DatabaseProvider::export_data`] 相比，额外支持 description 截断
+    /// 与旁路文件等导出选项，并返回本次写出的产物列表（可用于生成导出清单）。
+    /// 设置了压缩方式时实际写出的路径追加 `.gz`/`.zst`，以产物列表为准；
+    /// description 旁路文件不压缩。设置了 `hive_partitions` 时按日期分区
+    /// 写出（见 [`Self::export_hive_partitioned`]）。
+    ///
+    /// # Errors
+    /// 当 COPY 导出失败时返回错误
+    pub fn export_with_options(
+        &self,
+        format: ExportFormat,
+        output_path: &str,
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        if options.hive_partitions {
+            return self.export_hive_partitioned(format, output_path, options);
+        }
+        let _span = enter_span!("sqllog.export", format = format.extension());
+        match format {
+            ExportFormat::Template => {
+                return self.export_template_with_options(output_path, options);
+            }
+            ExportFormat::Avro => {
+                return self.export_avro_with_options(output_path, options);
+            }
+            ExportFormat::Json | ExportFormat::Csv => {}
+        }
+        let select_sql = Self::export_select_sql(&format, options);
+        let mut copy_options = match format {
+            ExportFormat::Json => options.format_options.json_copy_options(),
+            ExportFormat::Csv => options.format_options.csv_copy_options(),
+            ExportFormat::Template | ExportFormat::Avro => {
+                unreachable!("模板与 Avro 导出已单独处理")
+            }
+        };
+        // 压缩时输出路径追加 .gz/.zst，清单与报告中记录实际写出的路径
+        let compressed_path;
+        let output_path = match options.compression {
+            Some(compression) => {
+                copy_options.push_str(", ");
+                copy_options.push_str(compression.copy_option());
+                compressed_path = compression.output_path(output_path);
+                compressed_path.as_str()
+            }
+            None => output_path,
+        };
+        let exporter = format.extension();
+        self.copy_to(&select_sql, output_path, &copy_options)
+            .map_err(|e| disk_full_error(exporter, output_path, &[], e))?;
+
+        let filter = Self::export_filter_sql(options);
+        let records_exported = if filter.is_empty() {
+            self.count_records()?
+        } else {
+            let count: i64 = self
+                .connection
+                .query_row(
+                    &format!("SELECT COUNT(*) FROM sqllogs{filter}"),
+                    [],
+                    |row| row.get(0),
+                )
+                .context("查询导出记录数失败")?;
+            count.try_into().context("记录数转换失败：不能为负数")?
+        };
+        let mut report = ExportReport {
+            records_exported,
+            artifacts: vec![ExportArtifact {
+                path: output_path.to_string(),
+                records: records_exported,
+            }],
+        };
+
+        if let (Some(max_bytes), Some(overflow_path)) =
+            (options.description_max_bytes, &options.description_overflow_path)
+        {
+            let records = self
+                .export_description_overflow(
+                    &format,
+                    max_bytes,
+                    overflow_path,
+                    options,
+                )
+                .map_err(|e| {
+                    disk_full_error(
+                        exporter,
+                        &overflow_path.to_string_lossy(),
+                        &[output_path],
+                        e,
+                    )
+                })?;
+            report.artifacts.push(ExportArtifact {
+                path: overflow_path.to_string_lossy().to_string(),
+                records,
+            });
+        }
+
+        Ok(report)
+    }
+
+    /// 按记录的 `occurrence_time` 把导出写入 Hive 风格的日期分区目录
+    ///
+    /// 导出路径 `out/sqllogs.csv` 下，2025-09-21 的记录写入
+    /// `out/year=2025/month=09/day=21/sqllogs.csv`，日期无法识别的记录写入
+    /// `out/__HIVE_DEFAULT_PARTITION__/sqllogs.csv`。各分区按日期顺序分别
+    /// 导出，其他选项不变；description 旁路文件写入同一分区目录。
+    fn export_hive_partitioned(
+        &self,
+        format: ExportFormat,
+        output_path: &str,
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        let output = Path::new(output_path);
+        let file_name = output
+            .file_name()
+            .with_context(|| format!("导出路径缺少文件名: {output_path}"))?;
+        let root = output.parent().unwrap_or_else(|| Path::new(""));
+        let days: Vec<Option<String>> = self
+            .connection
+            .prepare(&format!(
+                "SELECT DISTINCT {OCCURRENCE_DAY_SQL} AS day FROM sqllogs{} \
+                 ORDER BY day NULLS LAST",
+                Self::export_filter_sql(options)
+            ))?
+            .query_map([], |row| row.get(0))?
+            .collect::<DuckResult<_>>()
+            .context("查询导出日期分区失败")?;
+
+        let mut report =
+            ExportReport { records_exported: 0, artifacts: Vec::new() };
+        for day in days {
+            let dir = match &day {
+                Some(day) => root.join(format!(
+                    "year={}/month={}/day={}",
+                    &day[..4],
+                    &day[5..7],
+                    &day[8..10]
+                )),
+                None => root.join(HIVE_DEFAULT_PARTITION),
+            };
+            std::fs::create_dir_all(&dir).with_context(|| {
+                format!("创建分区目录失败: {}", dir.display())
+            })?;
+            let partition = ExportOptions {
+                hive_partitions: false,
+                partition_day: Some(
+                    day.unwrap_or_else(|| HIVE_DEFAULT_PARTITION.to_string()),
+                ),
+                description_overflow_path: options
+                    .description_overflow_path
+                    .as_ref()
+                    .and_then(|path| path.file_name())
+                    .map(|name| dir.join(name)),
+                ..options.clone()
+            };
+            let part = self.export_with_options(
+                format.clone(),
+                &dir.join(file_name).to_string_lossy(),
+                &partition,
+            )?;
+            report.records_exported += part.records_exported;
+            report.artifacts.extend(part.artifacts);
+        }
+        Ok(report)
+    }
+
+    /// 把 DDL 语句（CREATE/ALTER/DROP）单独导出为审计文件
+    ///
+    /// 格式按 `output_path` 的扩展名选择（见 [`ExportFormat::from_path`]），
+    /// 无法识别时写出 CSV。列别名、脱敏规则与压缩方式与该格式的主导出文件
+    /// 相同；description 不截断，也不受 `top_sessions` 限制，不按日期分区。
+    ///
+    /// # Errors
+    /// 当导出失败时返回错误
+    pub fn export_ddl_audit(
+        &self,
+        output_path: &str,
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        let format = ExportFormat::from_path(Path::new(output_path))
+            .unwrap_or(ExportFormat::Csv);
+        let options = ExportOptions {
+            ddl_only: true,
+            hive_partitions: false,
+            top_sessions: None,
+            description_max_bytes: None,
+            description_overflow_path: None,
+            ..options.clone()
+        };
+        self.export_with_options(format, output_path, &options)
+    }
+
+    /// 按行模板导出数据，返回写出的行数
+    ///
+    /// 只查询模板引用的列，行过滤条件与脱敏规则（`redact.template`）与其他
+    /// 格式相同；记录按插入顺序输出。
+    ///
+    /// # Errors
+    /// 当文件无法创建、查询或写入失败时返回错误
+    pub fn export_template(
+        &self,
+        template: &LineTemplate,
+        output_path: &str,
+        options: &ExportOptions,
+    ) -> Result<u64> {
+        let columns = template.columns();
+        let select_list: Vec<String> = columns
+            .iter()
+            .map(|col| {
+                let value =
+                    options.redactions.column_sql(&ExportFormat::Template, col);
+                format!("CAST({value} AS VARCHAR)")
+            })
+            .collect();
+        let file = File::create(output_path)
+            .with_context(|| format!("无法创建导出文件: {output_path}"))?;
+        let mut exporter = TemplateExporter::with_template(
+            template.clone(),
+            BufWriter::new(file),
         );
 
-        self.connection
-            .execute_batch(&copy_sql)
-            .with_context(|| format!("无法导出 CSV 文件: {output_path}"))?;
+        let mut stmt = self
+            .connection
+            .prepare(&format!(
+                "SELECT {} FROM sqllogs{} ORDER BY rowid",
+                select_list.join(", "),
+                Self::export_filter_sql(options)
+            ))
+            .context("查询模板导出数据失败")?;
+        let mut rows = stmt.query([])?;
+        let mut values: Vec<Option<String>> = Vec::with_capacity(columns.len());
+        while let Some(row) = rows.next()? {
+            values.clear();
+            for i in 0..columns.len() {
+                values.push(row.get(i)?);
+            }
+            let line = template.render_with(|column| {
+                columns
+                    .iter()
+                    .position(|&c| c == column)
+                    .and_then(|i| values[i].as_deref())
+                    .map(Cow::Borrowed)
+            });
+            exporter
+                .write_line(&line)
+                .with_context(|| format!("写入导出文件失败: {output_path}"))?;
+        }
+        exporter
+            .finish()
+            .with_context(|| format!("写入导出文件失败: {output_path}"))
+    }
 
-        Ok(())
+    fn export_template_with_options(
+        &self,
+        output_path: &str,
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        let Some(template) = &options.template else {
+            bail!("template 导出格式需要配置 export.template 行模板");
+        };
+        if options.compression.is_some() {
+            bail!("template 导出格式不支持压缩，请移除 export.compression");
+        }
+        let records = self
+            .export_template(template, output_path, options)
+            .map_err(|e| disk_full_error("txt", output_path, &[], e))?;
+        Ok(ExportReport {
+            records_exported: records,
+            artifacts: vec![ExportArtifact {
+                path: output_path.to_string(),
+                records,
+            }],
+        })
+    }
+
+    /// 以 Avro 对象容器文件导出数据，返回写出的记录数
+    ///
+    /// 行过滤条件与脱敏规则（`redact.avro`）与其他格式相同；记录按插入顺序
+    /// 输出，不应用列别名与 description 截断。
+    ///
+    /// # Errors
+    /// 当文件无法创建、查询或写入失败时返回错误
+    #[cfg(feature = "exporter-avro")]
+    pub fn export_avro(
+        &self,
+        output_path: &str,
+        options: &ExportOptions,
+    ) -> Result<u64> {
+        let file = File::create(output_path)
+            .with_context(|| format!("无法创建导出文件: {output_path}"))?;
+        let mut exporter = super::avro::AvroExporter::new(
+            BufWriter::new(file),
+            options.format_options.avro,
+        )
+        .with_context(|| format!("写入导出文件失败: {output_path}"))?;
+
+        let columns: Vec<String> = SQLLOG_COLUMNS
+            .iter()
+            .map(|col| {
+                options.redactions.column_sql(&ExportFormat::Avro, col).into()
+            })
+            .collect();
+        let mut stmt = self
+            .connection
+            .prepare(&format!(
+                "SELECT {} FROM sqllogs{} ORDER BY rowid",
+                columns.join(", "),
+                Self::export_filter_sql(options)
+            ))
+            .context("查询 Avro 导出数据失败")?;
+        let mut rows = stmt.query([])?;
+        let mut batch = Vec::with_capacity(1024);
+        while let Some(row) = rows.next()? {
+            batch.push(sqllog_from_row(row)?);
+            if batch.len() == batch.capacity() {
+                exporter.write_records(&batch).with_context(|| {
+                    format!("写入导出文件失败: {output_path}")
+                })?;
+                batch.clear();
+            }
+        }
+        exporter
+            .write_records(&batch)
+            .with_context(|| format!("写入导出文件失败: {output_path}"))?;
+        exporter
+            .finish()
+            .with_context(|| format!("写入导出文件失败: {output_path}"))
+    }
+
+    fn export_avro_with_options(
+        &self,
+        output_path: &str,
+        options: &ExportOptions,
+    ) -> Result<ExportReport> {
+        #[cfg(feature = "exporter-avro")]
+        {
+            if options.compression.is_some() {
+                bail!("avro 导出格式不支持压缩，请移除 export.compression");
+            }
+            let records = self
+                .export_avro(output_path, options)
+                .map_err(|e| disk_full_error("avro", output_path, &[], e))?;
+            Ok(ExportReport {
+                records_exported: records,
+                artifacts: vec![ExportArtifact {
+                    path: output_path.to_string(),
+                    records,
+                }],
+            })
+        }
+        #[cfg(not(feature = "exporter-avro"))]
+        {
+            let _ = (output_path, options);
+            bail!("avro 导出格式需要启用 exporter-avro 特性")
+        }
+    }
+
+    /// 以 Arrow `RecordBatch` 读出导出范围内的记录
+    ///
+    /// 行过滤条件与导出相同，记录按插入顺序返回；列与
+    /// [`sqllog_schema`](super::sqllog_schema) 一致。
+    ///
+    /// # Errors
+    /// 当查询失败时返回错误
+    #[cfg(feature = "arrow")]
+    pub fn query_record_batches(
+        &self,
+        options: &ExportOptions,
+    ) -> Result<Vec<duckdb::arrow::record_batch::RecordBatch>> {
+        let mut stmt = self
+            .connection
+            .prepare(&format!(
+                "SELECT {} FROM sqllogs{} ORDER BY rowid",
+                SQLLOG_COLUMNS.join(", "),
+                Self::export_filter_sql(options)
+            ))
+            .context("查询 Arrow 数据失败")?;
+        let batches = stmt.query_arrow([]).context("查询 Arrow 数据失败")?;
+        Ok(batches.collect())
+    }
+
+    /// 统计 `execute_time` 不小于 `min_ms` 毫秒的记录数
+    ///
+    /// # Errors
+    /// 当数据库查询失败时返回错误
+    pub fn count_slow_queries(&self, min_ms: i64) -> Result<u64> {
+        let count: i64 = self
+            .connection
+            .query_row(
+                "SELECT COUNT(*) FROM sqllogs WHERE execute_time >= ?",
+                [min_ms],
+                |row| row.get(0),
+            )
+            .context("查询慢语句数失败")?;
+        count.try_into().context("记录数转换失败：不能为负数")
     }
 
     /// 获取数据库版本
@@ -336,6 +1127,152 @@ impl DuckDbProvider {
         Ok(())
     }
 
+    /// 按 `occurrence_time` 重新排列 sqllogs 表中的记录
+    ///
+    /// 记录以发生时间（相同时保持原有顺序）重新写入，使 `DuckDB` 各行组的
+    /// 最小/最大值统计按时间连续，时间范围查询可以跳过无关行组；导出时
+    /// `COPY` 也按该顺序输出。应在创建索引之前调用。
+    ///
+    /// # Errors
+    /// 当重写表失败时返回错误，此时表内容保持不变
+    pub fn cluster_by_occurrence_time(&mut self) -> Result<()> {
+        let started = Instant::now();
+        let result = self.connection.execute_batch(
+            "BEGIN TRANSACTION;
+             CREATE TEMP TABLE sqllogs_clustered AS
+                 SELECT * FROM sqllogs ORDER BY occurrence_time, rowid;
+             DELETE FROM sqllogs;
+             INSERT INTO sqllogs SELECT * FROM sqllogs_clustered;
+             DROP TABLE sqllogs_clustered;
+             COMMIT;",
+        );
+        if let Err(e) = result {
+            let _ = self.connection.execute_batch("ROLLBACK");
+            return Err(e).context("按发生时间重排 sqllogs 表失败");
+        }
+        log::info!(
+            "已按 occurrence_time 重排 sqllogs 表，耗时 {:?}",
+            started.elapsed()
+        );
+        Ok(())
+    }
+
+    /// 把 description 与 plan 移入冷字段表 [`COLD_TABLE`]
+    ///
+    /// 有 `record_id` 的记录按 ID 写入冷字段表，`sqllogs` 中只留下空的
+    /// description 与 NULL 的 plan，表保持原有结构、可以继续追加；再次调用
+    /// 只移动之后新写入的记录。同时创建（或替换）把冷字段拼回的视图
+    /// [`FULL_VIEW`]。没有 `record_id` 的记录保持不变。返回本次移动的记录数。
+    ///
+    /// # Errors
+    /// 当冷字段表中出现重复的 `record_id` 或执行失败时返回错误，此时回滚
+    pub fn split_cold_fields(&mut self) -> Result<u64> {
+        self.execute_sql("BEGIN TRANSACTION")?;
+        let moved = self.move_cold_fields();
+        if moved.is_err() {
+            let _ = self.connection.execute_batch("ROLLBACK");
+        } else {
+            self.execute_sql("COMMIT").context("提交冷字段拆分失败")?;
+        }
+        moved
+    }
+
+    fn move_cold_fields(&mut self) -> Result<u64> {
+        const PENDING: &str = "record_id IS NOT NULL \
+            AND (description <> '' OR plan IS NOT NULL)";
+        self.execute_sql(&format!(
+            "CREATE TABLE IF NOT EXISTS {COLD_TABLE} (
+                 record_id UBIGINT NOT NULL,
+                 description TEXT NOT NULL,
+                 plan TEXT
+             )"
+        ))?;
+        let moved = self
+            .connection
+            .execute(
+                &format!(
+                    "INSERT INTO {COLD_TABLE} \
+                     SELECT record_id, description, plan FROM sqllogs WHERE {PENDING}"
+                ),
+                [],
+            )
+            .context("写入冷字段表失败")?;
+        self.execute_sql(&format!(
+            "UPDATE sqllogs SET description = '', plan = NULL WHERE {PENDING}"
+        ))?;
+        let duplicates: i64 = self.connection.query_row(
+            &format!(
+                "SELECT count(*) FROM (SELECT record_id FROM {COLD_TABLE} \
+                 GROUP BY record_id HAVING count(*) > 1)"
+            ),
+            [],
+            |row| row.get(0),
+        )?;
+        if duplicates > 0 {
+            bail!("record_id 存在 {duplicates} 个重复值，无法按 ID 拆分冷字段");
+        }
+
+        let columns: Vec<String> = SQLLOG_COLUMNS
+            .iter()
+            .map(|col| match *col {
+                "description" | "plan" => {
+                    format!("COALESCE(c.{col}, h.{col}) AS {col}")
+                }
+                _ => format!("h.{col}"),
+            })
+            .collect();
+        self.execute_sql(&format!(
+            "CREATE OR REPLACE VIEW {FULL_VIEW} AS \
+             SELECT {}, h.rowid AS row_order FROM sqllogs h \
+             LEFT JOIN {COLD_TABLE} c ON h.record_id = c.record_id",
+            columns.join(", ")
+        ))?;
+        Ok(u64::try_from(moved).unwrap_or(u64::MAX))
+    }
+
+    /// 创建（或替换）按别名输出各列的视图 [`ALIASED_VIEW`]
+    ///
+    /// # Errors
+    /// 当视图创建失败时返回错误
+    pub fn create_aliased_view(
+        &mut self,
+        aliases: &ColumnAliases,
+    ) -> Result<()> {
+        self.execute_sql(&format!(
+            "CREATE OR REPLACE VIEW {ALIASED_VIEW} AS SELECT {} FROM sqllogs",
+            aliases.select_list()
+        ))
+    }
+
+    /// 在 `sqllogs` 表上执行聚合查询
+    ///
+    /// 查询经 [`AggregateQuery::to_sql`] 翻译后交给 `DuckDB` 执行，
+    /// 超出内存的分组状态由 `DuckDB` 自行溢写到磁盘。
+    ///
+    /// # Errors
+    /// 当 SQL 执行失败或结果类型转换失败时返回错误
+    pub fn aggregate(&self, query: &AggregateQuery) -> Result<AggregateResult> {
+        let sql = query.to_sql("sqllogs");
+        log::debug!("执行聚合查询: {sql}");
+        let mut stmt = self
+            .connection
+            .prepare(&sql)
+            .with_context(|| format!("准备聚合查询失败: {sql}"))?;
+        let rows = stmt
+            .query_map([], |row| {
+                query
+                    .select
+                    .iter()
+                    .enumerate()
+                    .map(|(i, expr)| aggregate_value(row, i, expr))
+                    .collect::<DuckResult<Vec<_>>>()
+            })
+            .context("执行聚合查询失败")?
+            .collect::<DuckResult<Vec<_>>>()
+            .context("读取聚合结果失败")?;
+        Ok(AggregateResult { columns: query.columns(), rows })
+    }
+
     /// 启用独立数据库处理模式
     pub fn enable_independent_processing(&mut self) {
         if self.independent_stats.is_none() {
@@ -380,6 +1317,12 @@ impl DuckDbProvider {
         base_config: &RuntimeConfig,
     ) -> Result<(Self, PathBuf)> {
         let temp_db_path = self.create_temp_database_path(base_config)?;
+        // 上次运行中断遗留的同名临时库中可能有旧记录，不能合并进主库
+        if temp_db_path.exists() {
+            std::fs::remove_file(&temp_db_path).with_context(|| {
+                format!("无法删除遗留的临时数据库: {}", temp_db_path.display())
+            })?;
+        }
 
         let mut temp_config = base_config.clone();
         temp_config.use_in_memory = false; // 强制使用文件
@@ -392,16 +1335,19 @@ impl DuckDbProvider {
     /// 处理单个文件到临时数据库
     /// 独立处理单个文件
     ///
+    /// 写入经由 `limiter` 限速；处理多个文件时应共用同一个限速器，
+    /// 使速率上限作用于整次运行而不是每个文件。
+    ///
     /// # Errors
     /// 当文件处理、数据库操作或文件解析失败时返回错误
     ///
     /// # Panics
     /// 当无法获取统计数据锁时会 panic
-    #[allow(clippy::too_many_lines)]
     pub fn process_file_independently<P>(
         &self,
         file_path: P,
         base_config: &RuntimeConfig,
+        limiter: &mut RateLimiter,
     ) -> Result<(IndependentDatabaseStats, PathBuf)>
     where
         P: AsRef<Path>,
@@ -432,79 +1378,17 @@ impl DuckDbProvider {
             ..Default::default()
         };
 
-        // 创建错误写入器（如果启用）
-        let error_writer = if base_config.sqllog_write_errors {
-            base_config.sqllog_errors_out_path.as_ref().map_or_else(
-                || {
-                    log::warn!("启用了错误写入但未指定输出路径");
-                    None
-                },
-                |error_path| match ErrorWriter::new(error_path) {
-                    Ok(writer) => {
-                        log::debug!(
-                            "错误写入器已启用，输出文件: {}",
-                            error_path.display()
-                        );
-                        Some(writer)
-                    }
-                    Err(e) => {
-                        log::error!("创建错误写入器失败: {e}，将仅记录到日志");
-                        None
-                    }
-                },
-            )
-        } else {
-            None
-        };
+        let error_writer = create_error_writer(base_config);
 
         // 解析文件并插入到临时数据库
-        let mut error_count = 0usize;
-        let chunk_size = base_config.sqllog_chunk_size.unwrap_or(0);
-
-        log::info!(
-            "process_file_independently: 开始解析文件，chunk_size = {chunk_size}"
-        );
-        let parse_result = crate::sqllog::Sqllog::parse_all(
+        let error_count = parse_file_cached(
+            &mut temp_provider,
             path,
-            chunk_size,
-            |records| {
-                log::debug!(
-                    "process_file_independently: 处理 {} 条记录",
-                    records.len()
-                );
-                match temp_provider.insert_batch(records) {
-                    Ok(inserted) => {
-                        local_stats.records_processed += records.len();
-                        local_stats.records_inserted += inserted;
-                        log::debug!(
-                            "process_file_independently: 成功插入 {} 条记录，累计处理: {}",
-                            inserted,
-                            local_stats.records_processed
-                        );
-                    }
-                    Err(e) => {
-                        log::error!("插入记录失败: {e}");
-                    }
-                }
-            },
-            |errors| {
-                error_count += errors.len();
-                log::warn!(
-                    "process_file_independently: 解析错误 {} 个",
-                    errors.len()
-                );
-
-                // 写入错误到文件（如果启用）
-                if let Some(ref writer) = error_writer {
-                    writer.write_errors(path, errors);
-                }
-            },
-        );
-
-        if let Err(e) = parse_result {
-            log::error!("process_file_independently: 解析文件失败: {e}");
-            return Err(e.into());
-        }
+            base_config,
+            limiter,
+            error_writer.as_deref(),
+            &mut local_stats,
+        )?;
 
         // 完成临时数据库架构
         temp_provider.finalize_schema()?;
@@ -526,12 +1410,7 @@ impl DuckDbProvider {
 
         // 更新全局统计（如果启用了独立处理）
         if let Some(stats) = &self.independent_stats {
-            let mut global_stats = stats.write().unwrap();
-            global_stats.records_processed += local_stats.records_processed;
-            global_stats.records_inserted += local_stats.records_inserted;
-            global_stats.files_processed += local_stats.files_processed;
-            global_stats.temp_databases_created +=
-                local_stats.temp_databases_created;
+            stats.write().unwrap().merge(&local_stats);
         }
 
         Ok((local_stats, temp_db_path))
@@ -550,6 +1429,22 @@ impl DuckDbProvider {
     /// # Errors
     /// 当数据库附加、数据插入或分离失败时返回错误
     pub fn merge_temp_database(&mut self, temp_db_path: &Path) -> Result<()> {
+        self.merge_temp_database_batch(temp_db_path, None)
+    }
+
+    /// 合并临时数据库，并在同一事务中登记已提交的批次
+    ///
+    /// `batch` 为 `(运行键, 批次号, 源文件)`；为 `None` 时与
+    /// [`Self::merge_temp_database`] 相同。
+    ///
+    /// # Errors
+    /// 当 ATTACH、插入或提交失败时返回错误，此时主库中不会留下该批次的记录
+    pub fn merge_temp_database_batch(
+        &mut self,
+        temp_db_path: &Path,
+        batch: Option<(&str, u64, &Path)>,
+    ) -> Result<()> {
+        let _span = enter_span!("sqllog.merge");
         if !temp_db_path.exists() {
             log::warn!("临时数据库文件不存在: {}", temp_db_path.display());
             return Ok(());
@@ -560,21 +1455,158 @@ impl DuckDbProvider {
         // 使用 DuckDB 的 ATTACH 和 INSERT FROM SELECT 来合并数据库
         let temp_path_str = temp_db_path.to_string_lossy();
         let attach_sql = format!("ATTACH '{temp_path_str}' AS temp_db");
-        let insert_sql = "INSERT INTO sqllogs SELECT * FROM temp_db.sqllogs";
         let detach_sql = "DETACH temp_db";
 
         // 执行合并操作
         self.execute_sql(&attach_sql).context("ATTACH 临时数据库失败")?;
+        if batch.is_some() {
+            self.execute_sql(RESUME_TABLE_SQL)?;
+        }
 
-        log::debug!("正在插入数据从 {} 到主数据库", temp_db_path.display());
-        self.execute_sql(insert_sql).context("插入数据到主数据库失败")?;
-
-        self.execute_sql(detach_sql).context("DETACH 临时数据库失败")?;
+        self.execute_sql("BEGIN TRANSACTION")?;
+        let merged = self.merge_attached(batch);
+        let finished = match merged {
+            Ok(()) => self.execute_sql("COMMIT").context("提交合并事务失败"),
+            Err(e) => {
+                let _ = self.execute_sql("ROLLBACK");
+                Err(e)
+            }
+        };
+        let detached =
+            self.execute_sql(detach_sql).context("DETACH 临时数据库失败");
+        finished?;
+        detached?;
 
         log::info!("数据库合并完成: {}", temp_db_path.display());
         Ok(())
     }
 
+    /// 在当前事务中把已 ATTACH 的 `temp_db` 写入主库
+    fn merge_attached(
+        &mut self,
+        batch: Option<(&str, u64, &Path)>,
+    ) -> Result<()> {
+        log::debug!("正在插入数据从临时数据库到主数据库");
+        let records = self
+            .connection
+            .execute("INSERT INTO sqllogs SELECT * FROM temp_db.sqllogs", [])
+            .context("插入数据到主数据库失败")?;
+
+        // 临时库中写入过解析错误时一并合并
+        let has_errors: i64 = self.connection.query_row(
+            "SELECT count(*) FROM duckdb_tables() WHERE database_name = 'temp_db' AND table_name = 'parse_errors'",
+            [],
+            |row| row.get(0),
+        )?;
+        if has_errors > 0 {
+            self.execute_sql(CREATE_ERRORS_TABLE_SQL)?;
+            self.execute_sql(
+                "INSERT INTO parse_errors SELECT * FROM temp_db.parse_errors",
+            )
+            .context("合并解析错误表失败")?;
+        }
+
+        if let Some((run_key, batch_id, source)) = batch {
+            self.connection
+                .execute(
+                    "INSERT INTO sqllog_resume (run_key, batch_id, path, records) VALUES (?, ?, ?, ?)",
+                    duckdb::params![
+                        run_key,
+                        i64::try_from(batch_id).unwrap_or(i64::MAX),
+                        source.display().to_string(),
+                        i64::try_from(records).unwrap_or(i64::MAX),
+                    ],
+                )
+                .context("登记已提交批次失败")?;
+        }
+        Ok(())
+    }
+
+    /// 运行键下已提交的最大批次号；没有提交过任何批次时返回 `None`
+    ///
+    /// # Errors
+    /// 当查询失败时返回错误
+    pub fn last_committed_batch(
+        &mut self,
+        run_key: &str,
+    ) -> Result<Option<u64>> {
+        self.execute_sql(RESUME_TABLE_SQL)?;
+        let last: Option<i64> = self
+            .connection
+            .query_row(
+                "SELECT max(batch_id) FROM sqllog_resume WHERE run_key = ?",
+                [run_key],
+                |row| row.get(0),
+            )
+            .context("查询已提交批次失败")?;
+        Ok(last.and_then(|id| u64::try_from(id).ok()))
+    }
+
+    /// 向 `runs` 表追加一次运行的元数据（表不存在时创建）
+    ///
+    /// # Errors
+    /// 当建表或写入失败时返回错误
+    pub fn record_run(&mut self, run: &RunRecord) -> Result<()> {
+        self.execute_sql(RUNS_TABLE_SQL)?;
+        let inputs =
+            serde_json::to_string(&run.inputs).context("序列化输入文件失败")?;
+        self.connection
+            .execute(
+                "INSERT INTO runs VALUES (?, CAST(? AS TIMESTAMP), CAST(? AS TIMESTAMP), ?, ?, ?, ?, ?, ?)",
+                duckdb::params![
+                    run.run_id,
+                    run.started_at,
+                    run.finished_at,
+                    run.crate_version,
+                    run.config_hash,
+                    inputs,
+                    i64::try_from(run.files_processed).unwrap_or(i64::MAX),
+                    i64::try_from(run.records_inserted).unwrap_or(i64::MAX),
+                    i64::try_from(run.parse_errors).unwrap_or(i64::MAX),
+                ],
+            )
+            .context("写入运行元数据失败")?;
+        Ok(())
+    }
+
+    /// 按列名把 Parquet 文件中的记录追加到 sqllogs 表，返回追加的记录数
+    ///
+    /// 文件中缺少的列写入 NULL，多余的列忽略；需要可用的 DuckDB parquet 扩展。
+    ///
+    /// # Errors
+    /// 当文件无法读取（含扩展不可用）或列类型不兼容时返回错误
+    pub fn load_parquet(&mut self, path: &Path) -> Result<u64> {
+        let path = path.to_string_lossy().replace('\'', "''");
+        let columns: Vec<String> = self
+            .connection
+            .prepare(&format!(
+                "SELECT name FROM parquet_schema('{path}') WHERE num_children IS NULL OR num_children = 0"
+            ))
+            .and_then(|mut stmt| {
+                stmt.query_map([], |row| row.get(0))?.collect()
+            })
+            .with_context(|| format!("无法读取 Parquet 文件: {path}"))?;
+        let shared: Vec<&str> = SQLLOG_COLUMNS
+            .iter()
+            .copied()
+            .filter(|col| columns.iter().any(|c| c == col))
+            .collect();
+        if shared.is_empty() {
+            bail!("Parquet 文件中没有 sqllogs 表的列: {path}");
+        }
+        let list = shared.join(", ");
+        let inserted = self
+            .connection
+            .execute(
+                &format!(
+                    "INSERT INTO sqllogs ({list}) SELECT {list} FROM read_parquet('{path}')"
+                ),
+                [],
+            )
+            .with_context(|| format!("导入 Parquet 文件失败: {path}"))?;
+        Ok(inserted as u64)
+    }
+
     /// 清理临时数据库文件
     /// 清理临时数据库文件
     ///
@@ -611,6 +1643,7 @@ impl DatabaseProvider for DuckDbProvider {
     fn initialize(&mut self) -> Result<()> {
         // 只创建表，不创建索引以提高插入性能
         self.create_table().context("创建数据库表失败")?;
+        self.migrate_ep_column()?;
 
         self.initialized = true;
         Ok(())
@@ -630,10 +1663,8 @@ impl DatabaseProvider for DuckDbProvider {
         format: ExportFormat,
         output_path: &str,
     ) -> Result<()> {
-        match format {
-            ExportFormat::Json => self.export_to_json(output_path),
-            ExportFormat::Csv => self.export_to_csv(output_path),
-        }
+        self.export_with_options(format, output_path, &ExportOptions::default())
+            .map(|_| ())
     }
 
     fn is_initialized(&self) -> bool {
@@ -665,6 +1696,53 @@ impl DatabaseProvider for DuckDbProvider {
         self.create_indexes().context("创建索引失败")?;
         Ok(())
     }
+
+    fn preflight(&self) -> Result<()> {
+        self.connection
+            .query_row("SELECT 1", [], |row| row.get::<_, i32>(0))
+            .context("数据库连接不可用")?;
+
+        // 在回滚的事务中建表，验证写权限且不留下痕迹
+        let probe = self.connection.execute_batch(
+            "BEGIN TRANSACTION;
+             CREATE TABLE sqllog_preflight_probe (x INTEGER);
+             ROLLBACK;",
+        );
+        if let Err(e) = probe {
+            let _ = self.connection.execute_batch("ROLLBACK");
+            return Err(e).context("数据库不可写");
+        }
+
+        let mut stmt = self.connection.prepare(
+            "SELECT column_name FROM information_schema.columns
+             WHERE table_name = 'sqllogs' AND table_schema = 'main'",
+        )?;
+        let existing = stmt
+            .query_map([], |row| row.get::<_, String>(0))?
+            .collect::<DuckResult<Vec<_>>>()
+            .context("读取 sqllogs 表结构失败")?;
+        if existing.is_empty() {
+            return Ok(());
+        }
+        let missing: Vec<&str> = SQLLOG_TABLE
+            .columns
+            .iter()
+            .filter(|col| {
+                !col.migrated
+                    && !existing
+                        .iter()
+                        .any(|e| e.eq_ignore_ascii_case(col.name))
+            })
+            .map(|col| col.name)
+            .collect();
+        if !missing.is_empty() {
+            anyhow::bail!(
+                "已存在的 sqllogs 表结构不兼容，缺少列: {}",
+                missing.join(", ")
+            );
+        }
+        Ok(())
+    }
 }
 
 impl Drop for DuckDbProvider {
@@ -674,12 +1752,513 @@ impl Drop for DuckDbProvider {
 }
 
 /// 独立数据库处理统计信息
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, serde::Serialize)]
 pub struct IndependentDatabaseStats {
     pub records_processed: usize,
     pub records_inserted: usize,
     pub files_processed: usize,
     pub temp_databases_created: usize,
+    /// 上报的解析错误数
+    pub parse_errors: usize,
+    /// 从解析缓存合并、未重新解析的文件数
+    pub files_from_cache: usize,
+    /// 断点续写时因已提交而跳过的文件数
+    pub files_resumed: usize,
+    /// 各文件的吞吐量与阶段耗时（按处理顺序）
+    pub files: Vec<FileThroughput>,
+}
+
+impl IndependentDatabaseStats {
+    /// 将另一份统计累加到当前统计
+    pub fn merge(&mut self, other: &Self) {
+        self.records_processed += other.records_processed;
+        self.records_inserted += other.records_inserted;
+        self.files_processed += other.files_processed;
+        self.temp_databases_created += other.temp_databases_created;
+        self.parse_errors += other.parse_errors;
+        self.files_from_cache += other.files_from_cache;
+        self.files_resumed += other.files_resumed;
+        self.files.extend(other.files.iter().cloned());
+    }
+
+    /// 全部文件的读取字节数
+    #[must_use]
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.bytes).sum()
+    }
+
+    /// 全部文件的处理耗时合计
+    #[must_use]
+    pub fn total_elapsed(&self) -> Duration {
+        self.files.iter().map(|f| f.elapsed).sum()
+    }
+}
+
+/// 单个文件的处理吞吐量统计
+///
+/// `elapsed` 为解析加写入的总耗时；其中 `insert_time` 为阻塞在数据库写入上的
+/// 时间，`throttle_time` 为写入限速造成的等待时间，剩余部分即解析耗时。
+#[derive(Debug, Default, Clone, serde::Serialize)]
+pub struct FileThroughput {
+    /// 文件路径
+    pub path: String,
+    /// 文件大小（字节）
+    pub bytes: u64,
+    /// 解析出的记录数
+    pub records: usize,
+    /// 总耗时
+    pub elapsed: Duration,
+    /// 数据库写入耗时
+    pub insert_time: Duration,
+    /// 写入限速等待耗时
+    pub throttle_time: Duration,
+}
+
+impl FileThroughput {
+    /// 解析耗时（总耗时扣除写入与限速等待）
+    #[must_use]
+    pub fn parse_time(&self) -> Duration {
+        self.elapsed.saturating_sub(self.insert_time + self.throttle_time)
+    }
+
+    /// 每秒处理的字节数（基于总耗时）
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn bytes_per_sec(&self) -> f64 {
+        per_sec(self.bytes as f64, self.elapsed)
+    }
+
+    /// 每秒处理的记录数（基于总耗时）
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn records_per_sec(&self) -> f64 {
+        per_sec(self.records as f64, self.elapsed)
+    }
+}
+
+fn per_sec(amount: f64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 { amount / secs } else { 0.0 }
+}
+
+/// 按运行时配置创建解析错误写入器（未启用或创建失败时返回 None）
+fn create_error_writer(
+    runtime_config: &RuntimeConfig,
+) -> Option<Box<dyn ErrorExporter>> {
+    if !runtime_config.sqllog_write_errors {
+        return None;
+    }
+    runtime_config.sqllog_errors_out_path.as_ref().map_or_else(
+        || {
+            log::warn!("启用了错误写入但未指定输出路径");
+            None
+        },
+        |error_path| match ErrorWriter::with_format(
+            error_path,
+            runtime_config.sqllog_errors_format,
+        ) {
+            Ok(writer) => {
+                log::info!(
+                    "错误写入器已启用，输出文件: {}（{:?}）",
+                    error_path.display(),
+                    runtime_config.sqllog_errors_format
+                );
+                Some(Box::new(writer) as Box<dyn ErrorExporter>)
+            }
+            Err(e) => {
+                log::error!("创建错误写入器失败: {e}，将仅记录到日志");
+                None
+            }
+        },
+    )
+}
+
+/// 写入 `path` 失败时，磁盘空间不足的错误转换为 [`DiskFullError`]
+///
+/// `completed` 为失败前已完整写出的文件；其他错误原样返回。
+fn disk_full_error(
+    exporter: &str,
+    path: &str,
+    completed: &[&str],
+    e: anyhow::Error,
+) -> anyhow::Error {
+    if !is_disk_full(&e) {
+        return e;
+    }
+    let mut err = DiskFullError::new(exporter, Some(PathBuf::from(path)), &e);
+    err.completed = completed.iter().map(PathBuf::from).collect();
+    err.into()
+}
+
+/// 解析单个文件并将记录写入 `provider`，累加 `stats` 中的记录计数
+///
+/// 解析错误（包括超时）通过日志与 `error_writer` 上报，不会中断处理，
+/// 启用 `sqllog_errors_table` 时还会在解析结束后写入 `parse_errors` 表；
+/// 返回值为该文件上报的解析错误数。
+///
+/// # Errors
+/// 当文件无法打开或读取时返回错误
+fn parse_file_into_provider(
+    provider: &mut DuckDbProvider,
+    path: &Path,
+    runtime_config: &RuntimeConfig,
+    limiter: &mut RateLimiter,
+    error_writer: Option<&dyn ErrorExporter>,
+    stats: &mut IndependentDatabaseStats,
+) -> Result<usize> {
+    let _span = enter_span!("sqllog.file", path = %path.display());
+    let mut options = runtime_config.parse_options();
+    let tuner = runtime_config.insert_auto_tune.map(BatchTuner::new);
+    if let Some(tuner) = &tuner {
+        // 自适应模式下解析按最小批大小分块，由写入器累积到当前批大小再写入
+        options.chunk_size = tuner.batch_size();
+    }
+    let plugin = match &runtime_config.plugin_command {
+        Some(command) => {
+            let plugin = ProcessPlugin::spawn(command)?;
+            Some(Box::new(plugin_stage(plugin, runtime_config.plugin_timeout))
+                as PluginFn)
+        }
+        None => None,
+    };
+    let mut error_count = 0usize;
+    let mut error_records = Vec::new();
+    let started = Instant::now();
+    let mut inserter = BatchInserter {
+        provider,
+        limiter,
+        tuner,
+        plugin,
+        plugin_error: None,
+        pending: Vec::new(),
+        pending_bytes: 0,
+        memory_limit: runtime_config.max_memory_bytes,
+        memory_error: None,
+        disk_full: None,
+        stats,
+        throughput: FileThroughput {
+            path: path.display().to_string(),
+            bytes: std::fs::metadata(path).map_or(0, |m| m.len()),
+            ..Default::default()
+        },
+    };
+
+    log::info!(
+        "开始解析文件 {}，chunk_size = {}，timeout = {:?}，自适应批大小 = {}",
+        path.display(),
+        options.chunk_size,
+        options.timeout,
+        inserter.tuner.is_some()
+    );
+    let parse_result = crate::sqllog::Sqllog::parse_with_options(
+        path,
+        &options,
+        |records| inserter.push(records),
+        |errors| {
+            error_count += errors.len();
+            log::warn!("解析错误 {} 个", errors.len());
+
+            // 写入错误到文件（如果启用）
+            if let Some(writer) = error_writer {
+                writer.export_errors(path, errors);
+            }
+            // 写入器在解析期间独占数据库连接，错误表在解析结束后统一写入
+            if runtime_config.sqllog_errors_table {
+                error_records
+                    .extend(ParseErrorRecord::from_errors(path, errors));
+            }
+        },
+    );
+    inserter.flush();
+    if let Err(e) = inserter.provider.insert_parse_errors(&error_records) {
+        log::error!("写入解析错误表失败: {e}");
+    }
+
+    if let Err(e) = parse_result {
+        log::error!("解析文件失败: {e}");
+        return Err(e.into());
+    }
+    if let Some(e) = inserter.disk_full.take() {
+        log::error!("文件 {} 写入时磁盘空间不足，已停止写入", path.display());
+        return Err(e.context(format!("解析文件失败: {}", path.display())));
+    }
+    if let Some(e) = inserter.memory_error.take() {
+        log::error!("文件 {} 超出内存上限: {e}", path.display());
+        return Err(e.context(format!("解析文件失败: {}", path.display())));
+    }
+    if let Some(e) = inserter.plugin_error.take() {
+        log::error!("文件 {} 经插件转换失败: {e:#}", path.display());
+        return Err(e.context(format!("解析文件失败: {}", path.display())));
+    }
+
+    let mut throughput = inserter.throughput;
+    throughput.elapsed = started.elapsed();
+    if let Some(tuner) = &inserter.tuner {
+        log::info!(
+            "文件 {} 自适应批大小: 最终 {}，调整 {} 次",
+            throughput.path,
+            tuner.batch_size(),
+            tuner.adjustments()
+        );
+    }
+    log::info!(
+        "文件 {} 吞吐量: {:.0} 条/秒，{:.0} 字节/秒（解析 {:?}，写入 {:?}，限速 {:?}）",
+        throughput.path,
+        throughput.records_per_sec(),
+        throughput.bytes_per_sec(),
+        throughput.parse_time(),
+        throughput.insert_time,
+        throughput.throttle_time
+    );
+    spans::file_finished(throughput.records, error_count, throughput.bytes);
+    inserter.stats.files.push(throughput);
+    inserter.stats.parse_errors += error_count;
+
+    Ok(error_count)
+}
+
+/// 按 `sqllog_cache_dir` 经解析缓存处理单个文件，返回值同
+/// [`parse_file_into_provider`]
+///
+/// 命中缓存时直接合并缓存条目；未命中时先解析到缓存目录中的临时库再合并，
+/// 没有解析错误时把临时库保留为缓存条目。未配置缓存目录或配置了记录转换
+/// 插件（插件的输出不由缓存键决定）时直接解析。
+///
+/// # Errors
+/// 当文件无法读取、解析失败或合并失败时返回错误
+fn parse_file_cached(
+    provider: &mut DuckDbProvider,
+    path: &Path,
+    runtime_config: &RuntimeConfig,
+    limiter: &mut RateLimiter,
+    error_writer: Option<&dyn ErrorExporter>,
+    stats: &mut IndependentDatabaseStats,
+) -> Result<usize> {
+    let dir = runtime_config
+        .sqllog_cache_dir
+        .as_ref()
+        .filter(|_| runtime_config.plugin_command.is_none());
+    let Some(dir) = dir else {
+        return parse_file_into_provider(
+            provider,
+            path,
+            runtime_config,
+            limiter,
+            error_writer,
+            stats,
+        );
+    };
+    let cache = ParseCache::new(dir);
+    let key = cache.key(path, &runtime_config.parse_options())?;
+    let entry = cache.entry_path(&key);
+
+    if entry.exists() {
+        let started = Instant::now();
+        let before = provider.count_records()?;
+        provider.merge_temp_database(&entry)?;
+        let records = usize::try_from(provider.count_records()? - before)
+            .unwrap_or(usize::MAX);
+        log::info!(
+            "文件 {} 命中解析缓存 {}，合并 {records} 条记录",
+            path.display(),
+            entry.display()
+        );
+        stats.records_processed += records;
+        stats.records_inserted += records;
+        stats.files_from_cache += 1;
+        stats.files.push(FileThroughput {
+            path: path.display().to_string(),
+            bytes: std::fs::metadata(path).map_or(0, |m| m.len()),
+            records,
+            elapsed: started.elapsed(),
+            ..Default::default()
+        });
+        return Ok(0);
+    }
+
+    let partial = cache.partial_path(&key);
+    if partial.exists() {
+        std::fs::remove_file(&partial).with_context(|| {
+            format!("无法删除遗留的缓存临时库: {}", partial.display())
+        })?;
+    }
+    let mut cache_config = runtime_config.clone();
+    cache_config.use_in_memory = false;
+    cache_config.db_path = partial.to_string_lossy().to_string();
+    let parsed = DuckDbProvider::new(&cache_config).and_then(|mut cached| {
+        cached.initialize()?;
+        parse_file_into_provider(
+            &mut cached,
+            path,
+            runtime_config,
+            limiter,
+            error_writer,
+            stats,
+        )
+    });
+    let error_count = match parsed {
+        Ok(count) => count,
+        Err(e) => {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+    };
+
+    provider.merge_temp_database(&partial)?;
+    if error_count > 0 {
+        log::info!("文件 {} 有解析错误，不写入解析缓存", path.display());
+        let _ = std::fs::remove_file(&partial);
+    } else if let Err(e) = std::fs::rename(&partial, &entry) {
+        log::warn!("写入解析缓存 {} 失败: {e}", entry.display());
+        let _ = std::fs::remove_file(&partial);
+    } else {
+        log::debug!(
+            "文件 {} 已写入解析缓存 {}",
+            path.display(),
+            entry.display()
+        );
+    }
+    Ok(error_count)
+}
+
+/// 配置了列别名时，在目标库中创建带别名的视图
+fn create_aliased_view(
+    provider: &mut DuckDbProvider,
+    runtime_config: &RuntimeConfig,
+) -> Result<()> {
+    let aliases = &runtime_config.export_options.column_aliases;
+    if aliases.is_empty() {
+        return Ok(());
+    }
+    provider.create_aliased_view(aliases)?;
+    log::info!("已创建带列别名的视图 {ALIASED_VIEW}");
+    Ok(())
+}
+
+/// 写库前对每个解析块执行的记录转换插件阶段
+type PluginFn = Box<dyn FnMut(&mut Vec<Sqllog>) -> Result<()> + Send>;
+
+/// 解析回调中的批量写入器：负责写入限速、耗时统计、自适应批大小与内存上限
+struct BatchInserter<'a> {
+    provider: &'a mut DuckDbProvider,
+    /// 整次运行共用的限速器
+    limiter: &'a mut RateLimiter,
+    tuner: Option<BatchTuner>,
+    /// 配置了 `[plugin]` 时写库前逐块转换记录
+    plugin: Option<PluginFn>,
+    /// 插件失败或超时的错误，之后的记录不再写入
+    plugin_error: Option<anyhow::Error>,
+    /// 自适应模式下尚未写入的记录
+    pending: Vec<Sqllog>,
+    /// `pending` 的估算内存（字节）
+    pending_bytes: usize,
+    /// 在途记录的内存上限（字节）
+    memory_limit: Option<usize>,
+    /// 单个解析块超过内存上限时的错误，之后的记录不再写入
+    memory_error: Option<anyhow::Error>,
+    /// 写入时磁盘空间不足的错误，之后的记录不再写入
+    disk_full: Option<anyhow::Error>,
+    stats: &'a mut IndependentDatabaseStats,
+    throughput: FileThroughput,
+}
+
+impl BatchInserter<'_> {
+    /// 接收一个解析块：固定批大小时直接写入，自适应模式下累积到当前批大小
+    ///
+    /// 设置了内存上限时，单个解析块超过上限即记录错误并停止写入；
+    /// 累积的记录将超过上限时提前写入。
+    fn push(&mut self, records: &[Sqllog]) {
+        if self.memory_error.is_some()
+            || self.disk_full.is_some()
+            || self.plugin_error.is_some()
+        {
+            return;
+        }
+        let transformed;
+        let records = match &mut self.plugin {
+            Some(plugin) => {
+                let mut batch = records.to_vec();
+                if let Err(e) = plugin(&mut batch) {
+                    self.plugin_error = Some(e);
+                    return;
+                }
+                transformed = batch;
+                &transformed
+            }
+            None => records,
+        };
+        let mut bytes = 0;
+        if let Some(limit) = self.memory_limit {
+            bytes = records.iter().map(Sqllog::estimated_bytes).sum();
+            if bytes > limit {
+                self.memory_error = Some(anyhow::anyhow!(
+                    "单个解析块 {} 条记录约 {bytes} 字节，超过内存上限 max_memory_bytes = {limit}；请减小 sqllog.chunk_size 或提高上限",
+                    records.len()
+                ));
+                return;
+            }
+            if self.pending_bytes + bytes > limit {
+                self.flush();
+            }
+        }
+
+        let Some(tuner) = &self.tuner else {
+            self.insert(records);
+            return;
+        };
+        self.pending.extend_from_slice(records);
+        self.pending_bytes += bytes;
+        if self.pending.len() >= tuner.batch_size() {
+            self.flush();
+        }
+    }
+
+    /// 写入全部累积的记录
+    fn flush(&mut self) {
+        if !self.pending.is_empty() {
+            let batch = std::mem::take(&mut self.pending);
+            self.pending_bytes = 0;
+            self.insert(&batch);
+        }
+    }
+
+    fn insert(&mut self, records: &[Sqllog]) {
+        let _span = enter_span!("sqllog.insert_batch", records = records.len());
+        log::debug!("处理 {} 条记录", records.len());
+        let waited = self.limiter.acquire(records.len());
+        if !waited.is_zero() {
+            log::trace!("写入限速，等待 {waited:?}");
+        }
+        self.throughput.throttle_time += waited;
+        self.throughput.records += records.len();
+
+        let insert_started = Instant::now();
+        let result = self.provider.insert_batch(records);
+        let elapsed = insert_started.elapsed();
+        self.throughput.insert_time += elapsed;
+        if let Some(tuner) = &mut self.tuner {
+            tuner.observe(records.len(), elapsed);
+        }
+
+        match result {
+            Ok(inserted) => {
+                self.stats.records_processed += records.len();
+                self.stats.records_inserted += inserted;
+                log::debug!(
+                    "成功插入 {} 条记录，累计: {}",
+                    inserted,
+                    self.stats.records_processed
+                );
+            }
+            Err(e) if is_disk_full(&e) => {
+                log::error!("插入记录失败，磁盘空间不足: {e:#}");
+                self.disk_full = Some(e);
+            }
+            Err(e) => {
+                log::error!("插入记录失败: {e}");
+            }
+        }
+    }
 }
 
 /// 使用独立数据库处理单个文件
@@ -706,74 +2285,25 @@ where
         ..Default::default()
     };
 
-    // 创建错误写入器（如果启用）
-    let error_writer = if runtime_config.sqllog_write_errors {
-        runtime_config.sqllog_errors_out_path.as_ref().map_or_else(
-            || {
-                log::warn!("启用了错误写入但未指定输出路径");
-                None
-            },
-            |error_path| match ErrorWriter::new(error_path) {
-                Ok(writer) => {
-                    log::info!(
-                        "错误写入器已启用，输出文件: {}",
-                        error_path.display()
-                    );
-                    Some(writer)
-                }
-                Err(e) => {
-                    log::error!("创建错误写入器失败: {e}，将仅记录到日志");
-                    None
-                }
-            },
-        )
-    } else {
-        None
-    };
+    let error_writer = create_error_writer(runtime_config);
+    let mut limiter = RateLimiter::new(runtime_config.insert_rate_limit);
 
     // 直接解析文件并插入到主数据库
-    let mut error_count = 0usize;
-    let chunk_size = runtime_config.sqllog_chunk_size.unwrap_or(0);
     let path = file_path.as_ref();
-
-    log::info!("开始解析文件 {}，chunk_size = {}", path.display(), chunk_size);
-    let parse_result = crate::sqllog::Sqllog::parse_all(
+    let error_count = parse_file_cached(
+        &mut main_provider,
         path,
-        chunk_size,
-        |records| {
-            log::debug!("直接处理 {} 条记录到主数据库", records.len());
-            match main_provider.insert_batch(records) {
-                Ok(inserted) => {
-                    stats.records_processed += records.len();
-                    stats.records_inserted += inserted;
-                    log::debug!(
-                        "成功插入 {} 条记录，累计: {}",
-                        inserted,
-                        stats.records_processed
-                    );
-                }
-                Err(e) => {
-                    log::error!("插入记录失败: {e}");
-                }
-            }
-        },
-        |errors| {
-            error_count += errors.len();
-            log::warn!("解析错误 {} 个", errors.len());
-
-            // 写入错误到文件（如果启用）
-            if let Some(ref writer) = error_writer {
-                writer.write_errors(path, errors);
-            }
-        },
-    );
-
-    if let Err(e) = parse_result {
-        log::error!("解析文件失败: {e}");
-        return Err(e.into());
+        runtime_config,
+        &mut limiter,
+        error_writer.as_deref(),
+        &mut stats,
+    )?;
+
+    if runtime_config.cluster_by_time {
+        main_provider.cluster_by_occurrence_time()?;
     }
-
     main_provider.finalize_schema()?;
+    create_aliased_view(&mut main_provider, runtime_config)?;
 
     if error_count > 0 {
         log::warn!(
@@ -792,7 +2322,6 @@ where
 ///
 /// # Errors
 /// 当数据库初始化、文件解析或数据处理失败时返回错误
-#[allow(clippy::too_many_lines)]
 pub fn process_files_with_independent_databases<P>(
     file_paths: &[P],
     runtime_config: &RuntimeConfig,
@@ -805,98 +2334,13 @@ where
         return Ok(IndependentDatabaseStats::default());
     }
 
-    // 如果只有一个文件，直接使用主数据库处理，不需要临时数据库和合并操作
-    if file_paths.len() == 1 {
-        log::info!("单文件处理，直接使用主数据库，无需合并");
-
-        let mut main_provider = DuckDbProvider::new(runtime_config)?;
-        main_provider.initialize()?;
-
-        let file_path = &file_paths[0];
-        let mut stats = IndependentDatabaseStats {
-            files_processed: 1,
-            temp_databases_created: 0, // 没有创建临时数据库
-            ..Default::default()
-        };
-
-        // 创建错误写入器（如果启用）
-        let error_writer = if runtime_config.sqllog_write_errors {
-            runtime_config.sqllog_errors_out_path.as_ref().map_or_else(
-                || {
-                    log::warn!("启用了错误写入但未指定输出路径");
-                    None
-                },
-                |error_path| match ErrorWriter::new(error_path) {
-                    Ok(writer) => {
-                        log::info!(
-                            "错误写入器已启用，输出文件: {}",
-                            error_path.display()
-                        );
-                        Some(writer)
-                    }
-                    Err(e) => {
-                        log::error!("创建错误写入器失败: {e}，将仅记录到日志");
-                        None
-                    }
-                },
-            )
-        } else {
-            None
-        };
-
-        // 直接解析文件并插入到主数据库
-        let mut error_count = 0usize;
-        let chunk_size = runtime_config.sqllog_chunk_size.unwrap_or(0);
-
-        log::info!(
-            "开始解析文件 {}，chunk_size = {}",
-            file_path.as_ref().display(),
-            chunk_size
+    // 如果只有一个文件，直接使用主数据库处理，不需要临时数据库和合并操作；
+    // 断点续写需要按批次提交，始终经由临时数据库
+    if file_paths.len() == 1 && !runtime_config.db_resume {
+        return process_file_with_independent_database(
+            &file_paths[0],
+            runtime_config,
         );
-        let parse_result = crate::sqllog::Sqllog::parse_all(
-            file_path,
-            chunk_size,
-            |records| {
-                log::debug!("直接处理 {} 条记录到主数据库", records.len());
-                match main_provider.insert_batch(records) {
-                    Ok(inserted) => {
-                        stats.records_processed += records.len();
-                        stats.records_inserted += inserted;
-                        log::debug!(
-                            "成功插入 {} 条记录，累计: {}",
-                            inserted,
-                            stats.records_processed
-                        );
-                    }
-                    Err(e) => {
-                        log::error!("插入记录失败: {e}");
-                    }
-                }
-            },
-            |errors| {
-                error_count += errors.len();
-                log::warn!("解析错误 {} 个", errors.len());
-
-                // 写入错误到文件（如果启用）
-                if let Some(ref writer) = error_writer {
-                    writer.write_errors(file_path, errors);
-                }
-            },
-        );
-
-        if let Err(e) = parse_result {
-            log::error!("解析文件失败: {e}");
-            return Err(e.into());
-        }
-
-        main_provider.finalize_schema()?;
-
-        if error_count > 0 {
-            log::warn!("文件处理完成，但有 {error_count} 个错误");
-        }
-
-        log::info!("单文件处理完成: {stats:?}");
-        return Ok(stats);
     }
 
     // 多文件处理：使用独立临时数据库
@@ -906,21 +2350,55 @@ where
     main_provider.enable_independent_processing();
     main_provider.initialize()?;
 
+    let resume = if runtime_config.db_resume {
+        let key = run_key(file_paths, &runtime_config.parse_options())?;
+        let last = main_provider.last_committed_batch(&key)?;
+        if let Some(last) = last {
+            log::info!("断点续写: 跳过已提交的批次 0..={last}");
+        }
+        Some((key, last))
+    } else {
+        None
+    };
+
     let mut all_temp_paths = Vec::new();
     let mut combined_stats = IndependentDatabaseStats::default();
+    // 限速作用于整次运行，所有文件共用一个限速器
+    let mut limiter = RateLimiter::new(runtime_config.insert_rate_limit);
 
     // 处理每个文件到独立的临时数据库
-    for file_path in file_paths {
-        let (file_stats, temp_path) = main_provider
-            .process_file_independently(file_path, runtime_config)?;
-
-        combined_stats.records_processed += file_stats.records_processed;
-        combined_stats.records_inserted += file_stats.records_inserted;
-        combined_stats.files_processed += file_stats.files_processed;
-        combined_stats.temp_databases_created +=
-            file_stats.temp_databases_created;
+    for (batch, file_path) in (0u64..).zip(file_paths) {
+        let committed = resume
+            .as_ref()
+            .and_then(|(_, last)| *last)
+            .is_some_and(|last| batch <= last);
+        if committed {
+            log::info!(
+                "文件 {} 已在之前的运行中提交，跳过",
+                file_path.as_ref().display()
+            );
+            combined_stats.files_resumed += 1;
+            continue;
+        }
 
-        all_temp_paths.push(temp_path);
+        let (file_stats, temp_path) = main_provider
+            .process_file_independently(
+                file_path,
+                runtime_config,
+                &mut limiter,
+            )?;
+
+        combined_stats.merge(&file_stats);
+
+        // 断点续写时逐个文件提交，中断前已解析的文件不必重跑
+        if let Some((key, _)) = &resume {
+            let marker = (key.as_str(), batch, file_path.as_ref());
+            main_provider
+                .merge_temp_database_batch(&temp_path, Some(marker))?;
+            main_provider.cleanup_temp_database(&temp_path)?;
+        } else {
+            all_temp_paths.push(temp_path);
+        }
     }
 
     // 合并所有临时数据库
@@ -929,7 +2407,11 @@ where
         main_provider.cleanup_temp_database(temp_path)?;
     }
 
+    if runtime_config.cluster_by_time {
+        main_provider.cluster_by_occurrence_time()?;
+    }
     main_provider.finalize_schema()?;
+    create_aliased_view(&mut main_provider, runtime_config)?;
 
     log::info!("所有数据库合并完成: {combined_stats:?}");
     Ok(combined_stats)
@@ -0,0 +1,158 @@
+// 共享 DuckDB 写入 - 多条流水线经由单一写入线程写入同一个库
+//
+// DuckDB 同一个库文件只允许一个进程写入，多个连接并发写入同一张表也会因
+// 事务冲突失败。[`SharedDuckDbSink`] 在打开时启动唯一的写入线程持有连接，
+// 各句柄（`clone_handle()`，可移动到其他线程）只把批次送入有界队列，由写入
+// 线程按到达顺序逐批插入，全部句柄结束后在 [`SharedDuckDbSink::finish`] 中
+// 创建索引并返回写入的记录数。
+//
+// 争用行为：
+// - 同一批的记录在表中保持连续；不同句柄的批次按进入队列的先后交错
+// - 队列满时写入方阻塞，直到写入线程取走批次，写入速度以数据库为准
+// - 某批插入失败后写入线程停止，之后所有句柄的写入都返回该错误
+
+use super::{DatabaseProvider, DuckDbProvider, SyncExporter};
+use crate::config::RuntimeConfig;
+use crate::sqllog::Sqllog;
+use anyhow::{Context, Result, anyhow};
+use std::io;
+use std::sync::mpsc::{Receiver, SyncSender, sync_channel};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+/// 默认的队列容量（批次数）
+pub const DEFAULT_SINK_QUEUE: usize = 16;
+
+/// 写入线程共享的状态
+#[derive(Debug)]
+struct SinkState {
+    /// 写入线程遇到的第一个错误
+    error: Mutex<Option<String>>,
+}
+
+/// 多条流水线共享的 DuckDB 写入句柄
+#[derive(Debug)]
+pub struct SharedDuckDbSink {
+    tx: SyncSender<Vec<Sqllog>>,
+    state: Arc<SinkState>,
+    worker: Arc<Mutex<Option<JoinHandle<Result<u64>>>>>,
+}
+
+impl SharedDuckDbSink {
+    /// 打开 `config.db_path` 指向的库并启动写入线程
+    ///
+    /// # Errors
+    /// 当数据库无法打开或建表失败时返回错误
+    pub fn open(config: &RuntimeConfig) -> Result<Self> {
+        Self::with_queue(config, DEFAULT_SINK_QUEUE)
+    }
+
+    /// 与 [`Self::open`] 相同，但指定队列容量（批次数，至少为 1）
+    ///
+    /// # Errors
+    /// 当数据库无法打开或建表失败时返回错误
+    pub fn with_queue(config: &RuntimeConfig, queue: usize) -> Result<Self> {
+        let mut provider = DuckDbProvider::new(config)?;
+        provider.initialize()?;
+        let (tx, rx) = sync_channel(queue.max(1));
+        let state = Arc::new(SinkState { error: Mutex::new(None) });
+        let worker_state = Arc::clone(&state);
+        let worker = thread::Builder::new()
+            .name("duckdb-sink".to_string())
+            .spawn(move || write_loop(provider, &rx, &worker_state))
+            .context("无法启动 DuckDB 写入线程")?;
+        Ok(Self { tx, state, worker: Arc::new(Mutex::new(Some(worker))) })
+    }
+
+    /// 创建写入同一个库的新句柄
+    #[must_use]
+    pub fn clone_handle(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            state: Arc::clone(&self.state),
+            worker: Arc::clone(&self.worker),
+        }
+    }
+
+    /// 当前存活的句柄数（含自身）
+    #[must_use]
+    pub fn handles(&self) -> usize {
+        Arc::strong_count(&self.worker)
+    }
+
+    /// 把一批记录送入写入队列，队列满时阻塞
+    ///
+    /// 写入是异步的：返回 `Ok` 只表示批次已入队，插入失败在之后的写入或
+    /// [`Self::finish`] 中报告。
+    ///
+    /// # Errors
+    /// 写入线程已因插入失败而停止时返回错误
+    pub fn write(&self, records: &[Sqllog]) -> io::Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        if let Some(error) = self.error() {
+            return Err(io::Error::new(io::ErrorKind::Other, error));
+        }
+        self.tx.send(records.to_vec()).map_err(|_| {
+            let error = self
+                .error()
+                .unwrap_or_else(|| "DuckDB 写入线程已退出".to_string());
+            io::Error::new(io::ErrorKind::Other, error)
+        })
+    }
+
+    /// 等待队列中的批次全部写入、创建索引，返回写入的记录数
+    ///
+    /// # Errors
+    /// 仍有其他句柄存活时原样返回自身；写入或建索引失败时返回
+    /// `Ok(Err(..))`
+    pub fn finish(self) -> Result<Result<u64>, Self> {
+        let worker = match Arc::try_unwrap(self.worker) {
+            Ok(worker) => worker,
+            Err(worker) => {
+                return Err(Self { tx: self.tx, state: self.state, worker });
+            }
+        };
+        drop(self.tx);
+        let handle =
+            worker.into_inner().unwrap_or_else(PoisonError::into_inner);
+        Ok(match handle {
+            Some(handle) => handle
+                .join()
+                .unwrap_or_else(|_| Err(anyhow!("DuckDB 写入线程 panic"))),
+            None => Err(anyhow!("DuckDB 写入线程已结束")),
+        })
+    }
+
+    fn error(&self) -> Option<String> {
+        self.state.error.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+impl SyncExporter for SharedDuckDbSink {
+    fn write_records(&mut self, records: &[Sqllog]) -> io::Result<()> {
+        self.write(records)
+    }
+}
+
+fn write_loop(
+    mut provider: DuckDbProvider,
+    rx: &Receiver<Vec<Sqllog>>,
+    state: &SinkState,
+) -> Result<u64> {
+    let mut written = 0u64;
+    let result = (|| {
+        for batch in rx {
+            provider.insert_batch(&batch)?;
+            written += batch.len() as u64;
+        }
+        provider.finalize_schema()
+    })();
+    if let Err(e) = &result {
+        log::error!("DuckDB 写入线程停止: {e:#}");
+        *state.error.lock().unwrap_or_else(PoisonError::into_inner) =
+            Some(format!("{e:#}"));
+    }
+    result.map(|()| written)
+}
@@ -0,0 +1,473 @@
+// 导出格式选项 - 各导出格式的类型化配置
+//
+// 每种导出格式有独立的选项结构体，均提供链式构造方法；`FormatOptions::set`
+// 支持以 `格式.键=值` 的文本形式逐项设置（例如 `csv.delimiter=;`），
+// 供配置文件的 `export.exporter_opts` 列表使用。
+
+use super::duckdb_impl::sql_string_literal;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// CSV 导出选项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CsvExportOptions {
+    /// 字段分隔符
+    pub delimiter: char,
+    /// 是否输出表头
+    pub header: bool,
+    /// 引号字符
+    pub quote: char,
+    /// NULL 值的输出文本，见 [`NullAs`]
+    pub null_string: String,
+    /// description 内换行的处理方式
+    pub newlines: Newlines,
+}
+
+/// CSV 中 NULL 的表示方式
+///
+/// 取值与 NULL 的表示相同的文本字段会加引号输出（如 NULL 输出为空时空串
+/// 输出为 `""`），读回时需按同样的 NULL 表示、且不把带引号的值视为 NULL
+/// （DuckDB 为 `read_csv(..., nullstr = ..., allow_quoted_nulls = false)`），
+/// 才能区分 NULL 与空串。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NullAs {
+    /// 空单元格，便于 Excel 等表格工具打开
+    #[default]
+    Empty,
+    /// `\N`，`MySQL`/`PostgreSQL` 等批量导入工具的约定
+    BackslashN,
+    /// 任意文本（如 `NULL`）
+    Literal(String),
+}
+
+impl NullAs {
+    /// NULL 的输出文本
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Empty => "",
+            Self::BackslashN => "\\N",
+            Self::Literal(text) => text,
+        }
+    }
+}
+
+impl From<&str> for NullAs {
+    /// `empty`（或空串）为空单元格，`\N` 为 [`NullAs::BackslashN`]，
+    /// 其他文本原样作为 NULL 的表示
+    fn from(s: &str) -> Self {
+        match s {
+            "" | "empty" => Self::Empty,
+            "\\N" => Self::BackslashN,
+            other => Self::Literal(other.to_string()),
+        }
+    }
+}
+
+/// CSV 中 description 内换行的处理方式
+///
+/// 多行 SQL 按 RFC 4180 加引号输出时，按行切分的简单解析器会把一条记录
+/// 拆成多行。[`Newlines::Escape`] 可由 [`Newlines::unfold`] 还原原文，
+/// [`Newlines::Space`] 不可还原。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Newlines {
+    /// 原样输出，由引号包裹（RFC 4180）
+    #[default]
+    Keep,
+    /// 换行写作 `\n`、回车写作 `\r`，反斜杠写作 `\\`
+    Escape,
+    /// `\r\n`、`\n`、`\r` 各替换为一个空格
+    Space,
+}
+
+impl Newlines {
+    /// 对一列取值做换行处理的 SQL 表达式
+    pub(crate) fn sql(self, value: &str) -> Cow<'_, str> {
+        match self {
+            Self::Keep => Cow::Borrowed(value),
+            Self::Escape => Cow::Owned(format!(
+                "replace(replace(replace({value}, '\\', '\\\\'), \
+                 chr(13), '\\r'), chr(10), '\\n')"
+            )),
+            Self::Space => Cow::Owned(format!(
+                "regexp_replace({value}, '\\r\\n|\\n|\\r', ' ', 'g')"
+            )),
+        }
+    }
+
+    /// 还原导出文本中的换行；只有 [`Newlines::Escape`] 需要还原
+    #[must_use]
+    pub fn unfold(self, text: &str) -> Cow<'_, str> {
+        if self != Self::Escape || !text.contains('\\') {
+            return Cow::Borrowed(text);
+        }
+        let mut out = String::with_capacity(text.len());
+        let mut chars = text.chars();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                out.push(c);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('r') => out.push('\r'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        }
+        Cow::Owned(out)
+    }
+}
+
+impl std::str::FromStr for Newlines {
+    type Err = String;
+
+    /// 解析 `keep` / `escape` / `space`（不区分大小写）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "keep" => Ok(Self::Keep),
+            "escape" => Ok(Self::Escape),
+            "space" => Ok(Self::Space),
+            _ => Err(format!("换行处理方式只能为 keep、escape 或 space: {s}")),
+        }
+    }
+}
+
+impl Default for CsvExportOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            header: true,
+            quote: '"',
+            null_string: String::new(),
+            newlines: Newlines::Keep,
+        }
+    }
+}
+
+impl CsvExportOptions {
+    /// 设置字段分隔符
+    #[must_use]
+    pub const fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// 设置是否输出表头
+    #[must_use]
+    pub const fn header(mut self, header: bool) -> Self {
+        self.header = header;
+        self
+    }
+
+    /// 设置引号字符
+    #[must_use]
+    pub const fn quote(mut self, quote: char) -> Self {
+        self.quote = quote;
+        self
+    }
+
+    /// 设置 NULL 值的输出文本
+    #[must_use]
+    pub fn null_string(mut self, null_string: &str) -> Self {
+        self.null_string = null_string.to_string();
+        self
+    }
+
+    /// 设置 NULL 的表示方式
+    #[must_use]
+    pub fn null_as(self, null_as: &NullAs) -> Self {
+        self.null_string(null_as.as_str())
+    }
+
+    /// 设置 description 内换行的处理方式
+    #[must_use]
+    pub const fn newlines(mut self, newlines: Newlines) -> Self {
+        self.newlines = newlines;
+        self
+    }
+}
+
+/// JSON 导出的文件布局
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum JsonLayout {
+    /// 每行一个 JSON 对象（JSONL）
+    #[default]
+    Lines,
+    /// 整个文件为一个 JSON 数组
+    Array,
+}
+
+/// JSON 导出选项
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct JsonExportOptions {
+    /// 文件布局
+    pub layout: JsonLayout,
+}
+
+impl JsonExportOptions {
+    /// 设置文件布局
+    #[must_use]
+    pub const fn layout(mut self, layout: JsonLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+}
+
+/// Avro 导出中 `occurrence_time` 的表示方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AvroTimestamps {
+    /// 原始文本（`string`）
+    #[default]
+    String,
+    /// `long` + `local-timestamp-millis` 逻辑类型（日志时间不带时区）
+    Logical,
+}
+
+/// Avro schema 的兼容模式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AvroCompatibility {
+    /// 可选字段为 `["null", T]` 且默认值为 null，新增可选字段不影响旧读者
+    #[default]
+    Backward,
+    /// 所有字段（含 occurrence_time/ep/description）都可为 null 且带默认值，
+    /// 字段增删两个方向都兼容
+    Full,
+}
+
+/// Avro 导出选项
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AvroExportOptions {
+    /// `occurrence_time` 的表示方式
+    pub timestamps: AvroTimestamps,
+    /// schema 兼容模式
+    pub compatibility: AvroCompatibility,
+}
+
+impl AvroExportOptions {
+    /// 设置 `occurrence_time` 的表示方式
+    #[must_use]
+    pub const fn timestamps(mut self, timestamps: AvroTimestamps) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// 设置 schema 兼容模式
+    #[must_use]
+    pub const fn compatibility(
+        mut self,
+        compatibility: AvroCompatibility,
+    ) -> Self {
+        self.compatibility = compatibility;
+        self
+    }
+}
+
+/// 导出文件的压缩方式
+///
+/// 由 `DuckDB` 在 COPY 写出时流式压缩，不需要对导出文件再做一遍压缩。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// gzip，扩展名 `.gz`
+    Gzip,
+    /// zstd，扩展名 `.zst`（需要 `DuckDB` 的 parquet 扩展）
+    Zstd,
+}
+
+impl Compression {
+    /// 压缩文件的扩展名
+    #[must_use]
+    pub const fn extension(self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+        }
+    }
+
+    /// 按输出路径末尾的 `.gz` / `.zst` 扩展名推断压缩方式
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "zst" => Some(Self::Zstd),
+            _ => None,
+        }
+    }
+
+    /// 为导出路径追加压缩扩展名（已带该扩展名时保持不变）
+    #[must_use]
+    pub fn output_path(self, path: &str) -> String {
+        let suffix = format!(".{}", self.extension());
+        if path.ends_with(&suffix) {
+            path.to_string()
+        } else {
+            format!("{path}{suffix}")
+        }
+    }
+
+    /// `DuckDB` COPY 的 `COMPRESSION` 取值
+    pub(crate) const fn copy_option(self) -> &'static str {
+        match self {
+            Self::Gzip => "COMPRESSION 'gzip'",
+            Self::Zstd => "COMPRESSION 'zstd'",
+        }
+    }
+}
+
+impl std::str::FromStr for Compression {
+    type Err = String;
+
+    /// 解析 `gzip` / `gz` / `zstd` / `zst`（不区分大小写）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (codec, level) = match s.trim().split_once(':') {
+            Some((codec, level)) => (codec, Some(level)),
+            None => (s.trim(), None),
+        };
+        let compression = match codec.to_lowercase().as_str() {
+            "gzip" | "gz" => Self::Gzip,
+            "zstd" | "zst" => Self::Zstd,
+            _ => {
+                return Err(format!(
+                    "不支持的压缩方式: {s}；可选值为 gzip/zstd"
+                ));
+            }
+        };
+        if let Some(level) = level {
+            return Err(format!(
+                "不支持指定压缩级别: {level}；DuckDB 导出使用默认级别，请只写 {codec}"
+            ));
+        }
+        Ok(compression)
+    }
+}
+
+/// 全部导出格式的选项集合
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub csv: CsvExportOptions,
+    pub json: JsonExportOptions,
+    pub avro: AvroExportOptions,
+}
+
+impl FormatOptions {
+    /// 解析 `格式.键=值` 形式的选项并应用
+    ///
+    /// 支持的键：
+    /// - `csv.delimiter` / `csv.quote`：单个字符（`\t` 表示制表符）
+    /// - `csv.header`：`true` / `false`
+    /// - `csv.null_string`：任意文本
+    /// - `csv.null_as`：`empty`、`\N` 或任意文本（见 [`NullAs`]）
+    /// - `csv.newlines`：`keep` / `escape` / `space`（见 [`Newlines`]）
+    /// - `json.layout`：`lines` / `array`
+    /// - `avro.timestamps`：`string` / `logical`
+    /// - `avro.compatibility`：`backward` / `full`
+    ///
+    /// # Errors
+    /// 当格式不是 `key=value`、键未知或值不合法时返回描述性错误
+    pub fn set(&mut self, pair: &str) -> Result<(), String> {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("导出选项格式应为 key=value: {pair}"))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        match key {
+            "csv.delimiter" => self.csv.delimiter = parse_char(key, value)?,
+            "csv.quote" => self.csv.quote = parse_char(key, value)?,
+            "csv.header" => {
+                self.csv.header = value.parse().map_err(|_| {
+                    format!("{key} 只能为 true 或 false: {value}")
+                })?;
+            }
+            "csv.null_string" => self.csv.null_string = value.to_string(),
+            "csv.null_as" => {
+                self.csv.null_string = NullAs::from(value).as_str().to_string();
+            }
+            "csv.newlines" => {
+                self.csv.newlines =
+                    value.parse().map_err(|e| format!("{key}: {e}"))?;
+            }
+            "json.layout" => {
+                self.json.layout = match value.to_lowercase().as_str() {
+                    "lines" => JsonLayout::Lines,
+                    "array" => JsonLayout::Array,
+                    _ => {
+                        return Err(format!(
+                            "{key} 只能为 lines 或 array: {value}"
+                        ));
+                    }
+                };
+            }
+            "avro.timestamps" => {
+                self.avro.timestamps = match value.to_lowercase().as_str() {
+                    "string" => AvroTimestamps::String,
+                    "logical" => AvroTimestamps::Logical,
+                    _ => {
+                        return Err(format!(
+                            "{key} 只能为 string 或 logical: {value}"
+                        ));
+                    }
+                };
+            }
+            "avro.compatibility" => {
+                self.avro.compatibility = match value.to_lowercase().as_str() {
+                    "backward" => AvroCompatibility::Backward,
+                    "full" => AvroCompatibility::Full,
+                    _ => {
+                        return Err(format!(
+                            "{key} 只能为 backward 或 full: {value}"
+                        ));
+                    }
+                };
+            }
+            _ => return Err(format!("未知的导出选项: {key}")),
+        }
+        Ok(())
+    }
+
+    /// 依次应用多条 `格式.键=值` 选项
+    ///
+    /// # Errors
+    /// 任一选项不合法时返回其错误
+    pub fn from_pairs<I, S>(pairs: I) -> Result<Self, String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut options = Self::default();
+        for pair in pairs {
+            options.set(pair.as_ref())?;
+        }
+        Ok(options)
+    }
+
+    /// 生成 CSV 导出的 `DuckDB` COPY 选项
+    pub(crate) fn csv_copy_options(&self) -> String {
+        let csv = &self.csv;
+        format!(
+            "FORMAT CSV, HEADER {}, DELIMITER {}, QUOTE {}, NULLSTR {}",
+            csv.header,
+            sql_string_literal(&csv.delimiter.to_string()),
+            sql_string_literal(&csv.quote.to_string()),
+            sql_string_literal(&csv.null_string),
+        )
+    }
+
+    /// 生成 JSON 导出的 `DuckDB` COPY 选项
+    pub(crate) fn json_copy_options(&self) -> String {
+        match self.json.layout {
+            JsonLayout::Lines => "FORMAT JSON".to_string(),
+            JsonLayout::Array => "FORMAT JSON, ARRAY true".to_string(),
+        }
+    }
+}
+
+fn parse_char(key: &str, value: &str) -> Result<char, String> {
+    if value == "\\t" {
+        return Ok('\t');
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c),
+        _ => Err(format!("{key} 必须是单个字符: {value}")),
+    }
+}
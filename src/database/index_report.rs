@@ -0,0 +1,273 @@
+// 库体积报告 - 汇总导出库各表与索引的规模及 sqllogs 的取值分布
+//
+// [`IndexReport::generate`] 读取库中的全部表与索引，给出行数、估算体积、
+// sqllogs 最早与最晚的 `occurrence_time` 以及各维度列的不同取值数，可序列化
+// 为 JSON 供命令行打印或保存（`--duckdb-report`）。
+//
+// 体积为按列取值估算的逻辑大小：定长类型按类型宽度计，其余类型按文本长度计；
+// 索引按键列的逻辑大小加每行 8 字节的行号估算，不含 ART 节点开销。`DuckDB`
+// 存储时会压缩，磁盘上的实际占用见 `file_bytes`。
+
+use super::duckdb_impl::sql_identifier;
+use super::{DatabaseMode, DatabaseProvider, DuckDbProvider};
+use anyhow::{Context, Result};
+use duckdb::Connection;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+/// 统计不同取值数的 sqllogs 列
+const DISTINCT_COLUMNS: [&str; 9] = [
+    "ep",
+    "session",
+    "thread",
+    "username",
+    "trx_id",
+    "statement",
+    "appname",
+    "ip",
+    "sql_type",
+];
+
+/// 表名及其各列（列名、类型）
+type TableColumns = (String, Vec<(String, String)>);
+
+/// 一张表的规模
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableSize {
+    /// 表名
+    pub name: String,
+    /// 行数
+    pub rows: u64,
+    /// 列数
+    pub columns: u64,
+    /// 估算的逻辑大小（字节）
+    pub estimated_bytes: u64,
+}
+
+/// 一个索引的规模
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexSize {
+    /// 索引名
+    pub name: String,
+    /// 所属表
+    pub table: String,
+    /// 键列（或表达式）
+    pub columns: Vec<String>,
+    /// 是否唯一索引
+    pub unique: bool,
+    /// 估算大小（字节）
+    pub estimated_bytes: u64,
+}
+
+/// 导出库的体积报告
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct IndexReport {
+    /// 数据库模式（内存/磁盘）
+    pub mode: DatabaseMode,
+    /// 库文件大小（字节），内存模式为 `None`
+    pub file_bytes: Option<u64>,
+    /// 各表，按表名排序
+    pub tables: Vec<TableSize>,
+    /// 各索引，按所属表与索引名排序
+    pub indexes: Vec<IndexSize>,
+    /// sqllogs 中最早的 `occurrence_time`
+    pub first_occurrence: Option<String>,
+    /// sqllogs 中最晚的 `occurrence_time`
+    pub last_occurrence: Option<String>,
+    /// sqllogs 各维度列的不同取值数（不含 NULL）
+    pub distinct: BTreeMap<String, u64>,
+}
+
+impl IndexReport {
+    /// 生成报告
+    ///
+    /// # Errors
+    /// 当查询库结构或统计失败时返回错误
+    pub fn generate(provider: &DuckDbProvider) -> Result<Self> {
+        let mode = provider.database_info().mode;
+        let file_bytes = match &mode {
+            DatabaseMode::Disk { path } => {
+                std::fs::metadata(path).ok().map(|m| m.len())
+            }
+            DatabaseMode::InMemory => None,
+        };
+        let conn = provider.connection();
+
+        let mut tables = Vec::new();
+        // (表名, 列名) -> 该列的逻辑大小
+        let mut column_bytes = HashMap::new();
+        for (table, columns) in user_tables(conn)? {
+            let (rows, sizes) = table_sizes(conn, &table, &columns)?;
+            tables.push(TableSize {
+                name: table.clone(),
+                rows,
+                columns: columns.len() as u64,
+                estimated_bytes: sizes.iter().sum(),
+            });
+            for ((column, _), bytes) in columns.into_iter().zip(sizes) {
+                column_bytes.insert((table.clone(), column), bytes);
+            }
+        }
+
+        let mut indexes = Vec::new();
+        let mut stmt = conn.prepare(
+            "SELECT index_name, table_name, expressions, is_unique \
+             FROM duckdb_indexes() \
+             WHERE database_name = current_database() \
+             ORDER BY table_name, index_name",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, bool>(3)?,
+            ))
+        })?;
+        for row in rows {
+            let (name, table, expressions, unique) = row?;
+            let columns = parse_expressions(expressions.as_deref());
+            let rows =
+                tables.iter().find(|t| t.name == table).map_or(0, |t| t.rows);
+            let keys: u64 = columns
+                .iter()
+                .filter_map(|c| column_bytes.get(&(table.clone(), c.clone())))
+                .sum();
+            indexes.push(IndexSize {
+                name,
+                table,
+                columns,
+                unique,
+                estimated_bytes: keys + rows * 8,
+            });
+        }
+
+        let mut report = Self {
+            mode,
+            file_bytes,
+            tables,
+            indexes,
+            first_occurrence: None,
+            last_occurrence: None,
+            distinct: BTreeMap::new(),
+        };
+        if report.tables.iter().any(|t| t.name == "sqllogs") {
+            report.fill_sqllogs_stats(conn)?;
+        }
+        Ok(report)
+    }
+
+    fn fill_sqllogs_stats(&mut self, conn: &Connection) -> Result<()> {
+        let counts: Vec<String> = DISTINCT_COLUMNS
+            .iter()
+            .map(|c| format!("COUNT(DISTINCT {c})"))
+            .collect();
+        let sql = format!(
+            "SELECT MIN(occurrence_time), MAX(occurrence_time), {} FROM sqllogs",
+            counts.join(", ")
+        );
+        conn.query_row(&sql, [], |row| {
+            self.first_occurrence =
+                row.get::<_, Option<String>>(0)?.map(|t| t.trim().to_string());
+            self.last_occurrence =
+                row.get::<_, Option<String>>(1)?.map(|t| t.trim().to_string());
+            for (i, column) in DISTINCT_COLUMNS.iter().enumerate() {
+                let count: i64 = row.get(i + 2)?;
+                self.distinct
+                    .insert((*column).to_string(), count.unsigned_abs());
+            }
+            Ok(())
+        })
+        .context("统计 sqllogs 取值分布失败")
+    }
+}
+
+/// 库中的用户表及其列（列名、类型），按表名排序
+fn user_tables(conn: &Connection) -> Result<Vec<TableColumns>> {
+    let mut stmt = conn.prepare(
+        "SELECT table_name, column_name, data_type FROM duckdb_columns() \
+         WHERE database_name = current_database() AND NOT internal \
+         AND table_name IN (SELECT table_name FROM duckdb_tables() \
+             WHERE database_name = current_database() AND NOT internal \
+             AND NOT temporary) \
+         ORDER BY table_name, column_index",
+    )?;
+    let rows = stmt.query_map([], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+        ))
+    })?;
+    let mut tables: Vec<TableColumns> = Vec::new();
+    for row in rows {
+        let (table, column, ty) = row?;
+        match tables.last_mut() {
+            Some((name, columns)) if *name == table => {
+                columns.push((column, ty))
+            }
+            _ => tables.push((table, vec![(column, ty)])),
+        }
+    }
+    Ok(tables)
+}
+
+/// 表的行数与各列的逻辑大小
+fn table_sizes(
+    conn: &Connection,
+    table: &str,
+    columns: &[(String, String)],
+) -> Result<(u64, Vec<u64>)> {
+    let mut items = vec!["COUNT(*)".to_string()];
+    items.extend(columns.iter().map(|(column, ty)| {
+        let column = sql_identifier(column);
+        match fixed_width(ty) {
+            Some(width) => format!("COUNT({column}) * {width}"),
+            None => format!(
+                "CAST(COALESCE(SUM(strlen(CAST({column} AS VARCHAR))), 0) AS BIGINT)"
+            ),
+        }
+    }));
+    let sql =
+        format!("SELECT {} FROM {}", items.join(", "), sql_identifier(table));
+    conn.query_row(&sql, [], |row| {
+        let rows: i64 = row.get(0)?;
+        let sizes = (1..=columns.len())
+            .map(|i| row.get::<_, i64>(i).map(i64::unsigned_abs))
+            .collect::<duckdb::Result<Vec<_>>>()?;
+        Ok((rows.unsigned_abs(), sizes))
+    })
+    .with_context(|| format!("统计表 {table} 的大小失败"))
+}
+
+/// 定长类型的宽度（字节），变长类型返回 `None`
+fn fixed_width(ty: &str) -> Option<u64> {
+    let base = ty.split('(').next().unwrap_or(ty).trim();
+    Some(match base {
+        "BOOLEAN" | "TINYINT" | "UTINYINT" => 1,
+        "SMALLINT" | "USMALLINT" => 2,
+        "INTEGER" | "UINTEGER" | "FLOAT" | "DATE" => 4,
+        "BIGINT"
+        | "UBIGINT"
+        | "DOUBLE"
+        | "TIME"
+        | "TIMESTAMP"
+        | "TIMESTAMP WITH TIME ZONE"
+        | "DECIMAL" => 8,
+        "HUGEINT" | "UHUGEINT" | "UUID" | "INTERVAL" => 16,
+        _ => return None,
+    })
+}
+
+/// `duckdb_indexes()` 的 expressions 形如 `[session]` 或 `['lower(ip)']`
+fn parse_expressions(expressions: Option<&str>) -> Vec<String> {
+    let Some(text) = expressions else {
+        return Vec::new();
+    };
+    let inner = text.trim().trim_start_matches('[').trim_end_matches(']');
+    inner
+        .split(", ")
+        .map(|e| e.trim().trim_matches('\'').trim_matches('"').to_string())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
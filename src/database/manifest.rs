@@ -0,0 +1,237 @@
+// 导出清单 - 导出产物的完整性校验信息
+//
+// 清单以 JSON 形式列出一次导出写出的全部产物及其 SHA-256、字节数和记录数，
+// 并附带解析/导出统计，便于下游任务在加载前校验文件完整性。
+
+use super::{ExportFormat, ExportReport, IndependentDatabaseStats};
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// 清单中的单个产物条目
+#[derive(Debug, Clone, Serialize)]
+pub struct ManifestArtifact {
+    /// 产物文件路径
+    pub path: String,
+    /// 文件字节数
+    pub bytes: u64,
+    /// 文件内容的 SHA-256（小写十六进制）
+    pub sha256: String,
+    /// 产物中包含的记录数
+    pub records: u64,
+}
+
+/// 导出清单
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportManifest {
+    /// 清单生成时间（本地时间）
+    pub generated_at: String,
+    /// 导出格式（文件扩展名形式，如 `csv`）
+    pub format: String,
+    /// 导出的记录总数
+    pub records_exported: u64,
+    /// 解析阶段统计（未提供时为 null）
+    pub parse_stats: Option<IndependentDatabaseStats>,
+    /// 所有产物
+    pub artifacts: Vec<ManifestArtifact>,
+}
+
+impl ExportManifest {
+    /// 根据导出结果构造清单，逐个读取产物计算 SHA-256。
+    ///
+    /// # Errors
+    /// 当任一产物文件无法读取时返回错误
+    pub fn build(
+        format: &ExportFormat,
+        report: &ExportReport,
+        parse_stats: Option<&IndependentDatabaseStats>,
+    ) -> Result<Self> {
+        let artifacts = report
+            .artifacts
+            .iter()
+            .map(|artifact| {
+                let path = Path::new(&artifact.path);
+                let bytes = fs::metadata(path)
+                    .with_context(|| {
+                        format!("无法读取导出产物: {}", artifact.path)
+                    })?
+                    .len();
+                Ok(ManifestArtifact {
+                    path: artifact.path.clone(),
+                    bytes,
+                    sha256: file_sha256(path)?,
+                    records: artifact.records,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            generated_at: chrono::Local::now()
+                .format("%Y-%m-%d %H:%M:%S%.3f")
+                .to_string(),
+            format: format.extension().to_string(),
+            records_exported: report.records_exported,
+            parse_stats: parse_stats.cloned(),
+            artifacts,
+        })
+    }
+
+    /// 将清单以格式化 JSON 写入指定路径
+    ///
+    /// # Errors
+    /// 当序列化或写入文件失败时返回错误
+    pub fn write_to(&self, path: &Path) -> Result<()> {
+        let json =
+            serde_json::to_string_pretty(self).context("序列化导出清单失败")?;
+        fs::write(path, json)
+            .with_context(|| format!("无法写入导出清单: {}", path.display()))
+    }
+}
+
+/// 计算文件内容的 SHA-256，返回小写十六进制字符串
+///
+/// # Errors
+/// 当文件无法打开或读取时返回错误
+pub fn file_sha256(path: &Path) -> Result<String> {
+    let file = File::open(path)
+        .with_context(|| format!("无法打开文件: {}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader
+            .read(&mut buf)
+            .with_context(|| format!("读取文件失败: {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hasher.finish_hex())
+}
+
+/// SHA-256 轮常量
+#[rustfmt::skip]
+const K: [u32; 64] = [
+    0x428a_2f98, 0x7137_4491, 0xb5c0_fbcf, 0xe9b5_dba5, 0x3956_c25b,
+    0x59f1_11f1, 0x923f_82a4, 0xab1c_5ed5, 0xd807_aa98, 0x1283_5b01,
+    0x2431_85be, 0x550c_7dc3, 0x72be_5d74, 0x80de_b1fe, 0x9bdc_06a7,
+    0xc19b_f174, 0xe49b_69c1, 0xefbe_4786, 0x0fc1_9dc6, 0x240c_a1cc,
+    0x2de9_2c6f, 0x4a74_84aa, 0x5cb0_a9dc, 0x76f9_88da, 0x983e_5152,
+    0xa831_c66d, 0xb003_27c8, 0xbf59_7fc7, 0xc6e0_0bf3, 0xd5a7_9147,
+    0x06ca_6351, 0x1429_2967, 0x27b7_0a85, 0x2e1b_2138, 0x4d2c_6dfc,
+    0x5338_0d13, 0x650a_7354, 0x766a_0abb, 0x81c2_c92e, 0x9272_2c85,
+    0xa2bf_e8a1, 0xa81a_664b, 0xc24b_8b70, 0xc76c_51a3, 0xd192_e819,
+    0xd699_0624, 0xf40e_3585, 0x106a_a070, 0x19a4_c116, 0x1e37_6c08,
+    0x2748_774c, 0x34b0_bcb5, 0x391c_0cb3, 0x4ed8_aa4a, 0x5b9c_ca4f,
+    0x682e_6ff3, 0x748f_82ee, 0x78a5_636f, 0x84c8_7814, 0x8cc7_0208,
+    0x90be_fffa, 0xa450_6ceb, 0xbef9_a3f7, 0xc671_78f2,
+];
+
+/// 流式 SHA-256（FIPS 180-4），只用于 [`file_sha256`] 计算校验和
+///
+/// 离线构建环境中没有 `sha2` 等现成实现，故在此自行实现；已知向量测试见
+/// `tests/export_tests.rs`。缓存键等内部摘要使用 SeaHash，不依赖这里。
+struct Sha256 {
+    state: [u32; 8],
+    block: [u8; 64],
+    block_len: usize,
+    total_len: u64,
+}
+
+impl Sha256 {
+    const fn new() -> Self {
+        Self {
+            state: [
+                0x6a09_e667,
+                0xbb67_ae85,
+                0x3c6e_f372,
+                0xa54f_f53a,
+                0x510e_527f,
+                0x9b05_688c,
+                0x1f83_d9ab,
+                0x5be0_cd19,
+            ],
+            block: [0u8; 64],
+            block_len: 0,
+            total_len: 0,
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        self.total_len = self.total_len.wrapping_add(data.len() as u64);
+        while !data.is_empty() {
+            let take = (64 - self.block_len).min(data.len());
+            self.block[self.block_len..self.block_len + take]
+                .copy_from_slice(&data[..take]);
+            self.block_len += take;
+            data = &data[take..];
+            if self.block_len == 64 {
+                let block = self.block;
+                self.compress(&block);
+                self.block_len = 0;
+            }
+        }
+    }
+
+    fn finish_hex(mut self) -> String {
+        let bit_len = self.total_len.wrapping_mul(8);
+        self.update(&[0x80]);
+        while self.block_len != 56 {
+            self.update(&[0]);
+        }
+        self.update(&bit_len.to_be_bytes());
+        self.state.iter().map(|word| format!("{word:08x}")).collect()
+    }
+
+    fn compress(&mut self, block: &[u8; 64]) {
+        let mut w = [0u32; 64];
+        for (i, chunk) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7)
+                ^ w[i - 15].rotate_right(18)
+                ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17)
+                ^ w[i - 2].rotate_right(19)
+                ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] =
+            self.state;
+        for i in 0..64 {
+            let s1 =
+                e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 =
+                a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+
+        for (slot, value) in self.state.iter_mut().zip([a, b, c, d, e, f, g, h])
+        {
+            *slot = slot.wrapping_add(value);
+        }
+    }
+}
@@ -6,18 +6,96 @@
 // - 批量数据插入功能
 // - 多格式数据导出功能
 // - 独立数据库并发处理
-
+// - analyze 聚合在内存不足时溢写到临时数据库
+// - 解析前的连接与输出路径预检
+// - 磁盘空间不足的识别与报告
+// - 按自定义行模板导出文本
+// - Avro 对象容器文件导出（`exporter-avro` 特性）
+// - 以 Arrow RecordBatch 提供数据（`arrow` 特性）
+// - 多条流水线共享同一导出器实例
+// - sqllogs 表结构的统一定义（建表、导出 schema 共用）
+// - 按导出格式分别配置的字段脱敏
+// - 复用未变化文件解析结果的解析缓存
+// - 合并中断后按已提交批次续写
+// - 以类型化方法查询导出库的只读接口
+// - description/plan 移入按记录 ID 关联的冷字段表
+// - 多条流水线经由单一写入线程共享同一个 DuckDB 库
+// - 在导出库中记录每次运行的元数据
+// - 从已保存的 JSON/Parquet 记录重新导出
+// - 以 JSON 为接口的记录转换插件
+// - 导出库各表与索引的体积报告
+
+mod aliases;
+mod analyze;
+#[cfg(feature = "arrow")]
+mod arrow_batch;
+mod autotune;
+#[cfg(feature = "exporter-avro")]
+mod avro;
+mod convert;
+mod disk_full;
 mod duckdb_impl;
+mod duckdb_sink;
+mod format_options;
+mod index_report;
+mod manifest;
+mod parse_cache;
+mod plugin;
+mod preflight;
+mod redact;
+mod resume;
+mod run_meta;
+mod schema;
+mod shared;
+mod store;
+mod template;
+mod throttle;
 mod types;
 
 use crate::{config, sqllog::Sqllog};
 use anyhow::Result;
 
+pub use aliases::{ALIASED_VIEW, ColumnAliases};
+pub use analyze::{AnalyzeOutcome, AnalyzeRunner};
+#[cfg(feature = "arrow")]
+pub use arrow_batch::{records_to_batch, sqllog_schema};
+pub use autotune::{AutoTune, BatchTuner};
+#[cfg(feature = "exporter-avro")]
+pub use avro::{AVRO_NAMESPACE, AVRO_RECORD_NAME, AvroExporter, avro_schema};
+pub use convert::{
+    DEFAULT_LOAD_BATCH, LoadOptions, SavedFormat, load_saved_records,
+    read_saved_records,
+};
+pub use disk_full::{DiskFullError, is_disk_full};
+#[cfg(feature = "arrow")]
+pub use duckdb::arrow;
 pub use duckdb_impl::{
-    DuckDbProvider, IndependentDatabaseStats,
+    COLD_TABLE, DuckDbProvider, FULL_VIEW, FileThroughput,
+    HIVE_DEFAULT_PARTITION, IndependentDatabaseStats,
     process_file_with_independent_database,
     process_files_with_independent_databases,
 };
+pub use duckdb_sink::{DEFAULT_SINK_QUEUE, SharedDuckDbSink};
+pub use format_options::{
+    AvroCompatibility, AvroExportOptions, AvroTimestamps, Compression,
+    CsvExportOptions, FormatOptions, JsonExportOptions, JsonLayout, Newlines,
+    NullAs,
+};
+pub use index_report::{IndexReport, IndexSize, TableSize};
+pub use manifest::{ExportManifest, ManifestArtifact, file_sha256};
+pub use parse_cache::ParseCache;
+pub use plugin::{ProcessPlugin, RecordPlugin, plugin_stage};
+pub use preflight::preflight;
+pub use redact::{RedactMode, Redactions};
+pub use resume::run_key;
+pub use run_meta::{RUNS_TABLE, RunInput, RunRecord, config_hash};
+pub use schema::{Column, ColumnType, SQLLOG_TABLE, TableSchema};
+pub use shared::{SharedExporter, SyncExporter};
+pub use store::SqllogStore;
+pub use template::{
+    Align, LineTemplate, Placeholder, TemplateError, TemplateExporter,
+};
+pub use throttle::{RateLimit, RateLimiter};
 pub use types::*;
 
 /// 数据库提供者抽象接口
@@ -71,6 +149,17 @@ pub trait DatabaseProvider: Send {
         // 默认实现：什么都不做
         Ok(())
     }
+
+    /// 解析开始前检查连接、写权限与已有表结构
+    ///
+    /// 用于在耗时的解析之前尽早发现错误的连接或不兼容的目标表。
+    ///
+    /// # Errors
+    /// 当连接不可用、无写权限或已有表结构不兼容时返回错误
+    fn preflight(&self) -> Result<()> {
+        // 默认实现：不做检查
+        Ok(())
+    }
 }
 
 /// 简化的数据库管理器
@@ -0,0 +1,108 @@
+// 解析缓存 - 复用内容未变化的文件的解析结果
+//
+// 设置 `sqllog.cache_dir` 后，每个文件先解析到缓存目录中的独立 DuckDB 文件，
+// 再合并进目标库；文件没有解析错误时该文件保留为缓存条目。之后的运行中，
+// 缓存键相同的文件直接从缓存条目合并，不再解析。
+//
+// 缓存键为 `<文件内容摘要>-<选项摘要>`，各为 16 位十六进制的 SeaHash。选项
+// 摘要覆盖文件名、影响记录内容的解析选项（记录 ID、执行计划、空白字段、抽样、
+// trace 行、解析模式、执行阶段关联与预过滤）以及 sqllogs 表结构，任一项变化
+// 都会换用新的条目。SeaHash 不是密码学摘要，缓存目录应只由本程序写入。
+// 有解析错误（含超时）的文件不缓存，下次运行重新解析并再次上报错误。
+//
+// 旧条目不会自动清理，缓存目录可以随时整体删除。
+
+use super::schema::SQLLOG_TABLE;
+use crate::sqllog::{ParseOptions, Prefilter};
+use anyhow::{Context, Result};
+use seahash::SeaHasher;
+use std::fs::File;
+use std::hash::Hasher;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// 缓存条目的扩展名
+const ENTRY_EXTENSION: &str = "duckdb";
+
+/// 解析缓存目录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseCache {
+    dir: PathBuf,
+}
+
+impl ParseCache {
+    /// 以 `dir` 为缓存目录（首次写入条目时创建）
+    #[must_use]
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// 缓存目录
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// 计算文件在给定解析选项下的缓存键
+    ///
+    /// # Errors
+    /// 当文件无法打开或读取时返回错误
+    pub fn key(&self, path: &Path, options: &ParseOptions) -> Result<String> {
+        let content = file_digest(path)?;
+        let mut hasher = SeaHasher::new();
+        let file_name =
+            path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        hasher.write(format!("{file_name}\n").as_bytes());
+        hasher.write(options_material(options).as_bytes());
+        Ok(format!("{content:016x}-{:016x}", hasher.finish()))
+    }
+
+    /// 缓存键对应的条目路径
+    #[must_use]
+    pub fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{ENTRY_EXTENSION}"))
+    }
+
+    /// 写入条目期间使用的临时路径，解析成功后改名为条目路径
+    #[must_use]
+    pub fn partial_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.{ENTRY_EXTENSION}.partial"))
+    }
+}
+
+/// 文件内容的 SeaHash
+fn file_digest(path: &Path) -> Result<u64> {
+    let mut file = File::open(path)
+        .with_context(|| format!("无法打开文件: {}", path.display()))?;
+    let mut hasher = SeaHasher::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = file
+            .read(&mut buf)
+            .with_context(|| format!("读取文件失败: {}", path.display()))?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/// 影响记录内容的解析选项与 sqllogs 表结构，用于缓存键与断点续写的运行键
+pub(super) fn options_material(options: &ParseOptions) -> String {
+    let mut material = format!(
+        "{:?}\n{}\n{:?}\n{:?}\n{:?}\n{:?}\n{}\n{:?}",
+        options.record_id,
+        options.extract_plans,
+        options.blank_fields,
+        options.sample,
+        options.trace_lines,
+        options.mode,
+        options.link_exec_phase,
+        options.prefilter.as_ref().map(Prefilter::as_str),
+    );
+    for column in SQLLOG_TABLE.columns {
+        material.push_str(&format!("\n{}:{:?}", column.name, column.ty));
+    }
+    material
+}
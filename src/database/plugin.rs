@@ -0,0 +1,225 @@
+// 记录转换插件 - 以 JSON 为接口逐条改写或丢弃记录
+//
+// 无法重新编译本 crate 的使用者可以把记录转换逻辑做成独立的可执行程序
+// （任意语言），由 [`ProcessPlugin`] 启动为子进程，在管道中作为一个阶段运行
+// （见 [`plugin_stage`]），或通过配置 `[plugin]` 在解析后、写库前逐条调用。
+//
+// 插件接口（按行的 JSON，经子进程的标准输入/输出交换）：
+//
+// - 输入：每条记录一行 JSON 对象，字段名与导出的 JSON 相同（`plan` 为对象）
+// - 输出：每读入一行必须输出一行：改写后的 JSON 对象（可省略可空字段），
+//   或 `null` 表示丢弃该记录；输出后应立即刷新标准输出
+// - 标准错误不被读取，直接继承自本进程；标准输入关闭后插件应退出
+//
+// 插件返回的记录与 `convert` 载入的记录一样按 sqllogs 表结构校验，字段缺失、
+// 类型不符或存在未知字段时阶段失败，管道随之停止。记录在源文件中的位置（行号、
+// 字节偏移）由宿主保留，不经过插件。
+//
+// 进程内的插件可以直接实现 [`RecordPlugin`]。WASM 模块需要 wasmtime 运行时，
+// 当前未提供。
+
+use super::convert::{parse_saved_record, record_to_json};
+use crate::sqllog::Sqllog;
+use anyhow::{Context, Result, bail};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender, channel};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 记录转换插件
+pub trait RecordPlugin: Send {
+    /// 插件名称，用于错误信息
+    fn name(&self) -> &str;
+
+    /// 转换一条记录：输入 JSON 对象文本，返回改写后的 JSON 文本，
+    /// `None` 表示丢弃该记录
+    ///
+    /// 给出 `deadline` 时应在此之前返回，超时则返回错误；无法中断的实现
+    /// 可以忽略，由阶段在每条记录处理完后检查。
+    ///
+    /// # Errors
+    /// 插件执行失败或超时时返回错误
+    fn call(
+        &mut self,
+        record: &str,
+        deadline: Option<Instant>,
+    ) -> Result<Option<String>>;
+}
+
+/// 以子进程运行的插件，按行交换 JSON
+#[derive(Debug)]
+pub struct ProcessPlugin {
+    name: String,
+    child: Child,
+    /// 交给写入线程的记录行；置为 `None` 即关闭插件的标准输入
+    input: Option<Sender<String>>,
+    /// 读取线程转发的输出行，进程退出或读取失败时断开
+    lines: Receiver<std::io::Result<String>>,
+}
+
+impl ProcessPlugin {
+    /// 启动插件进程，`command` 为程序及其参数
+    ///
+    /// # Errors
+    /// 命令为空或进程无法启动时返回错误
+    pub fn spawn(command: &[String]) -> Result<Self> {
+        let Some((program, args)) = command.split_first() else {
+            bail!("插件命令不能为空");
+        };
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .with_context(|| format!("启动插件 {program} 失败"))?;
+        let mut stdin = child.stdin.take().context("插件标准输入不可用")?;
+        let stdout = child.stdout.take().context("插件标准输出不可用")?;
+
+        // 读写都放在独立线程，插件不读输入或不输出时调用方都可以按截止时间
+        // 等待并在超时后结束进程
+        let (input, records) = channel::<String>();
+        thread::spawn(move || {
+            for record in records {
+                let written = stdin
+                    .write_all(record.as_bytes())
+                    .and_then(|()| stdin.write_all(b"\n"))
+                    .and_then(|()| stdin.flush());
+                if written.is_err() {
+                    break;
+                }
+            }
+        });
+        let (tx, lines) = channel();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self { name: program.clone(), child, input: Some(input), lines })
+    }
+
+    /// 结束插件进程，之后的调用均失败
+    fn kill(&mut self) {
+        self.input = None;
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+impl RecordPlugin for ProcessPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn call(
+        &mut self,
+        record: &str,
+        deadline: Option<Instant>,
+    ) -> Result<Option<String>> {
+        let Some(input) = &self.input else {
+            bail!("插件进程已结束");
+        };
+        if input.send(record.to_string()).is_err() {
+            self.kill();
+            bail!("插件进程已退出");
+        }
+
+        let line = match deadline {
+            Some(at) => {
+                let wait = at.saturating_duration_since(Instant::now());
+                match self.lines.recv_timeout(wait) {
+                    Ok(line) => line,
+                    Err(RecvTimeoutError::Timeout) => {
+                        self.kill();
+                        bail!("插件未在时限内返回，已结束插件进程");
+                    }
+                    Err(RecvTimeoutError::Disconnected) => {
+                        self.kill();
+                        bail!("插件进程已退出");
+                    }
+                }
+            }
+            None => match self.lines.recv() {
+                Ok(line) => line,
+                Err(_) => {
+                    self.kill();
+                    bail!("插件进程已退出");
+                }
+            },
+        }
+        .context("读取插件输出失败")?;
+
+        let line = line.trim();
+        Ok((line != "null").then(|| line.to_string()))
+    }
+}
+
+impl Drop for ProcessPlugin {
+    fn drop(&mut self) {
+        // 关闭标准输入通知插件退出，短暂等待后仍未退出则强制结束
+        self.input = None;
+        let waited = Instant::now();
+        while waited.elapsed() < Duration::from_millis(500) {
+            if !matches!(self.child.try_wait(), Ok(None)) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        self.kill();
+    }
+}
+
+/// 以插件逐条转换记录的管道阶段
+///
+/// 设置了 `timeout` 时，单个批次的累计处理时长超过该值则阶段失败；截止时间
+/// 会传给插件，[`ProcessPlugin`] 到期即结束插件进程，不会被卡住的插件阻塞。
+pub fn plugin_stage<P>(
+    mut plugin: P,
+    timeout: Option<Duration>,
+) -> impl FnMut(&mut Vec<Sqllog>) -> Result<()> + Send
+where
+    P: RecordPlugin,
+{
+    move |batch| {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut kept = Vec::with_capacity(batch.len());
+        for record in batch.drain(..) {
+            let input = record_to_json(&record).to_string();
+            let output = plugin.call(&input, deadline).with_context(|| {
+                format!(
+                    "插件 {} 处理第 {} 行的记录失败",
+                    plugin.name(),
+                    record.line
+                )
+            })?;
+            if let Some(output) = output {
+                let value =
+                    serde_json::from_str(&output).with_context(|| {
+                        format!("插件 {} 返回的不是合法 JSON", plugin.name())
+                    })?;
+                let mut transformed = parse_saved_record(value, false)
+                    .with_context(|| {
+                        format!("插件 {} 返回的记录无效", plugin.name())
+                    })?;
+                transformed.line = record.line;
+                transformed.byte_offset = record.byte_offset;
+                kept.push(transformed);
+            }
+            if let (Some(at), Some(timeout)) = (deadline, timeout) {
+                if Instant::now() > at {
+                    bail!(
+                        "插件 {} 处理批次超时（超过 {} ms）",
+                        plugin.name(),
+                        timeout.as_millis()
+                    );
+                }
+            }
+        }
+        *batch = kept;
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+// 解析前预检 - 在耗时的解析开始前验证各输出目标
+//
+// 依次检查：
+// - 数据库：连接可用、可写、已有 sqllogs 表与当前列结构兼容
+// - 解析错误输出：启用时文件可创建且可写
+// - 导出：输出目录可创建且可写
+//
+// 任何一项失败都立即返回，避免解析数小时后才在写出阶段报错。
+
+use super::{DatabaseProvider, DuckDbProvider};
+use crate::config::RuntimeConfig;
+use crate::error_writer::{ErrorExporter, ErrorWriter};
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// 按运行时配置预检数据库、错误输出与导出目标
+///
+/// # Errors
+/// 当任一输出目标不可用时返回错误，错误信息指明具体目标
+pub fn preflight(runtime: &RuntimeConfig) -> Result<()> {
+    let provider = DuckDbProvider::new(runtime)?;
+    provider.preflight().context("数据库预检失败")?;
+
+    if runtime.sqllog_write_errors {
+        if let Some(path) = &runtime.sqllog_errors_out_path {
+            ErrorWriter::with_format(path, runtime.sqllog_errors_format)
+                .and_then(|writer| writer.preflight())
+                .with_context(|| {
+                    format!("错误输出文件不可写: {}", path.display())
+                })?;
+        }
+    }
+
+    if runtime.export_enabled {
+        if let Some(out_path) = &runtime.export_out_path {
+            check_output_dir(out_path)?;
+        }
+    }
+    Ok(())
+}
+
+/// 确认导出文件所在目录可创建且可写入
+fn check_output_dir(out_path: &Path) -> Result<()> {
+    let dir = match out_path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("无法创建导出目录: {}", dir.display()))?;
+
+    let probe = dir.join(format!(".sqllog_preflight_{}", std::process::id()));
+    std::fs::write(&probe, b"")
+        .with_context(|| format!("导出目录不可写: {}", dir.display()))?;
+    let _ = std::fs::remove_file(&probe);
+    Ok(())
+}
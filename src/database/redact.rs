@@ -0,0 +1,169 @@
+// 字段脱敏 - 按导出格式分别脱敏或变换导出列
+//
+// 一次解析可以同时写出多种格式，各格式的脱敏规则相互独立：例如数据库保留
+// 完整数据，而对外共享的 CSV 把 description 中的字面量替换为 `?`、把用户名
+// 替换为哈希。规则在导出的 SELECT 中以 SQL 表达式实现，`sqllogs` 表本身与
+// 未配置规则的格式都不受影响。
+//
+// 配置以「格式 -> 列名 -> 方式」给出，列名使用表中的列名，`user` 作为
+// `username` 的同义词也被接受：
+//
+// ```toml
+// [export.redact.csv]
+// description = "literals"
+// user = "hash"
+// ip = "null"
+// ```
+
+use super::ExportFormat;
+use super::duckdb_impl::sql_string_literal;
+use super::schema::SQLLOG_TABLE;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// 单个列的脱敏方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedactMode {
+    /// 输出 NULL（只适用于可以缺失的列）
+    Null,
+    /// 输出取值的 MD5 十六进制摘要（文本列）；相同取值得到相同摘要，
+    /// 仍可用于分组与关联
+    Hash,
+    /// 把 SQL 文本中的字符串与数值字面量替换为 `?`（文本列）
+    Literals,
+}
+
+impl FromStr for RedactMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "null" => Ok(Self::Null),
+            "hash" => Ok(Self::Hash),
+            "literals" => Ok(Self::Literals),
+            _ => Err(format!(
+                "不支持的脱敏方式: {s}；可选值为 null/hash/literals"
+            )),
+        }
+    }
+}
+
+impl RedactMode {
+    /// 对列 `column` 取值的 SQL 表达式
+    #[must_use]
+    pub fn sql(self, column: &str) -> String {
+        match self {
+            Self::Null => "NULL".to_string(),
+            Self::Hash => format!("md5({column})"),
+            // 先替换带 '' 转义的字符串，再替换独立的数值（t1 中的 1 不受影响）
+            Self::Literals => format!(
+                "regexp_replace(regexp_replace({column}, {}, '?', 'g'), {}, '?', 'g')",
+                sql_string_literal("'(?:[^']|'')*'"),
+                sql_string_literal(r"\b[0-9]+(?:\.[0-9]+)?\b"),
+            ),
+        }
+    }
+}
+
+/// 各导出格式的脱敏规则
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Redactions {
+    /// 格式 -> 列名 -> 脱敏方式
+    rules: Vec<(ExportFormat, BTreeMap<String, RedactMode>)>,
+}
+
+impl Redactions {
+    /// 从「格式 -> 列名 -> 方式」映射构造并校验
+    ///
+    /// # Errors
+    /// 当格式或列不存在、方式不合法，或方式不适用于该列时返回错误描述
+    pub fn from_map(
+        map: &BTreeMap<String, BTreeMap<String, String>>,
+    ) -> Result<Self, String> {
+        let mut redactions = Self::default();
+        for (format, columns) in map {
+            let format = format.parse::<ExportFormat>()?;
+            for (column, mode) in columns {
+                let mode = mode
+                    .parse::<RedactMode>()
+                    .map_err(|e| format!("列 {column}: {e}"))?;
+                redactions = redactions.redact(format.clone(), column, mode)?;
+            }
+        }
+        Ok(redactions)
+    }
+
+    /// 追加一条规则，同一格式的同一列以最后一次为准
+    ///
+    /// # Errors
+    /// 当列不存在或方式不适用于该列时返回错误描述：`occurrence_time` 不能
+    /// 脱敏，`null` 只适用于可以缺失的列，`hash`/`literals` 只适用于文本列
+    pub fn redact(
+        mut self,
+        format: ExportFormat,
+        column: &str,
+        mode: RedactMode,
+    ) -> Result<Self, String> {
+        let name = if column == "user" { "username" } else { column };
+        let Some(col) = SQLLOG_TABLE.column(name) else {
+            return Err(format!("未知的列: {column}"));
+        };
+        if col.name == "occurrence_time" {
+            return Err("occurrence_time 是记录的时间键，不能脱敏".to_string());
+        }
+        match mode {
+            RedactMode::Null if col.always_present => {
+                return Err(format!("列 {column} 总是有值，不能脱敏为 null"));
+            }
+            RedactMode::Hash | RedactMode::Literals if !col.ty.is_text() => {
+                return Err(format!("列 {column} 不是文本列，只能脱敏为 null"));
+            }
+            _ => {}
+        }
+
+        let index = match self.rules.iter().position(|(f, _)| *f == format) {
+            Some(index) => index,
+            None => {
+                self.rules.push((format, BTreeMap::new()));
+                self.rules.len() - 1
+            }
+        };
+        self.rules[index].1.insert(col.name.to_string(), mode);
+        Ok(self)
+    }
+
+    /// 格式是否未配置任何规则
+    #[must_use]
+    pub fn is_empty_for(&self, format: &ExportFormat) -> bool {
+        self.rules_for(format).map_or(true, BTreeMap::is_empty)
+    }
+
+    /// 列在该格式下的脱敏方式
+    #[must_use]
+    pub fn mode(
+        &self,
+        format: &ExportFormat,
+        column: &str,
+    ) -> Option<RedactMode> {
+        self.rules_for(format)?.get(column).copied()
+    }
+
+    /// 列在该格式下的取值表达式；未配置规则时为列名本身
+    #[must_use]
+    pub fn column_sql<'a>(
+        &self,
+        format: &ExportFormat,
+        column: &'a str,
+    ) -> Cow<'a, str> {
+        self.mode(format, column)
+            .map_or(Cow::Borrowed(column), |mode| Cow::Owned(mode.sql(column)))
+    }
+
+    fn rules_for(
+        &self,
+        format: &ExportFormat,
+    ) -> Option<&BTreeMap<String, RedactMode>> {
+        self.rules.iter().find(|(f, _)| f == format).map(|(_, rules)| rules)
+    }
+}
@@ -0,0 +1,61 @@
+// 断点续写 - 合并中断后重跑时跳过已提交的批次
+//
+// 多文件处理时每个文件解析到独立的临时库，再逐个合并进主库。开启
+// `database.resume` 后，每个文件（按输入顺序编号的批次）的合并与一条
+// `sqllog_resume` 记录在同一事务中提交；中断后以相同的输入文件和解析配置
+// 重跑时，编号不大于已提交最大批次的文件直接跳过，不会重复写入。
+//
+// 运行键覆盖输入文件的路径、大小与修改时间，以及影响记录内容的解析选项和
+// sqllogs 表结构（以 SeaHash 摘要，16 位十六进制）；任一项变化都视为新的
+// 运行，从第一个批次开始写入。
+
+use super::parse_cache::options_material;
+use crate::sqllog::ParseOptions;
+use anyhow::{Context, Result};
+use seahash::SeaHasher;
+use std::hash::Hasher;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// 已提交批次表
+pub(super) const RESUME_TABLE_SQL: &str = r"
+    CREATE TABLE IF NOT EXISTS sqllog_resume (
+        run_key VARCHAR NOT NULL,
+        batch_id BIGINT NOT NULL,
+        path VARCHAR NOT NULL,
+        records BIGINT NOT NULL,
+        committed_at TIMESTAMP DEFAULT current_timestamp
+    );
+";
+
+/// 计算一组输入文件在给定解析选项下的运行键
+///
+/// # Errors
+/// 当任一文件的元数据无法读取时返回错误
+pub fn run_key<P: AsRef<Path>>(
+    paths: &[P],
+    options: &ParseOptions,
+) -> Result<String> {
+    let mut hasher = SeaHasher::new();
+    for path in paths {
+        let path = path.as_ref();
+        let metadata = std::fs::metadata(path)
+            .with_context(|| format!("无法读取文件信息: {}", path.display()))?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .unwrap_or_default();
+        hasher.write(
+            format!(
+                "{}\n{}\n{}\n",
+                path.display(),
+                metadata.len(),
+                modified.as_nanos()
+            )
+            .as_bytes(),
+        );
+    }
+    hasher.write(options_material(options).as_bytes());
+    Ok(format!("{:016x}", hasher.finish()))
+}
@@ -0,0 +1,123 @@
+// 运行元数据 - 在导出库中记录每次运行，使数据库可以自我说明
+//
+// 开启 `database.record_runs` 后，每次运行结束时向 `runs` 表追加一行：运行 ID、
+// 开始与结束时间、程序版本、配置摘要、输入文件（路径、字节数与 SHA-256 组成
+// 的 JSON 数组）以及文件数、记录数与解析错误数。事后拿到一个数据库文件时，
+// 可以据此确认数据来自哪些输入、由哪个版本以什么配置写入。
+//
+// 配置摘要为运行时配置的 SeaHash，配置相同的两次运行摘要相同。
+
+use super::IndependentDatabaseStats;
+use super::manifest::file_sha256;
+use crate::config::RuntimeConfig;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::path::Path;
+
+/// 运行元数据表名
+pub const RUNS_TABLE: &str = "runs";
+
+/// 运行元数据表
+pub(super) const RUNS_TABLE_SQL: &str = r"
+    CREATE TABLE IF NOT EXISTS runs (
+        run_id VARCHAR PRIMARY KEY,
+        started_at TIMESTAMP NOT NULL,
+        finished_at TIMESTAMP NOT NULL,
+        crate_version VARCHAR NOT NULL,
+        config_hash VARCHAR NOT NULL,
+        inputs VARCHAR NOT NULL,
+        files_processed BIGINT NOT NULL,
+        records_inserted BIGINT NOT NULL,
+        parse_errors BIGINT NOT NULL
+    );
+";
+
+/// 时间列的写入格式
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.3f";
+
+/// 一个输入文件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RunInput {
+    /// 文件路径
+    pub path: String,
+    /// 文件字节数
+    pub bytes: u64,
+    /// 文件内容的 SHA-256（小写十六进制）
+    pub sha256: String,
+}
+
+/// `runs` 表中的一行
+#[derive(Debug, Clone, Serialize)]
+pub struct RunRecord {
+    /// 运行 ID（开始时间与进程号）
+    pub run_id: String,
+    /// 开始时间（本地时间）
+    pub started_at: String,
+    /// 结束时间（本地时间）
+    pub finished_at: String,
+    /// 程序版本
+    pub crate_version: String,
+    /// 运行时配置的摘要（见 [`config_hash`]）
+    pub config_hash: String,
+    /// 输入文件
+    pub inputs: Vec<RunInput>,
+    /// 处理的文件数
+    pub files_processed: u64,
+    /// 写入的记录数
+    pub records_inserted: u64,
+    /// 解析错误数
+    pub parse_errors: u64,
+}
+
+impl RunRecord {
+    /// 根据本次运行的输入与统计构造记录，逐个读取输入文件计算 SHA-256，
+    /// 结束时间取调用时刻
+    ///
+    /// # Errors
+    /// 当任一输入文件无法读取时返回错误
+    pub fn build<P: AsRef<Path>>(
+        runtime: &RuntimeConfig,
+        files: &[P],
+        stats: &IndependentDatabaseStats,
+        started_at: DateTime<Local>,
+    ) -> Result<Self> {
+        let inputs = files
+            .iter()
+            .map(|path| {
+                let path = path.as_ref();
+                let bytes = std::fs::metadata(path)
+                    .with_context(|| {
+                        format!("无法读取文件信息: {}", path.display())
+                    })?
+                    .len();
+                Ok(RunInput {
+                    path: path.display().to_string(),
+                    bytes,
+                    sha256: file_sha256(path)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            run_id: format!(
+                "{}-{}",
+                started_at.format("%Y%m%d%H%M%S%3f"),
+                std::process::id()
+            ),
+            started_at: started_at.format(TIME_FORMAT).to_string(),
+            finished_at: Local::now().format(TIME_FORMAT).to_string(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            config_hash: config_hash(runtime),
+            inputs,
+            files_processed: stats.files_processed as u64,
+            records_inserted: stats.records_inserted as u64,
+            parse_errors: stats.parse_errors as u64,
+        })
+    }
+}
+
+/// 运行时配置的 SeaHash（16 位小写十六进制），用于区分不同配置的运行
+#[must_use]
+pub fn config_hash(runtime: &RuntimeConfig) -> String {
+    format!("{:016x}", seahash::hash(format!("{runtime:?}").as_bytes()))
+}
@@ -0,0 +1,211 @@
+// 表结构定义 - sqllogs 表各列的名称与类型
+//
+// [`SQLLOG_TABLE`] 是 sqllogs 表列的唯一定义：建表与迁移语句、插入与查询的
+// 列顺序、Arrow schema 与 Avro schema 都由它生成。给 `Sqllog` 增加字段时，
+// 在此追加一列（旧版本创建的表需要补齐的列标记 `migrated`），再补上各
+// 导出器中取值的代码即可。
+//
+// 列类型按 [`DatabaseType`] 转换为对应数据库的类型名，新增数据库时在
+// [`ColumnType::sql_type`] 中补充映射。
+
+use super::DatabaseType;
+
+/// 列的逻辑类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    /// 定长文本
+    Char(u16),
+    /// 限长文本
+    Varchar(u16),
+    /// 不限长文本
+    Text,
+    /// 32 位整数
+    Integer,
+    /// 64 位整数
+    BigInt,
+    /// 64 位无符号整数
+    UBigInt,
+    Boolean,
+}
+
+impl ColumnType {
+    /// 在指定数据库中的类型名
+    #[must_use]
+    pub fn sql_type(self, database: &DatabaseType) -> String {
+        match database {
+            DatabaseType::DuckDb => match self {
+                Self::Char(len) => format!("CHAR({len})"),
+                Self::Varchar(len) => format!("VARCHAR({len})"),
+                Self::Text => "TEXT".to_string(),
+                Self::Integer => "INTEGER".to_string(),
+                Self::BigInt => "BIGINT".to_string(),
+                Self::UBigInt => "UBIGINT".to_string(),
+                Self::Boolean => "BOOLEAN".to_string(),
+            },
+        }
+    }
+
+    /// 是否为文本类型
+    #[must_use]
+    pub const fn is_text(self) -> bool {
+        matches!(self, Self::Char(_) | Self::Varchar(_) | Self::Text)
+    }
+}
+
+/// 表中的一列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    pub name: &'static str,
+    pub ty: ColumnType,
+    /// 建表时带 `NOT NULL` 约束
+    pub not_null: bool,
+    /// 建表时带 `UNIQUE` 约束（允许多个 NULL）
+    pub unique: bool,
+    /// 记录中总是有值（对应 `Sqllog` 中不是 `Option` 的字段）
+    pub always_present: bool,
+    /// 后续版本新增的列：旧版本创建的表可以缺少，由迁移语句补齐
+    pub migrated: bool,
+}
+
+impl Column {
+    const fn new(name: &'static str, ty: ColumnType) -> Self {
+        Self {
+            name,
+            ty,
+            not_null: false,
+            unique: false,
+            always_present: false,
+            migrated: false,
+        }
+    }
+
+    const fn present(mut self) -> Self {
+        self.always_present = true;
+        self
+    }
+
+    const fn unique(mut self) -> Self {
+        self.unique = true;
+        self
+    }
+
+    const fn migrated(mut self) -> Self {
+        self.migrated = true;
+        self
+    }
+}
+
+/// 表名与按顺序排列的列
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableSchema {
+    pub name: &'static str,
+    pub columns: &'static [Column],
+}
+
+/// sqllogs 表
+pub const SQLLOG_TABLE: TableSchema = TableSchema {
+    name: "sqllogs",
+    columns: &[
+        Column {
+            name: "occurrence_time",
+            ty: ColumnType::Char(32),
+            not_null: true,
+            unique: false,
+            always_present: true,
+            migrated: false,
+        },
+        Column::new("ep", ColumnType::Integer).present(),
+        Column::new("session", ColumnType::Varchar(64)),
+        Column::new("thread", ColumnType::Varchar(64)),
+        Column::new("username", ColumnType::Varchar(128)),
+        Column::new("trx_id", ColumnType::Varchar(64)),
+        Column::new("statement", ColumnType::Varchar(64)),
+        Column::new("appname", ColumnType::Varchar(256)),
+        Column::new("ip", ColumnType::Varchar(45)),
+        Column::new("sql_type", ColumnType::Varchar(32)),
+        Column::new("description", ColumnType::Text).present(),
+        Column::new("execute_time", ColumnType::BigInt),
+        Column::new("rowcount", ColumnType::BigInt),
+        Column::new("execute_id", ColumnType::BigInt),
+        Column::new("record_id", ColumnType::UBigInt).unique().migrated(),
+        Column::new("plan", ColumnType::Text).migrated(),
+        Column::new("execute_time_us", ColumnType::BigInt).migrated(),
+        Column::new("partial", ColumnType::Boolean).present().migrated(),
+    ],
+};
+
+impl TableSchema {
+    /// 按名称查找列
+    #[must_use]
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|c| c.name == name)
+    }
+
+    /// 按顺序排列的列名
+    pub fn column_names(&self) -> impl Iterator<Item = &'static str> {
+        self.columns.iter().map(|c| c.name)
+    }
+
+    /// 建表语句，其后附带为旧版本创建的表补齐新增列的迁移语句
+    #[must_use]
+    pub fn create_table_sql(&self, database: &DatabaseType) -> String {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|c| {
+                let not_null = if c.not_null { " NOT NULL" } else { "" };
+                let unique = if c.unique { " UNIQUE" } else { "" };
+                format!(
+                    "        {} {}{not_null}{unique}",
+                    c.name,
+                    c.ty.sql_type(database)
+                )
+            })
+            .collect();
+        let mut sql = format!(
+            "CREATE TABLE IF NOT EXISTS {} (\n{}\n);\n",
+            self.name,
+            columns.join(",\n")
+        );
+        let migrations = self.migration_sql(database);
+        if !migrations.is_empty() {
+            sql.push_str("-- 兼容旧版本创建的数据库文件\n");
+            sql.push_str(&migrations);
+        }
+        sql
+    }
+
+    /// 为旧版本创建的表补齐新增列的语句
+    ///
+    /// 补齐的列不带约束（`DuckDB` 不支持新增带约束的列），唯一约束由收尾
+    /// 时创建的唯一索引补上。
+    #[must_use]
+    pub fn migration_sql(&self, database: &DatabaseType) -> String {
+        self.columns
+            .iter()
+            .filter(|c| c.migrated)
+            .map(|c| {
+                format!(
+                    "ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} {};\n",
+                    self.name,
+                    c.name,
+                    c.ty.sql_type(database)
+                )
+            })
+            .collect()
+    }
+}
+
+/// 由 `columns` 得到定长的列名数组（编译期求值）
+pub(super) const fn column_names<const N: usize>(
+    columns: &[Column],
+) -> [&'static str; N] {
+    assert!(columns.len() == N);
+    let mut names = [""; N];
+    let mut i = 0;
+    while i < N {
+        names[i] = columns[i].name;
+        i += 1;
+    }
+    names
+}
@@ -0,0 +1,109 @@
+// 共享导出器 - 让多条流水线写入同一个导出器实例
+//
+// [`SharedExporter`] 以 `Arc<Mutex<E>>` 包装导出器，`clone_handle()` 得到的
+// 句柄可以移动到其他线程，各句柄写入同一份输出。
+//
+// 争用行为：
+// - 每次写入持锁写完整批记录，同一批的记录在输出中保持连续；不同句柄的
+//   批次按获得锁的先后交错，批次之间没有顺序保证
+// - 等待锁的线程阻塞，直到持锁的写入完成；批越大，加锁次数越少、争用越低
+// - 某个线程在持锁写入时 panic 后，导出器可能只写出了半批，之后所有句柄的
+//   写入都返回错误，不再继续写出
+
+use super::template::TemplateExporter;
+use crate::sqllog::Sqllog;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// 按批写出记录的导出器
+pub trait SyncExporter: Send {
+    /// 写出一批记录
+    ///
+    /// # Errors
+    /// 写入失败时返回 I/O 错误
+    fn write_records(&mut self, records: &[Sqllog]) -> io::Result<()>;
+}
+
+impl<W: Write + Send> SyncExporter for TemplateExporter<W> {
+    fn write_records(&mut self, records: &[Sqllog]) -> io::Result<()> {
+        Self::write_records(self, records)
+    }
+}
+
+#[cfg(feature = "exporter-avro")]
+impl<W: Write + Send> SyncExporter for super::avro::AvroExporter<W> {
+    fn write_records(&mut self, records: &[Sqllog]) -> io::Result<()> {
+        Self::write_records(self, records)
+    }
+}
+
+/// 可在多个线程间共享的导出器句柄
+#[derive(Debug)]
+pub struct SharedExporter<E> {
+    inner: Arc<Mutex<E>>,
+}
+
+impl<E: SyncExporter> SharedExporter<E> {
+    /// 包装导出器
+    #[must_use]
+    pub fn new(exporter: E) -> Self {
+        Self { inner: Arc::new(Mutex::new(exporter)) }
+    }
+
+    /// 创建指向同一导出器的新句柄
+    #[must_use]
+    pub fn clone_handle(&self) -> Self {
+        Self { inner: Arc::clone(&self.inner) }
+    }
+
+    /// 当前存活的句柄数（含自身）
+    #[must_use]
+    pub fn handles(&self) -> usize {
+        Arc::strong_count(&self.inner)
+    }
+
+    /// 持锁写出一批记录，见模块文档中的争用行为
+    ///
+    /// # Errors
+    /// 写入失败，或导出器已因其他线程在写入时 panic 而不可用时返回错误
+    pub fn write(&self, records: &[Sqllog]) -> io::Result<()> {
+        self.lock()?.write_records(records)
+    }
+
+    /// 持锁访问导出器（如读取已写出的记录数）
+    ///
+    /// # Errors
+    /// 导出器已因其他线程在写入时 panic 而不可用时返回错误
+    pub fn with<R>(&self, f: impl FnOnce(&mut E) -> R) -> io::Result<R> {
+        Ok(f(&mut *self.lock()?))
+    }
+
+    /// 取回导出器（用于调用 `finish`）
+    ///
+    /// 写入时 panic 过的导出器同样返回，其输出可能不完整。
+    ///
+    /// # Errors
+    /// 仍有其他句柄存活时原样返回自身
+    pub fn into_inner(self) -> Result<E, Self> {
+        Arc::try_unwrap(self.inner)
+            .map(|mutex| {
+                mutex.into_inner().unwrap_or_else(PoisonError::into_inner)
+            })
+            .map_err(|inner| Self { inner })
+    }
+
+    fn lock(&self) -> io::Result<MutexGuard<'_, E>> {
+        self.inner.lock().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                "导出器已因其他线程在写入时 panic 而不可用",
+            )
+        })
+    }
+}
+
+impl<E: SyncExporter> SyncExporter for SharedExporter<E> {
+    fn write_records(&mut self, records: &[Sqllog]) -> io::Result<()> {
+        self.write(records)
+    }
+}
@@ -0,0 +1,190 @@
+// 结果查询 - 以类型化的方法读取导出的 DuckDB 库
+//
+// 供服务端直接消费解析结果，不需要拼写 SQL：
+//
+// ```no_run
+// use sqllog_analysis::database::SqllogStore;
+//
+// let store = SqllogStore::open("out.duckdb")?;
+// for record in store.top_slow(10)? {
+//     println!("{:?} {}", record.execute_time, record.description);
+// }
+// let recent = store.by_user("SYSDBA", "2025-09-21 12:00:00".."2025-09-21 13:00:00")?;
+// let stats = store.fingerprint_stats()?;
+// # Ok::<(), anyhow::Error>(())
+// ```
+//
+// 库以只读方式打开，可与其他只读进程同时使用。记录按 sqllogs 表的列还原，
+// 源文件行号与偏移不保存在表中，返回的记录中为 0 / `None`。拆分过冷字段的库
+// 从 `sqllogs_full` 视图读取，返回完整的 description 与 plan。
+
+use super::duckdb_impl::{FULL_VIEW, SQLLOG_COLUMNS, sqllog_from_row};
+use crate::analysis::{FingerprintAggregator, StatementStats};
+use crate::sqllog::{ExecTimeMs, Sqllog};
+use anyhow::{Context, Result, bail};
+use duckdb::{AccessMode, Config, Connection, ToSql};
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+use std::path::Path;
+
+/// 导出库的只读查询接口
+pub struct SqllogStore {
+    connection: Connection,
+    /// 查询的表或视图
+    source: &'static str,
+    /// 按写入顺序排序的列
+    order: &'static str,
+}
+
+impl SqllogStore {
+    /// 以只读方式打开导出的 DuckDB 库
+    ///
+    /// # Errors
+    /// 当文件不存在、无法打开或库中没有 sqllogs 表时返回错误
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if !path.exists() {
+            bail!("数据库文件不存在: {}", path.display());
+        }
+        let config = Config::default().access_mode(AccessMode::ReadOnly)?;
+        let connection = Connection::open_with_flags(path, config)
+            .with_context(|| format!("无法打开数据库: {}", path.display()))?;
+        let tables: i64 = connection.query_row(
+            "SELECT count(*) FROM duckdb_tables() WHERE table_name = 'sqllogs'",
+            [],
+            |row| row.get(0),
+        )?;
+        if tables == 0 {
+            bail!("数据库中没有 sqllogs 表: {}", path.display());
+        }
+        let split: i64 = connection.query_row(
+            "SELECT count(*) FROM duckdb_views() WHERE view_name = ?",
+            [FULL_VIEW],
+            |row| row.get(0),
+        )?;
+        let (source, order) = if split > 0 {
+            (FULL_VIEW, "row_order")
+        } else {
+            ("sqllogs", "rowid")
+        };
+        Ok(Self { connection, source, order })
+    }
+
+    /// 执行时间最长的 `n` 条记录，按执行时间降序（相同时按写入顺序）
+    ///
+    /// # Errors
+    /// 当查询失败时返回错误
+    pub fn top_slow(&self, n: usize) -> Result<Vec<Sqllog>> {
+        let limit = i64::try_from(n).unwrap_or(i64::MAX);
+        self.query_records(
+            &format!(
+                "WHERE execute_time IS NOT NULL ORDER BY execute_time DESC, {} LIMIT ?",
+                self.order
+            ),
+            &[&limit],
+        )
+        .context("查询慢语句失败")
+    }
+
+    /// 用户在时间范围内的记录，按写入顺序
+    ///
+    /// 范围按 `occurrence_time` 的文本比较，时间格式与日志相同（如
+    /// `2025-09-21 12:00:00`），可以使用 `a..b`、`a..=b`、`a..` 或 `..`。
+    ///
+    /// # Errors
+    /// 当查询失败时返回错误
+    pub fn by_user<'a>(
+        &self,
+        user: &str,
+        range: impl RangeBounds<&'a str>,
+    ) -> Result<Vec<Sqllog>> {
+        let mut clause = String::from("WHERE username = ?");
+        let mut params: Vec<&dyn ToSql> = vec![&user];
+        let start = range.start_bound().cloned();
+        let end = range.end_bound().cloned();
+        for (bound, inclusive, exclusive) in
+            [(&start, ">=", ">"), (&end, "<=", "<")]
+        {
+            match bound {
+                Bound::Included(value) => {
+                    clause.push_str(&format!(
+                        " AND occurrence_time {inclusive} ?"
+                    ));
+                    params.push(value);
+                }
+                Bound::Excluded(value) => {
+                    clause.push_str(&format!(
+                        " AND occurrence_time {exclusive} ?"
+                    ));
+                    params.push(value);
+                }
+                Bound::Unbounded => {}
+            }
+        }
+        clause.push_str(&format!(" ORDER BY {}", self.order));
+        self.query_records(&clause, &params).context("按用户查询记录失败")
+    }
+
+    /// 按 SQL 指纹聚合的调用次数、执行时间合计与 p95，按指纹排序
+    ///
+    /// # Errors
+    /// 当查询失败时返回错误
+    pub fn fingerprint_stats(
+        &self,
+    ) -> Result<BTreeMap<String, StatementStats>> {
+        let mut stmt = self
+            .connection
+            .prepare(&format!(
+                "SELECT description, execute_time FROM {} ORDER BY {}",
+                self.source, self.order
+            ))
+            .context("查询指纹统计失败")?;
+        let mut rows = stmt.query([])?;
+        let mut aggregator = FingerprintAggregator::new();
+        let mut batch = Vec::with_capacity(1024);
+        while let Some(row) = rows.next()? {
+            batch.push(Sqllog {
+                description: row.get(0)?,
+                execute_time: row
+                    .get::<_, Option<i64>>(1)?
+                    .map(ExecTimeMs::new),
+                ..Default::default()
+            });
+            if batch.len() == batch.capacity() {
+                aggregator.observe(&batch);
+                batch.clear();
+            }
+        }
+        aggregator.observe(&batch);
+        Ok(aggregator.finish())
+    }
+
+    /// 库中的记录数
+    ///
+    /// # Errors
+    /// 当查询失败时返回错误
+    pub fn count(&self) -> Result<u64> {
+        let count: i64 = self
+            .connection
+            .query_row("SELECT count(*) FROM sqllogs", [], |row| row.get(0))
+            .context("查询记录数失败")?;
+        count.try_into().context("记录数转换失败：不能为负数")
+    }
+
+    fn query_records(
+        &self,
+        clause: &str,
+        params: &[&dyn ToSql],
+    ) -> Result<Vec<Sqllog>> {
+        let sql = format!(
+            "SELECT {} FROM {} {clause}",
+            SQLLOG_COLUMNS.join(", "),
+            self.source
+        );
+        let mut stmt = self.connection.prepare(&sql)?;
+        let records = stmt
+            .query_map(params, sqllog_from_row)?
+            .collect::<duckdb::Result<Vec<_>>>()?;
+        Ok(records)
+    }
+}
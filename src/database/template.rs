@@ -0,0 +1,353 @@
+// 模板导出 - 按自定义行模板把记录写成定宽或任意格式的文本
+//
+// 模板语法：
+// - `{字段}` 输出字段值，NULL 输出为空串
+// - `{字段:格式}` 格式为 `[<|>][宽度][.最大字符数]`，例如 `{description:.100}`
+//   截断为最多 100 个字符，`{user:<12}` 左对齐并补空格到 12 个字符
+//   （默认左对齐），`{execute_time:>8}` 右对齐
+// - `{{` 与 `}}` 输出字面的花括号
+//
+// 字段名与 sqllogs 表的列名一致，另接受 `user` 作为 `username` 的别名。
+// 模板在构造时校验，未知字段、错误的格式说明与未闭合的花括号都会被拒绝。
+
+use super::duckdb_impl::SQLLOG_COLUMNS;
+use crate::sqllog::Sqllog;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// 模板解析错误
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TemplateError {
+    #[error("模板位置 {0} 处的 '{{' 未闭合")]
+    Unclosed(usize),
+    #[error("模板位置 {0} 处的 '}}' 没有对应的 '{{'，字面花括号请写作 '}}}}'")]
+    UnmatchedClose(usize),
+    #[error("模板中的字段名为空")]
+    EmptyField,
+    #[error("未知的模板字段: {0}")]
+    UnknownField(String),
+    #[error("字段 {field} 的格式说明无效: {spec}")]
+    InvalidSpec { field: String, spec: String },
+    #[error("模板不包含任何字段")]
+    NoFields,
+}
+
+/// 对齐方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+}
+
+/// 模板中的一个字段占位符
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Placeholder {
+    /// sqllogs 表的列名（`user` 已换成 `username`）
+    pub column: &'static str,
+    pub align: Align,
+    /// 最小宽度（字符数），不足时补空格
+    pub width: Option<usize>,
+    /// 最大字符数，超出部分截断
+    pub max_chars: Option<usize>,
+}
+
+impl Placeholder {
+    fn parse(body: &str) -> Result<Self, TemplateError> {
+        let (name, spec) = body.split_once(':').unwrap_or((body, ""));
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(TemplateError::EmptyField);
+        }
+        let lookup = if name == "user" { "username" } else { name };
+        let column = SQLLOG_COLUMNS
+            .iter()
+            .copied()
+            .find(|&c| c == lookup)
+            .ok_or_else(|| TemplateError::UnknownField(name.to_string()))?;
+
+        let invalid = || TemplateError::InvalidSpec {
+            field: name.to_string(),
+            spec: spec.to_string(),
+        };
+        let (align, rest) = match spec.chars().next() {
+            Some('<') => (Align::Left, &spec[1..]),
+            Some('>') => (Align::Right, &spec[1..]),
+            _ => (Align::Left, spec),
+        };
+        let (width, max_chars) = match rest.split_once('.') {
+            Some((width, max)) => (width, Some(max)),
+            None => (rest, None),
+        };
+        let number = |s: &str| s.parse::<usize>().map_err(|_| invalid());
+        let width = match width {
+            "" => None,
+            w => Some(number(w)?),
+        };
+        let max_chars = match max_chars {
+            Some(m) => Some(number(m)?),
+            None => None,
+        };
+        Ok(Self { column, align, width, max_chars })
+    }
+
+    fn render(&self, value: &str, out: &mut String) {
+        let truncated = match self.max_chars {
+            Some(max) => value
+                .char_indices()
+                .nth(max)
+                .map_or(value, |(i, _)| &value[..i]),
+            None => value,
+        };
+        let len = truncated.chars().count();
+        let pad = self.width.map_or(0, |w| w.saturating_sub(len));
+        if self.align == Align::Right {
+            out.extend(std::iter::repeat(' ').take(pad));
+        }
+        out.push_str(truncated);
+        if self.align == Align::Left {
+            out.extend(std::iter::repeat(' ').take(pad));
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Placeholder),
+}
+
+/// 已校验的行模板
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineTemplate {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl LineTemplate {
+    /// 解析并校验模板
+    ///
+    /// # Errors
+    /// 模板包含未知字段、无效的格式说明、未配对的花括号或没有任何字段时返回错误
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+        while let Some((pos, c)) = chars.next() {
+            match c {
+                '{' if chars.peek().is_some_and(|&(_, n)| n == '{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek().is_some_and(|&(_, n)| n == '}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let start = pos + 1;
+                    let end = template[start..]
+                        .find('}')
+                        .map(|i| start + i)
+                        .ok_or(TemplateError::Unclosed(pos))?;
+                    let body = &template[start..end];
+                    if body.contains('{') {
+                        return Err(TemplateError::Unclosed(pos));
+                    }
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(
+                            &mut literal,
+                        )));
+                    }
+                    segments.push(Segment::Field(Placeholder::parse(body)?));
+                    while chars.peek().is_some_and(|&(i, _)| i <= end) {
+                        chars.next();
+                    }
+                }
+                '}' => return Err(TemplateError::UnmatchedClose(pos)),
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        if !segments.iter().any(|s| matches!(s, Segment::Field(_))) {
+            return Err(TemplateError::NoFields);
+        }
+        Ok(Self { source: template.to_string(), segments })
+    }
+
+    /// 模板原文
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// 模板引用的列（去重，按首次出现的顺序）
+    #[must_use]
+    pub fn columns(&self) -> Vec<&'static str> {
+        let mut columns = Vec::new();
+        for segment in &self.segments {
+            if let Segment::Field(p) = segment {
+                if !columns.contains(&p.column) {
+                    columns.push(p.column);
+                }
+            }
+        }
+        columns
+    }
+
+    /// 按列名取值渲染一行（不含换行符），取不到的值输出为空串
+    pub fn render_with<'a, F>(&self, mut value: F) -> String
+    where
+        F: FnMut(&'static str) -> Option<Cow<'a, str>>,
+    {
+        let mut out = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => out.push_str(text),
+                Segment::Field(p) => {
+                    let v = value(p.column).unwrap_or_default();
+                    p.render(&v, &mut out);
+                }
+            }
+        }
+        out
+    }
+
+    /// 渲染一条解析出的记录
+    #[must_use]
+    pub fn render(&self, record: &Sqllog) -> String {
+        self.render_with(|column| record_value(record, column))
+    }
+}
+
+impl FromStr for LineTemplate {
+    type Err = TemplateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+/// 记录中与列名对应的文本值；数值字段输出为不带单位的整数
+fn record_value<'a>(record: &'a Sqllog, column: &str) -> Option<Cow<'a, str>> {
+    let text = |v: &'a Option<String>| v.as_deref().map(Cow::Borrowed);
+    match column {
+        "occurrence_time" => Some(Cow::Borrowed(&record.occurrence_time)),
+        "ep" => Some(Cow::Owned(record.ep.to_string())),
+        "session" => text(&record.session),
+        "thread" => text(&record.thread),
+        "username" => text(&record.user),
+        "trx_id" => text(&record.trx_id),
+        "statement" => text(&record.statement),
+        "appname" => text(&record.appname),
+        "ip" => text(&record.ip),
+        "sql_type" => text(&record.sql_type),
+        "description" => Some(Cow::Borrowed(&record.description)),
+        "execute_time" => {
+            record.execute_time.map(|v| Cow::Owned(v.get().to_string()))
+        }
+        "rowcount" => record.rowcount.map(|v| Cow::Owned(v.get().to_string())),
+        "execute_id" => {
+            record.execute_id.map(|v| Cow::Owned(v.get().to_string()))
+        }
+        "record_id" => record.record_id.map(|v| Cow::Owned(v.to_string())),
+        "plan" => record
+            .plan
+            .as_ref()
+            .and_then(|p| serde_json::to_string(p).ok())
+            .map(Cow::Owned),
+        "execute_time_us" => {
+            record.execute_time_us.map(|v| Cow::Owned(v.to_string()))
+        }
+        "partial" => {
+            Some(Cow::Borrowed(if record.partial { "true" } else { "false" }))
+        }
+        _ => None,
+    }
+}
+
+/// 模板导出器：每条记录按模板渲染为一行
+#[derive(Debug)]
+pub struct TemplateExporter<W: Write> {
+    template: LineTemplate,
+    writer: W,
+    written: u64,
+}
+
+impl TemplateExporter<BufWriter<File>> {
+    /// 创建写入文件的导出器
+    ///
+    /// # Errors
+    /// 模板无效时返回 [`TemplateError`]（包装为 `io::ErrorKind::InvalidInput`），
+    /// 文件无法创建时返回 I/O 错误
+    pub fn create(template: &str, path: &Path) -> io::Result<Self> {
+        let template = LineTemplate::parse(template)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        Ok(Self::with_template(template, BufWriter::new(File::create(path)?)))
+    }
+}
+
+impl<W: Write> TemplateExporter<W> {
+    /// 解析模板并创建导出器
+    ///
+    /// # Errors
+    /// 模板无效时返回错误
+    pub fn new(template: &str, writer: W) -> Result<Self, TemplateError> {
+        Ok(Self::with_template(LineTemplate::parse(template)?, writer))
+    }
+
+    /// 使用已解析的模板创建导出器
+    #[must_use]
+    pub const fn with_template(template: LineTemplate, writer: W) -> Self {
+        Self { template, writer, written: 0 }
+    }
+
+    /// 写出一批记录
+    ///
+    /// # Errors
+    /// 写入失败时返回 I/O 错误
+    pub fn write_records(&mut self, records: &[Sqllog]) -> io::Result<()> {
+        for record in records {
+            let line = self.template.render(record);
+            self.write_line(&line)?;
+        }
+        Ok(())
+    }
+
+    /// 写出一行已渲染的文本（不含换行符）
+    ///
+    /// # Errors
+    /// 写入失败时返回 I/O 错误
+    pub(crate) fn write_line(&mut self, line: &str) -> io::Result<()> {
+        self.writer.write_all(line.as_bytes())?;
+        self.writer.write_all(b"\n")?;
+        self.written += 1;
+        Ok(())
+    }
+
+    /// 使用的模板
+    #[must_use]
+    pub const fn template(&self) -> &LineTemplate {
+        &self.template
+    }
+
+    /// 已写出的行数
+    #[must_use]
+    pub const fn written(&self) -> u64 {
+        self.written
+    }
+
+    /// 刷新缓冲并返回已写出的行数
+    ///
+    /// # Errors
+    /// 刷新失败时返回 I/O 错误
+    pub fn finish(mut self) -> io::Result<u64> {
+        self.writer.flush()?;
+        Ok(self.written)
+    }
+}
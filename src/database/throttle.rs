@@ -0,0 +1,87 @@
+// 写入限速 - 控制批量写入数据库的速率
+//
+// 批量写入共享数据库时可能挤占其他业务的资源，限速器按「记录数/秒」或
+// 「批次数/秒」对写入循环进行节流：每次写入前根据已写入的总量计算应当经过
+// 的时间，若实际耗时不足则休眠补齐。一次运行只创建一个限速器，由各文件的
+// 写入循环共用，速率上限作用于整次运行。文件导出（COPY）不经过限速器。
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 写入速率上限（均为 `None` 表示不限速）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimit {
+    /// 每秒最多写入的记录数
+    pub records_per_sec: Option<u64>,
+    /// 每秒最多写入的批次数
+    pub batches_per_sec: Option<u64>,
+}
+
+impl RateLimit {
+    /// 是否配置了任一限速条件
+    #[must_use]
+    pub const fn is_limited(&self) -> bool {
+        self.records_per_sec.is_some() || self.batches_per_sec.is_some()
+    }
+}
+
+/// 写入限速器
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    started: Instant,
+    records: u64,
+    batches: u64,
+}
+
+impl RateLimiter {
+    /// 创建限速器，计时从创建时开始
+    #[must_use]
+    pub fn new(limit: RateLimit) -> Self {
+        Self { limit, started: Instant::now(), records: 0, batches: 0 }
+    }
+
+    /// 在写入一批 `records` 条记录之前调用，必要时阻塞直到满足速率上限。
+    ///
+    /// 返回本次休眠的时长（未限速时为零）。
+    pub fn acquire(&mut self, records: usize) -> Duration {
+        if !self.limit.is_limited() {
+            return Duration::ZERO;
+        }
+
+        // 限速针对「本批写入之前」已完成的量：第一批立即放行
+        let target = self.target_elapsed();
+        self.records += records as u64;
+        self.batches += 1;
+
+        let elapsed = self.started.elapsed();
+        if target > elapsed {
+            let wait = target - elapsed;
+            thread::sleep(wait);
+            wait
+        } else {
+            Duration::ZERO
+        }
+    }
+
+    /// 按已写入量计算在速率上限下至少应经过的时间
+    fn target_elapsed(&self) -> Duration {
+        let by_records = self
+            .limit
+            .records_per_sec
+            .map(|rate| Self::pace(self.records, rate));
+        let by_batches = self
+            .limit
+            .batches_per_sec
+            .map(|rate| Self::pace(self.batches, rate));
+        by_records.into_iter().chain(by_batches).max().unwrap_or_default()
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn pace(done: u64, rate: u64) -> Duration {
+        if rate == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(done as f64 / rate as f64)
+    }
+}
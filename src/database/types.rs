@@ -3,6 +3,7 @@
 // 定义数据库相关的枚举、结构体和常量
 
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 
 /// 支持的数据库类型
@@ -24,6 +25,10 @@ pub enum ExportFormat {
     Json,
     /// CSV 格式 (.csv)
     Csv,
+    /// 按 `export.template` 行模板渲染的文本 (.txt)
+    Template,
+    /// Avro 对象容器文件 (.avro)，需要启用 `exporter-avro` 特性
+    Avro,
 }
 
 impl FromStr for ExportFormat {
@@ -33,6 +38,8 @@ impl FromStr for ExportFormat {
         match s.to_lowercase().as_str() {
             "json" => Ok(Self::Json),
             "csv" => Ok(Self::Csv),
+            "template" | "txt" => Ok(Self::Template),
+            "avro" => Ok(Self::Avro),
             _ => Err(format!("不支持的导出格式: {s}")),
         }
     }
@@ -46,9 +53,46 @@ impl ExportFormat {
         match self {
             Self::Json => "json",
             Self::Csv => "csv",
+            Self::Template => "txt",
+            Self::Avro => "avro",
         }
     }
 
+    /// 按输出路径的扩展名推断导出格式，末尾的 `.gz` / `.zst` 压缩扩展名
+    /// 不参与推断（如 `out.csv.gz` 为 CSV）；无法识别时返回 `None`
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let path = match path.extension().and_then(|e| e.to_str()) {
+            Some("gz" | "zst") => Path::new(path.file_stem()?),
+            _ => path,
+        };
+        match path.extension()?.to_str()?.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            "txt" => Some(Self::Template),
+            "avro" => Some(Self::Avro),
+            _ => None,
+        }
+    }
+
+    /// 解析以逗号分隔的格式列表（如 `csv,json`），去重并保持顺序
+    ///
+    /// # Errors
+    /// 列表为空或包含不支持的格式时返回错误描述
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, String> {
+        let mut formats = Vec::new();
+        for part in s.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let format = part.parse::<Self>()?;
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        if formats.is_empty() {
+            return Err("未指定导出格式".to_string());
+        }
+        Ok(formats)
+    }
+
     /// 获取 MIME 类型
     /// 获取 MIME 类型
     #[must_use]
@@ -56,10 +100,48 @@ impl ExportFormat {
         match self {
             Self::Json => "application/json",
             Self::Csv => "text/csv",
+            Self::Template => "text/plain",
+            Self::Avro => "application/avro",
         }
     }
 }
 
+/// 单个导出产物（导出文件或旁路文件）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportArtifact {
+    /// 产物文件路径
+    pub path: String,
+    /// 产物中包含的记录数
+    pub records: u64,
+}
+
+/// 为每种导出格式确定输出路径
+///
+/// 只有一种格式时原样使用 `base`；多种格式时以各格式的扩展名替换 `base`
+/// 的扩展名，例如 `out.csv` 对应 `out.csv` 与 `out.json`。
+#[must_use]
+pub fn export_targets(
+    base: &Path,
+    formats: &[ExportFormat],
+) -> Vec<(ExportFormat, PathBuf)> {
+    match formats {
+        [format] => vec![(format.clone(), base.to_path_buf())],
+        _ => formats
+            .iter()
+            .map(|f| (f.clone(), base.with_extension(f.extension())))
+            .collect(),
+    }
+}
+
+/// 一次导出产生的结果汇总
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportReport {
+    /// 导出的记录总数
+    pub records_exported: u64,
+    /// 本次导出写出的所有产物（主导出文件在前）
+    pub artifacts: Vec<ExportArtifact>,
+}
+
 /// 数据库连接信息
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum DatabaseMode {
@@ -33,8 +33,8 @@
 //! ## 输出格式示例
 //!
 //! ```json
-//! {"path":"sqllog/test.log","line":42,"error":"日志格式错误: 行42: missing EXECTIME","raw":"SELECT * FROM users"}
-//! {"path":"sqllog/test.log","line":43,"error":"编码错误: 无效的UTF-8字符","raw":"SELECT * FROM 用户表"}
+//! {"path":"sqllog/test.log","line":42,"offset":3810,"error":"日志格式错误: 行42: missing EXECTIME","raw":"SELECT * FROM users"}
+//! {"path":"sqllog/test.log","line":43,"offset":120,"error":"UTF8解码错误: ...（字节 120..130）","raw":"len=10 prefix=[...]","raw_bytes":{"bytes":"U0VMRUNUIP/+Cg==","start":120,"end":130}}
 //! ```
 //!
 //! ## 使用场景
@@ -48,16 +48,101 @@
 //
 // 提供将解析错误写入 JSONL 文件的功能，支持：
 // - 线程安全的并发写入
-// - JSONL 格式（每行一个 JSON 对象）
+// - JSONL 格式（每行一个 JSON 对象）或 CSV 格式（带表头）
 // - 错误信息包含：文件路径、行号、错误描述、原始内容
+// - `ErrorExporter` 扩展点：错误流可以写入文件之外的目标（如数据库表）
 
-use crate::sqllog::SqllogError;
-use serde_json::json;
+use crate::sqllog::{RawSegment, SqllogError};
+use serde::Serialize;
 use std::fs::OpenOptions;
 use std::io::{BufWriter, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+/// 单条解析错误的导出记录
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ParseErrorRecord {
+    /// 源文件路径
+    pub path: String,
+    /// 错误发生的行号（源文件中的行号，从 1 开始）
+    pub line: usize,
+    /// 出错内容在源文件中的起始字节偏移（格式错误与 UTF8 解码错误）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// 错误描述
+    pub error: String,
+    /// 导致错误的原始内容
+    pub raw: String,
+    /// 无法解码的原始字节及其文件偏移（仅 UTF8 解码错误）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw_bytes: Option<RawSegment>,
+}
+
+impl ParseErrorRecord {
+    /// 将解析器上报的错误列表转换为导出记录
+    #[must_use]
+    pub fn from_errors(
+        file_path: &Path,
+        errors: &[(usize, String, SqllogError)],
+    ) -> Vec<Self> {
+        let path = file_path.to_string_lossy();
+        errors
+            .iter()
+            .map(|(line, raw, error)| Self {
+                path: path.to_string(),
+                line: *line,
+                offset: error.byte_offset(),
+                error: error.to_string(),
+                raw: raw.clone(),
+                raw_bytes: error.raw_segment().cloned(),
+            })
+            .collect()
+    }
+}
+
+/// 解析错误导出目标
+///
+/// 解析器按块上报错误，导出目标自行决定如何持久化；实现应当尽力而为，
+/// 写入失败只记录日志而不中断解析。
+pub trait ErrorExporter {
+    /// 导出一个文件的一批解析错误
+    fn export_errors(
+        &self,
+        file_path: &Path,
+        errors: &[(usize, String, SqllogError)],
+    );
+
+    /// 解析开始前检查输出目标是否可写
+    ///
+    /// # Errors
+    /// 当输出目标不可写时返回错误
+    fn preflight(&self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// 错误文件格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// 每行一个 JSON 对象
+    #[default]
+    Jsonl,
+    /// 带表头的 CSV，列为 `path,line,error,raw`（不含原始字节）
+    Csv,
+}
+
+impl std::str::FromStr for ErrorFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "jsonl" | "json" => Ok(Self::Jsonl),
+            "csv" => Ok(Self::Csv),
+            _ => Err(format!("不支持的错误文件格式: {s}")),
+        }
+    }
+}
+
 /// 错误写入器，线程安全地将解析错误写入 JSONL 文件
 ///
 /// ## 设计理念
@@ -80,6 +165,7 @@ use std::sync::{Arc, Mutex};
 pub struct ErrorWriter {
     writer: Arc<Mutex<BufWriter<std::fs::File>>>,
     path: PathBuf,
+    format: ErrorFormat,
 }
 
 impl ErrorWriter {
@@ -91,6 +177,19 @@ impl ErrorWriter {
     /// # Errors
     /// 当无法创建或打开输出文件时返回错误
     pub fn new<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        Self::with_format(path, ErrorFormat::Jsonl)
+    }
+
+    /// 创建指定格式的错误写入器
+    ///
+    /// CSV 格式仅在文件为空时写入表头，追加到已有文件时不会重复。
+    ///
+    /// # Errors
+    /// 当无法创建或打开输出文件时返回错误
+    pub fn with_format<P: AsRef<Path>>(
+        path: P,
+        format: ErrorFormat,
+    ) -> std::io::Result<Self> {
         let path = path.as_ref().to_path_buf();
 
         // 确保父目录存在
@@ -99,10 +198,16 @@ impl ErrorWriter {
         }
 
         let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let is_empty = file.metadata()?.len() == 0;
 
-        let writer = Arc::new(Mutex::new(BufWriter::new(file)));
+        let mut writer = BufWriter::new(file);
+        if format == ErrorFormat::Csv && is_empty {
+            writeln!(writer, "path,line,error,raw")?;
+            writer.flush()?;
+        }
+        let writer = Arc::new(Mutex::new(writer));
 
-        Ok(Self { writer, path })
+        Ok(Self { writer, path, format })
     }
 
     /// 写入解析错误到文件
@@ -127,6 +232,8 @@ impl ErrorWriter {
     /// - `line`: 错误发生的行号（从解析器角度）
     /// - `error`: 人类可读的错误描述信息
     /// - `raw`: 导致错误的原始日志内容，便于人工检查
+    /// - `raw_bytes`: 仅 UTF8 解码错误，`{"bytes": base64, "start", "end"}`，
+    ///   为原始字节及其在源文件中的偏移范围
     ///
     /// # Arguments
     /// * `file_path` - 发生错误的源文件路径
@@ -140,19 +247,17 @@ impl ErrorWriter {
             return;
         }
 
-        let file_path_str = file_path.as_ref().to_string_lossy();
+        let records = ParseErrorRecord::from_errors(file_path.as_ref(), errors);
 
         if let Ok(mut writer) = self.writer.lock() {
-            for (line_num, raw_line, error) in errors {
-                let json_obj = json!({
-                    "path": file_path_str,
-                    "line": line_num,
-                    "error": error.to_string(),
-                    "raw": raw_line
-                });
-
-                if let Ok(json_str) = serde_json::to_string(&json_obj) {
-                    if writeln!(writer, "{json_str}").is_err() {
+            for record in &records {
+                let line = match self.format {
+                    ErrorFormat::Jsonl => serde_json::to_string(record),
+                    ErrorFormat::Csv => Ok(csv_line(record)),
+                };
+
+                if let Ok(line) = line {
+                    if writeln!(writer, "{line}").is_err() {
                         log::error!(
                             "写入错误信息到文件失败: {}",
                             self.path.display()
@@ -179,6 +284,49 @@ impl ErrorWriter {
     }
 }
 
+impl ErrorExporter for ErrorWriter {
+    fn export_errors(
+        &self,
+        file_path: &Path,
+        errors: &[(usize, String, SqllogError)],
+    ) {
+        self.write_errors(file_path, errors);
+    }
+
+    fn preflight(&self) -> std::io::Result<()> {
+        if std::fs::metadata(&self.path)?.permissions().readonly() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                format!("错误输出文件只读: {}", self.path.display()),
+            ));
+        }
+        self.writer
+            .lock()
+            .map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "错误写入器锁已损坏",
+                )
+            })?
+            .flush()
+    }
+}
+
+/// 按 RFC 4180 输出一行 CSV（含逗号、引号或换行的字段加引号）
+fn csv_line(record: &ParseErrorRecord) -> String {
+    [record.path.as_str(), &record.line.to_string(), &record.error, &record.raw]
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                (*field).to_string()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
 impl Drop for ErrorWriter {
     fn drop(&mut self) {
         // 确保在销毁时刷新缓冲区
@@ -212,6 +360,7 @@ mod tests {
                 "malformed entry".to_string(),
                 SqllogError::Format {
                     line: 43,
+                    offset: None,
                     content: "missing field".to_string(),
                 },
             ),
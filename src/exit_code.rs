@@ -0,0 +1,84 @@
+//! 进程退出码 - 供 CI/ETL 包装脚本按运行结果分支
+//!
+//! | 退出码 | 含义 |
+//! |-------|------|
+//! | 0 | 成功 |
+//! | 1 | 运行失败（预检失败、文件处理中止等） |
+//! | 2 | 配置或命令行参数无效 |
+//! | 3 | 部分成功（部分导出格式或作业失败） |
+//! | 4 | 解析错误数超过 `--fail-on-errors` 阈值，或解析错误率熔断 |
+//! | 5 | 导出失败（没有任何导出格式成功） |
+//!
+//! 同时出现多种结果时取最严重的一种（见 [`ExitCode::or`]）。
+
+use std::fmt;
+
+/// 命令行的退出码
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExitCode {
+    #[default]
+    Success,
+    Failure,
+    InvalidConfig,
+    PartialSuccess,
+    ParseErrorsExceeded,
+    ExportFailed,
+}
+
+impl ExitCode {
+    /// 进程退出码数值
+    #[must_use]
+    pub const fn code(self) -> i32 {
+        match self {
+            Self::Success => 0,
+            Self::Failure => 1,
+            Self::InvalidConfig => 2,
+            Self::PartialSuccess => 3,
+            Self::ParseErrorsExceeded => 4,
+            Self::ExportFailed => 5,
+        }
+    }
+
+    /// 严重程度，数值越大越严重
+    const fn severity(self) -> u8 {
+        match self {
+            Self::Success => 0,
+            Self::PartialSuccess => 1,
+            Self::ParseErrorsExceeded => 2,
+            Self::ExportFailed => 3,
+            Self::InvalidConfig => 4,
+            Self::Failure => 5,
+        }
+    }
+
+    /// 合并两种结果，取更严重的一种
+    #[must_use]
+    pub const fn or(self, other: Self) -> Self {
+        if other.severity() > self.severity() { other } else { self }
+    }
+
+    /// 是否为成功
+    #[must_use]
+    pub const fn is_success(self) -> bool {
+        matches!(self, Self::Success)
+    }
+
+    /// 以该退出码结束进程
+    pub fn exit(self) -> ! {
+        std::process::exit(self.code())
+    }
+}
+
+impl fmt::Display for ExitCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Self::Success => "成功",
+            Self::Failure => "运行失败",
+            Self::InvalidConfig => "配置无效",
+            Self::PartialSuccess => "部分成功",
+            Self::ParseErrorsExceeded => "解析错误超过阈值",
+            Self::ExportFailed => "导出失败",
+        };
+        write!(f, "{text}（退出码 {}）", self.code())
+    }
+}
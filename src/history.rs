@@ -0,0 +1,247 @@
+//! 统计历史库 - 在 `DuckDB` 状态库中保存各次运行的统计快照
+//!
+//! 每次保存写入一条 `snapshots` 记录，以及对应的 `fingerprint_stats`
+//! （按 SQL 指纹）与 `user_stats`（按用户）明细，不保存原始记录。
+//! 下次运行时以 [`HistoryStore::latest`] 载入最近的快照作为基线，
+//! 用 [`compare`](crate::analysis::compare) 生成趋势报告。
+
+use crate::analysis::{StatementStats, StatsSnapshot, UserPercentiles};
+use crate::sqllog::ExecTimeMs;
+use anyhow::{Context, Result};
+use duckdb::{Connection, OptionalExt, params};
+use serde::Serialize;
+use std::path::Path;
+
+const HISTORY_SCHEMA_SQL: &str = "
+    CREATE SEQUENCE IF NOT EXISTS snapshot_id_seq START 1;
+    CREATE TABLE IF NOT EXISTS snapshots (
+        id BIGINT PRIMARY KEY DEFAULT nextval('snapshot_id_seq'),
+        label VARCHAR NOT NULL,
+        taken_at TIMESTAMP NOT NULL DEFAULT current_timestamp
+    );
+    CREATE TABLE IF NOT EXISTS fingerprint_stats (
+        snapshot_id BIGINT NOT NULL,
+        fingerprint VARCHAR NOT NULL,
+        calls BIGINT NOT NULL,
+        total_time BIGINT NOT NULL,
+        p95 BIGINT,
+        sample VARCHAR NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS user_stats (
+        snapshot_id BIGINT NOT NULL,
+        username VARCHAR NOT NULL,
+        calls BIGINT NOT NULL,
+        p50 BIGINT,
+        p95 BIGINT,
+        p99 BIGINT
+    );
+";
+
+/// 已保存快照的概要
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SnapshotInfo {
+    pub id: i64,
+    pub label: String,
+    pub taken_at: String,
+    /// 指纹数
+    pub fingerprints: u64,
+    /// 用户数
+    pub users: u64,
+}
+
+/// 统计快照的持久化存储
+pub struct HistoryStore {
+    connection: Connection,
+}
+
+fn to_i64(v: u64) -> i64 {
+    i64::try_from(v).unwrap_or(i64::MAX)
+}
+
+fn to_u64(v: i64) -> u64 {
+    u64::try_from(v).unwrap_or(0)
+}
+
+impl HistoryStore {
+    /// 打开（不存在时创建）历史库
+    ///
+    /// # Errors
+    /// 当历史库无法打开或建表失败时返回错误
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path).with_context(|| {
+            format!("无法打开统计历史库: {}", path.display())
+        })?;
+        Self::with_connection(connection)
+    }
+
+    /// 使用内存历史库（主要用于测试）
+    ///
+    /// # Errors
+    /// 当建表失败时返回错误
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection
+            .execute_batch(HISTORY_SCHEMA_SQL)
+            .context("创建统计历史表失败")?;
+        Ok(Self { connection })
+    }
+
+    /// 保存快照，返回快照 ID
+    ///
+    /// # Errors
+    /// 当历史库写入失败时返回错误
+    pub fn save(&self, snapshot: &StatsSnapshot) -> Result<i64> {
+        let tx = self
+            .connection
+            .unchecked_transaction()
+            .context("开始保存快照失败")?;
+        let id: i64 = tx
+            .query_row(
+                "INSERT INTO snapshots (label) VALUES (?) RETURNING id",
+                [&snapshot.label],
+                |row| row.get(0),
+            )
+            .context("保存快照失败")?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO fingerprint_stats VALUES (?, ?, ?, ?, ?, ?)",
+                )
+                .context("保存指纹统计失败")?;
+            for (fp, stats) in &snapshot.fingerprints {
+                stmt.execute(params![
+                    id,
+                    fp,
+                    to_i64(stats.calls),
+                    stats.total_time.get(),
+                    stats.p95.map(ExecTimeMs::get),
+                    stats.sample,
+                ])
+                .context("保存指纹统计失败")?;
+            }
+            let mut stmt = tx
+                .prepare("INSERT INTO user_stats VALUES (?, ?, ?, ?, ?, ?)")
+                .context("保存用户统计失败")?;
+            for (user, stats) in &snapshot.users {
+                stmt.execute(params![
+                    id,
+                    user,
+                    to_i64(stats.calls),
+                    stats.p50.map(ExecTimeMs::get),
+                    stats.p95.map(ExecTimeMs::get),
+                    stats.p99.map(ExecTimeMs::get),
+                ])
+                .context("保存用户统计失败")?;
+            }
+        }
+        tx.commit().context("保存快照失败")?;
+        Ok(id)
+    }
+
+    /// 列出已保存的快照（按保存顺序）
+    ///
+    /// # Errors
+    /// 当历史库读取失败时返回错误
+    pub fn snapshots(&self) -> Result<Vec<SnapshotInfo>> {
+        let mut stmt = self
+            .connection
+            .prepare(
+                "SELECT s.id, s.label, CAST(s.taken_at AS VARCHAR), \
+                 (SELECT COUNT(*) FROM fingerprint_stats f \
+                  WHERE f.snapshot_id = s.id), \
+                 (SELECT COUNT(*) FROM user_stats u \
+                  WHERE u.snapshot_id = s.id) \
+                 FROM snapshots s ORDER BY s.id",
+            )
+            .context("查询快照列表失败")?;
+        let rows = stmt.query_map([], |row| {
+            Ok(SnapshotInfo {
+                id: row.get(0)?,
+                label: row.get(1)?,
+                taken_at: row.get(2)?,
+                fingerprints: to_u64(row.get(3)?),
+                users: to_u64(row.get(4)?),
+            })
+        })?;
+        rows.collect::<Result<_, _>>().context("查询快照列表失败")
+    }
+
+    /// 载入指定快照
+    ///
+    /// # Errors
+    /// 当快照不存在或历史库读取失败时返回错误
+    pub fn load(&self, id: i64) -> Result<StatsSnapshot> {
+        let label: String = self
+            .connection
+            .query_row(
+                "SELECT label FROM snapshots WHERE id = ?",
+                [id],
+                |row| row.get(0),
+            )
+            .with_context(|| format!("快照不存在: {id}"))?;
+
+        let mut stmt = self
+            .connection
+            .prepare(
+                "SELECT fingerprint, calls, total_time, p95, sample \
+                 FROM fingerprint_stats WHERE snapshot_id = ?",
+            )
+            .context("载入指纹统计失败")?;
+        let fingerprints = stmt
+            .query_map([id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    StatementStats {
+                        calls: to_u64(row.get(1)?),
+                        total_time: ExecTimeMs::new(row.get(2)?),
+                        p95: row.get::<_, Option<i64>>(3)?.map(ExecTimeMs::new),
+                        sample: row.get(4)?,
+                    },
+                ))
+            })?
+            .collect::<Result<_, _>>()
+            .context("载入指纹统计失败")?;
+
+        let mut stmt = self
+            .connection
+            .prepare(
+                "SELECT username, calls, p50, p95, p99 \
+                 FROM user_stats WHERE snapshot_id = ?",
+            )
+            .context("载入用户统计失败")?;
+        let ms = |v: Option<i64>| v.map(ExecTimeMs::new);
+        let users = stmt
+            .query_map([id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    UserPercentiles {
+                        calls: to_u64(row.get(1)?),
+                        p50: ms(row.get(2)?),
+                        p95: ms(row.get(3)?),
+                        p99: ms(row.get(4)?),
+                    },
+                ))
+            })?
+            .collect::<Result<_, _>>()
+            .context("载入用户统计失败")?;
+
+        Ok(StatsSnapshot { label, fingerprints, users })
+    }
+
+    /// 载入最近保存的快照，尚无快照时返回 `None`
+    ///
+    /// # Errors
+    /// 当历史库读取失败时返回错误
+    pub fn latest(&self) -> Result<Option<StatsSnapshot>> {
+        let id: Option<i64> = self
+            .connection
+            .query_row("SELECT max(id) FROM snapshots", [], |row| row.get(0))
+            .optional()
+            .context("查询最近快照失败")?
+            .flatten();
+        id.map(|id| self.load(id)).transpose()
+    }
+}
@@ -1,6 +1,12 @@
 use crate::config::Config;
 use log::{info, trace};
-use std::{env, path::PathBuf};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::fs;
+use std::{
+    env,
+    path::{Path, PathBuf},
+};
 
 /// 获取 sqllog 文件夹路径，优先使用配置文件中的 `[sqllog].sqllog_dir`。
 /// 如果未在配置中提供，回退到当前工作目录。
@@ -20,3 +26,103 @@ pub fn get_sqllog_dir() -> PathBuf {
     info!("sqllog 路径: {}", cwd.display());
     cwd
 }
+
+/// 待处理文件的处理顺序（`sqllog.file_order`）
+///
+/// 文件按此顺序逐个解析、入库；作业队列按此顺序入队，认领顺序与之相同。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum FileOrder {
+    /// 目录扫描顺序（默认，取决于文件系统）
+    #[default]
+    Directory,
+    /// 按文件名排序
+    Name,
+    /// 按文件大小从大到小，大小相同时按文件名
+    LargestFirst,
+    /// 按来源轮转：文件名去掉末尾的日期/序号分段后相同的文件视为同一来源
+    /// （如同一实例的 `dmsql_DM1_20250916_*.log`），各来源的文件按文件名
+    /// 排序后依次各取一个
+    RoundRobin,
+    /// 文件名包含列表中靠前片段的文件优先，未命中任何片段的文件排在最后；
+    /// 同一优先级内按文件名
+    Priority(Vec<String>),
+}
+
+impl std::str::FromStr for FileOrder {
+    type Err = String;
+
+    /// 解析顺序名称；`priority` 的片段列表由 `sqllog.file_priority` 提供
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "directory" | "dir" => Ok(Self::Directory),
+            "name" => Ok(Self::Name),
+            "largest_first" | "largest-first" => Ok(Self::LargestFirst),
+            "round_robin" | "round-robin" => Ok(Self::RoundRobin),
+            "priority" => Ok(Self::Priority(Vec::new())),
+            _ => Err(format!("不支持的文件顺序: {s}")),
+        }
+    }
+}
+
+impl FileOrder {
+    /// 按顺序重排文件列表
+    pub fn apply(&self, files: &mut Vec<PathBuf>) {
+        match self {
+            Self::Directory => {}
+            Self::Name => files.sort_by(|a, b| file_name(a).cmp(file_name(b))),
+            Self::LargestFirst => {
+                files.sort_by_cached_key(|p| {
+                    let len = fs::metadata(p).map_or(0, |m| m.len());
+                    (Reverse(len), file_name(p).to_string())
+                });
+            }
+            Self::RoundRobin => round_robin(files),
+            Self::Priority(patterns) => files.sort_by_cached_key(|p| {
+                let name = file_name(p);
+                let rank = patterns
+                    .iter()
+                    .position(|pattern| name.contains(pattern.as_str()))
+                    .unwrap_or(patterns.len());
+                (rank, name.to_string())
+            }),
+        }
+    }
+}
+
+fn file_name(path: &Path) -> &str {
+    path.file_name().and_then(|n| n.to_str()).unwrap_or_default()
+}
+
+/// 文件来源：去掉扩展名后，再去掉末尾全为数字的 `_`/`-` 分段
+fn source_key(path: &Path) -> &str {
+    let mut key = path.file_stem().and_then(|n| n.to_str()).unwrap_or_default();
+    while let Some((head, tail)) = key.rsplit_once(['_', '-']) {
+        if tail.is_empty() || !tail.bytes().all(|b| b.is_ascii_digit()) {
+            break;
+        }
+        key = head;
+    }
+    key
+}
+
+fn round_robin(files: &mut Vec<PathBuf>) {
+    let mut groups: BTreeMap<String, Vec<PathBuf>> = BTreeMap::new();
+    for file in files.drain(..) {
+        groups.entry(source_key(&file).to_string()).or_default().push(file);
+    }
+    let mut queues: Vec<std::vec::IntoIter<PathBuf>> = groups
+        .into_values()
+        .map(|mut group| {
+            group.sort_by(|a, b| file_name(a).cmp(file_name(b)));
+            group.into_iter()
+        })
+        .collect();
+    while !queues.is_empty() {
+        queues.retain_mut(|queue| {
+            queue.next().is_some_and(|file| {
+                files.push(file);
+                true
+            })
+        });
+    }
+}
@@ -0,0 +1,504 @@
+//! 批量导入作业队列 - 可在崩溃后恢复的逐文件导入
+//!
+//! 每个待导入文件对应一个作业，作业状态持久化在独立的 `DuckDB` 状态库中：
+//!
+//! ```text
+//! pending ──claim──▶ running ──成功──▶ done
+//!    ▲                  │
+//!    └──失败且未超重试──┤
+//!                       └──失败且超过重试次数──▶ failed
+//! ```
+//!
+//! - 每次尝试都记入 `job_attempts` 表，可查询作业的历史记录
+//! - 进程崩溃后遗留的 `running` 作业在下次启动时由 [`JobStore::recover`]
+//!   放回 `pending`
+//! - [`run_jobs`] 逐个认领作业：文件先解析到独立临时库，成功后再合并到主库，
+//!   因此中断的文件不会在主库中留下部分记录
+//! - 合并完成与标记 `done` 之间崩溃时，该文件重试后会被重复导入
+
+use crate::config::RuntimeConfig;
+use crate::database::{
+    DatabaseProvider, DuckDbProvider, IndependentDatabaseStats, RateLimiter,
+    is_disk_full,
+};
+use crate::sqllog::ErrorBreaker;
+use anyhow::{Context, Result, bail};
+use duckdb::{Connection, OptionalExt, params};
+use serde::Serialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// 默认的失败重试次数（不含首次尝试）
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+
+const JOBS_SCHEMA_SQL: &str = "
+    CREATE SEQUENCE IF NOT EXISTS job_id_seq START 1;
+    CREATE TABLE IF NOT EXISTS jobs (
+        id BIGINT PRIMARY KEY DEFAULT nextval('job_id_seq'),
+        path VARCHAR NOT NULL UNIQUE,
+        status VARCHAR NOT NULL,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        records BIGINT,
+        last_error VARCHAR,
+        enqueued_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+        finished_at TIMESTAMP
+    );
+    CREATE TABLE IF NOT EXISTS job_attempts (
+        job_id BIGINT NOT NULL,
+        attempt INTEGER NOT NULL,
+        status VARCHAR NOT NULL,
+        started_at TIMESTAMP NOT NULL DEFAULT current_timestamp,
+        finished_at TIMESTAMP,
+        records BIGINT,
+        error VARCHAR
+    );
+";
+
+const JOB_COLUMNS: &str = "id, path, status, attempts, records, last_error, \
+     CAST(enqueued_at AS VARCHAR), CAST(finished_at AS VARCHAR)";
+
+/// 作业状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+impl JobStatus {
+    /// 状态库中保存的文本
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+impl fmt::Display for JobStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for JobStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "done" => Ok(Self::Done),
+            "failed" => Ok(Self::Failed),
+            _ => Err(format!("未知的作业状态: {s}")),
+        }
+    }
+}
+
+/// 一个导入作业
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Job {
+    pub id: i64,
+    pub path: PathBuf,
+    pub status: JobStatus,
+    /// 已尝试次数
+    pub attempts: u32,
+    /// 成功时导入的记录数
+    pub records: Option<u64>,
+    pub last_error: Option<String>,
+    pub enqueued_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// 作业的一次尝试
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct JobAttempt {
+    pub attempt: u32,
+    pub status: JobStatus,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub records: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// 各状态的作业数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct JobSummary {
+    pub pending: usize,
+    pub running: usize,
+    pub done: usize,
+    pub failed: usize,
+}
+
+/// 持久化的作业队列
+pub struct JobStore {
+    connection: Connection,
+    max_retries: u32,
+}
+
+impl JobStore {
+    /// 打开（不存在时创建）状态库
+    ///
+    /// # Errors
+    /// 当状态库无法打开或建表失败时返回错误
+    pub fn open(path: &Path) -> Result<Self> {
+        let connection = Connection::open(path).with_context(|| {
+            format!("无法打开作业状态库: {}", path.display())
+        })?;
+        Self::with_connection(connection)
+    }
+
+    /// 使用内存状态库（进程退出后状态丢失，主要用于测试）
+    ///
+    /// # Errors
+    /// 当建表失败时返回错误
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(connection: Connection) -> Result<Self> {
+        connection
+            .execute_batch(JOBS_SCHEMA_SQL)
+            .context("创建作业状态表失败")?;
+        Ok(Self { connection, max_retries: DEFAULT_MAX_RETRIES })
+    }
+
+    /// 设置失败重试次数（不含首次尝试）
+    #[must_use]
+    pub const fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// 加入一个作业；同一路径已有作业时保持原状态并返回 `false`
+    ///
+    /// # Errors
+    /// 当状态库读写失败时返回错误
+    pub fn enqueue(&self, path: &Path) -> Result<bool> {
+        let inserted = self
+            .connection
+            .execute(
+                "INSERT INTO jobs (path, status) VALUES (?, ?) \
+                 ON CONFLICT (path) DO NOTHING",
+                params![path.to_string_lossy(), JobStatus::Pending.as_str()],
+            )
+            .context("加入作业失败")?;
+        Ok(inserted > 0)
+    }
+
+    /// 把上次运行遗留的 `running` 作业放回 `pending`，返回恢复的作业数
+    ///
+    /// 对应的尝试记为失败。
+    ///
+    /// # Errors
+    /// 当状态库读写失败时返回错误
+    pub fn recover(&self) -> Result<usize> {
+        self.connection
+            .execute(
+                "UPDATE job_attempts SET status = 'failed', \
+                 finished_at = current_timestamp, error = '进程中断' \
+                 WHERE status = 'running'",
+                [],
+            )
+            .context("恢复作业失败")?;
+        let recovered = self
+            .connection
+            .execute(
+                "UPDATE jobs SET status = 'pending' WHERE status = 'running'",
+                [],
+            )
+            .context("恢复作业失败")?;
+        Ok(recovered)
+    }
+
+    /// 认领最早加入的 `pending` 作业并标记为 `running`
+    ///
+    /// # Errors
+    /// 当状态库读写失败时返回错误
+    pub fn claim_next(&self) -> Result<Option<Job>> {
+        let id: Option<i64> = self
+            .connection
+            .query_row(
+                "SELECT id FROM jobs WHERE status = 'pending' \
+                 ORDER BY id LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()
+            .context("查询待处理作业失败")?;
+        let Some(id) = id else { return Ok(None) };
+
+        self.connection
+            .execute(
+                "UPDATE jobs SET status = 'running', attempts = attempts + 1 \
+                 WHERE id = ?",
+                [id],
+            )
+            .context("认领作业失败")?;
+        let job = self.job(id)?;
+        self.connection
+            .execute(
+                "INSERT INTO job_attempts (job_id, attempt, status) \
+                 VALUES (?, ?, 'running')",
+                params![id, job.attempts],
+            )
+            .context("记录作业尝试失败")?;
+        Ok(Some(job))
+    }
+
+    /// 标记作业成功
+    ///
+    /// # Errors
+    /// 当状态库读写失败时返回错误
+    pub fn complete(&self, id: i64, records: u64) -> Result<()> {
+        let records = i64::try_from(records).unwrap_or(i64::MAX);
+        self.finish_attempt(id, JobStatus::Done, Some(records), None)?;
+        self.connection
+            .execute(
+                "UPDATE jobs SET status = 'done', records = ?, \
+                 last_error = NULL, finished_at = current_timestamp \
+                 WHERE id = ?",
+                params![records, id],
+            )
+            .context("更新作业状态失败")?;
+        Ok(())
+    }
+
+    /// 标记作业失败，未超过重试次数时放回 `pending`；返回作业的新状态
+    ///
+    /// # Errors
+    /// 当状态库读写失败时返回错误
+    pub fn fail(&self, id: i64, error: &str) -> Result<JobStatus> {
+        let job = self.job(id)?;
+        let status = if job.attempts > self.max_retries {
+            JobStatus::Failed
+        } else {
+            JobStatus::Pending
+        };
+        self.finish_attempt(id, JobStatus::Failed, None, Some(error))?;
+        self.connection
+            .execute(
+                "UPDATE jobs SET status = ?, last_error = ?, \
+                 finished_at = CASE WHEN ? = 'failed' \
+                     THEN current_timestamp END \
+                 WHERE id = ?",
+                params![status.as_str(), error, status.as_str(), id],
+            )
+            .context("更新作业状态失败")?;
+        Ok(status)
+    }
+
+    /// 把 `failed` 作业重新放回 `pending` 并清零尝试次数，返回重置的作业数
+    ///
+    /// # Errors
+    /// 当状态库读写失败时返回错误
+    pub fn retry_failed(&self) -> Result<usize> {
+        self.connection
+            .execute(
+                "UPDATE jobs SET status = 'pending', attempts = 0, \
+                 finished_at = NULL WHERE status = 'failed'",
+                [],
+            )
+            .context("重置失败作业失败")
+    }
+
+    fn finish_attempt(
+        &self,
+        id: i64,
+        status: JobStatus,
+        records: Option<i64>,
+        error: Option<&str>,
+    ) -> Result<()> {
+        self.connection
+            .execute(
+                "UPDATE job_attempts SET status = ?, records = ?, error = ?, \
+                 finished_at = current_timestamp \
+                 WHERE job_id = ? AND status = 'running'",
+                params![status.as_str(), records, error, id],
+            )
+            .context("记录作业尝试失败")?;
+        Ok(())
+    }
+
+    /// 查询单个作业
+    ///
+    /// # Errors
+    /// 当作业不存在或状态库读取失败时返回错误
+    pub fn job(&self, id: i64) -> Result<Job> {
+        let job = self
+            .connection
+            .query_row(
+                &format!("SELECT {JOB_COLUMNS} FROM jobs WHERE id = ?"),
+                [id],
+                job_from_row,
+            )
+            .optional()
+            .context("查询作业失败")?;
+        match job {
+            Some(job) => Ok(job),
+            None => bail!("作业不存在: {id}"),
+        }
+    }
+
+    /// 按加入顺序列出全部作业
+    ///
+    /// # Errors
+    /// 当状态库读取失败时返回错误
+    pub fn jobs(&self) -> Result<Vec<Job>> {
+        let mut stmt = self
+            .connection
+            .prepare(&format!("SELECT {JOB_COLUMNS} FROM jobs ORDER BY id"))?;
+        let jobs = stmt
+            .query_map([], job_from_row)?
+            .collect::<duckdb::Result<Vec<_>>>()
+            .context("查询作业失败")?;
+        Ok(jobs)
+    }
+
+    /// 作业的全部尝试记录
+    ///
+    /// # Errors
+    /// 当状态库读取失败时返回错误
+    pub fn attempts(&self, id: i64) -> Result<Vec<JobAttempt>> {
+        let mut stmt = self.connection.prepare(
+            "SELECT attempt, status, CAST(started_at AS VARCHAR), \
+             CAST(finished_at AS VARCHAR), records, error \
+             FROM job_attempts WHERE job_id = ? ORDER BY attempt",
+        )?;
+        let attempts = stmt
+            .query_map([id], |row| {
+                Ok(JobAttempt {
+                    attempt: row.get(0)?,
+                    status: parse_status(row, 1)?,
+                    started_at: row.get(2)?,
+                    finished_at: row.get(3)?,
+                    records: row.get::<_, Option<i64>>(4)?.map(to_u64),
+                    error: row.get(5)?,
+                })
+            })?
+            .collect::<duckdb::Result<Vec<_>>>()
+            .context("查询作业尝试失败")?;
+        Ok(attempts)
+    }
+
+    /// 各状态的作业数
+    ///
+    /// # Errors
+    /// 当状态库读取失败时返回错误
+    pub fn summary(&self) -> Result<JobSummary> {
+        let mut summary = JobSummary::default();
+        for job in self.jobs()? {
+            match job.status {
+                JobStatus::Pending => summary.pending += 1,
+                JobStatus::Running => summary.running += 1,
+                JobStatus::Done => summary.done += 1,
+                JobStatus::Failed => summary.failed += 1,
+            }
+        }
+        Ok(summary)
+    }
+}
+
+fn job_from_row(row: &duckdb::Row<'_>) -> duckdb::Result<Job> {
+    Ok(Job {
+        id: row.get(0)?,
+        path: PathBuf::from(row.get::<_, String>(1)?),
+        status: parse_status(row, 2)?,
+        attempts: row.get(3)?,
+        records: row.get::<_, Option<i64>>(4)?.map(to_u64),
+        last_error: row.get(5)?,
+        enqueued_at: row.get(6)?,
+        finished_at: row.get(7)?,
+    })
+}
+
+fn parse_status(
+    row: &duckdb::Row<'_>,
+    idx: usize,
+) -> duckdb::Result<JobStatus> {
+    let text: String = row.get(idx)?;
+    text.parse().map_err(|e: String| {
+        duckdb::Error::FromSqlConversionFailure(
+            idx,
+            duckdb::types::Type::Text,
+            e.into(),
+        )
+    })
+}
+
+fn to_u64(v: i64) -> u64 {
+    u64::try_from(v).unwrap_or(0)
+}
+
+/// 逐个处理队列中的 `pending` 作业，返回本次运行的合并统计
+///
+/// 每个文件解析到独立临时库，成功后合并到 `runtime` 指定的主库并标记完成；
+/// 失败的作业按重试次数放回队列，在本次运行中稍后重试。磁盘空间不足或
+/// 解析错误率熔断时立即停止，剩余作业保持 `pending`。
+///
+/// # Errors
+/// 当主库或状态库操作失败、磁盘空间不足或解析错误率熔断时返回错误
+pub fn run_jobs(
+    store: &JobStore,
+    runtime: &RuntimeConfig,
+) -> Result<IndependentDatabaseStats> {
+    let mut main_provider = DuckDbProvider::new(runtime)?;
+    main_provider.enable_independent_processing();
+    main_provider.initialize()?;
+
+    let mut stats = IndependentDatabaseStats::default();
+    let mut limiter = RateLimiter::new(runtime.insert_rate_limit);
+    while let Some(job) = store.claim_next()? {
+        log::info!(
+            "开始作业 #{}（第 {} 次尝试）: {}",
+            job.id,
+            job.attempts,
+            job.path.display()
+        );
+        match import_file(&mut main_provider, &job.path, runtime, &mut limiter)
+        {
+            Ok(file_stats) => {
+                store.complete(job.id, file_stats.records_inserted as u64)?;
+                stats.merge(&file_stats);
+            }
+            Err(e) => {
+                let status = store.fail(job.id, &format!("{e:#}"))?;
+                log::error!("作业 #{} 失败（{status}）: {e:#}", job.id);
+                if is_disk_full(&e) || ErrorBreaker::tripped(&e) {
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    if runtime.cluster_by_time {
+        main_provider.cluster_by_occurrence_time()?;
+    }
+    main_provider.finalize_schema()?;
+    let aliases = &runtime.export_options.column_aliases;
+    if !aliases.is_empty() {
+        main_provider.create_aliased_view(aliases)?;
+    }
+    Ok(stats)
+}
+
+/// 解析单个文件到临时库并合并到主库
+fn import_file(
+    main_provider: &mut DuckDbProvider,
+    path: &Path,
+    runtime: &RuntimeConfig,
+    limiter: &mut RateLimiter,
+) -> Result<IndependentDatabaseStats> {
+    let (file_stats, temp_path) =
+        main_provider.process_file_independently(path, runtime, limiter)?;
+    let merged = main_provider.merge_temp_database(&temp_path);
+    main_provider.cleanup_temp_database(&temp_path)?;
+    merged?;
+    Ok(file_stats)
+}
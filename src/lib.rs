@@ -1,6 +1,29 @@
+pub mod analysis;
+#[cfg(feature = "bin")]
 pub mod analysis_log;
+#[cfg(feature = "database")]
 pub mod config;
+mod convenience;
+#[cfg(feature = "database")]
 pub mod database;
 pub mod error_writer;
+pub mod exit_code;
+#[cfg(feature = "database")]
+pub mod history;
+#[cfg(feature = "database")]
 pub mod input_path;
+#[cfg(feature = "database")]
+pub mod jobs;
+pub mod notify;
+#[cfg(feature = "concurrent")]
+pub mod pipeline;
+pub mod prelude;
+#[cfg(feature = "database")]
+pub mod retention;
+#[cfg(feature = "database")]
+mod spans;
 pub mod sqllog;
+
+pub use convenience::parse_file;
+#[cfg(feature = "database")]
+pub use convenience::{ExportStats, SourceStats, export_file, export_files};
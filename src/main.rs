@@ -31,6 +31,146 @@
 //! sqllog-analysis --input /archive/ --database analytics.db
 //! ```
 //!
+//! ### 4. 审阅目标库结构
+//! ```bash
+//! # 打印将要执行的建表与索引语句，不写入任何数据
+//! sqllog-analysis --print-schema
+//! ```
+//!
+//! ### 5. 一次解析导出多种格式
+//! ```bash
+//! # 共用同一次解析，分别写出 output.csv 与 output.json
+//! sqllog-analysis --format csv --format json
+//! # --exporter-opt 可重复出现，覆盖配置中 export.exporter_opts 的同名选项
+//! sqllog-analysis --format csv --exporter-opt csv.delimiter=';' --exporter-opt csv.header=false
+//! ```
+//!
+//! ### 6. 即席聚合分析
+//! ```bash
+//! # 按用户统计调用次数与 p95 执行时间，不需要外部数据库
+//! sqllog-analysis analyze --select "user, count(*), p95(execute_time)" \
+//!     --group-by user --having "count(*) > 100" --order-by 2 desc
+//! ```
+//!
+//! ### 7. 升级前后对比
+//! ```bash
+//! # 按 SQL 指纹对比两组日志，列出新增、消失以及 p95 / 调用次数明显变化的语句
+//! sqllog-analysis diff /logs/before/ /logs/after/ --p95-threshold 20
+//! ```
+//!
+//! ### 8. 导入前探查
+//! ```bash
+//! # 只读取每个文件开头的 64 KB，报告编码、行尾、首条时间与记录数估算
+//! sqllog-analysis inspect /logs/sqllog/ --sample-kb 256
+//! ```
+//!
+//! ### 9. 日志覆盖检测
+//! ```bash
+//! # 列出超过 10 分钟没有任何日志的时段，并按小时报告覆盖率
+//! sqllog-analysis coverage /logs/sqllog/ --gap-secs 600
+//! ```
+//!
+//! ### 10. 压缩导出
+//! ```bash
+//! # 由 DuckDB 写出时直接压缩，得到 output.csv.gz，无需额外的压缩步骤
+//! sqllog-analysis --format csv --compress gzip
+//! ```
+//!
+//! ### 11. 实时监控
+//! ```bash
+//! # 跟随正在写入的日志，每 5 秒输出最近 5 分钟的 QPS、p95 与错误率，越限时告警
+//! sqllog-analysis watch /dm/log/dmsql_DM01.log --max-p95-ms 500 --max-error-rate 0.01
+//! ```
+//!
+//! ### 12. 按天趋势对比
+//! ```bash
+//! # 与历史库中最近一次快照对比指纹 p95 与用户百分位，再把今天的统计存为快照
+//! sqllog-analysis history compare /logs/sqllog/ --state history.duckdb --save
+//! ```
+//!
+//! ### 13. 会话并发
+//! ```bash
+//! # 按 5 分钟桶统计活跃会话数及各用户、appname 的并发峰值
+//! sqllog-analysis concurrency /logs/sqllog/ --bucket-secs 300
+//! ```
+//!
+//! ### 14. 数据画像
+//! ```bash
+//! # 查看字段缺失率、user/appname/ip 取值个数与 description 长度分布
+//! sqllog-analysis profile /logs/sqllog/
+//! ```
+//!
+//! ### 15. 安全分级
+//! ```bash
+//! # 按 [[classify.rules]] 规则统计 sensitive/forbidden 记录，forbidden 记录逐条写出
+//! sqllog-analysis classify /logs/sqllog/ --forbidden-out forbidden.jsonl
+//! ```
+//!
+//! ### 16. 按扩展名推断导出格式
+//! ```bash
+//! # 由 --output 的扩展名推断格式与压缩方式（此处为 gzip 压缩的 CSV），
+//! # 同时给出 --format 时以 --format 为准，两者冲突则报错
+//! sqllog-analysis --output report.csv.gz
+//! ```
+//!
+//! ### 17. 输出详细程度
+//! ```bash
+//! # -q 只在控制台输出错误；-v/-vv/-vvv 打开控制台日志，等级依次为 info/debug/trace
+//! sqllog-analysis -q --format csv
+//! sqllog-analysis -vv inspect /logs/sqllog/
+//! ```
+//!
+//! ### 18. 中断后续写
+//! ```bash
+//! # 按文件登记已提交的合并批次，重跑时跳过上次已写入的文件
+//! sqllog-analysis --resume
+//! ```
+//!
+//! ### 19. 清理过期输出
+//! ```bash
+//! # 按导出清单识别每次运行：删除 30 天前的运行，但每个子目录至少保留最近 5 次
+//! sqllog-analysis cleanup /data/exports --max-age-days 30 --keep-last 5 --dry-run
+//! ```
+//!
+//! ### 20. 只统计记录数
+//! ```bash
+//! # 完整扫描 sqllog_dir 下的文件，只按时间戳首行计数，不解析、不写数据库
+//! sqllog-analysis --count-only
+//! ```
+//!
+//! ### 21. 从已保存的记录重新导出
+//! ```bash
+//! # 读取此前导出的 JSON Lines（或 Parquet），跳过解析直接导出为 CSV
+//! sqllog-analysis convert records.jsonl --format csv --output records.csv
+//! # 写入 DuckDB 库文件；记录按表结构校验，--allow-extra-fields 忽略未知字段
+//! sqllog-analysis convert records.jsonl --db records.duckdb --allow-extra-fields
+//! ```
+//!
+//! ### 22. 按应用汇总负载
+//! ```bash
+//! # 按 appname（经 [apps.aliases] 统一）列出调用次数、p50/p95/p99 与主要 SQL 指纹
+//! sqllog-analysis apps /logs/sqllog/ --top 10
+//! ```
+//!
+//! ### 23. 补录历史日志到日期分区
+//! ```bash
+//! # 按每条记录的 occurrence_time 写入 exports/year=YYYY/month=MM/day=DD/sqllogs.csv
+//! sqllog-analysis --output exports/sqllogs.csv --backfill
+//! sqllog-analysis convert records.jsonl --output exports/sqllogs.csv --backfill
+//! ```
+//!
+//! ### 24. 导出库体积报告
+//! ```bash
+//! # 列出 db_path 库中各表与索引的行数和估算体积、时间范围与各维度的不同取值数
+//! sqllog-analysis --duckdb-report report.json
+//! ```
+//!
+//! ### 25. 索引建议
+//! ```bash
+//! # 按 WHERE/ON 中常用的过滤列为 SQL 引用的表推荐索引，按执行时间合计排序
+//! sqllog-analysis indexes /logs/sqllog/ --top 10
+//! ```
+//!
 //! ## 程序架构
 //!
 //! ```text
@@ -45,20 +185,236 @@
 //! - **日志初始化失败**：视为严重错误，退出码 2
 //! - **运行时异常**：记录详细日志和回溯信息，优雅退出
 //! - **数据处理错误**：隔离错误，继续处理其他数据
+//!
+//! ## 退出码
+//!
+//! 默认处理流程按结果使用不同的退出码，便于 CI/ETL 脚本分支（完整列表见
+//! `sqllog_analysis::exit_code`）：0 成功、1 运行失败、2 配置无效、3 部分
+//! 成功、4 解析错误超过 `--fail-on-errors N` 阈值、5 导出失败。
+//!
+//! ```bash
+//! sqllog-analysis --format csv --fail-on-errors 100 || echo "exit $?"
+//! ```
 
 mod analysis_log;
 mod app;
 
-use analysis_log::LogConfig;
+use analysis_log::{LogConfig, Verbosity};
 use sqllog_analysis::config::{Config, RuntimeConfig};
-use std::{backtrace::Backtrace, panic, process};
+use sqllog_analysis::database::{Compression, DuckDbProvider, ExportFormat};
+use sqllog_analysis::exit_code::ExitCode;
+use std::{
+    backtrace::Backtrace,
+    panic,
+    path::{Path, PathBuf},
+    process,
+};
 
 fn main() {
-    let runtime = load_runtime_config();
-    init_logging(&runtime);
+    // 仅打印目标库的建表与索引语句，不读取配置也不写入任何数据
+    if std::env::args().skip(1).any(|arg| arg == "--print-schema") {
+        print!("{}", DuckDbProvider::schema_sql());
+        return;
+    }
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let verbosity = Verbosity::take_from_args(&mut args).unwrap_or_else(|e| {
+        eprintln!("参数错误: {e}");
+        process::exit(ExitCode::InvalidConfig.code());
+    });
+    let mut runtime = load_runtime_config();
+    init_logging(&runtime, verbosity);
     set_panic_hook();
 
-    app::run();
+    match args.first().map(String::as_str) {
+        Some("analyze") => app::run_analyze(&runtime, &args[1..]),
+        Some("diff") => app::run_diff(&runtime, &args[1..]),
+        Some("coverage") => app::run_coverage(&runtime, &args[1..]),
+        Some("inspect") => app::run_inspect(&args[1..]),
+        Some("watch") => app::run_watch(&runtime, &args[1..]),
+        Some("history") => app::run_history(&runtime, &args[1..]),
+        Some("concurrency") => app::run_concurrency(&runtime, &args[1..]),
+        Some("profile") => app::run_profile(&runtime, &args[1..]),
+        Some("classify") => app::run_classify(&runtime, &args[1..]),
+        Some("apps") => app::run_apps(&runtime, &args[1..]),
+        Some("indexes") => app::run_indexes(&runtime, &args[1..]),
+        Some("cleanup") => app::run_cleanup(&runtime, &args[1..]),
+        Some("convert") => {
+            let format_given = apply_format_flags(&mut runtime, &args);
+            apply_compress_flag(&mut runtime, &args);
+            apply_exporter_opt_flags(&mut runtime, &args);
+            apply_output_flag(&mut runtime, &args, format_given);
+            apply_backfill_flag(&mut runtime, &args);
+            let code = app::run_convert(&runtime, &args[1..]);
+            if !code.is_success() {
+                log::warn!("convert 结束: {code}");
+                analysis_log::shutdown_telemetry();
+                code.exit();
+            }
+        }
+        _ => {
+            let format_given = apply_format_flags(&mut runtime, &args);
+            apply_compress_flag(&mut runtime, &args);
+            apply_exporter_opt_flags(&mut runtime, &args);
+            apply_output_flag(&mut runtime, &args, format_given);
+            apply_backfill_flag(&mut runtime, &args);
+            if args.iter().any(|arg| arg == "--resume") {
+                runtime.db_resume = true;
+            }
+            let code = if args.iter().any(|arg| arg == "--count-only") {
+                app::run_count_only(&runtime)
+            } else if let Some(pos) =
+                args.iter().position(|arg| arg == "--duckdb-report")
+            {
+                let output = args
+                    .get(pos + 1)
+                    .filter(|value| !value.starts_with("--"))
+                    .map(Path::new);
+                app::run_duckdb_report(&runtime, output)
+            } else {
+                app::run(&runtime, fail_on_errors_flag(&args))
+            };
+            if !code.is_success() {
+                log::warn!("运行结束: {code}");
+                analysis_log::shutdown_telemetry();
+                code.exit();
+            }
+        }
+    }
+    analysis_log::shutdown_telemetry();
+}
+
+/// 应用命令行中的 `--format` 参数：可重复出现或以逗号分隔（如
+/// `--format csv --format json`、`--format csv,json`），覆盖配置中的
+/// `export.format` 并启用导出，所有格式共用同一次解析。返回是否给出了该参数。
+fn apply_format_flags(runtime: &mut RuntimeConfig, args: &[String]) -> bool {
+    let mut formats = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--format" {
+            let Some(value) = iter.next() else {
+                eprintln!("参数错误: --format 缺少取值");
+                process::exit(2);
+            };
+            formats.push(value.as_str());
+        }
+    }
+    if formats.is_empty() {
+        return false;
+    }
+    runtime.export_format = formats.join(",");
+    runtime.export_enabled = true;
+    true
+}
+
+/// 应用命令行中的 `--exporter-opt key=value` 参数：可重复出现，按出现顺序
+/// 在配置中的 `export.exporter_opts` 之后设置，同名选项以命令行为准。
+fn apply_exporter_opt_flags(runtime: &mut RuntimeConfig, args: &[String]) {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg != "--exporter-opt" {
+            continue;
+        }
+        let Some(pair) = iter.next() else {
+            eprintln!("参数错误: --exporter-opt 缺少取值");
+            process::exit(2);
+        };
+        if let Err(e) = runtime.export_options.format_options.set(pair) {
+            eprintln!("参数错误: --exporter-opt 无效: {e}");
+            process::exit(2);
+        }
+    }
+}
+
+/// 应用命令行中的 `--compress gzip|zstd` 参数，覆盖配置中的
+/// `export.compression`。
+fn apply_compress_flag(runtime: &mut RuntimeConfig, args: &[String]) {
+    let Some(pos) = args.iter().position(|arg| arg == "--compress") else {
+        return;
+    };
+    let Some(value) = args.get(pos + 1) else {
+        eprintln!("参数错误: --compress 缺少取值");
+        process::exit(2);
+    };
+    match value.parse() {
+        Ok(compression) => {
+            runtime.export_options.compression = Some(compression);
+        }
+        Err(e) => {
+            eprintln!("参数错误: --compress 无效: {e}");
+            process::exit(2);
+        }
+    }
+}
+
+/// 应用命令行中的 `--output <路径>`：覆盖 `export.out_path` 并启用导出。
+///
+/// 未给出 `--format` 时按扩展名推断格式，无法识别扩展名则报错；给出单个
+/// `--format` 时以其为准，但扩展名对应另一种格式时报错。路径以 `.gz` /
+/// `.zst` 结尾且未通过 `--compress` 或配置指定压缩方式时按扩展名压缩。
+fn apply_output_flag(
+    runtime: &mut RuntimeConfig,
+    args: &[String],
+    format_given: bool,
+) {
+    let Some(pos) = args.iter().position(|arg| arg == "--output") else {
+        return;
+    };
+    let Some(value) = args.get(pos + 1) else {
+        eprintln!("参数错误: --output 缺少取值");
+        process::exit(2);
+    };
+    let path = PathBuf::from(value);
+    let inferred = ExportFormat::from_path(&path);
+    if format_given {
+        let formats = ExportFormat::parse_list(&runtime.export_format)
+            .unwrap_or_default();
+        let conflict = match (&inferred, formats.as_slice()) {
+            (Some(inferred), [format]) => inferred != format,
+            _ => false,
+        };
+        if conflict {
+            eprintln!(
+                "参数错误: --output {value} 的扩展名对应 {} 格式，与 --format {} 冲突",
+                inferred.as_ref().map_or("", ExportFormat::extension),
+                runtime.export_format
+            );
+            process::exit(2);
+        }
+    } else {
+        let Some(inferred) = inferred else {
+            eprintln!(
+                "参数错误: 无法从 --output {value} 的扩展名推断导出格式；请使用 --format 指定（json/csv/template/avro）"
+            );
+            process::exit(2);
+        };
+        runtime.export_format = inferred.extension().to_string();
+    }
+    if runtime.export_options.compression.is_none() {
+        runtime.export_options.compression = Compression::from_path(&path);
+    }
+    runtime.export_out_path = Some(path);
+    runtime.export_enabled = true;
+}
+
+/// 应用命令行中的 `--backfill`：按记录的 `occurrence_time` 把导出写入
+/// `year=/month=/day=` 分区目录，等同于 `export.hive_partitions = true`。
+fn apply_backfill_flag(runtime: &mut RuntimeConfig, args: &[String]) {
+    if args.iter().any(|arg| arg == "--backfill") {
+        runtime.export_options.hive_partitions = true;
+    }
+}
+
+/// 解析命令行中的 `--fail-on-errors N`：解析错误超过 N 条时以退出码 4 结束
+fn fail_on_errors_flag(args: &[String]) -> Option<usize> {
+    let pos = args.iter().position(|arg| arg == "--fail-on-errors")?;
+    match args.get(pos + 1).map(|v| v.parse()) {
+        Some(Ok(limit)) => Some(limit),
+        _ => {
+            eprintln!("参数错误: --fail-on-errors 需要非负整数");
+            process::exit(ExitCode::InvalidConfig.code());
+        }
+    }
 }
 
 /// 载入运行时配置。
@@ -72,18 +428,24 @@ fn load_runtime_config() -> RuntimeConfig {
 ///
 /// 参数：
 /// - `runtime`：运行时配置，包含日志级别、是否输出到 stdout、日志目录等。
-fn init_logging(runtime: &RuntimeConfig) {
-    let log_config = LogConfig {
+/// - `verbosity`：命令行 `-q`/`-v` 指定的详细程度，覆盖配置中的等级。
+fn init_logging(runtime: &RuntimeConfig, verbosity: Verbosity) {
+    let mut log_config = LogConfig {
         enable_stdout: runtime.enable_stdout,
         log_file: runtime.log_dir.clone(),
         level: runtime.log_level,
+        otlp_endpoint: runtime.log_otlp_endpoint.clone(),
+        otlp_service_name: runtime.log_otlp_service_name.clone(),
         ..Default::default()
     };
+    verbosity.apply(&mut log_config);
     // 在初始化日志之前先打印当前日志相关配置（便于在 enable_stdout=false 时也能看到等级）
-    println!(
-        "日志等级配置: {:?}, stdout: {}",
-        log_config.level, log_config.enable_stdout
-    );
+    if !verbosity.is_quiet() {
+        println!(
+            "日志等级配置: {:?}, stdout: {}",
+            log_config.level, log_config.enable_stdout
+        );
+    }
     if let Err(e) = log_config.init() {
         eprintln!("日志初始化失败: {e}");
         // 无法初始化日志属于严重错误，退出
@@ -0,0 +1,240 @@
+//! 运行结果通知 - 向 Webhook 推送运行摘要与阈值告警
+//!
+//! 运行结束后根据 [`RunSummary`] 检查错误率与慢语句数阈值，按目标平台
+//! （钉钉、企业微信、Slack 或通用 JSON）生成消息体并 POST 到配置的 Webhook。
+//!
+//! 消息体的生成与阈值检查总是可用；实际发送需要启用 `notify` 特性，
+//! 并要求运行环境的 `PATH` 中有 `curl`（由它完成 HTTPS 请求）。找不到
+//! `curl` 时通知失败。通知失败只记录日志，不影响运行结果。
+//!
+//! 每次请求都带有 `Idempotency-Key` 头，取值由输入文件的摘要与批次号确定
+//! （见 [`idempotency_key`]）；同一组输入重跑或 curl 重试时取值不变，
+//! 接收方可据此去重。
+
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+/// Webhook 目标平台，决定消息体格式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum WebhookFlavor {
+    /// `{"title", "text", "summary", "breaches"}`
+    #[default]
+    Generic,
+    /// 钉钉机器人：`{"msgtype": "markdown", ...}`
+    DingTalk,
+    /// 企业微信机器人：`{"msgtype": "markdown", ...}`
+    WeCom,
+    /// Slack Incoming Webhook：`{"text": ...}`
+    Slack,
+}
+
+impl FromStr for WebhookFlavor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "generic" | "json" => Ok(Self::Generic),
+            "dingtalk" => Ok(Self::DingTalk),
+            "wecom" | "wechat" => Ok(Self::WeCom),
+            "slack" => Ok(Self::Slack),
+            _ => Err(format!(
+                "未知的 Webhook 类型: {s}；可选值为 generic/dingtalk/wecom/slack"
+            )),
+        }
+    }
+}
+
+/// 通知配置
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotifyConfig {
+    pub webhook_url: String,
+    pub flavor: WebhookFlavor,
+    /// 运行完成时是否总是通知（否则只在超过阈值时通知）
+    pub on_complete: bool,
+    /// 解析错误数占（记录数 + 解析错误数）比例的上限
+    pub max_error_ratio: Option<f64>,
+    /// 慢语句数上限
+    pub max_slow_queries: Option<u64>,
+    /// 慢语句的 execute_time 下限（毫秒）
+    pub slow_query_ms: i64,
+    /// 单次请求超时（秒）
+    pub timeout_secs: u64,
+    /// 请求遇到超时或临时性错误时的重试次数
+    pub retries: u32,
+}
+
+/// 默认的慢语句下限（毫秒）
+pub const DEFAULT_SLOW_QUERY_MS: i64 = 1000;
+
+impl NotifyConfig {
+    /// 以默认选项创建配置
+    #[must_use]
+    pub fn new(webhook_url: &str) -> Self {
+        Self {
+            webhook_url: webhook_url.to_string(),
+            flavor: WebhookFlavor::default(),
+            on_complete: true,
+            max_error_ratio: None,
+            max_slow_queries: None,
+            slow_query_ms: DEFAULT_SLOW_QUERY_MS,
+            timeout_secs: 10,
+            retries: 0,
+        }
+    }
+}
+
+/// 一次运行的摘要
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct RunSummary {
+    pub files: usize,
+    pub records: usize,
+    pub parse_errors: usize,
+    /// 慢语句数（未统计时为 `None`）
+    pub slow_queries: Option<u64>,
+}
+
+impl RunSummary {
+    /// 解析错误占比
+    #[must_use]
+    pub fn error_ratio(&self) -> f64 {
+        let total = self.records + self.parse_errors;
+        if total == 0 { 0.0 } else { self.parse_errors as f64 / total as f64 }
+    }
+}
+
+/// 生成批次的幂等键：`<输入摘要前 32 位>-<批次号>`
+///
+/// 相同的输入摘要与批次号总是得到相同的键；运行摘要作为批次 0 发送。
+#[must_use]
+pub fn idempotency_key(input_hash: &str, batch: u64) -> String {
+    let prefix = input_hash.get(..32).unwrap_or(input_hash);
+    format!("{prefix}-{batch}")
+}
+
+/// 检查摘要是否超过阈值，返回各越限项的描述
+#[must_use]
+pub fn breaches(config: &NotifyConfig, summary: &RunSummary) -> Vec<String> {
+    let mut breaches = Vec::new();
+    if let Some(max) = config.max_error_ratio {
+        let ratio = summary.error_ratio();
+        if ratio > max {
+            breaches.push(format!(
+                "解析错误率 {:.2}% 超过阈值 {:.2}%",
+                ratio * 100.0,
+                max * 100.0
+            ));
+        }
+    }
+    if let (Some(max), Some(slow)) =
+        (config.max_slow_queries, summary.slow_queries)
+    {
+        if slow > max {
+            breaches.push(format!(
+                "慢语句（≥{}ms）{slow} 条，超过阈值 {max}",
+                config.slow_query_ms
+            ));
+        }
+    }
+    breaches
+}
+
+/// 生成通知消息体；未越限且未配置 `on_complete` 时返回 `None`
+#[must_use]
+pub fn payload(
+    config: &NotifyConfig,
+    summary: &RunSummary,
+) -> Option<serde_json::Value> {
+    let breaches = breaches(config, summary);
+    if breaches.is_empty() && !config.on_complete {
+        return None;
+    }
+    let title = if breaches.is_empty() {
+        "sqllog 分析完成"
+    } else {
+        "sqllog 分析告警"
+    };
+    let mut text = format!(
+        "文件 {}，记录 {}，解析错误 {}",
+        summary.files, summary.records, summary.parse_errors
+    );
+    if let Some(slow) = summary.slow_queries {
+        let _ = write!(text, "，慢语句 {slow}");
+    }
+    for breach in &breaches {
+        let _ = write!(text, "\n- {breach}");
+    }
+
+    let markdown = format!("### {title}\n{text}");
+    Some(match config.flavor {
+        WebhookFlavor::Generic => serde_json::json!({
+            "title": title,
+            "text": text,
+            "summary": summary,
+            "breaches": breaches,
+        }),
+        WebhookFlavor::DingTalk => serde_json::json!({
+            "msgtype": "markdown",
+            "markdown": { "title": title, "text": markdown },
+        }),
+        WebhookFlavor::WeCom => serde_json::json!({
+            "msgtype": "markdown",
+            "markdown": { "content": markdown },
+        }),
+        WebhookFlavor::Slack => serde_json::json!({
+            "text": format!("*{title}*\n{text}"),
+        }),
+    })
+}
+
+/// 把消息体 POST 到 Webhook，给出 `idempotency_key` 时作为
+/// `Idempotency-Key` 请求头发送
+///
+/// # Errors
+/// 当 `curl` 无法启动、请求失败或返回非 2xx 状态时返回错误
+#[cfg(feature = "notify")]
+pub fn send(
+    config: &NotifyConfig,
+    body: &serde_json::Value,
+    idempotency_key: Option<&str>,
+) -> anyhow::Result<()> {
+    use anyhow::{Context, bail};
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let mut command = Command::new("curl");
+    command
+        .args(["-sS", "--fail", "-X", "POST", "-m"])
+        .arg(config.timeout_secs.to_string())
+        .args(["-H", "Content-Type: application/json"]);
+    if let Some(key) = idempotency_key {
+        command.arg("-H").arg(format!("Idempotency-Key: {key}"));
+    }
+    if config.retries > 0 {
+        command.arg("--retry").arg(config.retries.to_string());
+    }
+    let mut child = command
+        .args(["--data-binary", "@-"])
+        // 以 --url 传入，地址以 - 开头时也不会被当作选项
+        .arg("--url")
+        .arg(&config.webhook_url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("无法启动 curl 发送通知（notify 特性需要 PATH 中有 curl）")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(body.to_string().as_bytes())
+            .context("写入通知内容失败")?;
+    }
+    let output = child.wait_with_output().context("等待 curl 结束失败")?;
+    if !output.status.success() {
+        bail!(
+            "发送通知失败（{}）: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
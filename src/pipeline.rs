@@ -0,0 +1,1000 @@
+//! 多阶段处理管道 - 可组合的记录处理流程
+//!
+//! 将「解析 → 导出」流程泛化为一组按顺序连接的阶段（Stage）。过滤、脱敏、
+//! 补充字段、去重以及写库等操作都可以实现为阶段，由使用者自由组合与排序。
+//!
+//! ## 执行模型
+//!
+//! ```text
+//! 数据源 ──chan──▶ 阶段 1 ──chan──▶ 阶段 2 ──chan──▶ ... ──▶ 阶段 N
+//! (解析线程)      (独立线程)       (独立线程)               (独立线程)
+//! ```
+//!
+//! - 每个阶段运行在独立线程上，阶段之间通过有界通道传递记录批次，
+//!   通道容量即背压上限
+//! - 阶段签名为 `FnMut(&mut Vec<Sqllog>) -> Result<()>`，可原地增删改记录；
+//!   处理后为空的批次不会继续向下游发送
+//! - 任一阶段返回错误时管道停止：上游在发送失败后退出，下游在通道关闭后退出，
+//!   `run` 返回第一个失败阶段的错误；启用 `database` 特性时，因磁盘空间不足
+//!   失败返回 [`DiskFullError`](crate::database::DiskFullError)，其中
+//!   `exporter` 为失败阶段的名称
+//! - 阶段闭包只需满足 `Send`，可以借用调用方的数据（例如 `&mut DuckDbProvider`）
+//! - 设置内存上限（[`Pipeline::with_memory_limit`]）后，数据源按记录的估算内存
+//!   记账：在途批次（通道中与各阶段处理中）合计将超过上限时，数据源阻塞等待
+//!   下游处理完成；单个批次就超过上限时管道立即失败，提示减小 `chunk_size`
+//! - 注册批次观察者（[`Pipeline::on_batch`]）后，数据源每发出一个批次都会以
+//!   [`BatchEvent`] 通知观察者（记录数、来源文件、在途批次数、已运行时长），
+//!   便于嵌入方实现自定义的进度与监控
+//! - 设置事件通道（[`Pipeline::with_events`]）后，管道把文件开始/结束、批次
+//!   解析完成、批次被最后一个阶段处理完以及失败以 [`PipelineEvent`] 发送到
+//!   通道，GUI 等嵌入方可在其他线程接收并展示进度；接收端关闭后不再发送
+//! - 每个阶段的输入队列容量即该阶段的在途批次上限，可用
+//!   [`Pipeline::stage_with_capacity`] 为单个阶段（如较慢的导出器）单独设置。
+//!   上游发现某阶段的输入队列已满时，首次记录一条警告（阶段名称、队列深度、
+//!   最近一次成功处理批次的时间），运行结束后在 [`StageStats`] 中给出队列
+//!   峰值与队列满的次数，并为出现背压的阶段再记录一条汇总警告
+//! - 单线程模式（[`Pipeline::single_threaded`]）不创建任何线程与通道：数据源
+//!   每产生一个批次，就在调用线程中依次执行全部阶段，处理完再读取下一批。
+//!   输出与多线程模式一致，执行顺序完全确定，panic 时的调用栈只有一条，
+//!   便于调试；统计中的队列与阻塞相关字段为 0
+//!
+//! ## 使用示例
+//!
+//! ```rust
+//! use sqllog_analysis::pipeline::{Pipeline, stages};
+//! use sqllog_analysis::sqllog::{ExecTimeMs, Sqllog};
+//!
+//! let mut exported = 0usize;
+//! let stats = Pipeline::new()
+//!     .stage("slow_only", stages::filter(|r| r.execute_time >= Some(ExecTimeMs::new(100))))
+//!     .stage("export", |batch| {
+//!         exported += batch.len();
+//!         Ok(())
+//!     })
+//!     .run(vec![vec![
+//!         Sqllog { execute_time: Some(ExecTimeMs::new(5)), ..Default::default() },
+//!         Sqllog { execute_time: Some(ExecTimeMs::new(500)), ..Default::default() },
+//!     ]])
+//!     .unwrap();
+//!
+//! assert_eq!(exported, 1);
+//! assert_eq!(stats.stages[0].records_out, 1);
+//! ```
+
+use crate::sqllog::{ParseOptions, Sqllog};
+use anyhow::{Result, anyhow};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{
+    Receiver, Sender, SyncSender, TrySendError, sync_channel,
+};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 阶段之间默认的通道容量（批次数）
+pub const DEFAULT_CHANNEL_CAPACITY: usize = 4;
+
+type StageFn<'a> = Box<dyn FnMut(&mut Vec<Sqllog>) -> Result<()> + Send + 'a>;
+
+type BatchObserver<'a> = Box<dyn FnMut(&BatchEvent<'_>) + Send + 'a>;
+
+/// 数据源发出一个批次时传给观察者的事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchEvent<'p> {
+    /// 批次序号（从 0 开始）
+    pub index: usize,
+    /// 批次中的记录数
+    pub records: usize,
+    /// 来源文件（仅 `run_file`）
+    pub source: Option<&'p Path>,
+    /// 已发出但尚未处理完的批次数（含本批次）
+    pub queue_depth: usize,
+    /// 管道开始运行以来的耗时
+    pub elapsed: Duration,
+}
+
+/// 通过事件通道发送的管道事件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PipelineEvent {
+    /// 开始解析文件（仅 `run_file`）
+    FileStarted { path: PathBuf },
+    /// 数据源发出一个批次
+    BatchParsed { index: usize, records: usize },
+    /// 最后一个阶段处理完一个批次，`records` 为处理后保留的记录数
+    BatchExported { index: usize, records: usize },
+    /// 文件处理完成（仅 `run_file`，管道成功结束时发送）
+    FileFinished { path: PathBuf, records: usize, parse_errors: usize },
+    /// 管道失败，`stage` 为失败阶段的名称（数据源失败时为 `None`）
+    Error { stage: Option<String>, message: String },
+}
+
+/// 单个阶段的运行统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StageStats {
+    /// 阶段名称
+    pub name: String,
+    /// 收到的批次数
+    pub batches: usize,
+    /// 收到的记录数
+    pub records_in: usize,
+    /// 处理后输出的记录数
+    pub records_out: usize,
+    /// 执行阶段函数的耗时
+    pub busy: Duration,
+    /// 等待上游批次的耗时
+    pub idle: Duration,
+    /// 向下游发送时因通道已满而阻塞的耗时
+    pub blocked: Duration,
+    /// 输入队列容量（在途批次上限）
+    pub capacity: usize,
+    /// 输入队列中等待处理的批次数峰值
+    pub max_queue_depth: usize,
+    /// 上游发送时发现输入队列已满的次数，大于 0 表示该阶段出现过背压
+    pub queue_full: usize,
+    /// 最近一次成功处理批次时距管道开始运行的时长，`None` 表示未处理过批次
+    pub last_batch_at: Option<Duration>,
+}
+
+impl StageStats {
+    /// 是否出现过背压（处理跟不上上游，输入队列曾被填满）
+    #[must_use]
+    pub const fn backpressured(&self) -> bool {
+        self.queue_full > 0
+    }
+}
+
+/// 管道运行统计
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PipelineStats {
+    /// 数据源产生的记录数
+    pub records_read: usize,
+    /// 数据源上报的解析错误数（仅 `run_file`）
+    pub parse_errors: usize,
+    /// 数据源向第一个阶段发送时阻塞的耗时（含等待内存预算的时间）
+    pub source_blocked: Duration,
+    /// 在途记录估算内存的峰值（字节，仅在设置内存上限时统计）
+    pub peak_memory_bytes: usize,
+    /// 各阶段统计（按阶段顺序）
+    pub stages: Vec<StageStats>,
+}
+
+impl PipelineStats {
+    /// 出现过背压的阶段
+    pub fn backpressured_stages(&self) -> impl Iterator<Item = &StageStats> {
+        self.stages.iter().filter(|stage| stage.backpressured())
+    }
+}
+
+/// 多阶段处理管道
+pub struct Pipeline<'a> {
+    /// 阶段名称、单独设置的输入队列容量与阶段函数
+    stages: Vec<(String, Option<usize>, StageFn<'a>)>,
+    channel_capacity: usize,
+    memory_limit: Option<usize>,
+    observer: Option<BatchObserver<'a>>,
+    events: Option<Sender<PipelineEvent>>,
+    single_threaded: bool,
+}
+
+impl Default for Pipeline<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> Pipeline<'a> {
+    /// 创建空管道
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            stages: Vec::new(),
+            channel_capacity: DEFAULT_CHANNEL_CAPACITY,
+            memory_limit: None,
+            observer: None,
+            events: None,
+            single_threaded: false,
+        }
+    }
+
+    /// 单线程模式：不启动阶段线程，各阶段在调用线程中按批次依次执行
+    ///
+    /// 输出与默认的多线程模式相同，但执行顺序确定、调用栈简单，适合调试。
+    /// 通道容量在此模式下不起作用；内存上限仍会拒绝超过上限的单个批次。
+    #[must_use]
+    pub const fn single_threaded(mut self) -> Self {
+        self.single_threaded = true;
+        self
+    }
+
+    /// 设置阶段之间的通道容量（至少为 1），即未单独设置的阶段的在途批次上限
+    #[must_use]
+    pub fn with_channel_capacity(mut self, capacity: usize) -> Self {
+        self.channel_capacity = capacity.max(1);
+        self
+    }
+
+    /// 设置在途记录的内存上限（字节，至少为 1）
+    #[must_use]
+    pub fn with_memory_limit(mut self, bytes: usize) -> Self {
+        self.memory_limit = Some(bytes.max(1));
+        self
+    }
+
+    /// 注册批次观察者，数据源每成功发出一个批次时在数据源线程中调用
+    ///
+    /// 观察者只读取事件，不影响批次内容；重复注册时以最后一次为准。
+    #[must_use]
+    pub fn on_batch<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&BatchEvent<'_>) + Send + 'a,
+    {
+        self.observer = Some(Box::new(f));
+        self
+    }
+
+    /// 设置事件通道，运行期间的 [`PipelineEvent`] 发送到 `tx`
+    ///
+    /// 事件在数据源与最后一个阶段的线程中发送，不会阻塞管道；接收端关闭后
+    /// 事件被丢弃。重复设置时以最后一次为准。
+    #[must_use]
+    pub fn with_events(mut self, tx: Sender<PipelineEvent>) -> Self {
+        self.events = Some(tx);
+        self
+    }
+
+    /// 在管道末尾追加一个阶段
+    #[must_use]
+    pub fn stage<F>(mut self, name: &str, f: F) -> Self
+    where
+        F: FnMut(&mut Vec<Sqllog>) -> Result<()> + Send + 'a,
+    {
+        self.stages.push((name.to_string(), None, Box::new(f)));
+        self
+    }
+
+    /// 在管道末尾追加一个阶段，并单独设置其输入队列容量（至少为 1）
+    ///
+    /// 容量即该阶段的在途批次上限：队列满时上游阻塞，而不是继续在内存中
+    /// 堆积批次。适合限制较慢的导出器。
+    #[must_use]
+    pub fn stage_with_capacity<F>(
+        mut self,
+        name: &str,
+        capacity: usize,
+        f: F,
+    ) -> Self
+    where
+        F: FnMut(&mut Vec<Sqllog>) -> Result<()> + Send + 'a,
+    {
+        self.stages.push((
+            name.to_string(),
+            Some(capacity.max(1)),
+            Box::new(f),
+        ));
+        self
+    }
+
+    /// 当前阶段数
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// 是否没有任何阶段
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// 以给定的记录批次为数据源运行管道
+    ///
+    /// # Errors
+    /// 当任一阶段返回错误或阶段线程 panic 时返回错误
+    pub fn run<I>(self, batches: I) -> Result<PipelineStats>
+    where
+        I: IntoIterator<Item = Vec<Sqllog>>,
+    {
+        self.run_with_source(None, |tx| {
+            let mut read = 0usize;
+            for batch in batches {
+                read += batch.len();
+                if !tx.send(batch) {
+                    break;
+                }
+            }
+            Ok((read, 0))
+        })
+    }
+
+    /// 以日志文件为数据源运行管道，解析在调用线程中进行
+    ///
+    /// 解析错误只计入统计，不会中断管道；`options.chunk_size` 决定
+    /// 每个批次的大小（0 表示整个文件一个批次）。
+    ///
+    /// # Errors
+    /// 当文件无法读取、任一阶段返回错误或阶段线程 panic 时返回错误
+    pub fn run_file(
+        self,
+        path: &Path,
+        options: &ParseOptions,
+    ) -> Result<PipelineStats> {
+        self.run_with_source(Some(path), |tx| {
+            let mut read = 0usize;
+            let mut errors = 0usize;
+            let mut closed = false;
+            Sqllog::parse_with_options(
+                path,
+                options,
+                |records| {
+                    read += records.len();
+                    if !closed && !tx.send(records.to_vec()) {
+                        closed = true;
+                    }
+                },
+                |errs| errors += errs.len(),
+            )?;
+            Ok((read, errors))
+        })
+    }
+
+    /// 启动各阶段线程并在当前线程运行数据源
+    fn run_with_source<S>(
+        self,
+        path: Option<&Path>,
+        source: S,
+    ) -> Result<PipelineStats>
+    where
+        S: FnOnce(&mut TimedSender<'_>) -> Result<(usize, usize)>,
+    {
+        let capacity = self.channel_capacity;
+        let budget = self.memory_limit.map(MemoryBudget::new);
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let observer = self.observer;
+        let events = self.events;
+        if let (Some(path), Some(tx)) = (path, &events) {
+            let _ = tx
+                .send(PipelineEvent::FileStarted { path: path.to_path_buf() });
+        }
+
+        let started = Instant::now();
+        if self.single_threaded {
+            let mut source_tx = TimedSender::inline(InlineStages {
+                stages: self
+                    .stages
+                    .into_iter()
+                    .map(|(name, _, stage)| {
+                        (stage, StageStats { name, ..Default::default() })
+                    })
+                    .collect(),
+                started,
+                exported: events.clone(),
+                error: None,
+            });
+            source_tx.budget = budget.clone();
+            source_tx.observer =
+                observer.map(|notify| SourceObserver { notify, path, started });
+            source_tx.events = events.clone();
+            let source_result = source(&mut source_tx);
+            let mut failure = source_tx.error.take().map(|e| (None, e));
+            let mut stats = PipelineStats {
+                peak_memory_bytes: budget.map_or(0, |b| b.peak()),
+                ..Default::default()
+            };
+            if let Downstream::Inline(inline) = source_tx.downstream {
+                if let Some((name, e)) = inline.error {
+                    failure.get_or_insert((Some(name), e));
+                }
+                stats.stages =
+                    inline.stages.into_iter().map(|(_, stats)| stats).collect();
+            }
+            return conclude(source_result, failure, stats, path, &events);
+        }
+
+        // 各阶段输入队列的监控状态，由上游发送端与阶段线程共享
+        let monitors: Vec<Arc<QueueMonitor>> = self
+            .stages
+            .iter()
+            .map(|(name, stage_capacity, _)| {
+                Arc::new(QueueMonitor::new(
+                    name,
+                    stage_capacity.unwrap_or(capacity),
+                    started,
+                ))
+            })
+            .collect();
+
+        thread::scope(|scope| {
+            let source_capacity =
+                monitors.first().map_or(capacity, |m| m.capacity);
+            let (source_tx, source_rx) = sync_channel(source_capacity);
+            let mut input = Some(source_rx);
+            let count = self.stages.len();
+            let mut handles = Vec::with_capacity(count);
+
+            for (index, (name, _, stage)) in self.stages.into_iter().enumerate()
+            {
+                let Some(rx) = input.take() else { break };
+                let output = monitors.get(index + 1).map(|next| {
+                    let (tx, next_rx) = sync_channel(next.capacity);
+                    input = Some(next_rx);
+                    (tx, Arc::clone(next))
+                });
+                let monitor = Arc::clone(&monitors[index]);
+                let budget = budget.clone();
+                let in_flight = Arc::clone(&in_flight);
+                let exported =
+                    if output.is_none() { events.clone() } else { None };
+                handles.push(scope.spawn(move || {
+                    run_stage(
+                        name,
+                        stage,
+                        (&rx, &monitor),
+                        output,
+                        budget.as_ref(),
+                        &in_flight,
+                        exported.as_ref(),
+                    )
+                }));
+            }
+            // 没有阶段时直接关闭数据源通道，数据源在首次发送失败后退出
+            drop(input);
+
+            let mut source_tx =
+                TimedSender::new(source_tx, monitors.first().cloned());
+            source_tx.budget = budget.clone();
+            source_tx.in_flight = Some(Arc::clone(&in_flight));
+            source_tx.observer =
+                observer.map(|notify| SourceObserver { notify, path, started });
+            source_tx.events = events.clone();
+            let source_result = source(&mut source_tx);
+            let source_blocked = source_tx.blocked;
+            let budget_error = source_tx.error.take();
+            drop(source_tx);
+
+            let mut stats = PipelineStats::default();
+            let mut first_error = None;
+            for handle in handles {
+                match handle.join() {
+                    Ok((stage_stats, result)) => {
+                        if let Err(e) = result {
+                            first_error.get_or_insert((
+                                Some(stage_stats.name.clone()),
+                                e,
+                            ));
+                        }
+                        stats.stages.push(stage_stats);
+                    }
+                    Err(_) => {
+                        first_error.get_or_insert((
+                            None,
+                            anyhow!("管道阶段线程 panic"),
+                        ));
+                    }
+                }
+            }
+
+            stats.source_blocked = source_blocked;
+            stats.peak_memory_bytes = budget.map_or(0, |b| b.peak());
+            let failure = budget_error.map(|e| (None, e)).or(first_error);
+            conclude(source_result, failure, stats, path, &events)
+        })
+    }
+}
+
+/// 汇总数据源结果与首个失败，补全统计并发送结束或失败事件
+fn conclude(
+    source_result: Result<(usize, usize)>,
+    failure: Option<(Option<String>, anyhow::Error)>,
+    mut stats: PipelineStats,
+    path: Option<&Path>,
+    events: &Option<Sender<PipelineEvent>>,
+) -> Result<PipelineStats> {
+    let emit = |event| {
+        if let Some(tx) = events {
+            let _ = tx.send(event);
+        }
+    };
+    let result = source_result
+        .map_err(|e| (None, e))
+        .and_then(|counts| failure.map_or(Ok(counts), Err));
+    let (read, parse_errors) = match result {
+        Ok(counts) => counts,
+        Err((stage, e)) => {
+            emit(PipelineEvent::Error { stage, message: format!("{e:#}") });
+            return Err(e);
+        }
+    };
+    stats.records_read = read;
+    stats.parse_errors = parse_errors;
+    for stage in stats.backpressured_stages() {
+        log::warn!(
+            "管道阶段 {} 出现背压：输入队列满 {} 次，峰值 {}/{} 个批次，最近一次成功处理批次在 {}",
+            stage.name,
+            stage.queue_full,
+            stage.max_queue_depth,
+            stage.capacity,
+            describe_last_batch(stage.last_batch_at)
+        );
+    }
+    if let Some(path) = path {
+        emit(PipelineEvent::FileFinished {
+            path: path.to_path_buf(),
+            records: read,
+            parse_errors,
+        });
+    }
+    Ok(stats)
+}
+
+/// 阶段之间传递的批次，附带数据源为其预留的内存字节数与批次序号
+struct Batch {
+    records: Vec<Sqllog>,
+    reserved: usize,
+    index: usize,
+}
+
+/// 包装阶段返回的错误：磁盘空间不足时转换为 `DiskFullError`（需要 `database` 特性）
+fn stage_error(name: &str, e: anyhow::Error) -> anyhow::Error {
+    #[cfg(feature = "database")]
+    if crate::database::is_disk_full(&e) {
+        return crate::database::DiskFullError::new(name.to_string(), None, &e)
+            .into();
+    }
+    e.context(format!("管道阶段 {name} 失败"))
+}
+
+/// 阶段线程主循环
+fn run_stage(
+    name: String,
+    mut stage: StageFn<'_>,
+    (input, monitor): (&Receiver<Batch>, &QueueMonitor),
+    output: Option<(SyncSender<Batch>, Arc<QueueMonitor>)>,
+    budget: Option<&MemoryBudget>,
+    in_flight: &AtomicUsize,
+    exported: Option<&Sender<PipelineEvent>>,
+) -> (StageStats, Result<()>) {
+    let mut stats =
+        StageStats { name, capacity: monitor.capacity, ..Default::default() };
+    let mut output = output.map(|(tx, next)| TimedSender::new(tx, Some(next)));
+    // 批次处理结束（被最后一个阶段处理完、被清空或阶段失败）
+    let release = |reserved| {
+        in_flight.fetch_sub(1, Ordering::Relaxed);
+        if let Some(budget) = budget {
+            budget.release(reserved);
+        }
+    };
+
+    loop {
+        let waiting = Instant::now();
+        let Ok(mut batch) = input.recv() else { break };
+        monitor.dequeue();
+        stats.idle += waiting.elapsed();
+        stats.batches += 1;
+        stats.records_in += batch.records.len();
+
+        let started = Instant::now();
+        let result = stage(&mut batch.records);
+        stats.busy += started.elapsed();
+        if let Err(e) = result {
+            release(batch.reserved);
+            // 通道中剩余的批次不会再被处理，唤醒等待预算的数据源使其退出
+            if let Some(budget) = budget {
+                budget.close();
+            }
+            let e = stage_error(&stats.name, e);
+            monitor.fill_stats(&mut stats);
+            return (stats, Err(e));
+        }
+        monitor.batch_done();
+        stats.records_out += batch.records.len();
+        if let Some(tx) = exported {
+            let _ = tx.send(PipelineEvent::BatchExported {
+                index: batch.index,
+                records: batch.records.len(),
+            });
+        }
+
+        match &mut output {
+            Some(tx) if !batch.records.is_empty() => {
+                let sent = tx.forward(batch);
+                stats.blocked = tx.blocked;
+                if !sent {
+                    // 下游已退出（通常是下游阶段失败），停止处理
+                    break;
+                }
+            }
+            // 最后一个阶段处理完或批次被清空，批次内存随之释放
+            _ => release(batch.reserved),
+        }
+    }
+
+    monitor.fill_stats(&mut stats);
+    (stats, Ok(()))
+}
+
+/// 阶段输入队列的监控状态
+struct QueueMonitor {
+    name: String,
+    capacity: usize,
+    started: Instant,
+    /// 已发送但尚未被阶段取出的批次数
+    depth: AtomicUsize,
+    max_depth: AtomicUsize,
+    full: AtomicUsize,
+    /// 最近一次成功处理批次时距开始的毫秒数加 1，0 表示尚未处理过批次
+    last_batch_ms: AtomicU64,
+}
+
+impl QueueMonitor {
+    fn new(name: &str, capacity: usize, started: Instant) -> Self {
+        Self {
+            name: name.to_string(),
+            capacity,
+            started,
+            depth: AtomicUsize::new(0),
+            max_depth: AtomicUsize::new(0),
+            full: AtomicUsize::new(0),
+            last_batch_ms: AtomicU64::new(0),
+        }
+    }
+
+    fn enqueue(&self) {
+        let depth = self.depth.fetch_add(1, Ordering::Relaxed) + 1;
+        self.max_depth.fetch_max(depth.min(self.capacity), Ordering::Relaxed);
+    }
+
+    fn dequeue(&self) {
+        self.depth.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// 上游发现队列已满，首次出现时记录警告
+    fn queue_full(&self) {
+        if self.full.fetch_add(1, Ordering::Relaxed) == 0 {
+            log::warn!(
+                "管道阶段 {} 处理缓慢，输入队列已满（{}/{} 个批次），上游开始阻塞；最近一次成功处理批次在 {}",
+                self.name,
+                self.capacity,
+                self.capacity,
+                describe_last_batch(self.last_batch_at())
+            );
+        }
+    }
+
+    fn batch_done(&self) {
+        let ms = u64::try_from(self.started.elapsed().as_millis())
+            .unwrap_or(u64::MAX - 1);
+        self.last_batch_ms.store(ms + 1, Ordering::Relaxed);
+    }
+
+    fn last_batch_at(&self) -> Option<Duration> {
+        match self.last_batch_ms.load(Ordering::Relaxed) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms - 1)),
+        }
+    }
+
+    fn fill_stats(&self, stats: &mut StageStats) {
+        stats.max_queue_depth = self.max_depth.load(Ordering::Relaxed);
+        stats.queue_full = self.full.load(Ordering::Relaxed);
+        stats.last_batch_at = self.last_batch_at();
+    }
+}
+
+/// 最近一次成功处理批次的时间描述
+fn describe_last_batch(at: Option<Duration>) -> String {
+    at.map_or_else(
+        || "尚未处理过批次".to_string(),
+        |at| format!("运行 {:.1}s 时", at.as_secs_f64()),
+    )
+}
+
+/// 数据源侧的批次观察者及其状态
+struct SourceObserver<'o> {
+    notify: BatchObserver<'o>,
+    path: Option<&'o Path>,
+    started: Instant,
+}
+
+/// 单线程模式下在数据源线程中依次执行的阶段
+struct InlineStages<'o> {
+    stages: Vec<(StageFn<'o>, StageStats)>,
+    started: Instant,
+    /// 事件通道，最后一个阶段处理完批次时发送 `BatchExported`
+    exported: Option<Sender<PipelineEvent>>,
+    /// 首个失败的阶段名称与错误
+    error: Option<(String, anyhow::Error)>,
+}
+
+impl InlineStages<'_> {
+    /// 依次执行各阶段；某阶段清空批次后不再执行后续阶段。阶段失败时返回
+    /// `false`
+    fn process(&mut self, mut batch: Batch) -> bool {
+        if self.error.is_some() {
+            return false;
+        }
+        let last = self.stages.len().saturating_sub(1);
+        for (index, (stage, stats)) in self.stages.iter_mut().enumerate() {
+            stats.batches += 1;
+            stats.records_in += batch.records.len();
+            let started = Instant::now();
+            let result = stage(&mut batch.records);
+            stats.busy += started.elapsed();
+            if let Err(e) = result {
+                let e = stage_error(&stats.name, e);
+                self.error = Some((stats.name.clone(), e));
+                return false;
+            }
+            stats.records_out += batch.records.len();
+            stats.last_batch_at = Some(self.started.elapsed());
+            if index == last {
+                if let Some(tx) = &self.exported {
+                    let _ = tx.send(PipelineEvent::BatchExported {
+                        index: batch.index,
+                        records: batch.records.len(),
+                    });
+                }
+            }
+            if batch.records.is_empty() {
+                break;
+            }
+        }
+        true
+    }
+}
+
+/// 发送端的下游：阶段线程的输入通道，或单线程模式下直接执行的阶段
+enum Downstream<'o> {
+    Channel(SyncSender<Batch>),
+    Inline(InlineStages<'o>),
+}
+
+/// 记录发送阻塞耗时的通道发送端
+struct TimedSender<'o> {
+    downstream: Downstream<'o>,
+    blocked: Duration,
+    /// 数据源的内存预算（阶段之间转发时为 `None`，沿用数据源的预留）
+    budget: Option<MemoryBudget>,
+    /// 内存预算导致的失败
+    error: Option<anyhow::Error>,
+    /// 在途批次计数（仅数据源，由各阶段在批次处理结束时递减）
+    in_flight: Option<Arc<AtomicUsize>>,
+    /// 批次观察者（仅数据源）
+    observer: Option<SourceObserver<'o>>,
+    /// 事件通道（仅数据源）
+    events: Option<Sender<PipelineEvent>>,
+    /// 已发出的批次数（仅数据源）
+    batches: usize,
+    /// 接收端阶段的输入队列监控
+    monitor: Option<Arc<QueueMonitor>>,
+}
+
+impl<'o> TimedSender<'o> {
+    const fn new(
+        tx: SyncSender<Batch>,
+        monitor: Option<Arc<QueueMonitor>>,
+    ) -> Self {
+        Self::with_downstream(Downstream::Channel(tx), monitor)
+    }
+
+    /// 单线程模式的数据源发送端
+    const fn inline(stages: InlineStages<'o>) -> Self {
+        Self::with_downstream(Downstream::Inline(stages), None)
+    }
+
+    const fn with_downstream(
+        downstream: Downstream<'o>,
+        monitor: Option<Arc<QueueMonitor>>,
+    ) -> Self {
+        Self {
+            downstream,
+            blocked: Duration::ZERO,
+            budget: None,
+            error: None,
+            in_flight: None,
+            observer: None,
+            events: None,
+            batches: 0,
+            monitor,
+        }
+    }
+
+    /// 发送一个批次；接收端已关闭或超出内存上限时返回 `false`
+    fn send(&mut self, records: Vec<Sqllog>) -> bool {
+        let mut reserved = 0;
+        if let Some(budget) = &self.budget {
+            reserved = records.iter().map(Sqllog::estimated_bytes).sum();
+            let started = Instant::now();
+            let acquired = budget.acquire(reserved);
+            self.blocked += started.elapsed();
+            match acquired {
+                Ok(true) => {}
+                Ok(false) => return false,
+                Err(e) => {
+                    budget.close();
+                    self.error = Some(e);
+                    return false;
+                }
+            }
+        }
+        let len = records.len();
+        // 先计入在途批次，避免下游先处理完导致计数短暂为负
+        let queue_depth = self
+            .in_flight
+            .as_ref()
+            .map_or(0, |n| n.fetch_add(1, Ordering::Relaxed) + 1);
+        let index = self.batches;
+        // 先于转发发送，保证同一批次的 BatchParsed 在 BatchExported 之前
+        if let Some(tx) = &self.events {
+            let _ = tx.send(PipelineEvent::BatchParsed { index, records: len });
+        }
+        let delivered = self.forward(Batch { records, reserved, index });
+        if let (Downstream::Inline(_), Some(budget)) =
+            (&self.downstream, &self.budget)
+        {
+            // 单线程模式下批次已处理完，内存随之释放
+            budget.release(reserved);
+        }
+        if !delivered {
+            if let Some(n) = &self.in_flight {
+                n.fetch_sub(1, Ordering::Relaxed);
+            }
+            return false;
+        }
+        self.batches += 1;
+        if let Some(observer) = &mut self.observer {
+            (observer.notify)(&BatchEvent {
+                index,
+                records: len,
+                source: observer.path,
+                queue_depth,
+                elapsed: observer.started.elapsed(),
+            });
+        }
+        true
+    }
+
+    /// 转发上游已预留内存的批次；单线程模式下直接执行各阶段
+    fn forward(&mut self, batch: Batch) -> bool {
+        let started = Instant::now();
+        if let Some(monitor) = &self.monitor {
+            monitor.enqueue();
+        }
+        let sent = match &mut self.downstream {
+            Downstream::Channel(tx) => match tx.try_send(batch) {
+                Ok(()) => true,
+                Err(TrySendError::Full(batch)) => {
+                    if let Some(monitor) = &self.monitor {
+                        monitor.queue_full();
+                    }
+                    tx.send(batch).is_ok()
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+            Downstream::Inline(stages) => stages.process(batch),
+        };
+        if !sent {
+            if let Some(monitor) = &self.monitor {
+                monitor.dequeue();
+            }
+        }
+        self.blocked += started.elapsed();
+        sent
+    }
+}
+
+/// 在途批次的内存预算
+#[derive(Clone)]
+struct MemoryBudget {
+    inner: Arc<(Mutex<BudgetState>, Condvar)>,
+    limit: usize,
+}
+
+#[derive(Default)]
+struct BudgetState {
+    used: usize,
+    peak: usize,
+    /// 管道已失败，不再等待释放
+    closed: bool,
+}
+
+impl MemoryBudget {
+    fn new(limit: usize) -> Self {
+        Self { inner: Arc::default(), limit }
+    }
+
+    /// 预留 `bytes` 字节，在途内存不足时阻塞等待下游释放
+    ///
+    /// 管道已失败时返回 `Ok(false)`；单个批次超过上限时返回错误。
+    fn acquire(&self, bytes: usize) -> Result<bool> {
+        if bytes > self.limit {
+            return Err(anyhow!(
+                "单个批次约 {bytes} 字节，超过内存上限 max_memory_bytes = {}；请减小 sqllog.chunk_size 或提高上限",
+                self.limit
+            ));
+        }
+        let (state, released) = &*self.inner;
+        let mut state =
+            state.lock().map_err(|_| anyhow!("内存预算锁已损坏"))?;
+        while !state.closed && state.used + bytes > self.limit {
+            state = released
+                .wait(state)
+                .map_err(|_| anyhow!("内存预算锁已损坏"))?;
+        }
+        if state.closed {
+            return Ok(false);
+        }
+        state.used += bytes;
+        state.peak = state.peak.max(state.used);
+        Ok(true)
+    }
+
+    fn release(&self, bytes: usize) {
+        let (state, released) = &*self.inner;
+        if let Ok(mut state) = state.lock() {
+            state.used = state.used.saturating_sub(bytes);
+        }
+        released.notify_all();
+    }
+
+    fn close(&self) {
+        let (state, released) = &*self.inner;
+        if let Ok(mut state) = state.lock() {
+            state.closed = true;
+        }
+        released.notify_all();
+    }
+
+    fn peak(&self) -> usize {
+        self.inner.0.lock().map_or(0, |state| state.peak)
+    }
+}
+
+/// 常用阶段构造函数
+pub mod stages {
+    use crate::sqllog::Sqllog;
+    use anyhow::Result;
+    use std::collections::HashSet;
+    use std::hash::Hash;
+
+    /// 过滤阶段：仅保留满足条件的记录
+    pub fn filter<P>(
+        mut predicate: P,
+    ) -> impl FnMut(&mut Vec<Sqllog>) -> Result<()> + Send
+    where
+        P: FnMut(&Sqllog) -> bool + Send,
+    {
+        move |batch| {
+            batch.retain(|record| predicate(record));
+            Ok(())
+        }
+    }
+
+    /// 逐条修改阶段：适用于脱敏、补充字段等原地改写
+    pub fn map_records<M>(
+        mut f: M,
+    ) -> impl FnMut(&mut Vec<Sqllog>) -> Result<()> + Send
+    where
+        M: FnMut(&mut Sqllog) + Send,
+    {
+        move |batch| {
+            batch.iter_mut().for_each(&mut f);
+            Ok(())
+        }
+    }
+
+    /// 去重阶段：按键去重，跨批次保留首次出现的记录
+    pub fn dedup_by<K, F>(
+        mut key: F,
+    ) -> impl FnMut(&mut Vec<Sqllog>) -> Result<()> + Send
+    where
+        K: Eq + Hash + Send,
+        F: FnMut(&Sqllog) -> K + Send,
+    {
+        let mut seen = HashSet::new();
+        move |batch| {
+            batch.retain(|record| seen.insert(key(record)));
+            Ok(())
+        }
+    }
+}
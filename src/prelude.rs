@@ -0,0 +1,33 @@
+//! 常用类型与函数的统一导入
+//!
+//! ```rust,no_run
+//! use sqllog_analysis::prelude::*;
+//!
+//! // 解析整个文件
+//! let records: Vec<Sqllog> = parse_file("dmsql_example.log")?;
+//! println!("{} 条记录", records.len());
+//!
+//! // 一步转换为 CSV（需要 `database` 特性）
+//! # #[cfg(feature = "database")]
+//! # {
+//! let stats = export_file("dmsql_example.log", ExportFormat::Csv, "out.csv")?;
+//! println!("导出 {} 条，跳过 {} 段", stats.records_exported, stats.parse_errors);
+//! # }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+pub use crate::analysis::{AnalysisEngine, Analyzer, Report};
+#[cfg(feature = "database")]
+pub use crate::config::RuntimeConfig;
+pub use crate::convenience::parse_file;
+#[cfg(feature = "database")]
+pub use crate::convenience::{
+    ExportStats, SourceStats, export_file, export_files,
+};
+#[cfg(feature = "database")]
+pub use crate::database::{DatabaseProvider, DuckDbProvider, ExportFormat};
+#[cfg(feature = "concurrent")]
+pub use crate::pipeline::{Pipeline, PipelineEvent};
+pub use crate::sqllog::{
+    ExecId, ExecTimeMs, ParseOptions, RowCount, Sqllog, SqllogError,
+};
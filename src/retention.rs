@@ -0,0 +1,231 @@
+//! 输出保留策略 - 按时间与运行次数清理导出产物
+//!
+//! 定时任务反复导出时，输出目录会不断累积。[`cleanup`] 扫描目录（含子目录），
+//! 以导出清单（`export.manifest_path` 写出的 JSON）为单位识别每一次运行：
+//! 清单与其中列出的产物一起保留或一起删除。子目录视为不同的实例
+//! （如 `out/<实例>/...`），运行次数按实例分别计算。
+//!
+//! - `max_age`：早于该时长的运行被删除
+//! - `keep_last`：每个实例保留最近的若干次运行
+//!
+//! 两条规则同时给出时，命中任一规则（足够新，或在最近的若干次之内）的运行
+//! 都会保留。仍被保留的清单引用的产物不会删除。不属于任何清单的导出文件
+//! （按扩展名识别）只按 `max_age` 清理。时间以文件的修改时间为准。
+
+use crate::database::ExportFormat;
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// 保留策略
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    /// 早于该时长的运行与导出文件被删除
+    pub max_age: Option<Duration>,
+    /// 每个实例保留最近的运行次数
+    pub keep_last: Option<usize>,
+    /// 只列出将被删除的文件，不实际删除
+    pub dry_run: bool,
+}
+
+/// 清理结果
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct CleanupReport {
+    /// 保留的运行数
+    pub runs_kept: usize,
+    /// 删除的运行数
+    pub runs_removed: usize,
+    /// 删除（或试运行时将删除）的文件
+    pub removed: Vec<PathBuf>,
+    /// 释放的字节数
+    pub bytes_freed: u64,
+    /// 是否为试运行
+    pub dry_run: bool,
+}
+
+impl fmt::Display for CleanupReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verb = if self.dry_run { "将删除" } else { "已删除" };
+        for path in &self.removed {
+            writeln!(f, "{verb}: {}", path.display())?;
+        }
+        writeln!(
+            f,
+            "运行: 保留 {}，删除 {}；文件 {} 个，共 {} 字节",
+            self.runs_kept,
+            self.runs_removed,
+            self.removed.len(),
+            self.bytes_freed
+        )
+    }
+}
+
+/// 清单中清理所需的字段
+#[derive(Deserialize)]
+struct ManifestFiles {
+    artifacts: Vec<ManifestPath>,
+}
+
+#[derive(Deserialize)]
+struct ManifestPath {
+    path: String,
+}
+
+/// 一次运行：清单及其产物
+struct Run {
+    manifest: PathBuf,
+    modified: SystemTime,
+    artifacts: Vec<PathBuf>,
+}
+
+/// 按保留策略清理 `root` 下的导出产物
+///
+/// # Errors
+/// 当策略未给出任何规则、目录无法读取或文件删除失败时返回错误
+pub fn cleanup(
+    root: &Path,
+    policy: &RetentionPolicy,
+    now: SystemTime,
+) -> Result<CleanupReport> {
+    if policy.max_age.is_none() && policy.keep_last.is_none() {
+        bail!("保留策略至少需要 max_age 或 keep_last 之一");
+    }
+    let mut files = Vec::new();
+    collect_files(root, &mut files)?;
+
+    let mut instances: BTreeMap<PathBuf, Vec<Run>> = BTreeMap::new();
+    let mut loose = Vec::new();
+    for path in files {
+        if let Some(artifacts) = read_manifest(&path) {
+            let instance = path
+                .parent()
+                .and_then(|p| p.strip_prefix(root).ok())
+                .map(Path::to_path_buf)
+                .unwrap_or_default();
+            instances.entry(instance).or_default().push(Run {
+                modified: modified(&path)?,
+                manifest: canonical(path),
+                artifacts: artifacts.into_iter().map(canonical).collect(),
+            });
+        } else if ExportFormat::from_path(&path).is_some() {
+            loose.push(canonical(path));
+        }
+    }
+
+    let expired = |time: SystemTime| {
+        policy.max_age.is_some_and(|max| {
+            now.duration_since(time).is_ok_and(|age| age > max)
+        })
+    };
+    let mut report =
+        CleanupReport { dry_run: policy.dry_run, ..Default::default() };
+    let mut kept_files = HashSet::new();
+    let mut removed_runs = Vec::new();
+    for runs in instances.values_mut() {
+        runs.sort_by_key(|run| std::cmp::Reverse(run.modified));
+        for (index, run) in runs.drain(..).enumerate() {
+            let recent = policy.keep_last.is_some_and(|k| index < k);
+            let young = policy.max_age.is_some() && !expired(run.modified);
+            if recent || young {
+                report.runs_kept += 1;
+                kept_files.insert(run.manifest);
+                kept_files.extend(run.artifacts);
+            } else {
+                report.runs_removed += 1;
+                removed_runs.push(run);
+            }
+        }
+    }
+
+    let mut doomed = Vec::new();
+    for run in removed_runs {
+        doomed.extend(run.artifacts);
+        doomed.push(run.manifest);
+    }
+    for path in loose {
+        if !kept_files.contains(&path) && expired(modified(&path)?) {
+            doomed.push(path);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for path in doomed {
+        if kept_files.contains(&path) || !seen.insert(path.clone()) {
+            continue;
+        }
+        // 清单中的产物可能已被手动删除
+        let Ok(metadata) = fs::metadata(&path) else { continue };
+        if !policy.dry_run {
+            fs::remove_file(&path)
+                .with_context(|| format!("无法删除: {}", path.display()))?;
+        }
+        report.bytes_freed += metadata.len();
+        report.removed.push(path);
+    }
+    Ok(report)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("无法读取目录: {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_files(&entry.path(), files)?;
+        } else if file_type.is_file() {
+            files.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+/// 统一路径写法，使清单中的产物路径与扫描到的路径可以比较
+fn canonical(path: PathBuf) -> PathBuf {
+    fs::canonicalize(&path).unwrap_or(path)
+}
+
+fn modified(path: &Path) -> Result<SystemTime> {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("无法读取修改时间: {}", path.display()))
+}
+
+/// 识别导出清单并返回其中的产物路径；不是清单时返回 `None`
+///
+/// 只检查 `.json` 文件开头是否有 `generated_at` 字段，避免完整读取大的 JSON
+/// 导出文件。产物为相对路径且不存在时按相对于清单所在目录解析。
+fn read_manifest(path: &Path) -> Option<Vec<PathBuf>> {
+    if path.extension().and_then(|e| e.to_str()) != Some("json") {
+        return None;
+    }
+    let mut head = [0u8; 256];
+    let n = fs::File::open(path).and_then(|mut f| f.read(&mut head)).ok()?;
+    let head = String::from_utf8_lossy(&head[..n]);
+    if !head.trim_start().starts_with('{') || !head.contains("\"generated_at\"")
+    {
+        return None;
+    }
+    let text = fs::read_to_string(path).ok()?;
+    let manifest: ManifestFiles = serde_json::from_str(&text).ok()?;
+    let base = path.parent().unwrap_or_else(|| Path::new(""));
+    Some(
+        manifest
+            .artifacts
+            .into_iter()
+            .map(|artifact| {
+                let artifact = PathBuf::from(artifact.path);
+                if artifact.is_relative() && !artifact.exists() {
+                    base.join(artifact)
+                } else {
+                    artifact
+                }
+            })
+            .collect(),
+    )
+}
@@ -0,0 +1,42 @@
+// 运行 span - 为文件、批次与导出器记录 tracing span 与指标事件
+//
+// 启用 `tracing` 特性时（命令行程序默认启用），[`enter_span!`] 创建并进入
+// 一个 info 级别的 span，返回的守卫离开作用域时退出；未启用时为空操作，
+// 库本身不依赖 tracing。span 名称统一以 `sqllog.` 开头：
+//
+// - `sqllog.file`：解析并写入一个文件（字段 `path`）
+// - `sqllog.insert_batch`：写入一批记录（字段 `records`）
+// - `sqllog.merge`：把临时库合并到主库
+// - `sqllog.export`：以一种格式导出（字段 `format`）
+//
+// 配合 `otel` 特性经 OTLP 导出后，可在 Jaeger/Tempo 中按阶段查看耗时。
+
+/// 进入一个 span，返回离开作用域时退出 span 的守卫
+macro_rules! enter_span {
+    ($name:literal $(, $($fields:tt)*)?) => {{
+        #[cfg(feature = "tracing")]
+        let guard = tracing::info_span!($name $(, $($fields)*)?).entered();
+        #[cfg(not(feature = "tracing"))]
+        let guard = $crate::spans::NoSpan;
+        guard
+    }};
+}
+
+pub(crate) use enter_span;
+
+/// 未启用 `tracing` 特性时 [`enter_span!`] 返回的空守卫
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct NoSpan;
+
+/// 记录一个文件处理完成的计数指标（`otel` 特性下导出为 OTLP 计数器）
+#[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+pub(crate) fn file_finished(records: usize, parse_errors: usize, bytes: u64) {
+    #[cfg(feature = "tracing")]
+    tracing::info!(
+        monotonic_counter.sqllog_files = 1_u64,
+        monotonic_counter.sqllog_records = records as u64,
+        monotonic_counter.sqllog_parse_errors = parse_errors as u64,
+        monotonic_counter.sqllog_bytes = bytes,
+        "文件处理完成"
+    );
+}
@@ -0,0 +1,116 @@
+//! 解析上下文 - 复用已编译的正则逐段解析
+//!
+//! [`Sqllog::from_line`] 每次调用都要经由全局的惰性静态变量取得已编译的
+//! 正则。自行组织解析流程、逐段调用数百万次时，可以创建一个
+//! [`ParserContext`] 并反复使用：上下文持有日志头与 description 解析所需
+//! 的全部正则，以及空白字段与解析模式，调用时不再经过任何全局查找。
+//!
+//! ```rust
+//! use sqllog_analysis::sqllog::{ParseMode, ParserContext};
+//!
+//! let ctx = ParserContext::new().with_mode(ParseMode::Lenient);
+//! let segments = [
+//!     "2025-09-16 20:02:53.562 (EP[0] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2) [SEL] select 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1.",
+//!     "not a sqllog segment",
+//! ];
+//! let results = ctx.parse_lines(&segments);
+//! assert!(matches!(results[0], Ok(Some(_))));
+//! assert!(results[1].is_err());
+//! ```
+
+use crate::sqllog::header::HeaderPatterns;
+use crate::sqllog::parser::DESC_PATTERN;
+use crate::sqllog::{BlankFields, ParseMode, ParseOptions, SResult, Sqllog};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    /// `Sqllog::from_line` 等关联函数使用的共享上下文
+    static ref SHARED: ParserContext = ParserContext::new();
+}
+
+/// 已编译的解析正则及逐段解析的选项
+///
+/// 克隆开销很小（正则内部共享），可以每个线程持有一份。
+#[derive(Debug, Clone)]
+pub struct ParserContext {
+    pub(crate) header: HeaderPatterns,
+    pub(crate) desc: Regex,
+    blank_fields: BlankFields,
+    mode: ParseMode,
+}
+
+impl ParserContext {
+    /// 编译全部正则，空白字段与解析模式取默认值
+    ///
+    /// # Panics
+    /// 正则均为常量，不会失败
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            header: HeaderPatterns::new(),
+            desc: Regex::new(DESC_PATTERN).unwrap(),
+            blank_fields: BlankFields::default(),
+            mode: ParseMode::default(),
+        }
+    }
+
+    /// 进程内共享的默认上下文（首次调用时编译）
+    #[must_use]
+    pub fn shared() -> &'static Self {
+        &SHARED
+    }
+
+    /// 按文件解析选项中的空白字段与解析模式创建
+    #[must_use]
+    pub fn from_options(options: &ParseOptions) -> Self {
+        Self::new()
+            .with_blank_fields(options.blank_fields)
+            .with_mode(options.mode)
+    }
+
+    /// 设置空白 appname 的规范化方式
+    #[must_use]
+    pub fn with_blank_fields(mut self, blank_fields: BlankFields) -> Self {
+        self.blank_fields = blank_fields;
+        self
+    }
+
+    /// 设置日志头解析模式
+    #[must_use]
+    pub fn with_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 解析单个日志段，语义与 [`Sqllog::from_line_mode`] 相同
+    ///
+    /// # Errors
+    /// 日志头不符合格式时返回 `SqllogError::Format`
+    pub fn parse(
+        &self,
+        segment: &str,
+        line_num: usize,
+    ) -> SResult<Option<Sqllog>> {
+        Sqllog::parse_in(self, segment, line_num, self.blank_fields, self.mode)
+    }
+
+    /// 批量解析多个日志段，结果与输入一一对应，第 `i` 段的行号为 `i + 1`
+    #[must_use]
+    pub fn parse_lines(
+        &self,
+        segments: &[&str],
+    ) -> Vec<SResult<Option<Sqllog>>> {
+        segments
+            .iter()
+            .enumerate()
+            .map(|(i, segment)| self.parse(segment, i + 1))
+            .collect()
+    }
+}
+
+impl Default for ParserContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,207 @@
+//! 源文件编码识别 - BOM 检测与 UTF-16 转码
+//!
+//! 从 Windows 主机拷贝的日志可能带有 UTF-8 BOM，或以带 BOM 的 UTF-16 保存。
+//! 打开文件时根据开头的 BOM 识别编码：UTF-8 BOM 被跳过，UTF-16 内容被流式
+//! 转码为 UTF-8，使解析器始终看到不带 BOM 的 UTF-8 字节流。
+//! 行尾的 `\r\n` 与 `\n` 在逐行解析时统一处理。
+
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// 源文件编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum SourceEncoding {
+    /// 不带 BOM 的 UTF-8（或未知编码，按 UTF-8 处理）
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// 带 BOM 的 UTF-8
+    #[serde(rename = "utf-8-bom")]
+    Utf8Bom,
+    /// 带 BOM 的 UTF-16 小端
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    /// 带 BOM 的 UTF-16 大端
+    #[serde(rename = "utf-16be")]
+    Utf16Be,
+}
+
+impl std::fmt::Display for SourceEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Utf8 => "UTF-8",
+            Self::Utf8Bom => "UTF-8 (BOM)",
+            Self::Utf16Le => "UTF-16LE",
+            Self::Utf16Be => "UTF-16BE",
+        })
+    }
+}
+
+impl SourceEncoding {
+    /// 根据文件开头的字节识别编码
+    ///
+    /// 日志以 ASCII 时间戳开头，因此仅当 UTF-16 BOM 之后紧跟一个 ASCII 码元时
+    /// 才判定为 UTF-16，避免把以 `FF FE` 开头的损坏 UTF-8 内容误判为 UTF-16。
+    #[must_use]
+    pub fn detect(head: &[u8]) -> Self {
+        match head {
+            [0xEF, 0xBB, 0xBF, ..] => Self::Utf8Bom,
+            [0xFF, 0xFE, c, 0x00, ..] if c.is_ascii() => Self::Utf16Le,
+            [0xFE, 0xFF, 0x00, c, ..] if c.is_ascii() => Self::Utf16Be,
+            _ => Self::Utf8,
+        }
+    }
+
+    /// BOM 的字节长度
+    #[must_use]
+    pub const fn bom_len(self) -> usize {
+        match self {
+            Self::Utf8 => 0,
+            Self::Utf8Bom => 3,
+            Self::Utf16Le | Self::Utf16Be => 2,
+        }
+    }
+}
+
+/// 打开源文件并返回跳过 BOM 后的 UTF-8 字节流
+///
+/// # Errors
+/// 当文件无法打开或读取时返回 I/O 错误
+pub fn open_source(
+    path: &Path,
+) -> io::Result<(SourceEncoding, Box<dyn BufRead>)> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let encoding = SourceEncoding::detect(reader.fill_buf()?);
+    reader.consume(encoding.bom_len());
+
+    let reader: Box<dyn BufRead> = match encoding {
+        SourceEncoding::Utf8 | SourceEncoding::Utf8Bom => Box::new(reader),
+        SourceEncoding::Utf16Le => {
+            Box::new(BufReader::new(Utf16Decoder::new(reader, false)))
+        }
+        SourceEncoding::Utf16Be => {
+            Box::new(BufReader::new(Utf16Decoder::new(reader, true)))
+        }
+    };
+    Ok((encoding, reader))
+}
+
+/// 将一段原始字节（含 BOM）按已识别的编码转为文本，无效字节替换为 U+FFFD
+pub(crate) fn decode_sample(encoding: SourceEncoding, bytes: &[u8]) -> String {
+    let body = bytes.get(encoding.bom_len()..).unwrap_or_default();
+    let decoded = match encoding {
+        SourceEncoding::Utf8 | SourceEncoding::Utf8Bom => body.to_vec(),
+        SourceEncoding::Utf16Le | SourceEncoding::Utf16Be => {
+            let big_endian = encoding == SourceEncoding::Utf16Be;
+            let mut out = Vec::new();
+            let _ = Utf16Decoder::new(body, big_endian).read_to_end(&mut out);
+            out
+        }
+    };
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// 将 UTF-16 字节流转码为 UTF-8 的读取器，无效代理项替换为 U+FFFD
+struct Utf16Decoder<R> {
+    inner: R,
+    big_endian: bool,
+    /// 待解码的高位代理项
+    pending_high: Option<u16>,
+    /// 已转码但尚未被读取的 UTF-8 字节
+    out: Vec<u8>,
+    out_pos: usize,
+}
+
+impl<R: Read> Utf16Decoder<R> {
+    const fn new(inner: R, big_endian: bool) -> Self {
+        Self {
+            inner,
+            big_endian,
+            pending_high: None,
+            out: Vec::new(),
+            out_pos: 0,
+        }
+    }
+
+    fn push_char(&mut self, c: char) {
+        let mut buf = [0u8; 4];
+        self.out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+    }
+
+    fn push_unit(&mut self, unit: u16) {
+        match (self.pending_high.take(), unit) {
+            (Some(high), 0xDC00..=0xDFFF) => {
+                let code = 0x10000
+                    + ((u32::from(high) - 0xD800) << 10)
+                    + (u32::from(unit) - 0xDC00);
+                self.push_char(
+                    char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER),
+                );
+            }
+            (high, 0xD800..=0xDBFF) => {
+                if high.is_some() {
+                    self.push_char(char::REPLACEMENT_CHARACTER);
+                }
+                self.pending_high = Some(unit);
+            }
+            (high, _) => {
+                if high.is_some() {
+                    self.push_char(char::REPLACEMENT_CHARACTER);
+                }
+                self.push_char(
+                    char::from_u32(u32::from(unit))
+                        .unwrap_or(char::REPLACEMENT_CHARACTER),
+                );
+            }
+        }
+    }
+
+    /// 读取并转码下一段输入；返回 false 表示输入已结束
+    fn fill(&mut self) -> io::Result<bool> {
+        self.out.clear();
+        self.out_pos = 0;
+
+        let mut raw = [0u8; 8192];
+        let mut len = 0;
+        // 保证读取到偶数个字节（除非到达 EOF）
+        loop {
+            let n = self.inner.read(&mut raw[len..])?;
+            len += n;
+            if n == 0 || len % 2 == 0 {
+                break;
+            }
+        }
+        if len == 0 {
+            if self.pending_high.take().is_some() {
+                self.push_char(char::REPLACEMENT_CHARACTER);
+            }
+            return Ok(!self.out.is_empty());
+        }
+
+        for pair in raw[..len].chunks(2) {
+            let unit = match pair {
+                [a, b] if self.big_endian => u16::from_be_bytes([*a, *b]),
+                [a, b] => u16::from_le_bytes([*a, *b]),
+                // 末尾的奇数字节无法组成码元
+                _ => 0xFFFD,
+            };
+            self.push_unit(unit);
+        }
+        Ok(true)
+    }
+}
+
+impl<R: Read> Read for Utf16Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.out_pos >= self.out.len() {
+            if !self.fill()? {
+                return Ok(0);
+            }
+        }
+        let n = buf.len().min(self.out.len() - self.out_pos);
+        buf[..n].copy_from_slice(&self.out[self.out_pos..self.out_pos + n]);
+        self.out_pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,140 @@
+//! 执行阶段关联 - 把单独输出的执行统计并入对应的 SQL 记录
+//!
+//! 达梦在部分配置下把一条语句拆成两条日志：先输出 SQL 文本（没有
+//! `EXECTIME`），执行结束后再以同一会话、同一语句句柄输出只含
+//! `EXECTIME: ... ROWCOUNT: ... EXEC_ID: ...` 的记录。[`ExecPhaseLinker`]
+//! 按 `(sess, stmt)` 把后者的执行时间、影响行数与执行 ID 填入前者并丢弃
+//! 后者，得到完整的记录。
+//!
+//! 等待执行阶段的 SQL 记录会暂存在关联器中，直到匹配的执行阶段记录到达、
+//! 同一句柄出现新的 SQL 记录或文件结束；交付顺序与原始顺序一致。会话或
+//! 语句句柄为 NULL 的记录不参与关联，找不到对应 SQL 记录的执行阶段记录
+//! 原样保留。
+
+use crate::sqllog::Sqllog;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// 默认最多暂存的待关联记录数，超过后最早的记录不再等待、直接交付
+pub const DEFAULT_MAX_PENDING: usize = 10_000;
+
+type HandleKey = (String, String);
+
+/// 执行阶段关联器（单个文件内使用）
+#[derive(Debug)]
+pub struct ExecPhaseLinker {
+    /// 尚未交付的记录，按原始顺序
+    queue: VecDeque<Sqllog>,
+    /// `queue` 首条记录的序号
+    base: u64,
+    /// 等待执行阶段的记录：句柄 -> 序号
+    pending: HashMap<HandleKey, u64>,
+    /// 等待执行阶段的记录：序号 -> 句柄
+    waiting: BTreeMap<u64, HandleKey>,
+    max_pending: usize,
+    linked: u64,
+}
+
+impl Default for ExecPhaseLinker {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_PENDING)
+    }
+}
+
+impl ExecPhaseLinker {
+    /// 创建最多暂存 `max_pending` 条待关联记录的关联器
+    #[must_use]
+    pub fn new(max_pending: usize) -> Self {
+        Self {
+            queue: VecDeque::new(),
+            base: 0,
+            pending: HashMap::new(),
+            waiting: BTreeMap::new(),
+            max_pending: max_pending.max(1),
+            linked: 0,
+        }
+    }
+
+    /// 记录是否为只含执行统计的执行阶段记录
+    #[must_use]
+    pub fn is_exec_phase(record: &Sqllog) -> bool {
+        record.execute_time.is_some()
+            && record.sql_type.is_none()
+            && record
+                .description
+                .trim_start()
+                .get(..9)
+                .is_some_and(|head| head.eq_ignore_ascii_case("EXECTIME:"))
+    }
+
+    /// 依次处理一批记录，把可以交付的记录追加到 `out`
+    ///
+    /// `flush` 为 `true`（文件结束）时交付全部暂存的记录。
+    pub fn link(
+        &mut self,
+        records: impl IntoIterator<Item = Sqllog>,
+        out: &mut Vec<Sqllog>,
+        flush: bool,
+    ) {
+        for record in records {
+            self.push(record);
+        }
+        self.release(out, flush);
+    }
+
+    /// 已关联的执行阶段记录数
+    #[must_use]
+    pub const fn linked(&self) -> u64 {
+        self.linked
+    }
+
+    fn push(&mut self, record: Sqllog) {
+        let Some(key) = handle_key(&record) else {
+            self.queue.push_back(record);
+            return;
+        };
+        if Self::is_exec_phase(&record) {
+            if let Some(seq) = self.pending.remove(&key) {
+                self.waiting.remove(&seq);
+                let index = usize::try_from(seq - self.base).unwrap_or(0);
+                attach(&mut self.queue[index], &record);
+                self.linked += 1;
+                return;
+            }
+        } else if record.execute_time.is_none() {
+            // 同一句柄的上一条 SQL 记录不再等待
+            let seq = self.base + self.queue.len() as u64;
+            if let Some(previous) = self.pending.insert(key.clone(), seq) {
+                self.waiting.remove(&previous);
+            }
+            self.waiting.insert(seq, key);
+        }
+        self.queue.push_back(record);
+    }
+
+    fn release(&mut self, out: &mut Vec<Sqllog>, flush: bool) {
+        while !self.queue.is_empty() {
+            if let Some(key) = self.waiting.get(&self.base) {
+                if !flush && self.waiting.len() <= self.max_pending {
+                    break;
+                }
+                self.pending.remove(key);
+                self.waiting.remove(&self.base);
+            }
+            if let Some(record) = self.queue.pop_front() {
+                out.push(record);
+            }
+            self.base += 1;
+        }
+    }
+}
+
+fn handle_key(record: &Sqllog) -> Option<HandleKey> {
+    Some((record.session.clone()?, record.statement.clone()?))
+}
+
+fn attach(target: &mut Sqllog, exec: &Sqllog) {
+    target.execute_time = exec.execute_time;
+    target.execute_time_us = exec.execute_time_us;
+    target.rowcount = exec.rowcount;
+    target.execute_id = exec.execute_id;
+}
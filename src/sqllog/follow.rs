@@ -0,0 +1,157 @@
+//! 文件跟随 - 持续读取日志文件新追加的记录
+//!
+//! 供 `watch` 模式使用：每次 [`FileFollower::poll`] 读取自上次读取位置之后
+//! 追加的内容，按时间戳首行切分记录。文件末尾的最后一条记录可能尚未写完，
+//! 会保留到下一条记录出现（或调用 [`FileFollower::flush`]）时再解析。
+//! 文件变短时视为被轮转或截断，从头重新读取。
+
+use crate::sqllog::utils::is_first_row_bytes;
+use crate::sqllog::{BlankFields, ParseMode, SResult, Sqllog, SqllogError};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// 日志文件跟随器
+#[derive(Debug)]
+pub struct FileFollower {
+    path: PathBuf,
+    offset: u64,
+    /// 尚未构成完整行的字节
+    partial_line: Vec<u8>,
+    /// 当前未结束的记录文本及其起始行号、字节偏移
+    pending: String,
+    pending_line: usize,
+    pending_offset: u64,
+    line_num: usize,
+    /// `partial_line` 首字节的文件偏移
+    line_offset: u64,
+    blank_fields: BlankFields,
+    mode: ParseMode,
+}
+
+impl FileFollower {
+    /// 创建跟随器；`from_start` 为 `false` 时跳过文件中已有的内容
+    ///
+    /// # Errors
+    /// 当文件无法读取元数据时返回 I/O 错误
+    pub fn new(path: &Path, from_start: bool) -> io::Result<Self> {
+        let offset = if from_start { 0 } else { path.metadata()?.len() };
+        Ok(Self {
+            path: path.to_path_buf(),
+            offset,
+            partial_line: Vec::new(),
+            pending: String::new(),
+            pending_line: 0,
+            pending_offset: 0,
+            line_num: 0,
+            line_offset: offset,
+            blank_fields: BlankFields::default(),
+            mode: ParseMode::default(),
+        })
+    }
+
+    /// 设置空白 appname 的处理方式
+    #[must_use]
+    pub const fn with_blank_fields(
+        mut self,
+        blank_fields: BlankFields,
+    ) -> Self {
+        self.blank_fields = blank_fields;
+        self
+    }
+
+    /// 设置日志头不合格式时的处理方式
+    #[must_use]
+    pub const fn with_parse_mode(mut self, mode: ParseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// 读取新追加的内容，返回其中已完整的记录与解析错误
+    ///
+    /// # Errors
+    /// 当文件无法打开或读取时返回 I/O 错误
+    pub fn poll(&mut self) -> io::Result<(Vec<Sqllog>, Vec<SqllogError>)> {
+        let mut file = File::open(&self.path)?;
+        let len = file.metadata()?.len();
+        if len < self.offset {
+            log::info!("文件 {} 变短，从头重新读取", self.path.display());
+            self.offset = 0;
+            self.partial_line.clear();
+            self.pending.clear();
+            self.line_num = 0;
+            self.line_offset = 0;
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let mut buf = Vec::new();
+        file.take(len - self.offset).read_to_end(&mut buf)?;
+        self.offset += buf.len() as u64;
+
+        self.partial_line.extend_from_slice(&buf);
+        let Some(last_newline) = memchr::memrchr(b'\n', &self.partial_line)
+        else {
+            return Ok((Vec::new(), Vec::new()));
+        };
+        let rest = self.partial_line.split_off(last_newline + 1);
+        let complete = std::mem::replace(&mut self.partial_line, rest);
+
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        let mut start = 0;
+        for end in memchr::memchr_iter(b'\n', &complete) {
+            let bytes = &complete[start..=end];
+            start = end + 1;
+            let line = String::from_utf8_lossy(bytes);
+            self.line_num += 1;
+            if bytes.get(0..23).is_some_and(is_first_row_bytes) {
+                self.take_pending(&mut records, &mut errors);
+            }
+            if self.pending.is_empty() {
+                self.pending_line = self.line_num;
+                self.pending_offset = self.line_offset;
+            }
+            self.pending.push_str(&line);
+            self.line_offset += bytes.len() as u64;
+        }
+        Ok((records, errors))
+    }
+
+    /// 解析缓冲中最后一条记录（例如停止跟随时）
+    pub fn flush(&mut self) -> (Vec<Sqllog>, Vec<SqllogError>) {
+        let mut records = Vec::new();
+        let mut errors = Vec::new();
+        self.take_pending(&mut records, &mut errors);
+        (records, errors)
+    }
+
+    fn take_pending(
+        &mut self,
+        records: &mut Vec<Sqllog>,
+        errors: &mut Vec<SqllogError>,
+    ) {
+        let segment = std::mem::take(&mut self.pending);
+        let segment = segment.trim_end();
+        if segment.is_empty() {
+            return;
+        }
+        let parsed: SResult<Option<Sqllog>> = Sqllog::from_line_mode(
+            segment,
+            self.pending_line,
+            self.blank_fields,
+            self.mode,
+        );
+        match parsed {
+            Ok(Some(mut record)) => {
+                record.byte_offset = Some(self.pending_offset);
+                records.push(record);
+            }
+            Ok(None) => {}
+            Err(mut e) => {
+                if let SqllogError::Format { offset, .. } = &mut e {
+                    *offset = Some(self.pending_offset);
+                }
+                errors.push(e);
+            }
+        }
+    }
+}
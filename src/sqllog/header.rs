@@ -0,0 +1,287 @@
+//! 日志头拆分 - 将一段日志拆为头部各字段与 description
+//!
+//! 日志头形如：
+//!
+//! ```text
+//! 2025-09-16 20:02:53.562 (EP[0] sess:0x6da8ccef0 thrd:4146217 user:EDM_BASE
+//!     trxid:122154453026 stmt:0x6da900ef0 appname:MyApp 2.0: batch
+//!     ip:::ffff:10.80.147.109) [SEL] select 1
+//! ```
+//!
+//! `appname` 之前的字段取值格式固定，直接由正则匹配；`appname` 是客户端
+//! 提供的任意文本，可能含空格、冒号甚至括号，因此单独处理：
+//! - 以双引号开头时按引号包围的值读取（`""` 为转义的引号）
+//! - 否则取到首行中第一个合法的 `ip` 字段（`ip:` + IPv4 地址，或单独的 `ip`）为止
+//! - 没有合法的 `ip` 字段时，取到括号配平后第一个后跟空白的 `)` 为止
+//!
+//! 头部之后可选的 `[INS]`/`[SEL]` 等类型标记之后即为 description。
+//!
+//! 宽松模式下（`HeaderPatterns::split_lenient`）不要求字段齐全、顺序固定：只要首行
+//! 以时间戳和 `(` 开头并能找到头部的右括号，就逐个查找各字段，缺失的字段
+//! 按 `NULL` 处理。
+
+use regex::Regex;
+
+/// 拆分后的日志头（借用原始段文本）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Header<'a> {
+    pub occurrence_time: &'a str,
+    pub ep: &'a str,
+    pub session: &'a str,
+    pub thread: &'a str,
+    pub user: &'a str,
+    pub trx_id: &'a str,
+    pub statement: &'a str,
+    /// 原始 appname 文本（未出现 `appname:` 字段时为 `None`，取值可能为空）
+    pub appname: Option<&'a str>,
+    /// IPv4 地址（已去掉 `::ffff:` 前缀）
+    pub ip: Option<&'a str>,
+    pub sql_type: Option<&'a str>,
+    pub description: &'a str,
+}
+
+/// 日志头拆分所用的已编译正则
+#[derive(Debug, Clone)]
+pub(crate) struct HeaderPatterns {
+    prefix: Regex,
+    /// 头部末尾的 ip 字段及右括号
+    ip_end: Regex,
+    sql_type: Regex,
+    /// 宽松模式：时间戳与头部左括号
+    lenient_start: Regex,
+    /// 宽松模式：头部中的各字段（取值为到下一个空白为止的文本）
+    lenient_ep: Regex,
+    lenient_field: Regex,
+    lenient_appname: Regex,
+    lenient_ip: Regex,
+}
+
+impl HeaderPatterns {
+    /// 编译全部正则
+    ///
+    /// # Panics
+    /// 正则均为常量，不会失败
+    pub(crate) fn new() -> Self {
+        Self {
+            prefix: Regex::new(
+                r"(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3}) \(EP\[(\d+)\] sess:(NULL|0x[0-9a-f]+) thrd:(-1|NULL|\d+) user:(NULL|\w+) trxid:(NULL|\d+) stmt:(NULL|0x[0-9a-f]+)",
+            )
+            .unwrap(),
+            ip_end: Regex::new(
+                r"\sip(?::(?:::ffff:)?([0-9]{1,3}(?:\.[0-9]{1,3}){3}))?\)\s",
+            )
+            .unwrap(),
+            sql_type: Regex::new(r"^\[(INS|DEL|ORA|UPD|SEL)\]:?\s").unwrap(),
+            lenient_start: Regex::new(
+                r"^(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3})\s+\(",
+            )
+            .unwrap(),
+            lenient_ep: Regex::new(r"\bEP\[(-?\d+)\]").unwrap(),
+            lenient_field: Regex::new(
+                r"(?:^|\s)(sess|thrd|user|trxid|stmt):(\S*)",
+            )
+            .unwrap(),
+            lenient_appname: Regex::new(
+                r"(?:^|\s)appname:(.*?)(?:\sip(?::\S*)?\s*$|$)",
+            )
+            .unwrap(),
+            lenient_ip: Regex::new(
+                r"(?:^|\s)ip:(?:::ffff:)?([0-9]{1,3}(?:\.[0-9]{1,3}){3})\s*$",
+            )
+            .unwrap(),
+        }
+    }
+
+    /// 拆分日志段；不符合日志头格式时返回 `None`
+    pub(crate) fn split<'a>(&self, segment: &'a str) -> Option<Header<'a>> {
+        let caps = self.prefix.captures(segment)?;
+        let field = |i: usize| caps.get(i).map_or("", |m| m.as_str());
+        let rest = &segment[caps.get(0)?.end()..];
+
+        let (appname, ip, body) =
+            if let Some(value) = rest.strip_prefix(" appname:") {
+                self.split_appname(value)?
+            } else if rest.starts_with(')') {
+                (None, None, after_paren(rest)?)
+            } else {
+                let (ip, body) = self.leading_ip(rest)?;
+                (None, ip, body)
+            };
+
+        let (sql_type, description) = self.split_sql_type(body);
+
+        Some(Header {
+            occurrence_time: field(1),
+            ep: field(2),
+            session: field(3),
+            thread: field(4),
+            user: field(5),
+            trx_id: field(6),
+            statement: field(7),
+            appname,
+            ip,
+            sql_type,
+            description,
+        })
+    }
+
+    /// 宽松拆分日志段：字段缺失或格式不符时尽量恢复，缺失的字段为 `NULL`
+    ///
+    /// 首行不以时间戳和 `(` 开头、或找不到头部的右括号时返回 `None`；
+    /// `EP[n]` 缺失或越界时 `ep` 为 `-1`。
+    pub(crate) fn split_lenient<'a>(
+        &self,
+        segment: &'a str,
+    ) -> Option<Header<'a>> {
+        let start = self.lenient_start.captures(segment)?;
+        let occurrence_time = start.get(1)?.as_str();
+        let rest = &segment[start.get(0)?.end()..];
+        // 头部不会跨行，右括号只在首行中查找
+        let first_line = rest.find('\n').map_or(rest, |i| &rest[..i]);
+        let end = balanced_close(first_line).or_else(|| {
+            // 没有 description 时右括号位于行尾
+            first_line.trim_end().strip_suffix(')').map(str::len)
+        })?;
+        let inner = &rest[..end];
+        let body = rest[end + 1..]
+            .strip_prefix(|c: char| c.is_whitespace())
+            .unwrap_or(&rest[end + 1..]);
+
+        let mut header = Header {
+            occurrence_time,
+            ep: self
+                .lenient_ep
+                .captures(inner)
+                .and_then(|c| c.get(1))
+                .map(|m| m.as_str())
+                .filter(|ep| ep.parse::<i32>().is_ok())
+                .unwrap_or("-1"),
+            session: "NULL",
+            thread: "NULL",
+            user: "NULL",
+            trx_id: "NULL",
+            statement: "NULL",
+            appname: None,
+            ip: None,
+            sql_type: None,
+            description: body,
+        };
+        // appname 之后的文本可能含 `user:` 等字样，只在其之前查找固定字段
+        let appname = self.lenient_appname.captures(inner);
+        let fixed = appname
+            .as_ref()
+            .and_then(|c| c.get(0))
+            .map_or(inner, |m| &inner[..m.start()]);
+        for caps in self.lenient_field.captures_iter(fixed) {
+            let value = caps.get(2).map_or("", |m| m.as_str());
+            let value = if value.is_empty() { "NULL" } else { value };
+            match &caps[1] {
+                "sess" => header.session = value,
+                "thrd" => header.thread = value,
+                "user" => header.user = value,
+                "trxid" => header.trx_id = value,
+                _ => header.statement = value,
+            }
+        }
+        header.appname = appname.and_then(|c| c.get(1)).map(|m| m.as_str());
+        header.ip = self
+            .lenient_ip
+            .captures(inner)
+            .and_then(|c| c.get(1))
+            .map(|m| m.as_str());
+        (header.sql_type, header.description) = self.split_sql_type(body);
+        Some(header)
+    }
+
+    /// 拆分头部之后的 `[SEL]` 等类型标记与 description
+    fn split_sql_type<'a>(&self, body: &'a str) -> (Option<&'a str>, &'a str) {
+        self.sql_type.captures(body).map_or((None, body), |c| {
+            let end = c.get(0).map_or(0, |m| m.end());
+            (c.get(1).map(|m| m.as_str()), &body[end..])
+        })
+    }
+
+    /// 拆分 `appname:` 之后的文本为 appname、ip 与头部之后的内容
+    fn split_appname<'a>(&self, value: &'a str) -> Option<AppnameParts<'a>> {
+        if value.starts_with('"') {
+            if let Some(parts) = self.quoted_appname(value) {
+                return Some(parts);
+            }
+        }
+
+        // appname 不会跨行，ip 字段只在首行中查找，避免匹配到多行 SQL 中的文本
+        let first_line = value.find('\n').map_or(value, |i| &value[..=i]);
+        if let Some(m) = self.ip_end.captures(first_line) {
+            let whole = m.get(0)?;
+            let appname = &value[..whole.start()];
+            let ip = m.get(1).map(|ip| ip.as_str());
+            return Some((Some(appname), ip, &value[whole.end()..]));
+        }
+
+        let end = balanced_close(value)?;
+        Some((Some(&value[..end]), None, after_paren(&value[end..])?))
+    }
+
+    /// 读取双引号包围的 appname，其后须紧跟 ip 字段或 `)`
+    fn quoted_appname<'a>(&self, value: &'a str) -> Option<AppnameParts<'a>> {
+        let bytes = value.as_bytes();
+        let mut i = 1;
+        while i < bytes.len() {
+            if bytes[i] == b'"' {
+                if bytes.get(i + 1) == Some(&b'"') {
+                    i += 2;
+                    continue;
+                }
+                // 引号内的文本原样保留（包括 `""` 转义）
+                let appname = &value[1..i];
+                let rest = &value[i + 1..];
+                if rest.starts_with(')') {
+                    return Some((Some(appname), None, after_paren(rest)?));
+                }
+                let (ip, body) = self.leading_ip(rest)?;
+                return Some((Some(appname), ip, body));
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// 解析紧接在开头的 ip 字段与右括号
+    fn leading_ip<'a>(
+        &self,
+        rest: &'a str,
+    ) -> Option<(Option<&'a str>, &'a str)> {
+        let m = self.ip_end.captures(rest)?;
+        let whole = m.get(0)?;
+        if whole.start() != 0 {
+            return None;
+        }
+        Some((m.get(1).map(|ip| ip.as_str()), &rest[whole.end()..]))
+    }
+}
+
+type AppnameParts<'a> = (Option<&'a str>, Option<&'a str>, &'a str);
+
+/// 括号配平后第一个后跟空白的 `)` 的位置
+fn balanced_close(value: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in value.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            ')' if value[i + 1..].starts_with(char::is_whitespace) => {
+                return Some(i);
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// 跳过 `)` 及其后的一个空白字符
+fn after_paren(rest: &str) -> Option<&str> {
+    let rest = rest.strip_prefix(')')?;
+    let mut chars = rest.chars();
+    chars.next().filter(|c| c.is_whitespace())?;
+    Some(chars.as_str())
+}
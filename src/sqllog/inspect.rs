@@ -0,0 +1,244 @@
+//! 文件探查 - 只读取文件开头的一段样本，估算编码、行尾与记录规模
+//!
+//! 用于在正式导入大批文件之前规划作业：样本中按时间戳首行统计记录数，
+//! 以完整记录的平均字节数推算整个文件的记录总数。样本覆盖整个文件时
+//! 记录数为精确值。
+//!
+//! 需要精确记录数时使用 [`count_records`]：完整扫描文件，但只检查每行开头
+//! 是否为时间戳，不构造记录也不解析日志头，速度接近顺序读取文件。
+
+use crate::sqllog::encoding::{self, SourceEncoding};
+use crate::sqllog::utils::{is_first_row, is_first_row_bytes};
+use serde::Serialize;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, Read};
+use std::path::{Path, PathBuf};
+
+/// 默认读取的样本大小（字节）
+pub const DEFAULT_SAMPLE_BYTES: usize = 64 * 1024;
+
+/// 样本中的行尾风格
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    /// `\n`
+    Lf,
+    /// `\r\n`
+    CrLf,
+    /// 两种行尾混用
+    Mixed,
+    /// 样本中没有换行
+    Unknown,
+}
+
+impl fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Lf => "LF",
+            Self::CrLf => "CRLF",
+            Self::Mixed => "混合",
+            Self::Unknown => "未知",
+        })
+    }
+}
+
+/// 单个文件的探查结果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileInspection {
+    pub path: PathBuf,
+    /// 文件总字节数
+    pub file_bytes: u64,
+    /// 实际读取的样本字节数
+    pub sample_bytes: usize,
+    pub encoding: SourceEncoding,
+    pub line_ending: LineEnding,
+    /// 样本中第一条记录的时间戳
+    pub first_timestamp: Option<String>,
+    /// 样本中出现的记录数（以时间戳首行计）
+    pub sample_records: u64,
+    /// 单条记录的平均字节数（按源文件字节计）
+    pub avg_record_bytes: Option<u64>,
+    /// 整个文件的记录数估算；样本覆盖整个文件时为精确值
+    pub estimated_records: Option<u64>,
+}
+
+impl FileInspection {
+    /// 样本是否覆盖整个文件
+    #[must_use]
+    pub fn is_exact(&self) -> bool {
+        self.sample_bytes as u64 >= self.file_bytes
+    }
+}
+
+impl fmt::Display for FileInspection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.path.display())?;
+        writeln!(f, "  大小:       {} 字节", self.file_bytes)?;
+        writeln!(f, "  编码:       {}", self.encoding)?;
+        writeln!(f, "  行尾:       {}", self.line_ending)?;
+        writeln!(
+            f,
+            "  首条时间:   {}",
+            self.first_timestamp.as_deref().unwrap_or("-")
+        )?;
+        match self.avg_record_bytes {
+            Some(avg) => writeln!(f, "  平均记录:   {avg} 字节")?,
+            None => writeln!(f, "  平均记录:   -")?,
+        }
+        match self.estimated_records {
+            Some(n) if self.is_exact() => writeln!(f, "  记录数:     {n}"),
+            Some(n) => writeln!(f, "  记录数估算: ~{n}"),
+            None => writeln!(f, "  记录数估算: -"),
+        }
+    }
+}
+
+/// 读取文件开头至多 `sample_bytes` 字节并生成探查结果
+///
+/// # Errors
+/// 当文件无法打开或读取时返回 I/O 错误
+pub fn inspect_file(
+    path: &Path,
+    sample_bytes: usize,
+) -> io::Result<FileInspection> {
+    let file = File::open(path)?;
+    let file_bytes = file.metadata()?.len();
+    let mut raw = Vec::with_capacity(sample_bytes.min(1 << 20));
+    file.take(sample_bytes as u64).read_to_end(&mut raw)?;
+
+    let encoding = SourceEncoding::detect(&raw);
+    let text = encoding::decode_sample(encoding, &raw);
+    let exact = raw.len() as u64 >= file_bytes;
+
+    let mut crlf = 0u64;
+    let mut lf = 0u64;
+    // 各记录首行在解码文本中的起始偏移
+    let mut starts = Vec::new();
+    let mut first_timestamp = None;
+    let mut offset = 0usize;
+    for line in text.split_inclusive('\n') {
+        if line.ends_with("\r\n") {
+            crlf += 1;
+        } else if line.ends_with('\n') {
+            lf += 1;
+        }
+        if let Some(ts) = line.get(0..23).filter(|ts| is_first_row(ts)) {
+            first_timestamp.get_or_insert_with(|| ts.to_string());
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+
+    let line_ending = match (crlf, lf) {
+        (0, 0) => LineEnding::Unknown,
+        (_, 0) => LineEnding::CrLf,
+        (0, _) => LineEnding::Lf,
+        _ => LineEnding::Mixed,
+    };
+
+    let sample_records = starts.len() as u64;
+    // 源文件字节与解码文本字节之比（UTF-16 约为 2）
+    let body_bytes = raw.len().saturating_sub(encoding.bom_len());
+    let ratio = if text.is_empty() {
+        1.0
+    } else {
+        body_bytes as f64 / text.len() as f64
+    };
+
+    let (avg_record_bytes, estimated_records) = if exact {
+        let avg = (sample_records > 0).then(|| {
+            (body_bytes as f64 / sample_records as f64).round() as u64
+        });
+        (avg, Some(sample_records))
+    } else if let [first, .., last] = starts[..] {
+        // 样本末尾的记录可能被截断，只用完整记录计算平均大小
+        let complete = (starts.len() - 1) as f64;
+        let avg = (last - first) as f64 * ratio / complete;
+        let total = file_bytes.saturating_sub(encoding.bom_len() as u64);
+        let estimate = (total as f64 / avg).round() as u64;
+        (Some(avg.round() as u64), Some(estimate))
+    } else {
+        (None, None)
+    };
+
+    Ok(FileInspection {
+        path: path.to_path_buf(),
+        file_bytes,
+        sample_bytes: raw.len(),
+        encoding,
+        line_ending,
+        first_timestamp,
+        sample_records,
+        avg_record_bytes,
+        estimated_records,
+    })
+}
+
+/// 时间戳 `YYYY-MM-DD HH:MM:SS.mmm` 的长度
+const TS_LEN: usize = 23;
+
+/// 完整扫描文件，返回记录数（以时间戳首行计）
+///
+/// 与解析时的记录边界一致：跳过行首的空格与制表符后，以 23 字节的时间戳
+/// 开头的行为一条记录的首行。日志头无法解析的记录同样计入。
+///
+/// # Errors
+/// 当文件无法打开或读取时返回 I/O 错误
+pub fn count_records(path: &Path) -> io::Result<u64> {
+    let (_, mut reader) = encoding::open_source(path)?;
+    let mut records = 0u64;
+    let mut head = LineHead::default();
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            break;
+        }
+        let mut start = 0;
+        for newline in memchr::memchr_iter(b'\n', buf) {
+            records += u64::from(head.feed(&buf[start..newline]));
+            head = LineHead::default();
+            start = newline + 1;
+        }
+        records += u64::from(head.feed(&buf[start..]));
+        let len = buf.len();
+        reader.consume(len);
+    }
+    Ok(records)
+}
+
+/// 当前行开头的字节（行可能跨越多次读取）
+#[derive(Default)]
+struct LineHead {
+    bytes: [u8; TS_LEN],
+    len: usize,
+    /// 已跳过行首空白
+    started: bool,
+    /// 已判定是否为首行
+    decided: bool,
+}
+
+impl LineHead {
+    /// 追加当前行的一段字节，凑满时间戳长度且为首行时返回 `true`
+    fn feed(&mut self, mut segment: &[u8]) -> bool {
+        if self.decided {
+            return false;
+        }
+        if !self.started {
+            let skip = segment
+                .iter()
+                .position(|b| !matches!(b, b' ' | b'\t'))
+                .unwrap_or(segment.len());
+            segment = &segment[skip..];
+            self.started = !segment.is_empty();
+        }
+        let take = (TS_LEN - self.len).min(segment.len());
+        self.bytes[self.len..self.len + take].copy_from_slice(&segment[..take]);
+        self.len += take;
+        if self.len < TS_LEN {
+            return false;
+        }
+        self.decided = true;
+        is_first_row_bytes(&self.bytes)
+    }
+}
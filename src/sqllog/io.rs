@@ -1,11 +1,14 @@
 use crate::sqllog::{
+    ExecPhaseLinker, KeySample, Prefilter, RecordIdGenerator, RecordIdMode,
+    TraceCounts, TraceLineMode, TraceLines,
+    encoding::{self, SourceEncoding},
+    options::{BlankFields, ErrorBreaker, ParseMode, ParseOptions},
+    parser::Segment,
+    plan,
     types::{Sqllog, SqllogError},
     utils,
 };
-use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-};
+use std::{fs::File, io::BufRead, ops::ControlFlow, time::Instant};
 
 impl Sqllog {
     /// 解析整个文件，并在解析出记录时通过 `hook` 回调发送记录片段。
@@ -35,9 +38,12 @@ impl Sqllog {
         F: FnMut(&[Self]),
         EF: FnMut(&[(usize, String, SqllogError)]),
     {
-        // chunk_size 为 0 时表示不分块，传递 None 给 stream_parse
-        let chunk_opt = if chunk_size == 0 { None } else { Some(chunk_size) };
-        Self::stream_parse(path, chunk_opt, hook, err_hook)
+        Self::parse_with_options(
+            path,
+            &ParseOptions::with_chunk_size(chunk_size),
+            hook,
+            err_hook,
+        )
     }
 
     /// 按块解析文件，每次最多 `chunk_size` 条记录，并在每个块解析完成后调用 `hook`。
@@ -63,7 +69,46 @@ impl Sqllog {
         F: FnMut(&[Self]),
         EF: FnMut(&[(usize, String, SqllogError)]),
     {
-        Self::stream_parse(path, Some(chunk_size), hook, err_hook)
+        Self::stream_parse(
+            path,
+            Some(chunk_size),
+            &ParseOptions::with_chunk_size(chunk_size),
+            hook,
+            err_hook,
+        )
+    }
+
+    /// 按给定的解析选项解析文件。
+    ///
+    /// `options.chunk_size` 的含义与 `parse_all` 相同（0 表示不分块）。
+    /// 设置了 `options.timeout` 时，超过时限后会先回调已解析的记录与错误，
+    /// 再通过 `err_hook` 上报一条 `SqllogError::Timeout`，并放弃该文件剩余内容，
+    /// 函数仍返回 `Ok(())`，便于调用方继续处理其他文件。
+    /// 设置了 `options.error_breaker` 且文件开头的错误率超过上限时，同样先
+    /// 回调已解析的记录与错误，再返回错误、放弃该文件剩余内容。
+    ///
+    /// # Errors
+    /// - `SqllogError::Io(_)` - 文件打开或读取时发生 I/O 错误
+    /// - `SqllogError::ErrorRateExceeded { .. }` - 文件开头的错误率超过熔断上限
+    /// - `SqllogError::RecordId { .. }` - 无法生成唯一的雪花记录 ID，出错的块
+    ///   及其后的记录不再回调
+    pub fn parse_with_options<P, F, EF>(
+        path: P,
+        options: &ParseOptions,
+        hook: F,
+        err_hook: EF,
+    ) -> Result<(), SqllogError>
+    where
+        P: AsRef<std::path::Path>,
+        F: FnMut(&[Self]),
+        EF: FnMut(&[(usize, String, SqllogError)]),
+    {
+        // chunk_size 为 0 时表示不分块，传递 None 给 stream_parse
+        let chunk_opt = match options.chunk_size {
+            0 => None,
+            n => Some(n),
+        };
+        Self::stream_parse(path, chunk_opt, options, hook, err_hook)
     }
 
     /// 流式解析实现（内部使用）。
@@ -73,6 +118,7 @@ impl Sqllog {
     /// 参数说明：
     /// - `path`: 要解析的文件路径。
     /// - `chunk_size`: 可选的块大小，若为 `Some(n)` 则在每 `n` 条记录时触发一次 `hook`。
+    /// - `options`: 其余解析选项（如单文件超时）。
     /// - `hook`: 成功解析记录时的回调，接收记录切片 `&[Sqllog]`。
     /// - `err_hook`: 解析发生错误时的回调，接收错误列表 `&[(usize, String, SqllogError)]`。
     ///
@@ -82,6 +128,7 @@ impl Sqllog {
     fn stream_parse<P, F, EF>(
         path: P,
         chunk_size: Option<usize>,
+        options: &ParseOptions,
         mut hook: F,
         mut err_hook: EF,
     ) -> Result<(), SqllogError>
@@ -107,11 +154,48 @@ impl Sqllog {
         }
 
         let mut state = ParseState::new(chunk_size);
+        state.extract_plans = options.extract_plans;
+        state.blank_fields = options.blank_fields;
+        state.mode = options.mode;
+        state.prefilter.clone_from(&options.prefilter);
+        state.sample = options.sample;
+        state.breaker = options.error_breaker;
+        if options.trace_lines.enabled() {
+            state.trace_lines = Some(options.trace_lines.clone());
+        }
+        if options.link_exec_phase {
+            state.exec_link = Some(ExecPhaseLinker::default());
+        }
+        if options.record_id != RecordIdMode::Disabled {
+            state.id_gen =
+                Some(RecordIdGenerator::new(options.record_id, &file_name));
+        }
 
-        let path_clone = path.as_ref().to_path_buf();
+        // 识别 BOM：UTF-8 BOM 直接跳过，UTF-16 转码为 UTF-8 后再逐行解析
+        let (encoding, reader) =
+            encoding::open_source(path_ref).map_err(SqllogError::Io)?;
+        match encoding {
+            SourceEncoding::Utf8 => {}
+            SourceEncoding::Utf8Bom => {
+                log::debug!(
+                    "stream_parse: 文件 {file_name} 带 UTF-8 BOM，已跳过"
+                );
+                state.byte_offset = encoding.bom_len() as u64;
+            }
+            // UTF-16 文件的字节偏移按转码后的 UTF-8 字节流计算
+            SourceEncoding::Utf16Le | SourceEncoding::Utf16Be => {
+                log::info!(
+                    "stream_parse: 文件 {file_name} 为 {encoding:?} 编码，按 UTF-8 转码解析"
+                );
+            }
+        }
 
         let mut line_count = 0u64;
-        let mut last_progress_report = std::time::Instant::now();
+        let mut last_progress_report = Instant::now();
+        let deadline =
+            options.timeout.map(|limit| (Instant::now() + limit, limit));
+        let mut timed_out = None;
+        let mut tripped = None;
 
         // 每读取一行字节后调用的闭包，会把字节传给 ParseState 进行处理
         let mut per_line = |line: &[u8]| {
@@ -126,30 +210,70 @@ impl Sqllog {
                     line_count,
                     state.chunk.len()
                 );
-                last_progress_report = std::time::Instant::now();
+                last_progress_report = Instant::now();
+            }
+
+            if let Some((at, limit)) = deadline {
+                if Instant::now() >= at {
+                    timed_out = Some((line_count, limit));
+                    return ControlFlow::Break(());
+                }
             }
 
             state.process_line_callback(line, &mut hook, &mut err_hook);
+            tripped =
+                state.id_error.take().or_else(|| state.check_breaker(false));
+            if tripped.is_some() {
+                return ControlFlow::Break(());
+            }
+            ControlFlow::Continue(())
         };
 
         log::debug!("stream_parse: 开始逐行读取文件");
-        Self::read_file_lines(path_clone, &mut per_line)?;
+        Self::read_file_lines(reader, &mut per_line)?;
 
-        if !state.content.is_empty() {
+        // 超时：交付已完成的记录，丢弃未结束的多行记录，然后上报超时错误
+        if let Some((line, limit)) = timed_out {
+            log::warn!(
+                "stream_parse: 文件 {file_name} 解析超过 {limit:?}，已在第 {line} 行放弃"
+            );
+            state.finalize_at_eof(&mut hook, &mut err_hook);
+            state.log_counts(&file_name);
+            if let Some(e) = state.id_error.take() {
+                log::error!("stream_parse: 文件 {file_name} {e}");
+                return Err(e);
+            }
+            let line = usize::try_from(line).unwrap_or(usize::MAX);
+            err_hook(&[(line, file_name, SqllogError::Timeout(limit))]);
+            return Ok(());
+        }
+        if let Some(e) = tripped {
+            return Err(state.abort(&file_name, e, &mut hook, &mut err_hook));
+        }
+
+        if !state.segment.content.is_empty() {
             Self::flush_content(
-                &state.content,
-                state.line_num,
+                &state.segment,
+                state.blank_fields,
+                state.mode,
+                state.prefilter.as_ref(),
                 &mut state.chunk,
                 &mut state.chunk_errors,
             );
         }
 
+        // 文件短于熔断检查窗口时在文件末尾检查
+        if let Some(e) = state.check_breaker(true) {
+            return Err(state.abort(&file_name, e, &mut hook, &mut err_hook));
+        }
+
         // 如果从未遇到过首行，特殊处理并返回，避免重复上报同一错误。
         if !state.has_first_row {
             let has_critical = state.chunk_errors.iter().any(|(_, _, e)| {
                 matches!(
                     e,
                     SqllogError::Utf8(_)
+                        | SqllogError::Undecodable { .. }
                         | SqllogError::Io(_)
                         | SqllogError::Regex(_)
                         | SqllogError::ParseInt(_)
@@ -167,31 +291,68 @@ impl Sqllog {
         }
 
         state.finalize_at_eof(&mut hook, &mut err_hook);
+        state.log_counts(&file_name);
+        if let Some(e) = state.id_error.take() {
+            log::error!("stream_parse: 文件 {file_name} {e}");
+            return Err(e);
+        }
 
         Ok(())
     }
 
-    /// 以行为单位读取文件，并将每行字节（包含换行符）传递给 `cb` 回调。
+    /// 以行为单位读取字节流，并将每行字节（包含换行符）传递给 `cb` 回调。
+    ///
+    /// 直接在 `BufRead` 的内部缓冲中用 `memchr` 查找换行符：整行位于缓冲内时
+    /// 原地交给回调，只有跨越缓冲边界的行才拷贝拼接。
     ///
     /// 参数说明：
-    /// - `path`: 要读取的文件路径。
-    /// - `cb`: 接收裁剪后的行字节切片 `&[u8]` 的回调。
+    /// - `reader`: 已跳过 BOM 的 UTF-8 字节流（见 `encoding::open_source`）。
+    /// - `cb`: 接收裁剪后的行字节切片 `&[u8]` 的回调，返回 `Break` 时提前停止读取。
     ///
-    /// 返回：当无法打开或读取文件时返回 `SqllogError::Io`。
-    fn read_file_lines<P, C>(path: P, mut cb: C) -> Result<(), SqllogError>
+    /// 返回：当读取失败时返回 `SqllogError::Io`。
+    fn read_file_lines<C>(
+        mut reader: impl BufRead,
+        mut cb: C,
+    ) -> Result<(), SqllogError>
     where
-        P: AsRef<std::path::Path>,
-        C: FnMut(&[u8]),
+        C: FnMut(&[u8]) -> ControlFlow<()>,
     {
-        let file = File::open(path.as_ref()).map_err(SqllogError::Io)?;
-        let mut reader = BufReader::new(file);
-        let mut buf = Vec::new();
+        // 跨越缓冲边界的行
+        let mut carry = Vec::new();
         loop {
-            buf.clear();
-            match reader.read_until(b'\n', &mut buf) {
-                Ok(0) => break,
+            let available = match reader.fill_buf() {
+                Ok(available) => available,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                    continue;
+                }
                 Err(e) => return Err(SqllogError::Io(e)),
-                Ok(_) => cb(&buf),
+            };
+            if available.is_empty() {
+                // 文件末尾没有换行符的最后一行
+                if !carry.is_empty() {
+                    let _ = cb(&carry);
+                }
+                break;
+            }
+
+            let Some(newline) = memchr::memchr(b'\n', available) else {
+                carry.extend_from_slice(available);
+                let consumed = available.len();
+                reader.consume(consumed);
+                continue;
+            };
+            let line = &available[..=newline];
+            let flow = if carry.is_empty() {
+                cb(line)
+            } else {
+                carry.extend_from_slice(line);
+                let flow = cb(&carry);
+                carry.clear();
+                flow
+            };
+            reader.consume(newline + 1);
+            if flow.is_break() {
+                break;
             }
         }
 
@@ -205,29 +366,42 @@ impl Sqllog {
     ///
     /// 参数说明：
     /// - `line_bytes`: 当前读取到的行字节（包含换行符）。
-    /// - `line_num`: 当前行号引用（会在必要时更新）。
+    /// - `offset`: 该行在源文件中的起始字节偏移。
+    /// - `line_num`: 该行在源文件中的行号（从 1 开始）。
     /// - `has_first_row`: 指示是否已遇到首行（用于跳过文件头或无效内容）。
-    /// - `content`: 解析时用于拼接多行记录的临时字符串缓冲。
+    /// - `segment`: 解析时用于拼接多行记录的缓冲及其起始位置。
+    /// - `blank_fields`: 空白 appname 的规范化方式。
+    /// - `mode`: 日志头不合格式时的处理方式。
+    /// - `prefilter`: 可选的原始段预过滤，不匹配的段直接跳过。
     /// - `sqllogs`: 当前块的解析结果向量，会把解析出的记录 push 到该向量中。
     /// - `errors`: 解析过程中收集的错误列表，包含行号、原始文本片段和错误类型。
+    #[allow(clippy::too_many_arguments)]
     fn handle_raw_line_impl(
         line_bytes: &[u8],
-        line_num: &mut usize,
+        offset: u64,
+        line_num: usize,
         has_first_row: &mut bool,
-        content: &mut String,
+        segment: &mut Segment,
+        blank_fields: BlankFields,
+        mode: ParseMode,
+        prefilter: Option<&Prefilter>,
         sqllogs: &mut Vec<Self>,
         errors: &mut Vec<(usize, String, SqllogError)>,
     ) {
         // 始终获取一个 String（在无效 UTF-8 情况下可能丢失信息）。UTF-8 错误会在
         // utils::line_bytes_to_str_impl 中被记录，但不会致命；解析会继续处理后续行。
         let line_str =
-            utils::line_bytes_to_str_impl(line_bytes, *line_num, errors);
+            utils::line_bytes_to_str_impl(line_bytes, line_num, offset, errors);
 
         Self::process_line(
             line_str.as_ref(),
-            has_first_row,
-            content,
             line_num,
+            offset,
+            has_first_row,
+            segment,
+            blank_fields,
+            mode,
+            prefilter,
             sqllogs,
             errors,
         );
@@ -264,12 +438,36 @@ impl Sqllog {
 /// 该结构保存了流式解析过程中需要的可变信息：当前行号、是否已遇到首条有效日志、
 /// 当前拼接内容缓冲、当前块的解析结果与错误集合以及可选的块大小设置。
 struct ParseState {
+    /// 下一行的行号（从 1 开始，全文件计数，跨块不重置）
     line_num: usize,
     has_first_row: bool,
-    content: String,
+    segment: Segment,
     chunk: Vec<Sqllog>,
     chunk_errors: Vec<(usize, String, SqllogError)>,
     chunk_size: Option<usize>,
+    id_gen: Option<RecordIdGenerator>,
+    /// 记录 ID 生成失败后不再交付记录；错误由 `id_error` 取走后上报
+    id_failed: bool,
+    id_error: Option<SqllogError>,
+    extract_plans: bool,
+    blank_fields: BlankFields,
+    mode: ParseMode,
+    /// 启用时在解析日志头之前按原始文本筛掉不匹配的段
+    prefilter: Option<Prefilter>,
+    sample: Option<KeySample>,
+    /// 启用时识别混入的非 sqllog 行
+    trace_lines: Option<TraceLines>,
+    /// 已识别的非 sqllog 行数（按分类）
+    trace_counts: TraceCounts,
+    /// 下一行在文件中的起始字节偏移
+    byte_offset: u64,
+    /// 尚未检查的错误率熔断（检查后置为 `None`）
+    breaker: Option<ErrorBreaker>,
+    /// 启用时把执行阶段记录并入对应的 SQL 记录
+    exec_link: Option<ExecPhaseLinker>,
+    /// 已交付的块中的记录数（抽样前）与错误数
+    finalized_records: usize,
+    finalized_errors: usize,
 }
 
 impl ParseState {
@@ -277,11 +475,63 @@ impl ParseState {
         Self {
             line_num: 1usize,
             has_first_row: false,
-            content: String::new(),
+            segment: Segment::default(),
             chunk: Vec::with_capacity(chunk_size.unwrap_or(1).max(1)),
             chunk_errors: Vec::new(),
             chunk_size,
+            id_gen: None,
+            id_failed: false,
+            id_error: None,
+            extract_plans: false,
+            blank_fields: BlankFields::default(),
+            mode: ParseMode::default(),
+            prefilter: None,
+            sample: None,
+            trace_lines: None,
+            trace_counts: TraceCounts::new(),
+            byte_offset: 0,
+            breaker: None,
+            exec_link: None,
+            finalized_records: 0,
+            finalized_errors: 0,
+        }
+    }
+
+    /// 读过熔断检查窗口（或已到文件末尾）时检查一次错误率，超过上限时返回错误
+    fn check_breaker(&mut self, at_eof: bool) -> Option<SqllogError> {
+        let breaker = self.breaker?;
+        if !at_eof && self.byte_offset < breaker.window_bytes() {
+            return None;
         }
+        self.breaker = None;
+        let records = self.finalized_records + self.chunk.len();
+        let errors = self.finalized_errors + self.chunk_errors.len();
+        breaker.trips(records, errors).then_some(
+            SqllogError::ErrorRateExceeded {
+                errors,
+                records,
+                bytes: self.byte_offset,
+                max_ratio: breaker.max_ratio(),
+            },
+        )
+    }
+
+    /// 熔断：交付已完成的记录与错误，丢弃未结束的多行记录
+    fn abort<F, EF>(
+        &mut self,
+        file_name: &str,
+        error: SqllogError,
+        hook: &mut F,
+        err_hook: &mut EF,
+    ) -> SqllogError
+    where
+        F: FnMut(&[Sqllog]),
+        EF: FnMut(&[(usize, String, SqllogError)]),
+    {
+        log::error!("stream_parse: 文件 {file_name} {error}");
+        self.finalize_at_eof(hook, err_hook);
+        self.log_counts(file_name);
+        error
     }
 
     /// 处理读取到的一行字节，将其解析并可能触发 `hook` 或 `err_hook`。
@@ -299,11 +549,19 @@ impl ParseState {
         F: FnMut(&[Sqllog]),
         EF: FnMut(&[(usize, String, SqllogError)]),
     {
+        let offset = self.byte_offset;
+        self.byte_offset += line.len() as u64;
+        let line_num = self.line_num;
+        self.line_num += 1;
         Sqllog::handle_raw_line_impl(
             line,
-            &mut self.line_num,
+            offset,
+            line_num,
             &mut self.has_first_row,
-            &mut self.content,
+            &mut self.segment,
+            self.blank_fields,
+            self.mode,
+            self.prefilter.as_ref(),
             &mut self.chunk,
             &mut self.chunk_errors,
         );
@@ -311,7 +569,7 @@ impl ParseState {
         // 若配置了 chunk_size 且达到阈值，则触发一次块终结与回调
         if let Some(n) = self.chunk_size {
             if self.chunk.len() >= n {
-                self.finalize_at_eof(hook, err_hook);
+                self.finalize(hook, err_hook, false);
             }
         }
     }
@@ -326,15 +584,97 @@ impl ParseState {
         F: FnMut(&[Sqllog]),
         EF: FnMut(&[(usize, String, SqllogError)]),
     {
+        self.finalize(hook, err_hook, true);
+    }
+
+    /// 终结当前块；`flush` 为 `false`（块边界）时执行阶段关联器可以继续
+    /// 暂存等待关联的记录
+    fn finalize<F, EF>(&mut self, hook: &mut F, err_hook: &mut EF, flush: bool)
+    where
+        F: FnMut(&[Sqllog]),
+        EF: FnMut(&[(usize, String, SqllogError)]),
+    {
+        self.classify_trace_lines();
+        self.finalized_records += self.chunk.len();
+        self.finalized_errors += self.chunk_errors.len();
         if !self.chunk_errors.is_empty() {
             err_hook(&self.chunk_errors);
         }
+        if let Some(linker) = &mut self.exec_link {
+            let parsed = std::mem::take(&mut self.chunk);
+            linker.link(parsed, &mut self.chunk, flush);
+        }
 
         if !self.chunk.is_empty() {
-            hook(&self.chunk);
+            // 先分配记录 ID 再抽样，保证抽样前后同一记录的 ID 一致
+            if let Some(id_gen) = &mut self.id_gen {
+                if !self.id_failed {
+                    if let Err(e) = id_gen.assign(&mut self.chunk) {
+                        self.id_failed = true;
+                        self.id_error = Some(e);
+                    }
+                }
+                if self.id_failed {
+                    self.chunk.clear();
+                }
+            }
+            if let Some(sample) = &self.sample {
+                sample.retain(&mut self.chunk);
+            }
+            if self.extract_plans {
+                for record in &mut self.chunk {
+                    record.plan = plan::extract_plan(&record.description);
+                }
+            }
+            if !self.chunk.is_empty() {
+                hook(&self.chunk);
+            }
         }
 
         self.chunk.clear();
         self.chunk_errors.clear();
     }
+
+    /// 记录本文件关联的执行阶段记录数与按分类识别到的非 sqllog 行数
+    fn log_counts(&self, file_name: &str) {
+        if let Some(linker) = &self.exec_link {
+            log::info!(
+                "文件 {file_name} 中关联执行阶段记录 {} 条",
+                linker.linked()
+            );
+        }
+        if self.trace_counts.is_empty() {
+            return;
+        }
+        let counts: Vec<String> = self
+            .trace_counts
+            .iter()
+            .map(|(category, n)| format!("{category} {n}"))
+            .collect();
+        log::info!("文件 {file_name} 中的非 sqllog 行: {}", counts.join("，"));
+    }
+
+    /// 识别格式错误中的非 sqllog 行：按分类计数，`Skip` 时移除，
+    /// `Collect` 时改为 `SqllogError::TraceLine`。
+    fn classify_trace_lines(&mut self) {
+        let Some(trace_lines) = &self.trace_lines else { return };
+        let collect = trace_lines.mode == TraceLineMode::Collect;
+        let counts = &mut self.trace_counts;
+        self.chunk_errors.retain_mut(|(line, content, error)| {
+            if !matches!(error, SqllogError::Format { .. }) {
+                return true;
+            }
+            let Some(category) = trace_lines.classify(content) else {
+                return true;
+            };
+            *counts.entry(category.to_string()).or_insert(0) += 1;
+            if collect {
+                *error = SqllogError::TraceLine {
+                    line: *line,
+                    category: category.to_string(),
+                };
+            }
+            collect
+        });
+    }
 }
@@ -1,7 +1,35 @@
+pub mod context;
+pub mod encoding;
+pub mod exec_link;
+pub mod follow;
+mod header;
+pub mod inspect;
 pub mod io;
+pub mod options;
 pub mod parser;
+pub mod plan;
+pub mod prefilter;
+pub mod record_id;
+pub mod sample;
+pub mod trace;
 pub mod types;
+pub mod units;
 pub mod utils;
 
-pub use types::{SResult, Sqllog, SqllogError};
-pub use utils::{find_first_row_pos, is_first_row, line_bytes_to_str_impl};
+pub use context::ParserContext;
+pub use encoding::SourceEncoding;
+pub use exec_link::ExecPhaseLinker;
+pub use follow::FileFollower;
+pub use inspect::{FileInspection, LineEnding, count_records, inspect_file};
+pub use options::{BlankFields, ErrorBreaker, ParseMode, ParseOptions};
+pub use plan::{PlanNode, extract_plan};
+pub use prefilter::Prefilter;
+pub use record_id::{RecordIdGenerator, RecordIdMode};
+pub use sample::{KeySample, SampleKey};
+pub use trace::{TraceCounts, TraceLineMode, TraceLines};
+pub use types::{RawSegment, SResult, Sqllog, SqllogError};
+pub use units::{ExecId, ExecTimeMs, RowCount};
+pub use utils::{
+    find_first_row_pos, is_first_row, is_first_row_bytes,
+    line_bytes_to_str_impl,
+};
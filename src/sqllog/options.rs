@@ -0,0 +1,176 @@
+use crate::sqllog::{
+    KeySample, Prefilter, RecordIdMode, SqllogError, TraceLines,
+};
+use std::time::Duration;
+
+/// 文件解析选项
+///
+/// 汇总 `Sqllog::parse_with_options` 的可调参数，默认值与
+/// `Sqllog::parse_all(path, 0, ..)` 的行为一致。
+#[derive(Debug, Clone, Default)]
+pub struct ParseOptions {
+    /// 每次回调包含的最大记录数，0 表示不分块（解析完成后一次性回调）
+    pub chunk_size: usize,
+    /// 单个文件的解析时限，超时后放弃该文件剩余内容并上报
+    /// `SqllogError::Timeout`；`None` 表示不限时
+    pub timeout: Option<Duration>,
+    /// 记录 ID 生成方式，默认不生成
+    pub record_id: RecordIdMode,
+    /// 是否从 description 中提取执行计划到 `Sqllog::plan`
+    pub extract_plans: bool,
+    /// 空白 appname 的规范化方式
+    pub blank_fields: BlankFields,
+    /// 按字段哈希抽样，只交付被抽中的记录；`None` 表示不抽样
+    pub sample: Option<KeySample>,
+    /// 混入文件的 trace/诊断行的识别与处理方式，默认按格式错误上报
+    pub trace_lines: TraceLines,
+    /// 日志头不合格式时的处理方式，默认按格式错误上报
+    pub mode: ParseMode,
+    /// 文件开头错误率熔断，`None` 表示不检查
+    pub error_breaker: Option<ErrorBreaker>,
+    /// 是否把单独输出的执行阶段记录（只含 `EXECTIME` 等统计）按会话与语句
+    /// 句柄并入对应的 SQL 记录，见 [`ExecPhaseLinker`](crate::sqllog::ExecPhaseLinker)
+    pub link_exec_phase: bool,
+    /// 原始段预过滤，只解析整段文本匹配的记录；`None` 表示不过滤
+    pub prefilter: Option<Prefilter>,
+}
+
+/// 解析错误率熔断
+///
+/// 读完文件开头 `window_bytes` 字节（文件更短时在文件末尾）后检查一次：
+/// 这部分内容的解析错误数占记录与错误总数的比例超过 `max_ratio`，或其中
+/// 没有任何有效日志行时，停止解析并返回 `SqllogError::ErrorRateExceeded`。
+/// 用于在几秒内发现「文件格式根本不对」的错误，而不是等整个文件解析完。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorBreaker {
+    max_ratio: f64,
+    window_bytes: u64,
+}
+
+impl ErrorBreaker {
+    /// 创建错误率上限为 `max_ratio`（`[0, 1)`）、检查窗口为文件开头
+    /// `window_bytes` 字节的熔断
+    ///
+    /// # Errors
+    /// 当 `max_ratio` 不在 `[0, 1)` 内或 `window_bytes` 为 0 时返回错误
+    pub fn new(max_ratio: f64, window_bytes: u64) -> Result<Self, String> {
+        if !(0.0..1.0).contains(&max_ratio) {
+            return Err(format!("错误率上限必须在 [0, 1) 内: {max_ratio}"));
+        }
+        if window_bytes == 0 {
+            return Err("检查窗口不能为 0 字节".to_string());
+        }
+        Ok(Self { max_ratio, window_bytes })
+    }
+
+    /// 错误率上限
+    #[must_use]
+    pub const fn max_ratio(&self) -> f64 {
+        self.max_ratio
+    }
+
+    /// 检查窗口（文件开头的字节数）
+    #[must_use]
+    pub const fn window_bytes(&self) -> u64 {
+        self.window_bytes
+    }
+
+    /// 错误链中是否有熔断导致的 `SqllogError::ErrorRateExceeded`
+    #[must_use]
+    pub fn tripped(error: &anyhow::Error) -> bool {
+        error.chain().any(|e| {
+            matches!(
+                e.downcast_ref::<SqllogError>(),
+                Some(SqllogError::ErrorRateExceeded { .. })
+            )
+        })
+    }
+
+    /// 按窗口内的记录数与错误数判断是否熔断
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn trips(&self, records: usize, errors: usize) -> bool {
+        if records == 0 {
+            return true;
+        }
+        errors as f64 / (records + errors) as f64 > self.max_ratio
+    }
+}
+
+/// 日志头解析模式
+///
+/// 在数据完整性与数据量之间取舍：严格模式只接受格式完整的日志头；宽松模式
+/// 在严格匹配失败时尽量逐个提取字段（如缺少 `trxid:`），恢复出的记录带
+/// `Sqllog::partial` 标记，缺失的字段为 `None`。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParseMode {
+    /// 日志头不合格式时上报格式错误（默认）
+    #[default]
+    Strict,
+    /// 尽量恢复字段，恢复出的记录标记为 `partial`
+    Lenient,
+}
+
+impl std::str::FromStr for ParseMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strict" => Ok(Self::Strict),
+            "lenient" | "loose" => Ok(Self::Lenient),
+            _ => Err(format!("不支持的解析模式: {s}")),
+        }
+    }
+}
+
+/// 日志头中空白文本字段（目前为 `appname`）的规范化方式
+///
+/// 字段出现但取值为空或仅含空白时（如 `appname: ip:...`）按此处理；
+/// 未出现该字段时始终为 `None`。导出时 `None` 一律写为 SQL NULL。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BlankFields {
+    /// 视为缺失：`None`
+    #[default]
+    Null,
+    /// 保留为空字符串：`Some("")`
+    Empty,
+    /// 原样保留日志中的文本（可能为空或仅含空白）
+    Raw,
+}
+
+impl BlankFields {
+    /// 按规范化方式转换字段的原始文本，`None` 表示字段未出现
+    #[must_use]
+    pub fn apply(self, raw: Option<&str>) -> Option<String> {
+        let value = raw?;
+        if !value.trim().is_empty() {
+            return Some(value.to_string());
+        }
+        match self {
+            Self::Null => None,
+            Self::Empty => Some(String::new()),
+            Self::Raw => Some(value.to_string()),
+        }
+    }
+}
+
+impl std::str::FromStr for BlankFields {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "null" | "none" => Ok(Self::Null),
+            "empty" => Ok(Self::Empty),
+            "raw" | "keep" => Ok(Self::Raw),
+            _ => Err(format!("不支持的空白字段处理方式: {s}")),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// 创建指定块大小、其余为默认值的解析选项
+    #[must_use]
+    pub fn with_chunk_size(chunk_size: usize) -> Self {
+        Self { chunk_size, ..Self::default() }
+    }
+}
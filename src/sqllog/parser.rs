@@ -62,146 +62,168 @@
 #![allow(clippy::missing_errors_doc)]
 #![allow(clippy::doc_markdown)]
 
+use crate::sqllog::context::ParserContext;
+use crate::sqllog::header::Header;
+use crate::sqllog::options::{BlankFields, ParseMode};
+use crate::sqllog::prefilter::Prefilter;
 use crate::sqllog::types::SqllogError;
 use crate::sqllog::types::{DescNumbers, SResult, Sqllog};
-use lazy_static::lazy_static;
+use crate::sqllog::units::{ExecId, ExecTimeMs, RowCount};
 use regex::Regex;
 
+/// description 末行中 `EXECTIME/ROWCOUNT/EXEC_ID` 的匹配模式
+pub(crate) const DESC_PATTERN: &str = r"(?i)\bEXEC_?TIME\s*:\s*(\d+)(?:\.(\d+))?\s*(?:\(\s*(ms|us|µs|μs|s)\s*\)|(ms|us|µs|μs|s)\b)?(?:[\s,;]*ROW_?COUNT\s*:\s*(\d+)(?:\s*\(rows?\))?)?(?:[\s,;]*EXEC_?ID\s*:\s*(\d+))?";
+
 impl Sqllog {
     /// 从单段日志文本解析出 `Sqllog` 结构体。
     ///
     /// 行为：对整个段使用静态正则进行匹配并解析字段，解析成功返回 `Ok(Some(Sqllog))`。
     ///
     /// 错误处理：若正则未匹配或解析字段失败，返回相应的 `SqllogError`（例如 `Format`）。
+    ///
+    /// 空白的 appname 按默认的 [`BlankFields::Null`] 处理为 `None`。
     pub fn from_line(segment: &str, line_num: usize) -> SResult<Option<Self>> {
-        // 静态正则表达式，提升性能
-        lazy_static! {
-            static ref SQLLOG_RE: Regex = Regex::new(r"(?s)(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3}) \(EP\[(\d+)\] sess:(NULL|0x[0-9a-f]+) thrd:(-1|NULL|\d+) user:(NULL|\w+) trxid:(NULL|\d+) stmt:(NULL|0x[0-9a-f]+)(?:\sappname:(.*?))?(?:\sip(?::(?:::ffff:)?([0-9]{1,3}(?:\.[0-9]{1,3}){3}))?)?\)\s(?:\[(INS|DEL|ORA|UPD|SEL)\]:?\s)?((?:.|\n)*)").unwrap();
-        }
+        Self::from_line_with(segment, line_num, BlankFields::default())
+    }
+
+    /// 与 [`Sqllog::from_line`] 相同，但按 `blank_fields` 规范化空白的 appname。
+    pub fn from_line_with(
+        segment: &str,
+        line_num: usize,
+        blank_fields: BlankFields,
+    ) -> SResult<Option<Self>> {
+        Self::from_line_mode(segment, line_num, blank_fields, ParseMode::Strict)
+    }
+
+    /// 与 [`Sqllog::from_line_with`] 相同，但按 `mode` 处理不合格式的日志头。
+    ///
+    /// [`ParseMode::Lenient`] 下严格匹配失败时尽量恢复字段，恢复出的记录
+    /// `partial` 为 `true`；仍无法恢复（如首行没有头部括号）时返回格式错误。
+    pub fn from_line_mode(
+        segment: &str,
+        line_num: usize,
+        blank_fields: BlankFields,
+        mode: ParseMode,
+    ) -> SResult<Option<Self>> {
+        Self::parse_in(
+            ParserContext::shared(),
+            segment,
+            line_num,
+            blank_fields,
+            mode,
+        )
+    }
 
-        // 只对完整段做正则匹配
-        if let Some(caps) = SQLLOG_RE.captures(segment) {
-            log::trace!("行{line_num} 匹配到 SQLLOG 正则，开始解析字段");
+    /// 批量解析多个日志段，结果与输入一一对应，第 `i` 段的行号为 `i + 1`。
+    ///
+    /// 整批只取一次共享的已编译正则；需要自定义空白字段或解析模式时使用
+    /// [`ParserContext::parse_lines`]。
+    #[must_use]
+    pub fn from_lines(segments: &[&str]) -> Vec<SResult<Option<Self>>> {
+        ParserContext::shared().parse_lines(segments)
+    }
+
+    /// 使用 `ctx` 中已编译的正则解析单个日志段
+    pub(crate) fn parse_in(
+        ctx: &ParserContext,
+        segment: &str,
+        line_num: usize,
+        blank_fields: BlankFields,
+        mode: ParseMode,
+    ) -> SResult<Option<Self>> {
+        if let Some(header) = ctx.header.split(segment) {
+            log::trace!("行{line_num} 匹配到日志头，开始解析字段");
             // 将字段解析提取到私有方法，减少本方法长度
-            let log = Self::parse_fields(&caps, segment, line_num)?;
+            let log = Self::parse_fields(
+                ctx,
+                &header,
+                segment,
+                line_num,
+                blank_fields,
+            )?;
             log::trace!("行{line_num} 字段解析成功");
             Ok(Some(log))
+        } else if let Some(header) = (mode == ParseMode::Lenient)
+            .then(|| ctx.header.split_lenient(segment))
+            .flatten()
+        {
+            log::trace!("行{line_num} 日志头不完整，按宽松模式恢复字段");
+            let mut log = Self::parse_fields(
+                ctx,
+                &header,
+                segment,
+                line_num,
+                blank_fields,
+            )?;
+            log.partial = true;
+            Ok(Some(log))
         } else {
-            log::trace!("行{line_num} 未匹配到 SQLLOG 正则，内容: {segment}");
-            Err(SqllogError::Format {
-                line: line_num,
-                content: segment.to_string(),
-            })
+            log::trace!("行{line_num} 未匹配到日志头，内容: {segment}");
+            Err(Self::format_err(line_num, segment))
         }
     }
 
-    /// 从正则捕获组中解析出各字段并构造 `Sqllog` 结构体。
+    /// 由拆分后的日志头构造 `Sqllog` 结构体。
     ///
     /// 参数：
-    /// - `caps`：正则匹配的捕获组。
+    /// - `ctx`：提供解析 description 数值所用的正则。
+    /// - `header`：拆分得到的日志头各字段。
     /// - `segment`：当前待解析的段文本。
     /// - `line_num`：段的起始行号（用于错误记录）。
+    /// - `blank_fields`：空白 appname 的规范化方式。
     ///
     /// 返回：解析成功返回 `Ok(Sqllog)`，解析过程中发生错误会返回对应的 `SqllogError`。
     fn parse_fields(
-        caps: &regex::Captures,
+        ctx: &ParserContext,
+        header: &Header<'_>,
         segment: &str,
         line_num: usize,
+        blank_fields: BlankFields,
     ) -> SResult<Self> {
-        let occurrence_time = Self::get_capture(caps, 1, line_num, segment)?;
-        let ep: i32 = Self::get_capture(caps, 2, line_num, segment)?
+        let ep: i32 = header
+            .ep
             .parse()
             .map_err(|_| Self::format_err(line_num, segment))?;
+        let description = header.description.to_string();
 
-        let session = Self::parse_optional(caps, 3, line_num, segment)?;
-        let thread = match caps.get(4).map(|m| m.as_str()) {
-            Some("NULL") => None,
-            Some("-1") => Some("-1".to_string()),
-            Some(s) => Some(s.to_string()),
-            None => {
-                return Err(SqllogError::Format {
-                    line: line_num,
-                    content: segment.to_string(),
-                });
-            }
-        };
-        let user = Self::parse_optional(caps, 5, line_num, segment)?;
-        let trx_id = Self::parse_optional(caps, 6, line_num, segment)?;
-        let statement = Self::parse_optional(caps, 7, line_num, segment)?;
-        let appname = caps.get(8).and_then(|m| {
-            let s = m.as_str();
-            if s.is_empty() { None } else { Some(s.to_string()) }
-        });
-        let ip = caps.get(9).and_then(|m| {
-            let s = m.as_str();
-            if s.is_empty() { None } else { Some(s.to_string()) }
-        });
-        let sql_type = caps.get(10).map(|m| m.as_str().to_string());
-        let description = Self::get_capture(caps, 11, line_num, segment)?;
-
-        let (execute_time, rowcount, execute_id): DescNumbers =
-            Self::parse_desc_numbers(&description, line_num);
+        let (execute_time_us, rowcount, execute_id): DescNumbers =
+            Self::parse_desc_numbers(&ctx.desc, &description);
+        // 保持 execute_time 的毫秒语义，亚毫秒部分仅保留在 execute_time_us 中
+        let execute_time = execute_time_us.map(ExecTimeMs::from_micros);
 
         Ok(Self {
-            occurrence_time,
+            occurrence_time: header.occurrence_time.to_string(),
             ep,
-            session,
-            thread,
-            user,
-            trx_id,
-            statement,
-            appname,
-            ip,
-            sql_type,
+            session: Self::parse_optional(header.session),
+            // thrd 为 -1 时保留原值
+            thread: Self::parse_optional(header.thread),
+            user: Self::parse_optional(header.user),
+            trx_id: Self::parse_optional(header.trx_id),
+            statement: Self::parse_optional(header.statement),
+            appname: blank_fields.apply(header.appname),
+            ip: header.ip.map(str::to_string),
+            sql_type: header.sql_type.map(str::to_string),
             description,
             execute_time,
-            rowcount,
-            execute_id,
+            execute_time_us,
+            rowcount: rowcount.map(RowCount::new),
+            execute_id: execute_id.map(ExecId::new),
+            record_id: None,
+            plan: None,
+            partial: false,
+            line: line_num,
+            byte_offset: None,
         })
     }
 
-    /// 获取指定索引的捕获文本，若不存在则构建格式错误并返回。
-    ///
-    /// 参数：
-    /// - `caps`：正则捕获组。
-    /// - `idx`：要提取的捕获组索引。
-    /// - `line_num`：用于错误报告的行号。
-    /// - `seg`：原始段文本，用于在错误中返回上下文。
-    ///
-    /// 返回：捕获文本的 `String` 或 `SqllogError::Format`。
-    fn get_capture(
-        caps: &regex::Captures,
-        idx: usize,
-        line_num: usize,
-        seg: &str,
-    ) -> Result<String, SqllogError> {
-        caps.get(idx)
-            .map(|m| m.as_str().to_string())
-            .ok_or_else(|| Self::format_err(line_num, seg))
-    }
-
-    /// 解析可选捕获字段：当匹配到 "NULL" 时返回 `None`，当存在文本时返回 `Some(String)`。
-    ///
-    /// 在缺少捕获组时返回 `SqllogError::Format`。
-    fn parse_optional(
-        caps: &regex::Captures,
-        idx: usize,
-        line_num: usize,
-        seg: &str,
-    ) -> Result<Option<String>, SqllogError> {
-        match caps.get(idx).map(|m| m.as_str()) {
-            Some("NULL") => Ok(None),
-            Some(s) => Ok(Some(s.to_string())),
-            None => Err(SqllogError::Format {
-                line: line_num,
-                content: seg.to_string(),
-            }),
-        }
+    /// 解析可选字段：`NULL` 表示缺失，返回 `None`。
+    fn parse_optional(value: &str) -> Option<String> {
+        (value != "NULL").then(|| value.to_string())
     }
 
     /// 构造 `SqllogError::Format` 错误，包含行号与原始内容字符串。
     fn format_err(line: usize, content: &str) -> SqllogError {
-        SqllogError::Format { line, content: content.to_string() }
+        SqllogError::Format { line, offset: None, content: content.to_string() }
     }
 
     /// 从 description 文本中解析 `EXECTIME/ROWCOUNT/EXEC_ID` 三个数值。
@@ -215,46 +237,71 @@ impl Sqllog {
     /// ## 解析策略
     ///
     /// - **只在最后一行搜索**：避免在拼接的多行内容中误匹配到中间行的参数
-    /// - **严格格式检查**：如果最后一行没有完整的 EXECTIME 模式，认为格式异常
-    /// - **错误上报机制**：格式异常的记录会被写入错误文件，不会插入数据库
+    /// - **取最后一处匹配**：SQL 文本本身包含类似片段时以行尾的统计信息为准
+    /// - **宽松模式**：EXECTIME 缺失时三个字段均为 `None`，记录仍然保留
     ///
-    /// ## 参数格式
+    /// ## 兼容的格式变体
     ///
-    /// 期望的最后一行格式：`EXECTIME: 123(ms) ROWCOUNT: 456 EXEC_ID: 789.`
+    /// 不同达梦版本的输出略有差异，以下写法均可识别：
     ///
-    /// ## 返回值
+    /// - `EXECTIME: 123(ms) ROWCOUNT: 456 EXEC_ID: 789.`（标准格式）
+    /// - `EXECTIME: 0.125(ms) ROWCOUNT: 1(rows) EXEC_ID: 5.`（小数与 `(rows)` 后缀）
+    /// - `EXECTIME:1500(us)`、`EXECTIME: 2 (s)`（微秒/秒单位，空格可有可无）
+    /// - `exec_time: 12ms`、`ExecTime: 12`（大小写、下划线、括号与单位可省略，默认毫秒）
     ///
-    /// - 成功：`Ok((Some(exectime), Some(rowcount), Some(exec_id)))`
-    /// - 格式错误：`Err(SqllogError::Format)` - 记录将被写入错误文件
-    ///
-    /// ## 错误处理
+    /// ## 返回值
     ///
-    /// 当 description 的最后一行不包含完整的 EXECTIME 模式时，整条记录被视为
-    /// 解析失败，这确保了：
-    /// 1. 数据库中只保存格式完整的记录
-    /// 2. 所有异常记录都能在错误文件中追踪到具体的行号和内容
-    /// 3. 多行拼接导致的格式问题能被正确识别
-    fn parse_desc_numbers(desc: &str, _line_num: usize) -> DescNumbers {
-        lazy_static! {
-            static ref DESC_RE_INNER: Regex = Regex::new(r"EXECTIME:\s*(\d+)\(ms\)(?:\s+ROWCOUNT:\s*(\d+))?(?:\s+EXEC_ID:\s*(\d+))?").unwrap();
-        }
-
-        // 保持顺序的宽松解析模式：要求EXECTIME存在，ROWCOUNT和EXEC_ID可选
-        // 这确保了解析的顺序性，同时允许不完整的记录通过解析
+    /// `(执行时间（微秒）, 影响行数, 执行 ID)`；数值无法解析或溢出时对应字段为 `None`。
+    fn parse_desc_numbers(desc_re: &Regex, desc: &str) -> DescNumbers {
         let last_line = desc.lines().last().unwrap_or("");
 
-        DESC_RE_INNER.captures(last_line).map_or((None, None, None), |caps| {
-            let execute_time =
-                caps.get(1).and_then(|m| m.as_str().parse::<i64>().ok());
+        desc_re.captures_iter(last_line).last().map_or(
+            (None, None, None),
+            |caps| {
+                let unit = caps
+                    .get(3)
+                    .or_else(|| caps.get(4))
+                    .map_or("ms", |m| m.as_str());
+                let execute_time_us = Self::to_micros(
+                    &caps[1],
+                    caps.get(2).map(|m| m.as_str()),
+                    unit,
+                );
 
-            let rowcount =
-                caps.get(2).and_then(|m| m.as_str().parse::<i64>().ok());
+                let rowcount =
+                    caps.get(5).and_then(|m| m.as_str().parse::<i64>().ok());
 
-            let execute_id =
-                caps.get(3).and_then(|m| m.as_str().parse::<i64>().ok());
+                let execute_id =
+                    caps.get(6).and_then(|m| m.as_str().parse::<i64>().ok());
 
-            (execute_time, rowcount, execute_id)
-        })
+                (execute_time_us, rowcount, execute_id)
+            },
+        )
+    }
+
+    /// 将 `整数部分[.小数部分] 单位` 换算为微秒（小数部分按位截断，不使用浮点）。
+    fn to_micros(
+        int_part: &str,
+        frac_part: Option<&str>,
+        unit: &str,
+    ) -> Option<i64> {
+        let (factor, scale_digits): (i64, usize) =
+            match unit.to_lowercase().as_str() {
+                "s" => (1_000_000, 6),
+                "us" | "µs" | "μs" => (1, 0),
+                _ => (1_000, 3),
+            };
+
+        let whole = int_part.parse::<i64>().ok()?.checked_mul(factor)?;
+        let frac = frac_part.map_or(Some(0), |digits| {
+            let digits: String = digits
+                .chars()
+                .chain(std::iter::repeat('0'))
+                .take(scale_digits)
+                .collect();
+            if digits.is_empty() { Some(0) } else { digits.parse::<i64>().ok() }
+        })?;
+        whole.checked_add(frac)
     }
 
     /// 将当前拼接的 `content` 刷新为 `Sqllog`：调用 `from_line` 并将结果写入 `sqllogs` 或 `errors`。
@@ -263,6 +310,7 @@ impl Sqllog {
     ///
     /// 这是解析流水线的关键节点，决定每个拼接完成的内容段的最终去向：
     ///
+    /// - **预过滤未命中**：给出 `prefilter` 且整段原始文本不匹配时直接跳过
     /// - **成功解析** (`Ok(Some(log))`)：记录推入 `sqllogs` 向量，最终写入数据库
     /// - **解析失败** (`Ok(None)` 或 `Err(e)`)：错误推入 `errors` 向量，最终写入错误文件
     ///
@@ -276,31 +324,46 @@ impl Sqllog {
     /// ## 错误追踪
     ///
     /// 错误元组 `(line_num, content, error)` 提供了完整的调试信息：
-    /// - `line_num`: 段起始行号，便于定位问题
+    /// - `line_num`: 段首行在源文件中的行号（全文件计数，跨块不重置），便于定位问题
     /// - `content`: 完整的原始内容，便于人工检查
     /// - `error`: 具体的错误类型和描述
     pub(crate) fn flush_content(
-        content: &str,
-        line_num: usize,
+        segment: &Segment,
+        blank_fields: BlankFields,
+        mode: ParseMode,
+        prefilter: Option<&Prefilter>,
         sqllogs: &mut Vec<Self>,
         errors: &mut Vec<(usize, String, SqllogError)>,
     ) {
+        let Segment { content, line: line_num, offset } = segment;
         // 忽略仅包含空白或换行的段，避免将其作为格式错误上报
         if content.trim().is_empty() {
             return;
         }
+        if prefilter.is_some_and(|filter| !filter.matches(content)) {
+            return;
+        }
 
-        match Self::from_line(content, line_num) {
-            Ok(Some(log)) => sqllogs.push(log),
+        match Self::from_line_mode(content, *line_num, blank_fields, mode) {
+            Ok(Some(mut log)) => {
+                log.byte_offset = Some(*offset);
+                sqllogs.push(log);
+            }
             Ok(None) => errors.push((
-                line_num,
-                content.to_string(),
+                *line_num,
+                content.clone(),
                 SqllogError::Format {
-                    line: line_num,
-                    content: content.to_string(),
+                    line: *line_num,
+                    offset: Some(*offset),
+                    content: content.clone(),
                 },
             )),
-            Err(e) => errors.push((line_num, content.to_string(), e)),
+            Err(mut e) => {
+                if let SqllogError::Format { offset: at, .. } = &mut e {
+                    *at = Some(*offset);
+                }
+                errors.push((*line_num, content.clone(), e));
+            }
         }
     }
 
@@ -335,11 +398,16 @@ impl Sqllog {
     /// - **空白内容过滤**：去除前导空白和特殊字符（如 `\u{FFFD}`）
     /// - **换行规范化**：确保段内行以 `\n` 分隔，行尾的 `\r\n` 被清理
     /// - **首行标记**：`has_first_row` 确保在遇到第一个时间戳前不进行段处理
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn process_line(
         line_str: &str,
+        line_num: usize,
+        offset: u64,
         has_first_row: &mut bool,
-        content: &mut String,
-        line_num: &mut usize,
+        segment: &mut Segment,
+        blank_fields: BlankFields,
+        mode: ParseMode,
+        prefilter: Option<&Prefilter>,
         sqllogs: &mut Vec<Self>,
         errors: &mut Vec<(usize, String, SqllogError)>,
     ) {
@@ -352,17 +420,35 @@ impl Sqllog {
 
         if is_new_segment {
             *has_first_row = true;
-            if !content.is_empty() {
-                Self::flush_content(content, *line_num, sqllogs, errors);
-                content.clear();
+            if !segment.content.is_empty() {
+                Self::flush_content(
+                    segment,
+                    blank_fields,
+                    mode,
+                    prefilter,
+                    sqllogs,
+                    errors,
+                );
+                segment.content.clear();
             }
-            *line_num = 1;
         }
 
-        if !content.is_empty() {
-            content.push('\n');
+        if segment.content.is_empty() {
+            segment.line = line_num;
+            segment.offset = offset;
+        } else {
+            segment.content.push('\n');
         }
-        content.push_str(clean);
-        *line_num += 1;
+        segment.content.push_str(clean);
     }
 }
+
+/// 正在拼接的多行记录：文本及其首行在源文件中的位置
+#[derive(Debug, Default)]
+pub(crate) struct Segment {
+    pub content: String,
+    /// 首行行号（从 1 开始）
+    pub line: usize,
+    /// 首行的字节偏移
+    pub offset: u64,
+}
@@ -0,0 +1,98 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::mem::size_of;
+
+/// 执行计划中的单个操作符节点
+///
+/// 对应达梦执行计划中的一行，例如：
+/// `#CSCN2: [1, 100, 30]; INDEX33555484(T)`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlanNode {
+    /// 操作符名称（如 `NSET2`、`PRJT2`、`CSCN2`）
+    pub operator: String,
+    /// 估算代价
+    pub cost: i64,
+    /// 估算行数
+    pub rows: i64,
+    /// 估算行宽
+    pub width: i64,
+    /// 操作符附加信息（`;` 之后的文本），无附加信息时为空
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub detail: String,
+    /// 子节点
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    /// 先序遍历整棵计划树
+    pub fn walk<'a>(&'a self, f: &mut impl FnMut(&'a Self)) {
+        f(self);
+        for child in &self.children {
+            child.walk(f);
+        }
+    }
+
+    /// 整棵计划树占用内存的估算值（字节）
+    #[must_use]
+    pub fn estimated_bytes(&self) -> usize {
+        size_of::<Self>()
+            + self.operator.capacity()
+            + self.detail.capacity()
+            + self.children.iter().map(Self::estimated_bytes).sum::<usize>()
+    }
+}
+
+lazy_static! {
+    /// 计划行：可选的行号前缀 + 缩进 + `#操作符: [代价, 行数, 行宽]` + 可选附加信息
+    static ref PLAN_LINE_RE: Regex = Regex::new(
+        r"^(?:\d+)?(\s*)#([A-Za-z_][A-Za-z0-9_]*):\s*\[\s*(-?\d+)\s*,\s*(-?\d+)\s*,\s*(-?\d+)\s*\]\s*;?\s*(.*?)\s*$"
+    )
+    .unwrap();
+}
+
+/// 从 description 中提取执行计划树
+///
+/// 识别连续的计划行并按缩进构造操作符树；description 中不含计划行时返回 `None`。
+/// 若存在多个顶层节点，后续顶层节点作为第一个节点的子节点挂载。
+#[must_use]
+pub fn extract_plan(description: &str) -> Option<PlanNode> {
+    // (缩进, 节点) 栈；栈底为根节点
+    let mut stack: Vec<(usize, PlanNode)> = Vec::new();
+
+    for line in description.lines() {
+        let Some(caps) = PLAN_LINE_RE.captures(line) else {
+            continue;
+        };
+        let indent = caps[1].len();
+        let node = PlanNode {
+            operator: caps[2].to_string(),
+            cost: caps[3].parse().unwrap_or(0),
+            rows: caps[4].parse().unwrap_or(0),
+            width: caps[5].parse().unwrap_or(0),
+            detail: caps[6].to_string(),
+            children: Vec::new(),
+        };
+
+        // 弹出缩进不小于当前行的节点（根节点保留）
+        while stack.len() > 1 && stack.last().is_some_and(|(i, _)| *i >= indent)
+        {
+            pop_into_parent(&mut stack);
+        }
+        stack.push((indent, node));
+    }
+
+    while stack.len() > 1 {
+        pop_into_parent(&mut stack);
+    }
+    stack.pop().map(|(_, root)| root)
+}
+
+fn pop_into_parent(stack: &mut Vec<(usize, PlanNode)>) {
+    if let Some((_, node)) = stack.pop() {
+        if let Some((_, parent)) = stack.last_mut() {
+            parent.children.push(node);
+        }
+    }
+}
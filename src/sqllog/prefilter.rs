@@ -0,0 +1,49 @@
+//! 原始段预过滤 - 在解析日志头之前按原始文本筛掉不需要的记录
+//!
+//! 定向提取（只要某个用户、只要带 `EXECTIME` 的记录等）时，大部分段最终都会
+//! 被丢弃，却仍要经过日志头正则与字段解析。[`Prefilter`] 在拼接完成的整段
+//! 原始文本（首行与续行）上做一次匹配，不匹配的段直接跳过：不产生记录，
+//! 也不上报格式错误。
+//!
+//! 匹配的是原始文本，`user:alice` 同样会命中 description 中出现的
+//! `user:alice`；需要精确条件时在解析后再按字段过滤。
+
+use regex::Regex;
+
+/// 原始段预过滤
+#[derive(Debug, Clone)]
+pub struct Prefilter {
+    regex: Regex,
+}
+
+impl Prefilter {
+    /// 以正则创建预过滤，段中任意位置匹配即保留
+    ///
+    /// # Errors
+    /// 正则无法编译时返回错误
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        Ok(Self { regex: Regex::new(pattern)? })
+    }
+
+    /// 包含任一给定文本即保留的预过滤
+    ///
+    /// # Errors
+    /// 文本过多导致正则超出大小限制时返回错误
+    pub fn any_of<S: AsRef<str>>(needles: &[S]) -> Result<Self, regex::Error> {
+        let alternatives: Vec<String> =
+            needles.iter().map(|n| regex::escape(n.as_ref())).collect();
+        Self::new(&alternatives.join("|"))
+    }
+
+    /// 段是否保留
+    #[must_use]
+    pub fn matches(&self, segment: &str) -> bool {
+        self.regex.is_match(segment)
+    }
+
+    /// 预过滤使用的正则
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        self.regex.as_str()
+    }
+}
@@ -0,0 +1,131 @@
+use crate::sqllog::{Sqllog, SqllogError};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+/// 记录 ID 生成方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecordIdMode {
+    /// 不生成记录 ID（`record_id` 为 `None`）
+    #[default]
+    Disabled,
+    /// 基于文件名、文件内序号与记录内容的 64 位 FNV-1a 哈希
+    Hash,
+    /// 雪花风格 ID：`发生时间毫秒(41 位) | 文件名哈希(10 位) | 同毫秒序号(12 位)`
+    ///
+    /// 同一文件内唯一：同一毫秒超过 4096 条记录或发生时间无法解析时解析
+    /// 失败。不同文件只靠 10 位文件名哈希区分，写入数据库时由 `record_id`
+    /// 的唯一约束检查冲突。
+    Snowflake,
+}
+
+impl std::str::FromStr for RecordIdMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" | "disabled" => Ok(Self::Disabled),
+            "hash" => Ok(Self::Hash),
+            "snowflake" => Ok(Self::Snowflake),
+            _ => Err(format!("不支持的记录 ID 生成方式: {s}")),
+        }
+    }
+}
+
+/// 雪花 ID 的时间起点：2020-01-01 00:00:00（毫秒时间戳）
+const SNOWFLAKE_EPOCH_MS: i64 = 1_577_836_800_000;
+
+/// 雪花 ID 中每毫秒可用的序号数（12 位）
+const SNOWFLAKE_SEQ_LIMIT: u64 = 1 << 12;
+
+pub(crate) const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// 64 位 FNV-1a 哈希
+pub(crate) fn fnv1a(hash: u64, bytes: &[u8]) -> u64 {
+    bytes.iter().fold(hash, |h, &b| (h ^ u64::from(b)).wrapping_mul(FNV_PRIME))
+}
+
+/// 单个文件的记录 ID 生成器
+///
+/// 同一文件按相同顺序解析时生成的 ID 完全一致；ID 只依赖文件名（不含目录），
+/// 文件移动位置后仍然稳定。
+#[derive(Debug, Clone)]
+pub struct RecordIdGenerator {
+    mode: RecordIdMode,
+    file_hash: u64,
+    seq: u64,
+    /// 发生时间毫秒 -> 已分配的序号数；记录时间可能乱序，按毫秒分别计数
+    ms_seq: HashMap<u64, u64>,
+}
+
+impl RecordIdGenerator {
+    /// 为指定文件名创建生成器
+    #[must_use]
+    pub fn new(mode: RecordIdMode, file_name: &str) -> Self {
+        Self {
+            mode,
+            file_hash: fnv1a(FNV_OFFSET, file_name.as_bytes()),
+            seq: 0,
+            ms_seq: HashMap::new(),
+        }
+    }
+
+    /// 按记录在文件中的顺序为其填充 `record_id`
+    ///
+    /// # Errors
+    /// 雪花 ID 无法保证唯一时（发生时间无法解析、同一毫秒超过 4096 条记录）
+    /// 返回 `SqllogError::RecordId`
+    pub fn assign(
+        &mut self,
+        records: &mut [Sqllog],
+    ) -> Result<(), SqllogError> {
+        for record in records {
+            record.record_id = match self.mode {
+                RecordIdMode::Disabled => None,
+                RecordIdMode::Hash => Some(self.hash_id(record)),
+                RecordIdMode::Snowflake => Some(self.snowflake_id(record)?),
+            };
+            self.seq += 1;
+        }
+        Ok(())
+    }
+
+    fn hash_id(&self, record: &Sqllog) -> u64 {
+        let mut h = fnv1a(self.file_hash, &self.seq.to_le_bytes());
+        h = fnv1a(h, record.occurrence_time.as_bytes());
+        for field in [&record.session, &record.thread, &record.trx_id] {
+            h = fnv1a(h, field.as_deref().unwrap_or("").as_bytes());
+        }
+        fnv1a(h, record.description.as_bytes())
+    }
+
+    fn snowflake_id(&mut self, record: &Sqllog) -> Result<u64, SqllogError> {
+        let error = |reason: String| SqllogError::RecordId {
+            line: record.line,
+            reason,
+        };
+        let time = NaiveDateTime::parse_from_str(
+            &record.occurrence_time,
+            "%Y-%m-%d %H:%M:%S%.3f",
+        )
+        .map_err(|e| {
+            error(format!("发生时间 {} 无法解析: {e}", record.occurrence_time))
+        })?;
+        let ms = u64::try_from(
+            (time.and_utc().timestamp_millis() - SNOWFLAKE_EPOCH_MS).max(0),
+        )
+        .unwrap_or(0)
+            & ((1 << 41) - 1);
+
+        let seq = self.ms_seq.entry(ms).or_insert(0);
+        if *seq >= SNOWFLAKE_SEQ_LIMIT {
+            return Err(error(format!(
+                "发生时间 {} 的记录超过 {SNOWFLAKE_SEQ_LIMIT} 条",
+                record.occurrence_time
+            )));
+        }
+        let id = (ms << 22) | ((self.file_hash & 0x3ff) << 12) | *seq;
+        *seq += 1;
+        Ok(id)
+    }
+}
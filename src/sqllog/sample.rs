@@ -0,0 +1,106 @@
+use crate::sqllog::Sqllog;
+use crate::sqllog::record_id::{FNV_OFFSET, fnv1a};
+
+/// 抽样所依据的记录字段
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SampleKey {
+    /// 会话 ID，保留被抽中会话的完整轨迹
+    #[default]
+    Session,
+    /// 用户名
+    User,
+    /// 事务 ID
+    Trx,
+}
+
+impl std::str::FromStr for SampleKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "session" | "sess" => Ok(Self::Session),
+            "user" => Ok(Self::User),
+            "trx" | "trxid" | "trx_id" => Ok(Self::Trx),
+            _ => Err(format!("不支持的抽样字段: {s}")),
+        }
+    }
+}
+
+/// 抽样比例的分桶数，比例精确到 0.01%
+const SAMPLE_BUCKETS: u64 = 10_000;
+
+/// 按字段哈希的确定性抽样
+///
+/// 对记录的抽样字段（默认会话 ID）做 64 位 FNV-1a 哈希后分桶，落在前
+/// `rate` 比例桶内的取值被保留。同一取值的记录要么全部保留、要么全部丢弃，
+/// 因而保留下来的会话轨迹是完整的；相同的字段、比例与种子在不同文件、
+/// 不同运行之间抽中的集合一致。缺少该字段的记录按空字符串参与分桶。
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeySample {
+    key: SampleKey,
+    rate: f64,
+    seed: u64,
+    buckets: u64,
+}
+
+impl KeySample {
+    /// 创建按 `key` 保留 `rate` 比例（`(0, 1]`）取值的抽样
+    ///
+    /// # Errors
+    /// 当 `rate` 不在 `(0, 1]` 内时返回错误
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        clippy::cast_precision_loss
+    )]
+    pub fn new(key: SampleKey, rate: f64) -> Result<Self, String> {
+        if !(rate > 0.0 && rate <= 1.0) {
+            return Err(format!("抽样比例必须在 (0, 1] 内: {rate}"));
+        }
+        let buckets = ((rate * SAMPLE_BUCKETS as f64).round() as u64).max(1);
+        Ok(Self { key, rate, seed: 0, buckets })
+    }
+
+    /// 设置哈希种子；换一个种子即换一批被抽中的取值
+    #[must_use]
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// 抽样字段
+    #[must_use]
+    pub fn key(&self) -> SampleKey {
+        self.key
+    }
+
+    /// 抽样比例
+    #[must_use]
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// 哈希种子
+    #[must_use]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// 判断记录是否被抽中
+    #[must_use]
+    pub fn keeps(&self, record: &Sqllog) -> bool {
+        let value = match self.key {
+            SampleKey::Session => &record.session,
+            SampleKey::User => &record.user,
+            SampleKey::Trx => &record.trx_id,
+        };
+        let h = fnv1a(FNV_OFFSET, &self.seed.to_le_bytes());
+        let h = fnv1a(h, value.as_deref().unwrap_or("").as_bytes());
+        h % SAMPLE_BUCKETS < self.buckets
+    }
+
+    /// 就地丢弃未被抽中的记录
+    pub fn retain(&self, records: &mut Vec<Sqllog>) {
+        records.retain(|record| self.keeps(record));
+    }
+}
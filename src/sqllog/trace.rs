@@ -0,0 +1,120 @@
+//! 非 sqllog 行识别 - 处理混入 sqllog 文件的 trace/诊断行
+//!
+//! 部分实例会把服务器日志、trace 等诊断输出写进 sqllog 文件。这些行同样以
+//! 时间戳开头，会被当作新记录的首行，再因日志头不匹配而产生大量格式错误。
+//! [`TraceLines`] 在格式错误上报前按规则识别这些段（只匹配首行），
+//! 按 [`TraceLineMode`] 保持原样上报、直接跳过，或以
+//! [`SqllogError::TraceLine`](crate::sqllog::SqllogError::TraceLine)
+//! 带分类单独上报，并按分类计数。
+
+use regex::Regex;
+use std::collections::BTreeMap;
+
+/// 识别到非 sqllog 行后的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TraceLineMode {
+    /// 不识别，按普通格式错误上报（默认）
+    #[default]
+    Error,
+    /// 跳过，只按分类计数
+    Skip,
+    /// 以带分类的 `SqllogError::TraceLine` 上报
+    Collect,
+}
+
+impl std::str::FromStr for TraceLineMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "error" | "none" => Ok(Self::Error),
+            "skip" => Ok(Self::Skip),
+            "collect" => Ok(Self::Collect),
+            _ => Err(format!("不支持的非 sqllog 行处理方式: {s}")),
+        }
+    }
+}
+
+/// 时间戳前缀，与 `is_first_row` 的格式一致
+const TS: &str = r"^\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2}\.\d{3}\s+";
+
+/// 内置识别规则：（分类, 首行正则）
+const BUILTIN_RULES: [(&str, &str); 2] = [
+    // dmserver 运行日志：时间戳后紧跟级别
+    ("server", r"\[(?:INFO|WARNING|ERROR|FATAL)\]"),
+    // trace/调试输出
+    ("trace", r"(?i)\[?(?:TRACE|TRC|DEBUG)\b"),
+];
+
+/// 一条识别规则
+#[derive(Debug, Clone)]
+pub struct TraceRule {
+    pub category: String,
+    pattern: Regex,
+}
+
+/// 非 sqllog 行的识别规则与处理方式
+#[derive(Debug, Clone)]
+pub struct TraceLines {
+    pub mode: TraceLineMode,
+    rules: Vec<TraceRule>,
+}
+
+impl TraceLines {
+    /// 使用内置规则创建
+    ///
+    /// # Panics
+    /// 内置正则均为常量，不会失败
+    #[must_use]
+    pub fn new(mode: TraceLineMode) -> Self {
+        let rules = BUILTIN_RULES
+            .iter()
+            .map(|(category, body)| TraceRule {
+                category: (*category).to_string(),
+                pattern: Regex::new(&format!("{TS}{body}")).unwrap(),
+            })
+            .collect();
+        Self { mode, rules }
+    }
+
+    /// 追加自定义规则，`pattern` 匹配时间戳之后的首行内容
+    ///
+    /// # Errors
+    /// 正则无效时返回错误
+    pub fn with_rule(
+        mut self,
+        category: &str,
+        pattern: &str,
+    ) -> Result<Self, regex::Error> {
+        self.rules.push(TraceRule {
+            category: category.to_string(),
+            pattern: Regex::new(&format!("{TS}(?:{pattern})"))?,
+        });
+        Ok(self)
+    }
+
+    /// 是否启用识别
+    #[must_use]
+    pub fn enabled(&self) -> bool {
+        self.mode != TraceLineMode::Error
+    }
+
+    /// 按首行识别段的分类，不是已知的非 sqllog 行时返回 `None`
+    #[must_use]
+    pub fn classify(&self, segment: &str) -> Option<&str> {
+        let first = segment.lines().next().unwrap_or("");
+        self.rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(first))
+            .map(|rule| rule.category.as_str())
+    }
+}
+
+impl Default for TraceLines {
+    fn default() -> Self {
+        Self::new(TraceLineMode::default())
+    }
+}
+
+/// 按分类统计的非 sqllog 行数
+pub type TraceCounts = BTreeMap<String, u64>;
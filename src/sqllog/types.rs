@@ -1,11 +1,17 @@
+use base64::Engine;
 use core::num;
-use std::{io, result, str};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::{io, mem::size_of, ops::Range, result, str, time::Duration};
 use thiserror::Error;
 
+use crate::sqllog::plan::PlanNode;
+use crate::sqllog::units::{ExecId, ExecTimeMs, RowCount};
+
 /// 通用结果类型，统一错误处理
 pub type SResult<T> = result::Result<T, SqllogError>;
 
-// 简短类型别名，表示 description 中解析出的三个可选数字
+// 简短类型别名，表示 description 中解析出的三个可选数字：
+// 执行时间（微秒）、影响行数、执行 ID
 pub type DescNumbers = (Option<i64>, Option<i64>, Option<i64>);
 
 /// 日志解析相关错误类型
@@ -19,6 +25,14 @@ pub enum SqllogError {
     #[error("UTF8解码错误: {0}")]
     Utf8(#[from] str::Utf8Error),
 
+    /// 日志行无法按 UTF8 解码，保留原始字节及其在源文件中的位置
+    #[error(
+        "UTF8解码错误: {source}（字节 {}..{}）",
+        .raw.offset.start,
+        .raw.offset.end
+    )]
+    Undecodable { source: str::Utf8Error, raw: RawSegment },
+
     /// 正则表达式解析错误
     #[error("正则解析错误: {0}")]
     Regex(#[from] regex::Error),
@@ -27,15 +41,102 @@ pub enum SqllogError {
     #[error("字段解析错误: {0}")]
     ParseInt(#[from] num::ParseIntError),
 
-    /// 日志格式错误，包含行号和内容
+    /// 日志格式错误，包含行号、字节偏移（流式解析时）和内容
     #[error("日志格式错误: 行{line}: {content}")]
-    Format { line: usize, content: String },
+    Format { line: usize, offset: Option<u64>, content: String },
+
+    /// 已识别的非 sqllog 行（trace/诊断输出），仅在
+    /// `TraceLineMode::Collect` 下上报
+    #[error("非 sqllog 行（{category}）: 行{line}")]
+    TraceLine { line: usize, category: String },
+
+    /// 单文件解析超时
+    #[error("解析超时: 超过 {0:?} 后放弃该文件剩余内容")]
+    Timeout(Duration),
+
+    /// 文件开头的解析错误率超过熔断上限（见 `ErrorBreaker`），解析已停止
+    #[error(
+        "解析错误率熔断: 文件开头 {bytes} 字节中 {errors} 个错误、{records} 条记录，超过上限 {max_ratio}，解析已停止"
+    )]
+    ErrorRateExceeded {
+        errors: usize,
+        records: usize,
+        bytes: u64,
+        max_ratio: f64,
+    },
+
+    /// 无法为记录生成唯一的记录 ID，解析已停止
+    #[error("记录 ID 生成失败: 行{line}: {reason}")]
+    RecordId { line: usize, reason: String },
 
     /// 其他未知错误
     #[error("未知错误: {0}")]
     Other(String),
 }
 
+impl SqllogError {
+    /// 错误携带的原始字节片段（仅无法解码的行）
+    #[must_use]
+    pub const fn raw_segment(&self) -> Option<&RawSegment> {
+        match self {
+            Self::Undecodable { raw, .. } => Some(raw),
+            _ => None,
+        }
+    }
+
+    /// 出错内容在源文件中的起始字节偏移（格式错误与无法解码的行）
+    #[must_use]
+    pub const fn byte_offset(&self) -> Option<u64> {
+        match self {
+            Self::Format { offset, .. } => *offset,
+            Self::Undecodable { raw, .. } => Some(raw.offset.start),
+            _ => None,
+        }
+    }
+}
+
+/// 源文件中的一段原始字节
+///
+/// 序列化为 `{"bytes": base64, "start": 起始偏移, "end": 结束偏移}`，
+/// 偏移为相对文件开头的字节位置（左闭右开），可据此从源文件精确截取重放。
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawSegment {
+    /// 原始字节（包含行尾换行符）
+    pub bytes: Vec<u8>,
+    /// 在源文件中的字节范围
+    pub offset: Range<u64>,
+}
+
+impl RawSegment {
+    /// 以文件偏移 `start` 处的字节创建片段
+    #[must_use]
+    pub fn new(bytes: &[u8], start: u64) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+            offset: start..start + bytes.len() as u64,
+        }
+    }
+
+    /// 原始字节的标准 base64 编码
+    #[must_use]
+    pub fn to_base64(&self) -> String {
+        base64::engine::general_purpose::STANDARD.encode(&self.bytes)
+    }
+}
+
+impl Serialize for RawSegment {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("RawSegment", 3)?;
+        state.serialize_field("bytes", &self.to_base64())?;
+        state.serialize_field("start", &self.offset.start)?;
+        state.serialize_field("end", &self.offset.end)?;
+        state.end()
+    }
+}
+
 /// 每月天数（非闰年），用于日期合法性校验
 pub const DAYS_IN_MONTH: [u8; 12] =
     [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
@@ -65,10 +166,49 @@ pub struct Sqllog {
     pub sql_type: Option<String>,
     /// 语句描述（原始文本）
     pub description: String,
-    /// 执行时间（毫秒）
-    pub execute_time: Option<i64>,
+    /// 执行时间（毫秒，亚毫秒部分截断）
+    pub execute_time: Option<ExecTimeMs>,
+    /// 执行时间（微秒），按日志中的单位换算，精度高于 `execute_time`
+    pub execute_time_us: Option<i64>,
     /// 影响行数
-    pub rowcount: Option<i64>,
+    pub rowcount: Option<RowCount>,
     /// 执行 ID
-    pub execute_id: Option<i64>,
+    pub execute_id: Option<ExecId>,
+    /// 记录 ID（仅在启用 `ParseOptions::record_id` 时生成）
+    pub record_id: Option<u64>,
+    /// 从 description 中提取的执行计划（仅在启用 `ParseOptions::extract_plans` 时填充）
+    pub plan: Option<PlanNode>,
+    /// 是否为宽松模式下从不完整的日志头恢复出的记录（缺失的字段为 `None`）
+    pub partial: bool,
+    /// 记录首行在源文件中的行号（从 1 开始，全文件计数）
+    pub line: usize,
+    /// 记录首行在源文件中的字节偏移（仅流式解析文件时填充）
+    pub byte_offset: Option<u64>,
+}
+
+impl Sqllog {
+    /// 单条记录占用内存的估算值（字节）
+    ///
+    /// 包括结构体本身与各字符串字段、执行计划的堆内存，用于管道的内存记账。
+    #[must_use]
+    pub fn estimated_bytes(&self) -> usize {
+        let optional = [
+            &self.session,
+            &self.thread,
+            &self.user,
+            &self.trx_id,
+            &self.statement,
+            &self.appname,
+            &self.ip,
+            &self.sql_type,
+        ];
+        size_of::<Self>()
+            + self.occurrence_time.capacity()
+            + self.description.capacity()
+            + optional
+                .iter()
+                .filter_map(|s| s.as_ref().map(String::capacity))
+                .sum::<usize>()
+            + self.plan.as_ref().map_or(0, PlanNode::estimated_bytes)
+    }
 }
@@ -0,0 +1,193 @@
+//! 带单位的数值类型 - 执行时间、影响行数与执行 ID
+//!
+//! 三者在日志里都是整数，直接用 `i64` 时很容易把毫秒与行数相加、或对执行 ID
+//! 做算术。这里用新类型区分语义：
+//! - `ExecTimeMs` 为时长，支持同类加减、求和及与微秒 / `Duration` 的换算
+//! - `RowCount` 为计数，支持同类加减与求和
+//! - `ExecId` 为标识符，只能比较，不提供算术运算
+//!
+//! 序列化时均为透明的整数。
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Sub};
+use std::time::Duration;
+
+/// 为数值新类型实现构造、取值、同类加减与求和
+macro_rules! numeric_newtype {
+    ($name:ident) => {
+        impl $name {
+            /// 以原始整数构造
+            #[must_use]
+            pub const fn new(value: i64) -> Self {
+                Self(value)
+            }
+
+            /// 原始整数值
+            #[must_use]
+            pub const fn get(self) -> i64 {
+                self.0
+            }
+        }
+
+        impl From<i64> for $name {
+            fn from(value: i64) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$name> for i64 {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl Add for $name {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self {
+                Self(self.0.saturating_add(rhs.0))
+            }
+        }
+
+        impl AddAssign for $name {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl Sub for $name {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self {
+                Self(self.0.saturating_sub(rhs.0))
+            }
+        }
+
+        impl Sum for $name {
+            fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+                iter.fold(Self::default(), Add::add)
+            }
+        }
+    };
+}
+
+/// 执行时间（毫秒）
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct ExecTimeMs(i64);
+
+numeric_newtype!(ExecTimeMs);
+
+impl ExecTimeMs {
+    /// 由微秒换算（亚毫秒部分截断）
+    #[must_use]
+    pub const fn from_micros(us: i64) -> Self {
+        Self(us / 1000)
+    }
+
+    /// 换算为微秒
+    #[must_use]
+    pub const fn as_micros(self) -> i64 {
+        self.0.saturating_mul(1000)
+    }
+
+    /// 换算为 `Duration`（负值视为零）
+    #[must_use]
+    pub fn as_duration(self) -> Duration {
+        Duration::from_millis(u64::try_from(self.0).unwrap_or(0))
+    }
+}
+
+impl fmt::Display for ExecTimeMs {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}ms", self.0)
+    }
+}
+
+/// 影响行数
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct RowCount(i64);
+
+numeric_newtype!(RowCount);
+
+impl fmt::Display for RowCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// 执行 ID
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Serialize,
+    Deserialize,
+)]
+#[serde(transparent)]
+pub struct ExecId(i64);
+
+impl ExecId {
+    /// 以原始整数构造
+    #[must_use]
+    pub const fn new(value: i64) -> Self {
+        Self(value)
+    }
+
+    /// 原始整数值
+    #[must_use]
+    pub const fn get(self) -> i64 {
+        self.0
+    }
+}
+
+impl From<i64> for ExecId {
+    fn from(value: i64) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ExecId> for i64 {
+    fn from(value: ExecId) -> Self {
+        value.0
+    }
+}
+
+impl fmt::Display for ExecId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
@@ -7,66 +7,88 @@ pub const fn is_leap_year(year: u16) -> bool {
     (year.trailing_zeros() >= 2 && year % 100 != 0) || year % 400 == 0
 }
 
-/// 判断一行是否为 SQL 日志的首行（时间戳格式）
-#[must_use]
-pub fn is_first_row(s: &str) -> bool {
-    // 首先检查长度是否正确 (23个字符)
-    if s.len() != 23 {
-        return false;
-    }
+/// 时间戳 `YYYY-MM-DD HH:MM:SS.mmm` 的长度
+const TS_LEN: usize = 23;
 
-    let b = s.as_bytes();
+/// 按 8 字节分组检查时间戳：（组起始位置, 分隔符模板, 数字位掩码）
+///
+/// 第三组与第二组重叠一个字节，三组覆盖全部 23 个字节。
+const TS_WORDS: [(usize, u64, u64); 3] = [
+    (0, ts_word(*b"0000-00-"), digit_mask(*b"0000-00-")),
+    (8, ts_word(*b"00 00:00"), digit_mask(*b"00 00:00")),
+    (15, ts_word(*b"0:00.000"), digit_mask(*b"0:00.000")),
+];
+
+const fn ts_word(bytes: [u8; 8]) -> u64 {
+    u64::from_le_bytes(bytes)
+}
 
-    // 检查所有分隔符位置
-    if !(b[4] == b'-'
-        && b[7] == b'-'
-        && b[10] == b' '
-        && b[13] == b':'
-        && b[16] == b':'
-        && b[19] == b'.')
-    {
-        return false;
+/// 模板中为 `0` 的字节位置对应 `0xFF`，其余为 `0`
+const fn digit_mask(bytes: [u8; 8]) -> u64 {
+    let mut mask = 0u64;
+    let mut i = 0;
+    while i < 8 {
+        if bytes[i] == b'0' {
+            mask |= 0xFF << (i * 8);
+        }
+        i += 1;
     }
+    mask
+}
 
-    // 检查所有数字位
-    // 年
-    if !b[0].is_ascii_digit()
-        || !b[1].is_ascii_digit()
-        || !b[2].is_ascii_digit()
-        || !b[3].is_ascii_digit()
-    {
-        return false;
-    }
+/// 8 字节内的时间戳格式检查：数字位均为 ASCII 数字，分隔符位与模板一致
+///
+/// 数字判断：高半字节为 `3`，且加 6 后仍不进位到高半字节（`0x30..=0x39`）。
+/// 第一步保证每个字节不超过 `0x3F`，加 6 不会跨字节进位。
+#[inline]
+const fn ts_word_ok(word: u64, template: u64, digits: u64) -> bool {
+    const HIGH: u64 = 0xF0F0_F0F0_F0F0_F0F0;
+    const ZEROS: u64 = 0x3030_3030_3030_3030;
+    const SIXES: u64 = 0x0606_0606_0606_0606;
+    // 分隔符位置替换为 '0'，只对数字位做判断
+    let d = (word & digits) | (ZEROS & !digits);
+    (word & !digits) == (template & !digits)
+        && (d & HIGH) == ZEROS
+        && ((d + SIXES) & HIGH) == ZEROS
+}
 
-    // 月日时分秒毫秒
-    if !b[5].is_ascii_digit()
-        || !b[6].is_ascii_digit()
-        || !b[8].is_ascii_digit()
-        || !b[9].is_ascii_digit()
-        || !b[11].is_ascii_digit()
-        || !b[12].is_ascii_digit()
-        || !b[14].is_ascii_digit()
-        || !b[15].is_ascii_digit()
-        || !b[17].is_ascii_digit()
-        || !b[18].is_ascii_digit()
-        || !b[20].is_ascii_digit()
-        || !b[21].is_ascii_digit()
-        || !b[22].is_ascii_digit()
-    {
+#[inline]
+fn two_digits(b: &[u8], i: usize) -> u8 {
+    (b[i] - b'0') * 10 + (b[i + 1] - b'0')
+}
+
+/// 判断一行是否为 SQL 日志的首行（时间戳格式）
+#[must_use]
+pub fn is_first_row(s: &str) -> bool {
+    is_first_row_bytes(s.as_bytes())
+}
+
+/// 与 [`is_first_row`] 相同，但直接检查字节（长度须恰为 23）
+///
+/// 格式检查按 8 字节一组整体比较，无逐字节分支；格式通过后再校验各字段的
+/// 取值范围。
+#[must_use]
+pub fn is_first_row_bytes(b: &[u8]) -> bool {
+    let Ok(b) = <&[u8; TS_LEN]>::try_from(b) else {
         return false;
+    };
+
+    for (start, template, digits) in TS_WORDS {
+        let mut word = [0u8; 8];
+        word.copy_from_slice(&b[start..start + 8]);
+        if !ts_word_ok(u64::from_le_bytes(word), template, digits) {
+            return false;
+        }
     }
 
     // 年份合法性校验
-    let year = u16::from(b[0] - b'0') * 1000
-        + u16::from(b[1] - b'0') * 100
-        + u16::from(b[2] - b'0') * 10
-        + u16::from(b[3] - b'0');
+    let year = u16::from(two_digits(b, 0)) * 100 + u16::from(two_digits(b, 2));
     if year == 0 {
         return false;
     }
 
     // 月份合法性校验
-    let month = (b[5] - b'0') * 10 + (b[6] - b'0');
+    let month = two_digits(b, 5);
     if month == 0 || month > 12 {
         return false;
     }
@@ -78,50 +100,53 @@ pub fn is_first_row(s: &str) -> bool {
     }
 
     // 日期合法性校验
-    let day = (b[8] - b'0') * 10 + (b[9] - b'0');
+    let day = two_digits(b, 8);
     if day == 0 || day > max_days {
         return false;
     }
 
     // 时分秒合法性校验
-    let hour = (b[11] - b'0') * 10 + (b[12] - b'0');
-    let minute = (b[14] - b'0') * 10 + (b[15] - b'0');
-    let second = (b[17] - b'0') * 10 + (b[18] - b'0');
-
-    // 一次性检查所有时间字段
-    hour <= 23 && minute <= 59 && second <= 59
+    two_digits(b, 11) <= 23
+        && two_digits(b, 14) <= 59
+        && two_digits(b, 17) <= 59
 }
 
 /// 在给定字符串中查找第一个符合首行时间戳格式的位置（返回起始索引）。
 ///
+/// 以 `memchr` 定位每个 `-`，只在其前 4 个字节处（年份之后的 `-`）检查
+/// 时间戳，不再逐位置比较。
+///
 /// 参数：
 /// - `s`：待搜索的字符串（可能包含多行）。
 ///
 /// 返回：找到则返回 `Some(index)`，否则返回 `None`。
 #[must_use]
 pub fn find_first_row_pos(s: &str) -> Option<usize> {
-    if s.len() < 23 {
-        return None;
-    }
-    (0..=s.len().saturating_sub(23)).find(|&i| is_first_row(&s[i..i + 23]))
+    let bytes = s.as_bytes();
+    memchr::memchr_iter(b'-', bytes)
+        .filter_map(|dash| dash.checked_sub(4))
+        .find(|&i| bytes.get(i..i + TS_LEN).is_some_and(is_first_row_bytes))
 }
 
 /// 将读取到的字节转换为字符串（尽可能为 Borrowed），并在遇到无效 UTF-8 时记录错误。
 ///
 /// 行为说明：
 /// - 如果字节序列是有效的 UTF-8，则返回 `Cow::Borrowed(&str)`，避免额外分配。
-/// - 若遇到无效 UTF-8，会将错误以 `(line_num, brief_msg, SqllogError::Utf8(_))` 的形式添加到 `errors`，
+/// - 若遇到无效 UTF-8，会将错误以 `(line_num, brief_msg, SqllogError::Undecodable { .. })`
+///   的形式添加到 `errors`（携带原始字节与文件偏移），
 ///   并返回一个经过修复与重同步的 owned `String`（`Cow::Owned`）。
 ///
 /// 参数：
 /// - `line_bytes`：原始行字节切片（可能包含换行符）。
 /// - `line_num`：当前行号（用于错误记录）。
+/// - `offset`：该行在源文件中的起始字节偏移。
 /// - `errors`：用于收集解析期间遇到的错误条目。
 ///
 /// 返回：`Cow<'a, str>`，在无错误时尽量返回 Borrowed，否则返回 Owned。
 pub fn line_bytes_to_str_impl<'a>(
     line_bytes: &'a [u8],
     line_num: usize,
+    offset: u64,
     errors: &mut Vec<(usize, String, SqllogError)>,
 ) -> Cow<'a, str> {
     match str::from_utf8(line_bytes) {
@@ -135,7 +160,12 @@ pub fn line_bytes_to_str_impl<'a>(
             if prefix_len < line_bytes.len() {
                 err_msg.push_str("...");
             }
-            errors.push((line_num, err_msg, SqllogError::Utf8(e)));
+            let raw = types::RawSegment::new(line_bytes, offset);
+            errors.push((
+                line_num,
+                err_msg,
+                SqllogError::Undecodable { source: e, raw },
+            ));
 
             // 使用 lossy 转换得到 owned String，可就地重同步以避免额外分配
             let mut s = String::from_utf8_lossy(line_bytes).into_owned();
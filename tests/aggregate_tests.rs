@@ -0,0 +1,232 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::analysis::{
+    AggregateQuery, Aggregator, QueryError, Value,
+};
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::AnalyzeRunner;
+use sqllog_analysis::sqllog::{ExecTimeMs, Sqllog};
+use std::io::Write;
+use std::path::PathBuf;
+
+mod common;
+
+fn record(user: &str, execute_time: i64) -> Sqllog {
+    Sqllog {
+        user: Some(user.to_string()),
+        execute_time: Some(ExecTimeMs::new(execute_time)),
+        ..common::record("")
+    }
+}
+
+fn sample() -> Vec<Sqllog> {
+    let mut records: Vec<Sqllog> = (1..=100).map(|t| record("A", t)).collect();
+    records.extend((1..=3).map(|t| record("B", t * 10)));
+    records.push(Sqllog { user: None, ..record("", 7) });
+    records
+}
+
+fn run(
+    select: &str,
+    group_by: &str,
+    having: &str,
+    order_by: &str,
+) -> Vec<Vec<Value>> {
+    let query = AggregateQuery::parse(select, group_by, having, order_by)
+        .expect("query parses");
+    let mut agg = Aggregator::new(query);
+    agg.observe(&sample());
+    agg.finish().rows
+}
+
+#[test]
+fn group_by_user_with_percentile() {
+    let rows = run(
+        "user, count(*), p95(execute_time), avg(execute_time)",
+        "user",
+        "",
+        "",
+    );
+    assert_eq!(rows.len(), 3);
+    // 分组键升序，空值在前
+    assert_eq!(rows[0][0], Value::Null);
+    assert_eq!(
+        rows[1],
+        vec![
+            Value::Text("A".into()),
+            Value::Int(100),
+            Value::Int(95),
+            Value::Float(50.5),
+        ]
+    );
+    assert_eq!(rows[2][1], Value::Int(3));
+    assert_eq!(rows[2][2], Value::Int(30));
+}
+
+#[test]
+fn having_order_by_and_limit() {
+    let rows = run("user, count(*)", "user", "count(*) > 2", "2 desc");
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Text("A".into()), Value::Int(100)],
+            vec![Value::Text("B".into()), Value::Int(3)],
+        ]
+    );
+
+    let rows = run(
+        "user, max(execute_time)",
+        "user",
+        "count(*) >= 1 and sum(execute_time) < 100",
+        "user desc",
+    );
+    assert_eq!(
+        rows,
+        vec![
+            vec![Value::Text("B".into()), Value::Int(30)],
+            vec![Value::Null, Value::Int(7)],
+        ]
+    );
+
+    let query =
+        AggregateQuery::parse("user, count(*)", "user", "", "count(*) desc")
+            .unwrap()
+            .with_limit(Some(1));
+    let mut agg = Aggregator::new(query);
+    agg.observe(&sample());
+    let result = agg.finish();
+    assert_eq!(result.columns, vec!["user", "count(*)"]);
+    assert_eq!(result.rows.len(), 1);
+    assert!(
+        result
+            .to_string()
+            .starts_with("user  count(*)\n----  --------\nA     100\n")
+    );
+}
+
+#[test]
+fn global_aggregate_without_records() {
+    let query =
+        AggregateQuery::parse("count(*), min(execute_time)", "", "", "")
+            .unwrap();
+    let rows = Aggregator::new(query).finish().rows;
+    assert_eq!(rows, vec![vec![Value::Int(0), Value::Null]]);
+}
+
+#[test]
+fn count_column_skips_nulls() {
+    let rows = run("count(user), count(*)", "", "", "");
+    assert_eq!(rows, vec![vec![Value::Int(103), Value::Int(104)]]);
+}
+
+#[test]
+fn invalid_queries_are_rejected() {
+    let parse =
+        |s: &str, g: &str, h: &str, o: &str| AggregateQuery::parse(s, g, h, o);
+    assert_eq!(
+        parse("nope, count(*)", "", "", ""),
+        Err(QueryError::UnknownColumn("nope".into()))
+    );
+    assert_eq!(
+        parse("median(execute_time)", "", "", ""),
+        Err(QueryError::UnknownFunction("median".into()))
+    );
+    assert!(matches!(
+        parse("sum(user)", "", "", ""),
+        Err(QueryError::NotNumeric { .. })
+    ));
+    assert!(matches!(
+        parse("user, count(*)", "", "", ""),
+        Err(QueryError::Invalid(_))
+    ));
+    assert!(matches!(parse("user", "", "", ""), Err(QueryError::Invalid(_))));
+    assert!(matches!(
+        parse("user, count(*)", "user", "", "3"),
+        Err(QueryError::Invalid(_))
+    ));
+    assert!(matches!(
+        parse("user, count(*)", "user", "count(*) >", ""),
+        Err(QueryError::Syntax(_))
+    ));
+    assert!(matches!(parse("sum(*)", "", "", ""), Err(QueryError::Syntax(_))));
+}
+
+fn write_log(dir: &std::path::Path, name: &str, users: &[&str]) -> PathBuf {
+    let path = dir.join(name);
+    let mut file = std::fs::File::create(&path).unwrap();
+    for (i, user) in users.iter().enumerate() {
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.{i:03} (EP[{ep}] sess:NULL thrd:1 user:{user} trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: {t}(ms) ROWCOUNT: 1 EXEC_ID: {i}.",
+            ep = i % 2,
+            t = i * 3 + 1,
+        )
+        .unwrap();
+    }
+    path
+}
+
+#[test]
+fn spilled_aggregation_matches_in_memory() {
+    let dir = tempfile::tempdir().unwrap();
+    let spill_dir = tempfile::tempdir().unwrap();
+    let mut users = vec!["A"; 40];
+    users.extend(["B"; 7]);
+    users.extend(["C"; 2]);
+    let files = vec![
+        write_log(dir.path(), "dmsql_1.log", &users),
+        write_log(dir.path(), "dmsql_2.log", &users[5..]),
+    ];
+    let runtime = RuntimeConfig {
+        analyze_temp_dir: Some(spill_dir.path().to_path_buf()),
+        ..Default::default()
+    };
+
+    let queries = [
+        (
+            "user, count(*), p95(execute_time), avg(execute_time), sum(rowcount), min(execute_id)",
+            "user",
+            "",
+            "",
+        ),
+        ("ep, count(*), max(execute_time)", "ep", "count(*) > 10", "2 desc"),
+        ("user, p50(execute_time)", "user", "", "p50(execute_time) desc, user"),
+        ("count(*), count(user), p99(execute_time)", "", "", ""),
+    ];
+    for (select, group_by, having, order_by) in queries {
+        let query =
+            AggregateQuery::parse(select, group_by, having, order_by).unwrap();
+        let memory =
+            AnalyzeRunner::new(&runtime, query.clone()).run(&files).unwrap();
+        let spilled = AnalyzeRunner::new(&runtime, query)
+            .with_memory_limit(1)
+            .run(&files)
+            .unwrap();
+
+        assert!(!memory.spilled);
+        assert!(spilled.spilled);
+        assert_eq!(memory.records, 93);
+        assert_eq!(spilled.records, memory.records);
+        assert_eq!(spilled.result, memory.result, "query: {select}");
+    }
+    // 临时数据库在聚合完成后被删除
+    assert_eq!(std::fs::read_dir(spill_dir.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn query_translates_to_sql() {
+    let query = AggregateQuery::parse(
+        "user, count(*), p95(execute_time)",
+        "user",
+        "count(*) > 100",
+        "2 desc",
+    )
+    .unwrap()
+    .with_limit(Some(5));
+    assert_eq!(
+        query.to_sql("sqllogs"),
+        "SELECT username, COUNT(*), quantile_disc(execute_time, 0.95) FROM sqllogs \
+         GROUP BY username HAVING COUNT(*) > 100 \
+         ORDER BY 2 DESC NULLS LAST, username ASC NULLS FIRST LIMIT 5"
+    );
+}
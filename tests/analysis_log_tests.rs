@@ -1,5 +1,7 @@
+#![cfg(feature = "bin")]
+
 use log::LevelFilter;
-use sqllog_analysis::analysis_log::LogConfig;
+use sqllog_analysis::analysis_log::{LogConfig, Verbosity};
 use std::path::PathBuf;
 use tempfile::tempdir;
 
@@ -10,6 +12,9 @@ fn test_logconfig_init_disabled_no_panic() {
         level: LevelFilter::Info,
         log_file: None,
         enable_stdout: false,
+        stdout_level: None,
+        otlp_endpoint: None,
+        otlp_service_name: None,
     };
     cfg.init().unwrap();
 }
@@ -23,8 +28,42 @@ fn test_logconfig_init_with_dir_creates_logs_dir() {
         level: LevelFilter::Info,
         log_file: Some(p.clone()),
         enable_stdout: false,
+        stdout_level: None,
+        otlp_endpoint: None,
+        otlp_service_name: None,
     };
     cfg.init().unwrap();
     // directory should exist (logs file inside)
     assert!(p.exists());
 }
+
+fn args(list: &[&str]) -> Vec<String> {
+    list.iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn verbosity_flags_are_taken_from_args() {
+    let mut list = args(&["-vv", "inspect", "/logs", "-v", "--json"]);
+    assert_eq!(Verbosity::take_from_args(&mut list), Ok(Verbosity::Verbose(3)));
+    assert_eq!(list, args(&["inspect", "/logs", "--json"]));
+
+    let mut list = args(&["--quiet", "--format", "csv"]);
+    assert_eq!(Verbosity::take_from_args(&mut list), Ok(Verbosity::Quiet));
+    assert_eq!(list, args(&["--format", "csv"]));
+
+    let mut list = args(&["-q", "--verbose"]);
+    assert!(Verbosity::take_from_args(&mut list).is_err());
+}
+
+#[test]
+fn verbosity_overrides_log_config() {
+    let mut cfg = LogConfig { level: LevelFilter::Warn, ..Default::default() };
+    Verbosity::Verbose(2).apply(&mut cfg);
+    assert!(cfg.enable_stdout);
+    assert_eq!(cfg.level, LevelFilter::Debug);
+
+    let mut cfg = LogConfig { enable_stdout: true, ..Default::default() };
+    Verbosity::Quiet.apply(&mut cfg);
+    assert_eq!(cfg.level, LevelFilter::Info);
+    assert_eq!(cfg.stdout_level, Some(LevelFilter::Error));
+}
@@ -0,0 +1,659 @@
+use sqllog_analysis::analysis::{
+    AlertThresholds, AppAliases, AppWorkloadAnalyzer, Classifier,
+    ClassifyRuleConfig, ConcurrencyAnalyzer, CoverageAnalyzer, IndexAdvisor,
+    KeywordAnalyzer, KeywordRuleConfig, MarkerSet, Partitioner, PlanAnalyzer,
+    ProfileAnalyzer, SecurityClass, SlidingWindow, TimeBucketAggregator,
+};
+use sqllog_analysis::sqllog::{ExecTimeMs, PlanNode, Sqllog};
+
+mod common;
+
+/// 未记录执行时间的记录，执行时间由各测试按需填写
+fn record(user: Option<&str>, description: &str) -> Sqllog {
+    Sqllog {
+        user: user.map(str::to_string),
+        execute_time: None,
+        ..common::record(description)
+    }
+}
+
+#[test]
+fn keyword_default_rules_tag_and_aggregate() {
+    let mut analyzer = KeywordAnalyzer::with_default_rules();
+    let records = vec![
+        record(Some("SYSDBA"), "update t set a = 1 [-6403]:锁超时"),
+        record(Some("SYSDBA"), "select 1 EXECTIME: 1(ms)"),
+        record(Some("APP"), "检测到死锁，事务已回滚"),
+        record(None, "insert into t values(1) [-6602]:违反表[T]唯一性约束"),
+    ];
+
+    assert!(analyzer.tag(&records[1]).is_empty());
+    assert!(analyzer.tag(&records[0]).contains(&"lock_wait"));
+
+    analyzer.observe(&records);
+    let report = analyzer.report();
+    assert_eq!(report.records_scanned, 4);
+    assert_eq!(report.records_matched, 3);
+    assert_eq!(report.by_category.get("lock_wait"), Some(&1));
+    assert_eq!(report.by_category.get("deadlock"), Some(&1));
+    assert_eq!(report.by_category.get("constraint"), Some(&1));
+    assert_eq!(report.by_category.get("error_code"), Some(&2));
+    assert_eq!(report.by_category_user["lock_wait"].get("SYSDBA"), Some(&1));
+    assert_eq!(report.by_category_user["constraint"].get("NULL"), Some(&1));
+}
+
+#[test]
+fn keyword_custom_rules_from_config() {
+    let configs = vec![KeywordRuleConfig {
+        category: "full_scan".to_string(),
+        pattern: r"(?i)select\s+\*".to_string(),
+    }];
+    let mut analyzer = KeywordAnalyzer::from_configs(&configs, false).unwrap();
+    assert_eq!(analyzer.rules().len(), 1);
+
+    analyzer.observe(&[
+        record(Some("A"), "SELECT * FROM t"),
+        record(Some("A"), "select * from u"),
+        record(Some("B"), "select id from t"),
+    ]);
+    assert_eq!(analyzer.report().by_category.get("full_scan"), Some(&2));
+
+    let bad = vec![KeywordRuleConfig {
+        category: "bad".to_string(),
+        pattern: "(".to_string(),
+    }];
+    assert!(KeywordAnalyzer::from_configs(&bad, true).is_err());
+}
+
+fn classify_rule(
+    name: &str,
+    class: SecurityClass,
+    user: Option<&str>,
+    ip: Option<&str>,
+    pattern: Option<&str>,
+) -> ClassifyRuleConfig {
+    ClassifyRuleConfig {
+        name: name.to_string(),
+        class,
+        user: user.map(str::to_string),
+        ip: ip.map(str::to_string),
+        pattern: pattern.map(str::to_string),
+    }
+}
+
+#[test]
+fn classifier_takes_most_severe_class_and_counts_rules() {
+    let configs = vec![
+        classify_rule(
+            "salary",
+            SecurityClass::Sensitive,
+            None,
+            None,
+            Some(r"(?i)\bsalary\b"),
+        ),
+        classify_rule(
+            "drop_from_vpn",
+            SecurityClass::Forbidden,
+            None,
+            Some(r"^172\.16\."),
+            Some(r"(?i)^\s*drop\b"),
+        ),
+        classify_rule(
+            "sysdba",
+            SecurityClass::Sensitive,
+            Some("^SYSDBA$"),
+            None,
+            None,
+        ),
+    ];
+    let mut classifier = Classifier::from_configs(&configs).unwrap();
+
+    let mut from_vpn = record(Some("SYSDBA"), "drop table salary");
+    from_vpn.ip = Some("172.16.0.9".to_string());
+    let records = vec![
+        from_vpn,
+        record(Some("SYSDBA"), "drop table salary"),
+        record(Some("APP"), "select salary from emp"),
+        record(None, "select 1"),
+    ];
+    assert_eq!(
+        classifier.classify(&records[0]),
+        (SecurityClass::Forbidden, vec!["salary", "drop_from_vpn", "sysdba"])
+    );
+
+    let mut forbidden = Vec::new();
+    classifier.observe_with(&records, |record, class, _| {
+        if class == SecurityClass::Forbidden {
+            forbidden.push(record.line);
+        }
+    });
+    assert_eq!(forbidden.len(), 1);
+
+    let report = classifier.report();
+    assert_eq!(report.records_scanned, 4);
+    assert_eq!(report.by_class.get(&SecurityClass::Forbidden), Some(&1));
+    assert_eq!(report.by_class.get(&SecurityClass::Sensitive), Some(&2));
+    assert_eq!(report.by_class.get(&SecurityClass::Normal), Some(&1));
+    assert_eq!(report.by_rule.get("salary"), Some(&3));
+    assert_eq!(report.by_rule.get("drop_from_vpn"), Some(&1));
+    assert_eq!(report.by_rule.get("sysdba"), Some(&2));
+}
+
+#[test]
+fn classify_rules_are_validated() {
+    let empty =
+        classify_rule("empty", SecurityClass::Forbidden, None, None, None);
+    assert!(Classifier::from_configs(&[empty]).is_err());
+    let bad =
+        classify_rule("bad", SecurityClass::Sensitive, Some("("), None, None);
+    assert!(Classifier::from_configs(&[bad]).is_err());
+
+    let parsed: ClassifyRuleConfig =
+        serde_json::from_str(r#"{"name":"x","class":"forbidden","user":"A"}"#)
+            .unwrap();
+    assert_eq!(parsed.class, SecurityClass::Forbidden);
+    assert!(
+        serde_json::from_str::<ClassifyRuleConfig>(
+            r#"{"name":"x","class":"secret","user":"A"}"#
+        )
+        .is_err()
+    );
+}
+
+#[test]
+fn time_buckets_annotated_with_markers() {
+    let mut agg = TimeBucketAggregator::new(60);
+    let mut r1 = record(Some("A"), "select 1");
+    r1.occurrence_time = "2025-09-21 12:00:05.000".to_string();
+    r1.execute_time = Some(ExecTimeMs::new(10));
+    let mut r2 = r1.clone();
+    r2.occurrence_time = "2025-09-21 12:00:59.999".to_string();
+    r2.execute_time = Some(ExecTimeMs::new(30));
+    let mut r3 = r1.clone();
+    r3.occurrence_time = "2025-09-21 12:05:00.000".to_string();
+    let mut bad = r1.clone();
+    bad.occurrence_time = "not a time".to_string();
+    agg.observe(&[r1, r2, r3, bad]);
+    assert_eq!(agg.skipped(), 1);
+
+    let mut buckets = agg.buckets();
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].records, 2);
+    assert_eq!(buckets[0].total_execute_time, ExecTimeMs::new(40));
+    assert_eq!(buckets[0].max_execute_time, Some(ExecTimeMs::new(30)));
+
+    let csv = "label,start,end\nsnap_1,2025-09-21 11:00:00,2025-09-21 12:01:00\ndeploy,2025-09-21 12:04:30.500,2025-09-21 12:10:00\n";
+    let markers = MarkerSet::from_csv(csv).unwrap();
+    markers.annotate(&mut buckets);
+    assert_eq!(buckets[0].markers, vec!["snap_1".to_string()]);
+    assert_eq!(buckets[1].markers, vec!["deploy".to_string()]);
+
+    let json = r#"[{"label":"snap_2","start":"2025-09-21 12:00:30","end":"2025-09-21 12:00:40"}]"#;
+    let markers = MarkerSet::from_json(json).unwrap();
+    markers.annotate(&mut buckets);
+    assert_eq!(buckets[0].markers, vec!["snap_2".to_string()]);
+    assert!(buckets[1].markers.is_empty());
+
+    assert!(MarkerSet::from_csv("label,start\nx,2025-09-21 12:00:00").is_err());
+}
+
+fn at(s: &str) -> chrono::NaiveDateTime {
+    chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+}
+
+#[test]
+fn partitioners_cover_shifts_iso_weeks_and_windows() {
+    let shifts: Partitioner =
+        "shifts:night=22:00,day=06:00,swing=14:00".parse().unwrap();
+    let p = shifts.partition(at("2025-09-22 03:00")).unwrap();
+    assert_eq!(p.key, "2025-09-21 night");
+    assert_eq!(
+        (p.start, p.end),
+        (at("2025-09-21 22:00"), at("2025-09-22 06:00"))
+    );
+    let p = shifts.partition(at("2025-09-22 14:00")).unwrap();
+    assert_eq!(p.key, "2025-09-22 swing");
+    assert_eq!(p.end, at("2025-09-22 22:00"));
+    let unnamed: Partitioner = "shifts:08:00,20:00".parse().unwrap();
+    assert_eq!(
+        unnamed.partition(at("2025-09-22 07:59")).unwrap().key,
+        "2025-09-21 2"
+    );
+
+    // ISO 周可以跨年
+    let week: Partitioner = "iso-week".parse().unwrap();
+    let p = week.partition(at("2024-12-30 10:00")).unwrap();
+    assert_eq!(p.key, "2025-W01");
+    assert_eq!(
+        (p.start, p.end),
+        (at("2024-12-30 00:00"), at("2025-01-06 00:00"))
+    );
+
+    let windows: Partitioner =
+        "windows:peak=mon-fri 09:00-18:00;batch=fri 22:00-02:00"
+            .parse()
+            .unwrap();
+    // 2025-09-19 为周五
+    assert_eq!(
+        windows.partition(at("2025-09-19 10:00")).unwrap().key,
+        "2025-09-19 peak"
+    );
+    let p = windows.partition(at("2025-09-20 01:30")).unwrap();
+    assert_eq!(p.key, "2025-09-19 batch");
+    assert_eq!(p.end, at("2025-09-20 02:00"));
+    assert!(windows.partition(at("2025-09-20 10:00")).is_none());
+    assert!(windows.partition(at("2025-09-19 18:00")).is_none());
+
+    for bad in [
+        "hourly",
+        "fixed:0",
+        "shifts:",
+        "shifts:25:00",
+        "shifts:6:00,06:00",
+        "windows:x=mon",
+        "windows:x=funday 01:00-02:00",
+    ] {
+        assert!(bad.parse::<Partitioner>().is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn time_buckets_follow_partitioner() {
+    let shifts: Partitioner = "shifts:day=08:00,night=20:00".parse().unwrap();
+    let mut agg = TimeBucketAggregator::with_partitioner(shifts);
+    let mut records = Vec::new();
+    for time in [
+        "2025-09-21 21:00:00.000",
+        "2025-09-22 07:00:00.000",
+        "2025-09-22 09:00:00.000",
+    ] {
+        let mut r = record(Some("A"), "select 1");
+        r.occurrence_time = time.to_string();
+        r.execute_time = Some(ExecTimeMs::new(5));
+        records.push(r);
+    }
+    agg.observe(&records);
+
+    let buckets = agg.buckets();
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0].key, "2025-09-21 night");
+    assert_eq!(buckets[0].records, 2);
+    assert_eq!(buckets[0].total_execute_time, ExecTimeMs::new(10));
+    assert_eq!(buckets[1].key, "2025-09-22 day");
+
+    let peak: Partitioner = "windows:peak=* 09:00-10:00".parse().unwrap();
+    let mut agg = TimeBucketAggregator::with_partitioner(peak);
+    agg.observe(&records);
+    assert_eq!(agg.buckets().len(), 1);
+    assert_eq!(agg.skipped(), 2);
+}
+
+#[test]
+fn coverage_reports_gaps_and_hourly_ratio() {
+    let mut analyzer = CoverageAnalyzer::new(300);
+    // 乱序到达：12:00、12:10、12:15、13:30 ×2
+    let times = [
+        "2025-09-21 13:30:00.000",
+        "2025-09-21 12:10:00.000",
+        "2025-09-21 12:00:00.500",
+        "2025-09-21 12:15:00.000",
+        "2025-09-21 13:30:00.900",
+        "bad",
+    ];
+    let records: Vec<Sqllog> = times
+        .iter()
+        .map(|t| Sqllog {
+            occurrence_time: (*t).to_string(),
+            ..record(None, "")
+        })
+        .collect();
+    analyzer.observe(&records);
+
+    let report = analyzer.report();
+    assert_eq!(report.skipped, 1);
+    // 12:10 -> 12:15 恰好等于阈值，不算空档
+    let gaps: Vec<(String, i64)> = report
+        .gaps
+        .iter()
+        .map(|g| (g.start.to_string(), g.duration_secs))
+        .collect();
+    assert_eq!(
+        gaps,
+        vec![
+            ("2025-09-21 12:00:00".to_string(), 600),
+            ("2025-09-21 12:15:00".to_string(), 4500),
+        ]
+    );
+
+    assert_eq!(report.hours.len(), 2);
+    assert_eq!(report.hours[0].records, 3);
+    assert_eq!(report.hours[1].records, 2);
+    // 12 点：3600 秒中 599 + 2699 秒落在空档内
+    assert!((report.hours[0].coverage - 302.0 / 3600.0).abs() < 1e-9);
+    // 13 点：只统计到最后一条记录为止（13:00:00 ~ 13:30:00，共 1801 秒）
+    assert!((report.hours[1].coverage - 1.0 / 1801.0).abs() < 1e-9);
+    assert!(report.to_string().contains("超过 300 秒的空档: 2 个"));
+
+    let empty = CoverageAnalyzer::default().report();
+    assert!(empty.first.is_none() && empty.hours.is_empty());
+}
+
+#[test]
+fn concurrency_tracks_active_sessions_and_peaks() {
+    let event =
+        |session: Option<&str>, user: &str, time: &str, ms: i64| Sqllog {
+            occurrence_time: format!("2025-09-21 {time}"),
+            session: session.map(str::to_string),
+            appname: Some("app".to_string()),
+            execute_time: Some(ExecTimeMs::new(ms)),
+            ..record(Some(user), "")
+        };
+    let mut analyzer = ConcurrencyAnalyzer::new(60);
+    // s1: 12:00 ~ 12:02；s2: 12:01:30 开始执行 40 秒，延伸到 12:02；
+    // s3: 12:04；无会话的记录被跳过
+    analyzer.observe(&[
+        event(Some("s1"), "A", "12:02:10.000", 1),
+        event(Some("s2"), "B", "12:01:30.000", 40_000),
+        event(Some("s1"), "A", "12:00:05.000", 1),
+        event(Some("s3"), "A", "12:04:00.000", 1),
+        event(None, "A", "12:03:00.000", 1),
+    ]);
+
+    let report = analyzer.report();
+    assert_eq!((report.sessions, report.skipped), (3, 1));
+    let active: Vec<u64> =
+        report.buckets.iter().map(|b| b.active_sessions).collect();
+    assert_eq!(active, vec![1, 2, 2, 0, 1]);
+    let peak = report.peak.as_ref().unwrap();
+    assert_eq!(
+        (peak.active_sessions, peak.start.to_string()),
+        (2, "2025-09-21 12:01:00".to_string())
+    );
+
+    let users: Vec<(&str, u64, u64)> = report
+        .by_user
+        .iter()
+        .map(|p| (p.name.as_str(), p.peak, p.sessions))
+        .collect();
+    assert_eq!(users, vec![("A", 1, 2), ("B", 1, 1)]);
+    assert_eq!(report.by_appname[0].peak, 2);
+    assert!(report.to_string().contains("并发峰值: 2"));
+
+    let empty = ConcurrencyAnalyzer::default().report();
+    assert!(empty.peak.is_none() && empty.buckets.is_empty());
+}
+
+#[test]
+fn profile_reports_null_rates_distinct_values_and_lengths() {
+    let mut analyzer = ProfileAnalyzer::new();
+    let mut records: Vec<Sqllog> = (1..=100)
+        .map(|i| Sqllog {
+            occurrence_time: format!("2025-09-21 12:{:02}:00.000", i % 60),
+            ip: (i % 2 == 0).then(|| format!("10.0.0.{}", i % 5)),
+            execute_time: Some(ExecTimeMs::new(1)),
+            ..record(Some(if i % 3 == 0 { "A" } else { "B" }), &"x".repeat(i))
+        })
+        .collect();
+    records[0].occurrence_time = "bad".to_string();
+    analyzer.observe(&records);
+
+    let report = analyzer.report();
+    assert_eq!((report.records, report.invalid_timestamps), (100, 1));
+    assert_eq!(report.first.unwrap().to_string(), "2025-09-21 12:00:00");
+    assert_eq!(report.last.unwrap().to_string(), "2025-09-21 12:59:00");
+
+    let field = |name: &str| {
+        report.fields.iter().find(|f| f.field == name).unwrap().clone()
+    };
+    assert_eq!((field("user").nulls, field("user").distinct), (0, Some(2)));
+    // 偶数记录才有 ip，取值为 10.0.0.{0,1,2,3,4}
+    assert_eq!((field("ip").nulls, field("ip").distinct), (50, Some(5)));
+    assert!((field("ip").null_rate - 0.5).abs() < 1e-9);
+    assert_eq!(field("appname").distinct, Some(0));
+    assert_eq!(field("session").distinct, None);
+    assert_eq!(field("rowcount").nulls, 100);
+
+    let len = report.description_length.as_ref().unwrap();
+    assert_eq!(
+        (len.min, len.max, len.p50, len.p95, len.p99),
+        (1, 100, 50, 95, 99)
+    );
+    assert!((len.mean - 50.5).abs() < 1e-9);
+    assert!(report.to_string().contains("取值 5 个"));
+
+    let empty = ProfileAnalyzer::new().report();
+    assert!(empty.description_length.is_none() && empty.first.is_none());
+}
+
+fn plan_node(operator: &str, cost: i64, children: Vec<PlanNode>) -> PlanNode {
+    PlanNode {
+        operator: operator.to_string(),
+        cost,
+        children,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn plan_analyzer_ranks_operator_hotspots() {
+    let mut scan = record(Some("A"), "select * from t");
+    scan.execute_time = Some(ExecTimeMs::new(500));
+    scan.plan = Some(plan_node(
+        "NSET2",
+        10,
+        vec![plan_node(
+            "PRJT2",
+            10,
+            vec![plan_node("CSCN2", 9, vec![]), plan_node("CSCN2", 4, vec![])],
+        )],
+    ));
+    let mut indexed = record(Some("A"), "select id from t where id = 1");
+    indexed.execute_time = Some(ExecTimeMs::new(2));
+    indexed.plan =
+        Some(plan_node("NSET2", 1, vec![plan_node("SSEK2", 1, vec![])]));
+    let no_plan = record(Some("B"), "commit");
+
+    let mut analyzer = PlanAnalyzer::new();
+    analyzer.observe(&[scan, indexed, no_plan]);
+    let report = analyzer.report();
+    assert_eq!(report.records_scanned, 3);
+    assert_eq!(report.records_with_plan, 2);
+
+    let cscn = &report.operators["CSCN2"];
+    assert_eq!((cscn.occurrences, cscn.records), (2, 1));
+    assert_eq!((cscn.total_cost, cscn.max_cost), (13, 9));
+    assert_eq!(cscn.total_execute_time, ExecTimeMs::new(500));
+    assert_eq!(report.operators["NSET2"].records, 2);
+
+    let top: Vec<_> =
+        report.hotspots(3).into_iter().map(|(name, _)| name).collect();
+    assert_eq!(top, ["NSET2", "CSCN2", "PRJT2"]);
+}
+
+#[test]
+fn engine_runs_custom_and_builtin_analyzers_in_one_pass() {
+    use sqllog_analysis::analysis::{AnalysisEngine, Analyzer, Report};
+    use std::path::Path;
+
+    /// 按文件统计记录数
+    #[derive(Default)]
+    struct PerFile {
+        current: u64,
+        files: Vec<(String, u64)>,
+    }
+
+    impl Analyzer for PerFile {
+        fn name(&self) -> &str {
+            "per_file"
+        }
+        fn on_record(&mut self, _record: &Sqllog) {
+            self.current += 1;
+        }
+        fn on_file_end(&mut self, path: &Path) {
+            let name = path.file_name().unwrap().to_string_lossy().into();
+            self.files.push((name, std::mem::take(&mut self.current)));
+        }
+        fn finish(self: Box<Self>) -> Report {
+            Report::new("per_file", &self.files)
+        }
+    }
+
+    let mut engine = AnalysisEngine::new();
+    engine
+        .register(Box::new(KeywordAnalyzer::with_default_rules()))
+        .register(Box::new(PerFile::default()));
+    assert_eq!(engine.len(), 2);
+
+    engine.observe(&[
+        record(Some("SYSDBA"), "update t set a = 1 [-6403]:锁超时"),
+        record(Some("APP"), "select 1"),
+    ]);
+    engine.file_end(Path::new("/logs/dmsql_a.log"));
+    engine.observe(&[record(None, "select 2")]);
+    engine.file_end(Path::new("/logs/dmsql_b.log"));
+
+    let reports = engine.finish();
+    assert_eq!(reports[0].analyzer, "keywords");
+    assert_eq!(reports[0].data["records_scanned"], 3);
+    assert_eq!(reports[0].data["by_category"]["lock_wait"], 1);
+    assert_eq!(reports[1].analyzer, "per_file");
+    assert_eq!(
+        reports[1].data,
+        serde_json::json!([["dmsql_a.log", 2], ["dmsql_b.log", 1]])
+    );
+}
+
+#[test]
+fn sliding_window_evicts_old_records_and_raises_alerts() {
+    let at = |time: &str, ms: i64, description: &str| Sqllog {
+        occurrence_time: format!("2025-09-21 {time}.000"),
+        execute_time: Some(ExecTimeMs::new(ms)),
+        description: description.to_string(),
+        ..Default::default()
+    };
+    let mut window = SlidingWindow::new(60);
+    window.observe(&[
+        at("12:00:00", 1000, "select 1"),
+        at("12:00:30", 10, "select 2"),
+        at("12:01:10", 20, "[-6403]:锁超时"),
+        at("12:01:20", 30, "select 3"),
+    ]);
+
+    // 12:00:00 已移出以 12:01:20 为终点的 60 秒窗口
+    let stats = window.stats();
+    assert_eq!(stats.records, 3);
+    assert_eq!(stats.p95_execute_time, Some(ExecTimeMs::new(30)));
+    assert_eq!(stats.error_records, 1);
+    assert!((stats.qps - 0.05).abs() < 1e-9);
+
+    let thresholds = AlertThresholds {
+        max_p95_ms: Some(25),
+        max_error_rate: Some(0.5),
+        ..Default::default()
+    };
+    let alerts = thresholds.check(&stats);
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].metric, "p95_ms");
+    assert!(thresholds.check(&SlidingWindow::new(60).stats()).is_empty());
+}
+
+#[test]
+fn app_workload_groups_by_normalized_aliased_appname() {
+    let aliases: std::collections::BTreeMap<String, String> = [
+        ("CRM-Web*", "crm"),
+        ("jdbc  thin client", "batch"),
+        ("crm-web-admin", "admin"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+    let aliases = AppAliases::new(&aliases);
+    assert_eq!(aliases.resolve(Some("  CRM-Web 2.1 ")), "crm");
+    assert_eq!(aliases.resolve(Some("crm-web-admin")), "admin");
+    assert_eq!(aliases.resolve(Some("JDBC Thin   Client")), "batch");
+    assert_eq!(aliases.resolve(Some("  Other  App ")), "other app");
+    assert_eq!(aliases.resolve(Some("  ")), "(unknown)");
+    assert_eq!(aliases.resolve(None), "(unknown)");
+
+    let mut records = Vec::new();
+    for (app, sql, ms) in [
+        ("crm-web 1.0", "select * from t where id = 1", 10),
+        ("CRM-WEB 2.0", "select * from t where id = 2", 20),
+        ("crm-web 2.0", "update t set a = 1", 100),
+        ("JDBC Thin Client", "select 1", 5),
+    ] {
+        let mut r = record(Some("A"), sql);
+        r.appname = Some(app.to_string());
+        r.execute_time = Some(ExecTimeMs::new(ms));
+        records.push(r);
+    }
+    records.push(record(Some("A"), "select 1"));
+
+    let mut analyzer = AppWorkloadAnalyzer::new(aliases).with_top(1);
+    analyzer.observe(&records);
+    let report = analyzer.report();
+    assert_eq!(report.records, 5);
+    let apps: Vec<&str> = report.apps.iter().map(|a| a.app.as_str()).collect();
+    assert_eq!(apps, ["crm", "batch", "(unknown)"]);
+
+    let crm = &report.apps[0];
+    assert_eq!(crm.calls, 3);
+    assert_eq!(crm.raw_names, ["CRM-WEB 2.0", "crm-web 1.0", "crm-web 2.0"]);
+    assert_eq!(crm.total_time, ExecTimeMs::new(130));
+    assert_eq!(crm.p50, Some(ExecTimeMs::new(20)));
+    assert_eq!(crm.p99, Some(ExecTimeMs::new(100)));
+    assert_eq!(crm.top_statements.len(), 1);
+    assert_eq!(crm.top_statements[0].calls, 1);
+    assert_eq!(crm.top_statements[0].total_time, ExecTimeMs::new(100));
+
+    let unknown = &report.apps[2];
+    assert_eq!(unknown.calls, 1);
+    assert!(unknown.p95.is_none());
+    assert!(report.to_string().contains("crm: 调用 3"));
+}
+
+#[test]
+fn index_advisor_ranks_filter_columns_by_total_time() {
+    let mut records = Vec::new();
+    for (sql, ms) in [
+        ("select * from orders where customer_id = 1", 10),
+        (
+            "select * from orders where customer_id = 2 and created > '2025-01-01'",
+            50,
+        ),
+        (
+            "select o.id from orders o join customers c on o.customer_id = c.id \
+             where c.region = 'east' and c.status in (1, 2)",
+            30,
+        ),
+        ("select * from orders where note <> 'x'", 500),
+        ("select 1", 5),
+    ] {
+        let mut r = record(Some("A"), sql);
+        r.execute_time = Some(ExecTimeMs::new(ms));
+        records.push(r);
+    }
+
+    let mut advisor = IndexAdvisor::new();
+    advisor.observe(&records);
+    let report = advisor.report();
+    assert_eq!(report.records, 5);
+    let ddl: Vec<&str> =
+        report.suggestions.iter().map(|s| s.ddl.as_str()).collect();
+    assert_eq!(
+        ddl,
+        [
+            "CREATE INDEX idx_orders_customer_id_created ON orders (customer_id, created);",
+            "CREATE INDEX idx_customers_region_status ON customers (region, status);",
+        ]
+    );
+    // 只按 customer_id 过滤的语句并入 (customer_id, created)
+    let orders = &report.suggestions[0];
+    assert_eq!(orders.calls, 2);
+    assert_eq!(orders.total_time, ExecTimeMs::new(60));
+    assert!(orders.example.contains("created"));
+
+    let mut top = IndexAdvisor::new().with_top(1);
+    top.observe(&records);
+    assert_eq!(top.report().suggestions.len(), 1);
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "arrow")]
+
+use sqllog_analysis::config::{ExportOptions, RuntimeConfig};
+use sqllog_analysis::database::arrow::array::{Array, Int64Array, StringArray};
+use sqllog_analysis::database::{
+    DatabaseProvider, DuckDbProvider, records_to_batch, sqllog_schema,
+};
+use sqllog_analysis::sqllog::Sqllog;
+
+mod common;
+use common::record;
+
+fn user_record(description: &str, user: Option<&str>) -> Sqllog {
+    Sqllog { user: user.map(str::to_string), ..record(description) }
+}
+
+#[test]
+fn records_convert_to_record_batch() {
+    let batch = records_to_batch(&[
+        user_record("select 1", Some("SYSDBA")),
+        user_record("select 2", None),
+    ])
+    .unwrap();
+    assert_eq!(batch.schema(), sqllog_schema());
+    assert_eq!(batch.num_rows(), 2);
+
+    let users = batch
+        .column_by_name("username")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<StringArray>()
+        .unwrap();
+    assert_eq!(users.value(0), "SYSDBA");
+    assert!(users.is_null(1));
+    let times = batch
+        .column_by_name("execute_time")
+        .unwrap()
+        .as_any()
+        .downcast_ref::<Int64Array>()
+        .unwrap();
+    assert_eq!(times.value(1), 1);
+}
+
+#[test]
+fn database_rows_are_queried_as_record_batches() {
+    let config = RuntimeConfig { use_in_memory: true, ..Default::default() };
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider.initialize().unwrap();
+    provider
+        .insert_batch(&[
+            user_record("a", Some("u1")),
+            user_record("b", Some("u2")),
+        ])
+        .unwrap();
+
+    let batches =
+        provider.query_record_batches(&ExportOptions::default()).unwrap();
+    let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+    assert_eq!(rows, 2);
+    let schema = batches[0].schema();
+    let names: Vec<&str> =
+        schema.fields().iter().map(|f| f.name().as_str()).collect();
+    assert_eq!(names[..3], ["occurrence_time", "ep", "session"]);
+}
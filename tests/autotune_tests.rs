@@ -0,0 +1,70 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    AutoTune, BatchTuner, process_file_with_independent_database,
+};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+fn config() -> AutoTune {
+    AutoTune {
+        target_latency: Duration::from_millis(100),
+        min_batch: 10,
+        max_batch: 40,
+    }
+}
+
+#[test]
+fn fast_full_batches_grow_up_to_max() {
+    let mut tuner = BatchTuner::new(config());
+    assert_eq!(tuner.batch_size(), 10);
+    assert_eq!(tuner.observe(10, Duration::from_millis(1)), 20);
+    // 未满批说明数据源已耗尽，不据此扩大批次
+    assert_eq!(tuner.observe(5, Duration::from_millis(1)), 20);
+    assert_eq!(tuner.observe(20, Duration::from_millis(1)), 40);
+    assert_eq!(tuner.observe(40, Duration::from_millis(1)), 40);
+    assert_eq!(tuner.adjustments(), 2);
+}
+
+#[test]
+fn slow_batches_shrink_down_to_min() {
+    let mut tuner = BatchTuner::new(config());
+    tuner.observe(10, Duration::ZERO);
+    tuner.observe(20, Duration::ZERO);
+    assert_eq!(tuner.observe(40, Duration::from_millis(150)), 20);
+    // 介于目标一半与目标之间时保持不变
+    assert_eq!(tuner.observe(20, Duration::from_millis(70)), 20);
+    assert_eq!(tuner.observe(20, Duration::from_millis(150)), 10);
+    assert_eq!(tuner.observe(10, Duration::from_millis(150)), 10);
+}
+
+#[test]
+fn auto_tuned_insert_writes_every_record() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..25 {
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}."
+        )
+        .unwrap();
+    }
+    file.flush().unwrap();
+
+    let runtime = RuntimeConfig {
+        use_in_memory: true,
+        insert_auto_tune: Some(AutoTune {
+            target_latency: Duration::from_secs(60),
+            min_batch: 2,
+            max_batch: 8,
+        }),
+        ..Default::default()
+    };
+    let stats =
+        process_file_with_independent_database(file.path(), &runtime).unwrap();
+
+    assert_eq!(stats.records_processed, 25);
+    assert_eq!(stats.records_inserted, 25);
+    assert_eq!(stats.files[0].records, 25);
+}
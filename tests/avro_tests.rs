@@ -0,0 +1,163 @@
+#![cfg(feature = "exporter-avro")]
+
+use sqllog_analysis::config::{ExportOptions, RuntimeConfig};
+use sqllog_analysis::database::{
+    AvroCompatibility, AvroExportOptions, AvroExporter, AvroTimestamps,
+    DatabaseProvider, DuckDbProvider, ExportFormat, FormatOptions, avro_schema,
+};
+use std::fs;
+use tempfile::tempdir;
+
+mod common;
+use common::record;
+
+/// 最小的 Avro 读取器，只覆盖导出用到的编码
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn long(&mut self) -> i64 {
+        let mut n = 0u64;
+        let mut shift = 0;
+        loop {
+            let b = self.buf[self.pos];
+            self.pos += 1;
+            n |= u64::from(b & 0x7f) << shift;
+            if b & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        ((n >> 1) as i64) ^ -((n & 1) as i64)
+    }
+
+    fn bytes(&mut self) -> &'a [u8] {
+        let len = self.long() as usize;
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        bytes
+    }
+
+    fn string(&mut self) -> String {
+        String::from_utf8(self.bytes().to_vec()).unwrap()
+    }
+
+    /// 读取文件头，返回 schema 与同步标记
+    fn header(&mut self) -> (serde_json::Value, Vec<u8>) {
+        assert_eq!(&self.buf[..4], b"Obj\x01");
+        self.pos = 4;
+        let mut schema = None;
+        loop {
+            let count = self.long();
+            if count == 0 {
+                break;
+            }
+            for _ in 0..count {
+                let key = self.string();
+                let value = self.bytes();
+                match key.as_str() {
+                    "avro.schema" => {
+                        schema = Some(serde_json::from_slice(value).unwrap());
+                    }
+                    "avro.codec" => assert_eq!(value, b"null"),
+                    _ => {}
+                }
+            }
+        }
+        let sync = self.buf[self.pos..self.pos + 16].to_vec();
+        self.pos += 16;
+        (schema.unwrap(), sync)
+    }
+}
+
+#[test]
+fn schema_marks_optional_fields_nullable_with_defaults() {
+    let schema = avro_schema(&AvroExportOptions::default());
+    let fields = schema["fields"].as_array().unwrap();
+    assert_eq!(fields.len(), 18);
+    assert_eq!(fields[0]["type"], "string");
+    assert_eq!(fields[4]["name"], "username");
+    assert_eq!(fields[4]["type"], serde_json::json!(["null", "string"]));
+    assert!(fields[4]["default"].is_null());
+
+    let full = avro_schema(
+        &AvroExportOptions::default()
+            .timestamps(AvroTimestamps::Logical)
+            .compatibility(AvroCompatibility::Full),
+    );
+    let first = &full["fields"][0];
+    assert_eq!(first["type"][1]["logicalType"], "local-timestamp-millis");
+    assert!(
+        full["fields"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|f| f["type"][0] == "null" && f["default"].is_null())
+    );
+}
+
+#[test]
+fn exporter_writes_container_file_with_blocks() {
+    let mut out = Vec::new();
+    let options =
+        AvroExportOptions::default().timestamps(AvroTimestamps::Logical);
+    let mut exporter = AvroExporter::new(&mut out, options).unwrap();
+    exporter.write_records(&[record("select 1")]).unwrap();
+    assert_eq!(exporter.finish().unwrap(), 1);
+
+    let mut reader = Reader { buf: &out, pos: 0 };
+    let (schema, sync) = reader.header();
+    assert_eq!(schema["name"], "Sqllog");
+    assert_eq!(reader.long(), 1);
+    let size = reader.long() as usize;
+    let block_end = reader.pos + size;
+
+    // occurrence_time（逻辑时间戳）与 ep 为必填字段
+    assert_eq!(reader.long(), 1_758_456_000_000);
+    assert_eq!(reader.long(), 1);
+    // session/thread 为 null，username 有值
+    assert_eq!((reader.long(), reader.long()), (0, 0));
+    assert_eq!(reader.long(), 1);
+    assert_eq!(reader.string(), "SYSDBA");
+    assert_eq!(&out[block_end..], &sync[..]);
+
+    let mut bad = record("x");
+    bad.occurrence_time = "not a time".to_string();
+    let mut exporter = AvroExporter::new(Vec::new(), options).unwrap();
+    assert!(exporter.write_records(&[bad]).is_err());
+}
+
+#[test]
+fn avro_format_exports_from_database() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.avro");
+    let config = RuntimeConfig { use_in_memory: true, ..Default::default() };
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider.initialize().unwrap();
+    provider.insert_batch(&[record("a"), record("b")]).unwrap();
+
+    let options = ExportOptions {
+        format_options: FormatOptions::from_pairs(["avro.timestamps=logical"])
+            .unwrap(),
+        ..Default::default()
+    };
+    let report = provider
+        .export_with_options(
+            ExportFormat::Avro,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+    assert_eq!(report.records_exported, 2);
+
+    let bytes = fs::read(&out).unwrap();
+    let mut reader = Reader { buf: &bytes, pos: 0 };
+    let (schema, _) = reader.header();
+    assert_eq!(
+        schema["fields"][0]["type"]["logicalType"],
+        "local-timestamp-millis"
+    );
+    assert_eq!(reader.long(), 2);
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "bin")]
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use tempfile::tempdir;
+
+const RECORD: &str = "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1.\n";
+
+/// 以 `dir` 下的配置文件运行命令行程序
+fn run_cli(dir: &Path, config: &str, args: &[&str]) -> Output {
+    let config_path = dir.join("config.toml");
+    fs::write(&config_path, config).unwrap();
+    Command::new(env!("CARGO_BIN_EXE_sqllog-analysis"))
+        .args(args)
+        .current_dir(dir)
+        .env("SQLLOG_CONFIG", &config_path)
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn exporter_opt_flags_override_config() {
+    let dir = tempdir().unwrap();
+    let logs = dir.path().join("logs");
+    fs::create_dir(&logs).unwrap();
+    fs::write(logs.join("dmsql_1.log"), RECORD).unwrap();
+    let config = format!(
+        "[log]\nenable_stdout = false\nlog_dir = {:?}\n\
+         [database]\ndb_path = {:?}\n\
+         [sqllog]\nsqllog_dir = {:?}\n\
+         [export]\nexporter_opts = [\"csv.delimiter=|\", \"csv.null_string=NULL\"]\n",
+        dir.path().join("out_logs"),
+        dir.path().join("sqllogs.duckdb"),
+        logs
+    );
+
+    let output = run_cli(
+        dir.path(),
+        &config,
+        &[
+            "-q",
+            "--output",
+            "out.csv",
+            "--exporter-opt",
+            "csv.delimiter=;",
+            "--exporter-opt",
+            "csv.header=false",
+        ],
+    );
+    assert!(output.status.success(), "{output:?}");
+    let csv = fs::read_to_string(dir.path().join("out.csv")).unwrap();
+    assert!(csv.starts_with("2025-09-21 12:00:00.000;1;NULL;1;usr;"), "{csv}");
+
+    let output = run_cli(
+        dir.path(),
+        &config,
+        &["-q", "--output", "out.csv", "--exporter-opt", "csv.header=yes"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--exporter-opt"));
+}
@@ -0,0 +1,31 @@
+//! 集成测试共用的记录构造函数
+//!
+//! 各测试文件通过 `mod common;` 引入，按需以结构体更新语法覆盖字段，
+//! 例如 `Sqllog { user: None, ..record("select 1") }`。
+
+#![allow(dead_code)]
+
+use sqllog_analysis::sqllog::{ExecTimeMs, Sqllog};
+
+/// 固定时间、EP 1、用户 SYSDBA、执行 1ms 的记录
+pub fn record(description: &str) -> Sqllog {
+    Sqllog {
+        occurrence_time: "2025-09-21 12:00:00.000".to_string(),
+        ep: 1,
+        user: Some("SYSDBA".to_string()),
+        description: description.to_string(),
+        execute_time: Some(ExecTimeMs::new(1)),
+        ..Default::default()
+    }
+}
+
+/// description 带执行指标尾部（`EXECTIME/ROWCOUNT/EXEC_ID`）的记录，
+/// 执行时间与尾部一致
+pub fn timed_record(sql: &str, execute_time: i64) -> Sqllog {
+    Sqllog {
+        execute_time: Some(ExecTimeMs::new(execute_time)),
+        ..record(&format!(
+            "{sql} EXECTIME: {execute_time}(ms) ROWCOUNT: 1 EXEC_ID: 7."
+        ))
+    }
+}
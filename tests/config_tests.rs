@@ -1,4 +1,8 @@
-use sqllog_analysis::config::Config;
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::{
+    Config, ConfigError, RuntimeConfig, RuntimeConfigBuilder,
+};
 use std::env;
 
 #[test]
@@ -24,3 +28,79 @@ fn test_load_from_config_file() {
     let runtime = cfg_obj;
     assert_eq!(runtime.db_path, "mydb.duckdb");
 }
+
+#[test]
+fn builder_validates_every_field() {
+    let config = RuntimeConfig::builder()
+        .sqllog_dir("logs")
+        .chunk_size(Some(5000))
+        .parser_threads(8)
+        .export("csv,json", "out/result")
+        .build()
+        .unwrap();
+    assert_eq!(config.sqllog_chunk_size, Some(5000));
+    assert!(config.export_enabled);
+    assert!(RuntimeConfig::default().validate().is_ok());
+
+    let errors = RuntimeConfigBuilder::new()
+        .chunk_size(Some(0))
+        .parser_threads(1000)
+        .export("csv,xml", "out")
+        .with(|c| c.export_options.top_sessions = Some(0))
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ConfigError::Zero {
+                field: "sqllog.chunk_size",
+                hint: "不分块时请使用 None",
+            },
+            ConfigError::OutOfRange {
+                field: "sqllog.parser_threads",
+                value: 1000,
+                min: 1,
+                max: 256,
+            },
+            ConfigError::Zero {
+                field: "export.top_sessions",
+                hint: "导出全部记录请使用 None",
+            },
+            ConfigError::Invalid {
+                field: "export.format",
+                value: "xml".to_string(),
+                reason: "不支持的导出格式: xml".to_string(),
+            },
+        ]
+    );
+    assert_eq!(
+        errors[1].to_string(),
+        "sqllog.parser_threads = 1000 超出范围 [1, 256]"
+    );
+}
+
+#[test]
+fn builder_reads_environment_defaults() {
+    let vars = |key: &str| match key {
+        "SQLLOG_DIR" => Some("/data/sqllog".to_string()),
+        "SQLLOG_PARSER_THREADS" => Some("4".to_string()),
+        "SQLLOG_CHUNK_SIZE" => Some("many".to_string()),
+        _ => None,
+    };
+    let builder = RuntimeConfigBuilder::new().with_env_vars(vars);
+    let errors = builder.validate().unwrap_err();
+    assert_eq!(errors.len(), 1);
+    assert!(matches!(
+        &errors[0],
+        ConfigError::Invalid { field: "SQLLOG_CHUNK_SIZE", value, .. } if value == "many"
+    ));
+
+    // 环境变量均合法时构造成功
+    let config = RuntimeConfigBuilder::new()
+        .with_env_vars(|key| {
+            (key == "SQLLOG_PARSER_THREADS").then(|| "4".to_string())
+        })
+        .build()
+        .unwrap();
+    assert_eq!(config.parser_threads, 4);
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::prelude::*;
+use std::fs;
+use std::io::Write;
+use tempfile::{NamedTempFile, tempdir};
+
+fn log_file() -> NamedTempFile {
+    let mut f = NamedTempFile::new().unwrap();
+    for i in 0..3 {
+        writeln!(
+            f,
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: {i}(ms) ROWCOUNT: 1 EXEC_ID: {i}."
+        )
+        .unwrap();
+    }
+    writeln!(f, "2025-09-21 12:00:01.000 not a header").unwrap();
+    f
+}
+
+#[test]
+fn parse_file_returns_all_records() {
+    let file = log_file();
+    let records = parse_file(file.path()).unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[2].execute_time, Some(ExecTimeMs::new(2)));
+
+    assert!(matches!(
+        parse_file("/nonexistent/dmsql_x.log"),
+        Err(SqllogError::Io(_))
+    ));
+}
+
+#[test]
+fn export_file_writes_csv_and_reports_stats() {
+    let file = log_file();
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+
+    let stats = export_file(file.path(), ExportFormat::Csv, &out).unwrap();
+    assert_eq!(
+        (stats.records_parsed, stats.parse_errors, stats.records_exported),
+        (3, 1, 3)
+    );
+    assert_eq!(stats.by_source().len(), 1);
+    assert_eq!(stats.by_source()[0].path, file.path());
+    let csv = fs::read_to_string(&out).unwrap();
+    assert_eq!(csv.lines().count(), 4);
+    assert!(csv.lines().next().unwrap().starts_with("occurrence_time,"));
+}
+
+#[test]
+fn export_files_attributes_records_and_failures_to_sources() {
+    let first = log_file();
+    let second = log_file();
+    let missing = std::path::PathBuf::from("/nonexistent/dmsql_x.log");
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+
+    let stats = export_files(
+        [first.path(), missing.as_path(), second.path()],
+        ExportFormat::Csv,
+        &out,
+    )
+    .unwrap();
+    assert_eq!(
+        (stats.records_parsed, stats.parse_errors, stats.records_exported),
+        (6, 2, 6)
+    );
+
+    let sources = stats.by_source();
+    assert_eq!(sources.len(), 3);
+    assert_eq!(sources[0].path, first.path());
+    assert_eq!(
+        (sources[0].records_parsed, sources[0].records_inserted),
+        (3, 3)
+    );
+    assert_eq!(sources[0].parse_errors, 1);
+    assert_eq!(sources[0].error, None);
+    assert_eq!(sources[1].records_parsed, 0);
+    assert!(sources[1].error.as_ref().unwrap().contains("dmsql_x.log"));
+    assert_eq!(sources[2].records_inserted, 3);
+
+    let failed: Vec<_> = stats.failed_sources().map(|s| &s.path).collect();
+    assert_eq!(failed, [&missing]);
+    assert_eq!(fs::read_to_string(&out).unwrap().lines().count(), 7);
+
+    // 单文件导出仍直接返回错误
+    assert!(export_file(&missing, ExportFormat::Csv, &out).is_err());
+}
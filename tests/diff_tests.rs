@@ -0,0 +1,103 @@
+use sqllog_analysis::analysis::{
+    DiffThresholds, FingerprintAggregator, diff, fingerprint,
+};
+use sqllog_analysis::sqllog::ExecTimeMs;
+
+mod common;
+use common::timed_record as record;
+
+#[test]
+fn fingerprint_normalizes_literals() {
+    assert_eq!(
+        fingerprint(
+            "SELECT *  FROM t1 WHERE id = 42 AND name = 'it''s' EXECTIME: 3(ms) ROWCOUNT: 1 EXEC_ID: 9."
+        ),
+        "select * from t1 where id = ? and name = ?"
+    );
+    assert_eq!(
+        fingerprint(
+            "select a from t where id in (1, 2,3) -- hint\n and x = -1.5"
+        ),
+        "select a from t where id in (?) and x = -?"
+    );
+    assert_eq!(
+        fingerprint(
+            "INSERT INTO \"Tab\" VALUES (1, 'a'), (2, 'b') /* batch */"
+        ),
+        "insert into \"Tab\" values (?)"
+    );
+    assert_eq!(fingerprint("select 1"), fingerprint("SELECT   2"));
+}
+
+#[test]
+fn diff_reports_new_disappeared_and_changed() {
+    let mut baseline = FingerprintAggregator::new();
+    let mut current = FingerprintAggregator::new();
+
+    // 变慢：p95 10ms -> 50ms
+    baseline.observe(
+        &(1..=10)
+            .map(|i| record(&format!("select * from a where id = {i}"), 10))
+            .collect::<Vec<_>>(),
+    );
+    current.observe(
+        &(1..=10)
+            .map(|i| record(&format!("select * from a where id = {i}"), 50))
+            .collect::<Vec<_>>(),
+    );
+    // 调用激增：2 次 -> 10 次
+    baseline.observe(&[
+        record("update b set x = 1", 5),
+        record("update b set x = 2", 5),
+    ]);
+    current.observe(
+        &(0..10).map(|_| record("update b set x = 3", 5)).collect::<Vec<_>>(),
+    );
+    // 未变化
+    baseline.observe(&[record("select 1 from c", 3)]);
+    current.observe(&[record("select 2 from c", 3)]);
+    // 消失与新增
+    baseline.observe(&[record("delete from old_t", 1)]);
+    current.observe(&[
+        record("delete from new_t", 1),
+        record("delete from new_t", 2),
+    ]);
+
+    let baseline = baseline.finish();
+    let current = current.finish();
+    assert_eq!(baseline["select * from a where id = ?"].calls, 10);
+    assert_eq!(
+        baseline["select * from a where id = ?"].p95,
+        Some(ExecTimeMs::new(10))
+    );
+
+    let report = diff(&baseline, &current, &DiffThresholds::default());
+    assert_eq!(report.new.len(), 1);
+    assert_eq!(report.new[0].0, "delete from new_t");
+    assert_eq!(report.new[0].1.calls, 2);
+    assert_eq!(report.disappeared.len(), 1);
+    assert_eq!(report.disappeared[0].0, "delete from old_t");
+    assert_eq!(report.unchanged, 1);
+
+    assert_eq!(report.changed.len(), 2);
+    let slow = &report.changed[0];
+    assert_eq!(slow.fingerprint, "select * from a where id = ?");
+    assert!(slow.p95_changed && !slow.calls_changed);
+    assert_eq!(slow.p95_change, Some(4.0));
+    let burst = &report.changed[1];
+    assert_eq!(burst.fingerprint, "update b set x = ?");
+    assert!(burst.calls_changed && !burst.p95_changed);
+    assert!((burst.calls_change - 4.0).abs() < f64::EPSILON);
+
+    let text = report.to_string();
+    assert!(text.contains("新增语句: 1"));
+    assert!(text.contains("p95=10ms->50ms (+400.0%)"));
+
+    // 提高阈值后不再视为变化
+    let lenient = DiffThresholds {
+        p95_ratio: 5.0,
+        calls_ratio: 5.0,
+        ..Default::default()
+    };
+    assert_eq!(diff(&baseline, &current, &lenient).unchanged, 3);
+}
@@ -0,0 +1,89 @@
+use sqllog_analysis::sqllog::{
+    ExecTimeMs, ParseOptions, SourceEncoding, Sqllog,
+};
+use std::fs;
+use tempfile::tempdir;
+
+const LOG: &str = "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:用户 trxid:1 stmt:NULL) [SEL]: select '😀'\nfrom dual EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1.\n2025-09-21 12:00:01.000 (EP[1] sess:NULL thrd:1 user:usr trxid:2 stmt:NULL) [SEL]: select 2 EXECTIME: 2(ms) ROWCOUNT: 1 EXEC_ID: 2.\n";
+
+fn parse(bytes: &[u8]) -> (Vec<Sqllog>, usize) {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("dmsql_test.log");
+    fs::write(&path, bytes).unwrap();
+
+    let mut records = Vec::new();
+    let mut errors = 0;
+    Sqllog::parse_with_options(
+        &path,
+        &ParseOptions::default(),
+        |chunk| records.extend_from_slice(chunk),
+        |errs| errors += errs.len(),
+    )
+    .unwrap();
+    (records, errors)
+}
+
+fn utf16(text: &str, big_endian: bool) -> Vec<u8> {
+    let mut bytes =
+        if big_endian { vec![0xFE, 0xFF] } else { vec![0xFF, 0xFE] };
+    for unit in text.encode_utf16() {
+        let pair =
+            if big_endian { unit.to_be_bytes() } else { unit.to_le_bytes() };
+        bytes.extend_from_slice(&pair);
+    }
+    bytes
+}
+
+fn summary(records: &[Sqllog]) -> Vec<(String, Option<String>, String)> {
+    records
+        .iter()
+        .map(|r| {
+            (r.occurrence_time.clone(), r.user.clone(), r.description.clone())
+        })
+        .collect()
+}
+
+#[test]
+fn detects_byte_order_marks() {
+    assert_eq!(
+        SourceEncoding::detect(b"\xEF\xBB\xBF2025"),
+        SourceEncoding::Utf8Bom
+    );
+    assert_eq!(SourceEncoding::detect(b"\xFF\xFE2\0"), SourceEncoding::Utf16Le);
+    assert_eq!(
+        SourceEncoding::detect(b"\xFE\xFF\x002"),
+        SourceEncoding::Utf16Be
+    );
+    assert_eq!(SourceEncoding::detect(b"2025"), SourceEncoding::Utf8);
+    // BOM 后不是 ASCII 码元时按（损坏的）UTF-8 处理
+    assert_eq!(SourceEncoding::detect(b"\xFF\xFE\xFD"), SourceEncoding::Utf8);
+    assert_eq!(SourceEncoding::detect(b""), SourceEncoding::Utf8);
+}
+
+#[test]
+fn same_log_parses_identically_across_platform_variants() {
+    let (expected, errors) = parse(LOG.as_bytes());
+    assert_eq!(errors, 0);
+    assert_eq!(expected.len(), 2);
+    assert_eq!(
+        expected[0].description,
+        "select '😀'\nfrom dual EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1."
+    );
+
+    let crlf = LOG.replace('\n', "\r\n");
+    let mut bom_crlf = b"\xEF\xBB\xBF".to_vec();
+    bom_crlf.extend_from_slice(crlf.as_bytes());
+
+    let variants = [
+        ("crlf", crlf.as_bytes().to_vec()),
+        ("utf8 bom + crlf", bom_crlf),
+        ("utf16le", utf16(LOG, false)),
+        ("utf16be + crlf", utf16(&crlf, true)),
+    ];
+    for (name, bytes) in variants {
+        let (records, errors) = parse(&bytes);
+        assert_eq!(errors, 0, "{name}");
+        assert_eq!(summary(&records), summary(&expected), "{name}");
+        assert_eq!(records[1].execute_time, Some(ExecTimeMs::new(2)), "{name}");
+    }
+}
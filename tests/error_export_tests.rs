@@ -0,0 +1,91 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::process_files_with_independent_databases;
+use sqllog_analysis::error_writer::{
+    ErrorExporter, ErrorFormat, ErrorWriter, ParseErrorRecord,
+};
+use sqllog_analysis::sqllog::SqllogError;
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+fn errors() -> Vec<(usize, String, SqllogError)> {
+    vec![
+        (3, "plain".to_string(), SqllogError::Other("bad".to_string())),
+        (
+            7,
+            "a,\"quoted\"\nline".to_string(),
+            SqllogError::Other("worse".to_string()),
+        ),
+    ]
+}
+
+#[test]
+fn csv_error_writer_quotes_fields_and_writes_header_once() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("errors.csv");
+
+    for _ in 0..2 {
+        let writer = ErrorWriter::with_format(&path, ErrorFormat::Csv).unwrap();
+        let exporter: &dyn ErrorExporter = &writer;
+        exporter.export_errors(Path::new("x.log"), &errors());
+    }
+
+    let content = fs::read_to_string(&path).unwrap();
+    assert_eq!(content.matches("path,line,error,raw").count(), 1);
+    assert!(content.contains("x.log,3,未知错误: bad,plain\n"));
+    assert!(content.contains("\"a,\"\"quoted\"\"\nline\""));
+}
+
+#[test]
+fn error_format_parses_known_names() {
+    assert_eq!("CSV".parse::<ErrorFormat>().unwrap(), ErrorFormat::Csv);
+    assert_eq!("jsonl".parse::<ErrorFormat>().unwrap(), ErrorFormat::Jsonl);
+    assert!("xml".parse::<ErrorFormat>().is_err());
+}
+
+#[test]
+fn record_conversion_keeps_line_and_raw() {
+    let records = ParseErrorRecord::from_errors(Path::new("y.log"), &errors());
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[1].path, "y.log");
+    assert_eq!(records[1].line, 7);
+    assert_eq!(records[1].error, "未知错误: worse");
+}
+
+#[test]
+fn errors_table_is_merged_from_every_file() {
+    let dir = tempdir().unwrap();
+    let mut files = Vec::new();
+    for i in 0..2 {
+        let path = dir.path().join(format!("dmsql_{i}.log"));
+        let mut content = format!(
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}.\n"
+        )
+        .into_bytes();
+        // 无效 UTF-8 行产生解析错误
+        content.extend_from_slice(&[0xFF, 0xFE, b'\n']);
+        fs::write(&path, content).unwrap();
+        files.push(path);
+    }
+    let db_path = dir.path().join("out.duckdb");
+
+    let runtime = RuntimeConfig {
+        db_path: db_path.to_string_lossy().to_string(),
+        sqllog_errors_table: true,
+        ..Default::default()
+    };
+    let stats =
+        process_files_with_independent_databases(&files, &runtime).unwrap();
+    assert_eq!(stats.records_inserted, 2);
+
+    let conn = duckdb::Connection::open(&db_path).unwrap();
+    let (count, lines): (i64, i64) = conn
+        .query_row("SELECT count(*), sum(line) FROM parse_errors", [], |r| {
+            Ok((r.get(0)?, r.get(1)?))
+        })
+        .unwrap();
+    assert_eq!(count, 2);
+    assert_eq!(lines, 4);
+}
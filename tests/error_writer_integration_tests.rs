@@ -1,7 +1,10 @@
+#![cfg(feature = "database")]
+
 // 错误写入功能的集成测试
 
 use sqllog_analysis::config::RuntimeConfig;
 use sqllog_analysis::database::process_files_with_independent_databases;
+use sqllog_analysis::sqllog::BlankFields;
 use std::fs;
 use std::io::Write;
 use tempfile::{NamedTempFile, tempdir};
@@ -13,12 +16,12 @@ fn test_error_writing_integration() {
     let log_dir = temp_dir.path();
 
     // 创建带有错误行的测试 sqllog 文件
-    let mut log_file = NamedTempFile::new_in(&log_dir).unwrap();
+    let mut log_file = NamedTempFile::new_in(log_dir).unwrap();
 
     // 写入测试数据：包含正确和错误的行
     writeln!(log_file, "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1.").unwrap();
     log_file.write_all(&[0xFF, 0xFE, 0xFD]).unwrap(); // 无效 UTF8 字节
-    writeln!(log_file, "").unwrap();
+    writeln!(log_file).unwrap();
     writeln!(log_file, "2025-09-21 12:00:01.000 (EP[1] sess:NULL thrd:1 user:usr trxid:2 stmt:NULL) [SEL]: select 2 EXECTIME: 2(ms) ROWCOUNT: 1 EXEC_ID: 2.").unwrap();
     log_file.flush().unwrap();
 
@@ -39,11 +42,27 @@ fn test_error_writing_integration() {
         enable_stdout: true,
         log_dir: Some(temp_dir.path().to_path_buf()),
         log_level: log::LevelFilter::Debug,
+        log_otlp_endpoint: None,
+        log_otlp_service_name: None,
         sqllog_dir: Some(log_dir.to_path_buf()),
         sqllog_chunk_size: Some(0),
         parser_threads: 1,
         sqllog_write_errors: true, // 启用错误写入
         sqllog_errors_out_path: Some(error_file_path.clone()),
+        sqllog_errors_format: Default::default(),
+        sqllog_errors_table: false,
+        sqllog_file_timeout: None,
+        sqllog_record_id: Default::default(),
+        sqllog_extract_plans: false,
+        sqllog_link_exec_phase: false,
+        sqllog_prefilter: None,
+        sqllog_blank_fields: BlankFields::Null,
+        sqllog_sample: None,
+        sqllog_trace_lines: Default::default(),
+        sqllog_parse_mode: Default::default(),
+        sqllog_file_order: Default::default(),
+        sqllog_error_breaker: None,
+        sqllog_cache_dir: None,
         export_enabled: false,
         export_format: "csv".to_string(),
         export_out_path: None,
@@ -55,8 +74,28 @@ fn test_error_writing_integration() {
                 append: false,
             },
             file_size_bytes: None,
+            ..Default::default()
         },
         use_in_memory: true,
+        insert_rate_limit: Default::default(),
+        insert_auto_tune: None,
+        cluster_by_time: false,
+        db_resume: false,
+        db_split_cold: false,
+        db_record_runs: false,
+        max_memory_bytes: None,
+        analyze_memory_limit_mb: 1024,
+        analyze_temp_dir: None,
+        jobs_state_path: None,
+        jobs_max_retries: 3,
+        notify: None,
+        history_state_path: None,
+        classify_rules: Vec::new(),
+        classify_forbidden_out: None,
+        apps_aliases: Default::default(),
+        apps_top_fingerprints: 5,
+        plugin_command: None,
+        plugin_timeout: None,
     };
 
     // 处理文件
@@ -92,7 +131,7 @@ fn test_error_writing_integration() {
 
     // 应该有 1 个或更多错误行（UTF8 错误）
     assert!(
-        error_lines.len() >= 1,
+        !error_lines.is_empty(),
         "应该至少有 1 个错误行，实际有 {}",
         error_lines.len()
     );
@@ -127,7 +166,7 @@ fn test_error_writing_disabled() {
     let log_dir = temp_dir.path();
 
     // 创建带有错误行的测试 sqllog 文件
-    let mut log_file = NamedTempFile::new_in(&log_dir).unwrap();
+    let mut log_file = NamedTempFile::new_in(log_dir).unwrap();
     writeln!(log_file, "这是一个无效的日志行").unwrap();
     log_file.flush().unwrap();
 
@@ -147,11 +186,27 @@ fn test_error_writing_disabled() {
         enable_stdout: true,
         log_dir: Some(temp_dir.path().to_path_buf()),
         log_level: log::LevelFilter::Debug,
+        log_otlp_endpoint: None,
+        log_otlp_service_name: None,
         sqllog_dir: Some(log_dir.to_path_buf()),
         sqllog_chunk_size: Some(0),
         parser_threads: 1,
         sqllog_write_errors: false, // 禁用错误写入
         sqllog_errors_out_path: Some(error_file_path.clone()),
+        sqllog_errors_format: Default::default(),
+        sqllog_errors_table: false,
+        sqllog_file_timeout: None,
+        sqllog_record_id: Default::default(),
+        sqllog_extract_plans: false,
+        sqllog_link_exec_phase: false,
+        sqllog_prefilter: None,
+        sqllog_blank_fields: BlankFields::Null,
+        sqllog_sample: None,
+        sqllog_trace_lines: Default::default(),
+        sqllog_parse_mode: Default::default(),
+        sqllog_file_order: Default::default(),
+        sqllog_error_breaker: None,
+        sqllog_cache_dir: None,
         export_enabled: false,
         export_format: "csv".to_string(),
         export_out_path: None,
@@ -163,8 +218,28 @@ fn test_error_writing_disabled() {
                 append: false,
             },
             file_size_bytes: None,
+            ..Default::default()
         },
         use_in_memory: true,
+        insert_rate_limit: Default::default(),
+        insert_auto_tune: None,
+        cluster_by_time: false,
+        db_resume: false,
+        db_split_cold: false,
+        db_record_runs: false,
+        max_memory_bytes: None,
+        analyze_memory_limit_mb: 1024,
+        analyze_temp_dir: None,
+        jobs_state_path: None,
+        jobs_max_retries: 3,
+        notify: None,
+        history_state_path: None,
+        classify_rules: Vec::new(),
+        classify_forbidden_out: None,
+        apps_aliases: Default::default(),
+        apps_top_fingerprints: 5,
+        plugin_command: None,
+        plugin_timeout: None,
     };
 
     // 处理文件
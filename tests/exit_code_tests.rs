@@ -0,0 +1,32 @@
+use sqllog_analysis::exit_code::ExitCode;
+
+#[test]
+fn exit_codes_are_distinct_and_stable() {
+    let codes = [
+        ExitCode::Success,
+        ExitCode::Failure,
+        ExitCode::InvalidConfig,
+        ExitCode::PartialSuccess,
+        ExitCode::ParseErrorsExceeded,
+        ExitCode::ExportFailed,
+    ];
+    let values: Vec<i32> = codes.iter().map(|c| c.code()).collect();
+    assert_eq!(values, vec![0, 1, 2, 3, 4, 5]);
+    assert!(ExitCode::default().is_success());
+    assert_eq!(ExitCode::ExportFailed.to_string(), "导出失败（退出码 5）");
+}
+
+#[test]
+fn combined_outcome_keeps_the_most_severe() {
+    let partial = ExitCode::Success.or(ExitCode::PartialSuccess);
+    assert_eq!(partial, ExitCode::PartialSuccess);
+    assert_eq!(
+        partial.or(ExitCode::ParseErrorsExceeded),
+        ExitCode::ParseErrorsExceeded
+    );
+    assert_eq!(
+        ExitCode::ExportFailed.or(ExitCode::ParseErrorsExceeded),
+        ExitCode::ExportFailed
+    );
+    assert_eq!(ExitCode::Failure.or(ExitCode::Success), ExitCode::Failure);
+}
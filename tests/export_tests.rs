@@ -0,0 +1,1177 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::analysis::SessionAnalyzer;
+use sqllog_analysis::config::{ExportOptions, RuntimeConfig};
+use sqllog_analysis::database::{
+    ALIASED_VIEW, ColumnAliases, Compression, CsvExportOptions,
+    DatabaseProvider, DuckDbProvider, ExportFormat, ExportManifest,
+    FormatOptions, HIVE_DEFAULT_PARTITION, IndependentDatabaseStats,
+    JsonLayout, LineTemplate, LoadOptions, Newlines, NullAs, RedactMode,
+    Redactions, SavedFormat, SharedDuckDbSink, SharedExporter, SqllogStore,
+    SyncExporter, TemplateError, TemplateExporter, export_targets, file_sha256,
+    load_saved_records, read_saved_records,
+};
+use sqllog_analysis::sqllog::{ExecTimeMs, RowCount, Sqllog};
+use std::collections::BTreeMap;
+use std::fs;
+use tempfile::tempdir;
+
+mod common;
+use common::record;
+
+fn memory_provider() -> DuckDbProvider {
+    let config = RuntimeConfig { use_in_memory: true, ..Default::default() };
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider.initialize().unwrap();
+    provider
+}
+
+#[test]
+fn export_truncates_description_and_writes_overflow_sidecar() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+    let sidecar = dir.path().join("overflow.jsonl");
+
+    let mut provider = memory_provider();
+    let long = "x".repeat(50);
+    provider.insert_batch(&[record("short"), record(&long)]).unwrap();
+
+    let options = ExportOptions {
+        description_max_bytes: Some(10),
+        description_overflow_path: Some(sidecar.clone()),
+        ..Default::default()
+    };
+    provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+
+    let csv = fs::read_to_string(&out).unwrap();
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("record_id,occurrence_time"));
+    assert!(csv.contains(",short,"));
+    assert!(csv.contains(&format!(",{},", "x".repeat(10))));
+    assert!(!csv.contains(&"x".repeat(11)));
+
+    let overflow = fs::read_to_string(&sidecar).unwrap();
+    let rows: Vec<serde_json::Value> =
+        overflow.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["record_id"], 1);
+    assert_eq!(rows[0]["description"], long);
+}
+
+#[test]
+fn export_truncates_description_at_utf8_char_boundary() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+    let sidecar = dir.path().join("overflow.jsonl");
+
+    let mut provider = memory_provider();
+    // 3 + 3 + 4 + 1 字节，只有前 6 字节的字符能完整放进 8 字节
+    provider.insert_batch(&[record("中文😀x"), record("中")]).unwrap();
+
+    let options = ExportOptions {
+        description_max_bytes: Some(8),
+        description_overflow_path: Some(sidecar.clone()),
+        ..Default::default()
+    };
+    provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+
+    let csv = fs::read_to_string(&out).unwrap();
+    assert!(csv.contains(",中文,"));
+    assert!(csv.contains(",中,"));
+    assert!(!csv.contains('😀'));
+
+    // 字符数不超过上限但字节数超出时同样写入旁路文件
+    let overflow = fs::read_to_string(&sidecar).unwrap();
+    let rows: Vec<serde_json::Value> =
+        overflow.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0]["description"], "中文😀x");
+}
+
+#[test]
+fn export_without_options_keeps_full_description() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+
+    let mut provider = memory_provider();
+    let long = "y".repeat(50);
+    provider.insert_batch(&[record(&long)]).unwrap();
+    provider.export_data(ExportFormat::Csv, &out.to_string_lossy()).unwrap();
+
+    let csv = fs::read_to_string(&out).unwrap();
+    assert!(csv.lines().next().unwrap().starts_with("occurrence_time,"));
+    assert!(csv.contains(&long));
+}
+
+#[test]
+fn gzip_compression_appends_extension_and_round_trips() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+
+    let mut provider = memory_provider();
+    provider.insert_batch(&[record("a"), record("b")]).unwrap();
+    let options = ExportOptions {
+        compression: Some(Compression::Gzip),
+        ..Default::default()
+    };
+    let report = provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+
+    let written = dir.path().join("out.csv.gz");
+    assert_eq!(report.artifacts[0].path, written.to_string_lossy());
+    assert!(!out.exists());
+    // gzip 魔数
+    assert_eq!(&fs::read(&written).unwrap()[..2], &[0x1f, 0x8b]);
+    let count: i64 = duckdb::Connection::open_in_memory()
+        .unwrap()
+        .query_row(
+            &format!(
+                "SELECT COUNT(*) FROM read_csv('{}')",
+                written.to_string_lossy()
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(count, 2);
+}
+
+#[test]
+fn compression_parses_codec_names() {
+    assert_eq!("gzip".parse(), Ok(Compression::Gzip));
+    assert_eq!("ZST".parse(), Ok(Compression::Zstd));
+    assert!("zstd:3".parse::<Compression>().unwrap_err().contains("级别"));
+    assert!("lz4".parse::<Compression>().is_err());
+    assert_eq!(Compression::Zstd.output_path("a.csv"), "a.csv.zst");
+    assert_eq!(Compression::Gzip.output_path("a.csv.gz"), "a.csv.gz");
+}
+
+#[test]
+fn sha256_matches_known_vectors() {
+    let dir = tempdir().unwrap();
+    let cases = [
+        (
+            "".to_string(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            "abc".to_string(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        // 填充恰好放得下 / 放不下长度字段的边界
+        (
+            "a".repeat(55),
+            "9f4390f8d30c2dd92ec9f095b65e2b9ae9b0a925a5258e241c9f1e910f734318",
+        ),
+        (
+            "a".repeat(64),
+            "ffe054fe7ae0cb6dc65c3af9b61d5209f439851db43d0ba5997337df154668eb",
+        ),
+        (
+            "abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"
+                .to_string(),
+            "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1",
+        ),
+        (
+            "abcdefghbcdefghicdefghijdefghijkefghijklfghijklmghijklmnhijklmnoijklmnopjklmnopqklmnopqrlmnopqrsmnopqrstnopqrstu"
+                .to_string(),
+            "cf5b16a778af8380036ce59e7b0492370b249b11e8f07a51afac45037afee9d1",
+        ),
+        (
+            "a".repeat(1_000_000),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0",
+        ),
+    ];
+    for (i, (content, expected)) in cases.iter().enumerate() {
+        let path = dir.path().join(format!("case_{i}"));
+        fs::write(&path, content).unwrap();
+        assert_eq!(file_sha256(&path).unwrap(), *expected);
+    }
+}
+
+#[test]
+fn manifest_lists_artifacts_with_checksums() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+    let sidecar = dir.path().join("overflow.jsonl");
+    let manifest_path = dir.path().join("manifest.json");
+
+    let mut provider = memory_provider();
+    let long = "z".repeat(50);
+    provider.insert_batch(&[record("a"), record("b"), record(&long)]).unwrap();
+
+    let options = ExportOptions {
+        description_max_bytes: Some(10),
+        description_overflow_path: Some(sidecar.clone()),
+        ..Default::default()
+    };
+    let report = provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+    assert_eq!(report.records_exported, 3);
+    assert_eq!(report.artifacts.len(), 2);
+
+    let stats = IndependentDatabaseStats {
+        records_processed: 3,
+        records_inserted: 3,
+        files_processed: 1,
+        temp_databases_created: 0,
+        ..Default::default()
+    };
+    ExportManifest::build(&ExportFormat::Csv, &report, Some(&stats))
+        .unwrap()
+        .write_to(&manifest_path)
+        .unwrap();
+
+    let manifest: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap())
+            .unwrap();
+    assert_eq!(manifest["format"], "csv");
+    assert_eq!(manifest["records_exported"], 3);
+    assert_eq!(manifest["parse_stats"]["files_processed"], 1);
+
+    let artifacts = manifest["artifacts"].as_array().unwrap();
+    assert_eq!(artifacts[0]["records"], 3);
+    assert_eq!(artifacts[1]["records"], 1);
+    assert_eq!(
+        artifacts[0]["sha256"].as_str().unwrap(),
+        file_sha256(&out).unwrap()
+    );
+    assert_eq!(
+        artifacts[1]["bytes"].as_u64().unwrap(),
+        fs::metadata(&sidecar).unwrap().len()
+    );
+}
+
+#[test]
+fn csv_format_options_control_copy_output() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+
+    let mut provider = memory_provider();
+    provider.insert_batch(&[record("select 1")]).unwrap();
+
+    let options = ExportOptions {
+        format_options: FormatOptions {
+            csv: CsvExportOptions::default()
+                .delimiter(';')
+                .header(false)
+                .null_string("NULL"),
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+
+    let csv = fs::read_to_string(&out).unwrap();
+    assert_eq!(csv.lines().count(), 1);
+    assert!(csv.starts_with("2025-09-21 12:00:00.000;1;NULL;"));
+    assert!(csv.contains(";select 1;"));
+}
+
+#[test]
+fn format_options_parse_key_value_pairs() {
+    let options = FormatOptions::from_pairs([
+        "csv.delimiter=\\t",
+        "csv.header = false",
+        "json.layout=array",
+    ])
+    .unwrap();
+    assert_eq!(options.csv.delimiter, '\t');
+    assert!(!options.csv.header);
+    assert_eq!(options.json.layout, JsonLayout::Array);
+
+    assert!(FormatOptions::from_pairs(["csv.delimiter=;;"]).is_err());
+    assert!(FormatOptions::from_pairs(["csv.header=yes"]).is_err());
+    assert!(FormatOptions::from_pairs(["sqlite.path=x"]).is_err());
+    assert!(FormatOptions::from_pairs(["csv.delimiter"]).is_err());
+}
+
+#[test]
+fn record_id_column_is_exported_and_indexed() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+    let sidecar = dir.path().join("overflow.jsonl");
+
+    let mut provider = memory_provider();
+    let long = "w".repeat(30);
+    let mut first = record("short");
+    first.record_id = Some(42);
+    let mut second = record(&long);
+    second.record_id = Some(u64::MAX);
+    provider.insert_batch(&[first, second]).unwrap();
+    provider.finalize_schema().unwrap();
+
+    provider.export_data(ExportFormat::Csv, &out.to_string_lossy()).unwrap();
+    let csv = fs::read_to_string(&out).unwrap();
+    assert!(
+        csv.lines()
+            .next()
+            .unwrap()
+            .ends_with(",record_id,plan,execute_time_us,partial")
+    );
+    assert!(csv.contains(",42,,,false\n"));
+
+    let options = ExportOptions {
+        description_max_bytes: Some(10),
+        description_overflow_path: Some(sidecar.clone()),
+        ..Default::default()
+    };
+    provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+    let overflow: serde_json::Value = serde_json::from_str(
+        fs::read_to_string(&sidecar).unwrap().lines().next().unwrap(),
+    )
+    .unwrap();
+    assert_eq!(overflow["record_id"], u64::MAX);
+
+    // record_id 的唯一约束拒绝重复的记录 ID
+    let mut dup = record("dup");
+    dup.record_id = Some(42);
+    assert!(provider.insert_batch(&[dup]).is_err());
+}
+
+fn aliases(pairs: &[(&str, &str)]) -> ColumnAliases {
+    let map: BTreeMap<String, String> = pairs
+        .iter()
+        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        .collect();
+    ColumnAliases::from_map(&map).unwrap()
+}
+
+#[test]
+fn export_applies_column_aliases() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("aliased.csv");
+    let sidecar = dir.path().join("overflow.jsonl");
+
+    let mut provider = memory_provider();
+    provider.insert_batch(&[record("short"), record(&"y".repeat(20))]).unwrap();
+
+    let column_aliases = aliases(&[
+        ("user", "db_user"),
+        ("occurrence_time", "event_time"),
+        ("record_id", "event_id"),
+        ("description", "sql text"),
+    ]);
+    let options = ExportOptions {
+        description_max_bytes: Some(8),
+        description_overflow_path: Some(sidecar.clone()),
+        column_aliases: column_aliases.clone(),
+        ..Default::default()
+    };
+    provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+
+    let csv = fs::read_to_string(&out).unwrap();
+    let header = csv.lines().next().unwrap();
+    assert!(
+        header.starts_with("event_id,event_time,ep,session,thread,db_user,")
+    );
+    assert!(header.contains(",sql text,"));
+    assert!(!header.contains("username"));
+
+    let overflow = fs::read_to_string(&sidecar).unwrap();
+    let row: serde_json::Value =
+        serde_json::from_str(overflow.lines().next().unwrap()).unwrap();
+    assert_eq!(row["sql text"], "y".repeat(20));
+    assert!(row.get("event_id").is_some());
+
+    // 数据库输出：带别名的视图，原表结构不变
+    provider.create_aliased_view(&column_aliases).unwrap();
+    let out_view = dir.path().join("view.csv");
+    provider
+        .execute_sql(&format!(
+            "COPY (SELECT db_user, \"sql text\" FROM {ALIASED_VIEW} ORDER BY 2) TO '{}' (HEADER)",
+            out_view.to_string_lossy()
+        ))
+        .unwrap();
+    let view_csv = fs::read_to_string(&out_view).unwrap();
+    assert_eq!(view_csv.lines().next().unwrap(), "db_user,sql text");
+    assert!(view_csv.contains("SYSDBA,short"));
+    assert_eq!(provider.count_records().unwrap(), 2);
+}
+
+#[test]
+fn column_aliases_are_validated() {
+    let map = |pairs: &[(&str, &str)]| -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    };
+    assert!(ColumnAliases::from_map(&map(&[("nope", "x")])).is_err());
+    assert!(ColumnAliases::from_map(&map(&[("ip", " ")])).is_err());
+    // 改名后与其他列重名
+    assert!(ColumnAliases::from_map(&map(&[("ip", "appname")])).is_err());
+    assert!(
+        ColumnAliases::from_map(&map(&[("ip", "addr"), ("appname", "addr")]))
+            .is_err()
+    );
+
+    let ok = aliases(&[("username", "db_user")]);
+    assert_eq!(ok.output_name("username"), "db_user");
+    assert_eq!(ok.output_name("ip"), "ip");
+    assert!(ColumnAliases::default().is_empty());
+}
+
+#[test]
+fn multiple_formats_share_one_export_base_path() {
+    assert_eq!(
+        ExportFormat::parse_list(" csv, JSON ,csv").unwrap(),
+        vec![ExportFormat::Csv, ExportFormat::Json]
+    );
+    assert!(ExportFormat::parse_list("csv,sqlite").is_err());
+    assert!(ExportFormat::parse_list(" , ").is_err());
+
+    let base = std::path::Path::new("out/result.csv");
+    assert_eq!(
+        export_targets(base, &[ExportFormat::Json]),
+        vec![(ExportFormat::Json, base.to_path_buf())]
+    );
+    assert_eq!(
+        export_targets(base, &[ExportFormat::Csv, ExportFormat::Json]),
+        vec![
+            (ExportFormat::Csv, "out/result.csv".into()),
+            (ExportFormat::Json, "out/result.json".into()),
+        ]
+    );
+}
+
+#[test]
+fn cluster_by_occurrence_time_orders_table_and_export() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+    let mut provider = memory_provider();
+    let at = |time: &str, description: &str| Sqllog {
+        occurrence_time: time.to_string(),
+        ..record(description)
+    };
+    provider
+        .insert_batch(&[
+            at("2025-09-21 12:00:02.000", "c"),
+            at("2025-09-21 12:00:00.000", "a1"),
+            at("2025-09-21 12:00:01.000", "b"),
+            at("2025-09-21 12:00:00.000", "a2"),
+        ])
+        .unwrap();
+
+    provider.cluster_by_occurrence_time().unwrap();
+    assert_eq!(provider.count_records().unwrap(), 4);
+    provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &ExportOptions::default(),
+        )
+        .unwrap();
+
+    let csv = fs::read_to_string(&out).unwrap();
+    let positions: Vec<usize> = ["a1", "a2", ",b,", ",c,"]
+        .iter()
+        .map(|needle| csv.find(needle).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|w| w[0] < w[1]), "{csv}");
+}
+
+#[test]
+fn top_sessions_export_matches_session_ranking() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.csv");
+
+    let session = |sess: Option<&str>, ms: i64, rows: i64| Sqllog {
+        session: sess.map(str::to_string),
+        execute_time: Some(ExecTimeMs::new(ms)),
+        rowcount: Some(RowCount::new(rows)),
+        ..record(&format!("{sess:?} {ms}"))
+    };
+    let records = vec![
+        session(Some("0x1"), 5, 1),
+        session(Some("0x1"), 5, 1),
+        session(Some("0x2"), 20, 0),
+        // 与 0x1 执行时间相同，按影响行数排在其前
+        session(Some("0x3"), 10, 7),
+        // 无会话信息的记录不参与排名
+        session(None, 100, 0),
+    ];
+
+    let mut analyzer = SessionAnalyzer::new(2);
+    analyzer.observe(&records);
+    let ranked: Vec<String> =
+        analyzer.report().into_iter().map(|s| s.session).collect();
+    assert_eq!(ranked, vec!["0x2", "0x3"]);
+    let top = &analyzer.top(3)[2];
+    assert_eq!((top.records, top.total_rowcount), (2, RowCount::new(2)));
+
+    let mut provider = memory_provider();
+    provider.insert_batch(&records).unwrap();
+    let options = ExportOptions { top_sessions: Some(2), ..Default::default() };
+    let report = provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+    assert_eq!(report.records_exported, 2);
+
+    let csv = fs::read_to_string(&out).unwrap();
+    let rows: Vec<&str> = csv.lines().skip(1).collect();
+    assert_eq!(rows.len(), 2);
+    assert!(rows.iter().all(|r| r.contains("0x2") || r.contains("0x3")));
+}
+
+#[test]
+fn line_template_is_validated_at_construction() {
+    assert_eq!(
+        LineTemplate::parse("{occurrence_time} {nope}").unwrap_err(),
+        TemplateError::UnknownField("nope".to_string())
+    );
+    assert!(matches!(
+        LineTemplate::parse("{description:.x}"),
+        Err(TemplateError::InvalidSpec { .. })
+    ));
+    assert!(matches!(
+        LineTemplate::parse("{user"),
+        Err(TemplateError::Unclosed(0))
+    ));
+    assert!(matches!(
+        LineTemplate::parse("a } b {ep}"),
+        Err(TemplateError::UnmatchedClose(2))
+    ));
+    assert_eq!(
+        LineTemplate::parse("no fields {{}}").unwrap_err(),
+        TemplateError::NoFields
+    );
+    assert!(TemplateExporter::new("{bogus}", Vec::new()).is_err());
+}
+
+#[test]
+fn template_renders_records_with_width_and_truncation() {
+    let template = LineTemplate::parse(
+        "{occurrence_time}\t{user:<8}|{execute_time:>4}ms \
+         {description:.6}{{{session}}}",
+    )
+    .unwrap();
+    assert_eq!(template.columns()[1], "username");
+
+    let mut out = Vec::new();
+    let mut exporter = TemplateExporter::with_template(template, &mut out);
+    exporter.write_records(&[record("select 1 from dual")]).unwrap();
+    assert_eq!(exporter.finish().unwrap(), 1);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "2025-09-21 12:00:00.000\tSYSDBA  |   1ms select{}\n"
+    );
+}
+
+#[test]
+fn shared_exporter_keeps_batches_contiguous_across_threads() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("shared.txt");
+    let exporter =
+        TemplateExporter::create("{user} {description}", &out).unwrap();
+    let shared = SharedExporter::new(exporter);
+
+    let workers: Vec<_> = ["a", "b", "c"]
+        .into_iter()
+        .map(|user| {
+            let mut handle = shared.clone_handle();
+            std::thread::spawn(move || {
+                for batch in 0..20 {
+                    let records: Vec<Sqllog> = (0..5)
+                        .map(|i| Sqllog {
+                            user: Some(user.to_string()),
+                            description: format!("{batch}-{i}"),
+                            ..Default::default()
+                        })
+                        .collect();
+                    handle.write_records(&records).unwrap();
+                }
+            })
+        })
+        .collect();
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    assert_eq!(shared.with(|e| e.written()).unwrap(), 300);
+
+    // 仍有其他句柄时不能取回导出器
+    let pending = shared.clone_handle();
+    assert_eq!(pending.handles(), 2);
+    let shared = shared.into_inner().unwrap_err();
+    drop(pending);
+    assert_eq!(shared.into_inner().unwrap().finish().unwrap(), 300);
+
+    let text = fs::read_to_string(&out).unwrap();
+    let lines: Vec<&str> = text.lines().collect();
+    for batch in lines.chunks(5) {
+        let (prefix, _) = batch[0].split_once('-').unwrap();
+        for (i, line) in batch.iter().enumerate() {
+            assert_eq!(*line, format!("{prefix}-{i}"));
+        }
+    }
+}
+
+#[test]
+fn shared_duckdb_sink_serializes_concurrent_writers() {
+    let dir = tempdir().unwrap();
+    let db = dir.path().join("shared.duckdb");
+    let config = RuntimeConfig {
+        db_path: db.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    let sink = SharedDuckDbSink::with_queue(&config, 2).unwrap();
+
+    let workers: Vec<_> = ["a", "b", "c", "d"]
+        .into_iter()
+        .map(|user| {
+            let mut handle = sink.clone_handle();
+            std::thread::spawn(move || {
+                for batch in 0..10 {
+                    let records: Vec<Sqllog> = (0..25)
+                        .map(|i| Sqllog {
+                            user: Some(user.to_string()),
+                            ..record(&format!("{batch}-{i}"))
+                        })
+                        .collect();
+                    handle.write_records(&records).unwrap();
+                }
+            })
+        })
+        .collect();
+
+    for worker in workers {
+        worker.join().unwrap();
+    }
+    // 仍有其他句柄时不能收尾
+    let pending = sink.clone_handle();
+    let sink = sink.finish().unwrap_err();
+    drop(pending);
+    assert_eq!(sink.finish().unwrap().unwrap(), 1000);
+
+    let store = SqllogStore::open(&db).unwrap();
+    assert_eq!(store.count().unwrap(), 1000);
+}
+
+#[test]
+fn template_format_exports_from_database() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("out.txt");
+
+    let mut provider = memory_provider();
+    provider.insert_batch(&[record("first"), record("second")]).unwrap();
+
+    let options = ExportOptions {
+        template: Some(
+            LineTemplate::parse("{ep}:{user}:{trx_id}:{description:.3}")
+                .unwrap(),
+        ),
+        ..Default::default()
+    };
+    let report = provider
+        .export_with_options(
+            ExportFormat::Template,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+    assert_eq!(report.records_exported, 2);
+    assert_eq!(
+        fs::read_to_string(&out).unwrap(),
+        "1:SYSDBA::fir\n1:SYSDBA::sec\n"
+    );
+
+    // 未配置模板时报错
+    assert!(
+        provider
+            .export_with_options(
+                ExportFormat::Template,
+                &out.to_string_lossy(),
+                &ExportOptions::default(),
+            )
+            .is_err()
+    );
+    assert_eq!("template".parse::<ExportFormat>(), Ok(ExportFormat::Template));
+}
+
+#[test]
+fn redaction_applies_only_to_its_export_format() {
+    let dir = tempdir().unwrap();
+    let csv_out = dir.path().join("shared.csv");
+    let txt_out = dir.path().join("internal.txt");
+
+    let mut provider = memory_provider();
+    let mut rec =
+        record("SELECT * FROM t1 WHERE name = 'it''s me' AND id = 42");
+    rec.ip = Some("10.0.0.1".to_string());
+    provider.insert_batch(&[rec]).unwrap();
+
+    let map: BTreeMap<String, BTreeMap<String, String>> = [(
+        "csv".to_string(),
+        [("description", "literals"), ("user", "hash"), ("ip", "null")]
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect(),
+    )]
+    .into_iter()
+    .collect();
+    let options = ExportOptions {
+        redactions: Redactions::from_map(&map).unwrap(),
+        template: Some(
+            LineTemplate::parse("{user}|{ip}|{description}").unwrap(),
+        ),
+        ..Default::default()
+    };
+    for (format, out) in
+        [(ExportFormat::Csv, &csv_out), (ExportFormat::Template, &txt_out)]
+    {
+        provider
+            .export_with_options(format, &out.to_string_lossy(), &options)
+            .unwrap();
+    }
+
+    let csv = fs::read_to_string(&csv_out).unwrap();
+    assert!(csv.contains("SELECT * FROM t1 WHERE name = ? AND id = ?"));
+    assert!(!csv.contains("SYSDBA"));
+    assert!(csv.contains(",4811df2c83bee7ee3a883640cc4d80af,"));
+    assert!(!csv.contains("10.0.0.1"));
+
+    // 未配置规则的格式与数据库保留完整数据
+    assert_eq!(
+        fs::read_to_string(&txt_out).unwrap(),
+        "SYSDBA|10.0.0.1|SELECT * FROM t1 WHERE name = 'it''s me' AND id = 42\n"
+    );
+    assert_eq!(provider.count_records().unwrap(), 1);
+}
+
+#[test]
+fn redaction_rules_are_validated() {
+    let rules = Redactions::default();
+    assert!(rules.is_empty_for(&ExportFormat::Csv));
+    assert!(
+        rules
+            .clone()
+            .redact(ExportFormat::Csv, "nope", RedactMode::Null)
+            .is_err()
+    );
+    // description 总是有值，execute_time 不是文本列，occurrence_time 不能脱敏
+    for (column, mode) in [
+        ("description", RedactMode::Null),
+        ("execute_time", RedactMode::Hash),
+        ("occurrence_time", RedactMode::Hash),
+    ] {
+        assert!(rules.clone().redact(ExportFormat::Csv, column, mode).is_err());
+    }
+    assert!("mask".parse::<RedactMode>().is_err());
+
+    let rules = rules
+        .redact(ExportFormat::Json, "user", RedactMode::Hash)
+        .unwrap()
+        .redact(ExportFormat::Json, "execute_time", RedactMode::Null)
+        .unwrap();
+    assert_eq!(
+        rules.mode(&ExportFormat::Json, "username"),
+        Some(RedactMode::Hash)
+    );
+    assert_eq!(rules.mode(&ExportFormat::Csv, "username"), None);
+    assert_eq!(rules.column_sql(&ExportFormat::Csv, "ip"), "ip");
+    assert_eq!(rules.column_sql(&ExportFormat::Json, "execute_time"), "NULL");
+}
+
+#[test]
+fn export_format_is_inferred_from_output_extension() {
+    let path = |p: &str| std::path::PathBuf::from(p);
+    assert_eq!(
+        ExportFormat::from_path(&path("out/report.CSV")),
+        Some(ExportFormat::Csv)
+    );
+    assert_eq!(
+        ExportFormat::from_path(&path("report.txt")),
+        Some(ExportFormat::Template)
+    );
+    assert_eq!(
+        ExportFormat::from_path(&path("report.json.gz")),
+        Some(ExportFormat::Json)
+    );
+    assert_eq!(ExportFormat::from_path(&path("report.sqlite")), None);
+    assert_eq!(ExportFormat::from_path(&path("report.gz")), None);
+    assert_eq!(ExportFormat::from_path(&path("report")), None);
+
+    assert_eq!(
+        Compression::from_path(&path("report.csv.zst")),
+        Some(Compression::Zstd)
+    );
+    assert_eq!(Compression::from_path(&path("report.csv")), None);
+}
+
+#[test]
+fn ddl_audit_file_contains_only_ddl_statements() {
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("ddl.csv");
+    let mut records = vec![
+        record("create table t1 (id int)"),
+        record("  ALTER TABLE t1 ADD c2 INT"),
+        record("select * from created_items"),
+        record("drop index idx_t1"),
+        record("insert into dropped values (1)"),
+    ];
+    records[0].sql_type = Some("ORA".to_string());
+    records[2].sql_type = Some("SEL".to_string());
+    records[4].sql_type = Some("INS".to_string());
+
+    let mut provider = memory_provider();
+    provider.insert_batch(&records).unwrap();
+    let options = ExportOptions { top_sessions: Some(1), ..Default::default() };
+    let report =
+        provider.export_ddl_audit(&out.to_string_lossy(), &options).unwrap();
+    assert_eq!(report.records_exported, 3);
+
+    let csv = fs::read_to_string(&out).unwrap();
+    let rows: Vec<&str> = csv.lines().skip(1).collect();
+    assert_eq!(rows.len(), 3);
+    assert!(rows[0].contains("create table t1"));
+    assert!(rows[2].contains("drop index idx_t1"));
+}
+
+#[test]
+fn saved_json_records_load_and_reexport() {
+    let dir = tempdir().unwrap();
+    let saved = dir.path().join("records.jsonl");
+    fs::write(
+        &saved,
+        concat!(
+            r#"{"occurrence_time":"2025-09-21 12:00:00.000","ep":1,"session":"0x1","thread":"7","username":"SYSDBA","trx_id":"9","statement":null,"appname":null,"ip":null,"sql_type":"SEL","description":"select 1","execute_time":5,"rowcount":1,"execute_id":42,"record_id":3,"plan":"{\"operator\":\"PRJT2\",\"cost\":1,\"rows\":2,\"width\":3,\"detail\":\"exp_num(1)\",\"children\":[]}","execute_time_us":null,"partial":false}"#,
+            "\n\n",
+            r#"{"occurrence_time":"2025-09-21 12:00:01.000","ep":0,"user":"bob","description":"update t set a = 1"}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+
+    let records = read_saved_records(&saved, &LoadOptions::default()).unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].user.as_deref(), Some("SYSDBA"));
+    assert_eq!(records[0].execute_id.map(i64::from), Some(42));
+    assert_eq!(records[0].plan.as_ref().unwrap().operator, "PRJT2");
+    assert_eq!(records[1].user.as_deref(), Some("bob"));
+    assert!(records[1].execute_time.is_none());
+
+    let mut provider = memory_provider();
+    let options = LoadOptions { batch_size: 1, ..Default::default() };
+    assert_eq!(load_saved_records(&mut provider, &saved, &options).unwrap(), 2);
+    let out = dir.path().join("out.csv");
+    let report = provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &ExportOptions::default(),
+        )
+        .unwrap();
+    assert_eq!(report.records_exported, 2);
+    let csv = fs::read_to_string(&out).unwrap();
+    assert!(csv.contains("SYSDBA"));
+    assert!(csv.contains("update t set a = 1"));
+
+    let array = dir.path().join("records.json");
+    fs::write(&array, r#" [{"occurrence_time":"t","ep":2,"description":""}]"#)
+        .unwrap();
+    let records = read_saved_records(&array, &LoadOptions::default()).unwrap();
+    assert_eq!(records[0].ep, 2);
+    assert!(records[0].description.is_empty());
+}
+
+#[test]
+fn saved_records_are_validated_against_schema() {
+    let dir = tempdir().unwrap();
+    let saved = dir.path().join("records.jsonl");
+    let strict = LoadOptions::default();
+    fs::write(
+        &saved,
+        concat!(
+            r#"{"occurrence_time":"t","ep":1,"description":"select 1"}"#,
+            "\n",
+            r#"{"occurrence_time":"t","ep":"1","exec_time":5,"trace":"x"}"#,
+            "\n",
+        ),
+    )
+    .unwrap();
+    let err = format!("{:#}", read_saved_records(&saved, &strict).unwrap_err());
+    assert!(err.contains("records.jsonl:2"), "{err}");
+    assert!(err.contains("缺少字段 description"), "{err}");
+    assert!(err.contains("字段 ep 应为32 位整数，实际为 字符串"), "{err}");
+    assert!(err.contains("未知字段 exec_time（是否为 execute_time 改名？）"));
+    assert!(err.contains("未知字段 trace"), "{err}");
+
+    // 允许多余字段时只忽略未知字段，其他问题照样报告
+    let lenient = LoadOptions { allow_extra_fields: true, ..strict };
+    let err =
+        format!("{:#}", read_saved_records(&saved, &lenient).unwrap_err());
+    assert!(!err.contains("未知字段"), "{err}");
+    fs::write(
+        &saved,
+        r#"{"occurrence_time":"t","ep":1,"description":"d","exec_time":5}"#,
+    )
+    .unwrap();
+    assert!(read_saved_records(&saved, &strict).is_err());
+    let records = read_saved_records(&saved, &lenient).unwrap();
+    assert!(records[0].execute_time.is_none());
+
+    let array = dir.path().join("records.json");
+    fs::write(&array, r#"[{"occurrence_time":"t","ep":1,"description":null}]"#)
+        .unwrap();
+    let err = format!("{:#}", read_saved_records(&array, &strict).unwrap_err());
+    assert!(err.contains("records.json[0]: 字段 description 不能为 null"));
+}
+
+#[test]
+fn saved_records_report_bad_line_and_unknown_extension() {
+    let dir = tempdir().unwrap();
+    let saved = dir.path().join("records.jsonl");
+    fs::write(
+        &saved,
+        "{\"occurrence_time\":\"t\",\"ep\":1,\"description\":\"\"}\n{\"ep\":1\n",
+    )
+    .unwrap();
+    let err = read_saved_records(&saved, &LoadOptions::default()).unwrap_err();
+    assert!(format!("{err:#}").contains("records.jsonl:2"));
+
+    let mut provider = memory_provider();
+    let csv = dir.path().join("records.csv");
+    fs::write(&csv, "").unwrap();
+    assert!(
+        load_saved_records(&mut provider, &csv, &LoadOptions::default())
+            .is_err()
+    );
+    assert_eq!(SavedFormat::from_path(&csv), None);
+    assert_eq!(
+        SavedFormat::from_path(std::path::Path::new("a.PARQUET")),
+        Some(SavedFormat::Parquet)
+    );
+}
+
+#[test]
+fn csv_null_as_keeps_null_and_empty_string_apart() {
+    let dir = tempdir().unwrap();
+    for null_as in
+        [NullAs::Empty, NullAs::BackslashN, NullAs::Literal("NULL".to_string())]
+    {
+        let mut with_empty = record("");
+        with_empty.session = Some(String::new());
+        with_empty.thread = Some(null_as.as_str().to_string());
+        let mut provider = memory_provider();
+        provider.insert_batch(&[with_empty, record("select 1")]).unwrap();
+
+        let out = dir.path().join("null.csv");
+        let options = ExportOptions {
+            format_options: FormatOptions {
+                csv: CsvExportOptions::default().null_as(&null_as),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        provider
+            .export_with_options(
+                ExportFormat::Csv,
+                &out.to_string_lossy(),
+                &options,
+            )
+            .unwrap();
+
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        let sql = format!(
+            "SELECT session, thread FROM read_csv('{}', nullstr = '{}', \
+             allow_quoted_nulls = false, all_varchar = true)",
+            out.display(),
+            null_as.as_str().replace('\'', "''")
+        );
+        let rows: Vec<(Option<String>, Option<String>)> = conn
+            .prepare(&sql)
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (Some(String::new()), Some(null_as.as_str().to_string())),
+                (None, None),
+            ],
+            "{null_as:?}"
+        );
+    }
+
+    let mut options = FormatOptions::default();
+    options.set("csv.null_as=\\N").unwrap();
+    assert_eq!(options.csv.null_string, "\\N");
+    options.set("csv.null_as=empty").unwrap();
+    assert_eq!(options.csv.null_string, "");
+}
+
+#[test]
+fn csv_newlines_round_trip_matrix() {
+    let descriptions = [
+        "select 1\nfrom t",
+        "select 2\r\nfrom t\r\nwhere x = 'a\nb'",
+        "select 3\rfrom t",
+        "select 'C:\\new\\table' from t",
+        "select '\\\\n' from t\n",
+        "select\t4",
+    ];
+    let dir = tempdir().unwrap();
+    for newlines in [Newlines::Keep, Newlines::Escape, Newlines::Space] {
+        let mut provider = memory_provider();
+        let records: Vec<Sqllog> =
+            descriptions.iter().map(|d| record(d)).collect();
+        provider.insert_batch(&records).unwrap();
+
+        let out = dir.path().join("newlines.csv");
+        let options = ExportOptions {
+            format_options: FormatOptions {
+                csv: CsvExportOptions::default().newlines(newlines),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        provider
+            .export_with_options(
+                ExportFormat::Csv,
+                &out.to_string_lossy(),
+                &options,
+            )
+            .unwrap();
+
+        let conn = duckdb::Connection::open_in_memory().unwrap();
+        let read: Vec<String> = conn
+            .prepare(&format!(
+                "SELECT description FROM read_csv('{}', all_varchar = true)",
+                out.display()
+            ))
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(read.len(), descriptions.len(), "{newlines:?}");
+
+        // 按行切分的解析器：除 keep 外每条记录恰好一行
+        let lines = fs::read_to_string(&out).unwrap().lines().count();
+        if newlines == Newlines::Keep {
+            assert!(lines > descriptions.len() + 1);
+        } else {
+            assert_eq!(lines, descriptions.len() + 1, "{newlines:?}");
+        }
+
+        for (original, exported) in descriptions.iter().zip(&read) {
+            match newlines {
+                Newlines::Keep | Newlines::Escape => assert_eq!(
+                    newlines.unfold(exported),
+                    *original,
+                    "{newlines:?}"
+                ),
+                Newlines::Space => assert_eq!(
+                    *exported,
+                    original.replace("\r\n", " ").replace(['\n', '\r'], " ")
+                ),
+            }
+        }
+    }
+
+    let mut options = FormatOptions::default();
+    options.set("csv.newlines=escape").unwrap();
+    assert_eq!(options.csv.newlines, Newlines::Escape);
+    assert!(options.set("csv.newlines=fold").is_err());
+}
+
+#[test]
+fn hive_partitions_route_records_by_occurrence_time() {
+    let mut provider = memory_provider();
+    let mut records = vec![record("select 1"), record("select 2")];
+    for (time, sql) in [
+        ("2024-12-31 23:59:59.999", "select 3"),
+        ("2025-01-01 00:00:00.000", "select 4"),
+        ("garbled", "select 5"),
+    ] {
+        let mut r = record(sql);
+        r.occurrence_time = time.to_string();
+        records.push(r);
+    }
+    provider.insert_batch(&records).unwrap();
+
+    let dir = tempdir().unwrap();
+    let out = dir.path().join("sqllogs.csv");
+    let options = ExportOptions { hive_partitions: true, ..Default::default() };
+    let report = provider
+        .export_with_options(
+            ExportFormat::Csv,
+            &out.to_string_lossy(),
+            &options,
+        )
+        .unwrap();
+
+    assert_eq!(report.records_exported, 5);
+    assert!(!out.exists());
+    let partitions: Vec<(String, u64)> = report
+        .artifacts
+        .iter()
+        .map(|a| {
+            let path = std::path::Path::new(&a.path);
+            assert_eq!(path.file_name().unwrap(), "sqllogs.csv");
+            let rel = path.parent().unwrap().strip_prefix(dir.path()).unwrap();
+            (rel.to_string_lossy().replace('\\', "/"), a.records)
+        })
+        .collect();
+    assert_eq!(
+        partitions,
+        vec![
+            ("year=2024/month=12/day=31".to_string(), 1),
+            ("year=2025/month=01/day=01".to_string(), 1),
+            ("year=2025/month=09/day=21".to_string(), 2),
+            (HIVE_DEFAULT_PARTITION.to_string(), 1),
+        ]
+    );
+    let day = fs::read_to_string(
+        dir.path().join("year=2025/month=09/day=21/sqllogs.csv"),
+    )
+    .unwrap();
+    assert!(day.contains("select 1") && day.contains("select 2"));
+    assert!(!day.contains("select 3"));
+}
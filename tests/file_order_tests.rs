@@ -0,0 +1,89 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::input_path::FileOrder;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+fn names(files: &[PathBuf]) -> Vec<&str> {
+    files.iter().map(|p| p.file_name().unwrap().to_str().unwrap()).collect()
+}
+
+fn create(dir: &Path, entries: &[(&str, usize)]) -> Vec<PathBuf> {
+    entries
+        .iter()
+        .map(|&(name, size)| {
+            let path = dir.join(name);
+            fs::write(&path, vec![b'x'; size]).unwrap();
+            path
+        })
+        .collect()
+}
+
+#[test]
+fn file_order_parses_known_names() {
+    assert_eq!("name".parse(), Ok(FileOrder::Name));
+    assert_eq!("Largest_First".parse(), Ok(FileOrder::LargestFirst));
+    assert_eq!("round-robin".parse(), Ok(FileOrder::RoundRobin));
+    assert_eq!("priority".parse(), Ok(FileOrder::Priority(Vec::new())));
+    assert!("random".parse::<FileOrder>().is_err());
+}
+
+#[test]
+fn largest_first_sorts_by_size_then_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut files = create(
+        dir.path(),
+        &[("dmsql_a.log", 10), ("dmsql_b.log", 300), ("dmsql_c.log", 10)],
+    );
+    FileOrder::LargestFirst.apply(&mut files);
+    assert_eq!(names(&files), ["dmsql_b.log", "dmsql_a.log", "dmsql_c.log"]);
+}
+
+#[test]
+fn round_robin_alternates_sources() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut files = create(
+        dir.path(),
+        &[
+            ("dmsql_DM1_20250916_120000.log", 1),
+            ("dmsql_DM1_20250917_120000.log", 1),
+            ("dmsql_DM1_20250918_120000.log", 1),
+            ("dmsql_DM2_20250917_120000.log", 1),
+            ("dmsql_DM2_20250916_120000.log", 1),
+        ],
+    );
+    FileOrder::RoundRobin.apply(&mut files);
+    assert_eq!(
+        names(&files),
+        [
+            "dmsql_DM1_20250916_120000.log",
+            "dmsql_DM2_20250916_120000.log",
+            "dmsql_DM1_20250917_120000.log",
+            "dmsql_DM2_20250917_120000.log",
+            "dmsql_DM1_20250918_120000.log",
+        ]
+    );
+}
+
+#[test]
+fn priority_puts_matching_files_first() {
+    let mut files: Vec<PathBuf> = [
+        "dmsql_DM1_0916.log",
+        "dmsql_DM2_0916.log",
+        "dmsql_DM3_0916.log",
+        "dmsql_DM2_0915.log",
+    ]
+    .iter()
+    .map(PathBuf::from)
+    .collect();
+    FileOrder::Priority(vec!["DM2".into(), "DM3".into()]).apply(&mut files);
+    assert_eq!(
+        names(&files),
+        [
+            "dmsql_DM2_0915.log",
+            "dmsql_DM2_0916.log",
+            "dmsql_DM3_0916.log",
+            "dmsql_DM1_0916.log",
+        ]
+    );
+}
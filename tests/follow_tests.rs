@@ -0,0 +1,49 @@
+use sqllog_analysis::sqllog::FileFollower;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use tempfile::tempdir;
+
+fn line(second: u32, id: u32) -> String {
+    format!(
+        "2025-09-21 12:00:{second:02}.000 (EP[1] sess:NULL thrd:1 user:usr trxid:{id} stmt:NULL) [SEL]: select {id} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {id}.\n"
+    )
+}
+
+#[test]
+fn follower_emits_records_once_they_are_complete() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("dmsql_live.log");
+    fs::write(&path, line(0, 1)).unwrap();
+
+    // 跳过已有内容
+    let mut follower = FileFollower::new(&path, false).unwrap();
+    let append = |text: &str| {
+        let mut f = OpenOptions::new().append(true).open(&path).unwrap();
+        f.write_all(text.as_bytes()).unwrap();
+    };
+    append(&line(1, 2));
+    // 最后一条记录可能还会追加续行，等下一条记录出现才解析
+    assert!(follower.poll().unwrap().0.is_empty());
+
+    append("  and more\n");
+    let third = line(2, 3);
+    let (head, tail) = third.split_at(30);
+    append(head);
+    // 新记录的首行尚未写完整，前一条记录仍可能有续行
+    assert!(follower.poll().unwrap().0.is_empty());
+
+    append(tail);
+    let (records, errors) = follower.poll().unwrap();
+    assert!(errors.is_empty());
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].trx_id.as_deref(), Some("2"));
+    assert!(records[0].description.contains("and more"));
+    let (records, _) = follower.flush();
+    assert_eq!(records[0].trx_id.as_deref(), Some("3"));
+
+    // 文件被截断后从头读取
+    fs::write(&path, format!("{}{}", line(5, 9), line(6, 10))).unwrap();
+    let (records, _) = follower.poll().unwrap();
+    assert_eq!(records.len(), 1);
+    assert_eq!(records[0].trx_id.as_deref(), Some("9"));
+}
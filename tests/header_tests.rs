@@ -0,0 +1,128 @@
+//! 日志头拆分语料：真实环境中出现过的各类 appname / ip 写法
+
+use sqllog_analysis::sqllog::Sqllog;
+
+const PREFIX: &str = "2025-09-21 12:00:00.000 (EP[0] sess:0x1 thrd:1 user:SYSDBA trxid:1 stmt:0x2";
+
+/// (日志头中 stmt 之后的部分, appname, ip, sql_type, description)
+type Case = (
+    &'static str,
+    Option<&'static str>,
+    Option<&'static str>,
+    Option<&'static str>,
+    &'static str,
+);
+
+const CORPUS: &[Case] = &[
+    // 常规
+    (
+        " appname:disql ip:::ffff:10.0.0.1) [SEL] select 1",
+        Some("disql"),
+        Some("10.0.0.1"),
+        Some("SEL"),
+        "select 1",
+    ),
+    // 含空格与冒号
+    (
+        " appname:MyApp 2.0: batch ip:::ffff:10.0.0.2) [UPD]: update t set a = 1",
+        Some("MyApp 2.0: batch"),
+        Some("10.0.0.2"),
+        Some("UPD"),
+        "update t set a = 1",
+    ),
+    // 含括号与 `) `
+    (
+        " appname:JDBC Thin Client (v2) worker-1 ip:10.0.0.3) [INS] insert into t values (1)",
+        Some("JDBC Thin Client (v2) worker-1"),
+        Some("10.0.0.3"),
+        Some("INS"),
+        "insert into t values (1)",
+    ),
+    // 看似字段的文本
+    (
+        " appname:svc user:foo ip: bar ip:::ffff:10.0.0.4) [DEL] delete from t",
+        Some("svc user:foo ip: bar"),
+        Some("10.0.0.4"),
+        Some("DEL"),
+        "delete from t",
+    ),
+    // Windows 路径
+    (
+        r" appname:C:\Program Files\App\app.exe ip:::ffff:10.0.0.5) select 2",
+        Some(r"C:\Program Files\App\app.exe"),
+        Some("10.0.0.5"),
+        None,
+        "select 2",
+    ),
+    // 双引号包围
+    (
+        r#" appname:"quoted ) app" ip:::ffff:10.0.0.6) [SEL] select 3"#,
+        Some("quoted ) app"),
+        Some("10.0.0.6"),
+        Some("SEL"),
+        "select 3",
+    ),
+    // 无 ip 字段，括号配平
+    (
+        " appname:report (nightly) ) [SEL] select 4",
+        Some("report (nightly) "),
+        None,
+        Some("SEL"),
+        "select 4",
+    ),
+    // 空 appname
+    (
+        " appname: ip:::ffff:10.0.0.7) PARAMS(SEQNO, TYPE, DATA)={(0, INT, 1)}",
+        None,
+        Some("10.0.0.7"),
+        None,
+        "PARAMS(SEQNO, TYPE, DATA)={(0, INT, 1)}",
+    ),
+    // 无 appname
+    (
+        " ip:::ffff:10.0.0.8) [ORA] begin null; end;",
+        None,
+        Some("10.0.0.8"),
+        Some("ORA"),
+        "begin null; end;",
+    ),
+    // 中文 appname
+    (
+        " appname:报表服务: 月结 ip:::ffff:10.0.0.9) select 5",
+        Some("报表服务: 月结"),
+        Some("10.0.0.9"),
+        None,
+        "select 5",
+    ),
+    // description 中出现 ip 字段文本，只在首行查找
+    (
+        " appname:app ip:::ffff:10.0.0.10) select '\n ip:1.2.3.4) '",
+        Some("app"),
+        Some("10.0.0.10"),
+        None,
+        "select '\n ip:1.2.3.4) '",
+    ),
+];
+
+#[test]
+fn header_corpus_splits_fields() {
+    for (tail, appname, ip, sql_type, description) in CORPUS {
+        let line = format!("{PREFIX}{tail}");
+        let log = Sqllog::from_line(&line, 1)
+            .unwrap_or_else(|e| panic!("解析失败 {line}: {e}"))
+            .unwrap();
+        assert_eq!(log.appname.as_deref(), *appname, "{line}");
+        assert_eq!(log.ip.as_deref(), *ip, "{line}");
+        assert_eq!(log.sql_type.as_deref(), *sql_type, "{line}");
+        assert_eq!(log.description, *description, "{line}");
+        assert_eq!(log.user.as_deref(), Some("SYSDBA"));
+    }
+}
+
+#[test]
+fn header_rejects_unterminated_headers() {
+    for tail in [" appname:never closed", " ip:!@#) x", " appname:x)"] {
+        let line = format!("{PREFIX}{tail}");
+        assert!(Sqllog::from_line(&line, 1).is_err(), "{line}");
+    }
+}
@@ -0,0 +1,84 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::analysis::{
+    DiffThresholds, SnapshotAggregator, UserStatsAggregator, compare,
+};
+use sqllog_analysis::history::HistoryStore;
+use sqllog_analysis::sqllog::{ExecTimeMs, Sqllog};
+
+mod common;
+
+fn record(user: &str, sql: &str, execute_time: i64) -> Sqllog {
+    Sqllog {
+        user: Some(user.to_string()),
+        ..common::timed_record(sql, execute_time)
+    }
+}
+
+fn run(label: &str, scale: i64) -> sqllog_analysis::analysis::StatsSnapshot {
+    let mut aggregator = SnapshotAggregator::new();
+    let records: Vec<_> = (1..=20)
+        .map(|i| {
+            record("app", &format!("select * from t where id = {i}"), i * scale)
+        })
+        .chain([record("etl", "delete from log", 100)])
+        .collect();
+    aggregator.observe(&records);
+    aggregator.finish(label)
+}
+
+#[test]
+fn user_percentiles_use_nearest_rank() {
+    let mut aggregator = UserStatsAggregator::new();
+    aggregator.observe(
+        &(1..=100).map(|i| record("u", "select 1", i)).collect::<Vec<_>>(),
+    );
+    aggregator.observe(&[Sqllog::default()]);
+    let users = aggregator.finish();
+    assert_eq!(users.len(), 1);
+    let u = &users["u"];
+    assert_eq!(u.calls, 100);
+    assert_eq!(u.p50, Some(ExecTimeMs::new(50)));
+    assert_eq!(u.p95, Some(ExecTimeMs::new(95)));
+    assert_eq!(u.p99, Some(ExecTimeMs::new(99)));
+}
+
+#[test]
+fn snapshots_round_trip_through_the_store() {
+    let store = HistoryStore::open_in_memory().unwrap();
+    assert!(store.latest().unwrap().is_none());
+
+    let first = run("2025-09-20", 1);
+    let second = run("2025-09-21", 3);
+    let first_id = store.save(&first).unwrap();
+    let second_id = store.save(&second).unwrap();
+    assert!(second_id > first_id);
+
+    assert_eq!(store.load(first_id).unwrap(), first);
+    assert_eq!(store.latest().unwrap(), Some(second));
+
+    let list = store.snapshots().unwrap();
+    assert_eq!(list.len(), 2);
+    assert_eq!(list[0].label, "2025-09-20");
+    assert_eq!((list[0].fingerprints, list[0].users), (2, 2));
+    assert!(store.load(999).is_err());
+}
+
+#[test]
+fn compare_reports_user_p95_changes() {
+    let baseline = run("yesterday", 1);
+    let current = run("today", 3);
+    let report = compare(&baseline, &current, &DiffThresholds::default());
+
+    assert_eq!(report.baseline_label, "yesterday");
+    assert_eq!(report.users.len(), 2);
+    // app 的 p95 从 19ms 变为 57ms，排在未变化的 etl 之前
+    assert_eq!(report.users[0].user, "app");
+    let change = report.users[0].p95_change.unwrap();
+    assert!((change - 2.0).abs() < 1e-9);
+    assert_eq!(report.users[1].p95_change, Some(0.0));
+    assert_eq!(report.statements.changed.len(), 1);
+
+    let text = report.to_string();
+    assert!(text.contains("app p95=19ms->57ms (+200.0%)"), "{text}");
+}
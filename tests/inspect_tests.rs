@@ -0,0 +1,107 @@
+use sqllog_analysis::sqllog::{
+    LineEnding, SourceEncoding, Sqllog, count_records, inspect_file,
+};
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn record(i: usize) -> String {
+    format!(
+        "2025-09-21 12:00:{:02}.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}.",
+        i % 60
+    )
+}
+
+fn write_tmp(bytes: &[u8]) -> NamedTempFile {
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(bytes).unwrap();
+    f
+}
+
+#[test]
+fn whole_file_sample_counts_exactly() {
+    let text: String = (0..5).map(|i| record(i) + "\r\n").collect();
+    let file = write_tmp(text.as_bytes());
+
+    let report = inspect_file(file.path(), 64 * 1024).unwrap();
+    assert!(report.is_exact());
+    assert_eq!(report.encoding, SourceEncoding::Utf8);
+    assert_eq!(report.line_ending, LineEnding::CrLf);
+    assert_eq!(
+        report.first_timestamp.as_deref(),
+        Some("2025-09-21 12:00:00.000")
+    );
+    assert_eq!(report.sample_records, 5);
+    assert_eq!(report.estimated_records, Some(5));
+}
+
+#[test]
+fn partial_sample_estimates_total_records() {
+    let mut text = String::new();
+    for i in 0..2000 {
+        text.push_str(&record(i));
+        text.push('\n');
+        // 多行记录
+        if i % 2 == 0 {
+            text.push_str("  and a = 1\n");
+        }
+    }
+    let file = write_tmp(text.as_bytes());
+
+    let report = inspect_file(file.path(), 8 * 1024).unwrap();
+    assert!(!report.is_exact());
+    assert_eq!(report.sample_bytes, 8 * 1024);
+    assert_eq!(report.line_ending, LineEnding::Lf);
+
+    let mut actual = 0usize;
+    Sqllog::parse_all(file.path(), 0, |c| actual += c.len(), |_| {}).unwrap();
+    let estimate = report.estimated_records.unwrap() as f64;
+    assert!((estimate - actual as f64).abs() / (actual as f64) < 0.05);
+}
+
+#[test]
+fn utf16_sample_scales_to_source_bytes() {
+    let text: String = (0..200).map(|i| record(i) + "\n").collect();
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    let file = write_tmp(&bytes);
+
+    let report = inspect_file(file.path(), 4 * 1024).unwrap();
+    assert_eq!(report.encoding, SourceEncoding::Utf16Le);
+    let estimate = report.estimated_records.unwrap() as f64;
+    assert!((estimate - 200.0).abs() / 200.0 < 0.05, "{estimate}");
+}
+
+#[test]
+fn file_without_records_has_no_estimate() {
+    let file = write_tmp(b"not a log\nstill not a log");
+    let report = inspect_file(file.path(), 1024).unwrap();
+    assert_eq!(report.sample_records, 0);
+    assert_eq!(report.avg_record_bytes, None);
+    assert_eq!(report.first_timestamp, None);
+    assert_eq!(report.line_ending, LineEnding::Lf);
+}
+
+#[test]
+fn count_records_matches_full_parse() {
+    let mut text = String::from("garbage before the first record\n");
+    for i in 0..5000 {
+        // 行首空白与 CRLF 不影响记录边界
+        let indent = if i % 7 == 0 { "  " } else { "" };
+        text.push_str(&format!("{indent}{}\r\n", record(i)));
+        if i % 3 == 0 {
+            text.push_str("  and a = 1\r\n2025-13-01 not a timestamp\r\n");
+        }
+    }
+    let file = write_tmp(text.as_bytes());
+
+    let mut parsed = 0u64;
+    Sqllog::parse_all(file.path(), 0, |c| parsed += c.len() as u64, |_| {})
+        .unwrap();
+    assert_eq!(parsed, 5000);
+    assert_eq!(count_records(file.path()).unwrap(), parsed);
+
+    let mut utf16 = vec![0xFF, 0xFE];
+    utf16.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    let file = write_tmp(&utf16);
+    assert_eq!(count_records(file.path()).unwrap(), 5000);
+}
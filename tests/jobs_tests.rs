@@ -0,0 +1,93 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{DatabaseProvider, DuckDbProvider};
+use sqllog_analysis::jobs::{JobStatus, JobStore, run_jobs};
+use std::fs;
+use std::path::Path;
+use tempfile::tempdir;
+
+const LINE: &str = "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1.";
+
+#[test]
+fn failed_jobs_are_retried_until_the_limit() {
+    let store = JobStore::open_in_memory().unwrap().with_max_retries(1);
+    assert!(store.enqueue(Path::new("a.log")).unwrap());
+    assert!(!store.enqueue(Path::new("a.log")).unwrap());
+
+    let job = store.claim_next().unwrap().unwrap();
+    assert_eq!((job.status, job.attempts), (JobStatus::Running, 1));
+    assert!(store.claim_next().unwrap().is_none());
+    assert_eq!(store.fail(job.id, "boom").unwrap(), JobStatus::Pending);
+
+    let job = store.claim_next().unwrap().unwrap();
+    assert_eq!(job.attempts, 2);
+    assert_eq!(store.fail(job.id, "boom again").unwrap(), JobStatus::Failed);
+    assert!(store.claim_next().unwrap().is_none());
+
+    let attempts = store.attempts(job.id).unwrap();
+    assert_eq!(attempts.len(), 2);
+    assert!(attempts.iter().all(|a| a.status == JobStatus::Failed));
+    assert_eq!(attempts[1].error.as_deref(), Some("boom again"));
+    assert_eq!(store.summary().unwrap().failed, 1);
+
+    assert_eq!(store.retry_failed().unwrap(), 1);
+    let job = store.claim_next().unwrap().unwrap();
+    store.complete(job.id, 42).unwrap();
+    let job = store.job(job.id).unwrap();
+    assert_eq!(job.status, JobStatus::Done);
+    assert_eq!(job.records, Some(42));
+    assert!(job.last_error.is_none() && job.finished_at.is_some());
+}
+
+#[test]
+fn interrupted_jobs_resume_after_reopening_the_store() {
+    let dir = tempdir().unwrap();
+    let state = dir.path().join("jobs.duckdb");
+    {
+        let store = JobStore::open(&state).unwrap();
+        store.enqueue(Path::new("a.log")).unwrap();
+        store.enqueue(Path::new("b.log")).unwrap();
+        let job = store.claim_next().unwrap().unwrap();
+        store.complete(job.id, 1).unwrap();
+        // 第二个作业认领后进程“崩溃”
+        store.claim_next().unwrap().unwrap();
+    }
+
+    let store = JobStore::open(&state).unwrap();
+    assert_eq!(store.recover().unwrap(), 1);
+    let job = store.claim_next().unwrap().unwrap();
+    assert_eq!(job.path, Path::new("b.log"));
+    assert_eq!(job.attempts, 2);
+    let history = store.attempts(job.id).unwrap();
+    assert_eq!(history[0].status, JobStatus::Failed);
+    assert_eq!(history[1].status, JobStatus::Running);
+}
+
+#[test]
+fn run_jobs_imports_pending_files_and_records_failures() {
+    let dir = tempdir().unwrap();
+    let good = dir.path().join("dmsql_a_20250921_120000.log");
+    fs::write(&good, format!("{LINE}\n")).unwrap();
+    let missing = dir.path().join("dmsql_missing_20250921_120000.log");
+
+    let runtime = RuntimeConfig {
+        db_path: dir.path().join("main.duckdb").to_string_lossy().to_string(),
+        use_in_memory: false,
+        ..Default::default()
+    };
+    let store = JobStore::open_in_memory().unwrap().with_max_retries(0);
+    store.enqueue(&good).unwrap();
+    store.enqueue(&missing).unwrap();
+
+    let stats = run_jobs(&store, &runtime).unwrap();
+    assert_eq!(stats.records_inserted, 1);
+    let summary = store.summary().unwrap();
+    assert_eq!((summary.done, summary.failed), (1, 1));
+
+    // 再次运行不会重复导入已完成的文件
+    let stats = run_jobs(&store, &runtime).unwrap();
+    assert_eq!(stats.records_inserted, 0);
+    let provider = DuckDbProvider::new(&runtime).unwrap();
+    assert_eq!(provider.count_records().unwrap(), 1);
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "bin")]
+
 use log::LevelFilter;
 use sqllog_analysis::analysis_log::LogConfig;
 use std::fs::File;
@@ -22,6 +24,9 @@ fn test_logconfig_init_dir_create_fail() {
         level: LevelFilter::Info,
         log_file: Some(parent_file),
         enable_stdout: false,
+        stdout_level: None,
+        otlp_endpoint: None,
+        otlp_service_name: None,
     };
 
     let res = cfg.init();
@@ -0,0 +1,69 @@
+use sqllog_analysis::notify::{
+    NotifyConfig, RunSummary, WebhookFlavor, breaches, idempotency_key, payload,
+};
+
+fn summary() -> RunSummary {
+    RunSummary {
+        files: 2,
+        records: 95,
+        parse_errors: 5,
+        slow_queries: Some(12),
+    }
+}
+
+#[test]
+fn thresholds_decide_whether_to_notify() {
+    let mut config = NotifyConfig::new("https://example.invalid/hook");
+    config.on_complete = false;
+    config.max_error_ratio = Some(0.1);
+    config.max_slow_queries = Some(20);
+    assert!(breaches(&config, &summary()).is_empty());
+    assert!(payload(&config, &summary()).is_none());
+
+    config.max_error_ratio = Some(0.01);
+    config.max_slow_queries = Some(10);
+    let found = breaches(&config, &summary());
+    assert_eq!(found.len(), 2);
+    assert!(found[0].contains("5.00%"));
+
+    let body = payload(&config, &summary()).unwrap();
+    assert_eq!(body["title"], "sqllog 分析告警");
+    assert_eq!(body["summary"]["parse_errors"], 5);
+    assert_eq!(body["breaches"].as_array().unwrap().len(), 2);
+}
+
+#[test]
+fn payload_matches_webhook_flavor() {
+    let mut config = NotifyConfig::new("https://example.invalid/hook");
+    config.flavor = "DingTalk".parse().unwrap();
+    let body = payload(&config, &summary()).unwrap();
+    assert_eq!(body["msgtype"], "markdown");
+    assert!(
+        body["markdown"]["text"]
+            .as_str()
+            .unwrap()
+            .starts_with("### sqllog 分析完成")
+    );
+
+    config.flavor = WebhookFlavor::WeCom;
+    let body = payload(&config, &summary()).unwrap();
+    assert!(
+        body["markdown"]["content"].as_str().unwrap().contains("慢语句 12")
+    );
+
+    config.flavor = WebhookFlavor::Slack;
+    let body = payload(&config, &summary()).unwrap();
+    assert!(body["text"].as_str().unwrap().starts_with("*sqllog 分析完成*"));
+
+    assert!("teams".parse::<WebhookFlavor>().is_err());
+}
+
+#[test]
+fn idempotency_key_is_deterministic_per_batch() {
+    let hash = "ab".repeat(32);
+    let key = idempotency_key(&hash, 3);
+    assert_eq!(key, format!("{}-3", "ab".repeat(16)));
+    assert_eq!(idempotency_key(&hash, 3), key);
+    assert_ne!(idempotency_key(&hash, 4), key);
+    assert_eq!(idempotency_key("short", 0), "short-0");
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    ParseCache, process_files_with_independent_databases,
+};
+use sqllog_analysis::sqllog::{ParseOptions, RecordIdMode};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn write_log(path: &Path, records: usize, bad_line: bool) {
+    let mut content = String::new();
+    for i in 0..records {
+        content.push_str(&format!(
+            "2025-09-21 12:00:0{i}.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}.\n"
+        ));
+    }
+    if bad_line {
+        content.push_str("2025-09-21 12:00:09.000 not a sqllog header\n");
+    }
+    fs::write(path, content).unwrap();
+}
+
+fn config(dir: &Path, db: &str) -> RuntimeConfig {
+    RuntimeConfig {
+        db_path: dir.join(db).to_string_lossy().to_string(),
+        sqllog_cache_dir: Some(dir.join("cache")),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn unchanged_files_are_merged_from_cache() {
+    let dir = tempdir().unwrap();
+    let files: Vec<PathBuf> =
+        ["a.log", "b.log"].iter().map(|n| dir.path().join(n)).collect();
+    write_log(&files[0], 3, false);
+    write_log(&files[1], 2, false);
+
+    let first = process_files_with_independent_databases(
+        &files,
+        &config(dir.path(), "1.duckdb"),
+    )
+    .unwrap();
+    assert_eq!(first.records_inserted, 5);
+    assert_eq!(first.files_from_cache, 0);
+
+    let second = process_files_with_independent_databases(
+        &files,
+        &config(dir.path(), "2.duckdb"),
+    )
+    .unwrap();
+    assert_eq!(second.records_inserted, 5);
+    assert_eq!(second.files_from_cache, 2);
+    assert_eq!(second.files[0].records, 3);
+
+    // 内容变化的文件重新解析
+    write_log(&files[1], 4, false);
+    let third = process_files_with_independent_databases(
+        &files,
+        &config(dir.path(), "3.duckdb"),
+    )
+    .unwrap();
+    assert_eq!(third.records_inserted, 7);
+    assert_eq!(third.files_from_cache, 1);
+}
+
+#[test]
+fn files_with_parse_errors_are_not_cached() {
+    let dir = tempdir().unwrap();
+    let files = vec![dir.path().join("bad.log")];
+    write_log(&files[0], 2, true);
+
+    for db in ["1.duckdb", "2.duckdb"] {
+        let stats = process_files_with_independent_databases(
+            &files,
+            &config(dir.path(), db),
+        )
+        .unwrap();
+        assert_eq!(stats.records_inserted, 2);
+        assert_eq!(stats.parse_errors, 1);
+        assert_eq!(stats.files_from_cache, 0);
+    }
+    let entries = fs::read_dir(dir.path().join("cache")).unwrap().count();
+    assert_eq!(entries, 0);
+}
+
+#[test]
+fn cache_key_depends_on_content_and_options() {
+    let dir = tempdir().unwrap();
+    let path = dir.path().join("a.log");
+    write_log(&path, 1, false);
+    let cache = ParseCache::new(dir.path().join("cache"));
+
+    let options = ParseOptions::default();
+    let key = cache.key(&path, &options).unwrap();
+    // 分块大小不影响解析结果
+    let chunked = ParseOptions { chunk_size: 10, ..Default::default() };
+    assert_eq!(cache.key(&path, &chunked).unwrap(), key);
+
+    let with_ids =
+        ParseOptions { record_id: RecordIdMode::Hash, ..Default::default() };
+    assert_ne!(cache.key(&path, &with_ids).unwrap(), key);
+
+    write_log(&path, 2, false);
+    assert_ne!(cache.key(&path, &options).unwrap(), key);
+    assert!(cache.entry_path(&key).starts_with(cache.dir()));
+}
@@ -0,0 +1,625 @@
+use sqllog_analysis::sqllog::{
+    ErrorBreaker, KeySample, ParseMode, ParseOptions, Prefilter, RecordIdMode,
+    SampleKey, Sqllog, SqllogError, TraceLineMode, TraceLines, extract_plan,
+};
+#[cfg(feature = "database")]
+use sqllog_analysis::{config::RuntimeConfig, sqllog::BlankFields};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+const SAMPLE: &str = "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1.\n";
+
+fn write_tmp(records: usize) -> NamedTempFile {
+    let mut f = NamedTempFile::new().unwrap();
+    for _ in 0..records {
+        f.write_all(SAMPLE.as_bytes()).unwrap();
+    }
+    f
+}
+
+/// 解析文件，返回（记录数, 错误数）
+fn parse(path: &std::path::Path, options: &ParseOptions) -> (usize, usize) {
+    let mut total = 0usize;
+    let mut errors = 0usize;
+    Sqllog::parse_with_options(
+        path,
+        options,
+        |chunk| total += chunk.len(),
+        |errs| errors += errs.len(),
+    )
+    .unwrap();
+    (total, errors)
+}
+
+#[test]
+fn expired_timeout_abandons_file_and_reports_error() {
+    let file = write_tmp(5);
+    let options =
+        ParseOptions { timeout: Some(Duration::ZERO), ..Default::default() };
+
+    let mut total = 0usize;
+    let mut timeouts = Vec::new();
+    Sqllog::parse_with_options(
+        file.path(),
+        &options,
+        |chunk| total += chunk.len(),
+        |errs| {
+            for (line, _, e) in errs {
+                if let SqllogError::Timeout(limit) = e {
+                    timeouts.push((*line, *limit));
+                }
+            }
+        },
+    )
+    .unwrap();
+
+    assert_eq!(total, 0);
+    assert_eq!(timeouts, vec![(1, Duration::ZERO)]);
+}
+
+#[test]
+fn generous_timeout_parses_whole_file() {
+    let file = write_tmp(5);
+    let options = ParseOptions {
+        chunk_size: 2,
+        timeout: Some(Duration::from_secs(60)),
+        ..Default::default()
+    };
+    let (total, errors) = parse(file.path(), &options);
+    assert_eq!((total, errors), (5, 0));
+}
+
+#[cfg(feature = "database")]
+#[test]
+fn runtime_config_builds_parse_options() {
+    let config = RuntimeConfig {
+        sqllog_chunk_size: Some(100),
+        sqllog_file_timeout: Some(Duration::from_secs(30)),
+        ..Default::default()
+    };
+    let options = config.parse_options();
+    assert_eq!(options.chunk_size, 100);
+    assert_eq!(options.timeout, Some(Duration::from_secs(30)));
+
+    let defaults = RuntimeConfig::default().parse_options();
+    assert_eq!(defaults.chunk_size, 0);
+    assert!(defaults.timeout.is_none());
+}
+
+fn record_ids(path: &std::path::Path, mode: RecordIdMode) -> Vec<u64> {
+    let options =
+        ParseOptions { chunk_size: 2, record_id: mode, ..Default::default() };
+    let mut ids = Vec::new();
+    Sqllog::parse_with_options(
+        path,
+        &options,
+        |chunk| ids.extend(chunk.iter().map(|r| r.record_id.unwrap())),
+        |_| {},
+    )
+    .unwrap();
+    ids
+}
+
+#[test]
+fn hash_record_ids_are_stable_and_unique() {
+    // 内容完全相同的记录也应得到不同的 ID（文件内序号参与哈希）
+    let file = write_tmp(5);
+    let first = record_ids(file.path(), RecordIdMode::Hash);
+    let second = record_ids(file.path(), RecordIdMode::Hash);
+    assert_eq!(first.len(), 5);
+    assert_eq!(first, second);
+
+    let mut unique = first.clone();
+    unique.sort_unstable();
+    unique.dedup();
+    assert_eq!(unique.len(), 5);
+}
+
+#[test]
+fn snowflake_record_ids_share_time_prefix() {
+    let file = write_tmp(3);
+    let ids = record_ids(file.path(), RecordIdMode::Snowflake);
+    assert_eq!(ids.len(), 3);
+    assert!(ids.windows(2).all(|w| w[0] + 1 == w[1]));
+    assert!(ids.iter().all(|id| id >> 22 == ids[0] >> 22));
+}
+
+#[test]
+fn snowflake_record_ids_stay_unique_when_time_goes_back() {
+    let mut file = NamedTempFile::new().unwrap();
+    for time in ["12:00:00.000", "12:00:00.001", "12:00:00.000"] {
+        let line = SAMPLE.replacen("12:00:00.000", time, 1);
+        file.write_all(line.as_bytes()).unwrap();
+    }
+    let ids = record_ids(file.path(), RecordIdMode::Snowflake);
+    assert_eq!(ids.len(), 3);
+    assert_eq!(ids[2], ids[0] + 1);
+    assert_ne!(ids[1], ids[2]);
+
+    // 同一毫秒超过 4096 条记录时报错而不是回绕
+    let file = write_tmp(4097);
+    let options = ParseOptions {
+        record_id: RecordIdMode::Snowflake,
+        ..Default::default()
+    };
+    let mut delivered = 0;
+    let err = Sqllog::parse_with_options(
+        file.path(),
+        &options,
+        |chunk| delivered += chunk.len(),
+        |_| {},
+    )
+    .unwrap_err();
+    assert!(matches!(err, SqllogError::RecordId { line: 4097, .. }));
+    assert_eq!(delivered, 0);
+}
+
+#[test]
+fn record_ids_disabled_by_default() {
+    let file = write_tmp(2);
+    let mut ids = Vec::new();
+    Sqllog::parse_all(
+        file.path(),
+        0,
+        |chunk| ids.extend(chunk.iter().map(|r| r.record_id)),
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(ids, vec![None, None]);
+    assert_eq!("snowflake".parse(), Ok(RecordIdMode::Snowflake));
+    assert!("uuid".parse::<RecordIdMode>().is_err());
+}
+
+const PLAN_RECORD: &str = "2025-09-21 12:00:00.000 (EP[0] sess:0x1 thrd:1 user:SYSDBA trxid:1 stmt:0x2) [SEL] select * from t where id > 1
+1   #NSET2: [10, 200, 48]
+2     #PRJT2: [10, 200, 48]; exp_num(2), is_atom(FALSE)
+3       #SLCT2: [10, 200, 48]; T.ID > 1
+4         #CSCN2: [9, 1000, 48]; INDEX33555484(T)
+5     #SORT3: [3, 200, 48]; key_num(1)
+ EXECTIME: 12(ms) ROWCOUNT: 200 EXEC_ID: 7.
+";
+
+#[test]
+fn extract_plan_builds_operator_tree() {
+    let plan = extract_plan(PLAN_RECORD).unwrap();
+    assert_eq!(plan.operator, "NSET2");
+    assert_eq!((plan.cost, plan.rows, plan.width), (10, 200, 48));
+    assert_eq!(plan.children.len(), 2);
+
+    let prjt = &plan.children[0];
+    assert_eq!(prjt.detail, "exp_num(2), is_atom(FALSE)");
+    assert_eq!(prjt.children[0].children[0].operator, "CSCN2");
+    assert_eq!(plan.children[1].operator, "SORT3");
+
+    assert!(extract_plan("select 1 EXECTIME: 1(ms)").is_none());
+}
+
+#[test]
+fn plans_extracted_only_when_enabled() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(PLAN_RECORD.as_bytes()).unwrap();
+
+    let collect = |options: &ParseOptions| {
+        let mut plans = Vec::new();
+        Sqllog::parse_with_options(
+            file.path(),
+            options,
+            |chunk| plans.extend(chunk.iter().map(|r| r.plan.clone())),
+            |_| {},
+        )
+        .unwrap();
+        plans
+    };
+
+    let plans = collect(&ParseOptions::default());
+    assert_eq!(plans.len(), 1);
+    assert!(plans[0].is_none());
+
+    let plans =
+        collect(&ParseOptions { extract_plans: true, ..Default::default() });
+    let plan = plans[0].as_ref().unwrap();
+    assert_eq!(plan.operator, "NSET2");
+    let json = serde_json::to_value(plan).unwrap();
+    assert_eq!(json["children"][1]["operator"], "SORT3");
+}
+
+#[cfg(feature = "database")]
+#[test]
+fn blank_fields_applied_when_streaming() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(
+        "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL appname:  ip:::ffff:10.0.0.1) select 1\n\
+         2025-09-21 12:00:01.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL appname:app ip:::ffff:10.0.0.1) select 2\n"
+            .as_bytes(),
+    )
+    .unwrap();
+
+    let collect = |blank_fields| {
+        let options = RuntimeConfig {
+            sqllog_chunk_size: Some(1),
+            sqllog_blank_fields: blank_fields,
+            ..Default::default()
+        }
+        .parse_options();
+        let mut appnames = Vec::new();
+        Sqllog::parse_with_options(
+            file.path(),
+            &options,
+            |chunk| appnames.extend(chunk.iter().map(|r| r.appname.clone())),
+            |_| {},
+        )
+        .unwrap();
+        appnames
+    };
+
+    let app = Some("app".to_string());
+    assert_eq!(collect(BlankFields::Null), vec![None, app.clone()]);
+    assert_eq!(collect(BlankFields::Empty), vec![Some(String::new()), app]);
+    assert_eq!(collect(BlankFields::Raw)[0].as_deref(), Some(" "));
+}
+
+#[test]
+fn key_sample_keeps_whole_sessions() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..600 {
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.000 (EP[1] sess:0x{:x} thrd:1 user:usr trxid:{i} stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}.",
+            i % 200
+        )
+        .unwrap();
+    }
+
+    let sessions = |sample: KeySample| {
+        let options = ParseOptions {
+            chunk_size: 7,
+            sample: Some(sample),
+            ..Default::default()
+        };
+        let mut counts = std::collections::BTreeMap::new();
+        Sqllog::parse_with_options(
+            file.path(),
+            &options,
+            |chunk| {
+                for r in chunk {
+                    *counts.entry(r.session.clone().unwrap()).or_insert(0) += 1;
+                }
+            },
+            |_| {},
+        )
+        .unwrap();
+        counts
+    };
+
+    let sample = KeySample::new(SampleKey::Session, 0.25).unwrap();
+    let kept = sessions(sample);
+    // 每个会话 3 条记录，被抽中的会话记录完整
+    assert!(kept.values().all(|&n| n == 3));
+    assert!((20..=80).contains(&kept.len()), "{}", kept.len());
+    // 确定性：再次抽样结果一致，换种子则抽中另一批
+    assert_eq!(sessions(sample), kept);
+    assert_ne!(sessions(sample.with_seed(7)), kept);
+    assert_eq!(
+        sessions(KeySample::new(SampleKey::Session, 1.0).unwrap()).len(),
+        200
+    );
+
+    assert!(KeySample::new(SampleKey::User, 0.0).is_err());
+    assert!(KeySample::new(SampleKey::User, 1.5).is_err());
+    assert_eq!("trxid".parse::<SampleKey>(), Ok(SampleKey::Trx));
+}
+
+#[test]
+fn trace_lines_are_skipped_or_collected_by_category() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        "{SAMPLE}\
+         2025-09-21 12:00:00.100 [INFO] database P0000001234 checkpoint begin\n\
+         2025-09-21 12:00:00.200 [TRACE] os_sema2_free\n\
+         {SAMPLE}\
+         2025-09-21 12:00:00.300 ckpt done\n\
+         2025-09-21 12:00:00.400 garbage line\n"
+    )
+    .unwrap();
+
+    let run = |trace_lines: TraceLines| {
+        let options = ParseOptions { trace_lines, ..Default::default() };
+        let mut records = 0usize;
+        let mut errors = Vec::new();
+        Sqllog::parse_with_options(
+            file.path(),
+            &options,
+            |chunk| records += chunk.len(),
+            |errs| errors.extend(errs.iter().map(|(_, _, e)| e.to_string())),
+        )
+        .unwrap();
+        (records, errors)
+    };
+
+    let (records, errors) = run(TraceLines::default());
+    assert_eq!((records, errors.len()), (2, 4));
+
+    let custom =
+        |mode| TraceLines::new(mode).with_rule("ckpt", "ckpt\\b").unwrap();
+    let (records, errors) = run(custom(TraceLineMode::Skip));
+    assert_eq!(records, 2);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].starts_with("日志格式错误"), "{errors:?}");
+
+    let (_, errors) = run(custom(TraceLineMode::Collect));
+    assert_eq!(
+        errors,
+        vec![
+            "非 sqllog 行（server）: 行2".to_string(),
+            "非 sqllog 行（trace）: 行3".to_string(),
+            "非 sqllog 行（ckpt）: 行5".to_string(),
+            "日志格式错误: 行6: 2025-09-21 12:00:00.400 garbage line"
+                .to_string(),
+        ]
+    );
+    assert!(TraceLines::default().with_rule("bad", "(").is_err());
+    assert_eq!("collect".parse::<TraceLineMode>(), Ok(TraceLineMode::Collect));
+}
+
+#[test]
+fn lenient_mode_recovers_incomplete_headers_as_partial() {
+    let mut file = NamedTempFile::new().unwrap();
+    write!(
+        file,
+        "{SAMPLE}\
+         2025-09-21 12:00:01.000 (EP[2] sess:0x1a thrd:7 user:usr stmt:0x2b appname:My App ip:::ffff:10.0.0.1) [UPD]: update t set a = 1\n\
+         2025-09-21 12:00:02.000 (sess:0x1a user:bad-name) select 2 EXECTIME: 5(ms) ROWCOUNT: 1 EXEC_ID: 9.\n\
+         2025-09-21 12:00:03.000 garbage line\n"
+    )
+    .unwrap();
+
+    let run = |mode| {
+        let options = ParseOptions { mode, ..Default::default() };
+        let mut records = Vec::new();
+        let mut errors = 0usize;
+        Sqllog::parse_with_options(
+            file.path(),
+            &options,
+            |chunk| records.extend_from_slice(chunk),
+            |errs| errors += errs.len(),
+        )
+        .unwrap();
+        (records, errors)
+    };
+
+    let (records, errors) = run(ParseMode::Strict);
+    assert_eq!((records.len(), errors), (1, 3));
+    assert!(!records[0].partial);
+
+    let (records, errors) = run(ParseMode::Lenient);
+    assert_eq!((records.len(), errors), (3, 1));
+    assert!(!records[0].partial);
+
+    let missing_trx = &records[1];
+    assert!(missing_trx.partial);
+    assert_eq!(missing_trx.ep, 2);
+    assert_eq!(missing_trx.trx_id, None);
+    assert_eq!(missing_trx.statement.as_deref(), Some("0x2b"));
+    assert_eq!(missing_trx.appname.as_deref(), Some("My App"));
+    assert_eq!(missing_trx.ip.as_deref(), Some("10.0.0.1"));
+    assert_eq!(missing_trx.sql_type.as_deref(), Some("UPD"));
+    assert_eq!(missing_trx.description, "update t set a = 1");
+
+    let sparse = &records[2];
+    assert!(sparse.partial);
+    assert_eq!(sparse.ep, -1);
+    assert_eq!(sparse.session.as_deref(), Some("0x1a"));
+    assert_eq!(sparse.user.as_deref(), Some("bad-name"));
+    assert_eq!(sparse.thread, None);
+    assert_eq!(sparse.execute_id.map(|v| v.get()), Some(9));
+
+    assert_eq!("lenient".parse::<ParseMode>(), Ok(ParseMode::Lenient));
+    assert!("sloppy".parse::<ParseMode>().is_err());
+}
+
+#[test]
+fn line_numbers_and_offsets_are_absolute_across_chunks() {
+    let text = format!(
+        "{SAMPLE}\
+         2025-09-21 12:00:01.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select *\n\
+         from t\n\
+         where a = 1 EXECTIME: 2(ms) ROWCOUNT: 1 EXEC_ID: 2.\n\
+         2025-09-21 12:00:02.000 broken header\n\
+         continued\n\
+         {SAMPLE}"
+    );
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(text.as_bytes()).unwrap();
+
+    let options = ParseOptions { chunk_size: 1, ..Default::default() };
+    let mut records = Vec::new();
+    let mut errors = Vec::new();
+    Sqllog::parse_with_options(
+        file.path(),
+        &options,
+        |chunk| records.extend_from_slice(chunk),
+        |errs| {
+            errors.extend(
+                errs.iter().map(|(line, _, e)| {
+                    (*line, e.to_string(), e.byte_offset())
+                }),
+            );
+        },
+    )
+    .unwrap();
+
+    let offset_of = |needle: &str| text.find(needle).unwrap() as u64;
+    let positions: Vec<(usize, Option<u64>)> =
+        records.iter().map(|r| (r.line, r.byte_offset)).collect();
+    assert_eq!(
+        positions,
+        vec![
+            (1, Some(0)),
+            (2, Some(offset_of("2025-09-21 12:00:01.000"))),
+            (7, Some(text.rfind("2025-09-21 12:00:00.000").unwrap() as u64)),
+        ]
+    );
+
+    assert_eq!(errors.len(), 1);
+    let (line, message, offset) = &errors[0];
+    assert_eq!(*line, 5);
+    assert!(message.starts_with("日志格式错误: 行5:"), "{message}");
+    assert_eq!(*offset, Some(offset_of("2025-09-21 12:00:02.000")));
+}
+
+#[test]
+fn error_breaker_validates_arguments() {
+    assert!(ErrorBreaker::new(0.5, 1).is_ok());
+    assert!(ErrorBreaker::new(1.0, 1).is_err());
+    assert!(ErrorBreaker::new(-0.1, 1).is_err());
+    assert!(ErrorBreaker::new(0.5, 0).is_err());
+}
+
+#[test]
+fn error_breaker_stops_after_window_with_too_many_errors() {
+    let mut file = NamedTempFile::new().unwrap();
+    file.write_all(SAMPLE.as_bytes()).unwrap();
+    for i in 0..1000 {
+        writeln!(file, "2025-09-21 12:00:00.000 not a sqllog header {i}")
+            .unwrap();
+    }
+    let window = 4 * SAMPLE.len() as u64;
+    let options = ParseOptions {
+        chunk_size: 100,
+        error_breaker: Some(ErrorBreaker::new(0.5, window).unwrap()),
+        ..Default::default()
+    };
+
+    let mut records = 0usize;
+    let mut errors = 0usize;
+    let err = Sqllog::parse_with_options(
+        file.path(),
+        &options,
+        |chunk| records += chunk.len(),
+        |errs| errors += errs.len(),
+    )
+    .unwrap_err();
+
+    let SqllogError::ErrorRateExceeded { bytes, max_ratio, .. } = err else {
+        panic!("应为熔断错误: {err}");
+    };
+    assert!(bytes >= window && bytes < 2 * window, "在窗口处停止: {bytes}");
+    assert!((max_ratio - 0.5).abs() < f64::EPSILON);
+    // 已解析的记录与错误在停止前交付，剩余内容不再解析
+    assert_eq!(records, 1);
+    assert!(errors > 0 && errors < 10, "errors = {errors}");
+    assert!(ErrorBreaker::tripped(&anyhow::Error::new(err)));
+}
+
+#[test]
+fn error_breaker_checks_short_files_at_eof() {
+    let mut garbage = NamedTempFile::new().unwrap();
+    garbage.write_all(b"this is not a sqllog file\n").unwrap();
+    let options = ParseOptions {
+        error_breaker: Some(ErrorBreaker::new(0.1, 1 << 20).unwrap()),
+        ..Default::default()
+    };
+    let err =
+        Sqllog::parse_with_options(garbage.path(), &options, |_| {}, |_| {})
+            .unwrap_err();
+    assert!(matches!(err, SqllogError::ErrorRateExceeded { records: 0, .. }));
+
+    // 错误率未超过上限时正常解析完整个文件
+    let good = write_tmp(20);
+    let mut records = 0usize;
+    Sqllog::parse_with_options(
+        good.path(),
+        &options,
+        |chunk| records += chunk.len(),
+        |_| {},
+    )
+    .unwrap();
+    assert_eq!(records, 20);
+}
+
+#[test]
+fn exec_phase_records_are_linked_to_statement_text() {
+    let content = "\
+2025-09-21 12:00:00.000 (EP[1] sess:0x1 thrd:1 user:usr trxid:1 stmt:0xa) [SEL]: select * from t
+2025-09-21 12:00:00.001 (EP[1] sess:0x2 thrd:2 user:usr trxid:2 stmt:0xb) [UPD]: update t set v = 1
+2025-09-21 12:00:00.005 (EP[1] sess:0x1 thrd:1 user:usr trxid:1 stmt:0xa) EXECTIME: 4(ms) ROWCOUNT: 10 EXEC_ID: 7.
+2025-09-21 12:00:00.006 (EP[1] sess:0x3 thrd:3 user:usr trxid:3 stmt:0xc) EXECTIME: 1(ms) ROWCOUNT: 0 EXEC_ID: 8.
+2025-09-21 12:00:00.009 (EP[1] sess:0x2 thrd:2 user:usr trxid:2 stmt:0xb) EXECTIME: 2(ms) ROWCOUNT: 1 EXEC_ID: 9.
+";
+    let mut f = NamedTempFile::new().unwrap();
+    f.write_all(content.as_bytes()).unwrap();
+
+    for chunk_size in [0, 1] {
+        let options = ParseOptions {
+            chunk_size,
+            link_exec_phase: true,
+            ..Default::default()
+        };
+        let mut records = Vec::new();
+        Sqllog::parse_with_options(
+            f.path(),
+            &options,
+            |chunk| records.extend_from_slice(chunk),
+            |errs| assert!(errs.is_empty()),
+        )
+        .unwrap();
+
+        // 找不到 SQL 记录的执行阶段记录原样保留，顺序不变
+        let described: Vec<(&str, Option<i64>, Option<i64>)> = records
+            .iter()
+            .map(|r| {
+                (
+                    r.description.as_str(),
+                    r.execute_time.map(|t| t.get()),
+                    r.execute_id.map(|id| id.get()),
+                )
+            })
+            .collect();
+        assert_eq!(
+            described,
+            [
+                ("select * from t", Some(4), Some(7)),
+                ("update t set v = 1", Some(2), Some(9)),
+                ("EXECTIME: 1(ms) ROWCOUNT: 0 EXEC_ID: 8.", Some(1), Some(8)),
+            ],
+            "chunk_size = {chunk_size}"
+        );
+        assert_eq!(records[0].rowcount.map(|r| r.get()), Some(10));
+    }
+}
+
+#[test]
+fn prefilter_skips_segments_before_parsing() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..30 {
+        let user = if i % 3 == 0 { "alice" } else { "bob" };
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:{user} trxid:1 stmt:NULL) [SEL]: select {i}\nfrom t EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}."
+        )
+        .unwrap();
+    }
+    // 不匹配的格式错误段同样被跳过
+    writeln!(file, "2025-09-21 12:00:01.000 garbage header").unwrap();
+
+    let options = |prefilter| ParseOptions { prefilter, ..Default::default() };
+    assert_eq!(parse(file.path(), &options(None)), (30, 1));
+
+    let alice = Prefilter::new(r"user:alice\b").unwrap();
+    let mut users = Vec::new();
+    Sqllog::parse_with_options(
+        file.path(),
+        &options(Some(alice)),
+        |chunk| users.extend(chunk.iter().map(|r| r.user.clone().unwrap())),
+        |errs| panic!("unexpected errors: {errs:?}"),
+    )
+    .unwrap();
+    assert_eq!(users.len(), 10);
+    assert!(users.iter().all(|u| u == "alice"));
+
+    // 续行中的文本同样参与匹配
+    let literal = Prefilter::any_of(&["select 4\nfrom", "select 5\n"]).unwrap();
+    assert_eq!(parse(file.path(), &options(Some(literal))), (2, 0));
+}
@@ -0,0 +1,572 @@
+#![cfg(all(feature = "concurrent", feature = "database"))]
+
+use anyhow::bail;
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    DatabaseProvider, DiskFullError, DuckDbProvider, ProcessPlugin,
+    RecordPlugin, is_disk_full, plugin_stage,
+    process_file_with_independent_database,
+};
+use sqllog_analysis::pipeline::{Pipeline, PipelineEvent, stages};
+use sqllog_analysis::sqllog::{ExecId, ExecTimeMs, ParseOptions, Sqllog};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tempfile::NamedTempFile;
+
+mod common;
+
+fn record(user: &str, execute_id: i64, description: &str) -> Sqllog {
+    Sqllog {
+        user: Some(user.to_string()),
+        execute_id: Some(ExecId::new(execute_id)),
+        ..common::record(description)
+    }
+}
+
+#[test]
+fn stages_run_in_order_across_batches() {
+    let mut collected = Vec::new();
+    let stats = Pipeline::new()
+        .with_channel_capacity(1)
+        .stage("filter", stages::filter(|r| r.user.as_deref() != Some("SYS")))
+        .stage(
+            "mask",
+            stages::map_records(|r| {
+                r.description = r.description.replace("secret", "***");
+            }),
+        )
+        .stage("dedup", stages::dedup_by(|r| r.execute_id))
+        .stage("collect", |batch| {
+            collected.append(batch);
+            Ok(())
+        })
+        .run(vec![
+            vec![record("A", 1, "secret"), record("SYS", 2, "x")],
+            vec![record("A", 1, "dup"), record("B", 3, "select secret")],
+            vec![record("SYS", 4, "y")],
+        ])
+        .unwrap();
+
+    assert_eq!(stats.records_read, 5);
+    let names: Vec<_> = stats.stages.iter().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, ["filter", "mask", "dedup", "collect"]);
+    assert_eq!(stats.stages[0].records_out, 3);
+    // 最后一批被过滤为空，不再向下游发送
+    assert_eq!(stats.stages[1].batches, 2);
+    assert_eq!(stats.stages[2].records_out, 2);
+
+    let descriptions: Vec<_> =
+        collected.iter().map(|r| r.description.as_str()).collect();
+    assert_eq!(descriptions, ["***", "select ***"]);
+}
+
+#[test]
+fn failing_stage_stops_pipeline_with_context() {
+    let batches: Vec<Vec<Sqllog>> =
+        (0..100).map(|i| vec![record("A", i, "x")]).collect();
+    let mut downstream = 0usize;
+    let err = Pipeline::new()
+        .stage("reject", |batch| {
+            if batch[0].execute_id == Some(ExecId::new(3)) {
+                bail!("bad record");
+            }
+            Ok(())
+        })
+        .stage("count", |batch| {
+            downstream += batch.len();
+            Ok(())
+        })
+        .run(batches)
+        .unwrap_err();
+
+    assert!(format!("{err:#}").contains("管道阶段 reject 失败"));
+    assert!(format!("{err:#}").contains("bad record"));
+    assert_eq!(downstream, 3);
+}
+
+#[test]
+fn run_file_feeds_parsed_chunks_into_database_stage() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..5 {
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: {i}(ms) ROWCOUNT: 1 EXEC_ID: {i}."
+        )
+        .unwrap();
+    }
+
+    let config = RuntimeConfig { use_in_memory: true, ..Default::default() };
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider.initialize().unwrap();
+
+    let stats = Pipeline::new()
+        .stage(
+            "slow",
+            stages::filter(|r| r.execute_time >= Some(ExecTimeMs::new(2))),
+        )
+        .stage("insert", |batch| provider.insert_batch(batch).map(|_| ()))
+        .run_file(file.path(), &ParseOptions::with_chunk_size(2))
+        .unwrap();
+
+    assert_eq!(stats.records_read, 5);
+    assert_eq!(stats.parse_errors, 0);
+    assert_eq!(stats.stages[0].batches, 3);
+    assert_eq!(provider.count_records().unwrap(), 3);
+}
+
+#[test]
+fn batch_observer_sees_each_batch_from_source() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..5 {
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i}"
+        )
+        .unwrap();
+    }
+
+    let mut events: Vec<(usize, usize, Option<PathBuf>)> = Vec::new();
+    let mut max_depth = 0usize;
+    Pipeline::new()
+        .with_channel_capacity(1)
+        .stage("slow", |_| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Ok(())
+        })
+        .on_batch(|event| {
+            events.push((
+                event.index,
+                event.records,
+                event.source.map(PathBuf::from),
+            ));
+            max_depth = max_depth.max(event.queue_depth);
+        })
+        .run_file(file.path(), &ParseOptions::with_chunk_size(2))
+        .unwrap();
+
+    let source = Some(file.path().to_path_buf());
+    assert_eq!(
+        events,
+        vec![(0, 2, source.clone()), (1, 2, source.clone()), (2, 1, source)]
+    );
+    // 阶段处理慢时批次在通道中排队
+    assert!(max_depth >= 2, "{max_depth}");
+
+    let mut depths = Vec::new();
+    Pipeline::new()
+        .stage("pass", |_| Ok(()))
+        .on_batch(|event| {
+            depths.push((event.source.is_some(), event.queue_depth));
+        })
+        .run(vec![vec![record("A", 1, "x")]])
+        .unwrap();
+    assert_eq!(depths.len(), 1);
+    assert!(!depths[0].0);
+    assert!(depths[0].1 >= 1);
+}
+
+#[test]
+fn events_channel_reports_file_progress() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..3 {
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i}"
+        )
+        .unwrap();
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    Pipeline::new()
+        .stage("drop_first", |batch| {
+            batch.retain(|r| !r.description.ends_with("select 0"));
+            Ok(())
+        })
+        .with_events(tx)
+        .run_file(file.path(), &ParseOptions::with_chunk_size(2))
+        .unwrap();
+
+    let events: Vec<PipelineEvent> = rx.try_iter().collect();
+    let path = file.path().to_path_buf();
+    assert_eq!(
+        events.first(),
+        Some(&PipelineEvent::FileStarted { path: path.clone() })
+    );
+    assert_eq!(
+        events.last(),
+        Some(&PipelineEvent::FileFinished {
+            path,
+            records: 3,
+            parse_errors: 0
+        })
+    );
+    let parsed: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            PipelineEvent::BatchParsed { index, records } => {
+                Some((*index, *records))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(parsed, [(0, 2), (1, 1)]);
+    let exported: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            PipelineEvent::BatchExported { index, records } => {
+                Some((*index, *records))
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(exported, [(0, 1), (1, 1)]);
+    // 同一批次先解析后导出
+    let position = |target: &PipelineEvent| {
+        events.iter().position(|e| e == target).unwrap()
+    };
+    assert!(
+        position(&PipelineEvent::BatchParsed { index: 1, records: 1 })
+            < position(&PipelineEvent::BatchExported { index: 1, records: 1 })
+    );
+}
+
+#[test]
+fn events_channel_reports_failing_stage() {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let result = Pipeline::new()
+        .with_events(tx)
+        .stage("broken", |_| bail!("boom"))
+        .run(vec![vec![record("A", 1, "x")]]);
+    assert!(result.is_err());
+
+    let events: Vec<PipelineEvent> = rx.try_iter().collect();
+    let Some(PipelineEvent::Error { stage, message }) = events.last() else {
+        panic!("缺少失败事件: {events:?}");
+    };
+    assert_eq!(stage.as_deref(), Some("broken"));
+    assert!(message.contains("boom"), "{message}");
+    assert!(
+        !events
+            .iter()
+            .any(|e| matches!(e, PipelineEvent::BatchExported { .. }))
+    );
+}
+
+#[test]
+fn slow_stage_shows_up_as_upstream_blocking() {
+    let batches: Vec<Vec<Sqllog>> =
+        (0..4).map(|i| vec![record("A", i, "x")]).collect();
+    let stats = Pipeline::new()
+        .with_channel_capacity(1)
+        .stage("pass", |_| Ok(()))
+        .stage("slow", |_| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            Ok(())
+        })
+        .run(batches)
+        .unwrap();
+
+    let slow = &stats.stages[1];
+    assert!(slow.busy >= std::time::Duration::from_millis(80));
+    // 下游处理慢，上游阶段在发送时被阻塞
+    assert!(stats.stages[0].blocked >= std::time::Duration::from_millis(20));
+    assert!(stats.stages[0].busy < slow.busy);
+}
+
+#[test]
+fn memory_limit_applies_backpressure_and_rejects_oversized_batches() {
+    let batch = || vec![record("A", 1, &"x".repeat(1000))];
+    let batch_bytes: usize = batch().iter().map(Sqllog::estimated_bytes).sum();
+
+    // 上限只容纳两个批次：数据源等待慢阶段释放内存
+    let stats = Pipeline::new()
+        .with_channel_capacity(8)
+        .with_memory_limit(batch_bytes * 2)
+        .stage("slow", |_| {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Ok(())
+        })
+        .run((0..6).map(|_| batch()))
+        .unwrap();
+    assert_eq!(stats.stages[0].records_in, 6);
+    assert!(stats.peak_memory_bytes <= batch_bytes * 2);
+    assert!(stats.peak_memory_bytes >= batch_bytes);
+    assert!(stats.source_blocked >= std::time::Duration::from_millis(20));
+
+    let err = Pipeline::new()
+        .with_memory_limit(batch_bytes / 2)
+        .stage("noop", |_| Ok(()))
+        .run(vec![batch()])
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("max_memory_bytes"), "{err}");
+}
+
+#[test]
+fn memory_limit_enables_chunked_parsing_by_default() {
+    let config = RuntimeConfig {
+        sqllog_chunk_size: None,
+        max_memory_bytes: Some(1 << 20),
+        ..Default::default()
+    };
+    assert_eq!(
+        config.parse_options().chunk_size,
+        sqllog_analysis::config::MEMORY_LIMITED_CHUNK_SIZE
+    );
+    let unlimited = RuntimeConfig { max_memory_bytes: None, ..config };
+    assert_eq!(unlimited.parse_options().chunk_size, 0);
+}
+
+#[test]
+fn disk_full_stage_error_names_the_exporter() {
+    let mut written = 0usize;
+    let err = Pipeline::new()
+        .stage("writer", |batch| {
+            if written > 0 {
+                return Err(std::io::Error::from_raw_os_error(28).into());
+            }
+            written += batch.len();
+            Ok(())
+        })
+        .run(vec![
+            vec![record("A", 1, "a")],
+            vec![record("B", 2, "b")],
+            vec![record("C", 3, "c")],
+        ])
+        .unwrap_err();
+
+    assert!(is_disk_full(&err));
+    let disk_full = err.downcast_ref::<DiskFullError>().unwrap();
+    assert_eq!(disk_full.exporter, "writer");
+    assert!(disk_full.path.is_none());
+    assert_eq!(written, 1);
+
+    // 其他错误不受影响
+    let other = anyhow::anyhow!("permission denied");
+    assert!(!is_disk_full(&other));
+    let duckdb = anyhow::anyhow!("IO Error: No space left on device")
+        .context("导出失败");
+    assert!(is_disk_full(&duckdb));
+}
+
+#[test]
+fn per_stage_capacity_limits_in_flight_batches_and_reports_backpressure() {
+    let batches: Vec<Vec<Sqllog>> =
+        (0..6).map(|i| vec![record("A", i, "x")]).collect();
+    let stats = Pipeline::new()
+        .with_channel_capacity(8)
+        .stage("pass", |_| Ok(()))
+        .stage_with_capacity("slow_exporter", 1, |_| {
+            std::thread::sleep(std::time::Duration::from_millis(20));
+            Ok(())
+        })
+        .run(batches)
+        .unwrap();
+
+    let pass = &stats.stages[0];
+    assert_eq!(pass.capacity, 8);
+    assert!(!pass.backpressured());
+
+    let slow = &stats.stages[1];
+    assert_eq!(slow.capacity, 1);
+    assert_eq!(slow.max_queue_depth, 1);
+    assert!(slow.backpressured());
+    assert!(
+        slow.last_batch_at.unwrap() >= std::time::Duration::from_millis(100)
+    );
+    let names: Vec<&str> =
+        stats.backpressured_stages().map(|s| s.name.as_str()).collect();
+    assert_eq!(names, vec!["slow_exporter"]);
+}
+
+#[test]
+fn single_threaded_mode_runs_stages_inline_with_same_output() {
+    let run = |single: bool| {
+        let caller = std::thread::current().id();
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut order = Vec::new();
+        let mut pipeline = Pipeline::new().with_events(tx);
+        if single {
+            pipeline = pipeline.single_threaded();
+        }
+        let stats = pipeline
+            .stage(
+                "filter",
+                stages::filter(|r| r.user.as_deref() != Some("SYS")),
+            )
+            .stage("collect", |batch| {
+                if single {
+                    assert_eq!(std::thread::current().id(), caller);
+                }
+                order.extend(batch.iter().map(|r| r.execute_id));
+                Ok(())
+            })
+            .run((0..6).map(|i| {
+                let user = if i % 2 == 0 { "SYS" } else { "APP" };
+                vec![record(user, i, "x"), record("APP", i + 100, "y")]
+            }))
+            .unwrap();
+        let events: Vec<PipelineEvent> = rx.try_iter().collect();
+        (order, stats.records_read, stats.stages[1].records_in, events.len())
+    };
+    assert_eq!(run(true), run(false));
+
+    let err = Pipeline::new()
+        .single_threaded()
+        .stage("broken", |_| bail!("boom"))
+        .run(vec![vec![record("A", 1, "x")]])
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("管道阶段 broken 失败"));
+}
+
+/// 丢弃 SYS 的记录、把 description 改为大写的 JSON 插件
+struct UpperPlugin {
+    delay: Duration,
+}
+
+impl RecordPlugin for UpperPlugin {
+    fn name(&self) -> &str {
+        "upper"
+    }
+
+    fn call(
+        &mut self,
+        record: &str,
+        _deadline: Option<Instant>,
+    ) -> anyhow::Result<Option<String>> {
+        std::thread::sleep(self.delay);
+        let mut value: serde_json::Value = serde_json::from_str(record)?;
+        if value["username"] == "SYS" {
+            return Ok(None);
+        }
+        let upper = value["description"].as_str().unwrap().to_uppercase();
+        value["description"] = upper.into();
+        Ok(Some(value.to_string()))
+    }
+}
+
+#[test]
+fn plugin_stage_rewrites_and_drops_records() {
+    let mut collected = Vec::new();
+    let mut first = record("A", 1, "select 1");
+    first.line = 42;
+    Pipeline::new()
+        .stage(
+            "plugin",
+            plugin_stage(UpperPlugin { delay: Duration::ZERO }, None),
+        )
+        .stage("collect", |batch| {
+            collected.append(batch);
+            Ok(())
+        })
+        .run(vec![vec![first, record("SYS", 2, "x"), record("B", 3, "y")]])
+        .unwrap();
+
+    let rows: Vec<_> = collected
+        .iter()
+        .map(|r| (r.description.as_str(), r.execute_id, r.line))
+        .collect();
+    assert_eq!(
+        rows,
+        [
+            ("SELECT 1", Some(ExecId::new(1)), 42),
+            ("Y", Some(ExecId::new(3)), 0)
+        ]
+    );
+
+    let err = Pipeline::new()
+        .stage(
+            "plugin",
+            plugin_stage(
+                UpperPlugin { delay: Duration::from_millis(20) },
+                Some(Duration::from_millis(10)),
+            ),
+        )
+        .run(vec![vec![record("A", 1, "a"), record("B", 2, "b")]])
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("超时"), "{err:#}");
+}
+
+#[cfg(unix)]
+fn sh_plugin(script: &str) -> ProcessPlugin {
+    ProcessPlugin::spawn(&["sh".to_string(), "-c".to_string(), script.into()])
+        .unwrap()
+}
+
+#[cfg(unix)]
+#[test]
+fn process_plugin_exchanges_json_lines() {
+    let plugin = sh_plugin(
+        r#"while IFS= read -r line; do
+             case "$line" in
+               *'"username":"SYS"'*) echo null ;;
+               *) printf '%s\n' "$line" | sed 's/"select /"SELECT /' ;;
+             esac
+           done"#,
+    );
+    let mut collected = Vec::new();
+    Pipeline::new()
+        .stage("plugin", plugin_stage(plugin, Some(Duration::from_secs(10))))
+        .stage("collect", |batch| {
+            collected.append(batch);
+            Ok(())
+        })
+        .run(vec![
+            vec![record("A", 1, "select 1"), record("SYS", 2, "select 2")],
+            vec![record("B", 3, "select 3")],
+        ])
+        .unwrap();
+    let descriptions: Vec<_> =
+        collected.iter().map(|r| r.description.as_str()).collect();
+    assert_eq!(descriptions, ["SELECT 1", "SELECT 3"]);
+
+    // 不返回的插件到期即被结束，阶段不会一直阻塞
+    let started = Instant::now();
+    let err = Pipeline::new()
+        .stage(
+            "plugin",
+            plugin_stage(
+                sh_plugin("read -r line; sleep 30"),
+                Some(Duration::from_millis(200)),
+            ),
+        )
+        .run(vec![vec![record("A", 1, "a")]])
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("时限"), "{err:#}");
+    assert!(started.elapsed() < Duration::from_secs(10));
+}
+
+#[cfg(unix)]
+#[test]
+fn configured_plugin_filters_records_before_insert() {
+    let mut file = NamedTempFile::new().unwrap();
+    for user in ["usr", "SYS", "usr"] {
+        writeln!(
+            file,
+            "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:{user} trxid:1 stmt:NULL) [SEL]: select 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1."
+        )
+        .unwrap();
+    }
+    file.flush().unwrap();
+
+    let plugin = |script: &str| RuntimeConfig {
+        use_in_memory: true,
+        plugin_command: Some(vec!["sh".into(), "-c".into(), script.into()]),
+        plugin_timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    };
+    let config = plugin(
+        r#"while IFS= read -r line; do
+             case "$line" in *'"SYS"'*) echo null ;; *) echo "$line" ;; esac
+           done"#,
+    );
+    let stats =
+        process_file_with_independent_database(file.path(), &config).unwrap();
+    assert_eq!(stats.records_inserted, 2);
+
+    let config = plugin("exit 3");
+    assert!(
+        process_file_with_independent_database(file.path(), &config).is_err()
+    );
+}
@@ -0,0 +1,55 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{DatabaseProvider, DuckDbProvider, preflight};
+use std::fs;
+use tempfile::tempdir;
+
+#[test]
+fn preflight_accepts_fresh_and_initialized_databases() {
+    let dir = tempdir().unwrap();
+    let config = RuntimeConfig {
+        use_in_memory: false,
+        db_path: dir.path().join("db/out.duckdb").to_string_lossy().into(),
+        sqllog_write_errors: true,
+        sqllog_errors_out_path: Some(dir.path().join("errors/e.jsonl")),
+        export_enabled: true,
+        export_out_path: Some(dir.path().join("export/out.csv")),
+        ..Default::default()
+    };
+    preflight(&config).unwrap();
+    assert!(dir.path().join("export").is_dir());
+    assert_eq!(fs::read_dir(dir.path().join("export")).unwrap().count(), 0);
+
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider.initialize().unwrap();
+    provider.preflight().unwrap();
+    // 预检不留下探测表
+    provider
+        .execute_sql("CREATE TABLE sqllog_preflight_probe (x INT)")
+        .unwrap();
+}
+
+#[test]
+fn preflight_rejects_incompatible_table_and_bad_export_dir() {
+    let config = RuntimeConfig { use_in_memory: true, ..Default::default() };
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider
+        .execute_sql("CREATE TABLE sqllogs (occurrence_time TEXT, ep TEXT)")
+        .unwrap();
+    let err = provider.preflight().unwrap_err().to_string();
+    assert!(err.contains("缺少列"), "{err}");
+    assert!(err.contains("username"), "{err}");
+
+    let dir = tempdir().unwrap();
+    let blocker = dir.path().join("not_a_dir");
+    fs::write(&blocker, b"x").unwrap();
+    let config = RuntimeConfig {
+        use_in_memory: true,
+        export_enabled: true,
+        export_out_path: Some(blocker.join("out.csv")),
+        ..Default::default()
+    };
+    let err = format!("{:#}", preflight(&config).unwrap_err());
+    assert!(err.contains("导出目录"), "{err}");
+}
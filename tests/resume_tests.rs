@@ -0,0 +1,102 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    process_files_with_independent_databases, run_key,
+};
+use sqllog_analysis::sqllog::{ParseOptions, RecordIdMode};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tempfile::tempdir;
+
+fn write_log(path: &Path, records: usize, user: &str) {
+    let mut content = String::new();
+    for i in 0..records {
+        content.push_str(&format!(
+            "2025-09-21 12:00:0{i}.000 (EP[1] sess:NULL thrd:1 user:{user} trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}.\n"
+        ));
+    }
+    fs::write(path, content).unwrap();
+}
+
+fn config(dir: &Path) -> RuntimeConfig {
+    RuntimeConfig {
+        db_path: dir.join("main.duckdb").to_string_lossy().to_string(),
+        db_resume: true,
+        ..Default::default()
+    }
+}
+
+fn count(db: &Path) -> i64 {
+    let conn = duckdb::Connection::open(db).unwrap();
+    conn.query_row("SELECT count(*) FROM sqllogs", [], |row| row.get(0))
+        .unwrap()
+}
+
+#[test]
+fn rerun_after_interruption_skips_committed_files() {
+    let dir = tempdir().unwrap();
+    let files: Vec<PathBuf> = ["a.log", "b.log", "c.log"]
+        .iter()
+        .map(|n| dir.path().join(n))
+        .collect();
+    write_log(&files[0], 3, "a");
+    write_log(&files[1], 2, "b");
+    write_log(&files[2], 4, "c");
+    let db = dir.path().join("main.duckdb");
+
+    let first =
+        process_files_with_independent_databases(&files, &config(dir.path()))
+            .unwrap();
+    assert_eq!(first.records_inserted, 9);
+    assert_eq!(first.files_resumed, 0);
+
+    // 模拟在第三个文件提交前中断：撤掉它的记录与批次登记
+    {
+        let conn = duckdb::Connection::open(&db).unwrap();
+        conn.execute_batch(
+            "DELETE FROM sqllogs WHERE username = 'c';
+             DELETE FROM sqllog_resume WHERE batch_id = 2;",
+        )
+        .unwrap();
+    }
+
+    let second =
+        process_files_with_independent_databases(&files, &config(dir.path()))
+            .unwrap();
+    assert_eq!(second.files_resumed, 2);
+    assert_eq!(second.records_inserted, 4);
+    assert_eq!(count(&db), 9);
+
+    // 相同输入与配置再次重跑：全部批次已提交，不重复写入
+    let third =
+        process_files_with_independent_databases(&files, &config(dir.path()))
+            .unwrap();
+    assert_eq!(third.files_resumed, 3);
+    assert_eq!(count(&db), 9);
+
+    // 输入文件变化时视为新的运行
+    write_log(&files[2], 5, "c");
+    let changed =
+        process_files_with_independent_databases(&files, &config(dir.path()))
+            .unwrap();
+    assert_eq!(changed.files_resumed, 0);
+    assert_eq!(count(&db), 19);
+}
+
+#[test]
+fn run_key_depends_on_inputs_and_options() {
+    let dir = tempdir().unwrap();
+    let files = vec![dir.path().join("a.log"), dir.path().join("b.log")];
+    write_log(&files[0], 1, "a");
+    write_log(&files[1], 1, "b");
+
+    let options = ParseOptions::default();
+    let key = run_key(&files, &options).unwrap();
+    assert_eq!(run_key(&files, &options).unwrap(), key);
+    assert_ne!(run_key(&files[..1], &options).unwrap(), key);
+
+    let with_ids =
+        ParseOptions { record_id: RecordIdMode::Hash, ..Default::default() };
+    assert_ne!(run_key(&files, &with_ids).unwrap(), key);
+}
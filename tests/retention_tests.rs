@@ -0,0 +1,92 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::retention::{RetentionPolicy, cleanup};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime};
+use tempfile::tempdir;
+
+const DAY: Duration = Duration::from_secs(24 * 3600);
+
+/// 写出一次运行：导出文件与列出它的清单
+fn write_run(dir: &Path, name: &str) -> (PathBuf, PathBuf) {
+    let artifact = dir.join(format!("{name}.csv"));
+    fs::write(&artifact, "a,b\n1,2\n").unwrap();
+    let manifest = dir.join(format!("{name}.manifest.json"));
+    let body = serde_json::json!({
+        "generated_at": "2025-09-21 12:00:00.000",
+        "format": "csv",
+        "records_exported": 1,
+        "parse_stats": null,
+        "artifacts": [{
+            "path": artifact.to_string_lossy(),
+            "bytes": 8,
+            "sha256": "",
+            "records": 1,
+        }],
+    });
+    fs::write(&manifest, serde_json::to_string_pretty(&body).unwrap()).unwrap();
+    // 保证各次运行的修改时间不同
+    sleep(Duration::from_millis(20));
+    (manifest, artifact)
+}
+
+#[test]
+fn keep_last_runs_per_instance() {
+    let dir = tempdir().unwrap();
+    let instance = dir.path().join("db1");
+    fs::create_dir(&instance).unwrap();
+    let runs: Vec<_> =
+        ["r1", "r2", "r3"].iter().map(|n| write_run(&instance, n)).collect();
+    let other = dir.path().join("db2");
+    fs::create_dir(&other).unwrap();
+    let other_run = write_run(&other, "r1");
+
+    let policy = RetentionPolicy {
+        keep_last: Some(2),
+        dry_run: true,
+        ..Default::default()
+    };
+    let report = cleanup(dir.path(), &policy, SystemTime::now()).unwrap();
+    assert_eq!((report.runs_kept, report.runs_removed), (3, 1));
+    assert_eq!(report.removed.len(), 2);
+    assert!(runs[0].0.exists() && runs[0].1.exists());
+
+    let policy = RetentionPolicy { dry_run: false, ..policy };
+    cleanup(dir.path(), &policy, SystemTime::now()).unwrap();
+    assert!(!runs[0].0.exists() && !runs[0].1.exists());
+    assert!(runs[1].1.exists() && runs[2].1.exists());
+    assert!(other_run.1.exists());
+}
+
+#[test]
+fn max_age_removes_old_runs_and_loose_exports() {
+    let dir = tempdir().unwrap();
+    let old = write_run(dir.path(), "old");
+    let new = write_run(dir.path(), "new");
+    let loose = dir.path().join("adhoc.csv");
+    fs::write(&loose, "x\n").unwrap();
+    let notes = dir.path().join("notes.md");
+    fs::write(&notes, "keep me").unwrap();
+
+    // 40 天后：全部过期，但 keep_last 保留最近一次运行
+    let later = SystemTime::now() + 40 * DAY;
+    let policy = RetentionPolicy {
+        max_age: Some(30 * DAY),
+        keep_last: Some(1),
+        dry_run: false,
+    };
+    let report = cleanup(dir.path(), &policy, later).unwrap();
+    assert_eq!((report.runs_kept, report.runs_removed), (1, 1));
+    assert!(!old.0.exists() && !old.1.exists());
+    assert!(new.0.exists() && new.1.exists());
+    assert!(!loose.exists());
+    assert!(notes.exists());
+
+    // 未过期时不删除任何文件
+    let policy = RetentionPolicy { keep_last: None, ..policy };
+    let report = cleanup(dir.path(), &policy, SystemTime::now()).unwrap();
+    assert!(report.removed.is_empty());
+    assert!(cleanup(dir.path(), &RetentionPolicy::default(), later).is_err());
+}
@@ -0,0 +1,57 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    DuckDbProvider, RUNS_TABLE, RunRecord, config_hash, file_sha256,
+    process_files_with_independent_databases,
+};
+use std::fs;
+use tempfile::tempdir;
+
+const LOG: &str = "\
+2025-09-21 12:00:01.000 (EP[1] sess:NULL thrd:1 user:alice trxid:1 stmt:NULL) [SEL]: select 1 EXECTIME: 5(ms) ROWCOUNT: 1 EXEC_ID: 1.
+2025-09-21 12:00:02.000 (EP[1] sess:NULL thrd:1 user:bob trxid:1 stmt:NULL) [SEL]: select 2 EXECTIME: 50(ms) ROWCOUNT: 1 EXEC_ID: 2.
+";
+
+#[test]
+fn runs_table_describes_each_run() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("dmsql_a.log");
+    fs::write(&log, LOG).unwrap();
+    let db = dir.path().join("out.duckdb");
+    let runtime = RuntimeConfig {
+        db_path: db.to_string_lossy().to_string(),
+        db_record_runs: true,
+        ..Default::default()
+    };
+    let files = vec![log.clone()];
+    let started = chrono::Local::now();
+    let stats =
+        process_files_with_independent_databases(&files, &runtime).unwrap();
+    let run = RunRecord::build(&runtime, &files, &stats, started).unwrap();
+    assert_eq!(run.records_inserted, 2);
+    assert_eq!(run.inputs[0].bytes, LOG.len() as u64);
+    assert_eq!(run.inputs[0].sha256, file_sha256(&log).unwrap());
+    assert_eq!(run.config_hash, config_hash(&runtime));
+    assert_ne!(run.config_hash, config_hash(&RuntimeConfig::default()));
+
+    let mut provider = DuckDbProvider::new(&runtime).unwrap();
+    provider.record_run(&run).unwrap();
+    drop(provider);
+
+    let conn = duckdb::Connection::open(&db).unwrap();
+    let (run_id, version, records, inputs): (String, String, i64, String) =
+        conn.query_row(
+            &format!(
+                "SELECT run_id, crate_version, records_inserted, inputs FROM {RUNS_TABLE} WHERE finished_at >= started_at"
+            ),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+        )
+        .unwrap();
+    assert_eq!(run_id, run.run_id);
+    assert_eq!(version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(records, 2);
+    let inputs: serde_json::Value = serde_json::from_str(&inputs).unwrap();
+    assert_eq!(inputs[0]["sha256"], run.inputs[0].sha256.as_str());
+}
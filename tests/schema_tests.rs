@@ -0,0 +1,108 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::analysis::{AggregateQuery, Value};
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    ColumnType, DatabaseProvider, DatabaseType, DuckDbProvider, SQLLOG_TABLE,
+};
+use sqllog_analysis::sqllog::Sqllog;
+
+#[test]
+fn schema_sql_lists_table_and_indexes() {
+    let sql = DuckDbProvider::schema_sql();
+
+    assert!(sql.starts_with("CREATE TABLE IF NOT EXISTS sqllogs"));
+    for column in
+        ["occurrence_time", "record_id", "plan", "execute_time_us", "partial"]
+    {
+        assert!(sql.contains(column), "缺少列 {column}");
+    }
+    for index in
+        ["idx_sqllogs_dmlg01", "idx_sqllogs_dmlg03", "idx_sqllogs_record_id"]
+    {
+        assert!(sql.contains(index), "缺少索引 {index}");
+    }
+}
+
+#[test]
+fn schema_sql_is_executable_and_matches_initialized_schema() {
+    let config = RuntimeConfig { use_in_memory: true, ..Default::default() };
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider.execute_sql(&DuckDbProvider::schema_sql()).unwrap();
+
+    // 与正常初始化流程兼容
+    provider.initialize().unwrap();
+    provider.finalize_schema().unwrap();
+    assert_eq!(provider.count_records().unwrap(), 0);
+}
+
+#[test]
+fn table_schema_drives_create_table_sql() {
+    let create = SQLLOG_TABLE.create_table_sql(&DatabaseType::DuckDb);
+    assert!(DuckDbProvider::schema_sql().starts_with(create.trim_end()));
+
+    let names: Vec<&str> = SQLLOG_TABLE.column_names().collect();
+    assert_eq!(names.len(), 18);
+    assert_eq!(names[0], "occurrence_time");
+    assert_eq!(names[17], "partial");
+
+    // 新增列既在建表语句中，也有对应的迁移语句
+    let migrations = SQLLOG_TABLE.migration_sql(&DatabaseType::DuckDb);
+    for column in ["record_id", "plan", "execute_time_us", "partial"] {
+        assert!(SQLLOG_TABLE.column(column).unwrap().migrated);
+        assert!(
+            migrations.contains(&format!("ADD COLUMN IF NOT EXISTS {column} ")),
+            "缺少迁移 {column}"
+        );
+    }
+    assert_eq!(
+        SQLLOG_TABLE.column("ep").unwrap().ty.sql_type(&DatabaseType::DuckDb),
+        "INTEGER"
+    );
+    assert!(SQLLOG_TABLE.column("description").unwrap().ty.is_text());
+    assert_eq!(SQLLOG_TABLE.column("rowcount").unwrap().ty, ColumnType::BigInt);
+}
+
+#[test]
+fn initialize_migrates_legacy_char_ep_column() {
+    let dir = tempfile::tempdir().unwrap();
+    let config = RuntimeConfig {
+        use_in_memory: false,
+        db_path: dir.path().join("legacy.duckdb").to_string_lossy().into(),
+        ..Default::default()
+    };
+
+    {
+        // 旧版本的表结构：ep CHAR(1)，且已创建索引
+        let mut legacy = DuckDbProvider::new(&config).unwrap();
+        legacy
+            .execute_sql(
+                "CREATE TABLE sqllogs (occurrence_time CHAR(32) NOT NULL, ep CHAR(1), session VARCHAR(64), thread VARCHAR(64), username VARCHAR(128), trx_id VARCHAR(64), statement VARCHAR(64), appname VARCHAR(256), ip VARCHAR(45), sql_type VARCHAR(32), description TEXT, execute_time BIGINT, rowcount BIGINT, execute_id BIGINT);
+                 CREATE INDEX idx_sqllogs_dmlg01 ON sqllogs(session);
+                 INSERT INTO sqllogs (occurrence_time, ep, session, description) VALUES ('2025-09-21 12:00:00.000', '3', 's1', 'old');",
+            )
+            .unwrap();
+    }
+
+    let mut provider = DuckDbProvider::new(&config).unwrap();
+    provider.initialize().unwrap();
+    provider
+        .insert_batch(&[Sqllog {
+            occurrence_time: "2025-09-21 12:00:01.000".to_string(),
+            ep: 12,
+            description: "new".to_string(),
+            ..Default::default()
+        }])
+        .unwrap();
+    provider.finalize_schema().unwrap();
+
+    let query = AggregateQuery::parse("ep, count(*)", "ep", "", "").unwrap();
+    let result = provider.aggregate(&query).unwrap();
+    assert_eq!(
+        result.rows,
+        vec![
+            vec![Value::Int(3), Value::Int(1)],
+            vec![Value::Int(12), Value::Int(1)]
+        ]
+    );
+}
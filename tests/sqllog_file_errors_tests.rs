@@ -71,3 +71,42 @@ fn test_from_file_with_errors_invalid_utf8() {
         errors.iter().any(|(_, _, err)| err.to_lowercase().contains("utf"));
     assert!(found_utf8, "应为 Utf8 错误");
 }
+
+#[test]
+fn test_invalid_utf8_error_carries_raw_bytes_and_offset() {
+    use sqllog_analysis::error_writer::ParseErrorRecord;
+    use sqllog_analysis::sqllog::SqllogError;
+
+    let dir = tempdir().unwrap();
+    let file_path = dir.path().join("raw.log");
+    let first = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2) [SEL]: SELECT 1\n";
+    let mut file = File::create(&file_path).unwrap();
+    file.write_all(first.as_bytes()).unwrap();
+    file.write_all(b"SELECT \xff\xfe\n").unwrap();
+    drop(file);
+
+    let mut records = Vec::new();
+    Sqllog::parse_all(
+        &file_path,
+        0,
+        |_| {},
+        |errs: &[(usize, String, SqllogError)]| {
+            records.extend(ParseErrorRecord::from_errors(&file_path, errs));
+        },
+    )
+    .unwrap();
+
+    let raw = records
+        .iter()
+        .find_map(|r| r.raw_bytes.clone())
+        .expect("应携带原始字节");
+    let start = first.len() as u64;
+    assert_eq!(raw.bytes, b"SELECT \xff\xfe\n");
+    assert_eq!(raw.offset, start..start + 10);
+
+    // 序列化时以 base64 输出原始字节
+    let json = serde_json::to_value(&raw).unwrap();
+    assert_eq!(json["bytes"], "U0VMRUNUIP/+Cg==");
+    assert_eq!(json["start"], start);
+    assert_eq!(json["end"], start + 10);
+}
@@ -65,9 +65,9 @@ fn test_from_line_null_fields() {
     assert_eq!(log.trx_id, None);
     assert_eq!(log.statement, None);
     // 严格验证模式下，包含完整 EXECTIME 参数的记录应该正常解析
-    assert_eq!(log.execute_time, Some(100));
-    assert_eq!(log.rowcount, Some(1));
-    assert_eq!(log.execute_id, Some(123));
+    assert_eq!(log.execute_time, Some(ExecTimeMs::new(100)));
+    assert_eq!(log.rowcount, Some(RowCount::new(1)));
+    assert_eq!(log.execute_id, Some(ExecId::new(123)));
 }
 
 #[test]
@@ -283,6 +283,88 @@ fn test_is_first_row_extreme_cases() {
     assert!(!is_first_row("20251010 101010.100"));
 }
 
+#[test]
+fn test_is_first_row_single_byte_mutations() {
+    use chrono::{NaiveDate, NaiveTime};
+
+    // 逐字节替换为任意 ASCII 字符，与按字段校验的参考实现比较
+    let reference = |b: &[u8]| {
+        let num = |r: std::ops::Range<usize>| {
+            b[r].iter().try_fold(0u32, |acc, &c| {
+                c.is_ascii_digit().then(|| acc * 10 + u32::from(c - b'0'))
+            })
+        };
+        let seps = [(4, b'-'), (7, b'-'), (10, b' '), (13, b':'), (16, b':')];
+        if seps.iter().any(|&(i, c)| b[i] != c) || b[19] != b'.' {
+            return false;
+        }
+        let (Some(y), Some(mo), Some(d), Some(h), Some(mi), Some(sec), Some(_)) = (
+            num(0..4),
+            num(5..7),
+            num(8..10),
+            num(11..13),
+            num(14..16),
+            num(17..19),
+            num(20..23),
+        ) else {
+            return false;
+        };
+        y > 0
+            && NaiveDate::from_ymd_opt(i32::try_from(y).unwrap(), mo, d)
+                .is_some()
+            && NaiveTime::from_hms_opt(h, mi, sec).is_some()
+    };
+
+    for base in ["2024-02-29 23:59:59.999", "2025-10-10 10:10:10.100"] {
+        for pos in 0..base.len() {
+            for c in 0u8..=127 {
+                let mut bytes = base.as_bytes().to_vec();
+                bytes[pos] = c;
+                let line = String::from_utf8(bytes).unwrap();
+                assert_eq!(
+                    is_first_row(&line),
+                    reference(line.as_bytes()),
+                    "{line:?}"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_find_first_row_pos() {
+    assert_eq!(find_first_row_pos("2025-10-10 10:10:10.100 x"), Some(0));
+    // 多字节字符与不合法的时间戳之后
+    let text = "中文-数据 2025-13-10 10:10:10.100 - 2025-10-10 10:10:10.100";
+    assert_eq!(find_first_row_pos(text), Some(text.rfind("2025").unwrap()));
+    assert_eq!(find_first_row_pos("2025-10-10 10:10:10.10"), None);
+    assert_eq!(find_first_row_pos("-"), None);
+    assert_eq!(find_first_row_pos(""), None);
+}
+
+#[test]
+fn test_from_file_lines_longer_than_read_buffer() {
+    // 超过读取缓冲区的长行，且最后一行没有换行符
+    let long_sql = format!("select '{}' from t", "x".repeat(64 * 1024));
+    let content = format!(
+        "2025-09-21 12:00:00.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: {long_sql}\n  where 1 = 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1.\n2025-09-21 12:00:01.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select 2 EXECTIME: 2(ms) ROWCOUNT: 1 EXEC_ID: 2."
+    );
+    let mut tmp = tempfile::NamedTempFile::new().unwrap();
+    tmp.write_all(content.as_bytes()).unwrap();
+
+    let (logs, errors) = parse_file_collect(tmp.path());
+    assert!(errors.is_empty(), "{errors:?}");
+    assert_eq!(logs.len(), 2);
+    assert_eq!(
+        logs[0].description,
+        format!(
+            "{long_sql}\nwhere 1 = 1 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 1."
+        )
+    );
+    assert_eq!(logs[1].line, 3);
+    assert_eq!(logs[1].execute_id, Some(ExecId::new(2)));
+}
+
 #[test]
 fn test_sqllogerror_display_all() {
     // 无需 FromUtf8Error，直接用 Utf8Error
@@ -367,6 +449,10 @@ fn test_appname_ip_edge_cases() {
     }
     let line_appname_space = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname:  ip:::ffff:127.0.0.1) test";
     let log = Sqllog::from_line(line_appname_space, 1).unwrap().unwrap();
+    assert_eq!(log.appname, None);
+    let log = Sqllog::from_line_with(line_appname_space, 1, BlankFields::Raw)
+        .unwrap()
+        .unwrap();
     assert!(matches!(log.appname, Some(ref s) if s.trim().is_empty()));
 }
 
@@ -380,7 +466,37 @@ fn test_appname_ip_none_and_empty() {
     // appname 为空字符串
     let line = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname:  ) test";
     let log = Sqllog::from_line(line, 1).unwrap().unwrap();
-    assert!(matches!(log.appname, Some(ref s) if s.trim().is_empty()));
+    assert_eq!(log.appname, None);
+    let log =
+        Sqllog::from_line_with(line, 1, BlankFields::Empty).unwrap().unwrap();
+    assert_eq!(log.appname.as_deref(), Some(""));
+}
+
+#[test]
+fn test_blank_fields_policy_is_uniform() {
+    // 未加引号的空值、仅含空白与引号包围的空值按同一策略处理
+    let lines = [
+        "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname: ip:::ffff:127.0.0.1) test",
+        "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname:  ip:::ffff:127.0.0.1) test",
+        r#"2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname:"" ip:::ffff:127.0.0.1) test"#,
+    ];
+    for line in lines {
+        let parse = |policy| {
+            Sqllog::from_line_with(line, 1, policy).unwrap().unwrap().appname
+        };
+        assert_eq!(parse(BlankFields::Null), None, "{line}");
+        assert_eq!(parse(BlankFields::Empty).as_deref(), Some(""), "{line}");
+        assert!(parse(BlankFields::Raw).is_some(), "{line}");
+    }
+
+    // 未出现 appname 字段时所有策略均为 None
+    let line = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2) test";
+    for policy in [BlankFields::Null, BlankFields::Empty, BlankFields::Raw] {
+        let log = Sqllog::from_line_with(line, 1, policy).unwrap().unwrap();
+        assert_eq!(log.appname, None);
+    }
+    assert_eq!("keep".parse(), Ok(BlankFields::Raw));
+    assert!("blank".parse::<BlankFields>().is_err());
 }
 
 #[test]
@@ -398,7 +514,7 @@ fn test_from_line_desc_regex_group_none() {
     let res = Sqllog::from_line(line, 1);
     assert!(res.is_ok());
     let log = res.unwrap().unwrap();
-    assert_eq!(log.execute_time, Some(123));
+    assert_eq!(log.execute_time, Some(ExecTimeMs::new(123)));
     assert_eq!(log.rowcount, None);
     assert_eq!(log.execute_id, None);
 }
@@ -472,9 +588,9 @@ fn test_from_line_all_option_fields_some() {
     assert_eq!(log.appname, Some("TestApp".to_string()));
     assert_eq!(log.ip, Some("127.0.0.1".to_string()));
     assert_eq!(log.sql_type, Some("SEL".to_string()));
-    assert_eq!(log.execute_time, Some(123));
-    assert_eq!(log.rowcount, Some(456));
-    assert_eq!(log.execute_id, Some(789));
+    assert_eq!(log.execute_time, Some(ExecTimeMs::new(123)));
+    assert_eq!(log.rowcount, Some(RowCount::new(456)));
+    assert_eq!(log.execute_id, Some(ExecId::new(789)));
 }
 
 #[test]
@@ -624,9 +740,9 @@ fn test_from_line_parses_full_segment() {
     assert_eq!(log.appname.as_deref(), Some("myapp"));
     assert_eq!(log.ip.as_deref(), Some("127.0.0.1"));
     assert_eq!(log.sql_type.as_deref(), Some("INS"));
-    assert_eq!(log.execute_time, Some(100));
-    assert_eq!(log.rowcount, Some(3));
-    assert_eq!(log.execute_id, Some(42));
+    assert_eq!(log.execute_time, Some(ExecTimeMs::new(100)));
+    assert_eq!(log.rowcount, Some(RowCount::new(3)));
+    assert_eq!(log.execute_id, Some(ExecId::new(42)));
 }
 
 #[test]
@@ -697,3 +813,85 @@ fn test_from_file_with_invalid_utf8_in_middle() {
             .any(|(_, _, e)| e.to_string().to_lowercase().contains("utf"))
     );
 }
+
+#[test]
+fn test_from_line_exectime_variants_normalized_to_micros() {
+    let prefix = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2) [SEL]: select 1";
+    let cases = [
+        (
+            " EXECTIME: 123(ms) ROWCOUNT: 4 EXEC_ID: 5.",
+            123_000,
+            Some(4),
+            Some(5),
+        ),
+        (
+            " EXECTIME: 0.125(ms) ROWCOUNT: 1(rows) EXEC_ID: 6.",
+            125,
+            Some(1),
+            Some(6),
+        ),
+        (" EXECTIME:1500(us) ROWCOUNT:2 EXEC_ID:7", 1_500, Some(2), Some(7)),
+        (" EXECTIME: 1500 (μs)", 1_500, None, None),
+        (" EXECTIME: 2 (s) ROWCOUNT: 3", 2_000_000, Some(3), None),
+        (" exec_time: 12ms, row_count: 8", 12_000, Some(8), None),
+        (" ExecTime : 7", 7_000, None, None),
+    ];
+
+    for (suffix, us, rowcount, execute_id) in cases {
+        let line = format!("{prefix}{suffix}");
+        let log = Sqllog::from_line(&line, 1).unwrap().unwrap();
+        assert_eq!(log.execute_time_us, Some(us), "{suffix}");
+        assert_eq!(
+            log.execute_time,
+            Some(ExecTimeMs::new(us / 1000)),
+            "{suffix}"
+        );
+        assert_eq!(log.rowcount, rowcount.map(RowCount::new), "{suffix}");
+        assert_eq!(log.execute_id, execute_id.map(ExecId::new), "{suffix}");
+    }
+}
+
+#[test]
+fn test_from_line_exectime_uses_last_match_on_last_line() {
+    let line = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2) [SEL]: select 'EXECTIME: 99(ms)' from t EXECTIME: 3(ms) ROWCOUNT: 1 EXEC_ID: 2.";
+    let log = Sqllog::from_line(line, 1).unwrap().unwrap();
+    assert_eq!(log.execute_time, Some(ExecTimeMs::new(3)));
+    assert_eq!(log.execute_id, Some(ExecId::new(2)));
+
+    // 列名中的 exec_time 不应被当作执行时间
+    let line = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2) [SEL]: select exec_time from t";
+    let log = Sqllog::from_line(line, 1).unwrap().unwrap();
+    assert_eq!(log.execute_time_us, None);
+}
+
+#[test]
+fn test_parser_context_matches_from_line() {
+    let ok = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U trxid:1 stmt:0x2 appname: ip:::ffff:10.0.0.1) [SEL]: select 1 EXECTIME: 3(ms) ROWCOUNT: 1 EXEC_ID: 2.";
+    let partial = "2025-10-10 10:10:10.100 (EP[1] sess:0x1 thrd:1 user:U stmt:0x2) [SEL]: select 1";
+    let segments = [ok, "garbage", partial];
+
+    let ctx = ParserContext::new();
+    for (i, segment) in segments.iter().enumerate() {
+        let expected = Sqllog::from_line(segment, i + 1);
+        let actual = ctx.parse(segment, i + 1);
+        assert_eq!(format!("{actual:?}"), format!("{expected:?}"));
+    }
+
+    // 批量解析：结果与输入一一对应，行号从 1 开始
+    let results = Sqllog::from_lines(&segments);
+    assert_eq!(results.len(), 3);
+    assert_eq!(results[0].as_ref().unwrap().as_ref().unwrap().line, 1);
+    assert!(matches!(results[1], Err(SqllogError::Format { line: 2, .. })));
+    assert!(results[2].is_err());
+
+    // 上下文中的空白字段与解析模式作用于每一段
+    let ctx = ParserContext::new()
+        .with_blank_fields(BlankFields::Empty)
+        .with_mode(ParseMode::Lenient);
+    let results = ctx.parse_lines(&segments);
+    let first = results[0].as_ref().unwrap().as_ref().unwrap();
+    assert_eq!(first.appname.as_deref(), Some(""));
+    let third = results[2].as_ref().unwrap().as_ref().unwrap();
+    assert!(third.partial);
+    assert_eq!(third.trx_id, None);
+}
@@ -0,0 +1,142 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    COLD_TABLE, DuckDbProvider, IndexReport, SqllogStore,
+    process_files_with_independent_databases,
+};
+use sqllog_analysis::sqllog::RecordIdMode;
+use std::fs;
+use tempfile::tempdir;
+
+const LOG: &str = "\
+2025-09-21 12:00:01.000 (EP[1] sess:NULL thrd:1 user:alice trxid:1 stmt:NULL) [SEL]: select * from t where id = 1 EXECTIME: 5(ms) ROWCOUNT: 1 EXEC_ID: 1.
+2025-09-21 12:00:02.000 (EP[1] sess:NULL thrd:1 user:bob trxid:1 stmt:NULL) [SEL]: select * from t where id = 2 EXECTIME: 50(ms) ROWCOUNT: 1 EXEC_ID: 2.
+2025-09-21 12:30:00.000 (EP[1] sess:NULL thrd:1 user:alice trxid:1 stmt:NULL) [UPD]: update t set v = 3 EXECTIME: 20(ms) ROWCOUNT: 1 EXEC_ID: 3.
+2025-09-21 13:00:00.000 (EP[1] sess:NULL thrd:1 user:alice trxid:1 stmt:NULL) [SEL]: select * from t where id = 4 EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: 4.
+";
+
+#[test]
+fn typed_queries_over_exported_database() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("dmsql_a.log");
+    fs::write(&log, LOG).unwrap();
+    let db = dir.path().join("out.duckdb");
+    let runtime = RuntimeConfig {
+        db_path: db.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    process_files_with_independent_databases(&[log], &runtime).unwrap();
+
+    let store = SqllogStore::open(&db).unwrap();
+    assert_eq!(store.count().unwrap(), 4);
+
+    let slow = store.top_slow(2).unwrap();
+    let times: Vec<i64> =
+        slow.iter().map(|r| r.execute_time.unwrap().get()).collect();
+    assert_eq!(times, [50, 20]);
+    assert_eq!(slow[0].user.as_deref(), Some("bob"));
+
+    let alice = store
+        .by_user("alice", "2025-09-21 12:00:00".."2025-09-21 13:00:00")
+        .unwrap();
+    assert_eq!(alice.len(), 2);
+    assert_eq!(alice[1].sql_type.as_deref(), Some("UPD"));
+    assert_eq!(store.by_user("alice", ..).unwrap().len(), 3);
+
+    let stats = store.fingerprint_stats().unwrap();
+    let select = stats.get("select * from t where id = ?").unwrap();
+    assert_eq!(select.calls, 3);
+}
+
+#[test]
+fn opening_missing_database_fails() {
+    let dir = tempdir().unwrap();
+    assert!(SqllogStore::open(dir.path().join("missing.duckdb")).is_err());
+}
+
+#[test]
+fn split_cold_fields_keeps_records_readable() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("dmsql_a.log");
+    fs::write(&log, LOG).unwrap();
+    let db = dir.path().join("out.duckdb");
+    let runtime = RuntimeConfig {
+        db_path: db.to_string_lossy().to_string(),
+        sqllog_record_id: RecordIdMode::Hash,
+        db_split_cold: true,
+        ..Default::default()
+    };
+    process_files_with_independent_databases(&[log], &runtime).unwrap();
+
+    let mut provider = DuckDbProvider::new(&runtime).unwrap();
+    assert_eq!(provider.split_cold_fields().unwrap(), 4);
+    // 已移动的记录不会再次移动
+    assert_eq!(provider.split_cold_fields().unwrap(), 0);
+    drop(provider);
+
+    let conn = duckdb::Connection::open(&db).unwrap();
+    let hot: i64 = conn
+        .query_row(
+            "SELECT count(*) FROM sqllogs WHERE description <> ''",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(hot, 0);
+    let cold: i64 = conn
+        .query_row(&format!("SELECT count(*) FROM {COLD_TABLE}"), [], |row| {
+            row.get(0)
+        })
+        .unwrap();
+    assert_eq!(cold, 4);
+    drop(conn);
+
+    let store = SqllogStore::open(&db).unwrap();
+    let slow = store.top_slow(1).unwrap();
+    assert!(slow[0].description.contains("id = 2"));
+    assert_eq!(store.fingerprint_stats().unwrap().len(), 2);
+}
+
+#[test]
+fn index_report_summarizes_exported_database() {
+    let dir = tempdir().unwrap();
+    let log = dir.path().join("dmsql_a.log");
+    fs::write(&log, LOG).unwrap();
+    let db = dir.path().join("out.duckdb");
+    let runtime = RuntimeConfig {
+        db_path: db.to_string_lossy().to_string(),
+        ..Default::default()
+    };
+    process_files_with_independent_databases(&[log], &runtime).unwrap();
+
+    let provider = DuckDbProvider::new(&runtime).unwrap();
+    let report = IndexReport::generate(&provider).unwrap();
+    assert!(report.file_bytes.unwrap() > 0);
+
+    let sqllogs = report.tables.iter().find(|t| t.name == "sqllogs").unwrap();
+    assert_eq!(sqllogs.rows, 4);
+    assert!(sqllogs.estimated_bytes > 0);
+    let thread =
+        report.indexes.iter().find(|i| i.name == "idx_sqllogs_dmlg02").unwrap();
+    assert_eq!(
+        (thread.table.as_str(), thread.columns.as_slice()),
+        ("sqllogs", ["thread".to_string()].as_slice())
+    );
+    // 键列 "1" 每行 1 字节，另加每行 8 字节的行号
+    assert_eq!(thread.estimated_bytes, 4 * (1 + 8));
+
+    assert_eq!(
+        report.first_occurrence.as_deref(),
+        Some("2025-09-21 12:00:01.000")
+    );
+    assert_eq!(
+        report.last_occurrence.as_deref(),
+        Some("2025-09-21 13:00:00.000")
+    );
+    assert_eq!(report.distinct["username"], 2);
+    assert_eq!(report.distinct["session"], 0);
+
+    let json = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["distinct"]["sql_type"], 2);
+}
@@ -0,0 +1,78 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    RateLimit, RateLimiter, process_files_with_independent_databases,
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn unlimited_rate_never_waits() {
+    let mut limiter = RateLimiter::new(RateLimit::default());
+    for _ in 0..100 {
+        assert_eq!(limiter.acquire(10_000), Duration::ZERO);
+    }
+}
+
+#[test]
+fn records_per_sec_paces_batches() {
+    let mut limiter = RateLimiter::new(RateLimit {
+        records_per_sec: Some(1000),
+        batches_per_sec: None,
+    });
+    let start = Instant::now();
+    // 第一批立即放行，之后每批 100 条需约 100ms
+    for _ in 0..3 {
+        limiter.acquire(100);
+    }
+    assert!(start.elapsed() >= Duration::from_millis(190));
+}
+
+#[test]
+fn stricter_of_both_limits_applies() {
+    let mut limiter = RateLimiter::new(RateLimit {
+        records_per_sec: Some(1_000_000),
+        batches_per_sec: Some(20),
+    });
+    let start = Instant::now();
+    for _ in 0..3 {
+        limiter.acquire(1);
+    }
+    assert!(start.elapsed() >= Duration::from_millis(95));
+}
+
+#[test]
+fn rate_limit_applies_across_files_of_a_run() {
+    let dir = tempfile::tempdir().unwrap();
+    let files: Vec<_> = (0..3)
+        .map(|i| {
+            let path = dir.path().join(format!("{i}.log"));
+            std::fs::write(
+                &path,
+                format!(
+                    "2025-09-21 12:00:0{i}.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}.\n"
+                ),
+            )
+            .unwrap();
+            path
+        })
+        .collect();
+    let config = RuntimeConfig {
+        db_path: dir.path().join("out.duckdb").to_string_lossy().to_string(),
+        insert_rate_limit: RateLimit {
+            records_per_sec: None,
+            batches_per_sec: Some(1),
+        },
+        ..Default::default()
+    };
+
+    let start = Instant::now();
+    let stats =
+        process_files_with_independent_databases(&files, &config).unwrap();
+
+    // 每个文件一批：各文件单独限速时每批都是首批、不会等待；共用限速器时
+    // 第三批最早在开始后 2 秒写入
+    assert_eq!(stats.records_inserted, 3);
+    assert!(start.elapsed() >= Duration::from_millis(1990));
+    assert!(stats.files.iter().any(|f| !f.throttle_time.is_zero()));
+}
@@ -0,0 +1,68 @@
+#![cfg(feature = "database")]
+
+use sqllog_analysis::config::RuntimeConfig;
+use sqllog_analysis::database::{
+    FileThroughput, IndependentDatabaseStats,
+    process_file_with_independent_database,
+};
+use std::io::Write;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+#[test]
+fn file_throughput_is_recorded_per_file() {
+    let mut file = NamedTempFile::new().unwrap();
+    for i in 0..3 {
+        writeln!(
+            file,
+            "2025-09-21 12:00:0{i}.000 (EP[1] sess:NULL thrd:1 user:usr trxid:1 stmt:NULL) [SEL]: select {i} EXECTIME: 1(ms) ROWCOUNT: 1 EXEC_ID: {i}."
+        )
+        .unwrap();
+    }
+    file.flush().unwrap();
+    let size = file.as_file().metadata().unwrap().len();
+
+    let config = RuntimeConfig {
+        use_in_memory: true,
+        sqllog_chunk_size: Some(2),
+        ..Default::default()
+    };
+    let stats =
+        process_file_with_independent_database(file.path(), &config).unwrap();
+
+    assert_eq!(stats.files.len(), 1);
+    let throughput = &stats.files[0];
+    assert_eq!(throughput.bytes, size);
+    assert_eq!(throughput.records, 3);
+    assert!(throughput.elapsed >= throughput.insert_time);
+    assert!(throughput.records_per_sec() > 0.0);
+    assert_eq!(stats.total_bytes(), size);
+}
+
+#[test]
+fn merge_accumulates_counts_and_files() {
+    let file = |records| FileThroughput {
+        path: "a.log".to_string(),
+        bytes: 100,
+        records,
+        elapsed: Duration::from_secs(2),
+        insert_time: Duration::from_millis(500),
+        throttle_time: Duration::from_millis(500),
+    };
+    let mut total = IndependentDatabaseStats::default();
+    for records in [10, 30] {
+        total.merge(&IndependentDatabaseStats {
+            records_processed: records,
+            files_processed: 1,
+            files: vec![file(records)],
+            ..Default::default()
+        });
+    }
+
+    assert_eq!(total.records_processed, 40);
+    assert_eq!(total.files_processed, 2);
+    assert_eq!(total.total_bytes(), 200);
+    assert_eq!(total.total_elapsed(), Duration::from_secs(4));
+    assert_eq!(total.files[1].parse_time(), Duration::from_secs(1));
+    assert!((total.files[1].records_per_sec() - 15.0).abs() < f64::EPSILON);
+}
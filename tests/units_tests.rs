@@ -0,0 +1,40 @@
+use sqllog_analysis::sqllog::{ExecId, ExecTimeMs, RowCount};
+use std::time::Duration;
+
+#[test]
+fn exec_time_converts_between_units() {
+    assert_eq!(ExecTimeMs::from_micros(1_999), ExecTimeMs::new(1));
+    assert_eq!(ExecTimeMs::new(3).as_micros(), 3_000);
+    assert_eq!(ExecTimeMs::new(250).as_duration(), Duration::from_millis(250));
+    assert_eq!(ExecTimeMs::new(-1).as_duration(), Duration::ZERO);
+    assert_eq!(ExecTimeMs::new(7).to_string(), "7ms");
+}
+
+#[test]
+fn same_unit_arithmetic_and_sum() {
+    let total: ExecTimeMs = [1, 2, 3].into_iter().map(ExecTimeMs::new).sum();
+    assert_eq!(total, ExecTimeMs::new(6));
+    assert_eq!(total - ExecTimeMs::new(1), ExecTimeMs::new(5));
+
+    let mut rows = RowCount::new(10);
+    rows += RowCount::new(5);
+    assert_eq!(rows.get(), 15);
+    assert_eq!(
+        ExecTimeMs::new(i64::MAX) + ExecTimeMs::new(1),
+        ExecTimeMs::new(i64::MAX)
+    );
+}
+
+#[test]
+fn serde_is_transparent() {
+    let json = serde_json::to_string(&(
+        ExecTimeMs::new(5),
+        RowCount::new(2),
+        ExecId::new(9),
+    ))
+    .unwrap();
+    assert_eq!(json, "[5,2,9]");
+
+    let id: ExecId = serde_json::from_str("42").unwrap();
+    assert_eq!(i64::from(id), 42);
+}